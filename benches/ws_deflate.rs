@@ -0,0 +1,62 @@
+use divan::counter::BytesCount;
+use rama::http::ws::protocol::{DeflateConfig, Role, WebSocket, WebSocketConfig};
+use rama::http::ws::{Message, Utf8Bytes};
+use std::io::Cursor;
+
+fn main() {
+    // Run registered benchmarks.
+    divan::main();
+}
+
+const MESSAGE_COUNT: usize = 16;
+
+fn json_payload(size: usize) -> Utf8Bytes {
+    let mut payload = String::from("[");
+    let mut id = 0;
+    while payload.len() < size {
+        if id > 0 {
+            payload.push(',');
+        }
+        payload.push_str(&format!(
+            r#"{{"id":{id},"name":"user-{id}","email":"user-{id}@example.com","active":{},"roles":["reader","writer"],"score":{}}}"#,
+            id % 2 == 0,
+            id * 7 % 100,
+        ));
+        id += 1;
+    }
+    payload.push(']');
+    payload.into()
+}
+
+fn config(compression: bool) -> Option<WebSocketConfig> {
+    Some(WebSocketConfig::default().compression(compression.then(DeflateConfig::default)))
+}
+
+#[divan::bench(args = [false, true], consts = [16 * 1024, 256 * 1024])]
+fn json_roundtrip<const SIZE: usize>(bencher: divan::Bencher, compression: bool) {
+    let payload = json_payload(SIZE);
+
+    bencher
+        .counter(BytesCount::new(payload.len() * MESSAGE_COUNT))
+        .bench_local(|| {
+            let mut server = WebSocket::from_raw_socket(
+                Cursor::new(Vec::new()),
+                Role::Server,
+                config(compression),
+            );
+            for _ in 0..MESSAGE_COUNT {
+                server.write(Message::Text(payload.clone())).unwrap();
+            }
+            server.flush().unwrap();
+
+            let outgoing = std::mem::take(server.get_mut().get_mut());
+            let mut client = WebSocket::from_raw_socket(
+                Cursor::new(outgoing),
+                Role::Client,
+                config(compression),
+            );
+            for _ in 0..MESSAGE_COUNT {
+                divan::black_box(client.read().unwrap());
+            }
+        });
+}
@@ -0,0 +1,68 @@
+use divan::counter::BytesCount;
+use rama::net::proxy::{ProxyRequest, StreamForwardService};
+use rama::net::stream::ZeroCopyForwarder;
+use rama::{Context, Service};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+fn main() {
+    // Run registered benchmarks.
+    divan::main();
+}
+
+async fn tcp_pair(listener: &TcpListener) -> (TcpStream, TcpStream) {
+    let addr = listener.local_addr().unwrap();
+    let (client, (server, _)) =
+        tokio::try_join!(TcpStream::connect(addr), listener.accept()).unwrap();
+    (client, server)
+}
+
+/// Sends `SIZE` bytes from a client to a server via a forwarding proxy,
+/// returning once the server has received all of them.
+async fn proxy_transfer<const SIZE: usize>(zero_copy: bool) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let (mut client, source) = tcp_pair(&listener).await;
+    let (target, mut server) = tcp_pair(&listener).await;
+
+    let proxy = tokio::spawn(async move {
+        if zero_copy {
+            let (mut source, mut target) = (source, target);
+            ZeroCopyForwarder::new()
+                .forward(&mut source, &mut target)
+                .await
+                .unwrap();
+        } else {
+            StreamForwardService::new()
+                .serve(Context::default(), ProxyRequest { source, target })
+                .await
+                .unwrap();
+        }
+    });
+
+    let payload = vec![b'x'; SIZE];
+    let client = tokio::spawn(async move {
+        client.write_all(&payload).await.unwrap();
+        client.shutdown().await.unwrap();
+        client.read_to_end(&mut Vec::new()).await.unwrap();
+    });
+
+    let mut buf = Vec::with_capacity(SIZE);
+    server.read_to_end(&mut buf).await.unwrap();
+    assert_eq!(buf.len(), SIZE);
+    drop(server);
+
+    client.await.unwrap();
+    proxy.await.unwrap();
+}
+
+#[divan::bench(args = [false, true], consts = [1024 * 1024, 16 * 1024 * 1024])]
+fn tcp_forward<const SIZE: usize>(bencher: divan::Bencher, zero_copy: bool) {
+    let rt = tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()
+        .unwrap();
+
+    bencher
+        .counter(BytesCount::new(SIZE))
+        .bench_local(|| rt.block_on(proxy_transfer::<SIZE>(zero_copy)));
+}
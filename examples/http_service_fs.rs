@@ -15,11 +15,18 @@
 //! ```
 //!
 //! You should see a response with `HTTP/1.1 200 OK` and the content of the `index.html` file.
+//! The response contains an `ETag` header, which can be used to make a conditional request:
+//!
+//! ```sh
+//! curl -v http://127.0.0.1:62009/test-files/index.html -H 'If-None-Match: "<etag>"'
+//! ```
+//!
+//! You should see a response with `HTTP/1.1 304 Not Modified` and no content.
 
 use rama::{
     Layer,
-    http::{server::HttpServer, service::fs::ServeDir},
-    layer::TraceErrLayer,
+    http::{layer::etag::ETagLayer, server::HttpServer, service::fs::ServeDir},
+    layer::{ConsumeErrLayer, TraceErrLayer},
     rt::Executor,
     tcp::server::TcpListener,
 };
@@ -35,7 +42,8 @@ async fn main() {
     // This will serve files in the current working dir
     let cwd = std::env::current_dir().expect("current working dir");
     println!("Serving files from: {cwd:?}");
-    let http_fs_server = HttpServer::auto(exec).service(ServeDir::new(cwd));
+    let http_fs_server = HttpServer::auto(exec)
+        .service((ConsumeErrLayer::default(), ETagLayer::new()).into_layer(ServeDir::new(cwd)));
 
     // Serve the HTTP server over TCP,
     // ...once running you can go in browser for example to:
@@ -17,13 +17,15 @@
 //! Within the telnet session, you can type anything and it will be echoed back to you.
 //! After 8 seconds the connection will be closed by the server.
 //! This is because of the `TimeoutLayer` that was added to the server.
+//! Dead connections are detected even sooner thanks to the `TcpKeepaliveLayer`,
+//! which enables TCP keepalive probes on accepted connections.
 
 use rama::{
     Layer,
     layer::{HijackLayer, TimeoutLayer, TraceErrLayer},
     net::stream::{matcher::SocketMatcher, service::EchoService},
     service::service_fn,
-    tcp::server::TcpListener,
+    tcp::server::{TcpKeepaliveConfig, TcpKeepaliveLayer, TcpListener},
     telemetry::tracing::{self, level_filters::LevelFilter},
 };
 use std::{convert::Infallible, time::Duration};
@@ -70,6 +72,11 @@ async fn main() {
                     ),
                     TraceErrLayer::new(),
                     TimeoutLayer::new(Duration::from_secs(8)),
+                    TcpKeepaliveLayer::new(TcpKeepaliveConfig {
+                        idle: Duration::from_secs(2),
+                        interval: Duration::from_secs(1),
+                        retries: 3,
+                    }),
                 )
                     .into_layer(EchoService::new()),
             )
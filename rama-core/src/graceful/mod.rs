@@ -4,3 +4,7 @@
 pub use ::tokio_graceful::{
     Shutdown, ShutdownBuilder, ShutdownGuard, WeakShutdownGuard, default_signal,
 };
+
+mod priority;
+#[doc(inline)]
+pub use priority::PriorityShutdown;
@@ -0,0 +1,227 @@
+use super::{Shutdown, ShutdownGuard, WeakShutdownGuard};
+use crate::telemetry::tracing;
+use std::cmp::Reverse;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::pin::Pin;
+use std::time::Duration;
+use tokio::sync::oneshot;
+use tokio::time::Instant;
+
+/// Graceful shutdown of components in order of their priority.
+///
+/// Guards are created for a priority level, where a higher priority means
+/// that the shutdown is signalled earlier. Once the shutdown signal is received,
+/// each level is signalled in descending order of priority, waiting for all guards
+/// of that level to be dropped and then for the configured inter-level drain
+/// duration, before the next level is signalled.
+///
+/// This allows, for example, to stop accepting requests (high priority)
+/// before closing a database pool used to serve them (low priority).
+///
+/// All guards must be created prior to awaiting [`PriorityShutdown::shutdown`].
+///
+/// # Example
+///
+/// ```
+/// use rama_core::graceful::PriorityShutdown;
+/// use std::time::Duration;
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// let mut shutdown = PriorityShutdown::new(async {})
+///     .with_inter_level_drain(Duration::from_millis(10));
+///
+/// shutdown.spawn_task_fn(1, async |guard| {
+///     // e.g. an http server accepting requests
+///     guard.cancelled().await;
+/// });
+/// shutdown.spawn_task_fn(0, async |guard| {
+///     // e.g. a database pool used by the http server
+///     guard.cancelled().await;
+/// });
+///
+/// shutdown.shutdown().await;
+/// # }
+/// ```
+pub struct PriorityShutdown {
+    signal: Pin<Box<dyn Future<Output = ()> + Send + 'static>>,
+    levels: BTreeMap<Reverse<u8>, PriorityLevel>,
+    inter_level_drain: Duration,
+}
+
+struct PriorityLevel {
+    trigger: oneshot::Sender<()>,
+    shutdown: Shutdown,
+}
+
+impl fmt::Debug for PriorityShutdown {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PriorityShutdown")
+            .field(
+                "levels",
+                &self
+                    .levels
+                    .keys()
+                    .map(|Reverse(level)| level)
+                    .collect::<Vec<_>>(),
+            )
+            .field("inter_level_drain", &self.inter_level_drain)
+            .finish()
+    }
+}
+
+impl PriorityShutdown {
+    /// Create a new [`PriorityShutdown`], which starts the shutdown
+    /// of all priority levels once the given signal resolves.
+    pub fn new(signal: impl Future + Send + 'static) -> Self {
+        Self {
+            signal: Box::pin(async move {
+                signal.await;
+            }),
+            levels: BTreeMap::new(),
+            inter_level_drain: Duration::ZERO,
+        }
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Set the duration to wait between the shutdown of a priority level
+        /// and signalling the shutdown to the next (lower) priority level.
+        ///
+        /// Defaults to no delay.
+        pub fn inter_level_drain(mut self, drain: Duration) -> Self {
+            self.inter_level_drain = drain;
+            self
+        }
+    }
+
+    /// Create a [`ShutdownGuard`] for the given priority level,
+    /// where a higher priority means an earlier shutdown signal.
+    pub fn guard(&mut self, priority: u8) -> ShutdownGuard {
+        self.level(priority).guard()
+    }
+
+    /// Create a [`WeakShutdownGuard`] for the given priority level,
+    /// where a higher priority means an earlier shutdown signal.
+    pub fn guard_weak(&mut self, priority: u8) -> WeakShutdownGuard {
+        self.level(priority).guard_weak()
+    }
+
+    /// Spawn the given task using a [`ShutdownGuard`] of the given priority level.
+    pub fn spawn_task_fn<F, Fut>(
+        &mut self,
+        priority: u8,
+        task: F,
+    ) -> tokio::task::JoinHandle<Fut::Output>
+    where
+        F: FnOnce(ShutdownGuard) -> Fut + Send + 'static,
+        Fut: Future<Output: Send + 'static> + Send + 'static,
+    {
+        self.level(priority).spawn_task_fn(task)
+    }
+
+    fn level(&mut self, priority: u8) -> &Shutdown {
+        &self
+            .levels
+            .entry(Reverse(priority))
+            .or_insert_with(|| {
+                let (trigger, rx) = oneshot::channel::<()>();
+                PriorityLevel {
+                    trigger,
+                    shutdown: Shutdown::new(async move {
+                        let _ = rx.await;
+                    }),
+                }
+            })
+            .shutdown
+    }
+
+    /// Wait for the shutdown signal, and shut down all priority levels
+    /// one by one, starting with the highest priority.
+    ///
+    /// Returns the total elapsed time since the shutdown signal was received.
+    pub async fn shutdown(self) -> Duration {
+        self.signal.await;
+        let start = Instant::now();
+
+        let mut levels = self.levels.into_iter().peekable();
+        while let Some((Reverse(priority), level)) = levels.next() {
+            tracing::trace!(
+                priority,
+                "PriorityShutdown: signal shutdown of priority level"
+            );
+            let _ = level.trigger.send(());
+            let elapsed = level.shutdown.shutdown().await;
+            tracing::trace!(
+                priority,
+                ?elapsed,
+                "PriorityShutdown: priority level shut down"
+            );
+            if levels.peek().is_some() && !self.inter_level_drain.is_zero() {
+                tokio::time::sleep(self.inter_level_drain).await;
+            }
+        }
+
+        start.elapsed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use parking_lot::Mutex;
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn test_priority_shutdown_order() {
+        let (tx, rx) = oneshot::channel::<()>();
+        let mut shutdown =
+            PriorityShutdown::new(rx).with_inter_level_drain(Duration::from_millis(20));
+
+        let order = Arc::new(Mutex::new(Vec::new()));
+        for priority in [0, 5, 2, 5, 1] {
+            let order = order.clone();
+            shutdown.spawn_task_fn(priority, async move |guard| {
+                guard.cancelled().await;
+                order.lock().push((priority, Instant::now()));
+            });
+        }
+
+        tx.send(()).unwrap();
+        let elapsed = shutdown.shutdown().await;
+
+        let order = order.lock();
+        let priorities: Vec<_> = order.iter().map(|(priority, _)| *priority).collect();
+        assert_eq!(priorities, [5, 5, 2, 1, 0]);
+        for window in order.windows(2) {
+            let ((a, a_at), (b, b_at)) = (window[0], window[1]);
+            if a != b {
+                assert!(b_at - a_at >= Duration::from_millis(20), "{a} -> {b}");
+            }
+        }
+        assert!(elapsed >= Duration::from_millis(60));
+    }
+
+    #[tokio::test]
+    async fn test_priority_shutdown_waits_for_level_guards() {
+        let mut shutdown = PriorityShutdown::new(async {});
+
+        let high = shutdown.guard(1);
+        let low = shutdown.guard_weak(0);
+
+        let released = Arc::new(Mutex::new(false));
+        let released_by_high = released.clone();
+        tokio::spawn(async move {
+            high.cancelled().await;
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            *released_by_high.lock() = true;
+        });
+        let low_checked = tokio::spawn(async move {
+            low.into_cancelled().await;
+            *released.lock()
+        });
+
+        shutdown.shutdown().await;
+        assert!(low_checked.await.unwrap());
+    }
+}
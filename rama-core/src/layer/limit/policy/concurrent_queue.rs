@@ -0,0 +1,284 @@
+//! A [`Policy`] that limits the number of concurrent requests,
+//! optionally queueing requests when the limit is reached.
+//!
+//! See [`ConcurrentQueuePolicy`].
+//!
+//! # Examples
+//!
+//! ```
+//! use rama_core::layer::limit::{Limit, policy::ConcurrentQueuePolicy};
+//! use rama_core::service::service_fn;
+//! use rama_core::{Context, Service};
+//! # use std::convert::Infallible;
+//!
+//! # #[tokio::main]
+//! # async fn main() {
+//!
+//! let service = service_fn(async |_, _| {
+//!     Ok::<_, Infallible>(())
+//! });
+//!
+//! // allow 2 concurrent requests, queueing up to 8 more requests
+//! let policy = ConcurrentQueuePolicy::queue(2, 8);
+//! let state = policy.state();
+//! let service = Limit::new(service, policy);
+//!
+//! let response = service.serve(Context::default(), ()).await;
+//! assert!(response.is_ok());
+//! assert_eq!(state.in_flight(), 0);
+//! # }
+//! ```
+
+use super::{LimitReached, Policy, PolicyOutput, PolicyResult};
+use crate::Context;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// What the [`ConcurrentQueuePolicy`] does with requests
+/// when the concurrency limit is reached.
+pub enum OverflowPolicy {
+    /// Abort the request with a [`LimitReached`] error.
+    Reject,
+    /// Wait for a request to finish, with at most `max_depth` requests waiting.
+    ///
+    /// Requests are aborted with a [`LimitReached`] error when the queue is full.
+    Queue {
+        /// The maximum amount of requests waiting.
+        max_depth: usize,
+    },
+}
+
+#[derive(Debug)]
+/// The state of a [`ConcurrentQueuePolicy`], shared by all its clones.
+///
+/// Can be used to expose the current load, e.g. for health checks or metrics.
+pub struct ConcurrencyState {
+    semaphore: Arc<Semaphore>,
+    max_concurrency: usize,
+    queue_depth: AtomicUsize,
+}
+
+impl ConcurrencyState {
+    /// The maximum amount of concurrent requests.
+    pub fn max_concurrency(&self) -> usize {
+        self.max_concurrency
+    }
+
+    /// The amount of requests currently in-flight.
+    pub fn in_flight(&self) -> usize {
+        self.max_concurrency - self.semaphore.available_permits()
+    }
+
+    /// The amount of requests currently waiting for a request to finish.
+    pub fn queue_depth(&self) -> usize {
+        self.queue_depth.load(Ordering::Acquire)
+    }
+}
+
+#[derive(Debug, Clone)]
+/// A [`Policy`] that limits the number of concurrent requests,
+/// using a [`Semaphore`].
+///
+/// Depending on its [`OverflowPolicy`] requests are either aborted immediately
+/// when the concurrency limit is reached, or queued until a request finishes.
+///
+/// The current load can be read from its [`ConcurrencyState`].
+pub struct ConcurrentQueuePolicy {
+    state: Arc<ConcurrencyState>,
+    overflow: OverflowPolicy,
+}
+
+impl ConcurrentQueuePolicy {
+    /// Create a new [`ConcurrentQueuePolicy`], allowing up to `max` concurrent requests,
+    /// handling requests beyond that limit according to the given [`OverflowPolicy`].
+    pub fn new(max: usize, overflow: OverflowPolicy) -> Self {
+        let max = max.min(Semaphore::MAX_PERMITS);
+        Self {
+            state: Arc::new(ConcurrencyState {
+                semaphore: Arc::new(Semaphore::new(max)),
+                max_concurrency: max,
+                queue_depth: AtomicUsize::new(0),
+            }),
+            overflow,
+        }
+    }
+
+    /// Create a new [`ConcurrentQueuePolicy`], allowing up to `max` concurrent requests,
+    /// and aborting requests beyond that limit.
+    pub fn reject(max: usize) -> Self {
+        Self::new(max, OverflowPolicy::Reject)
+    }
+
+    /// Create a new [`ConcurrentQueuePolicy`], allowing up to `max` concurrent requests,
+    /// and queueing up to `max_depth` requests beyond that limit.
+    pub fn queue(max: usize, max_depth: usize) -> Self {
+        Self::new(max, OverflowPolicy::Queue { max_depth })
+    }
+
+    /// The [`ConcurrencyState`] of this policy.
+    pub fn state(&self) -> Arc<ConcurrencyState> {
+        self.state.clone()
+    }
+
+    /// The [`OverflowPolicy`] of this policy.
+    pub fn overflow(&self) -> OverflowPolicy {
+        self.overflow
+    }
+
+    async fn acquire(&self) -> Result<OwnedSemaphorePermit, LimitReached> {
+        let semaphore = &self.state.semaphore;
+        if let Ok(permit) = semaphore.clone().try_acquire_owned() {
+            return Ok(permit);
+        }
+
+        let OverflowPolicy::Queue { max_depth } = self.overflow else {
+            return Err(LimitReached);
+        };
+
+        let queue_depth = &self.state.queue_depth;
+        if queue_depth
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |depth| {
+                (depth < max_depth).then_some(depth + 1)
+            })
+            .is_err()
+        {
+            return Err(LimitReached);
+        }
+
+        // leave the queue also when the request is cancelled while waiting
+        let _queued = QueuedGuard(queue_depth);
+        semaphore
+            .clone()
+            .acquire_owned()
+            .await
+            .map_err(|_| LimitReached)
+    }
+}
+
+struct QueuedGuard<'a>(&'a AtomicUsize);
+
+impl Drop for QueuedGuard<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+#[derive(Debug)]
+/// The guard of a [`ConcurrentQueuePolicy`],
+/// releasing the concurrency slot when dropped.
+pub struct ConcurrentQueueGuard {
+    _permit: OwnedSemaphorePermit,
+}
+
+impl<State, Request> Policy<State, Request> for ConcurrentQueuePolicy
+where
+    State: Clone + Send + Sync + 'static,
+    Request: Send + 'static,
+{
+    type Guard = ConcurrentQueueGuard;
+    type Error = LimitReached;
+
+    async fn check(
+        &self,
+        ctx: Context<State>,
+        request: Request,
+    ) -> PolicyResult<State, Request, Self::Guard, Self::Error> {
+        let output = match self.acquire().await {
+            Ok(permit) => PolicyOutput::Ready(ConcurrentQueueGuard { _permit: permit }),
+            Err(err) => PolicyOutput::Abort(err),
+        };
+        PolicyResult {
+            ctx,
+            request,
+            output,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_ready<S, R, G, E>(result: PolicyResult<S, R, G, E>) -> G {
+        match result.output {
+            PolicyOutput::Ready(guard) => guard,
+            _ => panic!("unexpected output, expected ready"),
+        }
+    }
+
+    fn assert_abort<S, R, G, E>(result: PolicyResult<S, R, G, E>) {
+        match result.output {
+            PolicyOutput::Abort(_) => (),
+            _ => panic!("unexpected output, expected abort"),
+        }
+    }
+
+    #[tokio::test]
+    async fn concurrent_queue_policy_reject() {
+        let policy = ConcurrentQueuePolicy::reject(2);
+        let state = policy.state();
+
+        let guard_1 = assert_ready(policy.check(Context::default(), ()).await);
+        let _guard_2 = assert_ready(policy.clone().check(Context::default(), ()).await);
+        assert_eq!(state.in_flight(), 2);
+
+        assert_abort(policy.check(Context::default(), ()).await);
+        assert_eq!(state.queue_depth(), 0);
+
+        drop(guard_1);
+        assert_eq!(state.in_flight(), 1);
+        assert_ready(policy.check(Context::default(), ()).await);
+    }
+
+    #[tokio::test]
+    async fn concurrent_queue_policy_queue() {
+        let policy = ConcurrentQueuePolicy::queue(1, 1);
+        let state = policy.state();
+
+        let guard = assert_ready(policy.check(Context::default(), ()).await);
+
+        let queued = tokio::spawn({
+            let policy = policy.clone();
+            async move {
+                let _guard = assert_ready(policy.check(Context::default(), ()).await);
+            }
+        });
+        while state.queue_depth() == 0 {
+            tokio::task::yield_now().await;
+        }
+
+        // queue is full
+        assert_abort(policy.check(Context::default(), ()).await);
+        assert_eq!(state.in_flight(), 1);
+        assert_eq!(state.queue_depth(), 1);
+
+        drop(guard);
+        queued.await.unwrap();
+        assert_eq!(state.in_flight(), 0);
+        assert_eq!(state.queue_depth(), 0);
+    }
+
+    #[tokio::test]
+    async fn concurrent_queue_policy_cancel_queued() {
+        let policy = ConcurrentQueuePolicy::queue(1, 1);
+        let state = policy.state();
+
+        let _guard = assert_ready(policy.check(Context::default(), ()).await);
+
+        let queued = tokio::spawn({
+            let policy = policy.clone();
+            async move {
+                let _guard = assert_ready(policy.check(Context::default(), ()).await);
+            }
+        });
+        while state.queue_depth() == 0 {
+            tokio::task::yield_now().await;
+        }
+
+        queued.abort();
+        let _ = queued.await;
+        assert_eq!(state.queue_depth(), 0);
+    }
+}
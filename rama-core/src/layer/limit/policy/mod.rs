@@ -35,8 +35,26 @@ mod concurrent;
 #[doc(inline)]
 pub use concurrent::{ConcurrentCounter, ConcurrentPolicy, ConcurrentTracker, LimitReached};
 
+mod concurrent_queue;
+#[doc(inline)]
+pub use concurrent_queue::{
+    ConcurrencyState, ConcurrentQueueGuard, ConcurrentQueuePolicy, OverflowPolicy,
+};
+
 mod matcher;
 
+mod rate_limit;
+#[doc(inline)]
+pub use rate_limit::{RateLimitInfo, RateLimitKeyFn, RateLimitReached};
+
+mod sliding_window;
+#[doc(inline)]
+pub use sliding_window::SlidingWindowPolicy;
+
+mod token_bucket;
+#[doc(inline)]
+pub use token_bucket::TokenBucketPolicy;
+
 /// The full result of a limit policy.
 pub struct PolicyResult<State, Request, Guard, Error> {
     /// The input context
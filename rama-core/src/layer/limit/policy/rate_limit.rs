@@ -0,0 +1,78 @@
+//! Types shared by the rate limit policies, such as
+//! [`TokenBucketPolicy`](super::TokenBucketPolicy)
+//! and [`SlidingWindowPolicy`](super::SlidingWindowPolicy).
+
+use crate::Context;
+use std::fmt;
+use std::hash::Hash;
+use std::time::Duration;
+
+/// A function used by a rate limit [`Policy`](super::Policy)
+/// to select the key a request is rate limited by,
+/// e.g. the client IP, a user ID or an API key.
+///
+/// Implemented for `()`, rate limiting all requests together,
+/// and for any `Fn(&Context<State>, &Request) -> Option<Key>`.
+pub trait RateLimitKeyFn<State, Request>: Send + Sync + 'static {
+    /// The key requests are rate limited by.
+    type Key: Hash + Eq + Send + 'static;
+
+    /// Return the key for the given request,
+    /// or `None` in case the request is not to be rate limited.
+    fn rate_limit_key(&self, ctx: &Context<State>, request: &Request) -> Option<Self::Key>;
+}
+
+impl<State, Request> RateLimitKeyFn<State, Request> for () {
+    type Key = ();
+
+    fn rate_limit_key(&self, _ctx: &Context<State>, _request: &Request) -> Option<Self::Key> {
+        Some(())
+    }
+}
+
+impl<State, Request, F, K> RateLimitKeyFn<State, Request> for F
+where
+    F: Fn(&Context<State>, &Request) -> Option<K> + Send + Sync + 'static,
+    K: Hash + Eq + Send + 'static,
+{
+    type Key = K;
+
+    fn rate_limit_key(&self, ctx: &Context<State>, request: &Request) -> Option<Self::Key> {
+        (self)(ctx, request)
+    }
+}
+
+/// Information about the rate limit quota of a request,
+/// inserted in the [`Context`] by rate limit policies such as [`TokenBucketPolicy`](super::TokenBucketPolicy)
+/// and [`SlidingWindowPolicy`](super::SlidingWindowPolicy).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RateLimitInfo {
+    /// The maximum amount of requests allowed in a burst.
+    pub limit: u64,
+    /// The remaining amount of requests allowed right now.
+    pub remaining: u64,
+    /// Time until the quota is fully restored.
+    pub reset: Duration,
+}
+
+/// Error returned by rate limit policies such as [`TokenBucketPolicy`](super::TokenBucketPolicy)
+/// and [`SlidingWindowPolicy`](super::SlidingWindowPolicy) when the rate limit is reached.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RateLimitReached {
+    /// The rate limit information at the time the request was aborted.
+    pub info: RateLimitInfo,
+    /// Time after which the request can be retried.
+    pub retry_after: Duration,
+}
+
+impl fmt::Display for RateLimitReached {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "request aborted due to exhausted rate limit (retry after {:?})",
+            self.retry_after
+        )
+    }
+}
+
+impl std::error::Error for RateLimitReached {}
@@ -0,0 +1,269 @@
+//! A [`Policy`] that rate limits requests using a sliding window.
+//!
+//! See [`SlidingWindowPolicy`].
+//!
+//! # Examples
+//!
+//! ```
+//! use rama_core::layer::limit::{Limit, policy::SlidingWindowPolicy};
+//! use rama_core::service::service_fn;
+//! use rama_core::{Context, Service};
+//! use std::time::Duration;
+//! # use std::convert::Infallible;
+//!
+//! # #[tokio::main]
+//! # async fn main() {
+//!
+//! let service = service_fn(async |_, _| {
+//!     Ok::<_, Infallible>(())
+//! });
+//!
+//! // allow 100 requests per minute
+//! let service = Limit::new(
+//!     service,
+//!     SlidingWindowPolicy::new(Duration::from_secs(60), 100),
+//! );
+//!
+//! let response = service.serve(Context::default(), ()).await;
+//! assert!(response.is_ok());
+//! # }
+//! ```
+
+use super::{Policy, PolicyOutput, PolicyResult, RateLimitInfo, RateLimitKeyFn, RateLimitReached};
+use crate::Context;
+use parking_lot::Mutex;
+use rama_utils::collections::PruningHashMap;
+use std::collections::VecDeque;
+use std::fmt;
+use std::hash::Hash;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::time::Instant;
+
+/// A [`Policy`] that rate limits requests using a sliding window.
+///
+/// At most `max_requests` requests are allowed within any `window` of time.
+/// Requests exceeding that limit are aborted with a [`RateLimitReached`] error,
+/// which can be retried once the oldest request in the window has expired.
+///
+/// By default all requests are rate limited together. Using
+/// [`SlidingWindowPolicy::with_key_fn`] requests can be limited per key instead,
+/// e.g. per client IP, user ID or API key.
+///
+/// On success a [`RateLimitInfo`] is inserted in the [`Context`],
+/// which can be used to inform the client about its remaining quota.
+pub struct SlidingWindowPolicy<F = (), K = ()> {
+    window: Duration,
+    max_requests: u64,
+    key_fn: F,
+    windows: Arc<Mutex<PruningHashMap<K, VecDeque<Instant>>>>,
+}
+
+impl<F: fmt::Debug, K> fmt::Debug for SlidingWindowPolicy<F, K> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SlidingWindowPolicy")
+            .field("window", &self.window)
+            .field("max_requests", &self.max_requests)
+            .field("key_fn", &self.key_fn)
+            .finish()
+    }
+}
+
+impl<F: Clone, K> Clone for SlidingWindowPolicy<F, K> {
+    fn clone(&self) -> Self {
+        Self {
+            window: self.window,
+            max_requests: self.max_requests,
+            key_fn: self.key_fn.clone(),
+            windows: self.windows.clone(),
+        }
+    }
+}
+
+impl SlidingWindowPolicy {
+    /// Create a new [`SlidingWindowPolicy`],
+    /// allowing at most `max_requests` requests within any `window` of time.
+    pub fn new(window: Duration, max_requests: u64) -> Self {
+        Self {
+            window,
+            max_requests,
+            key_fn: (),
+            windows: Default::default(),
+        }
+    }
+}
+
+impl<F, K> SlidingWindowPolicy<F, K> {
+    /// Rate limit requests per key, as returned by the given key function.
+    ///
+    /// Requests for which the function returns `None` are not rate limited.
+    ///
+    /// Windows are not shared with the original [`SlidingWindowPolicy`].
+    pub fn with_key_fn<F2, K2>(self, key_fn: F2) -> SlidingWindowPolicy<F2, K2> {
+        SlidingWindowPolicy {
+            window: self.window,
+            max_requests: self.max_requests,
+            key_fn,
+            windows: Default::default(),
+        }
+    }
+}
+
+fn prune(timestamps: &mut VecDeque<Instant>, now: Instant, window: Duration) {
+    while timestamps
+        .front()
+        .is_some_and(|ts| now.duration_since(*ts) >= window)
+    {
+        timestamps.pop_front();
+    }
+}
+
+impl<F, K, State, Request> Policy<State, Request> for SlidingWindowPolicy<F, K>
+where
+    F: RateLimitKeyFn<State, Request, Key = K>,
+    K: Hash + Eq + Send + 'static,
+    State: Clone + Send + Sync + 'static,
+    Request: Send + 'static,
+{
+    type Guard = ();
+    type Error = RateLimitReached;
+
+    async fn check(
+        &self,
+        mut ctx: Context<State>,
+        request: Request,
+    ) -> PolicyResult<State, Request, Self::Guard, Self::Error> {
+        let Some(key) = self.key_fn.rate_limit_key(&ctx, &request) else {
+            return PolicyResult {
+                ctx,
+                request,
+                output: PolicyOutput::Ready(()),
+            };
+        };
+
+        let now = Instant::now();
+        let max_requests = usize::try_from(self.max_requests).unwrap_or(usize::MAX);
+
+        let (allowed, count, oldest, newest) = {
+            let mut windows = self.windows.lock();
+
+            // drop all windows which are empty by now, as they are
+            // equivalent to a window that does not exist yet
+            windows.prune(|_, timestamps| {
+                prune(timestamps, now, self.window);
+                !timestamps.is_empty()
+            });
+
+            let timestamps = windows.entry(key).or_default();
+            prune(timestamps, now, self.window);
+
+            let allowed = timestamps.len() < max_requests;
+            if allowed {
+                timestamps.push_back(now);
+            }
+            (
+                allowed,
+                timestamps.len(),
+                timestamps.front().copied(),
+                timestamps.back().copied(),
+            )
+        };
+
+        let expires_in = |ts: Option<Instant>| {
+            ts.map(|ts| (ts + self.window).saturating_duration_since(now))
+                .unwrap_or_default()
+        };
+
+        let info = RateLimitInfo {
+            limit: self.max_requests,
+            remaining: self.max_requests.saturating_sub(count as u64),
+            reset: expires_in(newest),
+        };
+
+        if allowed {
+            ctx.insert(info);
+            PolicyResult {
+                ctx,
+                request,
+                output: PolicyOutput::Ready(()),
+            }
+        } else {
+            PolicyResult {
+                ctx,
+                request,
+                output: PolicyOutput::Abort(RateLimitReached {
+                    info,
+                    retry_after: expires_in(oldest),
+                }),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_ready<S, R, G, E>(result: PolicyResult<S, R, G, E>) -> Context<S> {
+        match result.output {
+            PolicyOutput::Ready(_) => result.ctx,
+            _ => panic!("unexpected output, expected ready"),
+        }
+    }
+
+    fn assert_abort<S, R, G, E>(result: PolicyResult<S, R, G, E>) -> E {
+        match result.output {
+            PolicyOutput::Abort(err) => err,
+            _ => panic!("unexpected output, expected abort"),
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn sliding_window_policy() {
+        let policy = SlidingWindowPolicy::new(Duration::from_secs(10), 2);
+
+        let ctx = assert_ready(policy.check(Context::default(), ()).await);
+        assert_eq!(
+            ctx.get::<RateLimitInfo>(),
+            Some(&RateLimitInfo {
+                limit: 2,
+                remaining: 1,
+                reset: Duration::from_secs(10),
+            })
+        );
+
+        tokio::time::advance(Duration::from_secs(4)).await;
+        assert_ready(policy.check(Context::default(), ()).await);
+
+        let err = assert_abort(policy.check(Context::default(), ()).await);
+        assert_eq!(err.info.remaining, 0);
+        assert_eq!(err.retry_after, Duration::from_secs(6));
+
+        // first request slides out of the window
+        tokio::time::advance(Duration::from_secs(6)).await;
+        assert_ready(policy.check(Context::default(), ()).await);
+        let err = assert_abort(policy.check(Context::default(), ()).await);
+        assert_eq!(err.retry_after, Duration::from_secs(4));
+
+        tokio::time::advance(Duration::from_secs(10)).await;
+        assert_ready(policy.check(Context::default(), ()).await);
+        assert_ready(policy.check(Context::default(), ()).await);
+        assert_abort(policy.check(Context::default(), ()).await);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn sliding_window_policy_per_key() {
+        let policy = SlidingWindowPolicy::new(Duration::from_secs(1), 1).with_key_fn(
+            |_ctx: &Context<()>, req: &&'static str| (!req.is_empty()).then_some(*req),
+        );
+
+        assert_ready(policy.check(Context::default(), "a").await);
+        assert_abort(policy.check(Context::default(), "a").await);
+        assert_ready(policy.check(Context::default(), "b").await);
+        assert_abort(policy.check(Context::default(), "b").await);
+
+        // not rate limited
+        assert_ready(policy.check(Context::default(), "").await);
+        assert_ready(policy.check(Context::default(), "").await);
+    }
+}
@@ -0,0 +1,293 @@
+//! A [`Policy`] that rate limits requests using the token bucket algorithm.
+//!
+//! See [`TokenBucketPolicy`].
+//!
+//! # Examples
+//!
+//! ```
+//! use rama_core::layer::limit::{Limit, policy::TokenBucketPolicy};
+//! use rama_core::service::service_fn;
+//! use rama_core::{Context, Service};
+//! # use std::convert::Infallible;
+//!
+//! # #[tokio::main]
+//! # async fn main() {
+//!
+//! let service = service_fn(async |_, _| {
+//!     Ok::<_, Infallible>(())
+//! });
+//!
+//! // allow bursts of 10 requests, refilling 2 requests per second
+//! let service = Limit::new(service, TokenBucketPolicy::new(10, 2.0));
+//!
+//! let response = service.serve(Context::default(), ()).await;
+//! assert!(response.is_ok());
+//! # }
+//! ```
+
+use super::{Policy, PolicyOutput, PolicyResult, RateLimitInfo, RateLimitKeyFn, RateLimitReached};
+use crate::Context;
+use parking_lot::Mutex;
+use rama_utils::collections::PruningHashMap;
+use std::fmt;
+use std::hash::Hash;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::time::Instant;
+
+/// A [`Policy`] that rate limits requests using the token bucket algorithm.
+///
+/// A bucket holds up to `capacity` tokens and is refilled at a rate
+/// of `rate` tokens per second. Each request consumes `cost` tokens
+/// (1 by default), and is aborted with a [`RateLimitReached`] error
+/// in case the bucket does not have sufficient tokens left.
+///
+/// By default a single bucket is shared by all requests. Using
+/// [`TokenBucketPolicy::with_key_fn`] a bucket can be used per key instead,
+/// e.g. per client IP or API key.
+///
+/// On success a [`RateLimitInfo`] is inserted in the [`Context`],
+/// which can be used to inform the client about its remaining quota.
+pub struct TokenBucketPolicy<F = (), K = ()> {
+    capacity: u64,
+    rate: f64,
+    cost: u64,
+    key_fn: F,
+    buckets: Arc<Mutex<PruningHashMap<K, Bucket>>>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Bucket {
+    tokens: f64,
+    last_refill: Instant,
+}
+
+impl<F: fmt::Debug, K> fmt::Debug for TokenBucketPolicy<F, K> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TokenBucketPolicy")
+            .field("capacity", &self.capacity)
+            .field("rate", &self.rate)
+            .field("cost", &self.cost)
+            .field("key_fn", &self.key_fn)
+            .finish()
+    }
+}
+
+impl<F: Clone, K> Clone for TokenBucketPolicy<F, K> {
+    fn clone(&self) -> Self {
+        Self {
+            capacity: self.capacity,
+            rate: self.rate,
+            cost: self.cost,
+            key_fn: self.key_fn.clone(),
+            buckets: self.buckets.clone(),
+        }
+    }
+}
+
+impl TokenBucketPolicy {
+    /// Create a new [`TokenBucketPolicy`], using a single bucket
+    /// which holds up to `capacity` tokens and is refilled
+    /// at `rate` tokens per second.
+    pub fn new(capacity: u64, rate: f64) -> Self {
+        Self {
+            capacity,
+            rate: rate.max(0.0),
+            cost: 1,
+            key_fn: (),
+            buckets: Default::default(),
+        }
+    }
+}
+
+impl<F, K> TokenBucketPolicy<F, K> {
+    rama_utils::macros::generate_set_and_with! {
+        /// Set the amount of tokens consumed by each request, 1 by default.
+        pub fn cost(mut self, cost: u64) -> Self {
+            self.cost = cost;
+            self
+        }
+    }
+
+    /// Use a bucket per key, as returned by the given key function.
+    ///
+    /// Requests for which the function returns `None` are not rate limited.
+    ///
+    /// Buckets are not shared with the original [`TokenBucketPolicy`].
+    pub fn with_key_fn<F2, K2>(self, key_fn: F2) -> TokenBucketPolicy<F2, K2> {
+        TokenBucketPolicy {
+            capacity: self.capacity,
+            rate: self.rate,
+            cost: self.cost,
+            key_fn,
+            buckets: Default::default(),
+        }
+    }
+
+    /// Time it takes to refill the given amount of tokens.
+    fn refill_duration(&self, tokens: f64) -> Duration {
+        if tokens <= 0.0 {
+            Duration::ZERO
+        } else if self.rate > 0.0 {
+            Duration::try_from_secs_f64(tokens / self.rate).unwrap_or(Duration::MAX)
+        } else {
+            Duration::MAX
+        }
+    }
+}
+
+impl<F, K, State, Request> Policy<State, Request> for TokenBucketPolicy<F, K>
+where
+    F: RateLimitKeyFn<State, Request, Key = K>,
+    K: Hash + Eq + Send + 'static,
+    State: Clone + Send + Sync + 'static,
+    Request: Send + 'static,
+{
+    type Guard = ();
+    type Error = RateLimitReached;
+
+    async fn check(
+        &self,
+        mut ctx: Context<State>,
+        request: Request,
+    ) -> PolicyResult<State, Request, Self::Guard, Self::Error> {
+        let Some(key) = self.key_fn.rate_limit_key(&ctx, &request) else {
+            return PolicyResult {
+                ctx,
+                request,
+                output: PolicyOutput::Ready(()),
+            };
+        };
+
+        let now = Instant::now();
+        let capacity = self.capacity as f64;
+        let cost = self.cost as f64;
+
+        let (allowed, tokens) = {
+            let mut buckets = self.buckets.lock();
+
+            // drop all buckets which are full by now, as they are
+            // equivalent to a bucket that does not exist yet
+            let rate = self.rate;
+            buckets.prune(|_, bucket| {
+                let elapsed = now.duration_since(bucket.last_refill).as_secs_f64();
+                elapsed.mul_add(rate, bucket.tokens) < capacity
+            });
+
+            let bucket = buckets.entry(key).or_insert(Bucket {
+                tokens: capacity,
+                last_refill: now,
+            });
+
+            let elapsed = now.duration_since(bucket.last_refill).as_secs_f64();
+            bucket.tokens = elapsed.mul_add(self.rate, bucket.tokens).min(capacity);
+            bucket.last_refill = now;
+
+            if bucket.tokens >= cost {
+                bucket.tokens -= cost;
+                (true, bucket.tokens)
+            } else {
+                (false, bucket.tokens)
+            }
+        };
+
+        let info = RateLimitInfo {
+            limit: self.capacity,
+            remaining: (tokens / cost.max(1.0)).floor() as u64,
+            reset: self.refill_duration(capacity - tokens),
+        };
+
+        if allowed {
+            ctx.insert(info);
+            PolicyResult {
+                ctx,
+                request,
+                output: PolicyOutput::Ready(()),
+            }
+        } else {
+            PolicyResult {
+                ctx,
+                request,
+                output: PolicyOutput::Abort(RateLimitReached {
+                    info,
+                    retry_after: self.refill_duration(cost - tokens),
+                }),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_ready<S, R, G, E>(result: PolicyResult<S, R, G, E>) -> Context<S> {
+        match result.output {
+            PolicyOutput::Ready(_) => result.ctx,
+            _ => panic!("unexpected output, expected ready"),
+        }
+    }
+
+    fn assert_abort<S, R, G, E>(result: PolicyResult<S, R, G, E>) -> E {
+        match result.output {
+            PolicyOutput::Abort(err) => err,
+            _ => panic!("unexpected output, expected abort"),
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn token_bucket_policy() {
+        let policy = TokenBucketPolicy::new(2, 1.0);
+
+        let ctx = assert_ready(policy.check(Context::default(), ()).await);
+        assert_eq!(
+            ctx.get::<RateLimitInfo>(),
+            Some(&RateLimitInfo {
+                limit: 2,
+                remaining: 1,
+                reset: Duration::from_secs(1),
+            })
+        );
+        assert_ready(policy.check(Context::default(), ()).await);
+
+        let err = assert_abort(policy.check(Context::default(), ()).await);
+        assert_eq!(err.info.remaining, 0);
+        assert_eq!(err.retry_after, Duration::from_secs(1));
+
+        tokio::time::advance(Duration::from_secs(1)).await;
+        assert_ready(policy.check(Context::default(), ()).await);
+        assert_abort(policy.check(Context::default(), ()).await);
+
+        tokio::time::advance(Duration::from_secs(10)).await;
+        assert_ready(policy.check(Context::default(), ()).await);
+        assert_ready(policy.check(Context::default(), ()).await);
+        assert_abort(policy.check(Context::default(), ()).await);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn token_bucket_policy_cost() {
+        let policy = TokenBucketPolicy::new(5, 1.0).with_cost(2);
+
+        assert_ready(policy.check(Context::default(), ()).await);
+        assert_ready(policy.check(Context::default(), ()).await);
+        let err = assert_abort(policy.check(Context::default(), ()).await);
+        assert_eq!(err.retry_after, Duration::from_secs(1));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn token_bucket_policy_per_key() {
+        let policy =
+            TokenBucketPolicy::new(1, 1.0).with_key_fn(|_ctx: &Context<()>, req: &&'static str| {
+                (!req.is_empty()).then_some(*req)
+            });
+
+        assert_ready(policy.check(Context::default(), "a").await);
+        assert_abort(policy.check(Context::default(), "a").await);
+        assert_ready(policy.check(Context::default(), "b").await);
+        assert_abort(policy.check(Context::default(), "b").await);
+
+        // not rate limited
+        assert_ready(policy.check(Context::default(), "").await);
+        assert_ready(policy.check(Context::default(), "").await);
+    }
+}
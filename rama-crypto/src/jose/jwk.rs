@@ -4,7 +4,9 @@ use aws_lc_rs::{
     rand::SystemRandom,
     signature::{
         self, ECDSA_P256_SHA256_FIXED_SIGNING, EcdsaKeyPair, EcdsaSigningAlgorithm,
-        EcdsaVerificationAlgorithm, KeyPair,
+        EcdsaVerificationAlgorithm, KeyPair, RSA_PKCS1_2048_8192_SHA256,
+        RSA_PKCS1_2048_8192_SHA384, RSA_PKCS1_2048_8192_SHA512, RSA_PSS_2048_8192_SHA256,
+        RSA_PSS_2048_8192_SHA384, RSA_PSS_2048_8192_SHA512, RsaParameters,
     },
 };
 use base64::{Engine as _, prelude::BASE64_URL_SAFE_NO_PAD};
@@ -20,6 +22,8 @@ use crate::jose::JWA;
 pub struct JWK {
     /// Intended algorithm to be used with this key
     alg: JWA,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    kid: Option<String>,
     #[serde(flatten)]
     key_type: JWKType,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -120,6 +124,7 @@ impl JWK {
 
         Ok(Self {
             alg,
+            kid: None,
             key_type: JWKType::EC {
                 crv: curve,
                 x: BASE64_URL_SAFE_NO_PAD.encode(x),
@@ -133,6 +138,16 @@ impl JWK {
         })
     }
 
+    /// Intended algorithm to be used with this [`JWK`]
+    pub fn alg(&self) -> JWA {
+        self.alg
+    }
+
+    /// Identifier of this [`JWK`], used to select the key from a set of keys
+    pub fn kid(&self) -> Option<&str> {
+        self.kid.as_deref()
+    }
+
     /// [`JWKThumb`] as defined in [`rfc7638`] is url safe identifier for a [`JWK`]
     ///
     /// [`rfc7638`]: https://datatracker.ietf.org/doc/html/rfc7638
@@ -150,7 +165,33 @@ impl JWK {
         &self,
     ) -> Result<signature::UnparsedPublicKey<Vec<u8>>, OpaqueError> {
         match &self.key_type {
-            JWKType::RSA { .. } => Err(OpaqueError::from_display("currently not supported")),
+            JWKType::RSA { n, e } => {
+                let alg: &'static RsaParameters = match self.alg {
+                    JWA::RS256 => &RSA_PKCS1_2048_8192_SHA256,
+                    JWA::RS384 => &RSA_PKCS1_2048_8192_SHA384,
+                    JWA::RS512 => &RSA_PKCS1_2048_8192_SHA512,
+                    JWA::PS256 => &RSA_PSS_2048_8192_SHA256,
+                    JWA::PS384 => &RSA_PSS_2048_8192_SHA384,
+                    JWA::PS512 => &RSA_PSS_2048_8192_SHA512,
+                    JWA::HS256 | JWA::HS384 | JWA::HS512 | JWA::ES256 | JWA::ES384 | JWA::ES512 => {
+                        return Err(OpaqueError::from_display(
+                            "RSA key cannot be used with a non-RSA algorithm",
+                        ));
+                    }
+                };
+
+                let n_bytes = BASE64_URL_SAFE_NO_PAD
+                    .decode(n)
+                    .context("decode rsa modulus")?;
+                let e_bytes = BASE64_URL_SAFE_NO_PAD
+                    .decode(e)
+                    .context("decode rsa public exponent")?;
+
+                Ok(signature::UnparsedPublicKey::new(
+                    alg,
+                    rsa_public_key_der(&n_bytes, &e_bytes),
+                ))
+            }
             JWKType::OCT { .. } => Err(OpaqueError::from_display(
                 "Symmetric key cannot be converted to public key",
             )),
@@ -176,6 +217,46 @@ impl JWK {
     }
 }
 
+/// DER encode the given RSA public key components as a `RSAPublicKey` structure,
+/// as defined in [`rfc8017`], which is the format expected to verify signatures.
+///
+/// [`rfc8017`]: https://datatracker.ietf.org/doc/html/rfc8017#appendix-A.1.1
+fn rsa_public_key_der(n: &[u8], e: &[u8]) -> Vec<u8> {
+    fn write_len(out: &mut Vec<u8>, len: usize) {
+        if len < 0x80 {
+            out.push(len as u8);
+        } else {
+            let bytes = len.to_be_bytes();
+            let skip = bytes.iter().take_while(|b| **b == 0).count();
+            out.push(0x80 | (bytes.len() - skip) as u8);
+            out.extend_from_slice(&bytes[skip..]);
+        }
+    }
+
+    fn write_uint(out: &mut Vec<u8>, value: &[u8]) {
+        let skip = value.iter().take_while(|b| **b == 0).count();
+        let value = &value[skip..];
+        // a leading zero is required to keep the integer positive
+        let pad = value.first().is_none_or(|b| *b & 0x80 != 0);
+        out.push(0x02);
+        write_len(out, value.len() + pad as usize);
+        if pad {
+            out.push(0);
+        }
+        out.extend_from_slice(value);
+    }
+
+    let mut content = Vec::with_capacity(n.len() + e.len() + 16);
+    write_uint(&mut content, n);
+    write_uint(&mut content, e);
+
+    let mut out = Vec::with_capacity(content.len() + 4);
+    out.push(0x30);
+    write_len(&mut out, content.len());
+    out.extend_from_slice(&content);
+    out
+}
+
 /// [`EcdsaKey`] which is used to identify and authenticate our requests
 ///
 /// This contains the private and public key we will be using for JWS
@@ -267,6 +348,41 @@ mod tests {
         assert_eq!(&output, expected_output);
     }
 
+    #[test]
+    fn rsa_jwk_can_verify_signatures() {
+        use aws_lc_rs::rsa::{KeyPair as RsaKeyPair, KeySize, PublicKeyComponents};
+
+        let key_pair = RsaKeyPair::generate(KeySize::Rsa2048).unwrap();
+        let components: PublicKeyComponents<Vec<u8>> = key_pair.public_key().into();
+        let jwk = JWK {
+            alg: JWA::RS256,
+            kid: None,
+            key_type: JWKType::RSA {
+                n: BASE64_URL_SAFE_NO_PAD.encode(&components.n),
+                e: BASE64_URL_SAFE_NO_PAD.encode(&components.e),
+            },
+            r#use: Some(JWKUse::Signature),
+            key_ops: None,
+            x5c: None,
+            x5t: None,
+            x5t_sha256: None,
+        };
+
+        let mut signature = vec![0; key_pair.public_modulus_len()];
+        key_pair
+            .sign(
+                &signature::RSA_PKCS1_SHA256,
+                &SystemRandom::new(),
+                b"message",
+                &mut signature,
+            )
+            .unwrap();
+
+        let public_key = jwk.unparsed_public_key().unwrap();
+        assert!(public_key.verify(b"message", &signature).is_ok());
+        assert!(public_key.verify(b"other message", &signature).is_err());
+    }
+
     #[test]
     fn can_generate_and_reuse_keys() {
         let key = EcdsaKey::generate().unwrap();
@@ -0,0 +1,351 @@
+use crate::DnsResolver;
+use parking_lot::RwLock;
+use rama_core::Layer;
+use rama_net::address::Domain;
+use rama_utils::macros::generate_set_and_with;
+use std::{
+    collections::HashMap,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr},
+    sync::{
+        Arc,
+        atomic::{AtomicUsize, Ordering},
+    },
+    time::Duration,
+};
+use tokio::time::Instant;
+
+const DEFAULT_TTL: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone, Default)]
+/// Shared cache of resolved dns records, used by the [`DnsCacheResolver`].
+///
+/// Cloning a [`DnsCache`] results in a handle to the same cache,
+/// which can for example be used to manually [`invalidate`] a domain.
+///
+/// [`invalidate`]: DnsCache::invalidate
+pub struct DnsCache {
+    entries: Arc<RwLock<HashMap<Domain, CacheEntry>>>,
+}
+
+#[derive(Debug, Default)]
+struct CacheEntry {
+    ipv4: Option<CachedRecords>,
+    ipv6: Option<CachedRecords>,
+}
+
+#[derive(Debug)]
+struct CachedRecords {
+    addresses: Vec<IpAddr>,
+    expires_at: Instant,
+    next: AtomicUsize,
+}
+
+impl CachedRecords {
+    fn new(addresses: Vec<IpAddr>, ttl: Duration) -> Self {
+        Self {
+            addresses,
+            expires_at: Instant::now() + ttl,
+            // the lookup which resolved the addresses used them as-is
+            next: AtomicUsize::new(1),
+        }
+    }
+
+    /// Returns the addresses rotated in round-robin fashion,
+    /// such that consecutive lookups start with a different address.
+    fn rotated(&self) -> Option<Vec<IpAddr>> {
+        if self.expires_at <= Instant::now() {
+            return None;
+        }
+        let mut addresses = self.addresses.clone();
+        if !addresses.is_empty() {
+            let offset = self.next.fetch_add(1, Ordering::Relaxed) % addresses.len();
+            addresses.rotate_left(offset);
+        }
+        Some(addresses)
+    }
+}
+
+impl DnsCache {
+    /// Creates a new empty [`DnsCache`].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Remove all cached records for the given domain,
+    /// such that the next lookup is resolved again.
+    pub fn invalidate(&self, domain: &Domain) {
+        self.entries.write().remove(domain);
+    }
+
+    /// Remove all cached records.
+    pub fn clear(&self) {
+        self.entries.write().clear();
+    }
+
+    fn get_ipv4(&self, domain: &Domain) -> Option<Vec<IpAddr>> {
+        self.entries.read().get(domain)?.ipv4.as_ref()?.rotated()
+    }
+
+    fn get_ipv6(&self, domain: &Domain) -> Option<Vec<IpAddr>> {
+        self.entries.read().get(domain)?.ipv6.as_ref()?.rotated()
+    }
+
+    fn insert_ipv4(&self, domain: Domain, addresses: Vec<IpAddr>, ttl: Duration) {
+        self.entries.write().entry(domain).or_default().ipv4 =
+            Some(CachedRecords::new(addresses, ttl));
+    }
+
+    fn insert_ipv6(&self, domain: Domain, addresses: Vec<IpAddr>, ttl: Duration) {
+        self.entries.write().entry(domain).or_default().ipv6 =
+            Some(CachedRecords::new(addresses, ttl));
+    }
+}
+
+#[derive(Debug, Clone)]
+/// A [`Layer`] which wraps a [`DnsResolver`] into a [`DnsCacheResolver`].
+///
+/// The resolver is used by connectors such as the `TcpConnector`,
+/// which otherwise resolve the domain for each connection they establish.
+pub struct DnsCacheLayer {
+    cache: DnsCache,
+    default_ttl: Duration,
+}
+
+impl DnsCacheLayer {
+    /// Creates a new [`DnsCacheLayer`] with its own [`DnsCache`],
+    /// caching records for 60 seconds.
+    pub fn new() -> Self {
+        Self {
+            cache: DnsCache::new(),
+            default_ttl: DEFAULT_TTL,
+        }
+    }
+
+    generate_set_and_with! {
+        /// Set the time-to-live of cached records.
+        pub fn default_ttl(mut self, ttl: Duration) -> Self {
+            self.default_ttl = ttl;
+            self
+        }
+    }
+
+    generate_set_and_with! {
+        /// Set the [`DnsCache`] to use, e.g. to share it between resolvers.
+        pub fn cache(mut self, cache: DnsCache) -> Self {
+            self.cache = cache;
+            self
+        }
+    }
+
+    /// Get a handle to the [`DnsCache`] used by this layer.
+    pub fn dns_cache(&self) -> &DnsCache {
+        &self.cache
+    }
+}
+
+impl Default for DnsCacheLayer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<R> Layer<R> for DnsCacheLayer {
+    type Service = DnsCacheResolver<R>;
+
+    fn layer(&self, inner: R) -> Self::Service {
+        DnsCacheResolver {
+            inner,
+            cache: self.cache.clone(),
+            default_ttl: self.default_ttl,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+/// A [`DnsResolver`] which caches the records resolved by the inner [`DnsResolver`].
+///
+/// Records are cached per domain and record type, and are returned in
+/// round-robin order, such that connections are spread over all addresses.
+/// Failed lookups are not cached.
+///
+/// The [`DnsResolver`] interface does not expose the TTL of the resolved records,
+/// and thus all records are cached for the configured default TTL.
+pub struct DnsCacheResolver<R> {
+    inner: R,
+    cache: DnsCache,
+    default_ttl: Duration,
+}
+
+impl<R> DnsCacheResolver<R> {
+    /// Creates a new [`DnsCacheResolver`] with its own [`DnsCache`],
+    /// caching records for 60 seconds.
+    pub fn new(inner: R) -> Self {
+        DnsCacheLayer::new().into_layer(inner)
+    }
+
+    /// Get a handle to the [`DnsCache`] used by this resolver.
+    pub fn dns_cache(&self) -> &DnsCache {
+        &self.cache
+    }
+
+    /// Get a reference to the inner [`DnsResolver`].
+    pub fn get_ref(&self) -> &R {
+        &self.inner
+    }
+}
+
+impl<R: DnsResolver> DnsResolver for DnsCacheResolver<R> {
+    type Error = R::Error;
+
+    async fn ipv4_lookup(&self, domain: Domain) -> Result<Vec<Ipv4Addr>, Self::Error> {
+        let addresses = match self.cache.get_ipv4(&domain) {
+            Some(addresses) => addresses,
+            None => {
+                let addresses: Vec<IpAddr> = self
+                    .inner
+                    .ipv4_lookup(domain.clone())
+                    .await?
+                    .into_iter()
+                    .map(Into::into)
+                    .collect();
+                self.cache
+                    .insert_ipv4(domain, addresses.clone(), self.default_ttl);
+                addresses
+            }
+        };
+        Ok(addresses
+            .into_iter()
+            .filter_map(|addr| match addr {
+                IpAddr::V4(addr) => Some(addr),
+                IpAddr::V6(_) => None,
+            })
+            .collect())
+    }
+
+    async fn ipv6_lookup(&self, domain: Domain) -> Result<Vec<Ipv6Addr>, Self::Error> {
+        let addresses = match self.cache.get_ipv6(&domain) {
+            Some(addresses) => addresses,
+            None => {
+                let addresses: Vec<IpAddr> = self
+                    .inner
+                    .ipv6_lookup(domain.clone())
+                    .await?
+                    .into_iter()
+                    .map(Into::into)
+                    .collect();
+                self.cache
+                    .insert_ipv6(domain, addresses.clone(), self.default_ttl);
+                addresses
+            }
+        };
+        Ok(addresses
+            .into_iter()
+            .filter_map(|addr| match addr {
+                IpAddr::V6(addr) => Some(addr),
+                IpAddr::V4(_) => None,
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::InMemoryDns;
+
+    #[derive(Debug, Clone)]
+    struct CountingDns {
+        dns: Arc<InMemoryDns>,
+        lookups: Arc<AtomicUsize>,
+    }
+
+    impl DnsResolver for CountingDns {
+        type Error = <InMemoryDns as DnsResolver>::Error;
+
+        async fn ipv4_lookup(&self, domain: Domain) -> Result<Vec<Ipv4Addr>, Self::Error> {
+            self.lookups.fetch_add(1, Ordering::SeqCst);
+            self.dns.ipv4_lookup(domain).await
+        }
+
+        async fn ipv6_lookup(&self, domain: Domain) -> Result<Vec<Ipv6Addr>, Self::Error> {
+            self.lookups.fetch_add(1, Ordering::SeqCst);
+            self.dns.ipv6_lookup(domain).await
+        }
+    }
+
+    fn counting_dns() -> CountingDns {
+        let mut dns = InMemoryDns::new();
+        dns.insert_addresses(
+            Domain::from_static("example.com"),
+            [
+                IpAddr::from(Ipv4Addr::new(127, 0, 0, 1)),
+                IpAddr::from(Ipv4Addr::new(127, 0, 0, 2)),
+                IpAddr::from(Ipv6Addr::LOCALHOST),
+            ],
+        );
+        CountingDns {
+            dns: Arc::new(dns),
+            lookups: Arc::new(AtomicUsize::new(0)),
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_dns_cache_ttl() {
+        let dns = counting_dns();
+        let resolver = DnsCacheLayer::new()
+            .with_default_ttl(Duration::from_secs(10))
+            .into_layer(dns.clone());
+        let domain = Domain::from_static("example.com");
+
+        resolver.ipv4_lookup(domain.clone()).await.unwrap();
+        resolver.ipv4_lookup(domain.clone()).await.unwrap();
+        assert_eq!(dns.lookups.load(Ordering::SeqCst), 1);
+
+        let result = resolver.ipv6_lookup(domain.clone()).await.unwrap();
+        assert_eq!(result, vec![Ipv6Addr::LOCALHOST]);
+        assert_eq!(dns.lookups.load(Ordering::SeqCst), 2);
+
+        tokio::time::advance(Duration::from_secs(11)).await;
+        resolver.ipv4_lookup(domain).await.unwrap();
+        assert_eq!(dns.lookups.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_dns_cache_round_robin() {
+        let resolver = DnsCacheResolver::new(counting_dns());
+        let domain = Domain::from_static("example.com");
+
+        let first = resolver.ipv4_lookup(domain.clone()).await.unwrap();
+        let second = resolver.ipv4_lookup(domain.clone()).await.unwrap();
+        let third = resolver.ipv4_lookup(domain).await.unwrap();
+        assert_eq!(
+            first,
+            vec![Ipv4Addr::new(127, 0, 0, 1), Ipv4Addr::new(127, 0, 0, 2)]
+        );
+        assert_eq!(
+            second,
+            vec![Ipv4Addr::new(127, 0, 0, 2), Ipv4Addr::new(127, 0, 0, 1)]
+        );
+        assert_eq!(first, third);
+    }
+
+    #[tokio::test]
+    async fn test_dns_cache_invalidate() {
+        let dns = counting_dns();
+        let layer = DnsCacheLayer::new();
+        let cache = layer.dns_cache().clone();
+        let resolver = layer.into_layer(dns.clone());
+        let domain = Domain::from_static("example.com");
+
+        resolver.ipv4_lookup(domain.clone()).await.unwrap();
+        cache.invalidate(&domain);
+        resolver.ipv4_lookup(domain.clone()).await.unwrap();
+        assert_eq!(dns.lookups.load(Ordering::SeqCst), 2);
+
+        // failed lookups are not cached
+        let unknown = Domain::from_static("unknown.example.com");
+        assert!(resolver.ipv4_lookup(unknown.clone()).await.is_err());
+        assert!(resolver.ipv4_lookup(unknown).await.is_err());
+        assert_eq!(dns.lookups.load(Ordering::SeqCst), 4);
+    }
+}
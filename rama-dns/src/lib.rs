@@ -132,6 +132,10 @@ pub use deny_all::{DenyAllDns, DnsDeniedError};
 
 pub mod chain;
 
+mod cache;
+#[doc(inline)]
+pub use cache::{DnsCache, DnsCacheLayer, DnsCacheResolver};
+
 mod variant;
 
 mod boxed;
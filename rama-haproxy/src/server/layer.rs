@@ -372,4 +372,52 @@ mod test {
             .unwrap();
         assert_eq!("foo", String::from_utf8(response).unwrap());
     }
+
+    #[tokio::test]
+    async fn test_haproxy_forwarded_client_socket_addr() {
+        async fn client_addr<State>(
+            ctx: Context<State>,
+            _stream: impl Stream + Unpin,
+        ) -> Result<Option<SocketAddr>, BoxError> {
+            Ok(ctx
+                .get::<Forwarded>()
+                .and_then(Forwarded::client_socket_addr))
+        }
+
+        let proxy_svc = HaProxyService::new(service_fn(client_addr));
+
+        for (data, expected) in [
+            (
+                b"PROXY TCP4 192.0.2.1 198.51.100.1 12345 80\r\n".to_vec(),
+                "192.0.2.1:12345",
+            ),
+            (
+                b"PROXY TCP6 2001:db8::1 2001:db8::2 12345 443\r\n".to_vec(),
+                "[2001:db8::1]:12345",
+            ),
+            (
+                vec![
+                    0x0D, 0x0A, 0x0D, 0x0A, 0x00, 0x0D, 0x0A, 0x51, 0x55, 0x49, 0x54, 0x0A, 0x21,
+                    0x11, 0x00, 0x0C, 0xC0, 0x00, 0x02, 0x01, 0xC6, 0x33, 0x64, 0x01, 0x30, 0x39,
+                    0x01, 0xBB,
+                ],
+                "192.0.2.1:12345",
+            ),
+        ] {
+            let addr = proxy_svc
+                .serve(Context::default(), std::io::Cursor::new(data))
+                .await
+                .unwrap();
+            assert_eq!(Some(expected.parse().unwrap()), addr);
+        }
+
+        let addr = proxy_svc
+            .serve(
+                Context::default(),
+                std::io::Cursor::new(b"PROXY UNKNOWN\r\n".to_vec()),
+            )
+            .await
+            .unwrap();
+        assert_eq!(None, addr);
+    }
 }
@@ -0,0 +1,423 @@
+use rama_core::{
+    Context, Layer, Service,
+    bytes::Bytes,
+    error::{BoxError, ErrorContext, OpaqueError},
+    telemetry::tracing,
+};
+use rama_http_core::h2::Reason;
+use rama_http_types::{
+    Body, Method, Request,
+    dep::{
+        http::request::Parts,
+        http_body,
+        http_body_util::{BodyExt, Limited},
+    },
+};
+use rama_utils::macros::{define_inner_service_accessors, generate_set_and_with};
+use std::{error::Error as StdError, fmt};
+
+const DEFAULT_MAX_REPLAYS: usize = 3;
+const DEFAULT_MAX_BODY_SIZE: usize = 1024 * 1024;
+
+/// Layer that applies the [`H2GoawayRetry`] middleware.
+#[derive(Debug, Clone)]
+pub struct H2GoawayRetryLayer {
+    max_replays: usize,
+    max_body_size: usize,
+}
+
+impl H2GoawayRetryLayer {
+    /// Create a new [`H2GoawayRetryLayer`], replaying a request at most 3 times,
+    /// and only in case its body is at most 1 MiB.
+    pub const fn new() -> Self {
+        Self {
+            max_replays: DEFAULT_MAX_REPLAYS,
+            max_body_size: DEFAULT_MAX_BODY_SIZE,
+        }
+    }
+
+    generate_set_and_with! {
+        /// Set the maximum amount of times a single request is replayed.
+        pub fn max_replays(mut self, max: usize) -> Self {
+            self.max_replays = max;
+            self
+        }
+    }
+
+    generate_set_and_with! {
+        /// Set the maximum size of a request body to be buffered for replay,
+        /// requests with a larger (or unknown) body size are passed through without replay.
+        pub fn max_body_size(mut self, max: usize) -> Self {
+            self.max_body_size = max;
+            self
+        }
+    }
+}
+
+impl Default for H2GoawayRetryLayer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<S> Layer<S> for H2GoawayRetryLayer {
+    type Service = H2GoawayRetry<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        H2GoawayRetry {
+            inner,
+            max_replays: self.max_replays,
+            max_body_size: self.max_body_size,
+        }
+    }
+}
+
+/// Replay requests refused by an upstream server which sent an HTTP/2 `GOAWAY` frame.
+///
+/// A `GOAWAY` frame contains the `last_stream_id` which the server might have processed.
+/// Requests in-flight on streams with a higher id are guaranteed to be unprocessed,
+/// and fail with a `GOAWAY` error, while the others complete on the old connection.
+/// The failed requests are replayed using the inner service, which establishes
+/// a new connection, as the closed connection is no longer used (or pooled).
+///
+/// As the server might however have processed requests on the old connection
+/// in case the `GOAWAY` is sent because of an error, only requests with an idempotent
+/// method are replayed, unless the `GOAWAY` is graceful (`NO_ERROR`) or
+/// explicitly refuses the stream (`REFUSED_STREAM`).
+///
+/// The request body is buffered such that the request can be replayed,
+/// requests with a body larger than the maximum body size (1 MiB by default),
+/// or of unknown size, are passed through as-is without replay.
+/// Once the maximum amount of replays is reached, the error is returned as-is.
+pub struct H2GoawayRetry<S> {
+    inner: S,
+    max_replays: usize,
+    max_body_size: usize,
+}
+
+impl<S> H2GoawayRetry<S> {
+    /// Create a new [`H2GoawayRetry`], replaying a request at most 3 times,
+    /// and only in case its body is at most 1 MiB.
+    pub const fn new(inner: S) -> Self {
+        Self {
+            inner,
+            max_replays: DEFAULT_MAX_REPLAYS,
+            max_body_size: DEFAULT_MAX_BODY_SIZE,
+        }
+    }
+
+    generate_set_and_with! {
+        /// Set the maximum amount of times a single request is replayed.
+        pub fn max_replays(mut self, max: usize) -> Self {
+            self.max_replays = max;
+            self
+        }
+    }
+
+    generate_set_and_with! {
+        /// Set the maximum size of a request body to be buffered for replay,
+        /// requests with a larger (or unknown) body size are passed through without replay.
+        pub fn max_body_size(mut self, max: usize) -> Self {
+            self.max_body_size = max;
+            self
+        }
+    }
+
+    define_inner_service_accessors!();
+}
+
+impl<S: fmt::Debug> fmt::Debug for H2GoawayRetry<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("H2GoawayRetry")
+            .field("inner", &self.inner)
+            .field("max_replays", &self.max_replays)
+            .field("max_body_size", &self.max_body_size)
+            .finish()
+    }
+}
+
+impl<S: Clone> Clone for H2GoawayRetry<S> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            max_replays: self.max_replays,
+            max_body_size: self.max_body_size,
+        }
+    }
+}
+
+impl<State, S, ReqBody> Service<State, Request<ReqBody>> for H2GoawayRetry<S>
+where
+    State: Clone + Send + Sync + 'static,
+    S: Service<State, Request, Error: Into<BoxError>>,
+    ReqBody: http_body::Body<Data = Bytes, Error: Into<BoxError>> + Send + Sync + 'static,
+{
+    type Response = S::Response;
+    type Error = BoxError;
+
+    async fn serve(
+        &self,
+        ctx: Context<State>,
+        req: Request<ReqBody>,
+    ) -> Result<Self::Response, Self::Error> {
+        let (parts, body) = req.into_parts();
+        if body
+            .size_hint()
+            .upper()
+            .is_none_or(|upper| upper > self.max_body_size as u64)
+        {
+            tracing::trace!(
+                url.full = %parts.uri,
+                "request body too large for h2 goaway replay: pass through",
+            );
+            let req = Request::from_parts(parts, Body::new(body));
+            return self.inner.serve(ctx, req).await.map_err(Into::into);
+        }
+
+        let body = Limited::new(body, self.max_body_size)
+            .collect()
+            .await
+            .map_err(OpaqueError::from_boxed)
+            .context("buffer request body for h2 goaway replay")?
+            .to_bytes();
+
+        let mut replays = 0;
+        loop {
+            let req = replay_request(&parts, &body);
+            let err = match self.inner.serve(ctx.clone(), req).await {
+                Ok(res) => return Ok(res),
+                Err(err) => err.into(),
+            };
+
+            let Some(reason) = remote_go_away_reason(err.as_ref()) else {
+                return Err(err);
+            };
+            if !is_idempotent(&parts.method)
+                && reason != Reason::NO_ERROR
+                && reason != Reason::REFUSED_STREAM
+            {
+                tracing::debug!(
+                    url.full = %parts.uri,
+                    "non-idempotent request failed by h2 goaway ({reason:?}): not replayed",
+                );
+                return Err(err);
+            }
+            if replays >= self.max_replays {
+                tracing::debug!(
+                    replays,
+                    "request refused by h2 goaway: max replays reached: {err}",
+                );
+                return Err(err);
+            }
+
+            replays += 1;
+            tracing::debug!(
+                url.full = %parts.uri,
+                replays,
+                "request refused by h2 goaway: replay on new connection",
+            );
+        }
+    }
+}
+
+fn replay_request(parts: &Parts, body: &Bytes) -> Request {
+    Request::from_parts(parts.clone(), Body::from(body.clone()))
+}
+
+/// Returns the reason of the `GOAWAY` error received from the peer,
+/// in case the error chain contains one.
+fn remote_go_away_reason(err: &(dyn StdError + 'static)) -> Option<Reason> {
+    let mut cause = Some(err);
+    while let Some(err) = cause {
+        if let Some(h2_err) = err.downcast_ref::<rama_http_core::h2::Error>() {
+            return if h2_err.is_go_away() && h2_err.is_remote() {
+                h2_err.reason()
+            } else {
+                None
+            };
+        }
+        cause = err.source();
+    }
+    None
+}
+
+/// Returns true for methods which can be safely replayed,
+/// even if the request was (partially) processed, as defined in RFC 9110, section 9.2.2.
+fn is_idempotent(method: &Method) -> bool {
+    matches!(
+        *method,
+        Method::GET | Method::HEAD | Method::OPTIONS | Method::TRACE | Method::PUT | Method::DELETE
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rama_core::{rt::Executor, service::service_fn};
+    use rama_http_types::{BodyExtractExt, Response};
+    use std::sync::{
+        Arc,
+        atomic::{AtomicUsize, Ordering},
+    };
+    use tokio::io::{AsyncReadExt, AsyncWriteExt, DuplexStream};
+
+    const FRAME_HEADERS: u8 = 0x1;
+    const FRAME_SETTINGS: u8 = 0x4;
+    const FRAME_GOAWAY: u8 = 0x7;
+
+    async fn write_frame(io: &mut DuplexStream, kind: u8, flags: u8, stream_id: u32, data: &[u8]) {
+        let mut frame = (data.len() as u32).to_be_bytes()[1..].to_vec();
+        frame.extend([kind, flags]);
+        frame.extend(stream_id.to_be_bytes());
+        frame.extend(data);
+        io.write_all(&frame).await.unwrap();
+    }
+
+    /// Minimal h2 upstream which either responds with `200 OK`
+    /// or sends a `GOAWAY` with `last_stream_id=0` and the given reason upon receiving a request.
+    async fn upstream(mut io: DuplexStream, go_away: Option<Reason>) {
+        let mut preface = [0; 24];
+        io.read_exact(&mut preface).await.unwrap();
+        write_frame(&mut io, FRAME_SETTINGS, 0, 0, &[]).await;
+
+        loop {
+            let mut header = [0; 9];
+            if io.read_exact(&mut header).await.is_err() {
+                return;
+            }
+            let len = u32::from_be_bytes([0, header[0], header[1], header[2]]) as usize;
+            let mut payload = vec![0; len];
+            io.read_exact(&mut payload).await.unwrap();
+
+            if header[3] == FRAME_HEADERS {
+                let stream_id = u32::from_be_bytes(header[5..9].try_into().unwrap());
+                if let Some(reason) = go_away {
+                    let mut payload = [0; 8];
+                    payload[4..].copy_from_slice(&u32::from(reason).to_be_bytes());
+                    write_frame(&mut io, FRAME_GOAWAY, 0, 0, &payload).await;
+                } else {
+                    // ':status: 200' (static table index 8), END_STREAM | END_HEADERS
+                    write_frame(&mut io, FRAME_HEADERS, 0x5, stream_id, &[0x88]).await;
+                }
+            }
+        }
+    }
+
+    fn client(
+        go_aways: usize,
+        reason: Reason,
+        attempts: Arc<AtomicUsize>,
+    ) -> impl Service<(), Request, Response = Response, Error = BoxError> {
+        service_fn(move |req: Request| {
+            let attempt = attempts.fetch_add(1, Ordering::SeqCst);
+            async move {
+                let (client_io, server_io) = tokio::io::duplex(64 * 1024);
+                tokio::spawn(upstream(server_io, (attempt < go_aways).then_some(reason)));
+
+                let (mut sender, conn) =
+                    rama_http_core::client::conn::http2::handshake(Executor::default(), client_io)
+                        .await?;
+                tokio::spawn(conn);
+                let res = sender.send_request(req).await?;
+                Ok::<_, BoxError>(res.map(Body::new))
+            }
+        })
+    }
+
+    fn request() -> Request {
+        Request::builder()
+            .uri("http://example.com/")
+            .body(Body::from("hello"))
+            .unwrap()
+    }
+
+    fn post_request() -> Request {
+        Request::builder()
+            .method(Method::POST)
+            .uri("http://example.com/")
+            .body(Body::from("hello"))
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_h2_goaway_replayed() {
+        let attempts = Arc::new(AtomicUsize::new(0));
+        let svc =
+            H2GoawayRetryLayer::new().into_layer(client(2, Reason::NO_ERROR, attempts.clone()));
+
+        let res = svc.serve(Context::default(), request()).await.unwrap();
+        assert_eq!(res.status(), 200);
+        assert_eq!(res.try_into_string().await.unwrap(), "");
+        assert_eq!(attempts.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_h2_goaway_max_replays() {
+        let attempts = Arc::new(AtomicUsize::new(0));
+        let svc = H2GoawayRetryLayer::new()
+            .with_max_replays(1)
+            .into_layer(client(usize::MAX, Reason::NO_ERROR, attempts.clone()));
+
+        let err = svc.serve(Context::default(), request()).await.unwrap_err();
+        assert!(remote_go_away_reason(err.as_ref()).is_some(), "{err:?}");
+        assert_eq!(attempts.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_h2_other_errors_not_replayed() {
+        let attempts = Arc::new(AtomicUsize::new(0));
+        let svc = H2GoawayRetryLayer::new().into_layer(service_fn({
+            let attempts = attempts.clone();
+            move |_req: Request| {
+                attempts.fetch_add(1, Ordering::SeqCst);
+                std::future::ready(Err::<Response, _>(
+                    OpaqueError::from_display("connection refused").into_boxed(),
+                ))
+            }
+        }));
+
+        assert!(svc.serve(Context::default(), request()).await.is_err());
+        assert_eq!(attempts.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_h2_goaway_non_idempotent_replay() {
+        for (reason, expected_attempts) in [
+            (Reason::NO_ERROR, 2),
+            (Reason::REFUSED_STREAM, 2),
+            (Reason::INTERNAL_ERROR, 1),
+        ] {
+            let attempts = Arc::new(AtomicUsize::new(0));
+            let svc = H2GoawayRetryLayer::new().into_layer(client(1, reason, attempts.clone()));
+
+            let result = svc.serve(Context::default(), post_request()).await;
+            assert_eq!(result.is_ok(), expected_attempts == 2, "reason: {reason:?}");
+            assert_eq!(
+                attempts.load(Ordering::SeqCst),
+                expected_attempts,
+                "reason: {reason:?}"
+            );
+        }
+
+        let attempts = Arc::new(AtomicUsize::new(0));
+        let svc = H2GoawayRetryLayer::new().into_layer(client(
+            1,
+            Reason::INTERNAL_ERROR,
+            attempts.clone(),
+        ));
+        svc.serve(Context::default(), request()).await.unwrap();
+        assert_eq!(attempts.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_h2_goaway_large_body_not_replayed() {
+        let attempts = Arc::new(AtomicUsize::new(0));
+        let svc = H2GoawayRetryLayer::new()
+            .with_max_body_size(4)
+            .into_layer(client(1, Reason::NO_ERROR, attempts.clone()));
+
+        let err = svc.serve(Context::default(), request()).await.unwrap_err();
+        assert!(remote_go_away_reason(err.as_ref()).is_some(), "{err:?}");
+        assert_eq!(attempts.load(Ordering::SeqCst), 1);
+    }
+}
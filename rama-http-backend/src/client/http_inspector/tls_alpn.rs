@@ -69,6 +69,7 @@ mod tests {
         let mut ctx = Context::default();
         ctx.insert(NegotiatedTlsParameters {
             application_layer_protocol: Some(rama_net::tls::ApplicationProtocol::HTTP_11),
+            cipher_suite: None,
             peer_certificate_chain: None,
             protocol_version: rama_net::tls::ProtocolVersion::TLSv1_3,
         });
@@ -81,6 +82,7 @@ mod tests {
         let mut ctx = Context::default();
         ctx.insert(NegotiatedTlsParameters {
             application_layer_protocol: Some(rama_net::tls::ApplicationProtocol::HTTP_2),
+            cipher_suite: None,
             peer_certificate_chain: None,
             protocol_version: rama_net::tls::ProtocolVersion::TLSv1_3,
         });
@@ -100,6 +102,7 @@ mod tests {
 
         ctx.insert(NegotiatedTlsParameters {
             application_layer_protocol: Some(rama_net::tls::ApplicationProtocol::HTTP_11),
+            cipher_suite: None,
             peer_certificate_chain: None,
             protocol_version: rama_net::tls::ProtocolVersion::TLSv1_3,
         });
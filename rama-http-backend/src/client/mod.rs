@@ -20,6 +20,10 @@ mod conn;
 #[doc(inline)]
 pub use conn::{HttpConnector, HttpConnectorLayer};
 
+mod goaway;
+#[doc(inline)]
+pub use goaway::{H2GoawayRetry, H2GoawayRetryLayer};
+
 pub mod http_inspector;
 pub mod proxy;
 
@@ -166,7 +170,8 @@ pub use easy_connector::EasyHttpWebClientBuilder;
 
 mod easy_connector {
     use super::{
-        HttpConnector, http_inspector::HttpVersionAdapater, proxy::layer::HttpProxyConnector,
+        HttpClientService, HttpConnector, http_inspector::HttpVersionAdapater,
+        proxy::layer::HttpProxyConnector,
     };
     use rama_core::{
         Layer, Service,
@@ -642,14 +647,23 @@ mod easy_connector {
         ///
         /// Use `wait_for_pool_timeout` to limit how long we wait for the pool to give us a connection
         ///
+        /// Idle connections which were closed in the meantime (e.g. by the server)
+        /// are dropped by the pool instead of being reused
+        ///
         /// If you need a different pool or custom way to group connection you can
         /// use [`EasyHttpWebClientBuilder::with_custom_connection_pool()`] to provide
         /// you own.
-        pub fn with_connection_pool<C>(
+        pub fn with_connection_pool<Body, I>(
             self,
             config: HttpPooledConnectorConfig,
-        ) -> Result<DefaultConnectionPoolBuilder<T, C>, OpaqueError> {
-            let connector = config.build_connector(self.connector)?;
+        ) -> Result<DefaultConnectionPoolBuilder<T, HttpClientService<Body, I>>, OpaqueError>
+        {
+            let pool = config
+                .build_pool()?
+                .with_health_check(|conn: &HttpClientService<Body, I>| !conn.is_closed());
+            let connector =
+                PooledConnector::new(self.connector, pool, BasicHttpConnIdentifier::default())
+                    .maybe_with_wait_for_pool_timeout(config.wait_for_pool_timeout);
 
             Ok(EasyHttpWebClientBuilder {
                 connector,
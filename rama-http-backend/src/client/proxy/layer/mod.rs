@@ -7,5 +7,11 @@ mod proxy_auth_header;
 pub use proxy_auth_header::{SetProxyAuthHttpHeaderLayer, SetProxyAuthHttpHeaderService};
 
 mod proxy_connector;
+#[cfg(feature = "tls")]
 #[doc(inline)]
-pub use proxy_connector::{HttpProxyConnector, HttpProxyConnectorLayer, HttpProxyError};
+pub use proxy_connector::HttpsProxyConnectorLayer;
+#[doc(inline)]
+pub use proxy_connector::{
+    ClientCredentialsRefresher, HttpProxyConnector, HttpProxyConnectorLayer, HttpProxyError,
+    NoProxy, NoProxyEntry, ProxyChain, TokenRefresher,
+};
@@ -2,7 +2,11 @@ use rama_core::telemetry::tracing;
 use rama_core::{Context, Layer, Service};
 use rama_http_headers::{HeaderMapExt, ProxyAuthorization};
 use rama_http_types::Request;
-use rama_net::{address::ProxyAddress, http::RequestContext, user::ProxyCredential};
+use rama_net::{
+    address::ProxyAddress,
+    http::RequestContext,
+    user::{Bearer, ProxyCredential},
+};
 use std::fmt;
 
 #[derive(Debug, Clone, Default)]
@@ -91,6 +95,20 @@ where
                         tracing::trace!("inserted proxy Bearer credentials into (http) request");
                         req.headers_mut().typed_insert(ProxyAuthorization(bearer))
                     }
+                    ProxyCredential::OAuth2 { token, .. } => match Bearer::new(token) {
+                        Ok(bearer) => {
+                            // OAuth2 tokens are used as Bearer tokens, and thus also always need to be inserted
+                            tracing::trace!(
+                                "inserted proxy OAuth2 credentials into (http) request"
+                            );
+                            req.headers_mut().typed_insert(ProxyAuthorization(bearer))
+                        }
+                        Err(err) => {
+                            tracing::debug!(
+                                "failed to insert invalid proxy OAuth2 token into (http) request: {err}"
+                            );
+                        }
+                    },
                 }
             }
         }
@@ -0,0 +1,100 @@
+use rama_net::address::ProxyAddress;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// An ordered list of http proxies, used to reach a target via multiple hops.
+///
+/// The first [`ProxyAddress`] is the proxy that is connected to directly,
+/// each following proxy is reached by tunneling (http `CONNECT`)
+/// through the previous hop, with the last hop being the proxy
+/// that is used to reach the target authority.
+///
+/// The target is always tunneled to, even for plain text requests,
+/// and the [`ProxyAddress`] found in the [`Context`] (if any) is left untouched.
+///
+/// It can be inserted into the [`Context`] or be used as a static chain
+/// by the [`HttpProxyConnectorLayer`].
+///
+/// [`Context`]: rama_core::Context
+/// [`HttpProxyConnectorLayer`]: super::HttpProxyConnectorLayer
+pub struct ProxyChain(Vec<ProxyAddress>);
+
+impl ProxyChain {
+    /// Create a new [`ProxyChain`] from the given hops.
+    pub fn new(hops: impl IntoIterator<Item = ProxyAddress>) -> Self {
+        Self(hops.into_iter().collect())
+    }
+
+    /// Return the number of hops in this [`ProxyChain`].
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Return `true` if this [`ProxyChain`] contains no hops.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Return an iterator over the hops of this [`ProxyChain`].
+    pub fn iter(&self) -> std::slice::Iter<'_, ProxyAddress> {
+        self.0.iter()
+    }
+
+    /// Return the first hop of this [`ProxyChain`],
+    /// being the proxy that is connected to directly.
+    pub fn first(&self) -> Option<&ProxyAddress> {
+        self.0.first()
+    }
+
+    /// Return the last hop of this [`ProxyChain`],
+    /// being the proxy that is used to reach the target.
+    pub fn last(&self) -> Option<&ProxyAddress> {
+        self.0.last()
+    }
+}
+
+impl From<Vec<ProxyAddress>> for ProxyChain {
+    fn from(value: Vec<ProxyAddress>) -> Self {
+        Self(value)
+    }
+}
+
+impl From<ProxyAddress> for ProxyChain {
+    fn from(value: ProxyAddress) -> Self {
+        Self(vec![value])
+    }
+}
+
+impl FromIterator<ProxyAddress> for ProxyChain {
+    fn from_iter<T: IntoIterator<Item = ProxyAddress>>(iter: T) -> Self {
+        Self::new(iter)
+    }
+}
+
+impl<'a> IntoIterator for &'a ProxyChain {
+    type Item = &'a ProxyAddress;
+    type IntoIter = std::slice::Iter<'a, ProxyAddress>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.iter()
+    }
+}
+
+impl IntoIterator for ProxyChain {
+    type Item = ProxyAddress;
+    type IntoIter = std::vec::IntoIter<ProxyAddress>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
+#[derive(Debug, Clone)]
+/// Where a [`ProxyChain`] is to be found, if at all.
+pub(super) enum ProxyChainSource {
+    /// Use the same static [`ProxyChain`] for all connections.
+    Static(ProxyChain),
+    /// Use the [`ProxyChain`] found in the [`Context`], if any.
+    ///
+    /// [`Context`]: rama_core::Context
+    Context,
+}
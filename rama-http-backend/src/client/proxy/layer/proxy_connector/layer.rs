@@ -1,5 +1,7 @@
-use super::HttpProxyConnector;
-use rama_core::Layer;
+use super::{
+    BoxTokenRefresher, HttpProxyConnector, ProxyChain, ProxyChainSource, ProxyEnv, TokenRefresher,
+};
+use rama_core::{Layer, error::OpaqueError};
 use rama_http_types::Version;
 
 #[derive(Debug, Clone, Default)]
@@ -9,6 +11,12 @@ use rama_http_types::Version;
 pub struct HttpProxyConnectorLayer {
     required: bool,
     version: Option<Version>,
+    chain: Option<ProxyChainSource>,
+    env: Option<ProxyEnv>,
+    token_refresher: Option<BoxTokenRefresher>,
+    preserve_upgrade_headers: bool,
+    #[cfg(feature = "tls")]
+    secure_proxy: bool,
 }
 
 impl HttpProxyConnectorLayer {
@@ -22,6 +30,12 @@ impl HttpProxyConnectorLayer {
         Self {
             required: false,
             version: Some(Version::HTTP_11),
+            chain: None,
+            env: None,
+            token_refresher: None,
+            preserve_upgrade_headers: false,
+            #[cfg(feature = "tls")]
+            secure_proxy: false,
         }
     }
 
@@ -35,9 +49,89 @@ impl HttpProxyConnectorLayer {
         Self {
             required: true,
             version: Some(Version::HTTP_11),
+            chain: None,
+            env: None,
+            token_refresher: None,
+            preserve_upgrade_headers: false,
+            #[cfg(feature = "tls")]
+            secure_proxy: false,
         }
     }
 
+    /// Create a new [`HttpProxyConnectorLayer`] which creates a [`HttpProxyConnector`]
+    /// which will always connect via the given [`ProxyChain`],
+    /// tunneling through each hop in order until the target is reached.
+    ///
+    /// A [`ProxyAddress`] found in the [`Context`] is ignored in this mode.
+    ///
+    /// [`Context`]: rama_core::Context
+    /// [`ProxyAddress`]: rama_net::address::ProxyAddress
+    pub fn proxy_chain(chain: impl Into<ProxyChain>) -> Self {
+        Self {
+            required: true,
+            version: Some(Version::HTTP_11),
+            chain: Some(ProxyChainSource::Static(chain.into())),
+            env: None,
+            token_refresher: None,
+            preserve_upgrade_headers: false,
+            #[cfg(feature = "tls")]
+            secure_proxy: false,
+        }
+    }
+
+    /// Create a new [`HttpProxyConnectorLayer`] which creates a [`HttpProxyConnector`]
+    /// which will connect via the [`ProxyChain`] found in the [`Context`],
+    /// falling back to the [`ProxyAddress`] in case no chain is available.
+    ///
+    /// The connection fails in case neither of the two is available.
+    ///
+    /// [`Context`]: rama_core::Context
+    /// [`ProxyAddress`]: rama_net::address::ProxyAddress
+    pub fn proxy_chain_from_context() -> Self {
+        Self {
+            required: true,
+            version: Some(Version::HTTP_11),
+            chain: Some(ProxyChainSource::Context),
+            env: None,
+            token_refresher: None,
+            preserve_upgrade_headers: false,
+            #[cfg(feature = "tls")]
+            secure_proxy: false,
+        }
+    }
+
+    /// Try to create a new [`HttpProxyConnectorLayer`] which creates a [`HttpProxyConnector`]
+    /// which will connect via the http proxy defined by the environment, following curl conventions:
+    ///
+    /// - `HTTPS_PROXY` for secure targets and `http_proxy` for all other targets,
+    ///   both falling back to `ALL_PROXY`;
+    /// - `NO_PROXY` as a comma-separated list of destinations to connect to directly,
+    ///   see [`NoProxy`] for more information.
+    ///
+    /// The lowercase variants of these variables take precedence. The uppercase
+    /// `HTTP_PROXY` is never read, as it can be set by a client in a CGI environment (httpoxy).
+    /// Proxies with a protocol which cannot be served by the [`HttpProxyConnector`]
+    /// (e.g. `ALL_PROXY=socks5://...`) are ignored.
+    /// A [`ProxyAddress`] or [`NoProxy`] found in the [`Context`] takes precedence
+    /// over the ones defined by the environment. A direct connection is made
+    /// in case no proxy is defined for the target.
+    ///
+    /// [`Context`]: rama_core::Context
+    /// [`ProxyAddress`]: rama_net::address::ProxyAddress
+    /// [`NoProxy`]: super::NoProxy
+    pub fn proxy_from_env() -> Result<Self, OpaqueError> {
+        Ok(Self {
+            required: false,
+            version: Some(Version::HTTP_11),
+            chain: None,
+            env: Some(ProxyEnv::try_from_env()?),
+            token_refresher: None,
+            preserve_upgrade_headers: false,
+            #[cfg(feature = "tls")]
+            secure_proxy: false,
+        })
+    }
+
     /// Set the HTTP version to use for the CONNECT request.
     ///
     /// By default this is set to HTTP/1.1.
@@ -63,6 +157,68 @@ impl HttpProxyConnectorLayer {
         self.version = None;
         self
     }
+
+    /// Tunnel (plain text) WebSocket connections through the proxy using
+    /// an http `CONNECT` request, such that upgrade headers are preserved.
+    ///
+    /// See [`HttpProxyConnector::with_preserve_upgrade_headers`] for more information.
+    pub fn with_preserve_upgrade_headers(mut self, preserve: bool) -> Self {
+        self.preserve_upgrade_headers = preserve;
+        self
+    }
+
+    /// Tunnel (plain text) WebSocket connections through the proxy using
+    /// an http `CONNECT` request, such that upgrade headers are preserved.
+    ///
+    /// See [`HttpProxyConnector::with_preserve_upgrade_headers`] for more information.
+    pub fn set_preserve_upgrade_headers(&mut self, preserve: bool) -> &mut Self {
+        self.preserve_upgrade_headers = preserve;
+        self
+    }
+
+    #[cfg(feature = "tls")]
+    /// Connect to the proxy over tls, regardless of the protocol of the [`ProxyAddress`].
+    ///
+    /// See [`HttpProxyConnector::with_secure_proxy`] for more information.
+    ///
+    /// [`ProxyAddress`]: rama_net::address::ProxyAddress
+    pub fn with_secure_proxy(mut self, secure: bool) -> Self {
+        self.secure_proxy = secure;
+        self
+    }
+
+    #[cfg(feature = "tls")]
+    /// Connect to the proxy over tls, regardless of the protocol of the [`ProxyAddress`].
+    ///
+    /// See [`HttpProxyConnector::with_secure_proxy`] for more information.
+    ///
+    /// [`ProxyAddress`]: rama_net::address::ProxyAddress
+    pub fn set_secure_proxy(&mut self, secure: bool) -> &mut Self {
+        self.secure_proxy = secure;
+        self
+    }
+
+    /// Set the [`TokenRefresher`] used to refresh [`ProxyCredential::OAuth2`]
+    /// credentials prior to the proxy handshake.
+    ///
+    /// See [`HttpProxyConnector::with_token_refresher`] for more information.
+    ///
+    /// [`ProxyCredential::OAuth2`]: rama_net::user::ProxyCredential::OAuth2
+    pub fn with_token_refresher(mut self, refresher: impl TokenRefresher) -> Self {
+        self.token_refresher = Some(BoxTokenRefresher::new(refresher));
+        self
+    }
+
+    /// Set the [`TokenRefresher`] used to refresh [`ProxyCredential::OAuth2`]
+    /// credentials prior to the proxy handshake.
+    ///
+    /// See [`HttpProxyConnector::with_token_refresher`] for more information.
+    ///
+    /// [`ProxyCredential::OAuth2`]: rama_net::user::ProxyCredential::OAuth2
+    pub fn set_token_refresher(&mut self, refresher: impl TokenRefresher) -> &mut Self {
+        self.token_refresher = Some(BoxTokenRefresher::new(refresher));
+        self
+    }
 }
 
 impl<S> Layer<S> for HttpProxyConnectorLayer {
@@ -70,6 +226,12 @@ impl<S> Layer<S> for HttpProxyConnectorLayer {
 
     fn layer(&self, inner: S) -> Self::Service {
         let mut svc = HttpProxyConnector::new(inner, self.required);
+        svc.chain = self.chain.clone();
+        svc.env = self.env.clone();
+        svc.token_refresher = self.token_refresher.clone();
+        svc.set_preserve_upgrade_headers(self.preserve_upgrade_headers);
+        #[cfg(feature = "tls")]
+        svc.set_secure_proxy(self.secure_proxy);
         match self.version {
             Some(version) => svc.set_version(version),
             None => svc.set_auto_version(),
@@ -77,3 +239,45 @@ impl<S> Layer<S> for HttpProxyConnectorLayer {
         svc
     }
 }
+
+#[cfg(feature = "tls")]
+#[derive(Debug, Clone)]
+/// A [`Layer`] which wraps the given service with a tls connector in tunnel mode
+/// (e.g. the `TlsConnectorLayer::tunnel` layer of `rama-tls-boring` or `rama-tls-rustls`),
+/// followed by a [`HttpProxyConnector`] which connects to the proxy over that tls connection.
+///
+/// This is needed for proxies which require tls to the proxy endpoint itself.
+/// The `CONNECT` request is sent over this secure channel, and the connection
+/// to a secure target can be secured once more on top of it by an outer tls connector.
+pub struct HttpsProxyConnectorLayer<L> {
+    tls: L,
+    proxy: HttpProxyConnectorLayer,
+}
+
+#[cfg(feature = "tls")]
+impl<L> HttpsProxyConnectorLayer<L> {
+    /// Create a new [`HttpsProxyConnectorLayer`] which secures the connection
+    /// to the proxy using the given tls (tunnel) connector layer, prior to
+    /// applying the given [`HttpProxyConnectorLayer`].
+    ///
+    /// The [`HttpProxyConnectorLayer`] is set to always connect to the proxy over tls,
+    /// see [`HttpProxyConnectorLayer::with_secure_proxy`].
+    pub fn new(tls: L, proxy: HttpProxyConnectorLayer) -> Self {
+        Self {
+            tls,
+            proxy: proxy.with_secure_proxy(true),
+        }
+    }
+}
+
+#[cfg(feature = "tls")]
+impl<L, S> Layer<S> for HttpsProxyConnectorLayer<L>
+where
+    L: Layer<S>,
+{
+    type Service = HttpProxyConnector<L::Service>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        self.proxy.layer(self.tls.layer(inner))
+    }
+}
@@ -2,6 +2,21 @@ mod connector;
 // internal usage only
 use connector::InnerHttpProxyConnector;
 
+mod chain;
+#[doc(inline)]
+pub use chain::ProxyChain;
+use chain::ProxyChainSource;
+
+mod env;
+use env::ProxyEnv;
+#[doc(inline)]
+pub use env::{NoProxy, NoProxyEntry};
+
+mod token_refresher;
+use token_refresher::BoxTokenRefresher;
+#[doc(inline)]
+pub use token_refresher::{ClientCredentialsRefresher, TokenRefresher};
+
 mod proxy_error;
 #[doc(inline)]
 pub use proxy_error::HttpProxyError;
@@ -9,6 +24,9 @@ pub use proxy_error::HttpProxyError;
 mod layer;
 #[doc(inline)]
 pub use layer::HttpProxyConnectorLayer;
+#[cfg(feature = "tls")]
+#[doc(inline)]
+pub use layer::HttpsProxyConnectorLayer;
 
 mod service;
 #[doc(inline)]
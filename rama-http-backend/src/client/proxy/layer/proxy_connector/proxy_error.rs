@@ -24,6 +24,18 @@ pub enum HttpProxyError {
     Other(String),
 }
 
+impl HttpProxyError {
+    /// Kind of the error, as recorded in tracing spans.
+    pub(super) fn kind(&self) -> &'static str {
+        match self {
+            HttpProxyError::AuthRequired => "auth_required",
+            HttpProxyError::Unavailable => "unavailable",
+            HttpProxyError::Transport(_) => "transport",
+            HttpProxyError::Other(_) => "other",
+        }
+    }
+}
+
 impl fmt::Display for HttpProxyError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
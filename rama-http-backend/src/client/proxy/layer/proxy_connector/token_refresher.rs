@@ -0,0 +1,371 @@
+use parking_lot::Mutex;
+use rama_core::{
+    Context, Service,
+    error::{BoxError, ErrorContext, OpaqueError},
+    telemetry::tracing,
+};
+use rama_http::service::client::HttpClientExt;
+use rama_http_types::{BodyExtractExt, Request, Response, Uri};
+use rama_net::user::{Basic, ProxyCredential};
+use serde::Deserialize;
+use std::{
+    fmt,
+    pin::Pin,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+/// Trait that can be implemented to refresh [`ProxyCredential::OAuth2`] credentials
+/// prior to them being used for an http proxy handshake.
+///
+/// See [`HttpProxyConnector::with_token_refresher`] for more information.
+///
+/// [`HttpProxyConnector::with_token_refresher`]: super::HttpProxyConnector::with_token_refresher
+pub trait TokenRefresher: Send + Sync + 'static {
+    /// Refresh the given [`ProxyCredential::OAuth2`] credential in case required,
+    /// returning the credential to be used for the proxy handshake.
+    fn refresh_token(
+        &self,
+        credential: ProxyCredential,
+    ) -> impl Future<Output = Result<ProxyCredential, OpaqueError>> + Send + '_;
+}
+
+/// Internal trait to support dynamic dispatch of trait with async fn.
+/// See trait [`rama_core::service::svc::DynService`] for more info about this pattern.
+trait DynTokenRefresher {
+    fn refresh_token_boxed(
+        &self,
+        credential: ProxyCredential,
+    ) -> Pin<Box<dyn Future<Output = Result<ProxyCredential, OpaqueError>> + Send + '_>>;
+}
+
+impl<T> DynTokenRefresher for T
+where
+    T: TokenRefresher,
+{
+    fn refresh_token_boxed(
+        &self,
+        credential: ProxyCredential,
+    ) -> Pin<Box<dyn Future<Output = Result<ProxyCredential, OpaqueError>> + Send + '_>> {
+        Box::pin(self.refresh_token(credential))
+    }
+}
+
+#[derive(Clone)]
+/// A type-erased [`TokenRefresher`].
+pub(super) struct BoxTokenRefresher(Arc<dyn DynTokenRefresher + Send + Sync>);
+
+impl BoxTokenRefresher {
+    pub(super) fn new(refresher: impl TokenRefresher) -> Self {
+        Self(Arc::new(refresher))
+    }
+
+    pub(super) async fn refresh_token(
+        &self,
+        credential: ProxyCredential,
+    ) -> Result<ProxyCredential, OpaqueError> {
+        self.0.refresh_token_boxed(credential).await
+    }
+}
+
+impl fmt::Debug for BoxTokenRefresher {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BoxTokenRefresher").finish()
+    }
+}
+
+/// A [`TokenRefresher`] which fetches a new access token from an OAuth2 token endpoint,
+/// using the `client_credentials` grant type, in case the [`ProxyCredential::OAuth2`]
+/// credential expires within the configured threshold.
+///
+/// The client id and secret are sent to the token endpoint using http basic authentication.
+/// The last fetched token is cached, and reused for as long as it does not expire
+/// within the threshold.
+pub struct ClientCredentialsRefresher<C> {
+    client: C,
+    token_endpoint: Uri,
+    credentials: Basic,
+    scope: Option<String>,
+    threshold: Duration,
+    cache: Arc<Mutex<Option<ProxyCredential>>>,
+}
+
+impl<C: fmt::Debug> fmt::Debug for ClientCredentialsRefresher<C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ClientCredentialsRefresher")
+            .field("client", &self.client)
+            .field("token_endpoint", &self.token_endpoint)
+            .field("credentials", &self.credentials)
+            .field("scope", &self.scope)
+            .field("threshold", &self.threshold)
+            .field("cache", &self.cache)
+            .finish()
+    }
+}
+
+impl<C: Clone> Clone for ClientCredentialsRefresher<C> {
+    fn clone(&self) -> Self {
+        Self {
+            client: self.client.clone(),
+            token_endpoint: self.token_endpoint.clone(),
+            credentials: self.credentials.clone(),
+            scope: self.scope.clone(),
+            threshold: self.threshold,
+            cache: self.cache.clone(),
+        }
+    }
+}
+
+impl<C> ClientCredentialsRefresher<C> {
+    /// Create a new [`ClientCredentialsRefresher`], which uses the given http client
+    /// to fetch new access tokens from the given token endpoint.
+    ///
+    /// By default tokens are refreshed when they expire within 60 seconds.
+    pub fn new(
+        client: C,
+        token_endpoint: Uri,
+        client_id: impl Into<String>,
+        client_secret: impl Into<String>,
+    ) -> Self {
+        Self {
+            client,
+            token_endpoint,
+            credentials: Basic::new(client_id.into(), client_secret.into()),
+            scope: None,
+            threshold: Duration::from_secs(60),
+            cache: Default::default(),
+        }
+    }
+
+    /// Set the scope to request the access token for.
+    pub fn with_scope(mut self, scope: impl Into<String>) -> Self {
+        self.scope = Some(scope.into());
+        self
+    }
+
+    /// Set the scope to request the access token for.
+    pub fn set_scope(&mut self, scope: impl Into<String>) -> &mut Self {
+        self.scope = Some(scope.into());
+        self
+    }
+
+    /// Set the threshold within which a to be expired token is refreshed.
+    pub fn with_threshold(mut self, threshold: Duration) -> Self {
+        self.threshold = threshold;
+        self
+    }
+
+    /// Set the threshold within which a to be expired token is refreshed.
+    pub fn set_threshold(&mut self, threshold: Duration) -> &mut Self {
+        self.threshold = threshold;
+        self
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct TokenResponse {
+    access_token: String,
+    #[serde(default)]
+    token_type: Option<String>,
+    #[serde(default)]
+    expires_in: Option<u64>,
+    #[serde(default)]
+    refresh_token: Option<String>,
+}
+
+impl<C> ClientCredentialsRefresher<C>
+where
+    C: Service<(), Request, Response = Response, Error: Into<BoxError>>,
+{
+    async fn fetch_token(
+        &self,
+        refresh_token: Option<String>,
+    ) -> Result<ProxyCredential, OpaqueError> {
+        let mut form = vec![("grant_type", "client_credentials")];
+        if let Some(scope) = self.scope.as_deref() {
+            form.push(("scope", scope));
+        }
+
+        let response = self
+            .client
+            .post(self.token_endpoint.clone())
+            .auth(self.credentials.clone())
+            .form(&form)
+            .send(Context::default())
+            .await
+            .context("send OAuth2 token request")?;
+        if !response.status().is_success() {
+            return Err(OpaqueError::from_display(format!(
+                "OAuth2 token request failed with status {}",
+                response.status()
+            )));
+        }
+
+        let token: TokenResponse = response
+            .try_into_json()
+            .await
+            .context("decode OAuth2 token response")?;
+        if let Some(token_type) = token.token_type.as_deref()
+            && !token_type.eq_ignore_ascii_case("bearer")
+        {
+            return Err(OpaqueError::from_display(format!(
+                "unsupported OAuth2 token type: {token_type}"
+            )));
+        }
+
+        Ok(ProxyCredential::OAuth2 {
+            token: token.access_token,
+            expires_at: token
+                .expires_in
+                .map(|secs| Instant::now() + Duration::from_secs(secs)),
+            refresh_token: token.refresh_token.or(refresh_token),
+        })
+    }
+}
+
+impl<C> TokenRefresher for ClientCredentialsRefresher<C>
+where
+    C: Service<(), Request, Response = Response, Error: Into<BoxError>>,
+{
+    async fn refresh_token(
+        &self,
+        credential: ProxyCredential,
+    ) -> Result<ProxyCredential, OpaqueError> {
+        if !credential.expires_within(self.threshold) {
+            return Ok(credential);
+        }
+
+        if let Some(cached) = self.cache.lock().clone()
+            && !cached.expires_within(self.threshold)
+        {
+            tracing::trace!("use cached OAuth2 proxy token");
+            return Ok(cached);
+        }
+
+        tracing::trace!(
+            url.full = %self.token_endpoint,
+            "refresh OAuth2 proxy token using client credentials grant",
+        );
+        let refresh_token = match credential {
+            ProxyCredential::OAuth2 { refresh_token, .. } => refresh_token,
+            _ => None,
+        };
+        let credential = self.fetch_token(refresh_token).await?;
+        *self.cache.lock() = Some(credential.clone());
+        Ok(credential)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rama_core::service::service_fn;
+    use rama_http_types::{Body, StatusCode, header};
+    use std::{
+        convert::Infallible,
+        sync::atomic::{AtomicUsize, Ordering},
+    };
+
+    fn oauth2(token: &str, expires_in: Option<Duration>) -> ProxyCredential {
+        ProxyCredential::OAuth2 {
+            token: token.to_owned(),
+            expires_at: expires_in.map(|d| Instant::now() + d),
+            refresh_token: Some("refresh".to_owned()),
+        }
+    }
+
+    fn token(credential: &ProxyCredential) -> &str {
+        match credential {
+            ProxyCredential::OAuth2 { token, .. } => token,
+            _ => panic!("unexpected credential: {credential:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_client_credentials_refresher() {
+        let counter = Arc::new(AtomicUsize::new(0));
+        let client = {
+            let counter = counter.clone();
+            service_fn(move |req: Request| {
+                let counter = counter.clone();
+                async move {
+                    let n = counter.fetch_add(1, Ordering::SeqCst);
+                    assert_eq!(req.method(), "POST");
+                    assert_eq!(req.uri(), "https://auth.example.com/token");
+                    assert_eq!(
+                        req.headers().get(header::AUTHORIZATION).unwrap(),
+                        "Basic aWQ6c2VjcmV0",
+                    );
+                    let body = req.try_into_string().await.unwrap();
+                    assert_eq!(body, "grant_type=client_credentials&scope=proxy");
+                    Ok::<_, Infallible>(Response::new(Body::from(format!(
+                        r#"{{"access_token":"token-{n}","token_type":"Bearer","expires_in":3600}}"#
+                    ))))
+                }
+            })
+        };
+        let refresher = ClientCredentialsRefresher::new(
+            client,
+            Uri::from_static("https://auth.example.com/token"),
+            "id",
+            "secret",
+        )
+        .with_scope("proxy")
+        .with_threshold(Duration::from_secs(30));
+
+        // valid tokens are left untouched
+        let credential = refresher
+            .refresh_token(oauth2("valid", Some(Duration::from_secs(60))))
+            .await
+            .unwrap();
+        assert_eq!(token(&credential), "valid");
+        let credential = refresher
+            .refresh_token(oauth2("forever", None))
+            .await
+            .unwrap();
+        assert_eq!(token(&credential), "forever");
+        assert_eq!(counter.load(Ordering::SeqCst), 0);
+
+        // tokens about to expire are refreshed
+        let credential = refresher
+            .refresh_token(oauth2("expiring", Some(Duration::from_secs(10))))
+            .await
+            .unwrap();
+        assert_eq!(token(&credential), "token-0");
+        assert!(!credential.expires_within(Duration::from_secs(30)));
+        match &credential {
+            ProxyCredential::OAuth2 { refresh_token, .. } => {
+                assert_eq!(refresh_token.as_deref(), Some("refresh"))
+            }
+            _ => unreachable!(),
+        }
+
+        // and the refreshed token is cached
+        let credential = refresher
+            .refresh_token(oauth2("expired", Some(Duration::ZERO)))
+            .await
+            .unwrap();
+        assert_eq!(token(&credential), "token-0");
+        assert_eq!(counter.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_client_credentials_refresher_error() {
+        let refresher = ClientCredentialsRefresher::new(
+            service_fn(async |_req: Request| {
+                let mut res = Response::new(Body::from(r#"{"error":"invalid_client"}"#));
+                *res.status_mut() = StatusCode::UNAUTHORIZED;
+                Ok::<_, Infallible>(res)
+            }),
+            Uri::from_static("https://auth.example.com/token"),
+            "id",
+            "secret",
+        );
+        assert!(
+            refresher
+                .refresh_token(oauth2("expired", Some(Duration::ZERO)))
+                .await
+                .is_err()
+        );
+    }
+}
@@ -39,6 +39,22 @@ pub struct HttpClientService<Body, I = ()> {
     pub(super) http_req_inspector: I,
 }
 
+impl<Body, I> HttpClientService<Body, I> {
+    /// Checks if the underlying http connection has been closed, e.g. by the peer.
+    ///
+    /// This is mostly a hint, as the connection can still be closed right after checking.
+    pub fn is_closed(&self) -> bool {
+        match &self.sender {
+            // a locked sender is in use, and thus was not (yet) closed
+            SendRequest::Http1(sender) => sender
+                .try_lock()
+                .map(|sender| sender.is_closed())
+                .unwrap_or_default(),
+            SendRequest::Http2(sender) => sender.is_closed(),
+        }
+    }
+}
+
 impl<State, BodyIn, BodyOut, I> Service<State, Request<BodyIn>> for HttpClientService<BodyOut, I>
 where
     State: Clone + Send + Sync + 'static,
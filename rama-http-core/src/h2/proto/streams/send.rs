@@ -598,4 +598,8 @@ impl Send {
     pub(crate) fn is_extended_connect_protocol_enabled(&self) -> bool {
         self.is_extended_connect_protocol_enabled
     }
+
+    pub(crate) fn is_push_enabled(&self) -> bool {
+        self.is_push_enabled
+    }
 }
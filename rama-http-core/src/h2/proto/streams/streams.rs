@@ -1367,6 +1367,16 @@ impl<B> StreamRef<B> {
     pub(crate) fn stream_id(&self) -> StreamId {
         self.opaque.stream_id()
     }
+
+    pub(crate) fn is_push_enabled(&self) -> bool {
+        self.opaque
+            .inner
+            .lock()
+            .unwrap()
+            .actions
+            .send
+            .is_push_enabled()
+    }
 }
 
 impl<B> Clone for StreamRef<B> {
@@ -1195,6 +1195,16 @@ impl<B: Buf> SendResponse<B> {
     pub fn stream_id(&self) -> StreamId {
         self.inner.stream_id()
     }
+
+    /// Returns whether the client allows server push,
+    /// as advertised using the `SETTINGS_ENABLE_PUSH` setting.
+    ///
+    /// # Panics
+    ///
+    /// If the lock on the stream store has been poisoned.
+    pub fn is_push_enabled(&self) -> bool {
+        self.inner.is_push_enabled()
+    }
 }
 
 // ===== impl SendPushedResponse =====
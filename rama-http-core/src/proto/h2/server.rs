@@ -1,3 +1,4 @@
+use std::io::Cursor;
 use std::pin::Pin;
 use std::task::{Context, Poll, ready};
 use std::time::Duration;
@@ -5,11 +6,12 @@ use std::time::Duration;
 use crate::h2::server::{Connection, Handshake, SendResponse};
 use crate::h2::{Reason, RecvStream};
 use pin_project_lite::pin_project;
-use rama_core::bytes::Bytes;
+use rama_core::bytes::{Buf, Bytes};
 use rama_core::error::BoxError;
 use rama_core::rt::Executor;
 use rama_core::telemetry::tracing::{Instrument, debug, trace, trace_root_span, warn};
 use rama_http::io::upgrade::{self, OnUpgrade, Pending, Upgraded};
+use rama_http::layer::h2_push::{H2PushEnabled, H2PushPromise, H2PushPromises};
 use rama_http::opentelemetry::version_as_protocol_version;
 use rama_http_types::{Method, Request, Response, header};
 use tokio::io::{AsyncRead, AsyncWrite};
@@ -246,6 +248,9 @@ where
 
                         let is_connect = req.method() == Method::CONNECT;
                         let (mut parts, stream) = req.into_parts();
+                        if respond.is_push_enabled() {
+                            parts.extensions.insert(H2PushEnabled);
+                        }
                         let (req, connect_parts) = if !is_connect {
                             (
                                 Request::from_parts(
@@ -456,6 +461,12 @@ where
                     let mut res = Response::from_parts(head, ());
                     super::strip_connection_headers(res.headers_mut(), false);
 
+                    if let Some(H2PushPromises(promises)) =
+                        res.extensions_mut().remove::<H2PushPromises>()
+                    {
+                        send_push_promises(me.reply, promises, *me.date_header);
+                    }
+
                     // set Date header if it isn't already set if instructed
                     if *me.date_header {
                         res.headers_mut()
@@ -517,6 +528,63 @@ where
     }
 }
 
+/// Send a push promise, followed by the pushed response, for each of the given promises.
+///
+/// Pushing stops at the first promise which is refused, e.g. because
+/// the client disabled server push.
+fn send_push_promises<B: Buf>(
+    reply: &mut SendResponse<SendBuf<B>>,
+    promises: Vec<H2PushPromise>,
+    date_header: bool,
+) {
+    for promise in promises {
+        let H2PushPromise {
+            uri,
+            request_headers,
+            status,
+            response_headers,
+            body,
+        } = promise;
+
+        let mut req = Request::new(());
+        *req.uri_mut() = uri;
+        *req.headers_mut() = request_headers;
+
+        let mut pushed = match reply.push_request(req) {
+            Ok(pushed) => pushed,
+            Err(e) => {
+                debug!("h2 push promise refused: {:?}", e);
+                return;
+            }
+        };
+
+        let mut res = Response::new(());
+        *res.status_mut() = status;
+        *res.headers_mut() = response_headers;
+        super::strip_connection_headers(res.headers_mut(), false);
+        if date_header {
+            res.headers_mut()
+                .entry(header::DATE)
+                .or_insert_with(date::update_and_header_value);
+        }
+        headers::set_content_length_if_missing(res.headers_mut(), body.len() as u64);
+
+        let eos = body.is_empty();
+        match pushed.send_response(res, eos) {
+            Ok(mut stream) if !eos => {
+                let data = SendBuf::Cursor(Cursor::new(body.to_vec().into_boxed_slice()));
+                if let Err(e) = stream.send_data(data, true) {
+                    debug!("send pushed response body error: {:?}", e);
+                }
+            }
+            Ok(_) => (),
+            Err(e) => {
+                debug!("send pushed response error: {:?}", e);
+            }
+        }
+    }
+}
+
 impl<F, B, E> Future for H2Stream<F, B>
 where
     F: Future<Output = Result<Response<B>, E>>,
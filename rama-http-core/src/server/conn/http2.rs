@@ -283,4 +283,127 @@ mod tests {
         fn g<T: Send + 'static>() {}
         g::<Connection<TcpStream, VoidHttpService>>();
     }
+
+    #[cfg(not(miri))]
+    #[tokio::test]
+    async fn test_push_promises() {
+        use crate::body::Bytes;
+        use crate::service::RamaHttpService;
+        use rama_core::Context;
+        use rama_core::service::service_fn;
+        use rama_http::layer::h2_push::{H2PushPromise, H2PushPromises};
+        use rama_http_types::{HeaderMap, Request, Response, StatusCode};
+        use std::convert::Infallible;
+        use tokio::net::TcpListener;
+
+        async fn handle(_req: Request) -> Result<Response, Infallible> {
+            let mut res = Response::new(rama_http_types::Body::from("index"));
+            res.extensions_mut()
+                .insert(H2PushPromises(vec![H2PushPromise {
+                    uri: "https://example.com/style.css".parse().unwrap(),
+                    request_headers: HeaderMap::new(),
+                    status: StatusCode::OK,
+                    response_headers: HeaderMap::new(),
+                    body: Bytes::from_static(b"body {}"),
+                }]));
+            Ok(res)
+        }
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            Builder::new(Executor::new())
+                .serve_connection(
+                    stream,
+                    RamaHttpService::new(Context::default(), service_fn(handle)),
+                )
+                .await
+                .unwrap();
+        });
+
+        let stream = TcpStream::connect(addr).await.unwrap();
+        let (client, connection) = crate::h2::client::Builder::new()
+            .enable_push(true)
+            .handshake::<_, Bytes>(stream)
+            .await
+            .unwrap();
+        tokio::spawn(connection);
+
+        let mut client = client.ready().await.unwrap();
+        let req = Request::builder()
+            .uri("https://example.com/")
+            .body(())
+            .unwrap();
+        let (mut res_fut, _) = client.send_request(req, true).unwrap();
+        let mut push_promises = res_fut.push_promises();
+
+        let res = res_fut.await.unwrap();
+        let mut body = res.into_body();
+        assert_eq!(body.data().await.unwrap().unwrap(), "index");
+
+        let (pushed_req, pushed_res) = push_promises
+            .push_promise()
+            .await
+            .unwrap()
+            .unwrap()
+            .into_parts();
+        assert_eq!(pushed_req.uri(), "https://example.com/style.css");
+        let pushed_res = pushed_res.await.unwrap();
+        assert_eq!(pushed_res.status(), StatusCode::OK);
+        let mut body = pushed_res.into_body();
+        assert_eq!(body.data().await.unwrap().unwrap(), "body {}");
+    }
+
+    #[cfg(not(miri))]
+    #[tokio::test]
+    async fn test_push_enabled_marker() {
+        use crate::body::Bytes;
+        use crate::service::RamaHttpService;
+        use rama_core::Context;
+        use rama_core::service::service_fn;
+        use rama_http::layer::h2_push::H2PushEnabled;
+        use rama_http_types::{Request, Response};
+        use std::convert::Infallible;
+        use tokio::net::TcpListener;
+
+        async fn handle(req: Request) -> Result<Response, Infallible> {
+            let enabled = req.extensions().get::<H2PushEnabled>().is_some();
+            Ok(Response::new(rama_http_types::Body::from(
+                enabled.to_string(),
+            )))
+        }
+
+        for enable_push in [true, false] {
+            let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let addr = listener.local_addr().unwrap();
+            tokio::spawn(async move {
+                let (stream, _) = listener.accept().await.unwrap();
+                Builder::new(Executor::new())
+                    .serve_connection(
+                        stream,
+                        RamaHttpService::new(Context::default(), service_fn(handle)),
+                    )
+                    .await
+                    .unwrap();
+            });
+
+            let stream = TcpStream::connect(addr).await.unwrap();
+            let (client, connection) = crate::h2::client::Builder::new()
+                .enable_push(enable_push)
+                .handshake::<_, Bytes>(stream)
+                .await
+                .unwrap();
+            tokio::spawn(connection);
+
+            let mut client = client.ready().await.unwrap();
+            let req = Request::builder()
+                .uri("https://example.com/")
+                .body(())
+                .unwrap();
+            let (res_fut, _) = client.send_request(req, true).unwrap();
+            let mut body = res_fut.await.unwrap().into_body();
+            assert_eq!(body.data().await.unwrap().unwrap(), enable_push.to_string());
+        }
+    }
 }
@@ -51,6 +51,18 @@ impl RetryAfter {
     pub fn delay(dur: Duration) -> RetryAfter {
         RetryAfter(After::Delay(dur.into()))
     }
+
+    /// Get the delay to wait before retrying, relative to `now`.
+    ///
+    /// A date value in the past results in a zero delay.
+    pub fn delay_from(&self, now: SystemTime) -> Duration {
+        match self.0 {
+            After::Delay(delay) => delay.into(),
+            After::DateTime(date) => SystemTime::from(date)
+                .duration_since(now)
+                .unwrap_or_default(),
+        }
+    }
 }
 
 impl TryFromValues for After {
@@ -83,7 +95,7 @@ impl<'a> From<&'a After> for HeaderValue {
 
 #[cfg(test)]
 mod tests {
-    use std::time::Duration;
+    use std::time::{Duration, SystemTime};
 
     use super::super::test_decode;
     use super::RetryAfter;
@@ -107,6 +119,23 @@ mod tests {
         };
     }
 
+    #[test]
+    fn delay_from() {
+        let now = SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        assert_eq!(
+            RetryAfter::delay(Duration::from_secs(30)).delay_from(now),
+            Duration::from_secs(30)
+        );
+        assert_eq!(
+            RetryAfter::date(now + Duration::from_secs(60)).delay_from(now),
+            Duration::from_secs(60)
+        );
+        assert_eq!(
+            RetryAfter::date(now - Duration::from_secs(60)).delay_from(now),
+            Duration::ZERO
+        );
+    }
+
     test_retry_after_datetime!(date_decode_rfc1123, "Sun, 06 Nov 1994 08:49:37 GMT");
     test_retry_after_datetime!(date_decode_rfc850, "Sunday, 06-Nov-94 08:49:37 GMT");
     test_retry_after_datetime!(date_decode_asctime, "Sun Nov  6 08:49:37 1994");
@@ -0,0 +1,145 @@
+use crate::dep::http_body::{self, Frame, SizeHint};
+use crate::dep::http_body_util::BodyExt;
+use rama_core::bytes::Bytes;
+use std::convert::Infallible;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+#[derive(Debug, Default)]
+/// A body which is fully read into memory, such that it can be read multiple times.
+///
+/// Each clone of a [`BufferedBody`] can be read from the start,
+/// cloning is cheap as the underlying [`Bytes`] are shared.
+///
+/// The [`ReadBodyLayer`] can be used to buffer the body of requests,
+/// making it available for extractors and layers alike.
+///
+/// [`ReadBodyLayer`]: crate::layer::read_body::ReadBodyLayer
+pub struct BufferedBody {
+    bytes: Bytes,
+    consumed: bool,
+}
+
+impl BufferedBody {
+    /// Create a new [`BufferedBody`] from the given bytes.
+    pub fn new(bytes: impl Into<Bytes>) -> Self {
+        Self {
+            bytes: bytes.into(),
+            consumed: false,
+        }
+    }
+
+    /// Read the given body fully into memory, creating a [`BufferedBody`].
+    pub async fn buffer<B>(body: B) -> Result<Self, B::Error>
+    where
+        B: http_body::Body,
+    {
+        Ok(Self::new(body.collect().await?.to_bytes()))
+    }
+
+    /// Return the buffered bytes.
+    pub fn bytes(&self) -> &Bytes {
+        &self.bytes
+    }
+
+    /// Turn this body into its buffered bytes.
+    pub fn into_bytes(self) -> Bytes {
+        self.bytes
+    }
+
+    /// Return the length of the buffered body in bytes.
+    pub fn len(&self) -> usize {
+        self.bytes.len()
+    }
+
+    /// Return `true` in case the buffered body is empty.
+    pub fn is_empty(&self) -> bool {
+        self.bytes.is_empty()
+    }
+}
+
+impl Clone for BufferedBody {
+    fn clone(&self) -> Self {
+        Self::new(self.bytes.clone())
+    }
+}
+
+impl From<Bytes> for BufferedBody {
+    fn from(bytes: Bytes) -> Self {
+        Self::new(bytes)
+    }
+}
+
+impl From<BufferedBody> for crate::Body {
+    fn from(body: BufferedBody) -> Self {
+        if body.consumed {
+            crate::Body::empty()
+        } else {
+            body.bytes.into()
+        }
+    }
+}
+
+impl http_body::Body for BufferedBody {
+    type Data = Bytes;
+    type Error = Infallible;
+
+    fn poll_frame(
+        mut self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
+        if self.is_end_stream() {
+            return Poll::Ready(None);
+        }
+        self.consumed = true;
+        Poll::Ready(Some(Ok(Frame::data(self.bytes.clone()))))
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.consumed || self.bytes.is_empty()
+    }
+
+    fn size_hint(&self) -> SizeHint {
+        if self.consumed {
+            SizeHint::with_exact(0)
+        } else {
+            SizeHint::with_exact(self.bytes.len() as u64)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::BodyExtractExt;
+    use crate::dep::http_body::Body as _;
+
+    #[tokio::test]
+    async fn test_buffered_body_replay() {
+        let body = BufferedBody::buffer(crate::Body::from("hello"))
+            .await
+            .unwrap();
+        assert_eq!(body.len(), 5);
+
+        let mut first = body.clone();
+        let frame = first.frame().await.unwrap().unwrap();
+        assert_eq!(frame.into_data().unwrap(), "hello");
+        assert!(first.is_end_stream());
+        assert!(first.frame().await.is_none());
+
+        let s = crate::Body::new(body.clone())
+            .try_into_string()
+            .await
+            .unwrap();
+        assert_eq!(s, "hello");
+        assert_eq!(body.into_bytes(), "hello");
+    }
+
+    #[test]
+    fn test_buffered_body_empty() {
+        let body = BufferedBody::default();
+        assert!(body.is_empty());
+        assert!(body.is_end_stream());
+        assert_eq!(body.size_hint().exact(), Some(0));
+    }
+}
@@ -0,0 +1,90 @@
+use crate::Body;
+use crate::dep::http_body::{self, Body as _};
+use rama_core::bytes::{Bytes, BytesMut};
+use rama_core::error::{BoxError, OpaqueError};
+use rama_core::futures::{StreamExt, stream};
+
+/// Outcome of [`collect_limited`].
+pub(crate) enum LimitedBody {
+    /// The body was read fully into memory.
+    Collected(Bytes),
+    /// The body exceeds the limit.
+    ///
+    /// The returned body replays the data read so far,
+    /// followed by the remainder of the original body.
+    Exceeded(Body),
+}
+
+/// Read the given body into memory for as long as it does not exceed the given limit.
+///
+/// Trailers are dropped, same as when collecting a body into [`Bytes`].
+pub(crate) async fn collect_limited<B>(body: B, limit: usize) -> Result<LimitedBody, OpaqueError>
+where
+    B: http_body::Body<Data = Bytes, Error: Into<BoxError>> + Send + Sync + 'static,
+{
+    let mut body = Body::new(body);
+    if body.size_hint().lower() > limit as u64 {
+        return Ok(LimitedBody::Exceeded(body));
+    }
+
+    let mut chunks = Vec::new();
+    let mut size = 0;
+    while let Some(chunk) = body.chunk().await.map_err(OpaqueError::from_boxed)? {
+        size += chunk.len();
+        chunks.push(chunk);
+        if size > limit {
+            let read = stream::iter(chunks.into_iter().map(Ok::<_, BoxError>));
+            return Ok(LimitedBody::Exceeded(Body::from_stream(
+                read.chain(body.into_data_stream()),
+            )));
+        }
+    }
+
+    Ok(LimitedBody::Collected(match chunks.len() {
+        0 => Bytes::new(),
+        1 => chunks.swap_remove(0),
+        _ => {
+            let mut bytes = BytesMut::with_capacity(size);
+            for chunk in chunks {
+                bytes.extend_from_slice(&chunk);
+            }
+            bytes.freeze()
+        }
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dep::http_body_util::BodyExt;
+
+    #[tokio::test]
+    async fn test_collect_limited() {
+        let body = || {
+            Body::from_stream(stream::iter(
+                ["hello", " ", "world"].map(|chunk| Ok::<_, BoxError>(Bytes::from(chunk))),
+            ))
+        };
+
+        match collect_limited(body(), 11).await.unwrap() {
+            LimitedBody::Collected(bytes) => assert_eq!(bytes, "hello world"),
+            LimitedBody::Exceeded(_) => panic!("body should fit within limit"),
+        }
+
+        match collect_limited(body(), 5).await.unwrap() {
+            LimitedBody::Collected(_) => panic!("body should exceed limit"),
+            LimitedBody::Exceeded(body) => {
+                let bytes = body.collect().await.unwrap().to_bytes();
+                assert_eq!(bytes, "hello world");
+            }
+        }
+
+        match collect_limited(Body::from("hello world"), 5).await.unwrap() {
+            LimitedBody::Collected(_) => panic!("body should exceed limit"),
+            LimitedBody::Exceeded(body) => {
+                let bytes = body.collect().await.unwrap().to_bytes();
+                assert_eq!(bytes, "hello world");
+            }
+        }
+    }
+}
@@ -0,0 +1,8 @@
+//! Http body utilities.
+
+mod buffered;
+#[doc(inline)]
+pub use buffered::BufferedBody;
+
+mod limited;
+pub(crate) use limited::{LimitedBody, collect_limited};
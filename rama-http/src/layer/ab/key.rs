@@ -0,0 +1,95 @@
+use crate::headers::{Cookie, HeaderMapExt};
+use crate::service::web::extract::client_ip::resolve_client_ip;
+use crate::{HeaderName, Request};
+use rama_core::Context;
+use std::borrow::Cow;
+
+#[derive(Debug, Clone)]
+/// The key used by the [`AbTestingService`] for sticky variant assignment.
+///
+/// [`AbTestingService`]: super::AbTestingService
+pub enum AbKey {
+    /// Use the value of the given header.
+    Header(HeaderName),
+    /// Use the value of the cookie with the given name.
+    Cookie(Cow<'static, str>),
+    /// Use the IP address of the client, resolved as done by the [`ClientIp`] extractor.
+    ///
+    /// [`ClientIp`]: crate::service::web::extract::ClientIp
+    ClientIp,
+}
+
+impl AbKey {
+    /// Create an [`AbKey`] using the value of the given header.
+    pub fn header(name: HeaderName) -> Self {
+        Self::Header(name)
+    }
+
+    /// Create an [`AbKey`] using the value of the cookie with the given name.
+    pub fn cookie(name: impl Into<Cow<'static, str>>) -> Self {
+        Self::Cookie(name.into())
+    }
+
+    /// Hash the key of the request, or return `None` if the request has no such key.
+    pub(super) fn hash<State, Body>(
+        &self,
+        ctx: &Context<State>,
+        req: &Request<Body>,
+    ) -> Option<u64> {
+        match self {
+            Self::Header(name) => req.headers().get(name).map(|value| fnv1a(value.as_bytes())),
+            Self::Cookie(name) => req
+                .headers()
+                .typed_get::<Cookie>()
+                .and_then(|cookie| cookie.get(name).map(|value| fnv1a(value.as_bytes()))),
+            Self::ClientIp => {
+                resolve_client_ip(ctx, req.headers()).map(|ip| fnv1a(ip.to_string().as_bytes()))
+            }
+        }
+    }
+}
+
+/// 64-bit FNV-1a hash, used as it is stable across builds and platforms,
+/// unlike the hashers of the standard library.
+fn fnv1a(bytes: &[u8]) -> u64 {
+    const OFFSET_BASIS: u64 = 0xcbf29ce484222325;
+    const PRIME: u64 = 0x100000001b3;
+
+    bytes.iter().fold(OFFSET_BASIS, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(PRIME)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Body;
+    use crate::service::web::extract::client_ip::ClientIpConfig;
+
+    #[test]
+    fn test_ab_key_hash() {
+        let req = Request::builder()
+            .header("x-user-id", "42")
+            .header("cookie", "theme=dark; session=42")
+            .header("x-forwarded-for", "1.2.3.4")
+            .body(Body::empty())
+            .unwrap();
+        let mut ctx = Context::default();
+        ctx.insert(ClientIpConfig::trust_all());
+
+        let header_hash = AbKey::header(HeaderName::from_static("x-user-id")).hash(&ctx, &req);
+        assert_eq!(header_hash, Some(fnv1a(b"42")));
+        assert_eq!(AbKey::cookie("session").hash(&ctx, &req), header_hash);
+        assert_eq!(AbKey::cookie("missing").hash(&ctx, &req), None);
+        assert_eq!(AbKey::ClientIp.hash(&ctx, &req), Some(fnv1a(b"1.2.3.4")));
+
+        let req = Request::new(Body::empty());
+        assert_eq!(AbKey::ClientIp.hash(&ctx, &req), None);
+    }
+
+    #[test]
+    fn test_fnv1a() {
+        assert_eq!(fnv1a(b""), 0xcbf29ce484222325);
+        assert_eq!(fnv1a(b"a"), 0xaf63dc4c8601ec8c);
+    }
+}
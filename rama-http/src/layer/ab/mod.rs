@@ -0,0 +1,71 @@
+//! Middleware to split traffic between two variants of a service, for A/B testing.
+//!
+//! A configurable percentage of the traffic is routed to the alternate service (`B`),
+//! while the remaining traffic goes to the primary (inner) service (`A`).
+//!
+//! Assignment is sticky: the variant is selected based on a consistent hash
+//! of the [`AbKey`] (e.g. a cookie, header or the client IP), such that the same
+//! user always hits the same variant, as long as the percentage is unchanged.
+//! Requests without a key are assigned randomly.
+//!
+//! The percentage is shared as an [`Arc<AtomicU8>`], allowing it to be adjusted
+//! at runtime without restarting the server. The selected [`AbVariant`]
+//! is inserted in the [`Context`], e.g. for logging.
+//!
+//! [`Context`]: rama_core::Context
+//!
+//! # Example
+//!
+//! ```
+//! use rama_core::service::service_fn;
+//! use rama_core::{Context, Layer, Service};
+//! use rama_http::layer::ab::{AbKey, AbTestingLayer, AbVariant};
+//! use rama_http::{Body, BodyExtractExt, Request, Response};
+//! use std::convert::Infallible;
+//! use std::sync::Arc;
+//! use std::sync::atomic::{AtomicU8, Ordering};
+//!
+//! # #[tokio::main]
+//! # async fn main() {
+//! let weight = Arc::new(AtomicU8::new(0));
+//!
+//! let variant_b = service_fn(async |_req: Request| {
+//!     Ok::<_, Infallible>(Response::new(Body::from("new checkout")))
+//! });
+//!
+//! let svc = AbTestingLayer::new(variant_b, weight.clone())
+//!     .with_key(AbKey::cookie("session"))
+//!     .into_layer(service_fn(async |ctx: Context<()>, _req: Request| {
+//!         assert_eq!(ctx.get::<AbVariant>().unwrap().name, "a");
+//!         Ok::<_, Infallible>(Response::new(Body::from("old checkout")))
+//!     }));
+//!
+//! let req = Request::builder()
+//!     .header("cookie", "session=42")
+//!     .body(Body::empty())
+//!     .unwrap();
+//! let res = svc.serve(Context::default(), req).await.unwrap();
+//! assert_eq!(res.into_body().try_into_string().await.unwrap(), "old checkout");
+//!
+//! // route all traffic to variant B, without restarting the service
+//! weight.store(100, Ordering::Relaxed);
+//! # }
+//! ```
+//!
+//! [`Arc<AtomicU8>`]: std::sync::atomic::AtomicU8
+
+mod key;
+#[doc(inline)]
+pub use key::AbKey;
+
+mod service;
+#[doc(inline)]
+pub use service::{AbTestingLayer, AbTestingService};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// The variant selected by the [`AbTestingService`] for a request,
+/// inserted in the [`Context`](rama_core::Context).
+pub struct AbVariant {
+    /// The name of the variant, `a` and `b` by default.
+    pub name: String,
+}
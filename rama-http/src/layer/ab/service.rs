@@ -0,0 +1,278 @@
+use super::{AbKey, AbVariant};
+use crate::Request;
+use rama_core::{Context, Layer, Service};
+use rama_utils::macros::define_inner_service_accessors;
+use std::fmt;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU8, Ordering};
+
+/// Layer that applies the [`AbTestingService`] middleware,
+/// routing a percentage of the traffic to an alternate service.
+///
+/// See the [module docs](super) for more details.
+pub struct AbTestingLayer<B> {
+    variant_b: Arc<B>,
+    weight: Arc<AtomicU8>,
+    key: AbKey,
+    names: Arc<(String, String)>,
+}
+
+impl<B> AbTestingLayer<B> {
+    /// Create a new [`AbTestingLayer`], routing the percentage (`0..=100`)
+    /// of traffic defined by `weight` to the given service (`B`).
+    ///
+    /// By default the client IP is used as [`AbKey`].
+    pub fn new(variant_b: B, weight: Arc<AtomicU8>) -> Self {
+        Self {
+            variant_b: Arc::new(variant_b),
+            weight,
+            key: AbKey::ClientIp,
+            names: Arc::new(("a".to_owned(), "b".to_owned())),
+        }
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Set the [`AbKey`] used for sticky variant assignment.
+        pub fn key(mut self, key: AbKey) -> Self {
+            self.key = key;
+            self
+        }
+    }
+
+    /// Set the names of the variants, as found in the [`AbVariant`],
+    /// by default `a` and `b`.
+    pub fn with_names(mut self, a: impl Into<String>, b: impl Into<String>) -> Self {
+        self.names = Arc::new((a.into(), b.into()));
+        self
+    }
+
+    /// Set the names of the variants, as found in the [`AbVariant`],
+    /// by default `a` and `b`.
+    pub fn set_names(&mut self, a: impl Into<String>, b: impl Into<String>) -> &mut Self {
+        self.names = Arc::new((a.into(), b.into()));
+        self
+    }
+}
+
+impl<B: fmt::Debug> fmt::Debug for AbTestingLayer<B> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AbTestingLayer")
+            .field("variant_b", &self.variant_b)
+            .field("weight", &self.weight)
+            .field("key", &self.key)
+            .field("names", &self.names)
+            .finish()
+    }
+}
+
+impl<B> Clone for AbTestingLayer<B> {
+    fn clone(&self) -> Self {
+        Self {
+            variant_b: self.variant_b.clone(),
+            weight: self.weight.clone(),
+            key: self.key.clone(),
+            names: self.names.clone(),
+        }
+    }
+}
+
+impl<S, B> Layer<S> for AbTestingLayer<B> {
+    type Service = AbTestingService<S, B>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        AbTestingService {
+            inner,
+            variant_b: self.variant_b.clone(),
+            weight: self.weight.clone(),
+            key: self.key.clone(),
+            names: self.names.clone(),
+        }
+    }
+
+    fn into_layer(self, inner: S) -> Self::Service {
+        AbTestingService {
+            inner,
+            variant_b: self.variant_b,
+            weight: self.weight,
+            key: self.key,
+            names: self.names,
+        }
+    }
+}
+
+/// Middleware which routes a percentage of the traffic to an alternate service (`B`),
+/// and the remaining traffic to the inner service (`A`).
+///
+/// See the [module docs](super) for more details.
+pub struct AbTestingService<S, B> {
+    inner: S,
+    variant_b: Arc<B>,
+    weight: Arc<AtomicU8>,
+    key: AbKey,
+    names: Arc<(String, String)>,
+}
+
+impl<S, B> AbTestingService<S, B> {
+    define_inner_service_accessors!();
+
+    /// Return `true` in case the request with the given key hash is assigned to variant `B`.
+    fn is_variant_b(&self, hash: Option<u64>) -> bool {
+        let weight = self.weight.load(Ordering::Relaxed).min(100);
+        let bucket = match hash {
+            Some(hash) => (hash % 100) as u8,
+            None => rand::random_range(0..100),
+        };
+        bucket < weight
+    }
+}
+
+impl<S: fmt::Debug, B: fmt::Debug> fmt::Debug for AbTestingService<S, B> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AbTestingService")
+            .field("inner", &self.inner)
+            .field("variant_b", &self.variant_b)
+            .field("weight", &self.weight)
+            .field("key", &self.key)
+            .field("names", &self.names)
+            .finish()
+    }
+}
+
+impl<S: Clone, B> Clone for AbTestingService<S, B> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            variant_b: self.variant_b.clone(),
+            weight: self.weight.clone(),
+            key: self.key.clone(),
+            names: self.names.clone(),
+        }
+    }
+}
+
+impl<S, B, State, Body> Service<State, Request<Body>> for AbTestingService<S, B>
+where
+    S: Service<State, Request<Body>>,
+    B: Service<State, Request<Body>, Response = S::Response, Error = S::Error>,
+    State: Clone + Send + Sync + 'static,
+    Body: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+
+    async fn serve(
+        &self,
+        mut ctx: Context<State>,
+        req: Request<Body>,
+    ) -> Result<Self::Response, Self::Error> {
+        let hash = self.key.hash(&ctx, &req);
+        if self.is_variant_b(hash) {
+            ctx.insert(AbVariant {
+                name: self.names.1.clone(),
+            });
+            self.variant_b.serve(ctx, req).await
+        } else {
+            ctx.insert(AbVariant {
+                name: self.names.0.clone(),
+            });
+            self.inner.serve(ctx, req).await
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Body, BodyExtractExt, HeaderName, Response};
+    use rama_core::service::service_fn;
+    use std::convert::Infallible;
+
+    fn service(
+        weight: Arc<AtomicU8>,
+    ) -> impl Service<(), Request, Response = Response, Error = Infallible> {
+        let variant = async |ctx: Context<()>, _req: Request| {
+            Ok::<_, Infallible>(Response::new(Body::from(
+                ctx.get::<AbVariant>().unwrap().name.clone(),
+            )))
+        };
+        AbTestingLayer::new(service_fn(variant), weight)
+            .with_key(AbKey::header(HeaderName::from_static("x-user-id")))
+            .with_names("control", "treatment")
+            .into_layer(service_fn(variant))
+    }
+
+    async fn variant_for(
+        svc: &impl Service<(), Request, Response = Response, Error = Infallible>,
+        user: Option<&str>,
+    ) -> String {
+        let mut req = Request::builder();
+        if let Some(user) = user {
+            req = req.header("x-user-id", user);
+        }
+        let res = svc
+            .serve(Context::default(), req.body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        res.into_body().try_into_string().await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_ab_testing_even_distribution() {
+        let svc = service(Arc::new(AtomicU8::new(50)));
+
+        let mut treatment = 0;
+        for user in 0..10_000 {
+            if variant_for(&svc, Some(&user.to_string())).await == "treatment" {
+                treatment += 1;
+            }
+        }
+        assert!(
+            (4_500..=5_500).contains(&treatment),
+            "treatment: {treatment}"
+        );
+
+        let mut treatment = 0;
+        for _ in 0..10_000 {
+            if variant_for(&svc, None).await == "treatment" {
+                treatment += 1;
+            }
+        }
+        assert!(
+            (4_500..=5_500).contains(&treatment),
+            "treatment: {treatment}"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_ab_testing_sticky_assignment() {
+        let svc = service(Arc::new(AtomicU8::new(50)));
+        for user in ["alice", "bob", "carol", "dave"] {
+            let variant = variant_for(&svc, Some(user)).await;
+            for _ in 0..10 {
+                assert_eq!(variant_for(&svc, Some(user)).await, variant, "user: {user}");
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_ab_testing_dynamic_weight() {
+        let weight = Arc::new(AtomicU8::new(0));
+        let svc = service(weight.clone());
+
+        for user in 0..100 {
+            assert_eq!(variant_for(&svc, Some(&user.to_string())).await, "control");
+        }
+
+        weight.store(100, Ordering::Relaxed);
+        for user in 0..100 {
+            assert_eq!(
+                variant_for(&svc, Some(&user.to_string())).await,
+                "treatment"
+            );
+        }
+
+        // values above 100 are treated as 100
+        weight.store(u8::MAX, Ordering::Relaxed);
+        assert_eq!(variant_for(&svc, None).await, "treatment");
+    }
+}
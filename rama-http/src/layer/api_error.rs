@@ -0,0 +1,163 @@
+//! Middleware to turn [`Service`] errors into structured [`ApiError`] responses.
+//!
+//! # Example
+//!
+//! ```
+//! use rama_core::{service::service_fn, Context, Layer, Service};
+//! use rama_http::layer::api_error::ApiErrorLayer;
+//! use rama_http::service::web::api_error::{ApiError, ApiErrorCode};
+//! use rama_http::{Body, Request, Response, StatusCode};
+//!
+//! # #[tokio::main]
+//! # async fn main() {
+//! let service = ApiErrorLayer::new().into_layer(service_fn(async |_req: Request| {
+//!     Err::<Response, _>(ApiError::new(ApiErrorCode::NotFound, "user not found"))
+//! }));
+//!
+//! let res = service.serve(Context::default(), Request::new(Body::empty())).await.unwrap();
+//! assert_eq!(res.status(), StatusCode::NOT_FOUND);
+//! # }
+//! ```
+
+use crate::service::web::api_error::ApiError;
+use crate::service::web::response::IntoResponse;
+use crate::{Request, Response};
+use rama_core::error::BoxError;
+use rama_core::{Context, Layer, Service};
+use rama_utils::macros::define_inner_service_accessors;
+use std::{convert::Infallible, fmt};
+
+#[derive(Debug, Clone, Default)]
+/// A [`Layer`] that produces [`ApiErrorService`]s,
+/// converting [`Service`] errors into [`ApiError`] responses.
+#[non_exhaustive]
+pub struct ApiErrorLayer;
+
+impl ApiErrorLayer {
+    /// Create a new [`ApiErrorLayer`].
+    pub const fn new() -> Self {
+        Self
+    }
+}
+
+impl<S> Layer<S> for ApiErrorLayer {
+    type Service = ApiErrorService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        ApiErrorService::new(inner)
+    }
+}
+
+/// A [`Service`] adapter that converts errors into [`ApiError`] responses.
+///
+/// Errors are converted using [`ApiError::from`], selecting the status code
+/// based on the known error types found in the error's source chain.
+pub struct ApiErrorService<S> {
+    inner: S,
+}
+
+impl<S> ApiErrorService<S> {
+    /// Create a new [`ApiErrorService`] wrapping the given service.
+    pub const fn new(inner: S) -> Self {
+        Self { inner }
+    }
+
+    define_inner_service_accessors!();
+}
+
+impl<S: fmt::Debug> fmt::Debug for ApiErrorService<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ApiErrorService")
+            .field("inner", &self.inner)
+            .finish()
+    }
+}
+
+impl<S: Clone> Clone for ApiErrorService<S> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl<S, State, Body> Service<State, Request<Body>> for ApiErrorService<S>
+where
+    S: Service<State, Request<Body>, Response: IntoResponse, Error: Into<BoxError>>,
+    State: Clone + Send + Sync + 'static,
+    Body: Send + 'static,
+{
+    type Response = Response;
+    type Error = Infallible;
+
+    async fn serve(
+        &self,
+        ctx: Context<State>,
+        req: Request<Body>,
+    ) -> Result<Self::Response, Self::Error> {
+        match self.inner.serve(ctx, req).await {
+            Ok(response) => Ok(response.into_response()),
+            Err(err) => Ok(ApiError::from(err.into()).into_response()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dep::http_body_util::BodyExt;
+    use crate::service::web::api_error::ApiErrorCode;
+    use crate::{Body, StatusCode};
+    use rama_core::error::OpaqueError;
+    use rama_core::layer::TimeoutLayer;
+    use rama_core::service::service_fn;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_api_error_layer() {
+        let svc = ApiErrorLayer::new().into_layer(service_fn(async |req: Request| {
+            match req.uri().path() {
+                "/ok" => Ok(Response::new(Body::from("ok"))),
+                "/internal" => Err(OpaqueError::from_display("secret").into_boxed()),
+                _ => Err(ApiError::new(ApiErrorCode::NotFound, "no route").into()),
+            }
+        }));
+
+        for (path, status, body) in [
+            ("/ok", StatusCode::OK, "ok"),
+            (
+                "/internal",
+                StatusCode::INTERNAL_SERVER_ERROR,
+                r#"{"error":{"code":"internal_error","message":"internal server error"}}"#,
+            ),
+            (
+                "/other",
+                StatusCode::NOT_FOUND,
+                r#"{"error":{"code":"not_found","message":"no route"}}"#,
+            ),
+        ] {
+            let req = Request::builder().uri(path).body(Body::empty()).unwrap();
+            let res = svc.serve(Context::default(), req).await.unwrap();
+            assert_eq!(res.status(), status, "path: {path}");
+            let res_body = res.into_body().collect().await.unwrap().to_bytes();
+            assert_eq!(res_body, body, "path: {path}");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_api_error_layer_timeout() {
+        let svc = (
+            ApiErrorLayer::new(),
+            TimeoutLayer::new(Duration::from_millis(10)),
+        )
+            .into_layer(service_fn(async |_req: Request| {
+                tokio::time::sleep(Duration::from_secs(1)).await;
+                Ok::<_, BoxError>(Response::new(Body::empty()))
+            }));
+        let res = svc
+            .serve(Context::default(), Request::new(Body::empty()))
+            .await
+            .unwrap();
+        assert_eq!(res.status(), StatusCode::GATEWAY_TIMEOUT);
+    }
+}
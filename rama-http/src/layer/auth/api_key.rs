@@ -0,0 +1,551 @@
+//! Middleware that authorizes requests using an API key.
+//!
+//! The [`ApiKeyService`] looks for an API key in the configured [`ApiKeySource`]s,
+//! by default the `Authorization: ApiKey <key>` header, and validates it
+//! using a [`KeyStore`] or [`AsyncKeyStore`], such as the [`MemoryKeyStore`].
+//! On success the [`ApiKeyIdentity`] of the key is inserted in the [`Context`],
+//! which can be used to rate limit requests per key using the [`ApiKeyIdentityKey`].
+//!
+//! Requests without a valid API key are rejected with a `401 Unauthorized` response.
+//!
+//! Validated keys can optionally be cached for a limited time,
+//! to avoid looking them up in the (possibly remote) store for each request.
+//!
+//! # Example
+//!
+//! ```
+//! use rama_core::service::service_fn;
+//! use rama_core::{Context, Layer, Service};
+//! use rama_http::layer::auth::api_key::{
+//!     ApiKeyIdentity, ApiKeyLayer, ApiKeySource, MemoryKeyStore,
+//! };
+//! use rama_http::{Body, Request, Response, StatusCode};
+//! use std::convert::Infallible;
+//! use std::time::Duration;
+//!
+//! # #[tokio::main]
+//! # async fn main() {
+//! let store = MemoryKeyStore::new().with_key(
+//!     "secret-key",
+//!     ApiKeyIdentity::new("client-a").with_scopes(["read"]),
+//! );
+//!
+//! let svc = ApiKeyLayer::new(store)
+//!     .with_sources([ApiKeySource::Authorization, ApiKeySource::query("api_key")])
+//!     .with_cache_ttl(Duration::from_secs(60))
+//!     .into_layer(service_fn(async |ctx: Context<()>, _req: Request| {
+//!         let identity = ctx.get::<ApiKeyIdentity>().unwrap();
+//!         Ok::<_, Infallible>(Response::new(Body::from(identity.key_id.clone())))
+//!     }));
+//!
+//! let req = Request::builder()
+//!     .uri("/?api_key=secret-key")
+//!     .body(Body::empty())
+//!     .unwrap();
+//! let res = svc.serve(Context::default(), req).await.unwrap();
+//! assert_eq!(res.status(), StatusCode::OK);
+//!
+//! let res = svc
+//!     .serve(Context::default(), Request::new(Body::empty()))
+//!     .await
+//!     .unwrap();
+//! assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+//! # }
+//! ```
+//!
+//! [`ApiKeyIdentityKey`]: crate::layer::rate_limit::ApiKeyIdentityKey
+
+use std::{collections::HashMap, fmt, sync::Arc, time::Duration};
+
+use parking_lot::Mutex;
+use rama_core::{Context, Layer, Service, telemetry::tracing};
+use rama_http_types::{Body, HeaderMap, HeaderName, Request, Response, StatusCode, Uri, header};
+use rama_utils::{collections::PruningHashMap, macros::define_inner_service_accessors};
+use tokio::time::Instant;
+
+/// The identity of a validated API key,
+/// inserted in the [`Context`] by the [`ApiKeyService`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ApiKeyIdentity {
+    /// The identifier of the key, which is not the secret key itself.
+    pub key_id: String,
+    /// The scopes granted to the key.
+    pub scopes: Vec<String>,
+}
+
+impl ApiKeyIdentity {
+    /// Create a new [`ApiKeyIdentity`] without any scopes.
+    pub fn new(key_id: impl Into<String>) -> Self {
+        Self {
+            key_id: key_id.into(),
+            scopes: Vec::new(),
+        }
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Set the scopes granted to the key.
+        pub fn scopes(mut self, scopes: impl IntoIterator<Item: Into<String>>) -> Self {
+            self.scopes = scopes.into_iter().map(Into::into).collect();
+            self
+        }
+    }
+
+    /// Returns `true` if the given scope is granted to the key.
+    pub fn has_scope(&self, scope: &str) -> bool {
+        self.scopes.iter().any(|s| s == scope)
+    }
+}
+
+/// A location of the request where an API key can be found.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ApiKeySource {
+    /// The `Authorization: ApiKey <key>` header.
+    Authorization,
+    /// The (full) value of a custom header, e.g. `x-api-key`.
+    Header(HeaderName),
+    /// The value of a query parameter, e.g. `api_key`.
+    Query(String),
+}
+
+impl ApiKeySource {
+    /// Create an [`ApiKeySource::Header`] for the given header name.
+    pub const fn header(name: HeaderName) -> Self {
+        Self::Header(name)
+    }
+
+    /// Create an [`ApiKeySource::Query`] for the given query parameter name.
+    pub fn query(name: impl Into<String>) -> Self {
+        Self::Query(name.into())
+    }
+
+    fn extract(&self, headers: &HeaderMap, uri: &Uri) -> Option<String> {
+        match self {
+            Self::Authorization => headers
+                .get_all(header::AUTHORIZATION)
+                .iter()
+                .filter_map(|value| value.to_str().ok())
+                .find_map(|value| {
+                    let (scheme, key) = value.trim().split_once(' ')?;
+                    scheme
+                        .eq_ignore_ascii_case("apikey")
+                        .then(|| key.trim().to_owned())
+                }),
+            Self::Header(name) => headers
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .map(|value| value.trim().to_owned()),
+            Self::Query(name) => serde_html_form::from_str::<Vec<(String, String)>>(uri.query()?)
+                .ok()?
+                .into_iter()
+                .find_map(|(param, value)| (param == *name).then_some(value)),
+        }
+        .filter(|key| !key.is_empty())
+    }
+}
+
+/// A store of API keys, used by the [`ApiKeyService`] to validate keys.
+///
+/// Use [`AsyncKeyStore`] instead for stores which require
+/// asynchronous lookups, e.g. a database.
+pub trait KeyStore: Send + Sync + 'static {
+    /// Get the [`ApiKeyIdentity`] of the given key, if it is valid.
+    fn lookup(&self, key: &str) -> Option<ApiKeyIdentity>;
+}
+
+/// An asynchronous store of API keys, used by the [`ApiKeyService`] to validate keys.
+///
+/// Implemented for all [`KeyStore`]s.
+pub trait AsyncKeyStore: Send + Sync + 'static {
+    /// Get the [`ApiKeyIdentity`] of the given key, if it is valid.
+    fn lookup(&self, key: &str) -> impl Future<Output = Option<ApiKeyIdentity>> + Send;
+}
+
+impl<K: KeyStore> AsyncKeyStore for K {
+    fn lookup(&self, key: &str) -> impl Future<Output = Option<ApiKeyIdentity>> + Send {
+        std::future::ready(KeyStore::lookup(self, key))
+    }
+}
+
+/// An in-memory [`KeyStore`], mapping keys to their [`ApiKeyIdentity`].
+#[derive(Debug, Clone, Default)]
+pub struct MemoryKeyStore(pub HashMap<String, ApiKeyIdentity>);
+
+impl MemoryKeyStore {
+    /// Create a new empty [`MemoryKeyStore`].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Add a key with the given [`ApiKeyIdentity`] to the store.
+        pub fn key(mut self, key: impl Into<String>, identity: ApiKeyIdentity) -> Self {
+            self.0.insert(key.into(), identity);
+            self
+        }
+    }
+}
+
+impl KeyStore for MemoryKeyStore {
+    fn lookup(&self, key: &str) -> Option<ApiKeyIdentity> {
+        self.0.get(key).cloned()
+    }
+}
+
+impl<K: AsyncKeyStore> AsyncKeyStore for Arc<K> {
+    fn lookup(&self, key: &str) -> impl Future<Output = Option<ApiKeyIdentity>> + Send {
+        (**self).lookup(key)
+    }
+}
+
+/// Cache of validated keys, shared between all services created by the same layer.
+#[derive(Debug)]
+struct KeyCache {
+    ttl: Duration,
+    entries: Mutex<PruningHashMap<String, (ApiKeyIdentity, Instant)>>,
+}
+
+impl KeyCache {
+    fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            entries: Default::default(),
+        }
+    }
+
+    fn get(&self, key: &str) -> Option<ApiKeyIdentity> {
+        let mut entries = self.entries.lock();
+        match entries.get(key) {
+            Some((identity, expires_at)) if *expires_at > Instant::now() => Some(identity.clone()),
+            Some(_) => {
+                entries.remove(key);
+                None
+            }
+            None => None,
+        }
+    }
+
+    fn insert(&self, key: String, identity: ApiKeyIdentity) {
+        let now = Instant::now();
+        let mut entries = self.entries.lock();
+        entries.prune(|_, (_, expires_at)| *expires_at > now);
+        entries.insert(key, (identity, now + self.ttl));
+    }
+}
+
+/// Layer that applies the [`ApiKeyService`] middleware.
+///
+/// See the [module docs](self) for more details.
+pub struct ApiKeyLayer<K> {
+    store: Arc<K>,
+    sources: Vec<ApiKeySource>,
+    cache: Option<Arc<KeyCache>>,
+}
+
+impl<K> ApiKeyLayer<K> {
+    /// Create a new [`ApiKeyLayer`] validating keys using the given store,
+    /// which can be a [`KeyStore`] or [`AsyncKeyStore`].
+    pub fn new(store: K) -> Self {
+        Self {
+            store: Arc::new(store),
+            sources: vec![ApiKeySource::Authorization],
+            cache: None,
+        }
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Set the locations where the API key is looked for, in order,
+        /// by default only [`ApiKeySource::Authorization`].
+        pub fn sources(mut self, sources: impl IntoIterator<Item = ApiKeySource>) -> Self {
+            self.sources = sources.into_iter().collect();
+            self
+        }
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Cache validated keys for the given duration,
+        /// by default keys are looked up for every request.
+        pub fn cache_ttl(mut self, ttl: Duration) -> Self {
+            self.cache = Some(Arc::new(KeyCache::new(ttl)));
+            self
+        }
+    }
+}
+
+impl<K: fmt::Debug> fmt::Debug for ApiKeyLayer<K> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ApiKeyLayer")
+            .field("store", &self.store)
+            .field("sources", &self.sources)
+            .field("cache", &self.cache)
+            .finish()
+    }
+}
+
+impl<K> Clone for ApiKeyLayer<K> {
+    fn clone(&self) -> Self {
+        Self {
+            store: self.store.clone(),
+            sources: self.sources.clone(),
+            cache: self.cache.clone(),
+        }
+    }
+}
+
+impl<S, K> Layer<S> for ApiKeyLayer<K> {
+    type Service = ApiKeyService<S, K>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        ApiKeyService {
+            inner,
+            store: self.store.clone(),
+            sources: self.sources.clone(),
+            cache: self.cache.clone(),
+        }
+    }
+
+    fn into_layer(self, inner: S) -> Self::Service {
+        ApiKeyService {
+            inner,
+            store: self.store,
+            sources: self.sources,
+            cache: self.cache,
+        }
+    }
+}
+
+/// Middleware which authorizes requests using an API key.
+///
+/// See the [module docs](self) for more details.
+pub struct ApiKeyService<S, K> {
+    inner: S,
+    store: Arc<K>,
+    sources: Vec<ApiKeySource>,
+    cache: Option<Arc<KeyCache>>,
+}
+
+impl<S, K> ApiKeyService<S, K> {
+    /// Create a new [`ApiKeyService`] validating keys using the given store,
+    /// which can be a [`KeyStore`] or [`AsyncKeyStore`].
+    pub fn new(inner: S, store: K) -> Self {
+        Self {
+            inner,
+            store: Arc::new(store),
+            sources: vec![ApiKeySource::Authorization],
+            cache: None,
+        }
+    }
+
+    define_inner_service_accessors!();
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Set the locations where the API key is looked for, in order,
+        /// by default only [`ApiKeySource::Authorization`].
+        pub fn sources(mut self, sources: impl IntoIterator<Item = ApiKeySource>) -> Self {
+            self.sources = sources.into_iter().collect();
+            self
+        }
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Cache validated keys for the given duration,
+        /// by default keys are looked up for every request.
+        pub fn cache_ttl(mut self, ttl: Duration) -> Self {
+            self.cache = Some(Arc::new(KeyCache::new(ttl)));
+            self
+        }
+    }
+}
+
+impl<S: fmt::Debug, K: fmt::Debug> fmt::Debug for ApiKeyService<S, K> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ApiKeyService")
+            .field("inner", &self.inner)
+            .field("store", &self.store)
+            .field("sources", &self.sources)
+            .field("cache", &self.cache)
+            .finish()
+    }
+}
+
+impl<S: Clone, K> Clone for ApiKeyService<S, K> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            store: self.store.clone(),
+            sources: self.sources.clone(),
+            cache: self.cache.clone(),
+        }
+    }
+}
+
+impl<S, K> ApiKeyService<S, K>
+where
+    K: AsyncKeyStore,
+{
+    async fn identify(&self, key: String) -> Option<ApiKeyIdentity> {
+        if let Some(identity) = self.cache.as_ref().and_then(|cache| cache.get(&key)) {
+            return Some(identity);
+        }
+        let identity = self.store.lookup(&key).await?;
+        if let Some(cache) = &self.cache {
+            cache.insert(key, identity.clone());
+        }
+        Some(identity)
+    }
+}
+
+impl<State, S, K, ReqBody, ResBody> Service<State, Request<ReqBody>> for ApiKeyService<S, K>
+where
+    State: Clone + Send + Sync + 'static,
+    S: Service<State, Request<ReqBody>, Response = Response<ResBody>>,
+    K: AsyncKeyStore,
+    ReqBody: Send + 'static,
+    ResBody: Send + 'static,
+    Body: Into<ResBody>,
+{
+    type Response = Response<ResBody>;
+    type Error = S::Error;
+
+    async fn serve(
+        &self,
+        mut ctx: Context<State>,
+        req: Request<ReqBody>,
+    ) -> Result<Self::Response, Self::Error> {
+        let key = self
+            .sources
+            .iter()
+            .find_map(|source| source.extract(req.headers(), req.uri()));
+
+        let identity = match key {
+            Some(key) => self.identify(key).await,
+            None => {
+                tracing::trace!("no api key found in request");
+                None
+            }
+        };
+
+        let Some(identity) = identity else {
+            let mut res = Response::new(Body::empty());
+            *res.status_mut() = StatusCode::UNAUTHORIZED;
+            return Ok(res.map(Into::into));
+        };
+
+        tracing::trace!(key_id = %identity.key_id, "api key validated");
+        ctx.insert(identity);
+        self.inner.serve(ctx, req).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::dep::http_body_util::BodyExt as _;
+    use rama_core::service::service_fn;
+    use std::convert::Infallible;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[derive(Debug, Default)]
+    struct CountingStore(AtomicUsize);
+
+    impl AsyncKeyStore for CountingStore {
+        async fn lookup(&self, key: &str) -> Option<ApiKeyIdentity> {
+            self.0.fetch_add(1, Ordering::SeqCst);
+            (key == "secret").then(|| ApiKeyIdentity::new("a"))
+        }
+    }
+
+    async fn serve_key_id(
+        svc: &impl Service<(), Request, Response = Response, Error = Infallible>,
+        req: Request,
+    ) -> Option<String> {
+        let res = svc.serve(Context::default(), req).await.unwrap();
+        if res.status() == StatusCode::UNAUTHORIZED {
+            return None;
+        }
+        let body = res.into_body().collect().await.unwrap().to_bytes();
+        Some(String::from_utf8(body.to_vec()).unwrap())
+    }
+
+    fn echo_key_id() -> impl Service<(), Request, Response = Response, Error = Infallible> + Clone {
+        service_fn(async |ctx: Context<()>, _req: Request| {
+            let identity = ctx.get::<ApiKeyIdentity>().unwrap();
+            Ok(Response::new(Body::from(identity.key_id.clone())))
+        })
+    }
+
+    #[tokio::test]
+    async fn api_key_sources() {
+        let store = MemoryKeyStore::new()
+            .with_key("key-a", ApiKeyIdentity::new("a").with_scopes(["read"]))
+            .with_key("key-b", ApiKeyIdentity::new("b"));
+
+        let svc = ApiKeyLayer::new(store.clone()).into_layer(echo_key_id());
+        let req = |name: HeaderName, value: &str| {
+            Request::builder()
+                .uri("/?api_key=key-b")
+                .header(name, value)
+                .body(Body::empty())
+                .unwrap()
+        };
+
+        assert_eq!(
+            serve_key_id(&svc, req(header::AUTHORIZATION, "ApiKey key-a")).await,
+            Some("a".to_owned())
+        );
+        assert_eq!(
+            serve_key_id(&svc, req(header::AUTHORIZATION, "Bearer key-a")).await,
+            None
+        );
+        assert_eq!(
+            serve_key_id(&svc, req(header::AUTHORIZATION, "ApiKey key-c")).await,
+            None
+        );
+
+        let x_api_key = HeaderName::from_static("x-api-key");
+        let svc = ApiKeyLayer::new(store)
+            .with_sources([
+                ApiKeySource::header(x_api_key.clone()),
+                ApiKeySource::query("api_key"),
+            ])
+            .into_layer(echo_key_id());
+
+        assert_eq!(
+            serve_key_id(&svc, req(x_api_key, "key-a")).await,
+            Some("a".to_owned())
+        );
+        assert_eq!(
+            serve_key_id(&svc, req(header::AUTHORIZATION, "ApiKey key-a")).await,
+            Some("b".to_owned())
+        );
+        assert_eq!(serve_key_id(&svc, Request::new(Body::empty())).await, None);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn api_key_cache() {
+        let store = Arc::new(CountingStore::default());
+        let svc = ApiKeyLayer::new(store.clone())
+            .with_cache_ttl(Duration::from_secs(10))
+            .into_layer(echo_key_id());
+
+        let req = |key: &str| {
+            Request::builder()
+                .header(header::AUTHORIZATION, format!("ApiKey {key}"))
+                .body(Body::empty())
+                .unwrap()
+        };
+
+        assert!(serve_key_id(&svc, req("secret")).await.is_some());
+        assert!(serve_key_id(&svc, req("secret")).await.is_some());
+        assert_eq!(store.0.load(Ordering::SeqCst), 1);
+
+        // invalid keys are not cached
+        assert!(serve_key_id(&svc, req("wrong")).await.is_none());
+        assert!(serve_key_id(&svc, req("wrong")).await.is_none());
+        assert_eq!(store.0.load(Ordering::SeqCst), 3);
+
+        tokio::time::advance(Duration::from_secs(10)).await;
+        assert!(serve_key_id(&svc, req("secret")).await.is_some());
+        assert_eq!(store.0.load(Ordering::SeqCst), 4);
+    }
+}
@@ -1,10 +1,23 @@
 //! Authorization related middleware.
 
 pub mod add_authorization;
+pub mod api_key;
+pub mod basic;
+#[cfg(feature = "crypto")]
+pub mod oauth2;
 pub mod validate_authorization;
 
 #[doc(inline)]
 pub use self::{
     add_authorization::{AddAuthorization, AddAuthorizationLayer},
+    api_key::{
+        ApiKeyIdentity, ApiKeyLayer, ApiKeyService, ApiKeySource, AsyncKeyStore, KeyStore,
+        MemoryKeyStore,
+    },
+    basic::{BasicAuthLayer, BasicAuthService, BasicAuthValidator, StaticValidator},
     validate_authorization::HttpAuthorizer,
 };
+
+#[cfg(feature = "crypto")]
+#[doc(inline)]
+pub use self::oauth2::{OAuth2Validator, OAuthClaims};
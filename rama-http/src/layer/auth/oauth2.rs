@@ -0,0 +1,616 @@
+//! Validate OAuth2 bearer tokens using [`ValidateRequest`].
+//!
+//! The [`OAuth2Validator`] decodes and validates the JWT found
+//! in the `Authorization: Bearer <token>` header using a [`JwtValidator`],
+//! inserting the decoded claims as [`OAuthClaims`] in the [`Context`].
+//!
+//! Requests without a valid token are rejected with a `401 Unauthorized` response,
+//! including a `WWW-Authenticate` header as defined in [RFC 6750].
+//!
+//! The public keys used to validate RS256 and ES256 signed tokens can be fetched
+//! from a remote JWKS endpoint using [`OAuth2Validator::with_jwks_uri`].
+//! These keys are cached for a configurable TTL, and refreshed in the background
+//! shortly before they expire. Alternatively the [`OAuth2Validator`] can be created
+//! from any [`JwtValidator`], e.g. one with a custom [`JwtVerifier`].
+//!
+//! # Example
+//!
+//! ```
+//! use rama_core::service::service_fn;
+//! use rama_core::{Context, Layer, Service};
+//! use rama_http::layer::auth::oauth2::{OAuth2Validator, OAuthClaims};
+//! use rama_http::layer::validate_request::ValidateRequestHeaderLayer;
+//! use rama_http::service::web::extract::JwtValidator;
+//! use rama_http::{Body, Request, Response, StatusCode};
+//! use serde::Deserialize;
+//! use std::convert::Infallible;
+//!
+//! #[derive(Debug, Clone, Deserialize)]
+//! struct Claims {
+//!     sub: String,
+//! }
+//!
+//! # #[tokio::main]
+//! # async fn main() {
+//! let validator = JwtValidator::hs256("secret")
+//!     .with_audience("my-api")
+//!     .with_issuer("https://auth.example.com");
+//!
+//! let svc = ValidateRequestHeaderLayer::oauth2(OAuth2Validator::<Claims>::new(validator))
+//!     .into_layer(service_fn(async |ctx: Context<()>, _req: Request| {
+//!         let OAuthClaims(claims) = ctx.get::<OAuthClaims<Claims>>().unwrap();
+//!         Ok::<_, Infallible>(Response::new(Body::from(claims.sub.clone())))
+//!     }));
+//!
+//! let res = svc
+//!     .serve(Context::default(), Request::new(Body::empty()))
+//!     .await
+//!     .unwrap();
+//! assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+//! # }
+//! ```
+//!
+//! Using a remote JWKS endpoint instead:
+//!
+//! ```
+//! use rama_core::service::service_fn;
+//! use rama_http::layer::auth::oauth2::OAuth2Validator;
+//! use rama_http::layer::validate_request::ValidateRequestHeaderLayer;
+//! use rama_http::{Body, Request, Response, Uri};
+//! use serde::Deserialize;
+//! use std::convert::Infallible;
+//! use std::time::Duration;
+//!
+//! #[derive(Debug, Clone, Deserialize)]
+//! struct Claims {
+//!     sub: String,
+//! }
+//!
+//! # let client = service_fn(async |_req: Request| {
+//! #     Ok::<_, Infallible>(Response::new(Body::from(r#"{"keys":[]}"#)))
+//! # });
+//! let validator = OAuth2Validator::<Claims>::with_jwks_uri(
+//!     client,
+//!     Uri::from_static("https://auth.example.com/.well-known/jwks.json"),
+//! )
+//! .with_jwks_ttl(Duration::from_secs(600))
+//! .with_audience("my-api")
+//! .with_issuer("https://auth.example.com");
+//!
+//! let layer = ValidateRequestHeaderLayer::oauth2(validator);
+//! # let _ = layer;
+//! ```
+//!
+//! [RFC 6750]: https://datatracker.ietf.org/doc/html/rfc6750#section-3
+//! [`JwtVerifier`]: crate::service::web::extract::jwt::JwtVerifier
+
+use std::{
+    fmt,
+    marker::PhantomData,
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+    time::{Duration, Instant},
+};
+
+use parking_lot::RwLock;
+use rama_core::{
+    Context, Service,
+    error::{BoxError, ErrorContext, OpaqueError},
+    layer::MapErr,
+    rt::Executor,
+    service::BoxService,
+    telemetry::tracing,
+};
+use rama_crypto::dep::aws_lc_rs::signature::UnparsedPublicKey;
+use rama_crypto::jose::JWK;
+use rama_http_headers::{Authorization, HeaderMapExt};
+use rama_http_types::{
+    Body, BodyExtractExt, HeaderValue, Request, Response, StatusCode, Uri, header,
+};
+use rama_net::user::Bearer;
+use serde::{Deserialize, de::DeserializeOwned};
+
+use crate::layer::validate_request::{
+    ValidateRequest, ValidateRequestHeader, ValidateRequestHeaderLayer,
+};
+use crate::service::web::extract::JwtValidator;
+use crate::service::web::extract::jwt::{JwtAlgorithm, JwtVerifier};
+
+/// The claims of a validated OAuth2 bearer token,
+/// inserted in the [`Context`] by the [`OAuth2Validator`].
+#[derive(Debug, Clone)]
+pub struct OAuthClaims<T>(pub T);
+
+/// [`ValidateRequest`] implementation which validates
+/// OAuth2 bearer tokens using a [`JwtValidator`].
+///
+/// See the [module docs](self) for more details.
+pub struct OAuth2Validator<T> {
+    validator: JwtValidator,
+    jwks: Option<RemoteJwks>,
+    _claims: PhantomData<fn() -> T>,
+}
+
+impl<T> OAuth2Validator<T> {
+    /// Create a new [`OAuth2Validator`] using the given [`JwtValidator`].
+    pub fn new(validator: JwtValidator) -> Self {
+        Self {
+            validator,
+            jwks: None,
+            _claims: PhantomData,
+        }
+    }
+
+    /// Create a new [`OAuth2Validator`] which validates RS256 and ES256 signed tokens
+    /// using the public keys fetched with the given http client from the given JWKS uri.
+    ///
+    /// The keys are fetched when validating the first token, and cached for 5 minutes
+    /// by default, see [`OAuth2Validator::set_jwks_ttl`]. Shortly before they expire
+    /// they are refreshed in the background, while the cached keys remain in use.
+    pub fn with_jwks_uri<C>(client: C, uri: Uri) -> Self
+    where
+        C: Service<(), Request, Response = Response, Error: Into<BoxError>>,
+    {
+        let keys: Arc<RwLock<Vec<JwksKey>>> = Default::default();
+        let validator = JwtValidator::new(
+            JwksVerifier(keys.clone()),
+            [JwtAlgorithm::RS256, JwtAlgorithm::ES256],
+        );
+        Self {
+            validator,
+            jwks: Some(RemoteJwks {
+                client: BoxService::new(MapErr::new(client, |err: C::Error| {
+                    OpaqueError::from_boxed(err.into())
+                })),
+                uri,
+                ttl: DEFAULT_JWKS_TTL,
+                keys,
+                state: Default::default(),
+            }),
+            _claims: PhantomData,
+        }
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Set the duration for which the keys fetched from the JWKS uri are cached.
+        ///
+        /// Only used for validators created with [`OAuth2Validator::with_jwks_uri`].
+        pub fn jwks_ttl(mut self, ttl: Duration) -> Self {
+            if let Some(jwks) = self.jwks.as_mut() {
+                jwks.ttl = ttl;
+            }
+            self
+        }
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Only accept tokens of which the `aud` claim contains the given audience.
+        pub fn audience(mut self, audience: impl Into<String>) -> Self {
+            self.validator.set_audience(audience);
+            self
+        }
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Only accept tokens of which the `iss` claim equals the given issuer.
+        pub fn issuer(mut self, issuer: impl Into<String>) -> Self {
+            self.validator.set_issuer(issuer);
+            self
+        }
+    }
+}
+
+impl<T> fmt::Debug for OAuth2Validator<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("OAuth2Validator")
+            .field("validator", &self.validator)
+            .field("jwks", &self.jwks)
+            .field(
+                "_claims",
+                &format_args!("{}", std::any::type_name::<fn() -> T>()),
+            )
+            .finish()
+    }
+}
+
+impl<T> Clone for OAuth2Validator<T> {
+    fn clone(&self) -> Self {
+        Self {
+            validator: self.validator.clone(),
+            jwks: self.jwks.clone(),
+            _claims: PhantomData,
+        }
+    }
+}
+
+const DEFAULT_JWKS_TTL: Duration = Duration::from_secs(300);
+
+/// A public key of a JWKS document, usable by the [`JwksVerifier`].
+struct JwksKey {
+    key_id: Option<String>,
+    algorithm: JwtAlgorithm,
+    key: UnparsedPublicKey<Vec<u8>>,
+}
+
+/// [`JwtVerifier`] using the keys last fetched by the [`RemoteJwks`].
+struct JwksVerifier(Arc<RwLock<Vec<JwksKey>>>);
+
+impl JwtVerifier for JwksVerifier {
+    fn verify(
+        &self,
+        algorithm: JwtAlgorithm,
+        key_id: Option<&str>,
+        message: &[u8],
+        signature: &[u8],
+    ) -> Result<(), OpaqueError> {
+        let keys = self.0.read();
+        let mut candidates = keys.iter().filter(|key| {
+            key.algorithm == algorithm
+                && key_id.is_none_or(|key_id| key.key_id.as_deref() == Some(key_id))
+        });
+        if candidates.any(|key| key.key.verify(message, signature).is_ok()) {
+            Ok(())
+        } else {
+            Err(OpaqueError::from_display(
+                "no jwks key found which verifies the signature",
+            ))
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct RemoteJwksState {
+    expires_at: RwLock<Option<Instant>>,
+    refreshing: AtomicBool,
+    fetching: tokio::sync::Mutex<()>,
+}
+
+#[derive(Clone)]
+/// Keys of a remote JWKS endpoint, cached for a configured TTL.
+struct RemoteJwks {
+    client: BoxService<(), Request, Response, OpaqueError>,
+    uri: Uri,
+    ttl: Duration,
+    keys: Arc<RwLock<Vec<JwksKey>>>,
+    state: Arc<RemoteJwksState>,
+}
+
+impl fmt::Debug for RemoteJwks {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RemoteJwks")
+            .field("uri", &self.uri)
+            .field("ttl", &self.ttl)
+            .field("state", &self.state)
+            .finish()
+    }
+}
+
+#[derive(Deserialize)]
+struct JwkSet {
+    keys: Vec<serde_json::Value>,
+}
+
+impl RemoteJwks {
+    /// Ensure usable keys are cached, fetching them in case they are missing or expired,
+    /// and refreshing them in the background in case they are about to expire.
+    async fn ensure_fresh(&self, executor: &Executor) {
+        let now = Instant::now();
+        let expires_at = *self.state.expires_at.read();
+        match expires_at {
+            Some(expires_at) if now + self.ttl / 10 < expires_at => return,
+            Some(expires_at) if now < expires_at => {
+                if !self.state.refreshing.swap(true, Ordering::AcqRel) {
+                    let jwks = self.clone();
+                    executor.spawn_task(async move {
+                        jwks.refresh().await;
+                        jwks.state.refreshing.store(false, Ordering::Release);
+                    });
+                }
+                return;
+            }
+            _ => (),
+        }
+
+        let _guard = self.state.fetching.lock().await;
+        if self
+            .state
+            .expires_at
+            .read()
+            .is_none_or(|expires_at| Instant::now() >= expires_at)
+        {
+            self.refresh().await;
+        }
+    }
+
+    async fn refresh(&self) {
+        match self.fetch().await {
+            Ok(keys) => {
+                tracing::trace!(url.full = %self.uri, "fetched {} jwks key(s)", keys.len());
+                *self.keys.write() = keys;
+                *self.state.expires_at.write() = Some(Instant::now() + self.ttl);
+            }
+            Err(err) => {
+                tracing::debug!(url.full = %self.uri, "failed to fetch jwks: {err:?}");
+            }
+        }
+    }
+
+    async fn fetch(&self) -> Result<Vec<JwksKey>, OpaqueError> {
+        let request = Request::get(self.uri.clone())
+            .body(Body::empty())
+            .context("build jwks request")?;
+        let response = self
+            .client
+            .serve(Context::default(), request)
+            .await
+            .context("send jwks request")?;
+        if !response.status().is_success() {
+            return Err(OpaqueError::from_display(format!(
+                "jwks request failed with status {}",
+                response.status()
+            )));
+        }
+
+        let jwk_set: JwkSet = response
+            .try_into_json()
+            .await
+            .context("decode jwks response")?;
+        Ok(jwk_set
+            .keys
+            .into_iter()
+            .filter_map(|value| {
+                let jwk: JWK = serde_json::from_value(value)
+                    .inspect_err(|err| tracing::trace!("skip unsupported jwk: {err}"))
+                    .ok()?;
+                let algorithm = JwtAlgorithm::try_from(jwk.alg())
+                    .ok()
+                    .filter(|alg| *alg != JwtAlgorithm::HS256)?;
+                let key = jwk.unparsed_public_key().ok()?;
+                Some(JwksKey {
+                    key_id: jwk.kid().map(Into::into),
+                    algorithm,
+                    key,
+                })
+            })
+            .collect())
+    }
+}
+
+fn unauthorized(www_authenticate: &'static str) -> Response {
+    let mut res = Response::new(Body::empty());
+    *res.status_mut() = StatusCode::UNAUTHORIZED;
+    res.headers_mut().insert(
+        header::WWW_AUTHENTICATE,
+        HeaderValue::from_static(www_authenticate),
+    );
+    res
+}
+
+impl<S, ReqBody, T> ValidateRequest<S, ReqBody> for OAuth2Validator<T>
+where
+    S: Clone + Send + Sync + 'static,
+    ReqBody: Send + 'static,
+    T: DeserializeOwned + Clone + Send + Sync + 'static,
+{
+    type ResponseBody = Body;
+
+    async fn validate(
+        &self,
+        mut ctx: Context<S>,
+        request: Request<ReqBody>,
+    ) -> Result<(Context<S>, Request<ReqBody>), Response<Self::ResponseBody>> {
+        let Some(Authorization(bearer)) = request.headers().typed_get::<Authorization<Bearer>>()
+        else {
+            return Err(unauthorized("Bearer"));
+        };
+        if let Some(jwks) = &self.jwks {
+            jwks.ensure_fresh(ctx.executor()).await;
+        }
+        match self.validator.validate::<T>(bearer.token()) {
+            Ok(claims) => {
+                ctx.insert(OAuthClaims(claims));
+                Ok((ctx, request))
+            }
+            Err(err) => {
+                tracing::trace!("oauth2 bearer token was not valid: {err:?}");
+                Err(unauthorized(r#"Bearer error="invalid_token""#))
+            }
+        }
+    }
+}
+
+impl<S, T> ValidateRequestHeader<S, OAuth2Validator<T>> {
+    #[inline]
+    /// Validate the request with an [`OAuth2Validator`].
+    pub fn oauth2(inner: S, validator: OAuth2Validator<T>) -> Self {
+        Self::custom(inner, validator)
+    }
+}
+
+impl<T> ValidateRequestHeaderLayer<OAuth2Validator<T>> {
+    #[inline]
+    /// Validate the request with an [`OAuth2Validator`].
+    pub fn oauth2(validator: OAuth2Validator<T>) -> Self {
+        Self::custom(validator)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::dep::http_body_util::BodyExt as _;
+    use crate::service::web::extract::jwt::tests::{SECRET, hs256_token, signed_token};
+    use rama_core::service::service_fn;
+    use rama_core::{Layer, Service};
+    use rama_crypto::dep::aws_lc_rs::signature::{ECDSA_P256_SHA256_FIXED_SIGNING, EcdsaKeyPair};
+    use rama_crypto::jose::EcdsaKey;
+    use serde::Deserialize;
+    use std::convert::Infallible;
+    use std::sync::atomic::AtomicUsize;
+
+    #[derive(Debug, Clone, Deserialize)]
+    struct Claims {
+        sub: String,
+    }
+
+    fn claims_service() -> impl Service<(), Request, Response = Response, Error = Infallible> {
+        service_fn(async |ctx: Context<()>, _req: Request| {
+            let OAuthClaims(claims) = ctx.get::<OAuthClaims<Claims>>().unwrap();
+            Ok::<_, Infallible>(Response::new(Body::from(claims.sub.clone())))
+        })
+    }
+
+    fn bearer_request(token: &str) -> Request {
+        Request::builder()
+            .header(header::AUTHORIZATION, format!("Bearer {token}"))
+            .body(Body::empty())
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn oauth2_validator() {
+        let validator = JwtValidator::hs256(SECRET).with_audience("rama");
+        let svc = ValidateRequestHeaderLayer::oauth2(OAuth2Validator::<Claims>::new(validator))
+            .into_layer(claims_service());
+
+        let token = hs256_token(r#"{"alg":"HS256"}"#, r#"{"sub":"john","aud":"rama"}"#);
+        let res = svc
+            .serve(Context::default(), bearer_request(&token))
+            .await
+            .unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+        let body = res.into_body().collect().await.unwrap().to_bytes();
+        assert_eq!(body, "john");
+
+        let token = hs256_token(r#"{"alg":"HS256"}"#, r#"{"sub":"john","aud":"other"}"#);
+        let res = svc
+            .serve(Context::default(), bearer_request(&token))
+            .await
+            .unwrap();
+        assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+        assert_eq!(
+            res.headers()[header::WWW_AUTHENTICATE],
+            r#"Bearer error="invalid_token""#
+        );
+
+        let req = Request::builder().body(Body::empty()).unwrap();
+        let res = svc.serve(Context::default(), req).await.unwrap();
+        assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+        assert_eq!(res.headers()[header::WWW_AUTHENTICATE], "Bearer");
+    }
+
+    #[tokio::test]
+    async fn oauth2_validator_jwks() {
+        let key = EcdsaKey::generate().unwrap();
+        let (_, pkcs8) = key.pkcs8_der().unwrap();
+        let key_pair =
+            EcdsaKeyPair::from_pkcs8(&ECDSA_P256_SHA256_FIXED_SIGNING, pkcs8.as_ref()).unwrap();
+        let token = |header: &str, claims: &str| {
+            signed_token(header, claims, |message| {
+                key_pair.sign(key.rng(), message).unwrap().as_ref().to_vec()
+            })
+        };
+
+        let mut jwk = serde_json::to_value(key.create_jwk()).unwrap();
+        jwk["kid"] = "key-1".into();
+        let jwks = serde_json::json!({ "keys": [jwk, { "kty": "unknown" }] }).to_string();
+
+        let fetched = Arc::new(AtomicUsize::new(0));
+        let client = service_fn({
+            let fetched = fetched.clone();
+            move |req: Request| {
+                let fetched = fetched.clone();
+                let jwks = jwks.clone();
+                async move {
+                    assert_eq!(req.uri(), "https://auth.example.com/jwks.json");
+                    fetched.fetch_add(1, Ordering::SeqCst);
+                    Ok::<_, Infallible>(Response::new(Body::from(jwks)))
+                }
+            }
+        });
+
+        let validator = OAuth2Validator::<Claims>::with_jwks_uri(
+            client,
+            Uri::from_static("https://auth.example.com/jwks.json"),
+        )
+        .with_issuer("https://auth.example.com");
+        let svc = ValidateRequestHeaderLayer::oauth2(validator).into_layer(claims_service());
+
+        let claims = r#"{"sub":"john","iss":"https://auth.example.com"}"#;
+        for header in [r#"{"alg":"ES256","kid":"key-1"}"#, r#"{"alg":"ES256"}"#] {
+            let res = svc
+                .serve(Context::default(), bearer_request(&token(header, claims)))
+                .await
+                .unwrap();
+            assert_eq!(res.status(), StatusCode::OK, "{header}");
+            let body = res.into_body().collect().await.unwrap().to_bytes();
+            assert_eq!(body, "john");
+        }
+
+        for (header, claims) in [
+            (r#"{"alg":"ES256","kid":"key-2"}"#, claims),
+            (
+                r#"{"alg":"ES256"}"#,
+                r#"{"sub":"john","iss":"https://evil.example.com"}"#,
+            ),
+        ] {
+            let res = svc
+                .serve(Context::default(), bearer_request(&token(header, claims)))
+                .await
+                .unwrap();
+            assert_eq!(res.status(), StatusCode::UNAUTHORIZED, "{header}");
+        }
+
+        let token = hs256_token(r#"{"alg":"HS256"}"#, claims);
+        let res = svc
+            .serve(Context::default(), bearer_request(&token))
+            .await
+            .unwrap();
+        assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+
+        // keys are cached
+        assert_eq!(fetched.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn oauth2_validator_jwks_refresh() {
+        let fetched = Arc::new(AtomicUsize::new(0));
+        let client = service_fn({
+            let fetched = fetched.clone();
+            move |_req: Request| {
+                fetched.fetch_add(1, Ordering::SeqCst);
+                std::future::ready(Ok::<_, Infallible>(Response::new(Body::from(
+                    r#"{"keys":[]}"#,
+                ))))
+            }
+        });
+
+        let validator = OAuth2Validator::<Claims>::with_jwks_uri(
+            client,
+            Uri::from_static("https://auth.example.com/jwks.json"),
+        )
+        .with_jwks_ttl(Duration::from_millis(100));
+        let jwks = validator.jwks.clone().unwrap();
+        let executor = Executor::default();
+
+        jwks.ensure_fresh(&executor).await;
+        jwks.ensure_fresh(&executor).await;
+        assert_eq!(fetched.load(Ordering::SeqCst), 1);
+
+        // keys about to expire are refreshed in the background
+        tokio::time::sleep(Duration::from_millis(95)).await;
+        jwks.ensure_fresh(&executor).await;
+        while jwks.state.refreshing.load(Ordering::Acquire) {
+            tokio::task::yield_now().await;
+        }
+        assert_eq!(fetched.load(Ordering::SeqCst), 2);
+
+        // expired keys are fetched before validating
+        tokio::time::sleep(Duration::from_millis(150)).await;
+        jwks.ensure_fresh(&executor).await;
+        assert_eq!(fetched.load(Ordering::SeqCst), 3);
+    }
+}
@@ -1,5 +1,10 @@
 //! Apply a limit to the request body.
 //!
+//! Requests with a `Content-Length` header exceeding the limit are rejected
+//! immediately with a `413 Payload Too Large` response, without calling the inner service.
+//! Other request bodies are wrapped such that they return an error
+//! once more data is read than the limit allows.
+//!
 //! # Example
 //!
 //! ```
@@ -29,8 +34,8 @@
 //! # }
 //! ```
 
-use crate::Request;
 use crate::dep::http_body_util::Limited;
+use crate::{Request, Response, StatusCode, header};
 use rama_core::{Context, Layer, Service, bytes::Bytes, error::BoxError};
 use rama_http_types::Body;
 use rama_utils::macros::define_inner_service_accessors;
@@ -80,10 +85,12 @@ impl<S> BodyLimitService<S> {
     define_inner_service_accessors!();
 }
 
-impl<S, State, ReqBody> Service<State, Request<ReqBody>> for BodyLimitService<S>
+impl<S, State, ReqBody, ResBody> Service<State, Request<ReqBody>> for BodyLimitService<S>
 where
-    S: Service<State, Request<Body>>,
+    S: Service<State, Request<Body>, Response = Response<ResBody>>,
     State: Clone + Send + Sync + 'static,
+    ResBody: Send + 'static,
+    Body: Into<ResBody>,
     ReqBody: rama_http_types::dep::http_body::Body<Data = Bytes, Error: Into<BoxError>>
         + Send
         + Sync
@@ -97,6 +104,18 @@ where
         ctx: Context<State>,
         req: Request<ReqBody>,
     ) -> Result<Self::Response, Self::Error> {
+        if self.size > 0
+            && req
+                .headers()
+                .get(header::CONTENT_LENGTH)
+                .and_then(|value| value.to_str().ok()?.parse::<usize>().ok())
+                .is_some_and(|length| length > self.size)
+        {
+            let mut res = Response::new(Body::empty());
+            *res.status_mut() = StatusCode::PAYLOAD_TOO_LARGE;
+            return Ok(res.map(Into::into));
+        }
+
         let req = req.map(|body| {
             if self.size == 0 {
                 Body::new(body)
@@ -119,3 +138,73 @@ where
             .finish()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::dep::http_body::Frame;
+    use crate::dep::http_body_util::{BodyExt, StreamBody};
+    use rama_core::service::service_fn;
+    use std::convert::Infallible;
+
+    async fn echo(req: Request) -> Result<Response, Infallible> {
+        Ok(match req.into_body().collect().await {
+            Ok(body) => Response::new(Body::from(body.to_bytes())),
+            Err(_) => {
+                let mut res = Response::new(Body::empty());
+                *res.status_mut() = StatusCode::PAYLOAD_TOO_LARGE;
+                res
+            }
+        })
+    }
+
+    #[tokio::test]
+    async fn test_body_limit_content_length() {
+        let svc = BodyLimitLayer::new(4).into_layer(service_fn(
+            async |_req: Request| -> Result<Response, Infallible> {
+                panic!("inner service should not be called")
+            },
+        ));
+
+        let req = Request::builder()
+            .header(header::CONTENT_LENGTH, "5")
+            .body(Body::from("hello"))
+            .unwrap();
+        let res = svc.serve(Context::default(), req).await.unwrap();
+        assert_eq!(res.status(), StatusCode::PAYLOAD_TOO_LARGE);
+    }
+
+    #[tokio::test]
+    async fn test_body_limit_streaming() {
+        let svc = BodyLimitLayer::new(4).into_layer(service_fn(echo));
+
+        let chunked_body = |chunks: &'static [&'static str]| {
+            Body::new(StreamBody::new(rama_core::futures::stream::iter(
+                chunks.iter().map(|chunk| {
+                    Ok::<_, Infallible>(Frame::data(Bytes::from_static(chunk.as_bytes())))
+                }),
+            )))
+        };
+
+        let res = svc
+            .serve(
+                Context::default(),
+                Request::new(chunked_body(&["he", "ll"])),
+            )
+            .await
+            .unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+        let body = res.into_body().collect().await.unwrap().to_bytes();
+        assert_eq!(body, "hell");
+
+        let res = svc
+            .serve(
+                Context::default(),
+                Request::new(chunked_body(&["he", "ll", "o"])),
+            )
+            .await
+            .unwrap();
+        assert_eq!(res.status(), StatusCode::PAYLOAD_TOO_LARGE);
+    }
+}
@@ -0,0 +1,88 @@
+//! Middleware which caches responses, following the semantics of [RFC 7234]
+//! for a shared cache.
+//!
+//! Only responses to `GET` and `HEAD` requests are cached, and only
+//! in case the response allows it according to its `Cache-Control`,
+//! `Expires` and `Vary` headers. The freshness of a stored response is determined by
+//! (in order of precedence) the `s-maxage` and `max-age` directives or the `Expires` header.
+//!
+//! A fresh response is returned directly from the [`CacheStore`], with an `Age` header added.
+//! A stale response which has an `ETag` or `Last-Modified` header is revalidated
+//! by forwarding the request with an `If-None-Match` or `If-Modified-Since` header,
+//! serving the stored response again in case the origin replies with `304 Not Modified`.
+//!
+//! Responses are stored per [`CacheKey`], which consists out of the method,
+//! the effective authority (e.g. the `Host` header) and the path and query of the request.
+//! A successful response to an unsafe request (e.g. `POST`) invalidates
+//! the responses stored for the same authority, path and query.
+//!
+//! Only a single variant is stored per key: in case the request
+//! does not match the headers listed in the `Vary` header of the stored response,
+//! it is handled as a cache miss and the stored response is replaced.
+//!
+//! Response bodies are buffered in memory in order to be stored,
+//! which is why only responses with a known size up to [`CacheLayer::with_max_body_size`]
+//! are cached.
+//!
+//! The [`CacheStatus`] of the request is inserted in the [`Context`] passed
+//! to the inner service, as well as in the extensions of the returned response.
+//!
+//! # Example
+//!
+//! ```
+//! use rama_core::service::service_fn;
+//! use rama_core::{Context, Layer, Service};
+//! use rama_http::layer::cache::{CacheLayer, CacheStatus, MemoryCache};
+//! use rama_http::{Body, Request, Response, header};
+//! use std::convert::Infallible;
+//!
+//! # #[tokio::main]
+//! # async fn main() {
+//! let svc = CacheLayer::new(MemoryCache::new(128)).into_layer(service_fn(
+//!     async |_req: Request| {
+//!         Ok::<_, Infallible>(
+//!             Response::builder()
+//!                 .header(header::CACHE_CONTROL, "max-age=60")
+//!                 .body(Body::from("hello"))
+//!                 .unwrap(),
+//!         )
+//!     },
+//! ));
+//!
+//! let res = svc.serve(Context::default(), Request::new(Body::empty())).await.unwrap();
+//! assert_eq!(res.extensions().get::<CacheStatus>(), Some(&CacheStatus::Miss));
+//!
+//! let res = svc.serve(Context::default(), Request::new(Body::empty())).await.unwrap();
+//! assert_eq!(res.extensions().get::<CacheStatus>(), Some(&CacheStatus::Hit));
+//! assert_eq!(res.headers()[header::AGE], "0");
+//! # }
+//! ```
+//!
+//! [RFC 7234]: https://datatracker.ietf.org/doc/html/rfc7234
+//! [`Context`]: rama_core::Context
+
+mod service;
+#[doc(inline)]
+pub use service::{Cache, CacheLayer};
+
+mod store;
+#[doc(inline)]
+pub use store::{CacheKey, CacheStore, CachedResponse, MemoryCache};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// How a request was handled by the [`Cache`] middleware.
+pub enum CacheStatus {
+    /// The response was served from the cache.
+    Hit,
+    /// The response was served by the inner service.
+    Miss,
+    /// A stale response was revalidated with the inner service,
+    /// and served from the cache.
+    Revalidated,
+    /// The request could not be served from the cache,
+    /// e.g. because of its method or `Cache-Control` header.
+    Bypass,
+}
+
+#[cfg(test)]
+mod tests;
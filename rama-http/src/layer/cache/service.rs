@@ -0,0 +1,367 @@
+use super::{CacheKey, CacheStatus, CacheStore, CachedResponse};
+use crate::dep::http_body;
+use crate::dep::http_body_util::BodyExt;
+use crate::headers::{CacheControl, Date, Expires, HeaderMapExt, Vary};
+use crate::{Body, HeaderMap, HeaderName, Method, Request, Response, StatusCode, header};
+use rama_core::bytes::Bytes;
+use rama_core::error::BoxError;
+use rama_core::{Context, Layer, Service};
+use rama_utils::macros::define_inner_service_accessors;
+use std::fmt;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+use tokio::time::Instant;
+
+const DEFAULT_MAX_BODY_SIZE: usize = 1024 * 1024;
+
+/// Layer that applies the [`Cache`] middleware, caching responses in a [`CacheStore`].
+///
+/// See the [module docs](super) for more details.
+pub struct CacheLayer<C> {
+    store: Arc<C>,
+    max_body_size: usize,
+}
+
+impl<C> CacheLayer<C> {
+    /// Create a new [`CacheLayer`], storing responses in the given [`CacheStore`].
+    pub fn new(store: C) -> Self {
+        Self {
+            store: Arc::new(store),
+            max_body_size: DEFAULT_MAX_BODY_SIZE,
+        }
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Set the maximum size of a response body which can be cached,
+        /// by default 1 MiB.
+        pub fn max_body_size(mut self, size: usize) -> Self {
+            self.max_body_size = size;
+            self
+        }
+    }
+}
+
+impl<C: fmt::Debug> fmt::Debug for CacheLayer<C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CacheLayer")
+            .field("store", &self.store)
+            .field("max_body_size", &self.max_body_size)
+            .finish()
+    }
+}
+
+impl<C> Clone for CacheLayer<C> {
+    fn clone(&self) -> Self {
+        Self {
+            store: self.store.clone(),
+            max_body_size: self.max_body_size,
+        }
+    }
+}
+
+impl<S, C> Layer<S> for CacheLayer<C> {
+    type Service = Cache<S, C>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        Cache {
+            inner,
+            store: self.store.clone(),
+            max_body_size: self.max_body_size,
+        }
+    }
+
+    fn into_layer(self, inner: S) -> Self::Service {
+        Cache {
+            inner,
+            store: self.store,
+            max_body_size: self.max_body_size,
+        }
+    }
+}
+
+/// Middleware which caches responses in a [`CacheStore`].
+///
+/// See the [module docs](super) for more details.
+pub struct Cache<S, C> {
+    inner: S,
+    store: Arc<C>,
+    max_body_size: usize,
+}
+
+impl<S, C> Cache<S, C> {
+    /// Create a new [`Cache`], storing responses in the given [`CacheStore`].
+    pub fn new(inner: S, store: C) -> Self {
+        Self {
+            inner,
+            store: Arc::new(store),
+            max_body_size: DEFAULT_MAX_BODY_SIZE,
+        }
+    }
+
+    define_inner_service_accessors!();
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Set the maximum size of a response body which can be cached,
+        /// by default 1 MiB.
+        pub fn max_body_size(mut self, size: usize) -> Self {
+            self.max_body_size = size;
+            self
+        }
+    }
+}
+
+impl<S: fmt::Debug, C: fmt::Debug> fmt::Debug for Cache<S, C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Cache")
+            .field("inner", &self.inner)
+            .field("store", &self.store)
+            .field("max_body_size", &self.max_body_size)
+            .finish()
+    }
+}
+
+impl<S: Clone, C> Clone for Cache<S, C> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            store: self.store.clone(),
+            max_body_size: self.max_body_size,
+        }
+    }
+}
+
+impl<State, S, C, ReqBody, ResBody> Service<State, Request<ReqBody>> for Cache<S, C>
+where
+    State: Clone + Send + Sync + 'static,
+    S: Service<State, Request<ReqBody>, Response = Response<ResBody>, Error: Into<BoxError>>,
+    C: CacheStore,
+    ReqBody: Send + 'static,
+    ResBody: http_body::Body<Data = Bytes, Error: Into<BoxError>> + Send + Sync + 'static,
+{
+    type Response = Response;
+    type Error = BoxError;
+
+    async fn serve(
+        &self,
+        mut ctx: Context<State>,
+        mut req: Request<ReqBody>,
+    ) -> Result<Self::Response, Self::Error> {
+        let key = CacheKey::from_request(&ctx, &req);
+
+        if !matches!(*req.method(), Method::GET | Method::HEAD) {
+            let response = self.serve_inner(ctx, req, CacheStatus::Bypass).await?;
+            if !is_safe_method(key.method())
+                && (response.status().is_success() || response.status().is_redirection())
+            {
+                for method in [Method::GET, Method::HEAD] {
+                    self.store.remove(&key.with_method(method)).await;
+                }
+            }
+            return Ok(response);
+        }
+
+        let req_cache_control = req.headers().typed_get::<CacheControl>();
+        if req_cache_control.as_ref().is_some_and(|cc| cc.no_store()) {
+            return self.serve_inner(ctx, req, CacheStatus::Bypass).await;
+        }
+
+        let cached = self
+            .store
+            .get(&key)
+            .await
+            .filter(|cached| cached.matches_vary(req.headers()));
+
+        let mut revalidating = None;
+        if let Some(cached) = cached {
+            let no_cache = req_cache_control.as_ref().is_some_and(|cc| cc.no_cache());
+            if !no_cache && cached.is_fresh() {
+                let mut response = cached.to_response();
+                response.extensions_mut().insert(CacheStatus::Hit);
+                return Ok(response);
+            }
+
+            let is_conditional = req.headers().contains_key(header::IF_NONE_MATCH)
+                || req.headers().contains_key(header::IF_MODIFIED_SINCE);
+            if !is_conditional {
+                if let Some(etag) = cached.headers.get(header::ETAG) {
+                    req.headers_mut()
+                        .insert(header::IF_NONE_MATCH, etag.clone());
+                    revalidating = Some(cached);
+                } else if let Some(last_modified) = cached.headers.get(header::LAST_MODIFIED) {
+                    req.headers_mut()
+                        .insert(header::IF_MODIFIED_SINCE, last_modified.clone());
+                    revalidating = Some(cached);
+                }
+            }
+        }
+
+        let vary_request_headers = req.headers().clone();
+        let is_authorized = req.headers().contains_key(header::AUTHORIZATION);
+
+        ctx.insert(CacheStatus::Miss);
+        let response = self.inner.serve(ctx, req).await.map_err(Into::into)?;
+
+        if let Some(mut cached) = revalidating
+            && response.status() == StatusCode::NOT_MODIFIED
+        {
+            for (name, value) in response.headers() {
+                if name != header::CONTENT_LENGTH && name != header::TRANSFER_ENCODING {
+                    cached.headers.insert(name.clone(), value.clone());
+                }
+            }
+            cached.stored_at = Instant::now();
+            cached.initial_age = initial_age(&cached.headers);
+            cached.freshness = freshness_lifetime(&cached.headers);
+
+            let mut response = cached.to_response();
+            self.store.insert(key, cached).await;
+            response.extensions_mut().insert(CacheStatus::Revalidated);
+            return Ok(response);
+        }
+
+        let Some(vary) = cacheable_vary(&response, &vary_request_headers, is_authorized) else {
+            return Ok(into_response(response, CacheStatus::Miss));
+        };
+        match http_body::Body::size_hint(response.body()).upper() {
+            Some(size) if size <= self.max_body_size as u64 => (),
+            _ => return Ok(into_response(response, CacheStatus::Miss)),
+        }
+
+        let (parts, body) = response.into_parts();
+        let body = body.collect().await.map_err(Into::into)?.to_bytes();
+
+        let cached = CachedResponse {
+            status: parts.status,
+            version: parts.version,
+            headers: parts.headers.clone(),
+            body: body.clone(),
+            vary,
+            stored_at: Instant::now(),
+            initial_age: initial_age(&parts.headers),
+            freshness: freshness_lifetime(&parts.headers),
+        };
+        self.store.insert(key, cached).await;
+
+        let mut response = Response::from_parts(parts, Body::from(body));
+        response.extensions_mut().insert(CacheStatus::Miss);
+        Ok(response)
+    }
+}
+
+impl<S, C> Cache<S, C> {
+    async fn serve_inner<State, ReqBody, ResBody>(
+        &self,
+        mut ctx: Context<State>,
+        req: Request<ReqBody>,
+        status: CacheStatus,
+    ) -> Result<Response, BoxError>
+    where
+        State: Clone + Send + Sync + 'static,
+        S: Service<State, Request<ReqBody>, Response = Response<ResBody>, Error: Into<BoxError>>,
+        ReqBody: Send + 'static,
+        ResBody: http_body::Body<Data = Bytes, Error: Into<BoxError>> + Send + Sync + 'static,
+    {
+        ctx.insert(status);
+        let response = self.inner.serve(ctx, req).await.map_err(Into::into)?;
+        Ok(into_response(response, status))
+    }
+}
+
+fn into_response<B>(response: Response<B>, status: CacheStatus) -> Response
+where
+    B: http_body::Body<Data = Bytes, Error: Into<BoxError>> + Send + Sync + 'static,
+{
+    let mut response = response.map(Body::new);
+    response.extensions_mut().insert(status);
+    response
+}
+
+fn is_safe_method(method: &Method) -> bool {
+    matches!(
+        *method,
+        Method::GET | Method::HEAD | Method::OPTIONS | Method::TRACE
+    )
+}
+
+/// Returns the request headers to be matched by future requests
+/// in case the response can be stored, or `None` otherwise.
+fn cacheable_vary<B>(
+    response: &Response<B>,
+    request_headers: &HeaderMap,
+    is_authorized: bool,
+) -> Option<Vec<(HeaderName, Option<crate::HeaderValue>)>> {
+    if !matches!(
+        response.status().as_u16(),
+        200 | 203 | 204 | 300 | 301 | 308 | 404 | 405 | 410 | 414 | 501
+    ) {
+        return None;
+    }
+
+    let headers = response.headers();
+    let cache_control = headers.typed_get::<CacheControl>();
+    if let Some(cc) = &cache_control
+        && (cc.no_store() || cc.private())
+    {
+        return None;
+    }
+    if is_authorized
+        && !cache_control
+            .as_ref()
+            .is_some_and(|cc| cc.public() || cc.s_max_age().is_some())
+    {
+        return None;
+    }
+
+    let has_freshness = cache_control
+        .as_ref()
+        .is_some_and(|cc| cc.max_age().is_some() || cc.s_max_age().is_some())
+        || headers.contains_key(header::EXPIRES);
+    let has_validator =
+        headers.contains_key(header::ETAG) || headers.contains_key(header::LAST_MODIFIED);
+    if !has_freshness && !has_validator {
+        return None;
+    }
+
+    match headers.typed_get::<Vary>() {
+        Some(vary) if vary.is_any() => None,
+        Some(vary) => vary
+            .iter_strs()
+            .map(|name| {
+                let name = HeaderName::try_from(name).ok()?;
+                let value = request_headers.get(&name).cloned();
+                Some((name, value))
+            })
+            .collect(),
+        None => Some(Vec::new()),
+    }
+}
+
+fn initial_age(headers: &HeaderMap) -> Duration {
+    headers
+        .typed_get::<crate::headers::Age>()
+        .map(Into::into)
+        .unwrap_or_default()
+}
+
+fn freshness_lifetime(headers: &HeaderMap) -> Duration {
+    if let Some(cc) = headers.typed_get::<CacheControl>() {
+        if cc.no_cache() {
+            return Duration::ZERO;
+        }
+        if let Some(max_age) = cc.s_max_age().or_else(|| cc.max_age()) {
+            return max_age;
+        }
+    }
+    match headers.typed_get::<Expires>() {
+        Some(expires) => {
+            let date = headers
+                .typed_get::<Date>()
+                .map(SystemTime::from)
+                .unwrap_or_else(SystemTime::now);
+            SystemTime::from(expires)
+                .duration_since(date)
+                .unwrap_or_default()
+        }
+        None => Duration::ZERO,
+    }
+}
@@ -0,0 +1,242 @@
+use crate::dep::http::uri::PathAndQuery;
+use crate::{
+    Body, HeaderMap, HeaderName, HeaderValue, Method, Request, Response, StatusCode, Version,
+    header,
+};
+use parking_lot::Mutex;
+use rama_core::Context;
+use rama_core::bytes::Bytes;
+use rama_net::address::Authority;
+use rama_net::http::RequestContext;
+use std::collections::{BTreeMap, HashMap};
+use std::time::Duration;
+use tokio::time::Instant;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+/// The key under which a [`CachedResponse`] is stored in a [`CacheStore`].
+///
+/// It consists out of the method, the effective authority
+/// and the path and query of the request.
+pub struct CacheKey {
+    method: Method,
+    authority: Option<Authority>,
+    path_and_query: PathAndQuery,
+}
+
+impl CacheKey {
+    /// Create a new [`CacheKey`] for the given method, authority and path and query.
+    pub const fn new(
+        method: Method,
+        authority: Option<Authority>,
+        path_and_query: PathAndQuery,
+    ) -> Self {
+        Self {
+            method,
+            authority,
+            path_and_query,
+        }
+    }
+
+    /// Create a new [`CacheKey`] for the given request,
+    /// using the (effective) authority found in the [`RequestContext`].
+    pub fn from_request<State, Body>(ctx: &Context<State>, req: &Request<Body>) -> Self {
+        let authority = match ctx.get::<RequestContext>() {
+            Some(request_ctx) => Some(request_ctx.authority.clone()),
+            None => RequestContext::try_from((ctx, req))
+                .ok()
+                .map(|request_ctx| request_ctx.authority),
+        };
+        let path_and_query = req
+            .uri()
+            .path_and_query()
+            .cloned()
+            .unwrap_or_else(|| PathAndQuery::from_static("/"));
+        Self::new(req.method().clone(), authority, path_and_query)
+    }
+
+    /// Create a copy of this [`CacheKey`] for another method.
+    pub fn with_method(&self, method: Method) -> Self {
+        Self {
+            method,
+            authority: self.authority.clone(),
+            path_and_query: self.path_and_query.clone(),
+        }
+    }
+
+    /// The method of the request this [`CacheKey`] was created for.
+    pub fn method(&self) -> &Method {
+        &self.method
+    }
+
+    /// The authority of the request this [`CacheKey`] was created for,
+    /// if it could be determined.
+    pub fn authority(&self) -> Option<&Authority> {
+        self.authority.as_ref()
+    }
+
+    /// The path and query of the request this [`CacheKey`] was created for.
+    pub fn path_and_query(&self) -> &PathAndQuery {
+        &self.path_and_query
+    }
+}
+
+#[derive(Debug, Clone)]
+/// A response stored in a [`CacheStore`].
+pub struct CachedResponse {
+    pub(super) status: StatusCode,
+    pub(super) version: Version,
+    pub(super) headers: HeaderMap,
+    pub(super) body: Bytes,
+    pub(super) vary: Vec<(HeaderName, Option<HeaderValue>)>,
+    pub(super) stored_at: Instant,
+    pub(super) initial_age: Duration,
+    pub(super) freshness: Duration,
+}
+
+impl CachedResponse {
+    /// The status code of the cached response.
+    pub fn status(&self) -> StatusCode {
+        self.status
+    }
+
+    /// The headers of the cached response.
+    pub fn headers(&self) -> &HeaderMap {
+        &self.headers
+    }
+
+    /// The body of the cached response.
+    pub fn body(&self) -> &Bytes {
+        &self.body
+    }
+
+    /// The current age of the cached response.
+    pub fn age(&self) -> Duration {
+        self.initial_age + self.stored_at.elapsed()
+    }
+
+    /// Returns `true` if the cached response can still be used
+    /// without revalidating it with the origin.
+    pub fn is_fresh(&self) -> bool {
+        self.age() < self.freshness
+    }
+
+    pub(super) fn matches_vary(&self, headers: &HeaderMap) -> bool {
+        self.vary
+            .iter()
+            .all(|(name, value)| headers.get(name) == value.as_ref())
+    }
+
+    pub(super) fn to_response(&self) -> Response {
+        let mut response = Response::new(Body::from(self.body.clone()));
+        *response.status_mut() = self.status;
+        *response.version_mut() = self.version;
+        *response.headers_mut() = self.headers.clone();
+        response
+            .headers_mut()
+            .insert(header::AGE, HeaderValue::from(self.age().as_secs()));
+        response
+    }
+}
+
+/// A store used by the [`Cache`] middleware to store responses.
+///
+/// [`Cache`]: super::Cache
+pub trait CacheStore: Send + Sync + 'static {
+    /// Get the response stored for the given key.
+    fn get(&self, key: &CacheKey) -> impl Future<Output = Option<CachedResponse>> + Send;
+
+    /// Store the response for the given key,
+    /// replacing any response previously stored for that key.
+    fn insert(&self, key: CacheKey, response: CachedResponse) -> impl Future<Output = ()> + Send;
+
+    /// Remove the response stored for the given key.
+    fn remove(&self, key: &CacheKey) -> impl Future<Output = ()> + Send;
+}
+
+/// An in-memory [`CacheStore`], evicting the least recently used
+/// response once it holds more than the configured amount of responses.
+#[derive(Debug)]
+pub struct MemoryCache {
+    capacity: usize,
+    inner: Mutex<MemoryCacheInner>,
+}
+
+#[derive(Debug, Default)]
+struct MemoryCacheInner {
+    entries: HashMap<CacheKey, (CachedResponse, u64)>,
+    recency: BTreeMap<u64, CacheKey>,
+    tick: u64,
+}
+
+impl MemoryCacheInner {
+    fn touch(&mut self, key: &CacheKey) -> Option<&CachedResponse> {
+        let tick = self.tick;
+        let (response, last_used) = self.entries.get_mut(key)?;
+        self.recency.remove(last_used);
+        *last_used = tick;
+        self.recency.insert(tick, key.clone());
+        self.tick += 1;
+        Some(response)
+    }
+}
+
+impl MemoryCache {
+    /// Create a new [`MemoryCache`] which holds up to `capacity` responses.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            inner: Default::default(),
+        }
+    }
+
+    /// The amount of responses currently stored.
+    pub fn len(&self) -> usize {
+        self.inner.lock().entries.len()
+    }
+
+    /// Returns `true` if no responses are stored.
+    pub fn is_empty(&self) -> bool {
+        self.inner.lock().entries.is_empty()
+    }
+}
+
+impl Default for MemoryCache {
+    fn default() -> Self {
+        Self::new(1024)
+    }
+}
+
+impl CacheStore for MemoryCache {
+    async fn get(&self, key: &CacheKey) -> Option<CachedResponse> {
+        self.inner.lock().touch(key).cloned()
+    }
+
+    async fn insert(&self, key: CacheKey, response: CachedResponse) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        let mut inner = self.inner.lock();
+        let tick = inner.tick;
+        inner.tick += 1;
+
+        if let Some((_, last_used)) = inner.entries.insert(key.clone(), (response, tick)) {
+            inner.recency.remove(&last_used);
+        }
+        inner.recency.insert(tick, key);
+
+        while inner.entries.len() > self.capacity {
+            let Some((_, key)) = inner.recency.pop_first() else {
+                break;
+            };
+            inner.entries.remove(&key);
+        }
+    }
+
+    async fn remove(&self, key: &CacheKey) {
+        let mut inner = self.inner.lock();
+        if let Some((_, last_used)) = inner.entries.remove(key) {
+            inner.recency.remove(&last_used);
+        }
+    }
+}
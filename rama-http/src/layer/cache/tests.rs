@@ -0,0 +1,232 @@
+use super::*;
+
+use crate::dep::http_body_util::BodyExt;
+use crate::{Body, Method, Request, Response, StatusCode, header};
+use rama_core::service::service_fn;
+use rama_core::{Context, Layer, Service};
+use std::convert::Infallible;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+async fn serve(
+    svc: &impl Service<(), Request, Response = Response, Error = rama_core::error::BoxError>,
+    req: Request,
+) -> (Option<CacheStatus>, Response) {
+    let res = svc.serve(Context::default(), req).await.unwrap();
+    (res.extensions().get::<CacheStatus>().copied(), res)
+}
+
+fn get(uri: &str) -> Request {
+    Request::builder().uri(uri).body(Body::empty()).unwrap()
+}
+
+async fn body_string(res: Response) -> String {
+    let body = res.into_body().collect().await.unwrap().to_bytes();
+    String::from_utf8(body.to_vec()).unwrap()
+}
+
+#[tokio::test(start_paused = true)]
+async fn cache_fresh_response() {
+    let counter = Arc::new(AtomicUsize::new(0));
+    let svc = CacheLayer::new(MemoryCache::default()).into_layer(service_fn({
+        let counter = counter.clone();
+        move |req: Request| {
+            let n = counter.fetch_add(1, Ordering::SeqCst);
+            let cache_control = if req.uri().path() == "/no-store" {
+                "no-store"
+            } else {
+                "max-age=10"
+            };
+            std::future::ready(Ok::<_, Infallible>(
+                Response::builder()
+                    .header(header::CACHE_CONTROL, cache_control)
+                    .body(Body::from(n.to_string()))
+                    .unwrap(),
+            ))
+        }
+    }));
+
+    let (status, res) = serve(&svc, get("/")).await;
+    assert_eq!(status, Some(CacheStatus::Miss));
+    assert_eq!(body_string(res).await, "0");
+
+    tokio::time::advance(Duration::from_secs(5)).await;
+
+    let (status, res) = serve(&svc, get("/")).await;
+    assert_eq!(status, Some(CacheStatus::Hit));
+    assert_eq!(res.headers()[header::AGE], "5");
+    assert_eq!(body_string(res).await, "0");
+
+    tokio::time::advance(Duration::from_secs(5)).await;
+
+    let (status, res) = serve(&svc, get("/")).await;
+    assert_eq!(status, Some(CacheStatus::Miss));
+    assert_eq!(body_string(res).await, "1");
+
+    let (status, res) = serve(&svc, get("/no-store")).await;
+    assert_eq!(status, Some(CacheStatus::Miss));
+    assert_eq!(body_string(res).await, "2");
+
+    let (status, res) = serve(&svc, get("/no-store")).await;
+    assert_eq!(status, Some(CacheStatus::Miss));
+    assert_eq!(body_string(res).await, "3");
+
+    let req = Request::builder()
+        .uri("/")
+        .header(header::CACHE_CONTROL, "no-store")
+        .body(Body::empty())
+        .unwrap();
+    let (status, res) = serve(&svc, req).await;
+    assert_eq!(status, Some(CacheStatus::Bypass));
+    assert_eq!(body_string(res).await, "4");
+}
+
+#[tokio::test(start_paused = true)]
+async fn cache_revalidate_response() {
+    let svc = CacheLayer::new(MemoryCache::default()).into_layer(service_fn(
+        async |ctx: Context<()>, req: Request| {
+            assert!(ctx.get::<CacheStatus>().is_some());
+            if req
+                .headers()
+                .get(header::IF_NONE_MATCH)
+                .is_some_and(|etag| etag == "\"v1\"")
+            {
+                return Ok::<_, Infallible>(
+                    Response::builder()
+                        .status(StatusCode::NOT_MODIFIED)
+                        .header(header::CACHE_CONTROL, "max-age=10")
+                        .body(Body::empty())
+                        .unwrap(),
+                );
+            }
+            Ok(Response::builder()
+                .header(header::CACHE_CONTROL, "no-cache")
+                .header(header::ETAG, "\"v1\"")
+                .body(Body::from("hello"))
+                .unwrap())
+        },
+    ));
+
+    let (status, res) = serve(&svc, get("/")).await;
+    assert_eq!(status, Some(CacheStatus::Miss));
+    assert_eq!(body_string(res).await, "hello");
+
+    let (status, res) = serve(&svc, get("/")).await;
+    assert_eq!(status, Some(CacheStatus::Revalidated));
+    assert_eq!(res.status(), StatusCode::OK);
+    assert_eq!(res.headers()[header::CACHE_CONTROL], "max-age=10");
+    assert_eq!(body_string(res).await, "hello");
+
+    let (status, res) = serve(&svc, get("/")).await;
+    assert_eq!(status, Some(CacheStatus::Hit));
+    assert_eq!(body_string(res).await, "hello");
+}
+
+#[tokio::test]
+async fn cache_vary_and_invalidation() {
+    let counter = Arc::new(AtomicUsize::new(0));
+    let svc = CacheLayer::new(MemoryCache::default()).into_layer(service_fn({
+        let counter = counter.clone();
+        move |_req: Request| {
+            let n = counter.fetch_add(1, Ordering::SeqCst);
+            std::future::ready(Ok::<_, Infallible>(
+                Response::builder()
+                    .header(header::CACHE_CONTROL, "max-age=60")
+                    .header(header::VARY, "accept-language")
+                    .body(Body::from(n.to_string()))
+                    .unwrap(),
+            ))
+        }
+    }));
+
+    let lang = |lang: &str| {
+        Request::builder()
+            .uri("/")
+            .header(header::ACCEPT_LANGUAGE, lang)
+            .body(Body::empty())
+            .unwrap()
+    };
+
+    assert_eq!(serve(&svc, lang("en")).await.0, Some(CacheStatus::Miss));
+    assert_eq!(serve(&svc, lang("en")).await.0, Some(CacheStatus::Hit));
+    assert_eq!(serve(&svc, lang("nl")).await.0, Some(CacheStatus::Miss));
+    assert_eq!(serve(&svc, lang("nl")).await.0, Some(CacheStatus::Hit));
+
+    let req = Request::builder()
+        .method(Method::POST)
+        .uri("/")
+        .body(Body::empty())
+        .unwrap();
+    assert_eq!(serve(&svc, req).await.0, Some(CacheStatus::Bypass));
+    assert_eq!(serve(&svc, lang("nl")).await.0, Some(CacheStatus::Miss));
+}
+
+#[tokio::test]
+async fn cache_key_authority() {
+    let svc =
+        CacheLayer::new(MemoryCache::default()).into_layer(service_fn(async |req: Request| {
+            let host = req.headers()[header::HOST].clone();
+            Ok::<_, Infallible>(
+                Response::builder()
+                    .header(header::CACHE_CONTROL, "max-age=60")
+                    .body(Body::from(host.as_bytes().to_vec()))
+                    .unwrap(),
+            )
+        }));
+
+    let req = |method: Method, host: &str| {
+        Request::builder()
+            .method(method)
+            .uri("/?q=1")
+            .header(header::HOST, host)
+            .body(Body::empty())
+            .unwrap()
+    };
+
+    let (status, res) = serve(&svc, req(Method::GET, "a.example.com")).await;
+    assert_eq!(status, Some(CacheStatus::Miss));
+    assert_eq!(body_string(res).await, "a.example.com");
+    let (status, res) = serve(&svc, req(Method::GET, "b.example.com")).await;
+    assert_eq!(status, Some(CacheStatus::Miss));
+    assert_eq!(body_string(res).await, "b.example.com");
+
+    let (status, res) = serve(&svc, req(Method::GET, "a.example.com")).await;
+    assert_eq!(status, Some(CacheStatus::Hit));
+    assert_eq!(body_string(res).await, "a.example.com");
+
+    // invalidation only applies to the same authority
+    let (status, _) = serve(&svc, req(Method::POST, "a.example.com")).await;
+    assert_eq!(status, Some(CacheStatus::Bypass));
+    let (status, _) = serve(&svc, req(Method::GET, "a.example.com")).await;
+    assert_eq!(status, Some(CacheStatus::Miss));
+    let (status, res) = serve(&svc, req(Method::GET, "b.example.com")).await;
+    assert_eq!(status, Some(CacheStatus::Hit));
+    assert_eq!(body_string(res).await, "b.example.com");
+}
+
+#[tokio::test]
+async fn memory_cache_lru_eviction() {
+    let store = MemoryCache::new(2);
+    let response = CachedResponse {
+        status: StatusCode::OK,
+        version: Default::default(),
+        headers: Default::default(),
+        body: Default::default(),
+        vary: Vec::new(),
+        stored_at: tokio::time::Instant::now(),
+        initial_age: Duration::ZERO,
+        freshness: Duration::ZERO,
+    };
+    let key = |path: &'static str| CacheKey::new(Method::GET, None, path.parse().unwrap());
+
+    store.insert(key("/a"), response.clone()).await;
+    store.insert(key("/b"), response.clone()).await;
+    assert!(store.get(&key("/a")).await.is_some());
+
+    store.insert(key("/c"), response).await;
+    assert_eq!(store.len(), 2);
+    assert!(store.get(&key("/a")).await.is_some());
+    assert!(store.get(&key("/b")).await.is_none());
+    assert!(store.get(&key("/c")).await.is_some());
+}
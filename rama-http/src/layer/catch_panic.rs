@@ -3,6 +3,10 @@
 //! Note that using panics for error handling is _not_ recommended. Prefer instead to use `Result`
 //! whenever possible.
 //!
+//! Panics are logged at the `ERROR` level. The backtrace of the panic is not available
+//! once it has been caught, it is however still printed by the standard panic hook
+//! in case `RUST_BACKTRACE=1` is set.
+//!
 //! # Example
 //!
 //! ```rust
@@ -36,6 +40,41 @@
 //! # }
 //! ```
 //!
+//! Using a custom response, while still logging the panic:
+//!
+//! ```rust
+//! use std::convert::Infallible;
+//!
+//! use rama_http::{Body, Request, Response, StatusCode};
+//! use rama_http::layer::catch_panic::CatchPanicLayer;
+//! use rama_core::service::service_fn;
+//! use rama_core::{Context, Service, Layer};
+//! use rama_core::error::BoxError;
+//!
+//! # #[tokio::main]
+//! # async fn main() -> Result<(), BoxError> {
+//! async fn handle(req: Request) -> Result<Response, Infallible> {
+//!     panic!("something went wrong...")
+//! }
+//!
+//! fn error_page() -> Response {
+//!     Response::builder()
+//!         .status(StatusCode::INTERNAL_SERVER_ERROR)
+//!         .body(Body::from("<h1>Oops, something went wrong</h1>"))
+//!         .unwrap()
+//! }
+//!
+//! let svc = (
+//!     CatchPanicLayer::with_response_fn(error_page),
+//! ).into_layer(service_fn(handle));
+//!
+//! let response = svc.serve(Context::default(), Request::new(Body::default())).await?;
+//! assert_eq!(response.status(), 500);
+//! #
+//! # Ok(())
+//! # }
+//! ```
+//!
 //! Using a custom panic handler:
 //!
 //! ```rust
@@ -133,6 +172,16 @@ impl CatchPanicLayer<DefaultResponseForPanic> {
     }
 }
 
+impl CatchPanicLayer<PanicResponseFn> {
+    /// Create a new `CatchPanicLayer` which logs the panic
+    /// and uses the given function to create the response.
+    pub const fn with_response_fn(response_fn: fn() -> Response) -> Self {
+        CatchPanicLayer {
+            panic_handler: PanicResponseFn(response_fn),
+        }
+    }
+}
+
 impl<T> CatchPanicLayer<T> {
     /// Create a new `CatchPanicLayer` with a custom panic handler.
     pub fn custom(panic_handler: T) -> Self
@@ -182,6 +231,17 @@ impl<S> CatchPanic<S, DefaultResponseForPanic> {
     }
 }
 
+impl<S> CatchPanic<S, PanicResponseFn> {
+    /// Create a new `CatchPanic` which logs the panic
+    /// and uses the given function to create the response.
+    pub const fn with_response_fn(inner: S, response_fn: fn() -> Response) -> Self {
+        Self {
+            inner,
+            panic_handler: PanicResponseFn(response_fn),
+        }
+    }
+}
+
 impl<S, T> CatchPanic<S, T> {
     define_inner_service_accessors!();
 
@@ -272,15 +332,7 @@ pub struct DefaultResponseForPanic;
 
 impl ResponseForPanic for DefaultResponseForPanic {
     fn response_for_panic(&self, err: Box<dyn Any + Send + 'static>) -> Response {
-        if let Some(s) = err.downcast_ref::<String>() {
-            tracing::error!("Service panicked: {}", s);
-        } else if let Some(s) = err.downcast_ref::<&str>() {
-            tracing::error!("Service panicked: {}", s);
-        } else {
-            tracing::error!(
-                "Service panicked but `CatchPanic` was unable to downcast the panic info"
-            );
-        };
+        log_panic(&err);
 
         let mut res = Response::new(Body::from("Service panicked"));
         *res.status_mut() = StatusCode::INTERNAL_SERVER_ERROR;
@@ -294,6 +346,29 @@ impl ResponseForPanic for DefaultResponseForPanic {
     }
 }
 
+/// The `ResponseForPanic` used by [`CatchPanicLayer::with_response_fn`].
+///
+/// It will log the panic message and return the response created by the wrapped function.
+#[derive(Debug, Clone, Copy)]
+pub struct PanicResponseFn(fn() -> Response);
+
+impl ResponseForPanic for PanicResponseFn {
+    fn response_for_panic(&self, err: Box<dyn Any + Send + 'static>) -> Response {
+        log_panic(&err);
+        (self.0)()
+    }
+}
+
+fn log_panic(err: &Box<dyn Any + Send + 'static>) {
+    if let Some(s) = err.downcast_ref::<String>() {
+        tracing::error!("Service panicked: {}", s);
+    } else if let Some(s) = err.downcast_ref::<&str>() {
+        tracing::error!("Service panicked: {}", s);
+    } else {
+        tracing::error!("Service panicked but `CatchPanic` was unable to downcast the panic info");
+    }
+}
+
 #[cfg(test)]
 mod tests {
     #![allow(unreachable_code)]
@@ -337,4 +412,38 @@ mod tests {
         let body = res.into_body().collect().await.unwrap().to_bytes();
         assert_eq!(&body[..], b"Service panicked");
     }
+
+    #[tokio::test]
+    async fn custom_response_fn() {
+        fn error_page() -> Response {
+            let mut res = Response::new(Body::from("error page"));
+            *res.status_mut() = StatusCode::INTERNAL_SERVER_ERROR;
+            res
+        }
+
+        let svc = CatchPanicLayer::with_response_fn(error_page).into_layer(service_fn(
+            async |req: Request<Body>| {
+                if req.uri().path() == "/panic" {
+                    panic!("future panic");
+                }
+                Ok::<_, Infallible>(Response::new(Body::from("ok")))
+            },
+        ));
+
+        let req = Request::builder()
+            .uri("/panic")
+            .body(Body::empty())
+            .unwrap();
+        let res = svc.serve(Context::default(), req).await.unwrap();
+        assert_eq!(res.status(), StatusCode::INTERNAL_SERVER_ERROR);
+        let body = res.into_body().collect().await.unwrap().to_bytes();
+        assert_eq!(&body[..], b"error page");
+
+        // the service keeps serving requests after a panic
+        let req = Request::new(Body::empty());
+        let res = svc.serve(Context::default(), req).await.unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+        let body = res.into_body().collect().await.unwrap().to_bytes();
+        assert_eq!(&body[..], b"ok");
+    }
 }
@@ -0,0 +1,724 @@
+//! Middleware that stops calling the inner service once it keeps failing.
+//!
+//! The [`CircuitBreaker`] implements the classic three-state machine:
+//!
+//! - **Closed**: requests are passed to the inner service, consecutive failures
+//!   (errors or `5xx` responses) are counted. Once the failure threshold is reached
+//!   the circuit opens. Optionally the circuit also opens once the percentage
+//!   of failures within a sliding window reaches the failure rate threshold.
+//! - **Open**: requests are rejected immediately (by default with a
+//!   `503 Service Unavailable` response) without calling the inner service.
+//!   Once the open duration has passed the circuit becomes half-open.
+//! - **Half-Open**: a limited amount of requests are passed to the inner service
+//!   as probes, all other requests are rejected until these probes resolve.
+//!   A single failure opens the circuit again, while reaching the success
+//!   threshold closes it.
+//!
+//! The state is shared between all clones of the service (and all services
+//! created by the same [`CircuitBreakerLayer`]), and can be inspected
+//! using [`CircuitBreakerState::status`], e.g. for health checks.
+//!
+//! # Example
+//!
+//! ```
+//! use rama_core::service::service_fn;
+//! use rama_core::{Context, Layer, Service};
+//! use rama_http::layer::circuit_breaker::{CircuitBreakerLayer, CircuitBreakerStatus};
+//! use rama_http::{Body, Request, Response, StatusCode};
+//! use std::convert::Infallible;
+//! use std::time::Duration;
+//!
+//! # #[tokio::main]
+//! # async fn main() {
+//! let layer = CircuitBreakerLayer::new()
+//!     .with_failure_threshold(1)
+//!     .with_open_duration(Duration::from_secs(30));
+//! let state = layer.state().clone();
+//!
+//! let svc = layer.into_layer(service_fn(async |_req: Request| {
+//!     Ok::<_, Infallible>(
+//!         Response::builder()
+//!             .status(StatusCode::INTERNAL_SERVER_ERROR)
+//!             .body(Body::empty())
+//!             .unwrap(),
+//!     )
+//! }));
+//!
+//! let res = svc.serve(Context::default(), Request::new(Body::empty())).await.unwrap();
+//! assert_eq!(res.status(), StatusCode::INTERNAL_SERVER_ERROR);
+//! assert_eq!(state.status(), CircuitBreakerStatus::Open);
+//!
+//! let res = svc.serve(Context::default(), Request::new(Body::empty())).await.unwrap();
+//! assert_eq!(res.status(), StatusCode::SERVICE_UNAVAILABLE);
+//! # }
+//! ```
+
+use crate::{Request, Response, StatusCode};
+use rama_core::telemetry::tracing;
+use rama_core::{Context, Layer, Service};
+use rama_utils::macros::define_inner_service_accessors;
+use std::fmt;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU8, AtomicU64, AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+/// Status of a [`CircuitBreaker`], as reported by [`CircuitBreakerState::status`].
+pub enum CircuitBreakerStatus {
+    /// Requests are passed to the inner service.
+    Closed,
+    /// Requests are rejected without calling the inner service.
+    Open,
+    /// Requests are passed to the inner service to probe whether it recovered.
+    HalfOpen,
+}
+
+const STATUS_CLOSED: u8 = 0;
+const STATUS_OPEN: u8 = 1;
+const STATUS_HALF_OPEN: u8 = 2;
+
+const WINDOW_BUCKETS: usize = 10;
+
+#[derive(Default)]
+/// Outcomes recorded during a single slot of the sliding window.
+struct WindowBucket {
+    slot: AtomicU64,
+    successes: AtomicU64,
+    failures: AtomicU64,
+}
+
+/// State of a [`CircuitBreaker`], shared between all its clones.
+pub struct CircuitBreakerState {
+    status: AtomicU8,
+    failures: AtomicUsize,
+    successes: AtomicUsize,
+    probes: AtomicUsize,
+    opened_at_ms: AtomicU64,
+    window: [WindowBucket; WINDOW_BUCKETS],
+    epoch: Instant,
+}
+
+impl fmt::Debug for CircuitBreakerState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CircuitBreakerState")
+            .field("status", &self.status())
+            .field("failures", &self.failures.load(Ordering::Relaxed))
+            .field("successes", &self.successes.load(Ordering::Relaxed))
+            .field("probes", &self.probes.load(Ordering::Relaxed))
+            .finish()
+    }
+}
+
+impl Default for CircuitBreakerState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CircuitBreakerState {
+    /// Create a new (closed) [`CircuitBreakerState`].
+    pub fn new() -> Self {
+        Self {
+            status: AtomicU8::new(STATUS_CLOSED),
+            failures: AtomicUsize::new(0),
+            successes: AtomicUsize::new(0),
+            probes: AtomicUsize::new(0),
+            opened_at_ms: AtomicU64::new(0),
+            window: Default::default(),
+            epoch: Instant::now(),
+        }
+    }
+
+    /// Get the current [`CircuitBreakerStatus`].
+    pub fn status(&self) -> CircuitBreakerStatus {
+        match self.status.load(Ordering::Acquire) {
+            STATUS_OPEN => CircuitBreakerStatus::Open,
+            STATUS_HALF_OPEN => CircuitBreakerStatus::HalfOpen,
+            _ => CircuitBreakerStatus::Closed,
+        }
+    }
+
+    fn now_ms(&self) -> u64 {
+        self.epoch
+            .elapsed()
+            .as_millis()
+            .try_into()
+            .unwrap_or(u64::MAX)
+    }
+
+    fn open(&self) {
+        self.opened_at_ms.store(self.now_ms(), Ordering::Release);
+        self.successes.store(0, Ordering::Release);
+        self.reset_window();
+        self.status.store(STATUS_OPEN, Ordering::Release);
+    }
+
+    /// Returns a [`Permit`] if the request is allowed to be passed to the inner service.
+    fn try_acquire(&self, config: &CircuitBreakerConfig) -> Option<Permit<'_>> {
+        match self.status.load(Ordering::Acquire) {
+            STATUS_CLOSED => return Some(Permit { probes: None }),
+            STATUS_OPEN => {
+                let opened_at = self.opened_at_ms.load(Ordering::Acquire);
+                let open_ms: u64 = config
+                    .open_duration
+                    .as_millis()
+                    .try_into()
+                    .unwrap_or(u64::MAX);
+                if self.now_ms().saturating_sub(opened_at) < open_ms {
+                    return None;
+                }
+                if self
+                    .status
+                    .compare_exchange(
+                        STATUS_OPEN,
+                        STATUS_HALF_OPEN,
+                        Ordering::AcqRel,
+                        Ordering::Acquire,
+                    )
+                    .is_ok()
+                {
+                    tracing::debug!("circuit breaker: half-open");
+                    self.successes.store(0, Ordering::Release);
+                }
+            }
+            _ => (),
+        }
+
+        if self.probes.fetch_add(1, Ordering::AcqRel) >= config.half_open_max_probes {
+            self.probes.fetch_sub(1, Ordering::AcqRel);
+            return None;
+        }
+        Some(Permit {
+            probes: Some(&self.probes),
+        })
+    }
+
+    fn record_success(&self, config: &CircuitBreakerConfig) {
+        match self.status.load(Ordering::Acquire) {
+            STATUS_HALF_OPEN => {
+                if self.successes.fetch_add(1, Ordering::AcqRel) + 1 >= config.success_threshold
+                    && self
+                        .status
+                        .compare_exchange(
+                            STATUS_HALF_OPEN,
+                            STATUS_CLOSED,
+                            Ordering::AcqRel,
+                            Ordering::Acquire,
+                        )
+                        .is_ok()
+                {
+                    tracing::debug!("circuit breaker: closed");
+                    self.failures.store(0, Ordering::Release);
+                    self.reset_window();
+                }
+            }
+            _ => {
+                self.failures.store(0, Ordering::Release);
+                if config.failure_rate_threshold.is_some() {
+                    self.record_window(false, config.sliding_window);
+                }
+            }
+        }
+    }
+
+    fn record_failure(&self, config: &CircuitBreakerConfig) {
+        match self.status.load(Ordering::Acquire) {
+            STATUS_HALF_OPEN => {
+                tracing::debug!("circuit breaker: probe failed, open");
+                self.open();
+            }
+            STATUS_CLOSED => {
+                let consecutive = self.failures.fetch_add(1, Ordering::AcqRel) + 1;
+                let rate_reached = config.failure_rate_threshold.is_some_and(|threshold| {
+                    let (failures, total) = self.record_window(true, config.sliding_window);
+                    total >= config.minimum_requests as u64
+                        && failures as f64 / total as f64 >= threshold
+                });
+                if consecutive >= config.failure_threshold {
+                    tracing::debug!("circuit breaker: failure threshold reached, open");
+                    self.open();
+                } else if rate_reached {
+                    tracing::debug!("circuit breaker: failure rate threshold reached, open");
+                    self.open();
+                }
+            }
+            _ => (),
+        }
+    }
+
+    /// Record the outcome of a request in the sliding window,
+    /// returning the amount of failures and requests within that window.
+    ///
+    /// The window is divided in a fixed amount of buckets, and therefore
+    /// only approximates the requests that happened within the window.
+    fn record_window(&self, failed: bool, window: Duration) -> (u64, u64) {
+        let window_ms: u64 = window.as_millis().try_into().unwrap_or(u64::MAX);
+        let bucket_ms = (window_ms / WINDOW_BUCKETS as u64).max(1);
+        // slots start at 1, as 0 marks an unused bucket
+        let slot = self.now_ms() / bucket_ms + 1;
+
+        let bucket = &self.window[(slot % WINDOW_BUCKETS as u64) as usize];
+        let bucket_slot = bucket.slot.load(Ordering::Acquire);
+        if bucket_slot != slot
+            && bucket
+                .slot
+                .compare_exchange(bucket_slot, slot, Ordering::AcqRel, Ordering::Acquire)
+                .is_ok()
+        {
+            bucket.successes.store(0, Ordering::Release);
+            bucket.failures.store(0, Ordering::Release);
+        }
+        if failed {
+            bucket.failures.fetch_add(1, Ordering::AcqRel);
+        } else {
+            bucket.successes.fetch_add(1, Ordering::AcqRel);
+        }
+
+        self.window
+            .iter()
+            .filter(|bucket| {
+                let bucket_slot = bucket.slot.load(Ordering::Acquire);
+                bucket_slot != 0 && bucket_slot + WINDOW_BUCKETS as u64 > slot
+            })
+            .fold((0, 0), |(failures, total), bucket| {
+                let bucket_failures = bucket.failures.load(Ordering::Acquire);
+                let bucket_successes = bucket.successes.load(Ordering::Acquire);
+                (
+                    failures + bucket_failures,
+                    total + bucket_failures + bucket_successes,
+                )
+            })
+    }
+
+    fn reset_window(&self) {
+        for bucket in &self.window {
+            bucket.slot.store(0, Ordering::Release);
+            bucket.successes.store(0, Ordering::Release);
+            bucket.failures.store(0, Ordering::Release);
+        }
+    }
+}
+
+/// Permission to pass a request to the inner service,
+/// releasing the half-open probe (if any) once dropped.
+struct Permit<'a> {
+    probes: Option<&'a AtomicUsize>,
+}
+
+impl Drop for Permit<'_> {
+    fn drop(&mut self) {
+        if let Some(probes) = self.probes {
+            probes.fetch_sub(1, Ordering::AcqRel);
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct CircuitBreakerConfig {
+    failure_threshold: usize,
+    failure_rate_threshold: Option<f64>,
+    sliding_window: Duration,
+    minimum_requests: usize,
+    success_threshold: usize,
+    half_open_max_probes: usize,
+    open_duration: Duration,
+    open_status: StatusCode,
+}
+
+impl Default for CircuitBreakerConfig {
+    fn default() -> Self {
+        Self {
+            failure_threshold: 5,
+            failure_rate_threshold: None,
+            sliding_window: Duration::from_secs(60),
+            minimum_requests: 10,
+            success_threshold: 1,
+            half_open_max_probes: 1,
+            open_duration: Duration::from_secs(30),
+            open_status: StatusCode::SERVICE_UNAVAILABLE,
+        }
+    }
+}
+
+macro_rules! impl_circuit_breaker_config_setters {
+    () => {
+        rama_utils::macros::generate_set_and_with! {
+            /// Set the amount of consecutive failures after which the circuit opens.
+            ///
+            /// Defaults to `5`.
+            pub fn failure_threshold(mut self, threshold: usize) -> Self {
+                self.config.failure_threshold = threshold.max(1);
+                self
+            }
+        }
+
+        rama_utils::macros::generate_set_and_with! {
+            /// Set the percentage of failures (between `0.0` and `1.0`) within
+            /// the sliding window at which the circuit opens.
+            ///
+            /// Disabled by default, in which case only consecutive failures are considered.
+            pub fn failure_rate_threshold(mut self, threshold: Option<f64>) -> Self {
+                self.config.failure_rate_threshold = threshold.map(|t| t.clamp(0.0, 1.0));
+                self
+            }
+        }
+
+        rama_utils::macros::generate_set_and_with! {
+            /// Set the duration of the sliding window used for the failure rate threshold.
+            ///
+            /// Defaults to `60s`.
+            pub fn sliding_window(mut self, window: Duration) -> Self {
+                self.config.sliding_window = window;
+                self
+            }
+        }
+
+        rama_utils::macros::generate_set_and_with! {
+            /// Set the minimum amount of requests within the sliding window
+            /// before the failure rate threshold is considered.
+            ///
+            /// Defaults to `10`.
+            pub fn minimum_requests(mut self, minimum: usize) -> Self {
+                self.config.minimum_requests = minimum.max(1);
+                self
+            }
+        }
+
+        rama_utils::macros::generate_set_and_with! {
+            /// Set the amount of probes which are passed concurrently
+            /// to the inner service while the circuit is half-open.
+            ///
+            /// Defaults to `1`.
+            pub fn half_open_max_probes(mut self, max: usize) -> Self {
+                self.config.half_open_max_probes = max.max(1);
+                self
+            }
+        }
+
+        rama_utils::macros::generate_set_and_with! {
+            /// Set the amount of successful probes required to close a half-open circuit.
+            ///
+            /// Defaults to `1`.
+            pub fn success_threshold(mut self, threshold: usize) -> Self {
+                self.config.success_threshold = threshold.max(1);
+                self
+            }
+        }
+
+        rama_utils::macros::generate_set_and_with! {
+            /// Set the duration a circuit stays open before becoming half-open.
+            ///
+            /// Defaults to `30s`.
+            pub fn open_duration(mut self, duration: Duration) -> Self {
+                self.config.open_duration = duration;
+                self
+            }
+        }
+
+        rama_utils::macros::generate_set_and_with! {
+            /// Set the status code of the response returned while the circuit is open.
+            ///
+            /// Defaults to `503 Service Unavailable`.
+            pub fn open_status(mut self, status: StatusCode) -> Self {
+                self.config.open_status = status;
+                self
+            }
+        }
+
+        /// Get a reference to the shared [`CircuitBreakerState`].
+        pub fn state(&self) -> &Arc<CircuitBreakerState> {
+            &self.state
+        }
+    };
+}
+
+/// Layer that applies the [`CircuitBreaker`] middleware.
+///
+/// All services created by the same layer share the same [`CircuitBreakerState`].
+///
+/// See the [module docs](self) for more details.
+#[derive(Debug, Clone, Default)]
+pub struct CircuitBreakerLayer {
+    state: Arc<CircuitBreakerState>,
+    config: CircuitBreakerConfig,
+}
+
+impl CircuitBreakerLayer {
+    /// Create a new [`CircuitBreakerLayer`] using the default configuration.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a new [`CircuitBreakerLayer`] using the given (shared) state.
+    pub fn new_with_state(state: Arc<CircuitBreakerState>) -> Self {
+        Self {
+            state,
+            config: CircuitBreakerConfig::default(),
+        }
+    }
+
+    impl_circuit_breaker_config_setters!();
+}
+
+impl<S> Layer<S> for CircuitBreakerLayer {
+    type Service = CircuitBreaker<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        CircuitBreaker {
+            inner,
+            state: self.state.clone(),
+            config: self.config,
+        }
+    }
+
+    fn into_layer(self, inner: S) -> Self::Service {
+        CircuitBreaker {
+            inner,
+            state: self.state,
+            config: self.config,
+        }
+    }
+}
+
+/// Middleware which rejects requests without calling the inner service
+/// once that service failed too many times in a row.
+///
+/// Errors returned by the inner service and responses
+/// with a `5xx` status code are considered failures.
+///
+/// See the [module docs](self) for more details.
+pub struct CircuitBreaker<S> {
+    inner: S,
+    state: Arc<CircuitBreakerState>,
+    config: CircuitBreakerConfig,
+}
+
+impl<S> CircuitBreaker<S> {
+    /// Create a new [`CircuitBreaker`] using the default configuration.
+    pub fn new(inner: S) -> Self {
+        Self {
+            inner,
+            state: Default::default(),
+            config: CircuitBreakerConfig::default(),
+        }
+    }
+
+    define_inner_service_accessors!();
+
+    impl_circuit_breaker_config_setters!();
+}
+
+impl<S: fmt::Debug> fmt::Debug for CircuitBreaker<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CircuitBreaker")
+            .field("inner", &self.inner)
+            .field("state", &self.state)
+            .field("config", &self.config)
+            .finish()
+    }
+}
+
+impl<S: Clone> Clone for CircuitBreaker<S> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            state: self.state.clone(),
+            config: self.config,
+        }
+    }
+}
+
+impl<S, State, ReqBody, ResBody> Service<State, Request<ReqBody>> for CircuitBreaker<S>
+where
+    S: Service<State, Request<ReqBody>, Response = Response<ResBody>>,
+    ReqBody: Send + 'static,
+    ResBody: Default + Send + 'static,
+    State: Clone + Send + Sync + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+
+    async fn serve(
+        &self,
+        ctx: Context<State>,
+        req: Request<ReqBody>,
+    ) -> Result<Self::Response, Self::Error> {
+        let Some(_permit) = self.state.try_acquire(&self.config) else {
+            let mut res = Response::new(ResBody::default());
+            *res.status_mut() = self.config.open_status;
+            return Ok(res);
+        };
+
+        let result = self.inner.serve(ctx, req).await;
+        match &result {
+            Ok(res) if !res.status().is_server_error() => self.state.record_success(&self.config),
+            _ => self.state.record_failure(&self.config),
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::Body;
+    use rama_core::service::service_fn;
+    use std::convert::Infallible;
+    use std::sync::atomic::AtomicBool;
+
+    fn flaky_service(
+        fail: Arc<AtomicBool>,
+    ) -> impl Service<(), Request, Response = Response, Error = Infallible> {
+        service_fn(move |_req: Request| {
+            let status = if fail.load(Ordering::SeqCst) {
+                StatusCode::INTERNAL_SERVER_ERROR
+            } else {
+                StatusCode::OK
+            };
+            std::future::ready(Ok(Response::builder()
+                .status(status)
+                .body(Body::empty())
+                .unwrap()))
+        })
+    }
+
+    async fn status(
+        svc: &impl Service<(), Request, Response = Response, Error = Infallible>,
+    ) -> StatusCode {
+        svc.serve(Context::default(), Request::new(Body::empty()))
+            .await
+            .unwrap()
+            .status()
+    }
+
+    #[tokio::test]
+    async fn circuit_breaker_state_machine() {
+        let fail = Arc::new(AtomicBool::new(true));
+        let layer = CircuitBreakerLayer::new()
+            .with_failure_threshold(2)
+            .with_success_threshold(2)
+            .with_open_duration(Duration::from_millis(50));
+        let state = layer.state().clone();
+        let svc = layer.into_layer(flaky_service(fail.clone()));
+
+        assert_eq!(status(&svc).await, StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(state.status(), CircuitBreakerStatus::Closed);
+        assert_eq!(status(&svc).await, StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(state.status(), CircuitBreakerStatus::Open);
+
+        // open: inner service is not called
+        fail.store(false, Ordering::SeqCst);
+        assert_eq!(status(&svc).await, StatusCode::SERVICE_UNAVAILABLE);
+
+        // half-open: a failed probe opens the circuit again
+        tokio::time::sleep(Duration::from_millis(60)).await;
+        fail.store(true, Ordering::SeqCst);
+        assert_eq!(status(&svc).await, StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(state.status(), CircuitBreakerStatus::Open);
+
+        // half-open: successful probes close the circuit
+        tokio::time::sleep(Duration::from_millis(60)).await;
+        fail.store(false, Ordering::SeqCst);
+        assert_eq!(status(&svc).await, StatusCode::OK);
+        assert_eq!(state.status(), CircuitBreakerStatus::HalfOpen);
+        assert_eq!(status(&svc).await, StatusCode::OK);
+        assert_eq!(state.status(), CircuitBreakerStatus::Closed);
+    }
+
+    #[tokio::test]
+    async fn circuit_breaker_success_resets_failures() {
+        let fail = Arc::new(AtomicBool::new(true));
+        let svc = CircuitBreaker::new(flaky_service(fail.clone()))
+            .with_failure_threshold(2)
+            .with_open_status(StatusCode::TOO_MANY_REQUESTS);
+
+        assert_eq!(status(&svc).await, StatusCode::INTERNAL_SERVER_ERROR);
+        fail.store(false, Ordering::SeqCst);
+        assert_eq!(status(&svc).await, StatusCode::OK);
+        fail.store(true, Ordering::SeqCst);
+        assert_eq!(status(&svc).await, StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(svc.state().status(), CircuitBreakerStatus::Closed);
+        assert_eq!(status(&svc).await, StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(svc.state().status(), CircuitBreakerStatus::Open);
+        assert_eq!(status(&svc).await, StatusCode::TOO_MANY_REQUESTS);
+    }
+
+    #[tokio::test]
+    async fn circuit_breaker_failure_rate() {
+        let fail = Arc::new(AtomicBool::new(false));
+        let svc = CircuitBreaker::new(flaky_service(fail.clone()))
+            .with_failure_threshold(100)
+            .with_failure_rate_threshold(0.5)
+            .with_minimum_requests(4);
+
+        // minimum amount of requests not yet reached
+        assert_eq!(status(&svc).await, StatusCode::OK);
+        fail.store(true, Ordering::SeqCst);
+        assert_eq!(status(&svc).await, StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(status(&svc).await, StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(svc.state().status(), CircuitBreakerStatus::Closed);
+
+        // 2 out of 4 requests failed
+        fail.store(false, Ordering::SeqCst);
+        assert_eq!(status(&svc).await, StatusCode::OK);
+        assert_eq!(svc.state().status(), CircuitBreakerStatus::Closed);
+        fail.store(true, Ordering::SeqCst);
+        assert_eq!(status(&svc).await, StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(svc.state().status(), CircuitBreakerStatus::Open);
+    }
+
+    #[tokio::test]
+    async fn circuit_breaker_half_open_max_probes() {
+        let fail = Arc::new(AtomicBool::new(true));
+        let release = Arc::new(tokio::sync::Semaphore::new(0));
+        let svc = Arc::new(
+            CircuitBreaker::new(service_fn({
+                let fail = fail.clone();
+                let release = release.clone();
+                move |_req: Request| {
+                    let fail = fail.load(Ordering::SeqCst);
+                    let release = release.clone();
+                    async move {
+                        let status = if fail {
+                            StatusCode::INTERNAL_SERVER_ERROR
+                        } else {
+                            release.acquire().await.unwrap().forget();
+                            StatusCode::OK
+                        };
+                        Ok::<_, Infallible>(
+                            Response::builder()
+                                .status(status)
+                                .body(Body::empty())
+                                .unwrap(),
+                        )
+                    }
+                }
+            }))
+            .with_failure_threshold(1)
+            .with_success_threshold(2)
+            .with_open_duration(Duration::from_millis(10)),
+        );
+
+        assert_eq!(status(&*svc).await, StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(svc.state().status(), CircuitBreakerStatus::Open);
+        tokio::time::sleep(Duration::from_millis(20)).await;
+
+        // a single probe is allowed, other requests are rejected until it resolves
+        fail.store(false, Ordering::SeqCst);
+        let probe = tokio::spawn({
+            let svc = svc.clone();
+            async move { status(&*svc).await }
+        });
+        while svc.state().status() != CircuitBreakerStatus::HalfOpen {
+            tokio::task::yield_now().await;
+        }
+        assert_eq!(status(&*svc).await, StatusCode::SERVICE_UNAVAILABLE);
+
+        release.add_permits(2);
+        assert_eq!(probe.await.unwrap(), StatusCode::OK);
+        assert_eq!(svc.state().status(), CircuitBreakerStatus::HalfOpen);
+        assert_eq!(status(&*svc).await, StatusCode::OK);
+        assert_eq!(svc.state().status(), CircuitBreakerStatus::Closed);
+    }
+}
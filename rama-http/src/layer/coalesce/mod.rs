@@ -0,0 +1,37 @@
+//! Middleware which coalesces identical concurrent requests.
+//!
+//! When many identical requests arrive at the same time, for example due to
+//! a cache miss storm, the [`RequestCoalescingLayer`] only sends the first of them
+//! to the inner service. The others wait for its response, which is buffered and
+//! then returned to all of them.
+//!
+//! Requests are identical when they have the same method, URI and `Host`, `Accept`,
+//! `Accept-Encoding` and `Accept-Language` headers. Other headers are not taken into account,
+//! so this layer should not be used for resources that vary per client otherwise.
+//! Only safe `GET` and `HEAD` requests are coalesced, and requests with credentials
+//! (`Authorization`, `Proxy-Authorization` or `Cookie` headers) are always sent on their own.
+//!
+//! Waiting requests are sent on their own after the configured timeout,
+//! when the request they waited for was cancelled, when its response is private
+//! (it sets a cookie or has a `Cache-Control: private` directive), or when its response body
+//! is larger than the configured maximum body size (1 MiB by default).
+//! In the last two cases the response is streamed to the leading request only.
+//!
+//! # Example
+//!
+//! ```
+//! use rama_http::layer::coalesce::RequestCoalescingLayer;
+//! use rama_http::{Body, Request, Response};
+//! use rama_core::{Layer, service::service_fn};
+//! use std::{convert::Infallible, time::Duration};
+//!
+//! let svc = RequestCoalescingLayer::new()
+//!     .with_timeout(Duration::from_secs(5))
+//!     .into_layer(service_fn(async |_req: Request| {
+//!         Ok::<_, Infallible>(Response::new(Body::empty()))
+//!     }));
+//! ```
+
+mod service;
+#[doc(inline)]
+pub use service::{RequestCoalescingLayer, RequestCoalescingService};
@@ -0,0 +1,549 @@
+use crate::body::{LimitedBody, collect_limited};
+use crate::dep::http_body::Body as HttpBody;
+use crate::headers::{CacheControl, HeaderMapExt};
+use crate::{Body, HeaderMap, HeaderValue, Method, Request, Response, Uri, header};
+use parking_lot::Mutex;
+use rama_core::bytes::Bytes;
+use rama_core::error::{BoxError, OpaqueError};
+use rama_core::telemetry::tracing;
+use rama_core::{Context, Layer, Service};
+use rama_utils::macros::{define_inner_service_accessors, generate_set_and_with};
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::broadcast;
+
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+const DEFAULT_MAX_BODY_SIZE: usize = 1024 * 1024;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct CoalescingKey {
+    method: Method,
+    uri: Uri,
+    host: Option<HeaderValue>,
+    accept: Option<HeaderValue>,
+    accept_encoding: Option<HeaderValue>,
+    accept_language: Option<HeaderValue>,
+}
+
+#[derive(Debug, Clone)]
+enum CoalescedResult {
+    Response(Response<Bytes>),
+    Error(Arc<str>),
+    /// The response is private or its body was too large to be buffered,
+    /// waiting requests have to be sent on their own.
+    Uncoalesced,
+}
+
+type InFlight = Arc<Mutex<HashMap<CoalescingKey, broadcast::Sender<CoalescedResult>>>>;
+
+/// Layer that applies the [`RequestCoalescingService`] middleware.
+///
+/// See the [module docs](super) for more details.
+#[derive(Debug, Clone)]
+pub struct RequestCoalescingLayer {
+    timeout: Duration,
+    max_body_size: usize,
+}
+
+impl RequestCoalescingLayer {
+    /// Create a new [`RequestCoalescingLayer`],
+    /// abandoning coalescing after 30 seconds.
+    pub const fn new() -> Self {
+        Self {
+            timeout: DEFAULT_TIMEOUT,
+            max_body_size: DEFAULT_MAX_BODY_SIZE,
+        }
+    }
+
+    generate_set_and_with! {
+        /// Set the time to wait for the response of a coalesced request,
+        /// after which the request is sent on its own instead.
+        pub fn timeout(mut self, timeout: Duration) -> Self {
+            self.timeout = timeout;
+            self
+        }
+    }
+
+    generate_set_and_with! {
+        /// Set the maximum size of a response body which is buffered
+        /// to be shared with coalesced requests, by default this is 1 MiB.
+        ///
+        /// Larger responses are streamed to the leading request only,
+        /// the waiting requests are then sent on their own.
+        pub fn max_body_size(mut self, size: usize) -> Self {
+            self.max_body_size = size;
+            self
+        }
+    }
+}
+
+impl Default for RequestCoalescingLayer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<S> Layer<S> for RequestCoalescingLayer {
+    type Service = RequestCoalescingService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        RequestCoalescingService {
+            inner,
+            timeout: self.timeout,
+            max_body_size: self.max_body_size,
+            in_flight: Default::default(),
+        }
+    }
+}
+
+/// Middleware which coalesces identical concurrent `GET` and `HEAD` requests
+/// into a single request to the inner service.
+///
+/// See the [module docs](super) for more details.
+pub struct RequestCoalescingService<S> {
+    inner: S,
+    timeout: Duration,
+    max_body_size: usize,
+    in_flight: InFlight,
+}
+
+impl<S> RequestCoalescingService<S> {
+    /// Create a new [`RequestCoalescingService`],
+    /// abandoning coalescing after 30 seconds.
+    pub fn new(inner: S) -> Self {
+        RequestCoalescingLayer::new().into_layer(inner)
+    }
+
+    generate_set_and_with! {
+        /// Set the time to wait for the response of a coalesced request,
+        /// after which the request is sent on its own instead.
+        pub fn timeout(mut self, timeout: Duration) -> Self {
+            self.timeout = timeout;
+            self
+        }
+    }
+
+    generate_set_and_with! {
+        /// Set the maximum size of a response body which is buffered
+        /// to be shared with coalesced requests, by default this is 1 MiB.
+        ///
+        /// Larger responses are streamed to the leading request only,
+        /// the waiting requests are then sent on their own.
+        pub fn max_body_size(mut self, size: usize) -> Self {
+            self.max_body_size = size;
+            self
+        }
+    }
+
+    define_inner_service_accessors!();
+}
+
+impl<S: fmt::Debug> fmt::Debug for RequestCoalescingService<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RequestCoalescingService")
+            .field("inner", &self.inner)
+            .field("timeout", &self.timeout)
+            .field("max_body_size", &self.max_body_size)
+            .finish()
+    }
+}
+
+impl<S: Clone> Clone for RequestCoalescingService<S> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            timeout: self.timeout,
+            max_body_size: self.max_body_size,
+            in_flight: self.in_flight.clone(),
+        }
+    }
+}
+
+/// Removes the in-flight entry once the leading request
+/// is completed or cancelled.
+struct InFlightGuard<'a> {
+    in_flight: &'a InFlight,
+    key: CoalescingKey,
+    sender: broadcast::Sender<CoalescedResult>,
+}
+
+impl Drop for InFlightGuard<'_> {
+    fn drop(&mut self) {
+        let mut in_flight = self.in_flight.lock();
+        if in_flight
+            .get(&self.key)
+            .map(|sender| sender.same_channel(&self.sender))
+            .unwrap_or_default()
+        {
+            in_flight.remove(&self.key);
+        }
+    }
+}
+
+impl<State, S, ReqBody, ResBody> Service<State, Request<ReqBody>> for RequestCoalescingService<S>
+where
+    State: Clone + Send + Sync + 'static,
+    S: Service<State, Request<ReqBody>, Response = Response<ResBody>, Error: Into<BoxError>>,
+    ReqBody: Send + 'static,
+    ResBody: HttpBody<Data = Bytes, Error: Into<BoxError>> + Send + Sync + 'static,
+{
+    type Response = Response;
+    type Error = BoxError;
+
+    async fn serve(
+        &self,
+        ctx: Context<State>,
+        req: Request<ReqBody>,
+    ) -> Result<Self::Response, Self::Error> {
+        if (req.method() != Method::GET && req.method() != Method::HEAD)
+            || has_credentials(req.headers())
+        {
+            return self.serve_uncoalesced(ctx, req).await;
+        }
+
+        let key = CoalescingKey {
+            method: req.method().clone(),
+            uri: req.uri().clone(),
+            host: req.headers().get(header::HOST).cloned(),
+            accept: req.headers().get(header::ACCEPT).cloned(),
+            accept_encoding: req.headers().get(header::ACCEPT_ENCODING).cloned(),
+            accept_language: req.headers().get(header::ACCEPT_LANGUAGE).cloned(),
+        };
+
+        let receiver = {
+            let mut in_flight = self.in_flight.lock();
+            match in_flight.get(&key) {
+                Some(sender) => Err(sender.subscribe()),
+                None => {
+                    let (sender, _) = broadcast::channel(1);
+                    in_flight.insert(key.clone(), sender.clone());
+                    Ok(sender)
+                }
+            }
+        };
+
+        let sender = match receiver {
+            Ok(sender) => sender,
+            Err(mut receiver) => {
+                match tokio::time::timeout(self.timeout, receiver.recv()).await {
+                    Ok(Ok(CoalescedResult::Response(res))) => return Ok(res.map(Body::from)),
+                    Ok(Ok(CoalescedResult::Error(err))) => {
+                        return Err(OpaqueError::from_display(format!(
+                            "coalesced request failed: {err}"
+                        ))
+                        .into_boxed());
+                    }
+                    Ok(Ok(CoalescedResult::Uncoalesced)) => tracing::debug!(
+                        http.request.method = %key.method,
+                        url.full = %key.uri,
+                        "coalesced response not shared: send request on its own",
+                    ),
+                    Ok(Err(_)) => tracing::debug!(
+                        http.request.method = %key.method,
+                        url.full = %key.uri,
+                        "coalesced request was cancelled: send request on its own",
+                    ),
+                    Err(_) => tracing::debug!(
+                        http.request.method = %key.method,
+                        url.full = %key.uri,
+                        "coalesced request timed out: send request on its own",
+                    ),
+                }
+                return self.serve_uncoalesced(ctx, req).await;
+            }
+        };
+
+        let guard = InFlightGuard {
+            in_flight: &self.in_flight,
+            key,
+            sender: sender.clone(),
+        };
+
+        let result = match self.inner.serve(ctx, req).await {
+            Ok(res) if is_private(res.headers()) => {
+                drop(guard);
+                let _ = sender.send(CoalescedResult::Uncoalesced);
+                return Ok(res.map(Body::new));
+            }
+            Ok(res) => {
+                let (parts, body) = res.into_parts();
+                match collect_limited(body, self.max_body_size).await {
+                    Ok(LimitedBody::Collected(bytes)) => Ok(Response::from_parts(parts, bytes)),
+                    Ok(LimitedBody::Exceeded(body)) => {
+                        drop(guard);
+                        let _ = sender.send(CoalescedResult::Uncoalesced);
+                        return Ok(Response::from_parts(parts, body));
+                    }
+                    Err(err) => Err(err.into_boxed()),
+                }
+            }
+            Err(err) => Err(err.into()),
+        };
+
+        // no longer coalesce new requests with this one, as the response is known
+        drop(guard);
+        let _ = sender.send(match &result {
+            Ok(res) => CoalescedResult::Response(res.clone()),
+            Err(err) => CoalescedResult::Error(err.to_string().into()),
+        });
+
+        result.map(|res| res.map(Body::from))
+    }
+}
+
+fn has_credentials(headers: &HeaderMap) -> bool {
+    [
+        header::AUTHORIZATION,
+        header::PROXY_AUTHORIZATION,
+        header::COOKIE,
+    ]
+    .into_iter()
+    .any(|name| headers.contains_key(name))
+}
+
+/// Returns `true` if the response is meant for a single client only.
+fn is_private(headers: &HeaderMap) -> bool {
+    headers.contains_key(header::SET_COOKIE)
+        || headers
+            .typed_get::<CacheControl>()
+            .is_some_and(|cache_control| cache_control.private())
+}
+
+impl<S> RequestCoalescingService<S> {
+    async fn serve_uncoalesced<State, ReqBody, ResBody>(
+        &self,
+        ctx: Context<State>,
+        req: Request<ReqBody>,
+    ) -> Result<Response, BoxError>
+    where
+        State: Clone + Send + Sync + 'static,
+        S: Service<State, Request<ReqBody>, Response = Response<ResBody>, Error: Into<BoxError>>,
+        ReqBody: Send + 'static,
+        ResBody: HttpBody<Data = Bytes, Error: Into<BoxError>> + Send + Sync + 'static,
+    {
+        match self.inner.serve(ctx, req).await {
+            Ok(res) => Ok(res.map(Body::new)),
+            Err(err) => Err(err.into()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::BodyExtractExt;
+    use crate::service::web::response::IntoResponse;
+    use rama_core::futures::future::join_all;
+    use rama_core::service::service_fn;
+    use std::convert::Infallible;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    fn slow_service(
+        delay: Duration,
+        counter: Arc<AtomicUsize>,
+    ) -> impl Service<(), Request, Response = Response, Error = Infallible> {
+        service_fn(move |req: Request| {
+            let counter = counter.clone();
+            async move {
+                let n = counter.fetch_add(1, Ordering::SeqCst);
+                tokio::time::sleep(delay).await;
+                Ok(format!("{} {}: {n}", req.method(), req.uri().path()).into_response())
+            }
+        })
+    }
+
+    fn request(method: Method, path: &str) -> Request {
+        Request::builder()
+            .method(method)
+            .uri(path)
+            .body(Body::empty())
+            .unwrap()
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_identical_requests_coalesced() {
+        let counter = Arc::new(AtomicUsize::new(0));
+        let svc = RequestCoalescingLayer::new()
+            .into_layer(slow_service(Duration::from_millis(100), counter.clone()));
+
+        let responses =
+            join_all((0..5).map(|_| svc.serve(Context::default(), request(Method::GET, "/a"))))
+                .await;
+        for res in responses {
+            assert_eq!(res.unwrap().try_into_string().await.unwrap(), "GET /a: 0");
+        }
+        assert_eq!(counter.load(Ordering::SeqCst), 1);
+
+        // completed requests are no longer coalesced
+        let res = svc
+            .serve(Context::default(), request(Method::GET, "/a"))
+            .await
+            .unwrap();
+        assert_eq!(res.try_into_string().await.unwrap(), "GET /a: 1");
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_different_and_unsafe_requests_not_coalesced() {
+        let counter = Arc::new(AtomicUsize::new(0));
+        let svc = RequestCoalescingLayer::new()
+            .into_layer(slow_service(Duration::from_millis(100), counter.clone()));
+
+        let responses = join_all([
+            svc.serve(Context::default(), request(Method::GET, "/a")),
+            svc.serve(Context::default(), request(Method::GET, "/b")),
+            svc.serve(Context::default(), request(Method::HEAD, "/a")),
+            svc.serve(Context::default(), request(Method::POST, "/a")),
+            svc.serve(Context::default(), request(Method::POST, "/a")),
+        ])
+        .await;
+        assert!(responses.iter().all(Result::is_ok));
+        assert_eq!(counter.load(Ordering::SeqCst), 5);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_coalescing_abandoned_after_timeout() {
+        let counter = Arc::new(AtomicUsize::new(0));
+        let svc = RequestCoalescingLayer::new()
+            .with_timeout(Duration::from_millis(50))
+            .into_layer(slow_service(Duration::from_millis(100), counter.clone()));
+
+        let responses =
+            join_all((0..3).map(|_| svc.serve(Context::default(), request(Method::GET, "/a"))))
+                .await;
+        assert!(responses.iter().all(Result::is_ok));
+        // the leading request and both abandoned ones
+        assert_eq!(counter.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_credentials_and_other_hosts_not_coalesced() {
+        let counter = Arc::new(AtomicUsize::new(0));
+        let svc = RequestCoalescingLayer::new()
+            .into_layer(slow_service(Duration::from_millis(100), counter.clone()));
+
+        let with_header = |name, value| {
+            let mut req = request(Method::GET, "/a");
+            req.headers_mut()
+                .insert(name, HeaderValue::from_static(value));
+            req
+        };
+
+        let responses = join_all([
+            svc.serve(Context::default(), with_header(header::HOST, "a.example")),
+            svc.serve(Context::default(), with_header(header::HOST, "b.example")),
+            svc.serve(
+                Context::default(),
+                with_header(header::AUTHORIZATION, "Bearer token"),
+            ),
+            svc.serve(
+                Context::default(),
+                with_header(header::AUTHORIZATION, "Bearer token"),
+            ),
+            svc.serve(Context::default(), with_header(header::COOKIE, "a=b")),
+            svc.serve(Context::default(), with_header(header::COOKIE, "a=b")),
+        ])
+        .await;
+        assert!(responses.iter().all(Result::is_ok));
+        assert_eq!(counter.load(Ordering::SeqCst), 6);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_other_negotiation_headers_not_coalesced() {
+        let counter = Arc::new(AtomicUsize::new(0));
+        let svc = RequestCoalescingLayer::new()
+            .into_layer(slow_service(Duration::from_millis(100), counter.clone()));
+
+        let with_header = |name, value| {
+            let mut req = request(Method::GET, "/a");
+            req.headers_mut()
+                .insert(name, HeaderValue::from_static(value));
+            req
+        };
+
+        let responses = join_all([
+            svc.serve(Context::default(), with_header(header::ACCEPT, "text/html")),
+            svc.serve(Context::default(), with_header(header::ACCEPT, "text/html")),
+            svc.serve(
+                Context::default(),
+                with_header(header::ACCEPT, "application/json"),
+            ),
+            svc.serve(
+                Context::default(),
+                with_header(header::ACCEPT_ENCODING, "gzip"),
+            ),
+            svc.serve(
+                Context::default(),
+                with_header(header::ACCEPT_ENCODING, "br"),
+            ),
+            svc.serve(
+                Context::default(),
+                with_header(header::ACCEPT_LANGUAGE, "en"),
+            ),
+            svc.serve(
+                Context::default(),
+                with_header(header::ACCEPT_LANGUAGE, "nl"),
+            ),
+        ])
+        .await;
+        assert!(responses.iter().all(Result::is_ok));
+        // only the two identical text/html requests are coalesced
+        assert_eq!(counter.load(Ordering::SeqCst), 6);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_private_responses_not_shared() {
+        for (name, value) in [
+            (header::SET_COOKIE, "sid=42"),
+            (header::CACHE_CONTROL, "private, max-age=60"),
+        ] {
+            let counter = Arc::new(AtomicUsize::new(0));
+            let svc = RequestCoalescingLayer::new().into_layer(service_fn({
+                let counter = counter.clone();
+                move |_req: Request| {
+                    let counter = counter.clone();
+                    let value = HeaderValue::from_static(value);
+                    let name = name.clone();
+                    async move {
+                        let n = counter.fetch_add(1, Ordering::SeqCst);
+                        tokio::time::sleep(Duration::from_millis(100)).await;
+                        let mut res = n.to_string().into_response();
+                        res.headers_mut().insert(name, value);
+                        Ok::<_, Infallible>(res)
+                    }
+                }
+            }));
+
+            let responses =
+                join_all((0..3).map(|_| svc.serve(Context::default(), request(Method::GET, "/a"))))
+                    .await;
+            let mut bodies = Vec::new();
+            for res in responses {
+                bodies.push(res.unwrap().try_into_string().await.unwrap());
+            }
+            bodies.sort();
+            assert_eq!(bodies, ["0", "1", "2"]);
+            assert_eq!(counter.load(Ordering::SeqCst), 3);
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_large_response_not_shared() {
+        let counter = Arc::new(AtomicUsize::new(0));
+        let svc = RequestCoalescingLayer::new()
+            .with_max_body_size(4)
+            .into_layer(slow_service(Duration::from_millis(100), counter.clone()));
+
+        let responses =
+            join_all((0..3).map(|_| svc.serve(Context::default(), request(Method::GET, "/a"))))
+                .await;
+        let mut bodies = Vec::new();
+        for res in responses {
+            bodies.push(res.unwrap().try_into_string().await.unwrap());
+        }
+        bodies.sort();
+        assert_eq!(bodies, ["GET /a: 0", "GET /a: 1", "GET /a: 2"]);
+        assert_eq!(counter.load(Ordering::SeqCst), 3);
+    }
+}
@@ -1,4 +1,5 @@
 use super::predicate::DefaultPredicate;
+use super::service::CompressionQuality;
 use super::{Compression, Predicate};
 use crate::headers::encoding::AcceptEncoding;
 use crate::layer::util::compression::CompressionLevel;
@@ -14,7 +15,7 @@ use rama_core::Layer;
 pub struct CompressionLayer<P = DefaultPredicate> {
     accept: AcceptEncoding,
     predicate: P,
-    quality: CompressionLevel,
+    quality: CompressionQuality,
 }
 
 impl<S, P> Layer<S> for CompressionLayer<P>
@@ -97,14 +98,68 @@ impl CompressionLayer {
     }
 
     /// Sets the compression quality.
+    ///
+    /// This quality is used for all encodings
+    /// for which no specific quality was set.
     pub fn quality(mut self, quality: CompressionLevel) -> Self {
-        self.quality = quality;
+        self.quality.default = quality;
         self
     }
 
     /// Sets the compression quality.
+    ///
+    /// This quality is used for all encodings
+    /// for which no specific quality was set.
     pub fn set_quality(&mut self, quality: CompressionLevel) -> &mut Self {
-        self.quality = quality;
+        self.quality.default = quality;
+        self
+    }
+
+    /// Sets the compression quality used for the gzip encoding.
+    pub fn gzip_quality(mut self, quality: CompressionLevel) -> Self {
+        self.quality.gzip = Some(quality);
+        self
+    }
+
+    /// Sets the compression quality used for the gzip encoding.
+    pub fn set_gzip_quality(&mut self, quality: CompressionLevel) -> &mut Self {
+        self.quality.gzip = Some(quality);
+        self
+    }
+
+    /// Sets the compression quality used for the Deflate encoding.
+    pub fn deflate_quality(mut self, quality: CompressionLevel) -> Self {
+        self.quality.deflate = Some(quality);
+        self
+    }
+
+    /// Sets the compression quality used for the Deflate encoding.
+    pub fn set_deflate_quality(&mut self, quality: CompressionLevel) -> &mut Self {
+        self.quality.deflate = Some(quality);
+        self
+    }
+
+    /// Sets the compression quality used for the Brotli encoding.
+    pub fn br_quality(mut self, quality: CompressionLevel) -> Self {
+        self.quality.br = Some(quality);
+        self
+    }
+
+    /// Sets the compression quality used for the Brotli encoding.
+    pub fn set_br_quality(&mut self, quality: CompressionLevel) -> &mut Self {
+        self.quality.br = Some(quality);
+        self
+    }
+
+    /// Sets the compression quality used for the Zstd encoding.
+    pub fn zstd_quality(mut self, quality: CompressionLevel) -> Self {
+        self.quality.zstd = Some(quality);
+        self
+    }
+
+    /// Sets the compression quality used for the Zstd encoding.
+    pub fn set_zstd_quality(&mut self, quality: CompressionLevel) -> &mut Self {
+        self.quality.zstd = Some(quality);
         self
     }
 
@@ -431,6 +431,55 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn compress_with_encoding_quality() {
+        const DATA: &str = "Check compression quality level! Check compression quality level! Check compression quality level!";
+        let level = CompressionLevel::Best;
+
+        let svc = service_fn(async |_| {
+            let resp = Response::builder()
+                .body(Body::from(DATA.as_bytes()))
+                .unwrap();
+            Ok::<_, std::io::Error>(resp)
+        });
+
+        let svc = Compression::new(svc)
+            .quality(CompressionLevel::Fastest)
+            .br_quality(level);
+
+        // call the service
+        let req = Request::builder()
+            .header("accept-encoding", "br")
+            .body(Body::empty())
+            .unwrap();
+        let res = svc.serve(Context::default(), req).await.unwrap();
+
+        // read the compressed body
+        let body = res.into_body();
+        let compressed_data = body.collect().await.unwrap().to_bytes();
+
+        // build the compressed body with the brotli specific quality level
+        let compressed_with_level = {
+            use async_compression::tokio::bufread::BrotliEncoder;
+
+            let stream = Box::pin(rama_core::futures::stream::once(async {
+                Ok::<_, std::io::Error>(DATA.as_bytes())
+            }));
+            let reader = StreamReader::new(stream);
+            let mut enc = BrotliEncoder::with_quality(reader, level.into_async_compression());
+
+            let mut buf = Vec::new();
+            enc.read_to_end(&mut buf).await.unwrap();
+            buf
+        };
+
+        assert_eq!(
+            compressed_data,
+            compressed_with_level.as_slice(),
+            "Encoding specific compression level is not respected"
+        );
+    }
+
     #[tokio::test]
     async fn should_not_compress_ranges() {
         let svc = service_fn(async |_| {
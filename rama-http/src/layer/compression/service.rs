@@ -21,7 +21,31 @@ pub struct Compression<S, P = DefaultPredicate> {
     pub(crate) inner: S,
     pub(crate) accept: AcceptEncoding,
     pub(crate) predicate: P,
-    pub(crate) quality: CompressionLevel,
+    pub(crate) quality: CompressionQuality,
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+/// Compression quality used by [`Compression`],
+/// optionally overwritten per encoding.
+pub(crate) struct CompressionQuality {
+    pub(crate) default: CompressionLevel,
+    pub(crate) gzip: Option<CompressionLevel>,
+    pub(crate) deflate: Option<CompressionLevel>,
+    pub(crate) br: Option<CompressionLevel>,
+    pub(crate) zstd: Option<CompressionLevel>,
+}
+
+impl CompressionQuality {
+    fn for_encoding(&self, encoding: Encoding) -> CompressionLevel {
+        match encoding {
+            Encoding::Gzip => self.gzip,
+            Encoding::Deflate => self.deflate,
+            Encoding::Brotli => self.br,
+            Encoding::Zstd => self.zstd,
+            Encoding::Identity => None,
+        }
+        .unwrap_or(self.default)
+    }
 }
 
 impl<S, P> std::fmt::Debug for Compression<S, P>
@@ -61,7 +85,7 @@ impl<S> Compression<S, DefaultPredicate> {
             inner: service,
             accept: AcceptEncoding::default(),
             predicate: DefaultPredicate::default(),
-            quality: CompressionLevel::default(),
+            quality: CompressionQuality::default(),
         }
     }
 }
@@ -118,14 +142,68 @@ impl<S, P> Compression<S, P> {
     }
 
     /// Sets the compression quality.
+    ///
+    /// This quality is used for all encodings
+    /// for which no specific quality was set.
     pub fn quality(mut self, quality: CompressionLevel) -> Self {
-        self.quality = quality;
+        self.quality.default = quality;
         self
     }
 
     /// Sets the compression quality.
+    ///
+    /// This quality is used for all encodings
+    /// for which no specific quality was set.
     pub fn set_quality(&mut self, quality: CompressionLevel) -> &mut Self {
-        self.quality = quality;
+        self.quality.default = quality;
+        self
+    }
+
+    /// Sets the compression quality used for the gzip encoding.
+    pub fn gzip_quality(mut self, quality: CompressionLevel) -> Self {
+        self.quality.gzip = Some(quality);
+        self
+    }
+
+    /// Sets the compression quality used for the gzip encoding.
+    pub fn set_gzip_quality(&mut self, quality: CompressionLevel) -> &mut Self {
+        self.quality.gzip = Some(quality);
+        self
+    }
+
+    /// Sets the compression quality used for the Deflate encoding.
+    pub fn deflate_quality(mut self, quality: CompressionLevel) -> Self {
+        self.quality.deflate = Some(quality);
+        self
+    }
+
+    /// Sets the compression quality used for the Deflate encoding.
+    pub fn set_deflate_quality(&mut self, quality: CompressionLevel) -> &mut Self {
+        self.quality.deflate = Some(quality);
+        self
+    }
+
+    /// Sets the compression quality used for the Brotli encoding.
+    pub fn br_quality(mut self, quality: CompressionLevel) -> Self {
+        self.quality.br = Some(quality);
+        self
+    }
+
+    /// Sets the compression quality used for the Brotli encoding.
+    pub fn set_br_quality(&mut self, quality: CompressionLevel) -> &mut Self {
+        self.quality.br = Some(quality);
+        self
+    }
+
+    /// Sets the compression quality used for the Zstd encoding.
+    pub fn zstd_quality(mut self, quality: CompressionLevel) -> Self {
+        self.quality.zstd = Some(quality);
+        self
+    }
+
+    /// Sets the compression quality used for the Zstd encoding.
+    pub fn set_zstd_quality(&mut self, quality: CompressionLevel) -> &mut Self {
+        self.quality.zstd = Some(quality);
         self
     }
 
@@ -219,6 +297,7 @@ where
                 .append(header::VARY, header::ACCEPT_ENCODING.into());
         }
 
+        let quality = self.quality.for_encoding(encoding);
         let body = match (should_compress, encoding) {
             // if compression is _not_ supported or the client doesn't accept it
             (false, _) | (_, Encoding::Identity) => {
@@ -229,16 +308,16 @@ where
             }
 
             (_, Encoding::Gzip) => {
-                CompressionBody::new(BodyInner::gzip(WrapBody::new(body, self.quality)))
+                CompressionBody::new(BodyInner::gzip(WrapBody::new(body, quality)))
             }
             (_, Encoding::Deflate) => {
-                CompressionBody::new(BodyInner::deflate(WrapBody::new(body, self.quality)))
+                CompressionBody::new(BodyInner::deflate(WrapBody::new(body, quality)))
             }
             (_, Encoding::Brotli) => {
-                CompressionBody::new(BodyInner::brotli(WrapBody::new(body, self.quality)))
+                CompressionBody::new(BodyInner::brotli(WrapBody::new(body, quality)))
             }
             (_, Encoding::Zstd) => {
-                CompressionBody::new(BodyInner::zstd(WrapBody::new(body, self.quality)))
+                CompressionBody::new(BodyInner::zstd(WrapBody::new(body, quality)))
             }
             #[allow(unreachable_patterns)]
             (true, _) => {
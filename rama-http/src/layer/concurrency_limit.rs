@@ -0,0 +1,112 @@
+//! Http utilities for limiting the number of concurrent requests.
+//!
+//! Limiting itself is done using the [`Limit`] middleware, e.g. using a
+//! [`ConcurrentQueuePolicy`], which either rejects requests immediately when
+//! the limit is reached or queues them (up to a maximum queue depth) until
+//! a request finishes, depending on its [`OverflowPolicy`]. The current amount
+//! of in-flight and queued requests can be read from its [`ConcurrencyState`],
+//! e.g. to expose it in a health check or as metrics.
+//!
+//! A [`LimitReached`] error can be turned into a `503 Service Unavailable` response
+//! using its [`IntoResponse`] implementation.
+//!
+//! # Example
+//!
+//! ```
+//! use rama_core::layer::limit::policy::{ConcurrentQueuePolicy, LimitReached};
+//! use rama_core::layer::LimitLayer;
+//! use rama_core::service::service_fn;
+//! use rama_core::{Context, Layer, Service};
+//! use rama_http::service::web::response::IntoResponse;
+//! use rama_http::{Body, Request, Response, StatusCode};
+//! use std::convert::Infallible;
+//!
+//! # #[tokio::main]
+//! # async fn main() {
+//! // allow 64 concurrent requests, queueing up to 256 more requests
+//! let policy = ConcurrentQueuePolicy::queue(64, 256);
+//! let state = policy.state();
+//!
+//! let svc = LimitLayer::new(policy)
+//!     .with_error_into_response_fn(|err: LimitReached| Ok::<_, Infallible>(err.into_response()))
+//!     .into_layer(service_fn(async |_req: Request| {
+//!         Ok::<_, Infallible>(Response::new(Body::empty()))
+//!     }));
+//!
+//! let res = svc.serve(Context::default(), Request::new(Body::empty())).await.unwrap();
+//! assert_eq!(res.status(), StatusCode::OK);
+//! assert_eq!(state.in_flight(), 0);
+//! # }
+//! ```
+//!
+//! [`Limit`]: rama_core::layer::Limit
+//! [`ConcurrentQueuePolicy`]: rama_core::layer::limit::policy::ConcurrentQueuePolicy
+//! [`OverflowPolicy`]: rama_core::layer::limit::policy::OverflowPolicy
+//! [`ConcurrencyState`]: rama_core::layer::limit::policy::ConcurrencyState
+
+use crate::service::web::response::IntoResponse;
+use crate::{Response, StatusCode};
+use rama_core::layer::limit::policy::LimitReached;
+
+impl IntoResponse for LimitReached {
+    fn into_response(self) -> Response {
+        StatusCode::SERVICE_UNAVAILABLE.into_response()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{Body, Request};
+    use rama_core::layer::LimitLayer;
+    use rama_core::layer::limit::policy::ConcurrentQueuePolicy;
+    use rama_core::service::service_fn;
+    use rama_core::{Context, Layer, Service};
+    use std::convert::Infallible;
+    use std::sync::Arc;
+    use tokio::sync::Notify;
+
+    #[tokio::test]
+    async fn concurrency_limit_rejects_with_service_unavailable() {
+        let release = Arc::new(Notify::new());
+        let svc_release = release.clone();
+        let policy = ConcurrentQueuePolicy::reject(1);
+        let state = policy.state();
+
+        let svc = LimitLayer::new(policy)
+            .with_error_into_response_fn(|err: LimitReached| {
+                Ok::<_, Infallible>(err.into_response())
+            })
+            .into_layer(service_fn(move |_req: Request| {
+                let release = svc_release.clone();
+                async move {
+                    release.notified().await;
+                    Ok::<_, Infallible>(Response::new(Body::empty()))
+                }
+            }));
+        let svc = Arc::new(svc);
+
+        let in_flight = tokio::spawn({
+            let svc = svc.clone();
+            async move {
+                svc.serve(Context::default(), Request::new(Body::empty()))
+                    .await
+                    .unwrap()
+            }
+        });
+        while state.in_flight() == 0 {
+            tokio::task::yield_now().await;
+        }
+
+        let res = svc
+            .serve(Context::default(), Request::new(Body::empty()))
+            .await
+            .unwrap();
+        assert_eq!(res.status(), StatusCode::SERVICE_UNAVAILABLE);
+
+        release.notify_one();
+        assert_eq!(in_flight.await.unwrap().status(), StatusCode::OK);
+        assert_eq!(state.in_flight(), 0);
+    }
+}
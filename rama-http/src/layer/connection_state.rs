@@ -0,0 +1,207 @@
+//! Middleware that injects per-connection state into the [`Context`]
+//! of every request served on that connection.
+//!
+//! The [`ConnectionStateLayer`] is to be applied on the service which serves
+//! the (transport) connection, e.g. the http server service which is served by
+//! a tcp listener. The value is computed only once, when the connection is accepted,
+//! from the [`Context`] and stream of that connection. As the http server clones
+//! that [`Context`] for each request it serves, the value is shared across all
+//! requests on the connection, be it HTTP/1.1 keep-alive or HTTP/2 streams.
+//!
+//! This is distinct from per-request state such as added by the
+//! [`AddExtensionLayer`], which is (re)inserted for each request.
+//!
+//! [`AddExtensionLayer`]: rama_core::layer::AddExtensionLayer
+//!
+//! # Example
+//!
+//! ```
+//! use rama_core::{Context, Layer, Service, service::service_fn};
+//! use rama_http::layer::connection_state::ConnectionStateLayer;
+//! use rama_http::{Body, Request, Response};
+//! use rama_net::stream::SocketInfo;
+//! use std::{convert::Infallible, net::SocketAddr};
+//!
+//! #[derive(Debug, Clone)]
+//! struct PeerAddr(SocketAddr);
+//!
+//! async fn handle(ctx: Context<()>, _req: Request) -> Result<Response, Infallible> {
+//!     let peer = ctx.get::<PeerAddr>().unwrap();
+//!     Ok(Response::new(Body::from(peer.0.to_string())))
+//! }
+//!
+//! # fn serve_http<S>(_: S) -> impl Service<(), tokio::net::TcpStream> {
+//! #     service_fn(async |_: tokio::net::TcpStream| Ok::<_, Infallible>(()))
+//! # }
+//! // the closure is called once per accepted connection
+//! let connection_svc = ConnectionStateLayer::new(|ctx: &Context<()>, _stream: &tokio::net::TcpStream| {
+//!     ctx.get::<SocketInfo>().map(|info| PeerAddr(*info.peer_addr()))
+//! })
+//! .into_layer(serve_http(service_fn(handle)));
+//! ```
+
+use rama_core::{Context, Layer, Service};
+use rama_utils::macros::define_inner_service_accessors;
+use std::fmt;
+
+/// [`Layer`] which injects per-connection state into the [`Context`].
+///
+/// See the [module docs](self) for more details.
+pub struct ConnectionStateLayer<F> {
+    f: F,
+}
+
+impl<F: fmt::Debug> fmt::Debug for ConnectionStateLayer<F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ConnectionStateLayer")
+            .field("f", &self.f)
+            .finish()
+    }
+}
+
+impl<F: Clone> Clone for ConnectionStateLayer<F> {
+    fn clone(&self) -> Self {
+        Self { f: self.f.clone() }
+    }
+}
+
+impl<F> ConnectionStateLayer<F> {
+    /// Create a new [`ConnectionStateLayer`].
+    ///
+    /// The given function is called once per connection, with its [`Context`]
+    /// and stream, and the returned value (if any) is inserted into the [`Context`].
+    pub const fn new(f: F) -> Self {
+        Self { f }
+    }
+}
+
+impl<S, F: Clone> Layer<S> for ConnectionStateLayer<F> {
+    type Service = ConnectionState<S, F>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        ConnectionState::new(inner, self.f.clone())
+    }
+
+    fn into_layer(self, inner: S) -> Self::Service {
+        ConnectionState::new(inner, self.f)
+    }
+}
+
+/// Middleware which injects per-connection state into the [`Context`].
+///
+/// See the [module docs](self) for more details.
+pub struct ConnectionState<S, F> {
+    inner: S,
+    f: F,
+}
+
+impl<S: fmt::Debug, F: fmt::Debug> fmt::Debug for ConnectionState<S, F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ConnectionState")
+            .field("inner", &self.inner)
+            .field("f", &self.f)
+            .finish()
+    }
+}
+
+impl<S: Clone, F: Clone> Clone for ConnectionState<S, F> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            f: self.f.clone(),
+        }
+    }
+}
+
+impl<S, F> ConnectionState<S, F> {
+    /// Create a new [`ConnectionState`] middleware.
+    ///
+    /// The given function is called once per connection, with its [`Context`]
+    /// and stream, and the returned value (if any) is inserted into the [`Context`].
+    pub const fn new(inner: S, f: F) -> Self {
+        Self { inner, f }
+    }
+
+    define_inner_service_accessors!();
+}
+
+impl<State, IO, S, T, F> Service<State, IO> for ConnectionState<S, F>
+where
+    State: Clone + Send + Sync + 'static,
+    IO: Send + 'static,
+    S: Service<State, IO>,
+    T: Clone + Send + Sync + 'static,
+    F: Fn(&Context<State>, &IO) -> Option<T> + Send + Sync + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+
+    fn serve(
+        &self,
+        mut ctx: Context<State>,
+        stream: IO,
+    ) -> impl Future<Output = Result<Self::Response, Self::Error>> + Send + '_ {
+        if let Some(value) = (self.f)(&ctx, &stream) {
+            ctx.insert(value);
+        }
+        self.inner.serve(ctx, stream)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Body, Request, Response};
+    use rama_core::service::service_fn;
+    use std::{
+        convert::Infallible,
+        sync::{
+            Arc,
+            atomic::{AtomicUsize, Ordering},
+        },
+    };
+
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    struct ConnectionId(usize);
+
+    /// Mock connection, carrying the requests served on it.
+    struct MockConnection(Vec<Request>);
+
+    #[tokio::test]
+    async fn test_connection_state_shared_by_requests() {
+        let connections = Arc::new(AtomicUsize::new(0));
+
+        let http_svc = service_fn(async |ctx: Context<()>, _req: Request| {
+            let id = ctx.get::<ConnectionId>().unwrap().0;
+            Ok::<_, Infallible>(Response::new(Body::from(id.to_string())))
+        });
+        // serves all requests on the connection using a clone of the connection context,
+        // as is done by the http server
+        let conn_svc = service_fn(move |ctx: Context<()>, conn: MockConnection| {
+            let http_svc = http_svc.clone();
+            async move {
+                let mut ids = Vec::new();
+                for req in conn.0 {
+                    let res = http_svc.serve(ctx.clone(), req).await?;
+                    ids.push(crate::BodyExtractExt::try_into_string(res).await.unwrap());
+                }
+                Ok::<_, Infallible>(ids)
+            }
+        });
+
+        let svc = ConnectionStateLayer::new({
+            let connections = connections.clone();
+            move |_ctx: &Context<()>, _conn: &MockConnection| {
+                Some(ConnectionId(connections.fetch_add(1, Ordering::SeqCst)))
+            }
+        })
+        .into_layer(conn_svc);
+
+        for expected in ["0", "1"] {
+            let conn = MockConnection(vec![Request::default(), Request::default()]);
+            let ids = svc.serve(Context::default(), conn).await.unwrap();
+            assert_eq!(ids, vec![expected, expected]);
+        }
+        assert_eq!(connections.load(Ordering::SeqCst), 2);
+    }
+}
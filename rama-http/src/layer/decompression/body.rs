@@ -29,6 +29,7 @@ pin_project! {
     {
         #[pin]
         pub(crate) inner: BodyInner<B>,
+        remaining: Option<usize>,
     }
 }
 
@@ -41,6 +42,7 @@ where
             inner: BodyInner::Identity {
                 inner: B::default(),
             },
+            remaining: None,
         }
     }
 }
@@ -50,7 +52,17 @@ where
     B: Body,
 {
     pub(crate) fn new(inner: BodyInner<B>) -> Self {
-        Self { inner }
+        Self {
+            inner,
+            remaining: None,
+        }
+    }
+
+    /// Limit the amount of decompressed bytes this body is allowed to produce,
+    /// erroring once more data is produced.
+    pub(crate) fn with_limit(mut self, limit: usize) -> Self {
+        self.remaining = Some(limit);
+        self
     }
 }
 
@@ -121,7 +133,8 @@ where
         self: Pin<&mut Self>,
         cx: &mut Context<'_>,
     ) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
-        match self.project().inner.project() {
+        let this = self.project();
+        let result = ready!(match this.inner.project() {
             BodyInnerProj::Gzip { inner } => inner.poll_frame(cx),
             BodyInnerProj::Deflate { inner } => inner.poll_frame(cx),
             BodyInnerProj::Brotli { inner } => inner.poll_frame(cx),
@@ -134,7 +147,20 @@ where
                 Some(Err(err)) => Poll::Ready(Some(Err(err.into()))),
                 None => Poll::Ready(None),
             },
+        });
+
+        if let (Some(remaining), Some(Ok(frame))) = (this.remaining.as_mut(), result.as_ref()) {
+            if let Some(data) = frame.data_ref() {
+                match remaining.checked_sub(data.len()) {
+                    Some(new_remaining) => *remaining = new_remaining,
+                    None => {
+                        return Poll::Ready(Some(Err(DecompressedLengthLimitError.into())));
+                    }
+                }
+            }
         }
+
+        Poll::Ready(result)
     }
 
     fn size_hint(&self) -> SizeHint {
@@ -145,6 +171,19 @@ where
     }
 }
 
+#[derive(Debug)]
+/// Error returned by [`DecompressionBody`] in case the decompressed body
+/// exceeds the configured limit.
+pub struct DecompressedLengthLimitError;
+
+impl std::fmt::Display for DecompressedLengthLimitError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("decompressed body length limit exceeded")
+    }
+}
+
+impl std::error::Error for DecompressedLengthLimitError {}
+
 impl<B> DecorateAsyncRead for GzipDecoder<B>
 where
     B: Body,
@@ -17,6 +17,7 @@ use rama_core::Layer;
 pub struct RequestDecompressionLayer {
     accept: AcceptEncoding,
     pass_through_unaccepted: bool,
+    max_decompressed_size: Option<usize>,
 }
 
 impl<S> Layer<S> for RequestDecompressionLayer {
@@ -27,6 +28,7 @@ impl<S> Layer<S> for RequestDecompressionLayer {
             inner: service,
             accept: self.accept,
             pass_through_unaccepted: self.pass_through_unaccepted,
+            max_decompressed_size: self.max_decompressed_size,
         }
     }
 }
@@ -37,6 +39,22 @@ impl RequestDecompressionLayer {
         Default::default()
     }
 
+    /// Limit the size of decompressed request bodies.
+    ///
+    /// See [`RequestDecompression::max_decompressed_size`] for more information.
+    pub fn max_decompressed_size(mut self, limit: usize) -> Self {
+        self.max_decompressed_size = Some(limit);
+        self
+    }
+
+    /// Limit the size of decompressed request bodies.
+    ///
+    /// See [`RequestDecompression::max_decompressed_size`] for more information.
+    pub fn set_max_decompressed_size(&mut self, limit: usize) -> &mut Self {
+        self.max_decompressed_size = Some(limit);
+        self
+    }
+
     /// Sets whether to support gzip encoding.
     pub fn gzip(mut self, enable: bool) -> Self {
         self.accept.set_gzip(enable);
@@ -6,7 +6,7 @@ mod tests {
     use super::service::RequestDecompression;
 
     use crate::dep::http_body_util::BodyExt;
-    use crate::layer::decompression::DecompressionBody;
+    use crate::layer::decompression::{DecompressedLengthLimitError, DecompressionBody};
     use crate::{Body, Request, Response, StatusCode, header};
     use rama_core::service::service_fn;
     use rama_core::{Context, Service};
@@ -45,6 +45,27 @@ mod tests {
         let _ = svc.serve(Context::default(), req).await.unwrap();
     }
 
+    #[tokio::test]
+    async fn decompress_within_max_decompressed_size() {
+        let req = request_gzip();
+        let svc = RequestDecompression::new(service_fn(assert_request_is_decompressed))
+            .max_decompressed_size(6);
+        let _ = svc.serve(Context::default(), req).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn decompress_exceeding_max_decompressed_size() {
+        let req = request_gzip();
+        let svc =
+            RequestDecompression::new(service_fn(async |req: Request<DecompressionBody<Body>>| {
+                let err = req.into_body().collect().await.unwrap_err();
+                assert!(err.is::<DecompressedLengthLimitError>());
+                Ok::<_, Infallible>(Response::new(Body::empty()))
+            }))
+            .max_decompressed_size(5);
+        let _ = svc.serve(Context::default(), req).await.unwrap();
+    }
+
     async fn assert_request_is_decompressed(
         req: Request<DecompressionBody<Body>>,
     ) -> Result<Response<Body>, Infallible> {
@@ -29,6 +29,7 @@ pub struct RequestDecompression<S> {
     pub(super) inner: S,
     pub(super) accept: AcceptEncoding,
     pub(super) pass_through_unaccepted: bool,
+    pub(super) max_decompressed_size: Option<usize>,
 }
 
 impl<S: fmt::Debug> fmt::Debug for RequestDecompression<S> {
@@ -37,6 +38,7 @@ impl<S: fmt::Debug> fmt::Debug for RequestDecompression<S> {
             .field("inner", &self.inner)
             .field("accept", &self.accept)
             .field("pass_through_unaccepted", &self.pass_through_unaccepted)
+            .field("max_decompressed_size", &self.max_decompressed_size)
             .finish()
     }
 }
@@ -47,6 +49,7 @@ impl<S: Clone> Clone for RequestDecompression<S> {
             inner: self.inner.clone(),
             accept: self.accept,
             pass_through_unaccepted: self.pass_through_unaccepted,
+            max_decompressed_size: self.max_decompressed_size,
         }
     }
 }
@@ -104,7 +107,11 @@ where
             } else {
                 BodyInner::identity(body)
             };
-        let body = DecompressionBody::new(body);
+        let is_decompressed = !matches!(body, BodyInner::Identity { .. });
+        let body = match self.max_decompressed_size {
+            Some(limit) if is_decompressed => DecompressionBody::new(body).with_limit(limit),
+            _ => DecompressionBody::new(body),
+        };
         let req = Request::from_parts(parts, body);
         self.inner
             .serve(ctx, req)
@@ -140,6 +147,7 @@ impl<S> RequestDecompression<S> {
             inner: service,
             accept: AcceptEncoding::default(),
             pass_through_unaccepted: false,
+            max_decompressed_size: None,
         }
     }
 
@@ -161,6 +169,28 @@ impl<S> RequestDecompression<S> {
         self
     }
 
+    /// Limit the size of decompressed request bodies.
+    ///
+    /// Once a decompressed body exceeds this limit it will return a
+    /// [`DecompressedLengthLimitError`] instead of more data,
+    /// protecting the underlying service against decompression bombs.
+    ///
+    /// By default there is no limit.
+    ///
+    /// [`DecompressedLengthLimitError`]: crate::layer::decompression::DecompressedLengthLimitError
+    pub fn max_decompressed_size(mut self, limit: usize) -> Self {
+        self.max_decompressed_size = Some(limit);
+        self
+    }
+
+    /// Limit the size of decompressed request bodies.
+    ///
+    /// See [`Self::max_decompressed_size`] for more information.
+    pub fn set_max_decompressed_size(&mut self, limit: usize) -> &mut Self {
+        self.max_decompressed_size = Some(limit);
+        self
+    }
+
     /// Sets whether to support gzip encoding.
     pub fn gzip(mut self, enable: bool) -> Self {
         self.accept.set_gzip(enable);
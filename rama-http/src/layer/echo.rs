@@ -0,0 +1,129 @@
+//! Middleware which emits the details of every request as a tracing debug event.
+//!
+//! This is useful when testing proxies, load balancers and middleware chains,
+//! to see the request as it arrives at a certain point in the stack.
+//! The request method, uri, version and headers are logged; the body is not,
+//! as it would require buffering it. Use [`WebService::echo_handler`] to
+//! respond with the full request, including its body.
+//!
+//! Headers marked as sensitive are logged as such, and in case a
+//! [`SensitiveHeaders`] is found in the [`Context`] it is used to redact the headers.
+//!
+//! # Example
+//!
+//! ```
+//! use rama_http::layer::echo::EchoLayer;
+//! use rama_http::{Body, Request, Response};
+//! use rama_core::service::service_fn;
+//! use rama_core::{Context, Layer, Service};
+//! use std::convert::Infallible;
+//!
+//! # #[tokio::main]
+//! # async fn main() {
+//! let svc = EchoLayer::new().into_layer(service_fn(async |_req: Request| {
+//!     Ok::<_, Infallible>(Response::new(Body::empty()))
+//! }));
+//!
+//! let _ = svc.serve(Context::default(), Request::new(Body::empty())).await.unwrap();
+//! # }
+//! ```
+//!
+//! [`WebService::echo_handler`]: crate::service::web::WebService::echo_handler
+//! [`SensitiveHeaders`]: crate::layer::sensitive_headers::SensitiveHeaders
+//! [`Context`]: rama_core::Context
+
+use crate::Request;
+use crate::layer::sensitive_headers::SensitiveHeaders;
+use rama_core::telemetry::tracing;
+use rama_core::{Context, Layer, Service};
+use rama_utils::macros::define_inner_service_accessors;
+use std::fmt;
+
+/// Layer that applies the [`EchoService`] middleware.
+///
+/// See the [module docs](self) for more details.
+#[derive(Debug, Clone, Default)]
+#[non_exhaustive]
+pub struct EchoLayer;
+
+impl EchoLayer {
+    /// Create a new [`EchoLayer`].
+    pub const fn new() -> Self {
+        Self
+    }
+}
+
+impl<S> Layer<S> for EchoLayer {
+    type Service = EchoService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        EchoService::new(inner)
+    }
+}
+
+/// Middleware which emits the details of every request as a tracing debug event.
+///
+/// See the [module docs](self) for more details.
+pub struct EchoService<S> {
+    inner: S,
+}
+
+impl<S> EchoService<S> {
+    /// Create a new [`EchoService`].
+    pub const fn new(inner: S) -> Self {
+        Self { inner }
+    }
+
+    define_inner_service_accessors!();
+}
+
+impl<S: fmt::Debug> fmt::Debug for EchoService<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EchoService")
+            .field("inner", &self.inner)
+            .finish()
+    }
+}
+
+impl<S: Clone> Clone for EchoService<S> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl<State, S, ReqBody> Service<State, Request<ReqBody>> for EchoService<S>
+where
+    State: Clone + Send + Sync + 'static,
+    S: Service<State, Request<ReqBody>>,
+    ReqBody: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+
+    async fn serve(
+        &self,
+        ctx: Context<State>,
+        req: Request<ReqBody>,
+    ) -> Result<Self::Response, Self::Error> {
+        if tracing::enabled!(tracing::Level::DEBUG) {
+            let redacted;
+            let headers = match ctx.get::<SensitiveHeaders>() {
+                Some(sensitive_headers) => {
+                    redacted = sensitive_headers.redact(req.headers());
+                    &redacted
+                }
+                None => req.headers(),
+            };
+            tracing::debug!(
+                http.request.method = %req.method(),
+                url.full = %req.uri(),
+                network.protocol.version = ?req.version(),
+                http.request.headers = ?headers,
+                "echo: received request",
+            );
+        }
+        self.inner.serve(ctx, req).await
+    }
+}
@@ -0,0 +1,58 @@
+//! Middleware which strips internal error details from server error responses.
+//!
+//! Server error (`5xx`) responses can contain details which are useful
+//! while developing but must not leak to end users in production,
+//! e.g. stack traces or database connection strings.
+//!
+//! The [`ErrorSanitizationLayer`] replaces the body of `5xx` responses
+//! which contain a recognizable error format with a generic message:
+//!
+//! - JSON bodies (`application/json` or a `+json` media type) of which the
+//!   top-level object contains an `"error"` field are replaced by
+//!   `{"error":"<message>"}`;
+//! - plain text bodies (`text/plain` or no `Content-Type`) are replaced
+//!   by the message as plain text.
+//!
+//! All other responses are left as-is. Of the original headers only the
+//! request id headers (`x-request-id` and `request-id`) are preserved,
+//! such that the sanitized error can still be correlated with server logs.
+//!
+//! Sanitization only happens in [`ErrorSanitizationMode::Production`] mode,
+//! in [`ErrorSanitizationMode::Debug`] mode all responses pass through verbatim.
+//! The mode is defined statically using [`ErrorSanitizationLayer::with_mode`],
+//! and can be overwritten per request by inserting an [`ErrorSanitizationMode`]
+//! in the [`Context`].
+//!
+//! # Example
+//!
+//! ```
+//! use rama_core::service::service_fn;
+//! use rama_core::{Context, Layer, Service};
+//! use rama_http::dep::http_body_util::BodyExt;
+//! use rama_http::layer::error::ErrorSanitizationLayer;
+//! use rama_http::{Body, Request, Response, StatusCode};
+//! use std::convert::Infallible;
+//!
+//! # #[tokio::main]
+//! # async fn main() {
+//! let svc = ErrorSanitizationLayer::new().into_layer(service_fn(async |_req: Request| {
+//!     Ok::<_, Infallible>(
+//!         Response::builder()
+//!             .status(StatusCode::INTERNAL_SERVER_ERROR)
+//!             .body(Body::from("connect postgres://admin:secret@db: timeout"))
+//!             .unwrap(),
+//!     )
+//! }));
+//!
+//! let res = svc.serve(Context::default(), Request::new(Body::empty())).await.unwrap();
+//! assert_eq!(res.status(), StatusCode::INTERNAL_SERVER_ERROR);
+//! let body = res.into_body().collect().await.unwrap().to_bytes();
+//! assert_eq!(body, "internal server error");
+//! # }
+//! ```
+//!
+//! [`Context`]: rama_core::Context
+
+mod service;
+#[doc(inline)]
+pub use service::{ErrorSanitizationLayer, ErrorSanitizationMode, ErrorSanitizationService};
@@ -0,0 +1,57 @@
+//! Middleware which adds an `ETag` header to responses,
+//! and handles `If-None-Match` conditional requests.
+//!
+//! Only successful (`200 OK`) responses to `GET` and `HEAD` requests are tagged,
+//! and responses which already have an `ETag` header keep it.
+//! The entity tag is computed as follows:
+//!
+//! - for responses with a `Last-Modified` header and a known body size
+//!   (e.g. files served by [`ServeDir`]) it is derived from the
+//!   last modification time and the size of the body;
+//! - for all other responses it is the SHA-256 hash of the body,
+//!   in which case (streaming) bodies are buffered in memory.
+//!
+//! Bodies are only hashed in case their size is known to be at most the configured
+//! maximum body size (see [`ETagLayer::with_max_body_size`]), which is 1 MiB by default.
+//! Responses without an upper bound on their body size (e.g. streaming responses)
+//! and `text/event-stream` responses are never buffered and thus not tagged.
+//!
+//! Entity tags are strong by default. Use [`ETagLayer::with_weak`] to generate
+//! weak entity tags (`W/"..."`) instead, e.g. in case the body is not guaranteed
+//! to be byte-for-byte equivalent for the same last modification time.
+//!
+//! In case the `If-None-Match` header of the request matches the entity tag
+//! of the response, a `304 Not Modified` response without body is returned instead.
+//!
+//! # Example
+//!
+//! ```
+//! use rama_core::service::service_fn;
+//! use rama_core::{Context, Layer, Service};
+//! use rama_http::layer::etag::ETagLayer;
+//! use rama_http::{Body, Request, Response, StatusCode, header};
+//! use std::convert::Infallible;
+//!
+//! # #[tokio::main]
+//! # async fn main() {
+//! let svc = ETagLayer::new().into_layer(service_fn(async |_req: Request| {
+//!     Ok::<_, Infallible>(Response::new(Body::from("hello")))
+//! }));
+//!
+//! let res = svc.serve(Context::default(), Request::new(Body::empty())).await.unwrap();
+//! let etag = res.headers()[header::ETAG].clone();
+//!
+//! let req = Request::builder()
+//!     .header(header::IF_NONE_MATCH, etag)
+//!     .body(Body::empty())
+//!     .unwrap();
+//! let res = svc.serve(Context::default(), req).await.unwrap();
+//! assert_eq!(res.status(), StatusCode::NOT_MODIFIED);
+//! # }
+//! ```
+//!
+//! [`ServeDir`]: crate::service::fs::ServeDir
+
+mod service;
+#[doc(inline)]
+pub use service::{ETagLayer, ETagService};
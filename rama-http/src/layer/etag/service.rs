@@ -0,0 +1,402 @@
+use crate::body::{LimitedBody, collect_limited};
+use crate::dep::http_body;
+use crate::headers::{ETag, HeaderMapExt, IfNoneMatch, LastModified};
+use crate::{Body, Method, Request, Response, StatusCode, header};
+use rama_core::bytes::Bytes;
+use rama_core::error::BoxError;
+use rama_core::telemetry::tracing;
+use rama_core::{Context, Layer, Service};
+use rama_utils::macros::define_inner_service_accessors;
+use sha2::{Digest, Sha256};
+use std::fmt;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const DEFAULT_MAX_BODY_SIZE: usize = 1024 * 1024;
+
+/// Layer that applies the [`ETagService`] middleware.
+///
+/// See the [module docs](super) for more details.
+#[derive(Debug, Clone)]
+pub struct ETagLayer {
+    weak: bool,
+    max_body_size: usize,
+}
+
+impl Default for ETagLayer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ETagLayer {
+    /// Create a new [`ETagLayer`], generating strong entity tags.
+    pub const fn new() -> Self {
+        Self {
+            weak: false,
+            max_body_size: DEFAULT_MAX_BODY_SIZE,
+        }
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Generate weak entity tags (`W/"..."`) instead of strong ones.
+        pub fn weak(mut self, weak: bool) -> Self {
+            self.weak = weak;
+            self
+        }
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Set the maximum size of a response body which is buffered
+        /// in order to hash it, by default this is 1 MiB.
+        ///
+        /// Responses with a larger body are passed through without an entity tag.
+        pub fn max_body_size(mut self, size: usize) -> Self {
+            self.max_body_size = size;
+            self
+        }
+    }
+}
+
+impl<S> Layer<S> for ETagLayer {
+    type Service = ETagService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        ETagService {
+            inner,
+            weak: self.weak,
+            max_body_size: self.max_body_size,
+        }
+    }
+}
+
+/// Middleware which adds an `ETag` header to responses,
+/// and handles `If-None-Match` conditional requests.
+///
+/// See the [module docs](super) for more details.
+pub struct ETagService<S> {
+    inner: S,
+    weak: bool,
+    max_body_size: usize,
+}
+
+impl<S> ETagService<S> {
+    /// Create a new [`ETagService`], generating strong entity tags.
+    pub const fn new(inner: S) -> Self {
+        Self {
+            inner,
+            weak: false,
+            max_body_size: DEFAULT_MAX_BODY_SIZE,
+        }
+    }
+
+    define_inner_service_accessors!();
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Generate weak entity tags (`W/"..."`) instead of strong ones.
+        pub fn weak(mut self, weak: bool) -> Self {
+            self.weak = weak;
+            self
+        }
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Set the maximum size of a response body which is buffered
+        /// in order to hash it, by default this is 1 MiB.
+        ///
+        /// Responses with a larger body are passed through without an entity tag.
+        pub fn max_body_size(mut self, size: usize) -> Self {
+            self.max_body_size = size;
+            self
+        }
+    }
+
+    fn etag(&self, tag: &str) -> Option<ETag> {
+        let etag = if self.weak {
+            format!("W/\"{tag}\"")
+        } else {
+            format!("\"{tag}\"")
+        };
+        etag.parse().ok()
+    }
+}
+
+impl<S: fmt::Debug> fmt::Debug for ETagService<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ETagService")
+            .field("inner", &self.inner)
+            .field("weak", &self.weak)
+            .field("max_body_size", &self.max_body_size)
+            .finish()
+    }
+}
+
+impl<S: Clone> Clone for ETagService<S> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            weak: self.weak,
+            max_body_size: self.max_body_size,
+        }
+    }
+}
+
+impl<State, S, ReqBody, ResBody> Service<State, Request<ReqBody>> for ETagService<S>
+where
+    State: Clone + Send + Sync + 'static,
+    S: Service<State, Request<ReqBody>, Response = Response<ResBody>, Error: Into<BoxError>>,
+    ReqBody: Send + 'static,
+    ResBody: http_body::Body<Data = Bytes, Error: Into<BoxError>> + Send + Sync + 'static,
+{
+    type Response = Response;
+    type Error = BoxError;
+
+    async fn serve(
+        &self,
+        ctx: Context<State>,
+        req: Request<ReqBody>,
+    ) -> Result<Self::Response, Self::Error> {
+        let is_head = match *req.method() {
+            Method::GET => false,
+            Method::HEAD => true,
+            _ => {
+                let response = self.inner.serve(ctx, req).await.map_err(Into::into)?;
+                return Ok(response.map(Body::new));
+            }
+        };
+        let if_none_match = req.headers().typed_get::<IfNoneMatch>();
+
+        let response = self.inner.serve(ctx, req).await.map_err(Into::into)?;
+        if response.status() != StatusCode::OK {
+            return Ok(response.map(Body::new));
+        }
+
+        let (mut parts, body) = response.into_parts();
+        let (etag, body) = match parts.headers.typed_get::<ETag>() {
+            Some(etag) => (etag, Body::new(body)),
+            None => {
+                let modified_tag = parts
+                    .headers
+                    .typed_get::<LastModified>()
+                    .zip(http_body::Body::size_hint(&body).exact())
+                    .and_then(|(last_modified, size)| {
+                        let modified = SystemTime::from(last_modified)
+                            .duration_since(UNIX_EPOCH)
+                            .ok()?;
+                        Some(format!("{:x}-{size:x}", modified.as_secs()))
+                    });
+                let (tag, body) = match modified_tag {
+                    Some(tag) => (tag, Body::new(body)),
+                    // the body of a response to a HEAD request is empty,
+                    // so its hash would not identify the representation
+                    None if is_head => return Ok(Response::from_parts(parts, Body::new(body))),
+                    // event streams are never complete, and neither are hashed
+                    // bodies without an upper bound on their size
+                    None if is_event_stream(&parts.headers)
+                        || http_body::Body::size_hint(&body)
+                            .upper()
+                            .is_none_or(|size| size > self.max_body_size as u64) =>
+                    {
+                        tracing::trace!("ETagService: body not hashed: unbounded or too large");
+                        return Ok(Response::from_parts(parts, Body::new(body)));
+                    }
+                    None => match collect_limited(body, self.max_body_size).await? {
+                        LimitedBody::Collected(bytes) => {
+                            let tag = hex::encode(Sha256::digest(&bytes));
+                            (tag, Body::from(bytes))
+                        }
+                        LimitedBody::Exceeded(body) => {
+                            tracing::trace!("ETagService: body not hashed: too large");
+                            return Ok(Response::from_parts(parts, body));
+                        }
+                    },
+                };
+                let Some(etag) = self.etag(&tag) else {
+                    return Ok(Response::from_parts(parts, body));
+                };
+                parts.headers.typed_insert(etag.clone());
+                (etag, body)
+            }
+        };
+
+        if if_none_match.is_some_and(|if_none_match| !if_none_match.precondition_passes(&etag)) {
+            tracing::trace!(?etag, "ETagService: entity tag matches: not modified");
+            parts.status = StatusCode::NOT_MODIFIED;
+            for name in [
+                header::CONTENT_LENGTH,
+                header::CONTENT_TYPE,
+                header::TRANSFER_ENCODING,
+            ] {
+                parts.headers.remove(name);
+            }
+            return Ok(Response::from_parts(parts, Body::empty()));
+        }
+
+        Ok(Response::from_parts(parts, body))
+    }
+}
+
+fn is_event_stream(headers: &crate::HeaderMap) -> bool {
+    headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| {
+            value
+                .split(';')
+                .next()
+                .is_some_and(|mime| mime.trim().eq_ignore_ascii_case("text/event-stream"))
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::HeaderValue;
+    use crate::dep::http_body_util::BodyExt;
+    use rama_core::futures::stream;
+    use rama_core::service::service_fn;
+    use std::convert::Infallible;
+
+    async fn serve(
+        svc: &impl Service<(), Request, Response = Response, Error = BoxError>,
+        method: Method,
+        if_none_match: Option<&HeaderValue>,
+    ) -> Response {
+        let mut builder = Request::builder().method(method);
+        if let Some(if_none_match) = if_none_match {
+            builder = builder.header(header::IF_NONE_MATCH, if_none_match);
+        }
+        svc.serve(Context::default(), builder.body(Body::empty()).unwrap())
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_etag_body_hash() {
+        let svc = ETagLayer::new().into_layer(service_fn(async || {
+            Ok::<_, Infallible>(
+                Response::builder()
+                    .header(header::CONTENT_TYPE, "text/plain")
+                    .body(Body::from("hello"))
+                    .unwrap(),
+            )
+        }));
+
+        let res = serve(&svc, Method::GET, None).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        let etag = res.headers()[header::ETAG].clone();
+        assert_eq!(
+            etag,
+            "\"2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824\""
+        );
+        let body = res.into_body().collect().await.unwrap().to_bytes();
+        assert_eq!(body, "hello");
+
+        let res = serve(&svc, Method::GET, Some(&etag)).await;
+        assert_eq!(res.status(), StatusCode::NOT_MODIFIED);
+        assert_eq!(res.headers()[header::ETAG], etag);
+        assert!(!res.headers().contains_key(header::CONTENT_TYPE));
+        let body = res.into_body().collect().await.unwrap().to_bytes();
+        assert!(body.is_empty());
+
+        let res = serve(
+            &svc,
+            Method::GET,
+            Some(&HeaderValue::from_static("\"other\"")),
+        )
+        .await;
+        assert_eq!(res.status(), StatusCode::OK);
+
+        let res = serve(&svc, Method::GET, Some(&HeaderValue::from_static("*"))).await;
+        assert_eq!(res.status(), StatusCode::NOT_MODIFIED);
+
+        let res = serve(&svc, Method::POST, Some(&etag)).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert!(!res.headers().contains_key(header::ETAG));
+
+        let res = serve(&svc, Method::HEAD, None).await;
+        assert!(!res.headers().contains_key(header::ETAG));
+    }
+
+    #[tokio::test]
+    async fn test_etag_last_modified() {
+        let svc = ETagLayer::new()
+            .with_weak(true)
+            .into_layer(service_fn(async || {
+                Ok::<_, Infallible>(
+                    Response::builder()
+                        .header(header::LAST_MODIFIED, "Sun, 06 Nov 1994 08:49:37 GMT")
+                        .header(header::CONTENT_LENGTH, "5")
+                        .body(Body::from("hello"))
+                        .unwrap(),
+                )
+            }));
+
+        for method in [Method::GET, Method::HEAD] {
+            let res = serve(&svc, method.clone(), None).await;
+            assert_eq!(res.headers()[header::ETAG], "W/\"2ebc98a1-5\"", "{method}");
+
+            // weak comparison is used for If-None-Match
+            let etag = HeaderValue::from_static("\"2ebc98a1-5\"");
+            let res = serve(&svc, method.clone(), Some(&etag)).await;
+            assert_eq!(res.status(), StatusCode::NOT_MODIFIED, "{method}");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_etag_existing() {
+        let svc = ETagService::new(service_fn(async || {
+            Ok::<_, Infallible>(
+                Response::builder()
+                    .header(header::ETAG, "\"v1\"")
+                    .body(Body::from("hello"))
+                    .unwrap(),
+            )
+        }));
+
+        let res = serve(&svc, Method::GET, None).await;
+        assert_eq!(res.headers()[header::ETAG], "\"v1\"");
+
+        let res = serve(
+            &svc,
+            Method::GET,
+            Some(&HeaderValue::from_static("W/\"v1\"")),
+        )
+        .await;
+        assert_eq!(res.status(), StatusCode::NOT_MODIFIED);
+    }
+
+    #[tokio::test]
+    async fn test_etag_body_not_hashed() {
+        let svc = ETagLayer::new()
+            .with_max_body_size(4)
+            .into_layer(service_fn(async |req: Request| {
+                let res = match req.uri().path() {
+                    "/stream" => {
+                        Response::new(Body::from_stream(stream::iter([Ok::<_, Infallible>(
+                            "hello",
+                        )])))
+                    }
+                    "/events" => Response::builder()
+                        .header(header::CONTENT_TYPE, "text/event-stream; charset=utf-8")
+                        .body(Body::from("data"))
+                        .unwrap(),
+                    "/large" => Response::new(Body::from("hello")),
+                    _ => Response::new(Body::from("hi")),
+                };
+                Ok::<_, Infallible>(res)
+            }));
+
+        for (path, tagged, expected) in [
+            ("/", true, "hi"),
+            ("/large", false, "hello"),
+            ("/stream", false, "hello"),
+            ("/events", false, "data"),
+        ] {
+            let req = Request::builder().uri(path).body(Body::empty()).unwrap();
+            let res = svc.serve(Context::default(), req).await.unwrap();
+            assert_eq!(res.headers().contains_key(header::ETAG), tagged, "{path}");
+            let body = res.into_body().collect().await.unwrap().to_bytes();
+            assert_eq!(body, expected, "{path}");
+        }
+    }
+}
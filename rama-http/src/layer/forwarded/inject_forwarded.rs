@@ -0,0 +1,306 @@
+use crate::Request;
+use crate::headers::forwarded::{
+    ForwardHeader, Forwarded, XForwardedFor, XForwardedHost, XForwardedProto,
+};
+use crate::headers::{Header, HeaderMapExt};
+use rama_core::error::BoxError;
+use rama_core::{Context, Layer, Service};
+use rama_net::forwarded::ForwardedElement;
+use rama_net::http::RequestContext;
+use rama_net::stream::SocketInfo;
+use rama_utils::macros::define_inner_service_accessors;
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+/// The amount of hops, counted from the end of the chain,
+/// which are trusted in the forwarded headers of incoming requests.
+///
+/// Entries in front of these hops are dropped from the chain
+/// injected by the [`ForwardedHeadersInjectionLayer`], as these
+/// could have been spoofed by the client.
+///
+/// Defaults to `0`, trusting no existing forwarded information at all.
+pub struct TrustedHops(pub u8);
+
+impl TrustedHops {
+    /// Trust all hops found in the forwarded headers of incoming requests.
+    pub const ALL: Self = Self(u8::MAX);
+}
+
+/// Layer to inject the client address information into proxied requests.
+///
+/// For each request, the [`SocketInfo`] peer IP of the client is appended
+/// to the chain of forwarded information already found in the [`Forwarded`]
+/// or [`X-Forwarded-For`] header of the request, limited to the [`TrustedHops`].
+///
+/// The request is sent with the following headers, overwriting any existing values:
+///
+/// - [`Forwarded`]: the full chain, with the new element containing the `for`, `host` and `proto`
+///   properties as defined in [`RFC 7239`](https://tools.ietf.org/html/rfc7239);
+/// - [`X-Forwarded-For`]: the IP addresses of the full chain;
+/// - [`X-Forwarded-Host`]: the host requested by the client;
+/// - [`X-Forwarded-Proto`]: the protocol used by the client.
+///
+/// Use the [`SetForwardedHeaderLayer`] instead in case you wish to
+/// control which (single) header is written, and the [`GetForwardedHeadersLayer`]
+/// to read the forwarded information on the receiving end.
+///
+/// [`X-Forwarded-For`]: XForwardedFor
+/// [`X-Forwarded-Host`]: XForwardedHost
+/// [`X-Forwarded-Proto`]: XForwardedProto
+/// [`SetForwardedHeaderLayer`]: super::SetForwardedHeaderLayer
+/// [`GetForwardedHeadersLayer`]: super::GetForwardedHeadersLayer
+///
+/// ## Example
+///
+/// ```rust
+/// use rama_core::service::service_fn;
+/// use rama_core::{Context, Layer, Service};
+/// use rama_http::layer::forwarded::{ForwardedHeadersInjectionLayer, TrustedHops};
+/// use rama_http::Request;
+/// use rama_net::stream::SocketInfo;
+/// use std::convert::Infallible;
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// async fn svc(request: Request<()>) -> Result<(), Infallible> {
+///     assert_eq!(request.headers()["x-forwarded-for"], "10.0.0.1, 42.37.100.50");
+///     assert_eq!(request.headers()["x-forwarded-host"], "example.com");
+///     assert_eq!(request.headers()["x-forwarded-proto"], "http");
+///     Ok(())
+/// }
+///
+/// let service = ForwardedHeadersInjectionLayer::new()
+///     .with_trusted_hops(TrustedHops(1))
+///     .into_layer(service_fn(svc));
+///
+/// let req = Request::builder()
+///     .uri("http://example.com")
+///     .header("x-forwarded-for", "1.2.3.4, 10.0.0.1")
+///     .body(())
+///     .unwrap();
+/// let mut ctx = Context::default();
+/// ctx.insert(SocketInfo::new(None, "42.37.100.50:62345".parse().unwrap()));
+/// service.serve(ctx, req).await.unwrap();
+/// # }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct ForwardedHeadersInjectionLayer {
+    trusted_hops: TrustedHops,
+}
+
+impl ForwardedHeadersInjectionLayer {
+    /// Create a new [`ForwardedHeadersInjectionLayer`],
+    /// trusting no existing forwarded information.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Set the [`TrustedHops`] kept from the forwarded headers of incoming requests.
+        pub fn trusted_hops(mut self, hops: TrustedHops) -> Self {
+            self.trusted_hops = hops;
+            self
+        }
+    }
+}
+
+impl<S> Layer<S> for ForwardedHeadersInjectionLayer {
+    type Service = ForwardedHeadersInjectionService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        ForwardedHeadersInjectionService {
+            inner,
+            trusted_hops: self.trusted_hops,
+        }
+    }
+}
+
+/// Middleware [`Service`] to inject the client address information into proxied requests.
+///
+/// See [`ForwardedHeadersInjectionLayer`] for more information.
+pub struct ForwardedHeadersInjectionService<S> {
+    inner: S,
+    trusted_hops: TrustedHops,
+}
+
+impl<S: fmt::Debug> fmt::Debug for ForwardedHeadersInjectionService<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ForwardedHeadersInjectionService")
+            .field("inner", &self.inner)
+            .field("trusted_hops", &self.trusted_hops)
+            .finish()
+    }
+}
+
+impl<S: Clone> Clone for ForwardedHeadersInjectionService<S> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            trusted_hops: self.trusted_hops,
+        }
+    }
+}
+
+impl<S> ForwardedHeadersInjectionService<S> {
+    /// Create a new [`ForwardedHeadersInjectionService`],
+    /// trusting no existing forwarded information.
+    pub fn new(inner: S) -> Self {
+        Self {
+            inner,
+            trusted_hops: TrustedHops::default(),
+        }
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Set the [`TrustedHops`] kept from the forwarded headers of incoming requests.
+        pub fn trusted_hops(mut self, hops: TrustedHops) -> Self {
+            self.trusted_hops = hops;
+            self
+        }
+    }
+
+    define_inner_service_accessors!();
+}
+
+impl<S, State, Body> Service<State, Request<Body>> for ForwardedHeadersInjectionService<S>
+where
+    S: Service<State, Request<Body>, Error: Into<BoxError>>,
+    Body: Send + 'static,
+    State: Clone + Send + Sync + 'static,
+{
+    type Response = S::Response;
+    type Error = BoxError;
+
+    async fn serve(
+        &self,
+        mut ctx: Context<State>,
+        mut req: Request<Body>,
+    ) -> Result<Self::Response, Self::Error> {
+        let mut chain: Vec<ForwardedElement> = match req.headers().typed_get::<Forwarded>() {
+            Some(forwarded) => forwarded.into_iter().collect(),
+            None => req
+                .headers()
+                .typed_get::<XForwardedFor>()
+                .map(|x_forwarded_for| x_forwarded_for.into_iter().collect())
+                .unwrap_or_default(),
+        };
+        let untrusted = chain.len().saturating_sub(self.trusted_hops.0 as usize);
+        chain.drain(..untrusted);
+
+        let request_ctx: &RequestContext =
+            ctx.get_or_try_insert_with_ctx(|ctx| (ctx, &req).try_into())?;
+
+        let mut element = if request_ctx.authority_has_default_port() {
+            ForwardedElement::forwarded_host(request_ctx.authority.host().clone())
+        } else {
+            ForwardedElement::forwarded_host(request_ctx.authority.clone())
+        };
+        if let Ok(forwarded_proto) = (&request_ctx.protocol).try_into() {
+            element.set_forwarded_proto(forwarded_proto);
+        }
+        if let Some(peer_ip) = ctx
+            .get::<SocketInfo>()
+            .map(|socket| socket.peer_addr().ip())
+        {
+            element.set_forwarded_for(peer_ip);
+        }
+
+        let headers = req.headers_mut();
+        if let Some(header) = XForwardedHost::try_from_forwarded([&element]) {
+            headers.typed_insert(header);
+        }
+        if let Some(header) = XForwardedProto::try_from_forwarded([&element]) {
+            headers.typed_insert(header);
+        }
+
+        chain.push(element);
+        match XForwardedFor::try_from_forwarded(chain.iter()) {
+            Some(header) => headers.typed_insert(header),
+            None => {
+                headers.remove(XForwardedFor::name());
+            }
+        }
+        if let Some(header) = Forwarded::try_from_forwarded(chain.iter()) {
+            headers.typed_insert(header);
+        }
+
+        self.inner.serve(ctx, req).await.map_err(Into::into)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::header::FORWARDED;
+    use rama_core::service::service_fn;
+    use std::convert::Infallible;
+
+    async fn serve_with_hops(hops: TrustedHops, req: Request<()>) -> Request<()> {
+        let service = ForwardedHeadersInjectionLayer::new()
+            .with_trusted_hops(hops)
+            .into_layer(service_fn(async |req: Request<()>| {
+                Ok::<_, Infallible>(req)
+            }));
+        let mut ctx = Context::default();
+        ctx.insert(SocketInfo::new(None, "42.37.100.50:62345".parse().unwrap()));
+        service.serve(ctx, req).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_inject_forwarded_headers_without_existing_chain() {
+        let req = Request::builder()
+            .uri("https://example.com/foo")
+            .body(())
+            .unwrap();
+        let req = serve_with_hops(TrustedHops::ALL, req).await;
+
+        let headers = req.headers();
+        assert_eq!(headers["x-forwarded-for"], "42.37.100.50");
+        assert_eq!(headers["x-forwarded-host"], "example.com");
+        assert_eq!(headers["x-forwarded-proto"], "https");
+        assert_eq!(
+            headers[FORWARDED],
+            r#"for=42.37.100.50;host=example.com;proto=https"#
+        );
+    }
+
+    #[tokio::test]
+    async fn test_inject_forwarded_headers_trusted_hops() {
+        for (hops, expected) in [
+            (TrustedHops(0), "42.37.100.50"),
+            (TrustedHops(1), "10.0.0.2, 42.37.100.50"),
+            (
+                TrustedHops::ALL,
+                "1.2.3.4, 10.0.0.1, 10.0.0.2, 42.37.100.50",
+            ),
+        ] {
+            let req = Request::builder()
+                .uri("http://example.com")
+                .header("x-forwarded-for", "1.2.3.4, 10.0.0.1")
+                .header("x-forwarded-for", "10.0.0.2")
+                .body(())
+                .unwrap();
+            let req = serve_with_hops(hops, req).await;
+            assert_eq!(req.headers()["x-forwarded-for"], expected, "{hops:?}");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_inject_forwarded_headers_prefers_forwarded_chain() {
+        let req = Request::builder()
+            .uri("http://example.com")
+            .header(FORWARDED, "for=1.2.3.4, for=10.0.0.1;proto=http")
+            .header("x-forwarded-for", "8.8.8.8")
+            .body(())
+            .unwrap();
+        let req = serve_with_hops(TrustedHops(1), req).await;
+
+        let headers = req.headers();
+        assert_eq!(headers["x-forwarded-for"], "10.0.0.1, 42.37.100.50");
+        assert_eq!(
+            headers[FORWARDED],
+            r#"for=10.0.0.1;proto=http,for=42.37.100.50;host=example.com;proto=http"#
+        );
+    }
+}
@@ -15,3 +15,9 @@ pub use set_forwarded::{SetForwardedHeaderLayer, SetForwardedHeaderService};
 mod set_forwarded_multi;
 #[doc(inline)]
 pub use set_forwarded_multi::{SetForwardedHeadersLayer, SetForwardedHeadersService};
+
+mod inject_forwarded;
+#[doc(inline)]
+pub use inject_forwarded::{
+    ForwardedHeadersInjectionLayer, ForwardedHeadersInjectionService, TrustedHops,
+};
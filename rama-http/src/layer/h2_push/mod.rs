@@ -0,0 +1,90 @@
+//! Middleware that pushes preloaded resources to HTTP/2 clients.
+//!
+//! When the inner service returns a response with `Link: <url>; rel=preload`
+//! headers for an HTTP/2 request, the [`H2Push`] middleware serves those
+//! resources using the same inner service and attaches them as
+//! [`H2PushPromises`] to the response extensions. The HTTP/2 server
+//! then sends a push promise, followed by the pushed response,
+//! for each of these resources prior to sending the response itself.
+//!
+//! Only links which are relative or of the same origin as the request are pushed,
+//! and only successful responses with a body within the configured limit.
+//! Pushing is skipped for links with the `nopush` parameter and for responses with
+//! a `Cache-Control: no-push` directive. Clients which disabled server push
+//! (`SETTINGS_ENABLE_PUSH=0`) do not receive any push promises: the HTTP/2 server
+//! marks requests of clients which allow it with [`H2PushEnabled`], and
+//! no resources are fetched for requests without that marker.
+//! The resources to push are fetched concurrently.
+//!
+//! # Example
+//!
+//! ```
+//! use std::convert::Infallible;
+//!
+//! use rama_core::service::service_fn;
+//! use rama_core::{Context, Layer, Service};
+//! use rama_http::layer::h2_push::{H2PushEnabled, H2PushLayer, H2PushPromises};
+//! use rama_http::{Body, Request, Response, Version, header};
+//!
+//! async fn handle(req: Request) -> Result<Response, Infallible> {
+//!     Ok(match req.uri().path() {
+//!         "/" => Response::builder()
+//!             .header(header::LINK, "</style.css>; rel=preload; as=style")
+//!             .body(Body::from("<html>...</html>"))
+//!             .unwrap(),
+//!         "/style.css" => Response::new(Body::from("body { color: red }")),
+//!         _ => unreachable!(),
+//!     })
+//! }
+//!
+//! # #[tokio::main]
+//! # async fn main() {
+//! let svc = H2PushLayer::new().into_layer(service_fn(handle));
+//!
+//! let req = Request::builder()
+//!     .uri("https://example.com/")
+//!     .version(Version::HTTP_2)
+//!     .extension(H2PushEnabled)
+//!     .body(Body::empty())
+//!     .unwrap();
+//! let res = svc.serve(Context::default(), req).await.unwrap();
+//!
+//! let pushes = res.extensions().get::<H2PushPromises>().unwrap();
+//! assert_eq!(pushes.0[0].uri, "https://example.com/style.css");
+//! assert_eq!(pushes.0[0].body, "body { color: red }");
+//! # }
+//! ```
+
+use crate::{HeaderMap, StatusCode, Uri};
+use rama_core::bytes::Bytes;
+
+mod service;
+pub use service::{H2Push, H2PushLayer};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+/// Marker inserted in the request extensions by the HTTP/2 server
+/// in case the client allows server push.
+///
+/// The [`H2Push`] middleware does not fetch any resources to push
+/// for requests without this marker.
+pub struct H2PushEnabled;
+
+#[derive(Debug, Clone, Default)]
+/// Resources to be pushed by the HTTP/2 server,
+/// inserted in the response extensions by the [`H2Push`] middleware.
+pub struct H2PushPromises(pub Vec<H2PushPromise>);
+
+#[derive(Debug, Clone)]
+/// A single resource to be pushed by the HTTP/2 server.
+pub struct H2PushPromise {
+    /// The uri of the promised `GET` request.
+    pub uri: Uri,
+    /// The headers of the promised request.
+    pub request_headers: HeaderMap,
+    /// The status of the pushed response.
+    pub status: StatusCode,
+    /// The headers of the pushed response.
+    pub response_headers: HeaderMap,
+    /// The body of the pushed response.
+    pub body: Bytes,
+}
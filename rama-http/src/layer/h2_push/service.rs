@@ -0,0 +1,483 @@
+use super::{H2PushEnabled, H2PushPromise, H2PushPromises};
+use crate::dep::http_body::Body;
+use crate::dep::http_body_util::{BodyExt, Limited};
+use crate::{HeaderMap, Method, Request, Response, Uri, Version, header};
+use rama_core::error::BoxError;
+use rama_core::futures::future::join_all;
+use rama_core::telemetry::tracing;
+use rama_core::{Context, Layer, Service};
+use rama_utils::macros::define_inner_service_accessors;
+use std::fmt;
+
+const DEFAULT_MAX_PUSHES: usize = 8;
+const DEFAULT_MAX_BODY_SIZE: usize = 1024 * 1024;
+
+/// Request headers copied from the original request to the promised requests.
+const PROMISED_REQUEST_HEADERS: [header::HeaderName; 3] = [
+    header::ACCEPT_ENCODING,
+    header::ACCEPT_LANGUAGE,
+    header::USER_AGENT,
+];
+
+/// Layer that applies the [`H2Push`] middleware which pushes
+/// preloaded resources to HTTP/2 clients.
+///
+/// See the [module docs](super) for more details.
+#[derive(Debug, Clone)]
+pub struct H2PushLayer {
+    max_pushes: usize,
+    max_body_size: usize,
+}
+
+impl Default for H2PushLayer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl H2PushLayer {
+    /// Create a new [`H2PushLayer`].
+    ///
+    /// By default at most 8 resources are pushed per response,
+    /// each with a body of at most 1 MiB.
+    pub const fn new() -> Self {
+        Self {
+            max_pushes: DEFAULT_MAX_PUSHES,
+            max_body_size: DEFAULT_MAX_BODY_SIZE,
+        }
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Set the maximum amount of resources pushed per response.
+        pub fn max_pushes(mut self, max: usize) -> Self {
+            self.max_pushes = max;
+            self
+        }
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Set the maximum body size of a pushed resource,
+        /// larger resources are not pushed.
+        pub fn max_body_size(mut self, max: usize) -> Self {
+            self.max_body_size = max;
+            self
+        }
+    }
+}
+
+impl<S> Layer<S> for H2PushLayer {
+    type Service = H2Push<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        H2Push {
+            inner,
+            max_pushes: self.max_pushes,
+            max_body_size: self.max_body_size,
+        }
+    }
+}
+
+/// Middleware which pushes preloaded resources to HTTP/2 clients.
+///
+/// See the [module docs](super) for more details.
+pub struct H2Push<S> {
+    inner: S,
+    max_pushes: usize,
+    max_body_size: usize,
+}
+
+impl<S> H2Push<S> {
+    /// Create a new [`H2Push`] middleware.
+    ///
+    /// See [`H2PushLayer::new`] for the default limits.
+    pub const fn new(inner: S) -> Self {
+        Self {
+            inner,
+            max_pushes: DEFAULT_MAX_PUSHES,
+            max_body_size: DEFAULT_MAX_BODY_SIZE,
+        }
+    }
+
+    define_inner_service_accessors!();
+}
+
+impl<S: fmt::Debug> fmt::Debug for H2Push<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("H2Push")
+            .field("inner", &self.inner)
+            .field("max_pushes", &self.max_pushes)
+            .field("max_body_size", &self.max_body_size)
+            .finish()
+    }
+}
+
+impl<S: Clone> Clone for H2Push<S> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            max_pushes: self.max_pushes,
+            max_body_size: self.max_body_size,
+        }
+    }
+}
+
+impl<S, State, ReqBody, ResBody> Service<State, Request<ReqBody>> for H2Push<S>
+where
+    S: Service<State, Request<ReqBody>, Response = Response<ResBody>>,
+    State: Clone + Send + Sync + 'static,
+    ReqBody: Default + Send + 'static,
+    ResBody: Body<Data: Send, Error: Into<BoxError>> + Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+
+    async fn serve(
+        &self,
+        ctx: Context<State>,
+        req: Request<ReqBody>,
+    ) -> Result<Self::Response, Self::Error> {
+        if req.version() != Version::HTTP_2
+            || self.max_pushes == 0
+            || req.extensions().get::<H2PushEnabled>().is_none()
+        {
+            return self.inner.serve(ctx, req).await;
+        }
+
+        let base_uri = req.uri().clone();
+        let mut request_headers = HeaderMap::new();
+        for name in PROMISED_REQUEST_HEADERS {
+            for value in req.headers().get_all(&name) {
+                request_headers.append(name.clone(), value.clone());
+            }
+        }
+
+        let mut res = self.inner.serve(ctx.clone(), req).await?;
+        if is_push_disabled(res.headers()) {
+            return Ok(res);
+        }
+
+        let mut uris: Vec<Uri> = Vec::new();
+        for uri in preload_links(res.headers())
+            .filter_map(|link| resolve_push_uri(&base_uri, link))
+            .take(self.max_pushes)
+        {
+            if !uris.contains(&uri) {
+                uris.push(uri);
+            }
+        }
+        if uris.is_empty() {
+            return Ok(res);
+        }
+
+        let promises: Vec<_> = join_all(
+            uris.into_iter()
+                .map(|uri| self.push_promise(ctx.clone(), uri, request_headers.clone())),
+        )
+        .await
+        .into_iter()
+        .flatten()
+        .collect();
+
+        if !promises.is_empty() {
+            res.extensions_mut().insert(H2PushPromises(promises));
+        }
+        Ok(res)
+    }
+}
+
+impl<S> H2Push<S> {
+    async fn push_promise<State, ReqBody, ResBody>(
+        &self,
+        ctx: Context<State>,
+        uri: Uri,
+        request_headers: HeaderMap,
+    ) -> Option<H2PushPromise>
+    where
+        S: Service<State, Request<ReqBody>, Response = Response<ResBody>>,
+        State: Clone + Send + Sync + 'static,
+        ReqBody: Default + Send + 'static,
+        ResBody: Body<Data: Send, Error: Into<BoxError>> + Send + 'static,
+    {
+        let mut req = Request::new(ReqBody::default());
+        *req.method_mut() = Method::GET;
+        *req.uri_mut() = uri.clone();
+        *req.version_mut() = Version::HTTP_2;
+        *req.headers_mut() = request_headers.clone();
+
+        let Ok(res) = self.inner.serve(ctx, req).await else {
+            tracing::debug!(%uri, "failed to serve resource to push");
+            return None;
+        };
+        if !res.status().is_success() {
+            return None;
+        }
+        if res
+            .body()
+            .size_hint()
+            .lower()
+            .try_into()
+            .is_ok_and(|lower: usize| lower > self.max_body_size)
+        {
+            return None;
+        }
+
+        let (parts, body) = res.into_parts();
+        let body = match Limited::new(body, self.max_body_size).collect().await {
+            Ok(body) => body.to_bytes(),
+            Err(err) => {
+                tracing::debug!(%uri, "failed to collect body of resource to push: {err}");
+                return None;
+            }
+        };
+
+        Some(H2PushPromise {
+            uri,
+            request_headers,
+            status: parts.status,
+            response_headers: parts.headers,
+            body,
+        })
+    }
+}
+
+/// Returns true in case the response opts out of server push
+/// using the `Cache-Control: no-push` directive.
+fn is_push_disabled(headers: &HeaderMap) -> bool {
+    headers
+        .get_all(header::CACHE_CONTROL)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .any(|directive| directive.trim().eq_ignore_ascii_case("no-push"))
+}
+
+/// Iterate over the targets of all `Link` headers with a `preload` relation,
+/// skipping those marked with the `nopush` parameter.
+fn preload_links(headers: &HeaderMap) -> impl Iterator<Item = &str> {
+    headers
+        .get_all(header::LINK)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .filter_map(|link| {
+            let (target, params) = link.trim().strip_prefix('<')?.split_once('>')?;
+
+            let mut preload = false;
+            for param in params.split(';').map(str::trim) {
+                if param.eq_ignore_ascii_case("nopush") {
+                    return None;
+                }
+                if let Some((name, value)) = param.split_once('=')
+                    && name.trim().eq_ignore_ascii_case("rel")
+                {
+                    preload |= value
+                        .trim()
+                        .trim_matches('"')
+                        .split_ascii_whitespace()
+                        .any(|rel| rel.eq_ignore_ascii_case("preload"));
+                }
+            }
+
+            preload.then_some(target.trim())
+        })
+}
+
+/// Resolve the given link target against the uri of the request,
+/// returning `None` in case it is not relative or of the same origin.
+fn resolve_push_uri(base: &Uri, target: &str) -> Option<Uri> {
+    let scheme = base.scheme()?;
+    let authority = base.authority()?;
+
+    let target = target.split_once('#').map_or(target, |(target, _)| target);
+    if target.is_empty() || target.starts_with("//") {
+        return None;
+    }
+
+    let path_and_query = if target.starts_with('/') {
+        target.to_owned()
+    } else if target.contains("://") {
+        let uri: Uri = target.parse().ok()?;
+        if uri.scheme() != Some(scheme) || uri.authority() != Some(authority) {
+            return None;
+        }
+        uri.path_and_query()?.as_str().to_owned()
+    } else {
+        let base_path = base.path();
+        let dir = &base_path[..=base_path.rfind('/')?];
+        format!("{dir}{target}")
+    };
+
+    if path_and_query
+        .split(['/', '?'])
+        .any(|segment| segment == "..")
+    {
+        return None;
+    }
+
+    Uri::builder()
+        .scheme(scheme.clone())
+        .authority(authority.clone())
+        .path_and_query(path_and_query)
+        .build()
+        .ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Body, HeaderValue};
+    use rama_core::service::service_fn;
+    use std::convert::Infallible;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[test]
+    fn test_preload_links() {
+        let mut headers = HeaderMap::new();
+        headers.append(
+            header::LINK,
+            HeaderValue::from_static(
+                r#"</a.css>; rel=preload; as=style, </b.js>; rel="preload modulepreload""#,
+            ),
+        );
+        headers.append(
+            header::LINK,
+            HeaderValue::from_static("</c.js>; rel=preload; nopush"),
+        );
+        headers.append(
+            header::LINK,
+            HeaderValue::from_static("<https://example.com>; rel=preconnect"),
+        );
+        let links: Vec<_> = preload_links(&headers).collect();
+        assert_eq!(links, ["/a.css", "/b.js"]);
+    }
+
+    #[test]
+    fn test_resolve_push_uri() {
+        let base: Uri = "https://example.com/app/index.html?a=b".parse().unwrap();
+        for (target, expected) in [
+            ("/style.css", Some("https://example.com/style.css")),
+            (
+                "style.css?v=1",
+                Some("https://example.com/app/style.css?v=1"),
+            ),
+            ("/app.js#main", Some("https://example.com/app.js")),
+            (
+                "https://example.com/font.woff2",
+                Some("https://example.com/font.woff2"),
+            ),
+            ("https://cdn.example.com/font.woff2", None),
+            ("http://example.com/font.woff2", None),
+            ("//cdn.example.com/font.woff2", None),
+            ("../secret", None),
+            ("", None),
+        ] {
+            assert_eq!(
+                resolve_push_uri(&base, target).map(|uri| uri.to_string()),
+                expected.map(ToOwned::to_owned),
+                "target: {target}"
+            );
+        }
+
+        assert!(resolve_push_uri(&"/index.html".parse().unwrap(), "/style.css").is_none());
+    }
+
+    async fn handle(req: Request) -> Result<Response, Infallible> {
+        Ok(match req.uri().path() {
+            "/" => Response::builder()
+                .header(
+                    header::LINK,
+                    "</a.css>; rel=preload, </b.css>; rel=preload, </missing>; rel=preload",
+                )
+                .header(header::LINK, "</large.js>; rel=preload")
+                .body(Body::empty())
+                .unwrap(),
+            "/no-push" => Response::builder()
+                .header(header::LINK, "</a.css>; rel=preload")
+                .header(header::CACHE_CONTROL, "no-cache, no-push")
+                .body(Body::empty())
+                .unwrap(),
+            "/a.css" => {
+                assert_eq!(req.headers()[header::ACCEPT_ENCODING], "gzip");
+                Response::new(Body::from("a"))
+            }
+            "/b.css" => Response::new(Body::from("b")),
+            "/large.js" => Response::new(Body::from("large resource")),
+            _ => Response::builder().status(404).body(Body::empty()).unwrap(),
+        })
+    }
+
+    fn request(path: &str, version: Version) -> Request {
+        Request::builder()
+            .uri(format!("https://example.com{path}"))
+            .version(version)
+            .header(header::ACCEPT_ENCODING, "gzip")
+            .extension(H2PushEnabled)
+            .body(Body::empty())
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_h2_push() {
+        let svc = H2PushLayer::new()
+            .with_max_body_size(8)
+            .into_layer(service_fn(handle));
+
+        let res = svc
+            .serve(Context::default(), request("/", Version::HTTP_2))
+            .await
+            .unwrap();
+        let H2PushPromises(promises) = res.extensions().get().unwrap();
+        let pushed: Vec<_> = promises
+            .iter()
+            .map(|p| (p.uri.path(), p.body.as_ref()))
+            .collect();
+        assert_eq!(pushed, [("/a.css", &b"a"[..]), ("/b.css", &b"b"[..])]);
+        assert_eq!(promises[0].request_headers[header::ACCEPT_ENCODING], "gzip");
+
+        let svc = H2PushLayer::new()
+            .with_max_pushes(1)
+            .into_layer(service_fn(handle));
+        let res = svc
+            .serve(Context::default(), request("/", Version::HTTP_2))
+            .await
+            .unwrap();
+        let H2PushPromises(promises) = res.extensions().get().unwrap();
+        assert_eq!(promises.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_h2_push_skipped() {
+        let svc = H2PushLayer::new().into_layer(service_fn(handle));
+
+        let res = svc
+            .serve(Context::default(), request("/", Version::HTTP_11))
+            .await
+            .unwrap();
+        assert!(res.extensions().get::<H2PushPromises>().is_none());
+
+        let res = svc
+            .serve(Context::default(), request("/no-push", Version::HTTP_2))
+            .await
+            .unwrap();
+        assert!(res.extensions().get::<H2PushPromises>().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_h2_push_disabled_by_client() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let svc = H2PushLayer::new().into_layer(service_fn({
+            let calls = calls.clone();
+            move |req| {
+                calls.fetch_add(1, Ordering::SeqCst);
+                handle(req)
+            }
+        }));
+
+        let mut req = request("/", Version::HTTP_2);
+        req.extensions_mut().remove::<H2PushEnabled>();
+        let res = svc.serve(Context::default(), req).await.unwrap();
+        assert!(res.extensions().get::<H2PushPromises>().is_none());
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+}
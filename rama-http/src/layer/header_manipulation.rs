@@ -0,0 +1,420 @@
+//! Middleware that inserts and removes request and response headers.
+//!
+//! Where the [`set_header`] and [`remove_header`] middlewares each apply a single
+//! operation, the [`HeaderManipulationLayer`] groups any amount of header
+//! operations in a single middleware, applied in the order they were added.
+//! This is useful for proxies, e.g. to strip internal headers before forwarding
+//! a request to an untrusted origin, or to inject authentication headers downstream.
+//!
+//! Request header values can be computed dynamically from the [`Context`]
+//! and request using a [`HeaderValueFn`]. As the [`Forwarded`] information is
+//! inserted in the [`Context`] by the [`GetForwardedHeaderLayer`] (or
+//! [`GetForwardedHeadersLayer`]), it can be used
+//! to compute header values, as long as that layer is applied first.
+//!
+//! # Example
+//!
+//! ```
+//! use rama_core::service::service_fn;
+//! use rama_core::{Context, Layer, Service};
+//! use rama_http::layer::forwarded::GetForwardedHeaderLayer;
+//! use rama_http::layer::header_manipulation::{HeaderManipulationLayer, HeaderValueFn};
+//! use rama_http::{Body, HeaderName, HeaderValue, Request, Response, header};
+//! use rama_net::forwarded::Forwarded;
+//! use std::convert::Infallible;
+//!
+//! # #[tokio::main]
+//! # async fn main() {
+//! let svc = (
+//!     GetForwardedHeaderLayer::x_forwarded_for(),
+//!     HeaderManipulationLayer::new()
+//!         .remove_request_header(header::X_FORWARDED_FOR.clone())
+//!         .insert_request_header_fn(
+//!             HeaderName::from_static("x-client-ip"),
+//!             HeaderValueFn::new(|ctx: &Context<()>, _parts| {
+//!                 let ip = ctx.get::<Forwarded>()?.client_ip()?;
+//!                 HeaderValue::try_from(ip.to_string()).ok()
+//!             }),
+//!         )
+//!         .remove_response_header(header::SERVER),
+//! )
+//!     .into_layer(service_fn(async |req: Request| {
+//!         assert!(!req.headers().contains_key(&header::X_FORWARDED_FOR));
+//!         assert_eq!(req.headers()["x-client-ip"], "12.34.56.78");
+//!         Ok::<_, Infallible>(
+//!             Response::builder()
+//!                 .header(header::SERVER, "internal")
+//!                 .body(Body::empty())
+//!                 .unwrap(),
+//!         )
+//!     }));
+//!
+//! let req = Request::builder()
+//!     .header(header::X_FORWARDED_FOR.clone(), "12.34.56.78")
+//!     .body(Body::empty())
+//!     .unwrap();
+//! let res = svc.serve(Context::default(), req).await.unwrap();
+//! assert!(!res.headers().contains_key(header::SERVER));
+//! # }
+//! ```
+//!
+//! [`set_header`]: super::set_header
+//! [`remove_header`]: super::remove_header
+//! [`Forwarded`]: rama_net::forwarded::Forwarded
+//! [`GetForwardedHeaderLayer`]: super::forwarded::GetForwardedHeaderLayer
+//! [`GetForwardedHeadersLayer`]: super::forwarded::GetForwardedHeadersLayer
+
+use crate::dep::http::request::Parts;
+use crate::{HeaderMap, HeaderName, HeaderValue, Request, Response};
+use rama_core::{Context, Layer, Service};
+use rama_utils::macros::define_inner_service_accessors;
+use std::fmt;
+use std::sync::Arc;
+
+/// Function used to compute a request header value
+/// from the [`Context`] and request parts.
+///
+/// The header is not inserted in case `None` is returned.
+pub struct HeaderValueFn<State>(
+    Arc<dyn Fn(&Context<State>, &Parts) -> Option<HeaderValue> + Send + Sync>,
+);
+
+impl<State> HeaderValueFn<State> {
+    /// Create a new [`HeaderValueFn`].
+    pub fn new(
+        f: impl Fn(&Context<State>, &Parts) -> Option<HeaderValue> + Send + Sync + 'static,
+    ) -> Self {
+        Self(Arc::new(f))
+    }
+}
+
+impl<State> Clone for HeaderValueFn<State> {
+    fn clone(&self) -> Self {
+        Self(self.0.clone())
+    }
+}
+
+impl<State> fmt::Debug for HeaderValueFn<State> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("HeaderValueFn").finish()
+    }
+}
+
+enum RequestHeaderOp<State> {
+    Insert(HeaderName, HeaderValue),
+    InsertFn(HeaderName, HeaderValueFn<State>),
+    Remove(HeaderName),
+}
+
+impl<State> Clone for RequestHeaderOp<State> {
+    fn clone(&self) -> Self {
+        match self {
+            Self::Insert(name, value) => Self::Insert(name.clone(), value.clone()),
+            Self::InsertFn(name, f) => Self::InsertFn(name.clone(), f.clone()),
+            Self::Remove(name) => Self::Remove(name.clone()),
+        }
+    }
+}
+
+impl<State> fmt::Debug for RequestHeaderOp<State> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Insert(name, value) => f.debug_tuple("Insert").field(name).field(value).finish(),
+            Self::InsertFn(name, value_fn) => f
+                .debug_tuple("InsertFn")
+                .field(name)
+                .field(value_fn)
+                .finish(),
+            Self::Remove(name) => f.debug_tuple("Remove").field(name).finish(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+enum ResponseHeaderOp {
+    Insert(HeaderName, HeaderValue),
+    Remove(HeaderName),
+}
+
+struct HeaderOps<State> {
+    request: Vec<RequestHeaderOp<State>>,
+    response: Vec<ResponseHeaderOp>,
+}
+
+impl<State> HeaderOps<State> {
+    fn apply_request(&self, ctx: &Context<State>, parts: &mut Parts) {
+        for op in &self.request {
+            match op {
+                RequestHeaderOp::Insert(name, value) => {
+                    parts.headers.insert(name.clone(), value.clone());
+                }
+                RequestHeaderOp::InsertFn(name, value_fn) => {
+                    if let Some(value) = (value_fn.0)(ctx, parts) {
+                        parts.headers.insert(name.clone(), value);
+                    }
+                }
+                RequestHeaderOp::Remove(name) => {
+                    parts.headers.remove(name);
+                }
+            }
+        }
+    }
+
+    fn apply_response(&self, headers: &mut HeaderMap) {
+        for op in &self.response {
+            match op {
+                ResponseHeaderOp::Insert(name, value) => {
+                    headers.insert(name.clone(), value.clone());
+                }
+                ResponseHeaderOp::Remove(name) => {
+                    headers.remove(name);
+                }
+            }
+        }
+    }
+}
+
+impl<State> Clone for HeaderOps<State> {
+    fn clone(&self) -> Self {
+        Self {
+            request: self.request.clone(),
+            response: self.response.clone(),
+        }
+    }
+}
+
+impl<State> fmt::Debug for HeaderOps<State> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("HeaderOps")
+            .field("request", &self.request)
+            .field("response", &self.response)
+            .finish()
+    }
+}
+
+/// Layer that applies the [`HeaderManipulationService`] middleware,
+/// inserting and removing request and response headers.
+///
+/// See the [module docs](self) for an example.
+pub struct HeaderManipulationLayer<State = ()> {
+    ops: HeaderOps<State>,
+}
+
+impl<State> HeaderManipulationLayer<State> {
+    /// Create a new [`HeaderManipulationLayer`] without any header operations.
+    pub const fn new() -> Self {
+        Self {
+            ops: HeaderOps {
+                request: Vec::new(),
+                response: Vec::new(),
+            },
+        }
+    }
+
+    /// Insert the given request header, replacing any existing values.
+    pub fn insert_request_header(mut self, name: HeaderName, value: HeaderValue) -> Self {
+        self.ops.request.push(RequestHeaderOp::Insert(name, value));
+        self
+    }
+
+    /// Insert the request header computed by the given [`HeaderValueFn`],
+    /// replacing any existing values.
+    ///
+    /// No header is inserted in case the function returns `None`.
+    pub fn insert_request_header_fn(
+        mut self,
+        name: HeaderName,
+        value_fn: HeaderValueFn<State>,
+    ) -> Self {
+        self.ops
+            .request
+            .push(RequestHeaderOp::InsertFn(name, value_fn));
+        self
+    }
+
+    /// Remove all values of the given request header.
+    pub fn remove_request_header(mut self, name: HeaderName) -> Self {
+        self.ops.request.push(RequestHeaderOp::Remove(name));
+        self
+    }
+
+    /// Insert the given response header, replacing any existing values.
+    pub fn insert_response_header(mut self, name: HeaderName, value: HeaderValue) -> Self {
+        self.ops
+            .response
+            .push(ResponseHeaderOp::Insert(name, value));
+        self
+    }
+
+    /// Remove all values of the given response header.
+    pub fn remove_response_header(mut self, name: HeaderName) -> Self {
+        self.ops.response.push(ResponseHeaderOp::Remove(name));
+        self
+    }
+}
+
+impl<State> Default for HeaderManipulationLayer<State> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<State> Clone for HeaderManipulationLayer<State> {
+    fn clone(&self) -> Self {
+        Self {
+            ops: self.ops.clone(),
+        }
+    }
+}
+
+impl<State> fmt::Debug for HeaderManipulationLayer<State> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("HeaderManipulationLayer")
+            .field("ops", &self.ops)
+            .finish()
+    }
+}
+
+impl<S, State> Layer<S> for HeaderManipulationLayer<State> {
+    type Service = HeaderManipulationService<S, State>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        HeaderManipulationService {
+            inner,
+            ops: Arc::new(self.ops.clone()),
+        }
+    }
+
+    fn into_layer(self, inner: S) -> Self::Service {
+        HeaderManipulationService {
+            inner,
+            ops: Arc::new(self.ops),
+        }
+    }
+}
+
+/// Middleware which inserts and removes request and response headers.
+///
+/// Created using the [`HeaderManipulationLayer`].
+/// See the [module docs](self) for an example.
+pub struct HeaderManipulationService<S, State = ()> {
+    inner: S,
+    ops: Arc<HeaderOps<State>>,
+}
+
+impl<S, State> HeaderManipulationService<S, State> {
+    define_inner_service_accessors!();
+}
+
+impl<S: Clone, State> Clone for HeaderManipulationService<S, State> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            ops: self.ops.clone(),
+        }
+    }
+}
+
+impl<S: fmt::Debug, State> fmt::Debug for HeaderManipulationService<S, State> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("HeaderManipulationService")
+            .field("inner", &self.inner)
+            .field("ops", &self.ops)
+            .finish()
+    }
+}
+
+impl<State, S, ReqBody, ResBody> Service<State, Request<ReqBody>>
+    for HeaderManipulationService<S, State>
+where
+    State: Clone + Send + Sync + 'static,
+    S: Service<State, Request<ReqBody>, Response = Response<ResBody>>,
+    ReqBody: Send + 'static,
+    ResBody: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+
+    async fn serve(
+        &self,
+        ctx: Context<State>,
+        req: Request<ReqBody>,
+    ) -> Result<Self::Response, Self::Error> {
+        let (mut parts, body) = req.into_parts();
+        self.ops.apply_request(&ctx, &mut parts);
+        let req = Request::from_parts(parts, body);
+
+        let mut res = self.inner.serve(ctx, req).await?;
+        self.ops.apply_response(res.headers_mut());
+        Ok(res)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{Body, header};
+    use rama_core::service::service_fn;
+    use std::convert::Infallible;
+
+    #[derive(Debug, Clone)]
+    struct Tenant(&'static str);
+
+    #[tokio::test]
+    async fn header_manipulation() {
+        let svc = HeaderManipulationLayer::new()
+            .remove_request_header(header::AUTHORIZATION)
+            .insert_request_header(
+                HeaderName::from_static("x-internal"),
+                HeaderValue::from_static("1"),
+            )
+            .insert_request_header_fn(
+                HeaderName::from_static("x-tenant"),
+                HeaderValueFn::new(|ctx: &Context<()>, _parts| {
+                    ctx.get::<Tenant>()
+                        .map(|tenant| HeaderValue::from_static(tenant.0))
+                }),
+            )
+            .insert_request_header_fn(
+                HeaderName::from_static("x-path"),
+                HeaderValueFn::new(|_ctx, parts| HeaderValue::try_from(parts.uri.path()).ok()),
+            )
+            .insert_response_header(header::CACHE_CONTROL, HeaderValue::from_static("no-store"))
+            .remove_response_header(HeaderName::from_static("x-internal"))
+            .into_layer(service_fn(async |req: Request| {
+                assert!(!req.headers().contains_key(header::AUTHORIZATION));
+                assert_eq!(req.headers()["x-internal"], "1");
+                assert_eq!(req.headers()["x-path"], "/foo");
+
+                let mut res = Response::new(Body::empty());
+                if let Some(tenant) = req.headers().get("x-tenant") {
+                    res.headers_mut().insert("x-tenant", tenant.clone());
+                }
+                res.headers_mut()
+                    .insert("x-internal", HeaderValue::from_static("1"));
+                res.headers_mut()
+                    .insert(header::CACHE_CONTROL, HeaderValue::from_static("public"));
+                Ok::<_, Infallible>(res)
+            }));
+
+        let request = || {
+            Request::builder()
+                .uri("/foo")
+                .header(header::AUTHORIZATION, "secret")
+                .header("x-internal", "0")
+                .body(Body::empty())
+                .unwrap()
+        };
+
+        let res = svc.serve(Context::default(), request()).await.unwrap();
+        assert!(!res.headers().contains_key("x-tenant"));
+        assert!(!res.headers().contains_key("x-internal"));
+        assert_eq!(res.headers()[header::CACHE_CONTROL], "no-store");
+
+        let mut ctx = Context::default();
+        ctx.insert(Tenant("acme"));
+        let res = svc.serve(ctx, request()).await.unwrap();
+        assert_eq!(res.headers()["x-tenant"], "acme");
+    }
+}
@@ -0,0 +1,141 @@
+use super::TeeBody;
+use crate::dep::http_body;
+use crate::{Body, Request, Response};
+use rama_core::bytes::Bytes;
+use rama_core::error::BoxError;
+use rama_core::telemetry::tracing;
+use rama_core::{Context, Layer, Service};
+use rama_utils::macros::define_inner_service_accessors;
+use std::fmt;
+
+/// Layer that applies the [`ErrorBodyLogService`] middleware.
+///
+/// See the [module docs](super) for more details.
+#[derive(Debug, Clone)]
+pub struct ErrorBodyLogLayer {
+    max_bytes: usize,
+}
+
+impl ErrorBodyLogLayer {
+    /// Create a new [`ErrorBodyLogLayer`], logging up to `max_bytes`
+    /// of the body of each error response.
+    pub const fn new(max_bytes: usize) -> Self {
+        Self { max_bytes }
+    }
+}
+
+impl<S> Layer<S> for ErrorBodyLogLayer {
+    type Service = ErrorBodyLogService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        ErrorBodyLogService::new(inner, self.max_bytes)
+    }
+}
+
+/// Middleware which logs (the start of) the body of error responses.
+///
+/// See the [module docs](super) for more details.
+pub struct ErrorBodyLogService<S> {
+    inner: S,
+    max_bytes: usize,
+}
+
+impl<S> ErrorBodyLogService<S> {
+    /// Create a new [`ErrorBodyLogService`], logging up to `max_bytes`
+    /// of the body of each error response.
+    pub const fn new(inner: S, max_bytes: usize) -> Self {
+        Self { inner, max_bytes }
+    }
+
+    define_inner_service_accessors!();
+}
+
+impl<S: fmt::Debug> fmt::Debug for ErrorBodyLogService<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ErrorBodyLogService")
+            .field("inner", &self.inner)
+            .field("max_bytes", &self.max_bytes)
+            .finish()
+    }
+}
+
+impl<S: Clone> Clone for ErrorBodyLogService<S> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            max_bytes: self.max_bytes,
+        }
+    }
+}
+
+impl<State, S, ReqBody, ResBody> Service<State, Request<ReqBody>> for ErrorBodyLogService<S>
+where
+    State: Clone + Send + Sync + 'static,
+    S: Service<State, Request<ReqBody>, Response = Response<ResBody>>,
+    ReqBody: Send + 'static,
+    ResBody: http_body::Body<Data = Bytes, Error: Into<BoxError>> + Send + Sync + 'static,
+{
+    type Response = Response;
+    type Error = S::Error;
+
+    async fn serve(
+        &self,
+        ctx: Context<State>,
+        req: Request<ReqBody>,
+    ) -> Result<Self::Response, Self::Error> {
+        let method = req.method().clone();
+        let uri = req.uri().clone();
+
+        let res = self.inner.serve(ctx, req).await?;
+
+        let status = res.status();
+        if !status.is_client_error() && !status.is_server_error() {
+            return Ok(res.map(Body::new));
+        }
+
+        Ok(res.map(|body| {
+            Body::new(TeeBody::new(body, self.max_bytes, move |data| {
+                tracing::error!(
+                    http.request.method = %method,
+                    url.full = %uri,
+                    http.response.status_code = status.as_u16(),
+                    "error response body: {}",
+                    String::from_utf8_lossy(&data),
+                );
+            }))
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::StatusCode;
+    use crate::dep::http_body_util::BodyExt;
+    use rama_core::service::service_fn;
+    use std::convert::Infallible;
+
+    #[tokio::test]
+    async fn test_error_body_log_replays_body() {
+        for status in [
+            StatusCode::OK,
+            StatusCode::NOT_FOUND,
+            StatusCode::BAD_GATEWAY,
+        ] {
+            let svc =
+                ErrorBodyLogLayer::new(4).into_layer(service_fn(move |_req: Request| async move {
+                    let mut res = Response::new(Body::from("upstream says no"));
+                    *res.status_mut() = status;
+                    Ok::<_, Infallible>(res)
+                }));
+
+            let res = svc
+                .serve(Context::default(), Request::new(Body::empty()))
+                .await
+                .unwrap();
+            assert_eq!(res.status(), status);
+            let body = res.into_body().collect().await.unwrap().to_bytes();
+            assert_eq!(body, "upstream says no", "{status}");
+        }
+    }
+}
@@ -0,0 +1,32 @@
+//! Middleware which logs details of responses.
+//!
+//! When a proxied upstream returns a `4xx` or `5xx` response, its body often
+//! contains useful diagnostic information. The [`ErrorBodyLogLayer`] logs up to
+//! `max_bytes` of the body of such responses as an `ERROR` level tracing event.
+//!
+//! The body is not buffered upfront: using a [`TeeBody`] the data is copied
+//! while it is passed on to the caller, such that the response body is
+//! received as-is. The event is emitted as soon as `max_bytes` are copied,
+//! or when the body ends, fails or is dropped before that.
+//!
+//! # Example
+//!
+//! ```
+//! use rama_http::layer::log::ErrorBodyLogLayer;
+//! use rama_http::{Body, Request, Response};
+//! use rama_core::{Layer, service::service_fn};
+//! use std::convert::Infallible;
+//!
+//! let svc = ErrorBodyLogLayer::new(1024)
+//!     .into_layer(service_fn(async |_req: Request| {
+//!         Ok::<_, Infallible>(Response::new(Body::empty()))
+//!     }));
+//! ```
+
+mod tee;
+#[doc(inline)]
+pub use tee::TeeBody;
+
+mod error_body;
+#[doc(inline)]
+pub use error_body::{ErrorBodyLogLayer, ErrorBodyLogService};
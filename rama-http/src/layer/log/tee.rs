@@ -0,0 +1,169 @@
+use crate::dep::http_body::{Body, Frame, SizeHint};
+use pin_project_lite::pin_project;
+use rama_core::bytes::{Bytes, BytesMut};
+use std::{
+    fmt,
+    pin::Pin,
+    task::{Context, Poll, ready},
+};
+
+type OnComplete = Box<dyn FnOnce(Bytes) + Send + Sync + 'static>;
+
+pin_project! {
+    /// Wrapper around a [`Body`] which copies (up to `max_bytes` of) its data
+    /// into a buffer, while passing all frames downstream as-is.
+    ///
+    /// The buffered data is given to the `on_complete` callback once
+    /// the buffer is full, the body is fully consumed, the body
+    /// returns an error or the body is dropped, whichever comes first.
+    pub struct TeeBody<B> {
+        #[pin]
+        body: B,
+        buffer: BytesMut,
+        max_bytes: usize,
+        on_complete: Option<OnComplete>,
+    }
+
+    impl<B> PinnedDrop for TeeBody<B> {
+        fn drop(this: Pin<&mut Self>) {
+            let this = this.project();
+            complete(this.on_complete, this.buffer);
+        }
+    }
+}
+
+impl<B> TeeBody<B> {
+    /// Creates a new [`TeeBody`].
+    pub fn new(
+        body: B,
+        max_bytes: usize,
+        on_complete: impl FnOnce(Bytes) + Send + Sync + 'static,
+    ) -> Self {
+        Self {
+            body,
+            buffer: BytesMut::new(),
+            max_bytes,
+            on_complete: Some(Box::new(on_complete)),
+        }
+    }
+}
+
+impl<B: fmt::Debug> fmt::Debug for TeeBody<B> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TeeBody")
+            .field("body", &self.body)
+            .field("buffered", &self.buffer.len())
+            .field("max_bytes", &self.max_bytes)
+            .finish()
+    }
+}
+
+fn complete(on_complete: &mut Option<OnComplete>, buffer: &mut BytesMut) {
+    if let Some(on_complete) = on_complete.take() {
+        on_complete(std::mem::take(buffer).freeze());
+    }
+}
+
+impl<B> Body for TeeBody<B>
+where
+    B: Body<Data = Bytes>,
+{
+    type Data = Bytes;
+    type Error = B::Error;
+
+    fn poll_frame(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
+        let this = self.project();
+        let frame = ready!(this.body.poll_frame(cx));
+
+        match &frame {
+            Some(Ok(frame)) => {
+                if let Some(data) = frame.data_ref().filter(|_| this.on_complete.is_some()) {
+                    let remaining = this.max_bytes.saturating_sub(this.buffer.len());
+                    this.buffer
+                        .extend_from_slice(&data[..data.len().min(remaining)]);
+                    if this.buffer.len() >= *this.max_bytes {
+                        complete(this.on_complete, this.buffer);
+                    }
+                }
+            }
+            Some(Err(_)) | None => complete(this.on_complete, this.buffer),
+        }
+
+        Poll::Ready(frame)
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.body.is_end_stream()
+    }
+
+    fn size_hint(&self) -> SizeHint {
+        self.body.size_hint()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dep::http_body_util::{BodyExt, StreamBody};
+    use std::convert::Infallible;
+    use std::sync::{Arc, Mutex};
+
+    fn chunked_body(
+        chunks: &'static [&'static str],
+    ) -> impl Body<Data = Bytes, Error = Infallible> {
+        StreamBody::new(rama_core::futures::stream::iter(
+            chunks
+                .iter()
+                .map(|chunk| Ok(Frame::data(Bytes::from_static(chunk.as_bytes())))),
+        ))
+    }
+
+    fn tee(
+        chunks: &'static [&'static str],
+        max_bytes: usize,
+    ) -> (
+        TeeBody<impl Body<Data = Bytes, Error = Infallible>>,
+        Arc<Mutex<Option<Bytes>>>,
+    ) {
+        let teed = Arc::new(Mutex::new(None));
+        let body = TeeBody::new(chunked_body(chunks), max_bytes, {
+            let teed = teed.clone();
+            move |data| *teed.lock().unwrap() = Some(data)
+        });
+        (body, teed)
+    }
+
+    #[tokio::test]
+    async fn test_tee_body_passthrough() {
+        for max_bytes in [0, 4, 11, 64] {
+            let (body, teed) = tee(&["hello", " ", "world"], max_bytes);
+            let data = body.collect().await.unwrap().to_bytes();
+            assert_eq!(data, "hello world", "max_bytes={max_bytes}");
+            assert_eq!(
+                teed.lock().unwrap().as_deref(),
+                Some(&b"hello world"[..max_bytes.min(11)]),
+                "max_bytes={max_bytes}"
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_tee_body_complete_when_full() {
+        let (mut body, teed) = tee(&["hello", " ", "world"], 3);
+        let frame = body.frame().await.unwrap().unwrap();
+        assert_eq!(frame.into_data().unwrap(), "hello");
+        assert_eq!(teed.lock().unwrap().as_deref(), Some(&b"hel"[..]));
+    }
+
+    #[tokio::test]
+    async fn test_tee_body_complete_on_drop() {
+        let (mut body, teed) = tee(&["hello", " ", "world"], 64);
+        let _ = body.frame().await.unwrap().unwrap();
+        assert!(teed.lock().unwrap().is_none());
+        drop(body);
+        assert_eq!(teed.lock().unwrap().as_deref(), Some(&b"hello"[..]));
+    }
+}
@@ -0,0 +1,7 @@
+//! Http metrics [`Layer`]s.
+//!
+//! Requires the `prometheus` feature.
+//!
+//! [`Layer`]: rama_core::Layer
+
+pub mod prometheus;
@@ -0,0 +1,295 @@
+use opentelemetry_sdk::error::OTelSdkResult;
+use opentelemetry_sdk::metrics::data::{
+    AggregatedMetrics, Histogram, Metric, MetricData, ResourceMetrics, Sum,
+};
+use opentelemetry_sdk::metrics::reader::MetricReader;
+use opentelemetry_sdk::metrics::{InstrumentKind, ManualReader, Pipeline, Temporality};
+use rama_core::error::{ErrorContext, OpaqueError};
+use rama_core::telemetry::opentelemetry::KeyValue;
+use rama_core::telemetry::tracing;
+use std::fmt::{self, Display, Write};
+use std::sync::{Arc, Weak};
+use std::time::Duration;
+
+#[derive(Debug, Clone, Default)]
+/// A pull based OpenTelemetry [`MetricReader`], which renders
+/// the metrics of the meter provider it is registered with
+/// in the Prometheus text exposition format.
+///
+/// Clones share the same reader, and thus can be registered only once,
+/// using [`SdkMeterProviderBuilder::with_reader`].
+///
+/// [`SdkMeterProviderBuilder::with_reader`]: opentelemetry_sdk::metrics::MeterProviderBuilder::with_reader
+pub struct PrometheusExporter {
+    reader: Arc<ManualReader>,
+}
+
+impl PrometheusExporter {
+    /// Create a new [`PrometheusExporter`].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Collect all metrics of the registered meter provider
+    /// and render them in the Prometheus text exposition format.
+    ///
+    /// Counters and gauges are rendered as-is, histograms as cumulative buckets,
+    /// exponential histograms are not supported and skipped.
+    pub fn render(&self) -> Result<String, OpaqueError> {
+        let mut metrics = ResourceMetrics::default();
+        self.reader
+            .collect(&mut metrics)
+            .context("collect prometheus metrics")?;
+
+        let mut out = String::new();
+        for scope in metrics.scope_metrics() {
+            for metric in scope.metrics() {
+                // writing to a String cannot fail
+                let _ = render_metric(&mut out, metric);
+            }
+        }
+        Ok(out)
+    }
+}
+
+impl MetricReader for PrometheusExporter {
+    fn register_pipeline(&self, pipeline: Weak<Pipeline>) {
+        self.reader.register_pipeline(pipeline)
+    }
+
+    fn collect(&self, rm: &mut ResourceMetrics) -> OTelSdkResult {
+        self.reader.collect(rm)
+    }
+
+    fn force_flush(&self) -> OTelSdkResult {
+        self.reader.force_flush()
+    }
+
+    fn shutdown_with_timeout(&self, timeout: Duration) -> OTelSdkResult {
+        self.reader.shutdown_with_timeout(timeout)
+    }
+
+    fn temporality(&self, kind: InstrumentKind) -> Temporality {
+        self.reader.temporality(kind)
+    }
+}
+
+fn render_metric(out: &mut String, metric: &Metric) -> fmt::Result {
+    let name = MetricName(metric.name());
+    match metric.data() {
+        AggregatedMetrics::F64(data) => render_data(out, name, metric.description(), data),
+        AggregatedMetrics::U64(data) => render_data(out, name, metric.description(), data),
+        AggregatedMetrics::I64(data) => render_data(out, name, metric.description(), data),
+    }
+}
+
+fn render_data<T: Display + Copy>(
+    out: &mut String,
+    name: MetricName<'_>,
+    description: &str,
+    data: &MetricData<T>,
+) -> fmt::Result {
+    match data {
+        MetricData::Sum(sum) => render_sum(out, name, description, sum),
+        MetricData::Gauge(gauge) => {
+            render_header(out, name, description, "gauge")?;
+            for point in gauge.data_points() {
+                let attributes: Vec<_> = point.attributes().collect();
+                writeln!(out, "{name}{} {}", Labels(&attributes, None), point.value())?;
+            }
+            Ok(())
+        }
+        MetricData::Histogram(histogram) => render_histogram(out, name, description, histogram),
+        MetricData::ExponentialHistogram(_) => {
+            tracing::debug!("skip unsupported exponential histogram metric {name}");
+            Ok(())
+        }
+    }
+}
+
+fn render_sum<T: Display + Copy>(
+    out: &mut String,
+    name: MetricName<'_>,
+    description: &str,
+    sum: &Sum<T>,
+) -> fmt::Result {
+    let kind = if sum.is_monotonic() {
+        "counter"
+    } else {
+        "gauge"
+    };
+    render_header(out, name, description, kind)?;
+    for point in sum.data_points() {
+        let attributes: Vec<_> = point.attributes().collect();
+        writeln!(out, "{name}{} {}", Labels(&attributes, None), point.value())?;
+    }
+    Ok(())
+}
+
+fn render_histogram<T: Display + Copy>(
+    out: &mut String,
+    name: MetricName<'_>,
+    description: &str,
+    histogram: &Histogram<T>,
+) -> fmt::Result {
+    render_header(out, name, description, "histogram")?;
+    for point in histogram.data_points() {
+        let attributes: Vec<_> = point.attributes().collect();
+        let mut cumulative = 0;
+        for (bound, count) in point.bounds().zip(point.bucket_counts()) {
+            cumulative += count;
+            writeln!(
+                out,
+                "{name}_bucket{} {cumulative}",
+                Labels(&attributes, Some(&bound))
+            )?;
+        }
+        writeln!(
+            out,
+            "{name}_bucket{} {}",
+            Labels(&attributes, Some(&"+Inf")),
+            point.count()
+        )?;
+        let labels = Labels(&attributes, None);
+        writeln!(out, "{name}_sum{labels} {}", point.sum())?;
+        writeln!(out, "{name}_count{labels} {}", point.count())?;
+    }
+    Ok(())
+}
+
+fn render_header(
+    out: &mut String,
+    name: MetricName<'_>,
+    description: &str,
+    kind: &str,
+) -> fmt::Result {
+    if !description.is_empty() {
+        writeln!(out, "# HELP {name} {}", Escaped(description, false))?;
+    }
+    writeln!(out, "# TYPE {name} {kind}")
+}
+
+/// A metric (or label) name, with all characters not allowed
+/// by the text exposition format replaced by an underscore.
+#[derive(Clone, Copy)]
+struct MetricName<'a>(&'a str);
+
+impl fmt::Display for MetricName<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, c) in self.0.chars().enumerate() {
+            if c.is_ascii_alphabetic() || c == '_' || c == ':' || (i > 0 && c.is_ascii_digit()) {
+                f.write_char(c)?;
+            } else {
+                f.write_char('_')?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The labels of a data point, optionally followed by the `le` label of a histogram bucket.
+struct Labels<'a>(&'a [&'a KeyValue], Option<&'a dyn Display>);
+
+impl fmt::Display for Labels<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.0.is_empty() && self.1.is_none() {
+            return Ok(());
+        }
+
+        f.write_char('{')?;
+        let mut first = true;
+        for label in self.0 {
+            if !first {
+                f.write_char(',')?;
+            }
+            first = false;
+            write!(
+                f,
+                "{}=\"{}\"",
+                MetricName(label.key.as_str()),
+                Escaped(&label.value.as_str(), true)
+            )?;
+        }
+        if let Some(le) = self.1 {
+            if !first {
+                f.write_char(',')?;
+            }
+            write!(f, "le=\"{le}\"")?;
+        }
+        f.write_char('}')
+    }
+}
+
+/// Escapes a label value (or help text) as required by the text exposition format.
+struct Escaped<'a>(&'a str, bool);
+
+impl fmt::Display for Escaped<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for c in self.0.chars() {
+            match c {
+                '\\' => f.write_str("\\\\")?,
+                '"' if self.1 => f.write_str("\\\"")?,
+                '\n' => f.write_str("\\n")?,
+                c => f.write_char(c)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use opentelemetry_sdk::metrics::SdkMeterProvider;
+    use rama_core::telemetry::opentelemetry::metrics::MeterProvider as _;
+
+    #[test]
+    fn test_render() {
+        let exporter = PrometheusExporter::new();
+        let provider = SdkMeterProvider::builder()
+            .with_reader(exporter.clone())
+            .build();
+        let meter = provider.meter("test");
+
+        let counter = meter.u64_counter("requests_total").build();
+        counter.add(2, &[KeyValue::new("path", "say \"hi\"\\")]);
+        counter.add(1, &[]);
+
+        let gauge = meter
+            .i64_up_down_counter("in.flight")
+            .with_description("In flight\nrequests.")
+            .build();
+        gauge.add(3, &[]);
+
+        let histogram = meter
+            .f64_histogram("duration_seconds")
+            .with_boundaries(vec![0.1, 1.0])
+            .build();
+        for value in [0.25, 0.5, 4.0] {
+            histogram.record(value, &[KeyValue::new("method", "GET")]);
+        }
+
+        let out = exporter.render().unwrap();
+        for line in [
+            "# TYPE requests_total counter",
+            r#"requests_total{path="say \"hi\"\\"} 2"#,
+            "requests_total 1",
+            r"# HELP in_flight In flight\nrequests.",
+            "# TYPE in_flight gauge",
+            "in_flight 3",
+            "# TYPE duration_seconds histogram",
+            r#"duration_seconds_bucket{method="GET",le="0.1"} 0"#,
+            r#"duration_seconds_bucket{method="GET",le="1"} 2"#,
+            r#"duration_seconds_bucket{method="GET",le="+Inf"} 3"#,
+            r#"duration_seconds_sum{method="GET"} 4.75"#,
+            r#"duration_seconds_count{method="GET"} 3"#,
+        ] {
+            assert!(out.lines().any(|l| l == line), "missing {line} in:\n{out}");
+        }
+    }
+
+    #[test]
+    fn test_render_unregistered() {
+        assert!(PrometheusExporter::new().render().is_err());
+    }
+}
@@ -0,0 +1,462 @@
+//! Middleware to record http metrics using an OpenTelemetry meter,
+//! exposed in the [Prometheus] text format by a [`PrometheusExporter`].
+//!
+//! The following metrics are recorded:
+//!
+//! - `http_requests_total{method, path, status}`: counter of all requests;
+//! - `http_request_duration_seconds{method, path}`: histogram of the time
+//!   it took for the inner service to produce a response;
+//! - `http_request_body_bytes{method, path}`: histogram of the request body sizes;
+//! - `http_response_body_bytes{method, path}`: histogram of the response body sizes.
+//!
+//! Body sizes are only recorded when known upfront, from the exact size hint of
+//! the body or its `Content-Length` header.
+//!
+//! To keep the cardinality of the metrics bounded, the `path` label is never the raw path,
+//! but the first matching route template (e.g. `/users/{id}`) registered using
+//! [`PrometheusLayer::with_route`]. Requests matching no route are labeled as `<other>`.
+//!
+//! The metrics are served (by default) on `GET /metrics`. By default the
+//! [`PrometheusLayer`] records them using its own meter provider, use
+//! [`PrometheusLayer::with_meter_provider`] to record them using a meter provider
+//! which also exports them elsewhere (e.g. using OTLP).
+//!
+//! [Prometheus]: https://prometheus.io/docs/instrumenting/exposition_formats/
+//!
+//! # Example
+//!
+//! ```
+//! use rama_core::{Context, Layer, Service};
+//! use rama_http::layer::metrics::prometheus::PrometheusLayer;
+//! use rama_http::service::web::WebService;
+//! use rama_http::{Body, BodyExtractExt, Request};
+//!
+//! # #[tokio::main]
+//! # async fn main() {
+//! let service = PrometheusLayer::new()
+//!     .with_route("/users/{id}")
+//!     .into_layer(WebService::default().get("/users/{id}", "user"));
+//!
+//! let req = Request::get("/users/42").body(Body::empty()).unwrap();
+//! service.serve(Context::default(), req).await.unwrap();
+//!
+//! let req = Request::get("/metrics").body(Body::empty()).unwrap();
+//! let res = service.serve(Context::default(), req).await.unwrap();
+//! let metrics = res.into_body().try_into_string().await.unwrap();
+//! assert!(metrics.contains(r#"http_requests_total{method="GET",path="/users/{id}",status="200"} 1"#));
+//! # }
+//! ```
+
+use crate::dep::http_body::Body as HttpBody;
+use crate::matcher::PathMatcher;
+use crate::service::web::response::IntoResponse;
+use crate::{HeaderMap, Method, Request, Response, StatusCode, header};
+use opentelemetry_sdk::metrics::SdkMeterProvider;
+use rama_core::telemetry::opentelemetry::{
+    InstrumentationScope, KeyValue,
+    metrics::{Counter, Histogram, Meter, MeterProvider as _},
+};
+use rama_core::telemetry::tracing;
+use rama_core::{Context, Layer, Service};
+use rama_utils::macros::define_inner_service_accessors;
+use std::borrow::Cow;
+use std::fmt;
+use std::sync::Arc;
+use std::time::Instant;
+
+mod exporter;
+#[doc(inline)]
+pub use exporter::PrometheusExporter;
+
+const OTHER_PATH: &str = "<other>";
+
+const CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+
+const DURATION_BUCKETS: &[f64] = &[
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+const BYTES_BUCKETS: &[f64] = &[
+    64.0, 256.0, 1024.0, 4096.0, 16384.0, 65536.0, 262144.0, 1048576.0, 4194304.0,
+];
+
+#[derive(Debug, Clone)]
+struct Metrics {
+    requests_total: Counter<u64>,
+    request_duration: Histogram<f64>,
+    request_body_bytes: Histogram<u64>,
+    response_body_bytes: Histogram<u64>,
+}
+
+impl Metrics {
+    fn new(meter: &Meter) -> Self {
+        Self {
+            requests_total: meter
+                .u64_counter("http_requests_total")
+                .with_description("Total number of HTTP requests.")
+                .build(),
+            request_duration: meter
+                .f64_histogram("http_request_duration_seconds")
+                .with_description("Duration of HTTP requests in seconds.")
+                .with_unit("s")
+                .with_boundaries(DURATION_BUCKETS.to_vec())
+                .build(),
+            request_body_bytes: meter
+                .u64_histogram("http_request_body_bytes")
+                .with_description("Size of HTTP request bodies in bytes.")
+                .with_unit("By")
+                .with_boundaries(BYTES_BUCKETS.to_vec())
+                .build(),
+            response_body_bytes: meter
+                .u64_histogram("http_response_body_bytes")
+                .with_description("Size of HTTP response bodies in bytes.")
+                .with_unit("By")
+                .with_boundaries(BYTES_BUCKETS.to_vec())
+                .build(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Route {
+    template: Arc<str>,
+    matcher: PathMatcher,
+}
+
+/// Layer that applies the [`PrometheusService`] middleware,
+/// recording http metrics exported by a [`PrometheusExporter`].
+///
+/// See the [module docs](self) for more details.
+#[derive(Debug, Clone)]
+pub struct PrometheusLayer {
+    metrics: Arc<Metrics>,
+    provider: SdkMeterProvider,
+    exporter: PrometheusExporter,
+    routes: Arc<Vec<Route>>,
+    metrics_path: Option<Cow<'static, str>>,
+}
+
+impl Default for PrometheusLayer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PrometheusLayer {
+    /// Create a new [`PrometheusLayer`], recording metrics using a new meter provider,
+    /// exported by a new [`PrometheusExporter`].
+    pub fn new() -> Self {
+        let exporter = PrometheusExporter::new();
+        let provider = SdkMeterProvider::builder()
+            .with_reader(exporter.clone())
+            .build();
+        Self::with_meter_provider(provider, exporter)
+    }
+
+    /// Create a new [`PrometheusLayer`], recording metrics using the given meter provider.
+    ///
+    /// The given [`PrometheusExporter`] is used to serve the metrics,
+    /// and is expected to be registered as a reader of the meter provider.
+    pub fn with_meter_provider(provider: SdkMeterProvider, exporter: PrometheusExporter) -> Self {
+        let meter = provider.meter_with_scope(
+            InstrumentationScope::builder(const_format::formatcp!(
+                "{}-network-http",
+                rama_utils::info::NAME
+            ))
+            .with_version(rama_utils::info::VERSION)
+            .build(),
+        );
+        Self {
+            metrics: Arc::new(Metrics::new(&meter)),
+            provider,
+            exporter,
+            routes: Arc::new(Vec::new()),
+            metrics_path: Some(Cow::Borrowed("/metrics")),
+        }
+    }
+
+    /// Return the [`PrometheusExporter`] used to serve the metrics.
+    pub fn exporter(&self) -> &PrometheusExporter {
+        &self.exporter
+    }
+
+    /// Register a route template (e.g. `/users/{id}`), used as `path` label
+    /// for the requests matching it. Routes are matched in order of registration.
+    pub fn with_route(mut self, template: impl Into<Arc<str>>) -> Self {
+        self.set_route(template);
+        self
+    }
+
+    /// Register a route template (e.g. `/users/{id}`), used as `path` label
+    /// for the requests matching it. Routes are matched in order of registration.
+    pub fn set_route(&mut self, template: impl Into<Arc<str>>) -> &mut Self {
+        let template = template.into();
+        Arc::make_mut(&mut self.routes).push(Route {
+            matcher: PathMatcher::new(&template),
+            template,
+        });
+        self
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Set the path on which the metrics are served, `/metrics` by default.
+        ///
+        /// Use `None` to not serve the metrics, e.g. in case they are
+        /// served by another service using [`PrometheusExporter::render`].
+        pub fn metrics_path(mut self, path: Option<Cow<'static, str>>) -> Self {
+            self.metrics_path = path;
+            self
+        }
+    }
+}
+
+impl<S> Layer<S> for PrometheusLayer {
+    type Service = PrometheusService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        PrometheusService {
+            inner,
+            metrics: self.metrics.clone(),
+            provider: self.provider.clone(),
+            exporter: self.exporter.clone(),
+            routes: self.routes.clone(),
+            metrics_path: self.metrics_path.clone(),
+        }
+    }
+
+    fn into_layer(self, inner: S) -> Self::Service {
+        PrometheusService {
+            inner,
+            metrics: self.metrics,
+            provider: self.provider,
+            exporter: self.exporter,
+            routes: self.routes,
+            metrics_path: self.metrics_path,
+        }
+    }
+}
+
+/// Middleware which records http metrics using an OpenTelemetry meter,
+/// and serves them in the Prometheus text format using a [`PrometheusExporter`].
+///
+/// See the [module docs](self) for more details.
+pub struct PrometheusService<S> {
+    inner: S,
+    metrics: Arc<Metrics>,
+    // keeps the meter provider alive, as long as metrics are recorded
+    provider: SdkMeterProvider,
+    exporter: PrometheusExporter,
+    routes: Arc<Vec<Route>>,
+    metrics_path: Option<Cow<'static, str>>,
+}
+
+impl<S> PrometheusService<S> {
+    define_inner_service_accessors!();
+
+    /// Return the [`PrometheusExporter`] used to serve the metrics.
+    pub fn exporter(&self) -> &PrometheusExporter {
+        &self.exporter
+    }
+
+    fn route_label(&self, path: &str) -> Arc<str> {
+        self.routes
+            .iter()
+            .find(|route| route.matcher.matches_path(path).is_some())
+            .map(|route| route.template.clone())
+            .unwrap_or_else(|| OTHER_PATH.into())
+    }
+}
+
+impl<S: fmt::Debug> fmt::Debug for PrometheusService<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PrometheusService")
+            .field("inner", &self.inner)
+            .field("metrics", &self.metrics)
+            .field("provider", &self.provider)
+            .field("exporter", &self.exporter)
+            .field("routes", &self.routes)
+            .field("metrics_path", &self.metrics_path)
+            .finish()
+    }
+}
+
+impl<S: Clone> Clone for PrometheusService<S> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            metrics: self.metrics.clone(),
+            provider: self.provider.clone(),
+            exporter: self.exporter.clone(),
+            routes: self.routes.clone(),
+            metrics_path: self.metrics_path.clone(),
+        }
+    }
+}
+
+impl<S, State, ReqBody> Service<State, Request<ReqBody>> for PrometheusService<S>
+where
+    S: Service<State, Request<ReqBody>, Response = Response>,
+    State: Clone + Send + Sync + 'static,
+    ReqBody: HttpBody + Send + 'static,
+{
+    type Response = Response;
+    type Error = S::Error;
+
+    async fn serve(
+        &self,
+        ctx: Context<State>,
+        req: Request<ReqBody>,
+    ) -> Result<Self::Response, Self::Error> {
+        if req.method() == Method::GET
+            && self
+                .metrics_path
+                .as_deref()
+                .is_some_and(|path| path == req.uri().path())
+        {
+            return Ok(match self.exporter.render() {
+                Ok(metrics) => ([(header::CONTENT_TYPE, CONTENT_TYPE)], metrics).into_response(),
+                Err(err) => {
+                    tracing::error!("failed to render prometheus metrics: {err}");
+                    StatusCode::INTERNAL_SERVER_ERROR.into_response()
+                }
+            });
+        }
+
+        let attributes = [
+            KeyValue::new("method", method_label(req.method())),
+            KeyValue::new("path", self.route_label(req.uri().path())),
+        ];
+        let request_body_bytes = body_size(req.body(), req.headers());
+
+        let start = Instant::now();
+        let res = self.inner.serve(ctx, req).await?;
+
+        self.metrics
+            .request_duration
+            .record(start.elapsed().as_secs_f64(), &attributes);
+        if let Some(bytes) = request_body_bytes {
+            self.metrics.request_body_bytes.record(bytes, &attributes);
+        }
+        if let Some(bytes) = body_size(res.body(), res.headers()) {
+            self.metrics.response_body_bytes.record(bytes, &attributes);
+        }
+        let [method, path] = attributes;
+        self.metrics.requests_total.add(
+            1,
+            &[
+                method,
+                path,
+                KeyValue::new("status", i64::from(res.status().as_u16())),
+            ],
+        );
+        Ok(res)
+    }
+}
+
+/// Bound the method label to the standard methods.
+fn method_label(method: &Method) -> &'static str {
+    match *method {
+        Method::GET => "GET",
+        Method::POST => "POST",
+        Method::PUT => "PUT",
+        Method::DELETE => "DELETE",
+        Method::HEAD => "HEAD",
+        Method::OPTIONS => "OPTIONS",
+        Method::CONNECT => "CONNECT",
+        Method::PATCH => "PATCH",
+        Method::TRACE => "TRACE",
+        _ => "OTHER",
+    }
+}
+
+fn body_size(body: &impl HttpBody, headers: &HeaderMap) -> Option<u64> {
+    body.size_hint().exact().or_else(|| {
+        headers
+            .get(header::CONTENT_LENGTH)?
+            .to_str()
+            .ok()?
+            .parse()
+            .ok()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::service::web::WebService;
+    use crate::{Body, BodyExtractExt, StatusCode};
+    use std::convert::Infallible;
+
+    fn service() -> impl Service<(), Request, Response = Response, Error = Infallible> {
+        PrometheusLayer::new()
+            .with_route("/users/{id}")
+            .with_route("/")
+            .into_layer(
+                WebService::default()
+                    .get("/", "home")
+                    .post("/users/{id}", StatusCode::CREATED),
+            )
+    }
+
+    async fn metrics(
+        svc: &impl Service<(), Request, Response = Response, Error = Infallible>,
+    ) -> String {
+        let req = Request::get("/metrics").body(Body::empty()).unwrap();
+        let res = svc.serve(Context::default(), req).await.unwrap();
+        assert_eq!(res.headers()[header::CONTENT_TYPE], CONTENT_TYPE);
+        res.into_body().try_into_string().await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_prometheus_layer() {
+        let svc = service();
+        for (method, path, body) in [
+            (Method::GET, "/", ""),
+            (Method::GET, "/", ""),
+            (Method::POST, "/users/1", "hello"),
+            (Method::POST, "/users/2", "world!"),
+            (Method::GET, "/unknown/1", ""),
+            (Method::GET, "/unknown/2", ""),
+            (Method::from_bytes(b"CUSTOM").unwrap(), "/", ""),
+        ] {
+            let req = Request::builder()
+                .method(method)
+                .uri(path)
+                .body(Body::from(body))
+                .unwrap();
+            svc.serve(Context::default(), req).await.unwrap();
+        }
+
+        let out = metrics(&svc).await;
+        for line in [
+            r#"http_requests_total{method="GET",path="/",status="200"} 2"#,
+            r#"http_requests_total{method="POST",path="/users/{id}",status="201"} 2"#,
+            r#"http_requests_total{method="GET",path="<other>",status="404"} 2"#,
+            r#"http_requests_total{method="OTHER",path="/",status="404"} 1"#,
+            r#"http_request_duration_seconds_count{method="POST",path="/users/{id}"} 2"#,
+            r#"http_request_body_bytes_sum{method="POST",path="/users/{id}"} 11"#,
+            r#"http_response_body_bytes_sum{method="GET",path="/"} 8"#,
+        ] {
+            assert!(out.lines().any(|l| l == line), "missing {line} in:\n{out}");
+        }
+        assert!(!out.contains("/unknown"));
+        assert!(!out.contains("/metrics"));
+    }
+
+    #[tokio::test]
+    async fn test_prometheus_layer_without_metrics_path() {
+        let layer = PrometheusLayer::new().maybe_with_metrics_path(None);
+        let exporter = layer.exporter().clone();
+        let svc = layer.into_layer(WebService::default().get("/", "home"));
+
+        let req = Request::get("/metrics").body(Body::empty()).unwrap();
+        let res = svc.serve(Context::default(), req).await.unwrap();
+        assert_eq!(res.status(), StatusCode::NOT_FOUND);
+        assert!(
+            exporter
+                .render()
+                .unwrap()
+                .contains(r#"http_requests_total{method="GET",path="<other>",status="404"} 1"#)
+        );
+    }
+}
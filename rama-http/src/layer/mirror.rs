@@ -0,0 +1,427 @@
+//! Middleware that mirrors requests to a secondary service (shadow traffic).
+//!
+//! Each (sampled) request is sent to both the inner service and the mirror service.
+//! Only the response of the inner service is returned, the mirror is called
+//! in a fire-and-forget manner and its response is discarded.
+//!
+//! As the [`Body`] of a request can only be consumed once, it is buffered in memory
+//! so that it can be sent to both services. Requests with a body larger than
+//! the configured maximum body size (1 MiB by default) are passed through
+//! to the inner service without being mirrored.
+//!
+//! # Example
+//!
+//! ```
+//! use rama_core::service::service_fn;
+//! use rama_core::{Context, Layer, Service};
+//! use rama_http::layer::mirror::MirrorLayer;
+//! use rama_http::{Body, Request, Response};
+//! use std::convert::Infallible;
+//! use std::time::Duration;
+//!
+//! # #[tokio::main]
+//! # async fn main() {
+//! let shadow = service_fn(async |_req: Request| {
+//!     Ok::<_, Infallible>(Response::new(Body::from("shadow")))
+//! });
+//!
+//! let svc = MirrorLayer::new(shadow)
+//!     .with_sample_rate(0.1)
+//!     .with_timeout(Duration::from_secs(5))
+//!     .into_layer(service_fn(async |_req: Request| {
+//!         Ok::<_, Infallible>(Response::new(Body::from("primary")))
+//!     }));
+//!
+//! let res = svc
+//!     .serve(Context::default(), Request::new(Body::from("hello")))
+//!     .await
+//!     .unwrap();
+//! # let _ = res;
+//! # }
+//! ```
+
+use crate::body::{LimitedBody, collect_limited};
+use crate::{Body, Request};
+use rama_core::error::{BoxError, ErrorContext};
+use rama_core::telemetry::tracing;
+use rama_core::{Context, Layer, Service};
+use rama_utils::macros::define_inner_service_accessors;
+use std::fmt;
+use std::sync::Arc;
+use std::time::Duration;
+
+const DEFAULT_MAX_BODY_SIZE: usize = 1024 * 1024;
+
+/// Layer that applies the [`MirrorService`] middleware,
+/// sending a copy of each (sampled) request to a mirror service.
+///
+/// See the [module docs](self) for more details.
+pub struct MirrorLayer<M> {
+    mirror: Arc<M>,
+    sample_rate: f64,
+    timeout: Option<Duration>,
+    log_errors: bool,
+    max_body_size: usize,
+}
+
+impl<M> MirrorLayer<M> {
+    /// Create a new [`MirrorLayer`], mirroring all requests to the given service.
+    pub fn new(mirror: M) -> Self {
+        Self {
+            mirror: Arc::new(mirror),
+            sample_rate: 1.0,
+            timeout: None,
+            log_errors: true,
+            max_body_size: DEFAULT_MAX_BODY_SIZE,
+        }
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Set the fraction of requests (between `0.0` and `1.0`)
+        /// which are mirrored, by default all requests are mirrored.
+        pub fn sample_rate(mut self, rate: f64) -> Self {
+            self.sample_rate = rate.clamp(0.0, 1.0);
+            self
+        }
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Set the timeout after which a mirrored request is abandoned,
+        /// by default no timeout is applied.
+        pub fn timeout(mut self, timeout: Option<Duration>) -> Self {
+            self.timeout = timeout;
+            self
+        }
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Define whether or not errors (and timeouts) of the mirror service are logged,
+        /// by default they are logged.
+        pub fn log_errors(mut self, log: bool) -> Self {
+            self.log_errors = log;
+            self
+        }
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Set the maximum size of a request body which is mirrored,
+        /// by default this is 1 MiB.
+        ///
+        /// Requests with a larger body are passed through without being mirrored.
+        pub fn max_body_size(mut self, size: usize) -> Self {
+            self.max_body_size = size;
+            self
+        }
+    }
+}
+
+impl<M: fmt::Debug> fmt::Debug for MirrorLayer<M> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MirrorLayer")
+            .field("mirror", &self.mirror)
+            .field("sample_rate", &self.sample_rate)
+            .field("timeout", &self.timeout)
+            .field("log_errors", &self.log_errors)
+            .field("max_body_size", &self.max_body_size)
+            .finish()
+    }
+}
+
+impl<M> Clone for MirrorLayer<M> {
+    fn clone(&self) -> Self {
+        Self {
+            mirror: self.mirror.clone(),
+            sample_rate: self.sample_rate,
+            timeout: self.timeout,
+            log_errors: self.log_errors,
+            max_body_size: self.max_body_size,
+        }
+    }
+}
+
+impl<S, M> Layer<S> for MirrorLayer<M> {
+    type Service = MirrorService<S, M>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        MirrorService {
+            inner,
+            mirror: self.mirror.clone(),
+            sample_rate: self.sample_rate,
+            timeout: self.timeout,
+            log_errors: self.log_errors,
+            max_body_size: self.max_body_size,
+        }
+    }
+
+    fn into_layer(self, inner: S) -> Self::Service {
+        MirrorService {
+            inner,
+            mirror: self.mirror,
+            sample_rate: self.sample_rate,
+            timeout: self.timeout,
+            log_errors: self.log_errors,
+            max_body_size: self.max_body_size,
+        }
+    }
+}
+
+/// Middleware which sends a copy of each (sampled) request to a mirror service,
+/// returning only the response of the inner service.
+///
+/// See the [module docs](self) for more details.
+pub struct MirrorService<S, M> {
+    inner: S,
+    mirror: Arc<M>,
+    sample_rate: f64,
+    timeout: Option<Duration>,
+    log_errors: bool,
+    max_body_size: usize,
+}
+
+impl<S, M> MirrorService<S, M> {
+    /// Create a new [`MirrorService`], mirroring all requests to the given service.
+    pub fn new(inner: S, mirror: M) -> Self {
+        Self {
+            inner,
+            mirror: Arc::new(mirror),
+            sample_rate: 1.0,
+            timeout: None,
+            log_errors: true,
+            max_body_size: DEFAULT_MAX_BODY_SIZE,
+        }
+    }
+
+    define_inner_service_accessors!();
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Set the fraction of requests (between `0.0` and `1.0`)
+        /// which are mirrored, by default all requests are mirrored.
+        pub fn sample_rate(mut self, rate: f64) -> Self {
+            self.sample_rate = rate.clamp(0.0, 1.0);
+            self
+        }
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Set the timeout after which a mirrored request is abandoned,
+        /// by default no timeout is applied.
+        pub fn timeout(mut self, timeout: Option<Duration>) -> Self {
+            self.timeout = timeout;
+            self
+        }
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Define whether or not errors (and timeouts) of the mirror service are logged,
+        /// by default they are logged.
+        pub fn log_errors(mut self, log: bool) -> Self {
+            self.log_errors = log;
+            self
+        }
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Set the maximum size of a request body which is mirrored,
+        /// by default this is 1 MiB.
+        ///
+        /// Requests with a larger body are passed through without being mirrored.
+        pub fn max_body_size(mut self, size: usize) -> Self {
+            self.max_body_size = size;
+            self
+        }
+    }
+}
+
+impl<S: fmt::Debug, M: fmt::Debug> fmt::Debug for MirrorService<S, M> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MirrorService")
+            .field("inner", &self.inner)
+            .field("mirror", &self.mirror)
+            .field("sample_rate", &self.sample_rate)
+            .field("timeout", &self.timeout)
+            .field("log_errors", &self.log_errors)
+            .field("max_body_size", &self.max_body_size)
+            .finish()
+    }
+}
+
+impl<S: Clone, M> Clone for MirrorService<S, M> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            mirror: self.mirror.clone(),
+            sample_rate: self.sample_rate,
+            timeout: self.timeout,
+            log_errors: self.log_errors,
+            max_body_size: self.max_body_size,
+        }
+    }
+}
+
+impl<S, M, State> Service<State, Request> for MirrorService<S, M>
+where
+    S: Service<State, Request, Error: Into<BoxError>>,
+    M: Service<State, Request, Error: Into<BoxError>>,
+    State: Clone + Send + Sync + 'static,
+{
+    type Response = S::Response;
+    type Error = BoxError;
+
+    async fn serve(
+        &self,
+        ctx: Context<State>,
+        req: Request,
+    ) -> Result<Self::Response, Self::Error> {
+        if !rand::random_bool(self.sample_rate) {
+            return self.inner.serve(ctx, req).await.map_err(Into::into);
+        }
+
+        let (parts, body) = req.into_parts();
+        let bytes = match collect_limited(body, self.max_body_size)
+            .await
+            .context("MirrorService: collect request body")?
+        {
+            LimitedBody::Collected(bytes) => bytes,
+            LimitedBody::Exceeded(body) => {
+                tracing::trace!(
+                    max_body_size = self.max_body_size,
+                    "MirrorService: request body too large: skip mirror",
+                );
+                let req = Request::from_parts(parts, body);
+                return self.inner.serve(ctx, req).await.map_err(Into::into);
+            }
+        };
+
+        let mirror_req = Request::from_parts(parts.clone(), Body::from(bytes.clone()));
+        let mirror = self.mirror.clone();
+        let timeout = self.timeout;
+        let log_errors = self.log_errors;
+        let mirror_ctx = ctx.clone();
+        ctx.spawn(async move {
+            let fut = mirror.serve(mirror_ctx, mirror_req);
+            let result = match timeout {
+                Some(timeout) => match tokio::time::timeout(timeout, fut).await {
+                    Ok(result) => result.map_err(Into::into),
+                    Err(err) => Err(err.into()),
+                },
+                None => fut.await.map_err(Into::into),
+            };
+            if let Err(err) = result
+                && log_errors
+            {
+                tracing::debug!(error = %err, "MirrorService: mirrored request failed");
+            }
+        });
+
+        let req = Request::from_parts(parts, Body::from(bytes));
+        self.inner.serve(ctx, req).await.map_err(Into::into)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::Response;
+    use crate::dep::http_body_util::BodyExt;
+    use rama_core::error::OpaqueError;
+    use rama_core::service::service_fn;
+    use std::convert::Infallible;
+    use tokio::sync::mpsc;
+
+    #[tokio::test]
+    async fn mirror_request() {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+
+        let svc = MirrorLayer::new(service_fn(move |req: Request| {
+            let tx = tx.clone();
+            async move {
+                let body = req.into_body().collect().await.unwrap().to_bytes();
+                tx.send(body).unwrap();
+                Ok::<_, Infallible>(Response::new(Body::from("mirror")))
+            }
+        }))
+        .into_layer(service_fn(async |req: Request| {
+            let body = req.into_body().collect().await.unwrap().to_bytes();
+            assert_eq!(body, "hello");
+            Ok::<_, Infallible>(Response::new(Body::from("primary")))
+        }));
+
+        let res = svc
+            .serve(Context::default(), Request::new(Body::from("hello")))
+            .await
+            .unwrap();
+        let body = res.into_body().collect().await.unwrap().to_bytes();
+        assert_eq!(body, "primary");
+
+        assert_eq!(rx.recv().await.unwrap(), "hello");
+    }
+
+    #[tokio::test]
+    async fn mirror_request_not_sampled() {
+        let (tx, mut rx) = mpsc::unbounded_channel::<()>();
+
+        let svc = MirrorLayer::new(service_fn(move |_req: Request| {
+            tx.send(()).unwrap();
+            std::future::ready(Ok::<_, Infallible>(Response::new(Body::empty())))
+        }))
+        .with_sample_rate(0.0)
+        .into_layer(service_fn(async |_req: Request| {
+            Ok::<_, Infallible>(Response::new(Body::from("primary")))
+        }));
+
+        let res = svc
+            .serve(Context::default(), Request::new(Body::from("hello")))
+            .await
+            .unwrap();
+        let body = res.into_body().collect().await.unwrap().to_bytes();
+        assert_eq!(body, "primary");
+
+        drop(svc);
+        assert!(rx.recv().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn mirror_request_body_too_large() {
+        let (tx, mut rx) = mpsc::unbounded_channel::<()>();
+
+        let svc = MirrorLayer::new(service_fn(move |_req: Request| {
+            tx.send(()).unwrap();
+            std::future::ready(Ok::<_, Infallible>(Response::new(Body::empty())))
+        }))
+        .with_max_body_size(4)
+        .into_layer(service_fn(async |req: Request| {
+            let body = req.into_body().collect().await.unwrap().to_bytes();
+            assert_eq!(body, "hello");
+            Ok::<_, Infallible>(Response::new(Body::from("primary")))
+        }));
+
+        let res = svc
+            .serve(Context::default(), Request::new(Body::from("hello")))
+            .await
+            .unwrap();
+        let body = res.into_body().collect().await.unwrap().to_bytes();
+        assert_eq!(body, "primary");
+
+        drop(svc);
+        assert!(rx.recv().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn mirror_error_does_not_affect_response() {
+        let svc = MirrorLayer::new(service_fn(async |_req: Request| {
+            Err::<Response, _>(OpaqueError::from_display("mirror failure"))
+        }))
+        .with_timeout(Duration::from_millis(10))
+        .into_layer(service_fn(async |_req: Request| {
+            Ok::<_, Infallible>(Response::new(Body::from("primary")))
+        }));
+
+        let res = svc
+            .serve(Context::default(), Request::new(Body::empty()))
+            .await
+            .unwrap();
+        let body = res.into_body().collect().await.unwrap().to_bytes();
+        assert_eq!(body, "primary");
+    }
+}
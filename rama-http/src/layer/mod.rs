@@ -16,39 +16,69 @@
 //! [`Layer`]: rama_core::Layer
 //! [`Service`]: rama_core::Service
 
+pub mod ab;
+pub mod api_error;
 pub mod auth;
 pub mod body_limit;
+pub mod cache;
 pub mod catch_panic;
+pub mod circuit_breaker;
 pub mod classify;
+pub mod coalesce;
 pub mod collect_body;
+pub mod concurrency_limit;
+pub mod connection_state;
 pub mod cors;
 pub mod dns;
+pub mod echo;
+pub mod error;
 pub mod error_handling;
+pub mod etag;
 pub mod follow_redirect;
 pub mod forwarded;
+pub mod h2_push;
 pub mod header_config;
 pub mod header_from_str_config;
+pub mod header_manipulation;
 pub mod header_option_value;
+pub mod log;
 pub mod map_request_body;
 pub mod map_response_body;
+pub mod mirror;
+pub mod negotiate;
 pub mod normalize_path;
 pub mod propagate_headers;
 pub mod proxy_auth;
+pub mod rate_limit;
+pub mod read_body;
 pub mod remove_header;
 pub mod request_id;
 pub mod required_header;
 pub mod retry;
+pub mod rewrite;
+pub mod route;
 pub mod sensitive_headers;
 pub mod set_header;
 pub mod set_status;
+pub mod sse;
+pub mod streaming;
+pub mod throttle;
 pub mod timeout;
 pub mod trace;
 pub mod traffic_writer;
 pub mod ua;
 pub mod validate_request;
 
+#[cfg(feature = "prometheus")]
+pub mod metrics;
+
+#[cfg(feature = "crypto")]
+pub mod signature;
+
 #[cfg(feature = "opentelemetry")]
 pub mod opentelemetry;
+#[cfg(feature = "opentelemetry")]
+pub mod telemetry;
 
 pub(crate) mod util;
 
@@ -0,0 +1,112 @@
+//! Middleware that serializes responses in the format preferred by the client.
+//!
+//! The inner service of [`ContentNegotiation`] returns its response as a
+//! [`Negotiated`] value, which is serialized using the registered [`BodySerializer`]
+//! that best matches the `Accept` header of the request, respecting its q-values.
+//! In case of a tie the serializer registered first is preferred.
+//!
+//! Requests which do not accept any of the registered formats are rejected
+//! with a `406 Not Acceptable` response, without calling the inner service.
+//!
+//! By default serializers are registered for the following formats,
+//! in order of preference:
+//!
+//! - `application/json`: [`JsonSerializer`];
+//! - `application/msgpack`: `MsgPackSerializer` (requires the `msgpack` feature);
+//! - `application/cbor`: `CborSerializer` (requires the `cbor` feature).
+//!
+//! # Example
+//!
+//! ```
+//! use rama_core::service::service_fn;
+//! use rama_core::{Context, Layer, Service};
+//! use rama_http::layer::negotiate::{ContentNegotiationLayer, Negotiated};
+//! use rama_http::{Body, Request, StatusCode, header};
+//! use serde::Serialize;
+//! use std::convert::Infallible;
+//!
+//! #[derive(Debug, Serialize)]
+//! struct User {
+//!     name: &'static str,
+//! }
+//!
+//! # #[tokio::main]
+//! # async fn main() {
+//! let svc = ContentNegotiationLayer::new().into_layer(service_fn(async |_req: Request| {
+//!     Ok::<_, Infallible>(Negotiated::new(User { name: "john" }))
+//! }));
+//!
+//! let req = Request::builder()
+//!     .header(header::ACCEPT, "text/html;q=0.5, application/json")
+//!     .body(Body::empty())
+//!     .unwrap();
+//! let res = svc.serve(Context::default(), req).await.unwrap();
+//! assert_eq!(res.status(), StatusCode::OK);
+//! assert_eq!(res.headers()[header::CONTENT_TYPE], "application/json");
+//!
+//! let req = Request::builder()
+//!     .header(header::ACCEPT, "text/html")
+//!     .body(Body::empty())
+//!     .unwrap();
+//! let res = svc.serve(Context::default(), req).await.unwrap();
+//! assert_eq!(res.status(), StatusCode::NOT_ACCEPTABLE);
+//! # }
+//! ```
+
+use crate::StatusCode;
+
+mod serializer;
+mod service;
+
+#[doc(inline)]
+pub use serializer::{BodySerializer, JsonSerializer};
+
+#[cfg(feature = "msgpack")]
+#[doc(inline)]
+pub use serializer::MsgPackSerializer;
+
+#[cfg(feature = "cbor")]
+#[doc(inline)]
+pub use serializer::CborSerializer;
+
+#[doc(inline)]
+pub use service::{ContentNegotiation, ContentNegotiationLayer};
+
+#[cfg(test)]
+mod tests;
+
+/// A response value to be serialized by the [`ContentNegotiation`] middleware,
+/// in the format preferred by the client.
+#[derive(Debug, Clone)]
+pub struct Negotiated<T> {
+    value: T,
+    status: StatusCode,
+}
+
+impl<T> Negotiated<T> {
+    /// Create a new [`Negotiated`] response with a `200 OK` status.
+    pub const fn new(value: T) -> Self {
+        Self {
+            value,
+            status: StatusCode::OK,
+        }
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Set the status code of the response.
+        pub fn status(mut self, status: StatusCode) -> Self {
+            self.status = status;
+            self
+        }
+    }
+
+    /// The value to be serialized.
+    pub fn value(&self) -> &T {
+        &self.value
+    }
+
+    /// Consume the [`Negotiated`] response, returning the value to be serialized.
+    pub fn into_value(self) -> T {
+        self.value
+    }
+}
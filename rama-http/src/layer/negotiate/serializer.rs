@@ -0,0 +1,98 @@
+use crate::HeaderValue;
+use rama_core::bytes::Bytes;
+use rama_core::error::BoxError;
+use serde::Serialize;
+
+const APPLICATION_JSON: HeaderValue = HeaderValue::from_static("application/json");
+#[cfg(feature = "msgpack")]
+const APPLICATION_MSGPACK: HeaderValue = HeaderValue::from_static("application/msgpack");
+#[cfg(feature = "cbor")]
+const APPLICATION_CBOR: HeaderValue = HeaderValue::from_static("application/cbor");
+
+/// A serializer of response values, used by the [`ContentNegotiation`] middleware.
+///
+/// [`ContentNegotiation`]: super::ContentNegotiation
+pub trait BodySerializer<T>: Send + Sync + 'static {
+    /// The media type of the serialized body, used to match
+    /// the `Accept` header and set as the `Content-Type` of the response.
+    fn content_type(&self) -> HeaderValue;
+
+    /// Serialize the given value into a response body.
+    fn serialize(&self, value: &T) -> Result<Bytes, BoxError>;
+}
+
+#[derive(Debug, Clone, Default)]
+#[non_exhaustive]
+/// [`BodySerializer`] which serializes values as `application/json`.
+pub struct JsonSerializer;
+
+impl JsonSerializer {
+    /// Create a new [`JsonSerializer`].
+    pub const fn new() -> Self {
+        Self
+    }
+}
+
+impl<T: Serialize> BodySerializer<T> for JsonSerializer {
+    fn content_type(&self) -> HeaderValue {
+        APPLICATION_JSON
+    }
+
+    fn serialize(&self, value: &T) -> Result<Bytes, BoxError> {
+        Ok(serde_json::to_vec(value)?.into())
+    }
+}
+
+#[cfg(feature = "msgpack")]
+#[derive(Debug, Clone, Default)]
+#[non_exhaustive]
+/// [`BodySerializer`] which serializes values as `application/msgpack`.
+///
+/// Structs are serialized as maps, using their field names as keys.
+pub struct MsgPackSerializer;
+
+#[cfg(feature = "msgpack")]
+impl MsgPackSerializer {
+    /// Create a new [`MsgPackSerializer`].
+    pub const fn new() -> Self {
+        Self
+    }
+}
+
+#[cfg(feature = "msgpack")]
+impl<T: Serialize> BodySerializer<T> for MsgPackSerializer {
+    fn content_type(&self) -> HeaderValue {
+        APPLICATION_MSGPACK
+    }
+
+    fn serialize(&self, value: &T) -> Result<Bytes, BoxError> {
+        Ok(rmp_serde::to_vec_named(value)?.into())
+    }
+}
+
+#[cfg(feature = "cbor")]
+#[derive(Debug, Clone, Default)]
+#[non_exhaustive]
+/// [`BodySerializer`] which serializes values as `application/cbor`.
+pub struct CborSerializer;
+
+#[cfg(feature = "cbor")]
+impl CborSerializer {
+    /// Create a new [`CborSerializer`].
+    pub const fn new() -> Self {
+        Self
+    }
+}
+
+#[cfg(feature = "cbor")]
+impl<T: Serialize> BodySerializer<T> for CborSerializer {
+    fn content_type(&self) -> HeaderValue {
+        APPLICATION_CBOR
+    }
+
+    fn serialize(&self, value: &T) -> Result<Bytes, BoxError> {
+        let mut buf = Vec::new();
+        ciborium::into_writer(value, &mut buf)?;
+        Ok(buf.into())
+    }
+}
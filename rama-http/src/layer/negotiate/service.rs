@@ -0,0 +1,258 @@
+use super::{BodySerializer, JsonSerializer, Negotiated};
+use crate::dep::mime::{self, Mime};
+use crate::headers::{Accept, HeaderMapExt};
+use crate::{Body, HeaderMap, HeaderValue, Request, Response, StatusCode, header};
+use rama_core::telemetry::tracing;
+use rama_core::{Context, Layer, Service};
+use rama_utils::macros::define_inner_service_accessors;
+use serde::Serialize;
+use std::fmt;
+use std::sync::Arc;
+
+type Serializers<T> = Vec<Arc<dyn BodySerializer<T>>>;
+
+/// Layer that applies the [`ContentNegotiation`] middleware.
+///
+/// See the [module docs](super) for more details.
+pub struct ContentNegotiationLayer<T> {
+    serializers: Serializers<T>,
+}
+
+impl<T: Serialize + 'static> ContentNegotiationLayer<T> {
+    /// Create a new [`ContentNegotiationLayer`] with the default serializers registered.
+    ///
+    /// See the [module docs](super) for more details.
+    pub fn new() -> Self {
+        Self {
+            serializers: default_serializers(),
+        }
+    }
+}
+
+impl<T: Serialize + 'static> Default for ContentNegotiationLayer<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> ContentNegotiationLayer<T> {
+    /// Create a new [`ContentNegotiationLayer`] without any serializers registered.
+    pub fn empty() -> Self {
+        Self {
+            serializers: Vec::new(),
+        }
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Register a [`BodySerializer`], preferred less than
+        /// the serializers registered before it.
+        pub fn serializer(mut self, serializer: impl BodySerializer<T>) -> Self {
+            self.serializers.push(Arc::new(serializer));
+            self
+        }
+    }
+}
+
+fn default_serializers<T: Serialize + 'static>() -> Serializers<T> {
+    vec![
+        Arc::new(JsonSerializer::new()),
+        #[cfg(feature = "msgpack")]
+        Arc::new(super::MsgPackSerializer::new()),
+        #[cfg(feature = "cbor")]
+        Arc::new(super::CborSerializer::new()),
+    ]
+}
+
+fn fmt_serializers<T: 'static>(serializers: &Serializers<T>) -> Vec<HeaderValue> {
+    serializers.iter().map(|s| s.content_type()).collect()
+}
+
+impl<T: 'static> fmt::Debug for ContentNegotiationLayer<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ContentNegotiationLayer")
+            .field("serializers", &fmt_serializers(&self.serializers))
+            .finish()
+    }
+}
+
+impl<T> Clone for ContentNegotiationLayer<T> {
+    fn clone(&self) -> Self {
+        Self {
+            serializers: self.serializers.clone(),
+        }
+    }
+}
+
+impl<S, T> Layer<S> for ContentNegotiationLayer<T> {
+    type Service = ContentNegotiation<S, T>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        ContentNegotiation {
+            inner,
+            serializers: self.serializers.clone(),
+        }
+    }
+
+    fn into_layer(self, inner: S) -> Self::Service {
+        ContentNegotiation {
+            inner,
+            serializers: self.serializers,
+        }
+    }
+}
+
+/// Middleware which serializes [`Negotiated`] responses
+/// in the format preferred by the client.
+///
+/// See the [module docs](super) for more details.
+pub struct ContentNegotiation<S, T> {
+    inner: S,
+    serializers: Serializers<T>,
+}
+
+impl<S, T: Serialize + 'static> ContentNegotiation<S, T> {
+    /// Create a new [`ContentNegotiation`] with the default serializers registered.
+    ///
+    /// See the [module docs](super) for more details.
+    pub fn new(inner: S) -> Self {
+        Self {
+            inner,
+            serializers: default_serializers(),
+        }
+    }
+}
+
+impl<S, T> ContentNegotiation<S, T> {
+    /// Create a new [`ContentNegotiation`] without any serializers registered.
+    pub fn empty(inner: S) -> Self {
+        Self {
+            inner,
+            serializers: Vec::new(),
+        }
+    }
+
+    define_inner_service_accessors!();
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Register a [`BodySerializer`], preferred less than
+        /// the serializers registered before it.
+        pub fn serializer(mut self, serializer: impl BodySerializer<T>) -> Self {
+            self.serializers.push(Arc::new(serializer));
+            self
+        }
+    }
+}
+
+impl<S, T: 'static> ContentNegotiation<S, T> {
+    /// Select the serializer best matching the `Accept` header,
+    /// or `None` if none of them is acceptable.
+    fn select(&self, headers: &HeaderMap) -> Option<&Arc<dyn BodySerializer<T>>> {
+        let Some(accept) = headers.typed_get::<Accept>() else {
+            return self.serializers.first();
+        };
+
+        let mut selected = None;
+        let mut selected_quality = 0;
+        for serializer in &self.serializers {
+            let Some(content_type) = serializer
+                .content_type()
+                .to_str()
+                .ok()
+                .and_then(|content_type| content_type.parse::<Mime>().ok())
+            else {
+                continue;
+            };
+            let quality = accepted_quality(&accept, &content_type);
+            if quality > selected_quality {
+                selected = Some(serializer);
+                selected_quality = quality;
+            }
+        }
+        selected
+    }
+}
+
+/// The quality of the most specific media range in the
+/// `Accept` header which matches the given content type.
+fn accepted_quality(accept: &Accept, content_type: &Mime) -> u16 {
+    accept
+        .iter()
+        .filter_map(|range| {
+            let specificity = if range.value.type_() == mime::STAR {
+                0
+            } else if range.value.type_() != content_type.type_() {
+                return None;
+            } else if range.value.subtype() == mime::STAR {
+                1
+            } else if range.value.subtype() == content_type.subtype() {
+                2
+            } else {
+                return None;
+            };
+            Some((specificity, range.quality.as_u16()))
+        })
+        .max_by_key(|(specificity, _)| *specificity)
+        .map(|(_, quality)| quality)
+        .unwrap_or_default()
+}
+
+impl<S: fmt::Debug, T: 'static> fmt::Debug for ContentNegotiation<S, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ContentNegotiation")
+            .field("inner", &self.inner)
+            .field("serializers", &fmt_serializers(&self.serializers))
+            .finish()
+    }
+}
+
+impl<S: Clone, T> Clone for ContentNegotiation<S, T> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            serializers: self.serializers.clone(),
+        }
+    }
+}
+
+impl<State, S, T, ReqBody> Service<State, Request<ReqBody>> for ContentNegotiation<S, T>
+where
+    State: Clone + Send + Sync + 'static,
+    S: Service<State, Request<ReqBody>, Response = Negotiated<T>>,
+    T: Send + 'static,
+    ReqBody: Send + 'static,
+{
+    type Response = Response;
+    type Error = S::Error;
+
+    async fn serve(
+        &self,
+        ctx: Context<State>,
+        req: Request<ReqBody>,
+    ) -> Result<Self::Response, Self::Error> {
+        let Some(serializer) = self.select(req.headers()).cloned() else {
+            let mut res = Response::new(Body::empty());
+            *res.status_mut() = StatusCode::NOT_ACCEPTABLE;
+            return Ok(res);
+        };
+
+        let negotiated = self.inner.serve(ctx, req).await?;
+
+        let body = match serializer.serialize(&negotiated.value) {
+            Ok(body) => body,
+            Err(err) => {
+                tracing::debug!("failed to serialize negotiated response: {err}");
+                let mut res = Response::new(Body::empty());
+                *res.status_mut() = StatusCode::INTERNAL_SERVER_ERROR;
+                return Ok(res);
+            }
+        };
+
+        let mut res = Response::new(Body::from(body));
+        *res.status_mut() = negotiated.status;
+        res.headers_mut()
+            .insert(header::CONTENT_TYPE, serializer.content_type());
+        res.headers_mut()
+            .append(header::VARY, HeaderValue::from_static("accept"));
+        Ok(res)
+    }
+}
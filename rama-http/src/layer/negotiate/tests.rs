@@ -0,0 +1,151 @@
+use super::*;
+
+use crate::dep::http_body_util::BodyExt;
+use crate::{Body, Request, Response, StatusCode, header};
+use rama_core::bytes::Bytes;
+use rama_core::service::service_fn;
+use rama_core::{Context, Layer, Service};
+use serde::Serialize;
+use std::convert::Infallible;
+
+#[derive(Debug, Serialize)]
+struct Data {
+    a: u64,
+    b: Vec<serde_json::Value>,
+}
+
+fn data() -> Data {
+    Data {
+        a: 1,
+        b: vec![
+            true.into(),
+            serde_json::Value::Null,
+            (-1).into(),
+            "x".into(),
+        ],
+    }
+}
+
+#[cfg(feature = "msgpack")]
+#[test]
+fn serialize_msgpack() {
+    let serializer = MsgPackSerializer::new();
+    let body = BodySerializer::serialize(&serializer, &data()).unwrap();
+    assert_eq!(
+        body,
+        Bytes::from_static(&[
+            0x82, 0xa1, b'a', 0x01, 0xa1, b'b', 0x94, 0xc3, 0xc0, 0xff, 0xa1, b'x'
+        ])
+    );
+}
+
+#[cfg(feature = "cbor")]
+#[test]
+fn serialize_cbor() {
+    let serializer = CborSerializer::new();
+    let body = BodySerializer::serialize(&serializer, &data()).unwrap();
+    assert_eq!(
+        body,
+        Bytes::from_static(&[
+            0xa2, 0x61, b'a', 0x01, 0x61, b'b', 0x84, 0xf5, 0xf6, 0x20, 0x61, b'x'
+        ])
+    );
+}
+
+#[tokio::test]
+async fn content_negotiation() {
+    let svc = ContentNegotiationLayer::new().into_layer(service_fn(async |_req: Request| {
+        Ok::<_, Infallible>(Negotiated::new(data()).with_status(StatusCode::CREATED))
+    }));
+
+    let serve = async |accept: Option<&str>| -> Response {
+        let mut builder = Request::builder();
+        if let Some(accept) = accept {
+            builder = builder.header(header::ACCEPT, accept);
+        }
+        svc.serve(Context::default(), builder.body(Body::empty()).unwrap())
+            .await
+            .unwrap()
+    };
+
+    #[cfg_attr(not(any(feature = "msgpack", feature = "cbor")), allow(unused_mut))]
+    let mut cases = vec![
+        (None, "application/json"),
+        (Some("*/*"), "application/json"),
+        (
+            Some("text/html, application/json;q=0.1"),
+            "application/json",
+        ),
+    ];
+    #[cfg(feature = "msgpack")]
+    cases.extend([
+        (Some("application/msgpack"), "application/msgpack"),
+        (Some("application/json;q=0, */*"), "application/msgpack"),
+    ]);
+    #[cfg(feature = "cbor")]
+    cases.extend([
+        (
+            Some("application/*;q=0.5, application/cbor;q=0.8"),
+            "application/cbor",
+        ),
+        (
+            Some("text/html, application/json;q=0.1, application/cbor;q=0.1"),
+            "application/json",
+        ),
+    ]);
+
+    for (accept, content_type) in cases {
+        let res = serve(accept).await;
+        assert_eq!(res.status(), StatusCode::CREATED, "{accept:?}");
+        assert_eq!(
+            res.headers()[header::CONTENT_TYPE],
+            content_type,
+            "{accept:?}"
+        );
+        assert_eq!(res.headers()[header::VARY], "accept");
+    }
+
+    let res = serve(None).await;
+    let body = res.into_body().collect().await.unwrap().to_bytes();
+    assert_eq!(body, r#"{"a":1,"b":[true,null,-1,"x"]}"#);
+
+    for accept in ["text/html", "application/json;q=0, application/*;q=0"] {
+        let res = serve(Some(accept)).await;
+        assert_eq!(res.status(), StatusCode::NOT_ACCEPTABLE, "{accept}");
+    }
+}
+
+#[tokio::test]
+async fn content_negotiation_custom_serializer() {
+    #[derive(Debug)]
+    struct PlainText;
+
+    impl BodySerializer<String> for PlainText {
+        fn content_type(&self) -> crate::HeaderValue {
+            crate::HeaderValue::from_static("text/plain; charset=utf-8")
+        }
+
+        fn serialize(&self, value: &String) -> Result<Bytes, rama_core::error::BoxError> {
+            Ok(Bytes::from(value.clone()))
+        }
+    }
+
+    let svc = ContentNegotiationLayer::empty()
+        .with_serializer(PlainText)
+        .into_layer(service_fn(async |_req: Request| {
+            Ok::<_, Infallible>(Negotiated::new("hello".to_owned()))
+        }));
+
+    let req = Request::builder()
+        .header(header::ACCEPT, "text/*")
+        .body(Body::empty())
+        .unwrap();
+    let res = svc.serve(Context::default(), req).await.unwrap();
+    assert_eq!(res.status(), StatusCode::OK);
+    assert_eq!(
+        res.headers()[header::CONTENT_TYPE],
+        "text/plain; charset=utf-8"
+    );
+    let body = res.into_body().collect().await.unwrap().to_bytes();
+    assert_eq!(body, "hello");
+}
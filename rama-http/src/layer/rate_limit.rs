@@ -0,0 +1,417 @@
+//! Http utilities for rate limiting, such as middleware to inform
+//! clients about their rate limit quota.
+//!
+//! Rate limiting itself is done using the [`Limit`] middleware,
+//! e.g. using a [`TokenBucketPolicy`] or [`SlidingWindowPolicy`].
+//! Requests can be rate limited per client using the [`ClientIpKey`],
+//! [`HeaderValueKey`] or [`ApiKeyIdentityKey`] key functions. Such policies
+//! insert a [`RateLimitInfo`] in the [`Context`], which the [`RateLimitHeaders`]
+//! middleware uses to add the following headers to the response:
+//!
+//! - `x-ratelimit-limit`: the maximum amount of requests allowed in a burst;
+//! - `x-ratelimit-remaining`: the remaining amount of requests allowed right now;
+//! - `x-ratelimit-reset`: seconds until the quota is fully restored.
+//!
+//! As the [`RateLimitInfo`] is inserted in the [`Context`] passed to the inner service
+//! of [`Limit`], the [`RateLimitHeadersLayer`] has to be applied after the [`LimitLayer`].
+//!
+//! A [`RateLimitReached`] error can be turned into a `429 Too Many Requests` response,
+//! including the same headers as well as a `retry-after` header,
+//! using its [`IntoResponse`] implementation.
+//!
+//! # Example
+//!
+//! ```
+//! use rama_core::layer::limit::policy::{RateLimitReached, TokenBucketPolicy};
+//! use rama_core::layer::LimitLayer;
+//! use rama_core::service::service_fn;
+//! use rama_core::{Context, Layer, Service};
+//! use rama_http::layer::rate_limit::RateLimitHeadersLayer;
+//! use rama_http::service::web::response::IntoResponse;
+//! use rama_http::{Body, Request, Response, StatusCode};
+//! use std::convert::Infallible;
+//!
+//! # #[tokio::main]
+//! # async fn main() {
+//! let svc = (
+//!     LimitLayer::new(TokenBucketPolicy::new(1, 1.0)).with_error_into_response_fn(
+//!         |err: RateLimitReached| Ok::<_, Infallible>(err.into_response()),
+//!     ),
+//!     RateLimitHeadersLayer::new(),
+//! )
+//!     .into_layer(service_fn(async |_req: Request| {
+//!         Ok::<_, Infallible>(Response::new(Body::empty()))
+//!     }));
+//!
+//! let res = svc.serve(Context::default(), Request::new(Body::empty())).await.unwrap();
+//! assert_eq!(res.status(), StatusCode::OK);
+//! assert_eq!(res.headers()["x-ratelimit-remaining"], "0");
+//!
+//! let res = svc.serve(Context::default(), Request::new(Body::empty())).await.unwrap();
+//! assert_eq!(res.status(), StatusCode::TOO_MANY_REQUESTS);
+//! assert_eq!(res.headers()["retry-after"], "1");
+//! # }
+//! ```
+//!
+//! [`Limit`]: rama_core::layer::Limit
+//! [`LimitLayer`]: rama_core::layer::LimitLayer
+//! [`TokenBucketPolicy`]: rama_core::layer::limit::policy::TokenBucketPolicy
+//! [`SlidingWindowPolicy`]: rama_core::layer::limit::policy::SlidingWindowPolicy
+
+use crate::layer::auth::ApiKeyIdentity;
+use crate::service::web::extract::client_ip::resolve_client_ip;
+use crate::service::web::response::IntoResponse;
+use crate::{HeaderMap, HeaderName, HeaderValue, Request, Response, StatusCode, header};
+use rama_core::layer::limit::policy::{RateLimitInfo, RateLimitKeyFn, RateLimitReached};
+use rama_core::{Context, Layer, Service};
+use rama_utils::macros::define_inner_service_accessors;
+use std::fmt;
+use std::net::IpAddr;
+use std::time::Duration;
+
+/// The `x-ratelimit-limit` header.
+pub const X_RATELIMIT_LIMIT: HeaderName = HeaderName::from_static("x-ratelimit-limit");
+/// The `x-ratelimit-remaining` header.
+pub const X_RATELIMIT_REMAINING: HeaderName = HeaderName::from_static("x-ratelimit-remaining");
+/// The `x-ratelimit-reset` header.
+pub const X_RATELIMIT_RESET: HeaderName = HeaderName::from_static("x-ratelimit-reset");
+
+/// [`RateLimitKeyFn`] which rate limits requests per client IP,
+/// resolved in the same way as the [`ClientIp`] extractor.
+///
+/// Requests for which no client IP can be resolved are not rate limited.
+///
+/// [`ClientIp`]: crate::service::web::extract::ClientIp
+#[derive(Debug, Clone, Default)]
+#[non_exhaustive]
+pub struct ClientIpKey;
+
+impl ClientIpKey {
+    /// Create a new [`ClientIpKey`].
+    pub const fn new() -> Self {
+        Self
+    }
+}
+
+impl<State, Body> RateLimitKeyFn<State, Request<Body>> for ClientIpKey {
+    type Key = IpAddr;
+
+    fn rate_limit_key(&self, ctx: &Context<State>, request: &Request<Body>) -> Option<Self::Key> {
+        resolve_client_ip(ctx, request.headers())
+    }
+}
+
+/// [`RateLimitKeyFn`] which rate limits requests per value of the given header,
+/// e.g. an API key header.
+///
+/// Requests without the header are not rate limited.
+#[derive(Debug, Clone)]
+pub struct HeaderValueKey(pub HeaderName);
+
+impl<State, Body> RateLimitKeyFn<State, Request<Body>> for HeaderValueKey {
+    type Key = HeaderValue;
+
+    fn rate_limit_key(&self, _ctx: &Context<State>, request: &Request<Body>) -> Option<Self::Key> {
+        request.headers().get(&self.0).cloned()
+    }
+}
+
+/// [`RateLimitKeyFn`] which rate limits requests per API key,
+/// using the [`ApiKeyIdentity`] inserted in the [`Context`] by the [`ApiKeyService`].
+///
+/// Requests without an [`ApiKeyIdentity`] are not rate limited.
+///
+/// [`ApiKeyService`]: crate::layer::auth::ApiKeyService
+#[derive(Debug, Clone, Default)]
+#[non_exhaustive]
+pub struct ApiKeyIdentityKey;
+
+impl ApiKeyIdentityKey {
+    /// Create a new [`ApiKeyIdentityKey`].
+    pub const fn new() -> Self {
+        Self
+    }
+}
+
+impl<State, Body> RateLimitKeyFn<State, Request<Body>> for ApiKeyIdentityKey {
+    type Key = String;
+
+    fn rate_limit_key(&self, ctx: &Context<State>, _request: &Request<Body>) -> Option<Self::Key> {
+        ctx.get::<ApiKeyIdentity>()
+            .map(|identity| identity.key_id.clone())
+    }
+}
+
+/// Layer that applies [`RateLimitHeaders`] which adds rate limit headers to the response.
+#[derive(Debug, Clone, Default)]
+#[non_exhaustive]
+pub struct RateLimitHeadersLayer;
+
+impl RateLimitHeadersLayer {
+    /// Create a new [`RateLimitHeadersLayer`].
+    pub const fn new() -> Self {
+        Self
+    }
+}
+
+impl<S> Layer<S> for RateLimitHeadersLayer {
+    type Service = RateLimitHeaders<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        RateLimitHeaders::new(inner)
+    }
+}
+
+/// Middleware which adds rate limit headers to the response,
+/// based on the [`RateLimitInfo`] found in the [`Context`].
+///
+/// See the [module docs](self) for more details.
+pub struct RateLimitHeaders<S> {
+    inner: S,
+}
+
+impl<S> RateLimitHeaders<S> {
+    /// Create a new [`RateLimitHeaders`].
+    pub const fn new(inner: S) -> Self {
+        Self { inner }
+    }
+
+    define_inner_service_accessors!();
+}
+
+impl<S: fmt::Debug> fmt::Debug for RateLimitHeaders<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RateLimitHeaders")
+            .field("inner", &self.inner)
+            .finish()
+    }
+}
+
+impl<S: Clone> Clone for RateLimitHeaders<S> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl<State, S, ReqBody, ResBody> Service<State, Request<ReqBody>> for RateLimitHeaders<S>
+where
+    State: Clone + Send + Sync + 'static,
+    S: Service<State, Request<ReqBody>, Response = Response<ResBody>>,
+    ReqBody: Send + 'static,
+    ResBody: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+
+    async fn serve(
+        &self,
+        ctx: Context<State>,
+        req: Request<ReqBody>,
+    ) -> Result<Self::Response, Self::Error> {
+        let info = ctx.get::<RateLimitInfo>().copied();
+        let mut response = self.inner.serve(ctx, req).await?;
+        if let Some(info) = info {
+            insert_rate_limit_headers(response.headers_mut(), &info);
+        }
+        Ok(response)
+    }
+}
+
+fn ceil_secs(duration: Duration) -> u64 {
+    let secs = duration.as_secs();
+    if duration.subsec_nanos() > 0 {
+        secs.saturating_add(1)
+    } else {
+        secs
+    }
+}
+
+fn insert_rate_limit_headers(headers: &mut HeaderMap, info: &RateLimitInfo) {
+    headers.insert(X_RATELIMIT_LIMIT, HeaderValue::from(info.limit));
+    headers.insert(X_RATELIMIT_REMAINING, HeaderValue::from(info.remaining));
+    headers.insert(X_RATELIMIT_RESET, HeaderValue::from(ceil_secs(info.reset)));
+}
+
+impl IntoResponse for RateLimitReached {
+    fn into_response(self) -> Response {
+        let mut response = StatusCode::TOO_MANY_REQUESTS.into_response();
+        let headers = response.headers_mut();
+        insert_rate_limit_headers(headers, &self.info);
+        headers.insert(
+            header::RETRY_AFTER,
+            HeaderValue::from(ceil_secs(self.retry_after)),
+        );
+        response
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::Body;
+    use crate::service::web::extract::client_ip::ClientIpConfig;
+    use rama_core::layer::LimitLayer;
+    use rama_core::layer::limit::policy::{SlidingWindowPolicy, TokenBucketPolicy};
+    use rama_core::service::service_fn;
+    use std::convert::Infallible;
+
+    #[tokio::test(start_paused = true)]
+    async fn rate_limit_headers() {
+        let svc = (
+            LimitLayer::new(TokenBucketPolicy::new(2, 0.5)).with_error_into_response_fn(
+                |err: RateLimitReached| Ok::<_, Infallible>(err.into_response()),
+            ),
+            RateLimitHeadersLayer::new(),
+        )
+            .into_layer(service_fn(async |_req: Request| {
+                Ok::<_, Infallible>(Response::new(Body::empty()))
+            }));
+
+        let res = svc
+            .serve(Context::default(), Request::new(Body::empty()))
+            .await
+            .unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(res.headers()[X_RATELIMIT_LIMIT], "2");
+        assert_eq!(res.headers()[X_RATELIMIT_REMAINING], "1");
+        assert_eq!(res.headers()[X_RATELIMIT_RESET], "2");
+
+        let res = svc
+            .serve(Context::default(), Request::new(Body::empty()))
+            .await
+            .unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(res.headers()[X_RATELIMIT_REMAINING], "0");
+        assert_eq!(res.headers()[X_RATELIMIT_RESET], "4");
+
+        let res = svc
+            .serve(Context::default(), Request::new(Body::empty()))
+            .await
+            .unwrap();
+        assert_eq!(res.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(res.headers()[X_RATELIMIT_REMAINING], "0");
+        assert_eq!(res.headers()[header::RETRY_AFTER], "2");
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn sliding_window_per_client_ip() {
+        let svc = LimitLayer::new(
+            SlidingWindowPolicy::new(Duration::from_secs(60), 1).with_key_fn(ClientIpKey::new()),
+        )
+        .with_error_into_response_fn(|err: RateLimitReached| {
+            Ok::<_, Infallible>(err.into_response())
+        })
+        .into_layer(service_fn(async |_req: Request| {
+            Ok::<_, Infallible>(Response::new(Body::empty()))
+        }));
+
+        // trust the forwarding headers to simulate multiple clients
+        let mut ctx = Context::default();
+        ctx.insert(ClientIpConfig::trust_all());
+
+        let req = |ip: &str| {
+            Request::builder()
+                .header("x-forwarded-for", ip)
+                .body(Body::empty())
+                .unwrap()
+        };
+
+        let res = svc.serve(ctx.clone(), req("1.1.1.1")).await.unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+
+        tokio::time::advance(Duration::from_secs(15)).await;
+
+        let res = svc.serve(ctx.clone(), req("1.1.1.1")).await.unwrap();
+        assert_eq!(res.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(res.headers()[header::RETRY_AFTER], "45");
+
+        let res = svc.serve(ctx.clone(), req("2.2.2.2")).await.unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn token_bucket_per_header_value() {
+        let svc = LimitLayer::new(
+            TokenBucketPolicy::new(1, 0.1)
+                .with_key_fn(HeaderValueKey(HeaderName::from_static("x-api-key"))),
+        )
+        .with_error_into_response_fn(|err: RateLimitReached| {
+            Ok::<_, Infallible>(err.into_response())
+        })
+        .into_layer(service_fn(async |_req: Request| {
+            Ok::<_, Infallible>(Response::new(Body::empty()))
+        }));
+
+        let req = |key: Option<&str>| {
+            let mut builder = Request::builder();
+            if let Some(key) = key {
+                builder = builder.header("x-api-key", key);
+            }
+            builder.body(Body::empty()).unwrap()
+        };
+
+        for (key, status) in [
+            (Some("a"), StatusCode::OK),
+            (Some("a"), StatusCode::TOO_MANY_REQUESTS),
+            (Some("b"), StatusCode::OK),
+            (None, StatusCode::OK),
+            (None, StatusCode::OK),
+        ] {
+            let res = svc.serve(Context::default(), req(key)).await.unwrap();
+            assert_eq!(res.status(), status);
+        }
+    }
+
+    #[tokio::test]
+    async fn token_bucket_per_api_key() {
+        use crate::layer::auth::{ApiKeyIdentity, ApiKeyLayer, MemoryKeyStore};
+
+        let svc = (
+            ApiKeyLayer::new(
+                MemoryKeyStore::new()
+                    .with_key("key-a", ApiKeyIdentity::new("a"))
+                    .with_key("key-b", ApiKeyIdentity::new("b")),
+            ),
+            LimitLayer::new(TokenBucketPolicy::new(1, 0.1).with_key_fn(ApiKeyIdentityKey::new()))
+                .with_error_into_response_fn(|err: RateLimitReached| {
+                    Ok::<_, Infallible>(err.into_response())
+                }),
+        )
+            .into_layer(service_fn(async |_req: Request| {
+                Ok::<_, Infallible>(Response::new(Body::empty()))
+            }));
+
+        let req = |key: &str| {
+            Request::builder()
+                .header(header::AUTHORIZATION, format!("ApiKey {key}"))
+                .body(Body::empty())
+                .unwrap()
+        };
+
+        for (key, status) in [
+            ("key-a", StatusCode::OK),
+            ("key-a", StatusCode::TOO_MANY_REQUESTS),
+            ("key-b", StatusCode::OK),
+            ("key-c", StatusCode::UNAUTHORIZED),
+        ] {
+            let res = svc.serve(Context::default(), req(key)).await.unwrap();
+            assert_eq!(res.status(), status);
+        }
+    }
+
+    #[tokio::test]
+    async fn no_rate_limit_headers_without_info() {
+        let svc = RateLimitHeadersLayer::new().into_layer(service_fn(async |_req: Request| {
+            Ok::<_, Infallible>(Response::new(Body::empty()))
+        }));
+
+        let res = svc
+            .serve(Context::default(), Request::new(Body::empty()))
+            .await
+            .unwrap();
+        assert!(!res.headers().contains_key(X_RATELIMIT_LIMIT));
+    }
+}
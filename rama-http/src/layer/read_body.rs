@@ -0,0 +1,199 @@
+//! Middleware to buffer the request body, such that it can be read multiple times.
+//!
+//! The body is read fully into memory as a [`BufferedBody`], which is stored in the [`Context`]
+//! and replayed as the body of the request passed to the inner service. This allows
+//! multiple consumers of the body (e.g. signature verification, retries and body logging)
+//! as well as the [`ReadBody`] extractor to access the same body.
+//!
+//! As the body is read in full, it is recommended to limit its size
+//! using the [`BodyLimitLayer`] in front of this layer. Requests whose body exceeds
+//! the limit are responded with `413 Payload Too Large`.
+//!
+//! # Example
+//!
+//! ```
+//! use rama_core::{Context, Layer, Service};
+//! use rama_http::layer::body_limit::BodyLimitLayer;
+//! use rama_http::layer::read_body::ReadBodyLayer;
+//! use rama_http::service::web::WebService;
+//! use rama_http::service::web::extract::{ReadBody, Text};
+//! use rama_http::{Body, BodyExtractExt, Request};
+//!
+//! # #[tokio::main]
+//! # async fn main() {
+//! let service = (BodyLimitLayer::new(1024), ReadBodyLayer::new()).into_layer(
+//!     WebService::default().post("/", async |ReadBody(raw): ReadBody, Text(text): Text| {
+//!         assert_eq!(raw, text);
+//!         text
+//!     }),
+//! );
+//!
+//! let req = Request::post("/")
+//!     .header("content-type", "text/plain")
+//!     .body(Body::from("hello"))
+//!     .unwrap();
+//! let res = service.serve(Context::default(), req).await.unwrap();
+//! assert_eq!(res.into_body().try_into_string().await.unwrap(), "hello");
+//! # }
+//! ```
+//!
+//! [`ReadBody`]: crate::service::web::extract::ReadBody
+//! [`BodyLimitLayer`]: crate::layer::body_limit::BodyLimitLayer
+
+use crate::body::BufferedBody;
+use crate::dep::http_body::Body as HttpBody;
+use crate::dep::http_body_util::LengthLimitError;
+use crate::{Body, Request, Response, StatusCode};
+use rama_core::bytes::Bytes;
+use rama_core::error::BoxError;
+use rama_core::telemetry::tracing;
+use rama_core::{Context, Layer, Service};
+use rama_utils::macros::define_inner_service_accessors;
+use std::fmt;
+
+#[derive(Debug, Clone, Default)]
+/// A [`Layer`] that produces [`ReadBodyService`]s,
+/// buffering the request body before calling the inner [`Service`].
+#[non_exhaustive]
+pub struct ReadBodyLayer;
+
+impl ReadBodyLayer {
+    /// Create a new [`ReadBodyLayer`].
+    pub const fn new() -> Self {
+        Self
+    }
+}
+
+impl<S> Layer<S> for ReadBodyLayer {
+    type Service = ReadBodyService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        ReadBodyService::new(inner)
+    }
+}
+
+/// A [`Service`] which buffers the request body as a [`BufferedBody`].
+///
+/// See the [module docs](self) for more information.
+pub struct ReadBodyService<S> {
+    inner: S,
+}
+
+impl<S> ReadBodyService<S> {
+    /// Create a new [`ReadBodyService`] wrapping the given service.
+    pub const fn new(inner: S) -> Self {
+        Self { inner }
+    }
+
+    define_inner_service_accessors!();
+}
+
+impl<S: fmt::Debug> fmt::Debug for ReadBodyService<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ReadBodyService")
+            .field("inner", &self.inner)
+            .finish()
+    }
+}
+
+impl<S: Clone> Clone for ReadBodyService<S> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl<S, State, ReqBody, ResBody> Service<State, Request<ReqBody>> for ReadBodyService<S>
+where
+    S: Service<State, Request<Body>, Response = Response<ResBody>>,
+    State: Clone + Send + Sync + 'static,
+    ReqBody: HttpBody<Data = Bytes, Error: Into<BoxError>> + Send + 'static,
+    ResBody: Default + Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+
+    async fn serve(
+        &self,
+        mut ctx: Context<State>,
+        req: Request<ReqBody>,
+    ) -> Result<Self::Response, Self::Error> {
+        let (parts, body) = req.into_parts();
+        let body = match BufferedBody::buffer(body).await {
+            Ok(body) => body,
+            Err(err) => {
+                let err = err.into();
+                let status = if is_length_limit_error(err.as_ref()) {
+                    StatusCode::PAYLOAD_TOO_LARGE
+                } else {
+                    StatusCode::BAD_REQUEST
+                };
+                tracing::debug!("failed to buffer request body: {err}");
+                let mut res = Response::new(ResBody::default());
+                *res.status_mut() = status;
+                return Ok(res);
+            }
+        };
+        ctx.insert(body.clone());
+        self.inner
+            .serve(ctx, Request::from_parts(parts, body.into()))
+            .await
+    }
+}
+
+fn is_length_limit_error(err: &(dyn std::error::Error + 'static)) -> bool {
+    let mut source = Some(err);
+    while let Some(err) = source {
+        if err.is::<LengthLimitError>() {
+            return true;
+        }
+        source = err
+            .source()
+            .filter(|next| !std::ptr::addr_eq(*next as *const _, err as *const _));
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::BodyExtractExt;
+    use crate::layer::body_limit::BodyLimitLayer;
+    use rama_core::service::service_fn;
+    use std::convert::Infallible;
+
+    #[tokio::test]
+    async fn test_read_body_layer() {
+        let svc =
+            ReadBodyLayer::new().into_layer(service_fn(async |ctx: Context<()>, req: Request| {
+                let buffered = ctx.get::<BufferedBody>().unwrap().clone();
+                let body = req.into_body().try_into_string().await.unwrap();
+                assert_eq!(buffered.bytes(), body.as_bytes());
+                Ok::<_, Infallible>(Response::new(Body::from(buffered)))
+            }));
+
+        let req = Request::new(Body::from("hello"));
+        let res = svc.serve(Context::default(), req).await.unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(res.into_body().try_into_string().await.unwrap(), "hello");
+    }
+
+    #[tokio::test]
+    async fn test_read_body_layer_limit() {
+        let svc = (BodyLimitLayer::new(4), ReadBodyLayer::new()).into_layer(service_fn(
+            async |_req: Request| -> Result<Response, Infallible> {
+                panic!("inner service should not be called")
+            },
+        ));
+
+        let req = Request::new(Body::from_stream(rama_core::futures::stream::iter([Ok::<
+            _,
+            Infallible,
+        >(
+            "hello world",
+        )])));
+        let res = svc.serve(Context::default(), req).await.unwrap();
+        assert_eq!(res.status(), StatusCode::PAYLOAD_TOO_LARGE);
+    }
+}
@@ -17,6 +17,10 @@ pub use body::RetryBody;
 pub mod managed;
 pub use managed::ManagedPolicy;
 
+mod retry_after;
+#[doc(inline)]
+pub use retry_after::{RetryAfterLayer, RetryAfterService};
+
 #[cfg(test)]
 mod tests;
 
@@ -71,6 +75,23 @@ impl<P, S> Retry<P, S> {
     define_inner_service_accessors!();
 }
 
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+/// An [`Extensions`] value inserted by [`Retry`] in the [`Context`]
+/// prior to each attempt, such that the inner service can observe
+/// how many times the request has been retried so far.
+///
+/// It is `0` for the first attempt.
+///
+/// [`Extensions`]: rama_core::context::Extensions
+pub struct RetryCount(usize);
+
+impl RetryCount {
+    /// Get the amount of times the request has been retried.
+    pub const fn get(&self) -> usize {
+        self.0
+    }
+}
+
 #[derive(Debug)]
 /// Error type for [`Retry`]
 pub struct RetryError {
@@ -135,8 +156,10 @@ where
         let mut request = Request::from_parts(parts, body);
 
         let mut cloned = self.policy.clone_input(&ctx, &request);
+        let mut retry_count = 0;
 
         loop {
+            ctx.insert(RetryCount(retry_count));
             let resp = self.inner.serve(ctx, request).await;
             match cloned.take() {
                 Some((cloned_ctx, cloned_req)) => {
@@ -154,6 +177,7 @@ where
                     cloned = self.policy.clone_input(&cloned_ctx, &cloned_req);
                     ctx = cloned_ctx;
                     request = cloned_req;
+                    retry_count += 1;
                 }
                 // no clone was made, so no possibility to retry
                 None => {
@@ -0,0 +1,257 @@
+use super::{RetryBody, RetryCount, RetryError, RetryErrorKind};
+use crate::dep::http_body::Body as HttpBody;
+use crate::dep::http_body_util::BodyExt;
+use crate::headers::{HeaderMapExt, RetryAfter};
+use crate::{Request, Response, StatusCode};
+use rama_core::error::BoxError;
+use rama_core::telemetry::tracing;
+use rama_core::{Context, Layer, Service};
+use rama_utils::macros::define_inner_service_accessors;
+use std::fmt;
+use std::time::{Duration, SystemTime};
+
+/// Layer that applies the [`RetryAfterService`] middleware.
+#[derive(Debug, Clone)]
+pub struct RetryAfterLayer {
+    max_wait: Duration,
+}
+
+impl RetryAfterLayer {
+    /// Create a new [`RetryAfterLayer`], waiting at most `max_wait`
+    /// before retrying a `503 Service Unavailable` response.
+    pub const fn new(max_wait: Duration) -> Self {
+        Self { max_wait }
+    }
+}
+
+impl<S> Layer<S> for RetryAfterLayer {
+    type Service = RetryAfterService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        RetryAfterService::new(inner, self.max_wait)
+    }
+}
+
+/// Retry a request once when the inner service responds with
+/// `503 Service Unavailable` and a `Retry-After` header.
+///
+/// The request (body) is buffered such that it can be sent again after the
+/// delay indicated by the `Retry-After` header, which can be either a delay in
+/// seconds or an HTTP-date. In case that delay exceeds the configured `max_wait`,
+/// or the retried request fails again, the `503` response is returned as-is.
+///
+/// A [`RetryCount`] is inserted in the [`Context`] prior to each attempt.
+pub struct RetryAfterService<S> {
+    inner: S,
+    max_wait: Duration,
+}
+
+impl<S> RetryAfterService<S> {
+    /// Create a new [`RetryAfterService`], waiting at most `max_wait`
+    /// before retrying a `503 Service Unavailable` response.
+    pub const fn new(inner: S, max_wait: Duration) -> Self {
+        Self { inner, max_wait }
+    }
+
+    define_inner_service_accessors!();
+}
+
+impl<S: fmt::Debug> fmt::Debug for RetryAfterService<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RetryAfterService")
+            .field("inner", &self.inner)
+            .field("max_wait", &self.max_wait)
+            .finish()
+    }
+}
+
+impl<S: Clone> Clone for RetryAfterService<S> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            max_wait: self.max_wait,
+        }
+    }
+}
+
+impl<S> RetryAfterService<S> {
+    /// Returns the delay to wait before retrying,
+    /// in case the response is to be retried at all.
+    fn retry_delay<B>(&self, res: &Response<B>) -> Option<Duration> {
+        if res.status() != StatusCode::SERVICE_UNAVAILABLE {
+            return None;
+        }
+        let delay = res
+            .headers()
+            .typed_get::<RetryAfter>()?
+            .delay_from(SystemTime::now());
+        if delay > self.max_wait {
+            tracing::debug!(
+                ?delay,
+                max_wait = ?self.max_wait,
+                "retry-after delay exceeds max wait: do not retry",
+            );
+            return None;
+        }
+        Some(delay)
+    }
+}
+
+impl<State, S, Body, ResBody> Service<State, Request<Body>> for RetryAfterService<S>
+where
+    State: Clone + Send + Sync + 'static,
+    S: Service<State, Request<RetryBody>, Response = Response<ResBody>, Error: Into<BoxError>>,
+    Body: HttpBody<Data: Send + 'static, Error: Into<BoxError>> + Send + 'static,
+    ResBody: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = RetryError;
+
+    async fn serve(
+        &self,
+        mut ctx: Context<State>,
+        request: Request<Body>,
+    ) -> Result<Self::Response, Self::Error> {
+        // consume body so we can clone the request
+        let (parts, body) = request.into_parts();
+        let body = body.collect().await.map_err(|e| RetryError {
+            kind: RetryErrorKind::BodyConsume,
+            inner: Some(e.into()),
+        })?;
+        let request = Request::from_parts(parts, RetryBody::new(body.to_bytes()));
+
+        ctx.insert(RetryCount(0));
+        let res = self
+            .inner
+            .serve(ctx.clone(), request.clone())
+            .await
+            .map_err(|e| RetryError {
+                kind: RetryErrorKind::Service,
+                inner: Some(e.into()),
+            })?;
+
+        let Some(delay) = self.retry_delay(&res) else {
+            return Ok(res);
+        };
+
+        tracing::debug!(?delay, "service unavailable: retry request after delay");
+        tokio::time::sleep(delay).await;
+
+        ctx.insert(RetryCount(1));
+        self.inner
+            .serve(ctx, request)
+            .await
+            .map_err(|e| RetryError {
+                kind: RetryErrorKind::Service,
+                inner: Some(e.into()),
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::service::web::response::IntoResponse;
+    use crate::{Body, BodyExtractExt};
+    use rama_core::service::service_fn;
+    use std::convert::Infallible;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    fn unavailable_once(
+        retry_after: RetryAfter,
+        counter: Arc<AtomicUsize>,
+    ) -> impl Service<(), Request<RetryBody>, Response = Response, Error = Infallible> {
+        service_fn(move |ctx: Context<()>, req: Request<RetryBody>| {
+            let retry_after = retry_after.clone();
+            let counter = counter.clone();
+            async move {
+                counter.fetch_add(1, Ordering::SeqCst);
+                let retry_count = ctx.get::<RetryCount>().unwrap().get();
+                if retry_count == 0 {
+                    let mut res = StatusCode::SERVICE_UNAVAILABLE.into_response();
+                    res.headers_mut().typed_insert(retry_after);
+                    return Ok(res);
+                }
+                let body = req.try_into_string().await.unwrap();
+                Ok(format!("{retry_count}: {body}").into_response())
+            }
+        })
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_retry_after_delay() {
+        let counter = Arc::new(AtomicUsize::new(0));
+        let svc = RetryAfterLayer::new(Duration::from_secs(10)).into_layer(unavailable_once(
+            RetryAfter::delay(Duration::from_secs(5)),
+            counter.clone(),
+        ));
+
+        let start = tokio::time::Instant::now();
+        let res = svc
+            .serve(Context::default(), Request::new(Body::from("hello")))
+            .await
+            .unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(res.try_into_string().await.unwrap(), "1: hello");
+        assert_eq!(counter.load(Ordering::SeqCst), 2);
+        assert!(start.elapsed() >= Duration::from_secs(5));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_retry_after_date() {
+        let counter = Arc::new(AtomicUsize::new(0));
+        let svc = RetryAfterLayer::new(Duration::from_secs(10)).into_layer(unavailable_once(
+            RetryAfter::date(SystemTime::now() + Duration::from_secs(3)),
+            counter.clone(),
+        ));
+
+        let res = svc
+            .serve(Context::default(), Request::new(Body::from("hello")))
+            .await
+            .unwrap();
+        assert_eq!(res.try_into_string().await.unwrap(), "1: hello");
+        assert_eq!(counter.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_retry_after_exceeds_max_wait() {
+        let counter = Arc::new(AtomicUsize::new(0));
+        let svc = RetryAfterLayer::new(Duration::from_secs(10)).into_layer(unavailable_once(
+            RetryAfter::delay(Duration::from_secs(60)),
+            counter.clone(),
+        ));
+
+        let res = svc
+            .serve(Context::default(), Request::new(Body::from("hello")))
+            .await
+            .unwrap();
+        assert_eq!(res.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(counter.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_retry_after_retries_once() {
+        let counter = Arc::new(AtomicUsize::new(0));
+        let svc = RetryAfterLayer::new(Duration::from_secs(10)).into_layer(service_fn({
+            let counter = counter.clone();
+            move || {
+                let counter = counter.clone();
+                async move {
+                    counter.fetch_add(1, Ordering::SeqCst);
+                    let mut res = StatusCode::SERVICE_UNAVAILABLE.into_response();
+                    res.headers_mut()
+                        .typed_insert(RetryAfter::delay(Duration::from_secs(1)));
+                    Ok::<_, Infallible>(res)
+                }
+            }
+        }));
+
+        let res = svc
+            .serve(Context::default(), Request::new(Body::empty()))
+            .await
+            .unwrap();
+        assert_eq!(res.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(counter.load(Ordering::SeqCst), 2);
+    }
+}
@@ -68,11 +68,12 @@ async fn retry_limit() {
 
         async fn serve(
             &self,
-            _ctx: Context<State>,
+            ctx: Context<State>,
             req: Request<RetryBody>,
         ) -> Result<Self::Response, Self::Error> {
             assert_eq!(req.try_into_string().await.unwrap(), "hello");
-            self.error_counter.fetch_add(1, Ordering::AcqRel);
+            let attempt = self.error_counter.fetch_add(1, Ordering::AcqRel);
+            assert_eq!(ctx.get::<RetryCount>().map(RetryCount::get), Some(attempt));
             Err(error!("error forever"))
         }
     }
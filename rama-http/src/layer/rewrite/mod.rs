@@ -0,0 +1,48 @@
+//! Middleware that rewrites the path of request URIs.
+//!
+//! A [`RewriteRule`] can strip a path prefix, add a path prefix
+//! or rewrite the path using a regex pattern. The query of the URI is
+//! always preserved as is.
+//!
+//! The [`UrlRewrite`] middleware inserts the URI as it was received
+//! as [`OriginalUri`] in the [`Context`], prior to rewriting it,
+//! such that downstream handlers and loggers can still access it.
+//!
+//! Using [`UrlRewriteLayer::from_context`] the [`RewriteRule`] is instead
+//! read from the [`Context`], e.g. to rewrite requests differently per route.
+//!
+//! # Example
+//!
+//! ```
+//! use std::convert::Infallible;
+//!
+//! use rama_core::service::service_fn;
+//! use rama_core::{Context, Layer, Service};
+//! use rama_http::layer::rewrite::{OriginalUri, UrlRewriteLayer};
+//! use rama_http::{Body, Request, Response};
+//!
+//! # #[tokio::main]
+//! # async fn main() {
+//! let svc = UrlRewriteLayer::strip_prefix("/api").into_layer(service_fn(
+//!     async |ctx: Context<()>, req: Request| {
+//!         assert_eq!(req.uri(), "/users?id=1");
+//!         assert_eq!(ctx.get::<OriginalUri>().unwrap().0, "/api/users?id=1");
+//!         Ok::<_, Infallible>(Response::new(Body::empty()))
+//!     },
+//! ));
+//!
+//! let req = Request::builder()
+//!     .uri("/api/users?id=1")
+//!     .body(Body::empty())
+//!     .unwrap();
+//! svc.serve(Context::default(), req).await.unwrap();
+//! # }
+//! ```
+//!
+//! [`Context`]: rama_core::Context
+
+mod rule;
+mod service;
+
+pub use rule::RewriteRule;
+pub use service::{OriginalUri, UrlRewrite, UrlRewriteLayer};
@@ -0,0 +1,176 @@
+use crate::Uri;
+use crate::matcher::uri::dep::regex::Regex;
+use std::borrow::Cow;
+
+#[derive(Debug, Clone)]
+/// A rule used by the [`UrlRewrite`] middleware to rewrite the path of a request URI.
+///
+/// It can also be inserted in the [`Context`] to be used
+/// by a [`UrlRewriteLayer::from_context`] middleware.
+///
+/// [`UrlRewrite`]: super::UrlRewrite
+/// [`UrlRewriteLayer::from_context`]: super::UrlRewriteLayer::from_context
+/// [`Context`]: rama_core::Context
+pub struct RewriteRule {
+    kind: RewriteKind,
+}
+
+#[derive(Debug, Clone)]
+enum RewriteKind {
+    StripPrefix(String),
+    AddPrefix(String),
+    Regex { pattern: Regex, replacement: String },
+}
+
+impl RewriteRule {
+    /// Create a [`RewriteRule`] which removes the given prefix from the path.
+    ///
+    /// The prefix is only removed when it matches whole path segments,
+    /// e.g. `/api` is stripped from `/api/users` but not from `/apis`.
+    pub fn strip_prefix(prefix: &str) -> Self {
+        Self {
+            kind: RewriteKind::StripPrefix(normalize_prefix(prefix)),
+        }
+    }
+
+    /// Create a [`RewriteRule`] which adds the given prefix to the path.
+    pub fn add_prefix(prefix: &str) -> Self {
+        Self {
+            kind: RewriteKind::AddPrefix(normalize_prefix(prefix)),
+        }
+    }
+
+    /// Create a [`RewriteRule`] which replaces all matches of the given
+    /// pattern in the path with the given replacement.
+    ///
+    /// The replacement supports capture group references such as `$1` or `$name`,
+    /// see [`Regex::replace_all`] for more information.
+    pub fn rewrite(pattern: Regex, replacement: &str) -> Self {
+        Self {
+            kind: RewriteKind::Regex {
+                pattern,
+                replacement: replacement.to_owned(),
+            },
+        }
+    }
+
+    /// Rewrite the path of the given [`Uri`] according to this rule,
+    /// preserving its query.
+    ///
+    /// Returns `None` in case the rule does not apply
+    /// or the rewritten path is not valid.
+    pub fn rewrite_uri(&self, uri: &Uri) -> Option<Uri> {
+        let path = self.rewrite_path(uri.path())?;
+
+        let path_and_query = match uri.query() {
+            Some(query) => format!("{path}?{query}"),
+            None => path.into_owned(),
+        };
+
+        let mut parts = uri.clone().into_parts();
+        parts.path_and_query = Some(path_and_query.parse().ok()?);
+        Uri::from_parts(parts).ok()
+    }
+
+    fn rewrite_path<'a>(&self, path: &'a str) -> Option<Cow<'a, str>> {
+        match &self.kind {
+            RewriteKind::StripPrefix(prefix) => {
+                if prefix.is_empty() {
+                    return None;
+                }
+                let rest = path.strip_prefix(prefix.as_str())?;
+                if rest.is_empty() {
+                    Some(Cow::Borrowed("/"))
+                } else if rest.starts_with('/') {
+                    Some(Cow::Borrowed(rest))
+                } else {
+                    None
+                }
+            }
+            RewriteKind::AddPrefix(prefix) => {
+                if prefix.is_empty() {
+                    return None;
+                }
+                Some(Cow::Owned(format!("{prefix}{path}")))
+            }
+            RewriteKind::Regex {
+                pattern,
+                replacement,
+            } => {
+                if !pattern.is_match(path) {
+                    return None;
+                }
+                let path = pattern.replace_all(path, replacement.as_str());
+                if path.starts_with('/') {
+                    Some(path)
+                } else {
+                    Some(Cow::Owned(format!("/{path}")))
+                }
+            }
+        }
+    }
+}
+
+/// Ensure a prefix starts with a slash and does not end with one,
+/// with an empty string representing the root prefix.
+fn normalize_prefix(prefix: &str) -> String {
+    let prefix = prefix.trim_matches('/');
+    if prefix.is_empty() {
+        String::new()
+    } else {
+        format!("/{prefix}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rewrite(rule: &RewriteRule, uri: &str) -> Option<String> {
+        rule.rewrite_uri(&uri.parse().unwrap())
+            .map(|uri| uri.to_string())
+    }
+
+    #[test]
+    fn test_strip_prefix() {
+        let rule = RewriteRule::strip_prefix("/api/");
+        assert_eq!(rewrite(&rule, "/api/users").as_deref(), Some("/users"));
+        assert_eq!(rewrite(&rule, "/api").as_deref(), Some("/"));
+        assert_eq!(rewrite(&rule, "/api/?a=b").as_deref(), Some("/?a=b"));
+        assert_eq!(
+            rewrite(&rule, "http://example.com/api/users?a=b").as_deref(),
+            Some("http://example.com/users?a=b")
+        );
+        assert_eq!(rewrite(&rule, "/apis"), None);
+        assert_eq!(rewrite(&rule, "/users"), None);
+    }
+
+    #[test]
+    fn test_add_prefix() {
+        let rule = RewriteRule::add_prefix("v1");
+        assert_eq!(rewrite(&rule, "/users").as_deref(), Some("/v1/users"));
+        assert_eq!(rewrite(&rule, "/?a=b").as_deref(), Some("/v1/?a=b"));
+
+        let rule = RewriteRule::add_prefix("/");
+        assert_eq!(rewrite(&rule, "/users"), None);
+    }
+
+    #[test]
+    fn test_regex_rewrite() {
+        let rule = RewriteRule::rewrite(
+            Regex::new(r"^/users/(?<id>\d+)$").unwrap(),
+            "/api/v2/users/$id",
+        );
+        assert_eq!(
+            rewrite(&rule, "/users/42?a=b").as_deref(),
+            Some("/api/v2/users/42?a=b")
+        );
+        assert_eq!(rewrite(&rule, "/users/abc"), None);
+
+        let rule = RewriteRule::rewrite(Regex::new("^/old").unwrap(), "");
+        assert_eq!(rewrite(&rule, "/old").as_deref(), Some("/"));
+
+        let rule = RewriteRule::rewrite(Regex::new("a").unwrap(), " ");
+        assert_eq!(rewrite(&rule, "/a"), None);
+    }
+}
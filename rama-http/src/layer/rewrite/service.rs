@@ -0,0 +1,223 @@
+use super::RewriteRule;
+use crate::matcher::uri::dep::regex::Regex;
+use crate::{Request, Uri};
+use rama_core::telemetry::tracing;
+use rama_core::{Context, Layer, Service};
+use rama_utils::macros::define_inner_service_accessors;
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// The [`Uri`] of the request as it was received by the [`UrlRewrite`] middleware,
+/// inserted in the [`Context`] prior to rewriting it.
+///
+/// In case multiple [`UrlRewrite`] middlewares are applied,
+/// the [`Uri`] as received by the outermost one is preserved.
+pub struct OriginalUri(pub Uri);
+
+/// Layer that applies the [`UrlRewrite`] middleware which rewrites the request URI.
+///
+/// See the [module docs](super) for an example.
+#[derive(Debug, Clone)]
+pub struct UrlRewriteLayer {
+    rule: Option<RewriteRule>,
+}
+
+impl UrlRewriteLayer {
+    /// Creates a new [`UrlRewriteLayer`] which applies the given [`RewriteRule`].
+    ///
+    /// A [`RewriteRule`] found in the [`Context`] is ignored,
+    /// use [`UrlRewriteLayer::from_context`] to apply such rules instead.
+    pub const fn new(rule: RewriteRule) -> Self {
+        Self { rule: Some(rule) }
+    }
+
+    /// Creates a new [`UrlRewriteLayer`] which removes the given prefix from the path.
+    ///
+    /// See [`RewriteRule::strip_prefix`] for more information.
+    pub fn strip_prefix(prefix: &str) -> Self {
+        Self::new(RewriteRule::strip_prefix(prefix))
+    }
+
+    /// Creates a new [`UrlRewriteLayer`] which adds the given prefix to the path.
+    ///
+    /// See [`RewriteRule::add_prefix`] for more information.
+    pub fn add_prefix(prefix: &str) -> Self {
+        Self::new(RewriteRule::add_prefix(prefix))
+    }
+
+    /// Creates a new [`UrlRewriteLayer`] which rewrites the path using the given pattern.
+    ///
+    /// See [`RewriteRule::rewrite`] for more information.
+    pub fn rewrite(pattern: Regex, replacement: &str) -> Self {
+        Self::new(RewriteRule::rewrite(pattern, replacement))
+    }
+
+    /// Creates a new [`UrlRewriteLayer`] which only applies the [`RewriteRule`]
+    /// found in the [`Context`], the URI is not rewritten in case it is missing.
+    pub const fn from_context() -> Self {
+        Self { rule: None }
+    }
+}
+
+impl<S> Layer<S> for UrlRewriteLayer {
+    type Service = UrlRewrite<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        UrlRewrite {
+            inner,
+            rule: self.rule.clone(),
+        }
+    }
+
+    fn into_layer(self, inner: S) -> Self::Service {
+        UrlRewrite {
+            inner,
+            rule: self.rule,
+        }
+    }
+}
+
+/// Middleware which rewrites the request URI.
+///
+/// See the [module docs](super) for an example.
+pub struct UrlRewrite<S> {
+    inner: S,
+    rule: Option<RewriteRule>,
+}
+
+impl<S> UrlRewrite<S> {
+    /// Creates a new [`UrlRewrite`] which applies the given [`RewriteRule`].
+    ///
+    /// A [`RewriteRule`] found in the [`Context`] is ignored,
+    /// use [`UrlRewrite::from_context`] to apply such rules instead.
+    pub const fn new(inner: S, rule: RewriteRule) -> Self {
+        Self {
+            inner,
+            rule: Some(rule),
+        }
+    }
+
+    /// Creates a new [`UrlRewrite`] which only applies the [`RewriteRule`]
+    /// found in the [`Context`], the URI is not rewritten in case it is missing.
+    pub const fn from_context(inner: S) -> Self {
+        Self { inner, rule: None }
+    }
+
+    define_inner_service_accessors!();
+}
+
+impl<S: fmt::Debug> fmt::Debug for UrlRewrite<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("UrlRewrite")
+            .field("inner", &self.inner)
+            .field("rule", &self.rule)
+            .finish()
+    }
+}
+
+impl<S: Clone> Clone for UrlRewrite<S> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            rule: self.rule.clone(),
+        }
+    }
+}
+
+impl<S, State, ReqBody> Service<State, Request<ReqBody>> for UrlRewrite<S>
+where
+    S: Service<State, Request<ReqBody>>,
+    ReqBody: Send + 'static,
+    State: Clone + Send + Sync + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+
+    async fn serve(
+        &self,
+        mut ctx: Context<State>,
+        mut req: Request<ReqBody>,
+    ) -> Result<Self::Response, Self::Error> {
+        if !ctx.contains::<OriginalUri>() {
+            ctx.insert(OriginalUri(req.uri().clone()));
+        }
+
+        let rule = match self.rule.as_ref() {
+            Some(rule) => Some(rule),
+            None => ctx.get::<RewriteRule>(),
+        };
+        if let Some(uri) = rule.and_then(|rule| rule.rewrite_uri(req.uri())) {
+            tracing::trace!(original = %req.uri(), rewritten = %uri, "rewrite request uri");
+            *req.uri_mut() = uri;
+        }
+
+        self.inner.serve(ctx, req).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Body, Response};
+    use rama_core::service::service_fn;
+    use std::convert::Infallible;
+
+    async fn echo_uri(ctx: Context<()>, req: Request) -> Result<Response<String>, Infallible> {
+        let original = ctx.get::<OriginalUri>().unwrap();
+        Ok(Response::new(format!("{} {}", original.0, req.uri())))
+    }
+
+    async fn serve(
+        svc: &impl Service<(), Request, Response = Response<String>>,
+        ctx: Context<()>,
+        uri: &str,
+    ) -> String {
+        let req = Request::builder().uri(uri).body(Body::empty()).unwrap();
+        match svc.serve(ctx, req).await {
+            Ok(res) => res.into_body(),
+            Err(_) => unreachable!(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_url_rewrite() {
+        let svc = (
+            UrlRewriteLayer::strip_prefix("/api"),
+            UrlRewriteLayer::add_prefix("/v1"),
+        )
+            .into_layer(service_fn(echo_uri));
+
+        assert_eq!(
+            serve(&svc, Context::default(), "/api/users?a=b").await,
+            "/api/users?a=b /v1/users?a=b"
+        );
+        assert_eq!(
+            serve(&svc, Context::default(), "/users").await,
+            "/users /v1/users"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_url_rewrite_from_context() {
+        let svc = UrlRewriteLayer::from_context().into_layer(service_fn(echo_uri));
+
+        assert_eq!(serve(&svc, Context::default(), "/foo").await, "/foo /foo");
+
+        let mut ctx = Context::default();
+        ctx.insert(RewriteRule::rewrite(Regex::new("^/foo").unwrap(), "/bar"));
+        assert_eq!(serve(&svc, ctx, "/foo/baz").await, "/foo/baz /bar/baz");
+    }
+
+    #[tokio::test]
+    async fn test_url_rewrite_context_ignored_by_static_rule() {
+        let svc = (
+            UrlRewriteLayer::from_context(),
+            UrlRewriteLayer::add_prefix("/v1"),
+        )
+            .into_layer(service_fn(echo_uri));
+
+        let mut ctx = Context::default();
+        ctx.insert(RewriteRule::add_prefix("/v2"));
+        assert_eq!(serve(&svc, ctx, "/foo").await, "/foo /v1/v2/foo");
+    }
+}
@@ -0,0 +1,318 @@
+use super::Pattern;
+use crate::{HeaderName, Request, Response};
+use rama_core::error::BoxError;
+use rama_core::layer::MapErr;
+use rama_core::service::BoxService;
+use rama_core::telemetry::tracing;
+use rama_core::{Context, Layer, Service};
+use rama_utils::macros::define_inner_service_accessors;
+use std::fmt;
+use std::sync::Arc;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// The name of the route matched by a [`HeaderRouterService`],
+/// inserted in the [`Context`] for logging purposes.
+///
+/// The name has the format `<header>:<pattern>`, e.g. `x-api-version:exact(2)`.
+pub struct MatchedHeaderRoute(pub String);
+
+struct HeaderRoute<State> {
+    name: String,
+    header: HeaderName,
+    pattern: Pattern,
+    service: BoxService<State, Request, Response, BoxError>,
+}
+
+impl<State> Clone for HeaderRoute<State> {
+    fn clone(&self) -> Self {
+        Self {
+            name: self.name.clone(),
+            header: self.header.clone(),
+            pattern: self.pattern.clone(),
+            service: self.service.clone(),
+        }
+    }
+}
+
+impl<State> HeaderRoute<State> {
+    fn matches(&self, req: &Request) -> bool {
+        req.headers()
+            .get_all(&self.header)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .any(|value| self.pattern.matches(value))
+    }
+}
+
+/// A [`Layer`] that produces [`HeaderRouterService`]s,
+/// dispatching requests to a route service based on header value [`Pattern`]s.
+///
+/// Routes are tried in the order they are added, with the first route of which
+/// the [`Pattern`] matches any of the values of its header serving the request.
+/// When no route matches, the request falls through to the wrapped (default) service.
+///
+/// The name of the matched route is inserted in the [`Context`] as [`MatchedHeaderRoute`].
+///
+/// # Example
+///
+/// ```
+/// use rama_core::service::service_fn;
+/// use rama_core::{Context, Layer, Service};
+/// use rama_http::layer::route::{HeaderRouterLayer, Pattern};
+/// use rama_http::service::web::response::IntoResponse;
+/// use rama_http::{Body, HeaderName, Request, Response};
+/// use std::convert::Infallible;
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// let service = HeaderRouterLayer::new()
+///     .route(
+///         HeaderName::from_static("x-api-version"),
+///         Pattern::exact("2"),
+///         service_fn(async || Ok::<_, Infallible>("v2".into_response())),
+///     )
+///     .into_layer(service_fn(async || Ok::<_, Infallible>("v1".into_response())));
+///
+/// let req = Request::builder()
+///     .header("x-api-version", "2")
+///     .body(Body::empty())
+///     .unwrap();
+/// let res: Response = service.serve(Context::default(), req).await.unwrap();
+/// # }
+/// ```
+pub struct HeaderRouterLayer<State> {
+    routes: Vec<HeaderRoute<State>>,
+}
+
+impl<State> fmt::Debug for HeaderRouterLayer<State> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("HeaderRouterLayer")
+            .field(
+                "routes",
+                &self.routes.iter().map(|r| &r.name).collect::<Vec<_>>(),
+            )
+            .finish()
+    }
+}
+
+impl<State> Clone for HeaderRouterLayer<State> {
+    fn clone(&self) -> Self {
+        Self {
+            routes: self.routes.clone(),
+        }
+    }
+}
+
+impl<State> Default for HeaderRouterLayer<State> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<State> HeaderRouterLayer<State> {
+    /// Create a new [`HeaderRouterLayer`] without any routes.
+    pub fn new() -> Self {
+        Self { routes: Vec::new() }
+    }
+}
+
+impl<State> HeaderRouterLayer<State>
+where
+    State: Clone + Send + Sync + 'static,
+{
+    /// Add a route dispatching requests to the given [`Service`]
+    /// in case a value of the given header matches the [`Pattern`].
+    pub fn route<S>(mut self, header: HeaderName, pattern: Pattern, service: S) -> Self
+    where
+        S: Service<State, Request, Response = Response, Error: Into<BoxError>>,
+    {
+        self.set_route(header, pattern, service);
+        self
+    }
+
+    /// Add a route dispatching requests to the given [`Service`]
+    /// in case a value of the given header matches the [`Pattern`].
+    pub fn set_route<S>(&mut self, header: HeaderName, pattern: Pattern, service: S) -> &mut Self
+    where
+        S: Service<State, Request, Response = Response, Error: Into<BoxError>>,
+    {
+        self.routes.push(HeaderRoute {
+            name: format!("{header}:{pattern}"),
+            header,
+            pattern,
+            service: MapErr::new(service, Into::into).boxed(),
+        });
+        self
+    }
+}
+
+impl<State, S> Layer<S> for HeaderRouterLayer<State> {
+    type Service = HeaderRouterService<State, S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        self.clone().into_layer(inner)
+    }
+
+    fn into_layer(self, inner: S) -> Self::Service {
+        HeaderRouterService {
+            inner,
+            routes: self.routes.into(),
+        }
+    }
+}
+
+/// Middleware [`Service`] dispatching requests to a route service based on header value [`Pattern`]s.
+///
+/// See [`HeaderRouterLayer`] for more information.
+pub struct HeaderRouterService<State, S> {
+    inner: S,
+    routes: Arc<[HeaderRoute<State>]>,
+}
+
+impl<State, S: fmt::Debug> fmt::Debug for HeaderRouterService<State, S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("HeaderRouterService")
+            .field("inner", &self.inner)
+            .field(
+                "routes",
+                &self.routes.iter().map(|r| &r.name).collect::<Vec<_>>(),
+            )
+            .finish()
+    }
+}
+
+impl<State, S: Clone> Clone for HeaderRouterService<State, S> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            routes: self.routes.clone(),
+        }
+    }
+}
+
+impl<State, S> HeaderRouterService<State, S> {
+    define_inner_service_accessors!();
+}
+
+impl<State, S> Service<State, Request> for HeaderRouterService<State, S>
+where
+    State: Clone + Send + Sync + 'static,
+    S: Service<State, Request, Response = Response, Error: Into<BoxError>>,
+{
+    type Response = Response;
+    type Error = BoxError;
+
+    async fn serve(
+        &self,
+        mut ctx: Context<State>,
+        req: Request,
+    ) -> Result<Self::Response, Self::Error> {
+        match self.routes.iter().find(|route| route.matches(&req)) {
+            Some(route) => {
+                tracing::trace!(route = %route.name, "HeaderRouterService: route matched");
+                ctx.insert(MatchedHeaderRoute(route.name.clone()));
+                route.service.serve(ctx, req).await
+            }
+            None => self.inner.serve(ctx, req).await.map_err(Into::into),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dep::http_body_util::BodyExt as _;
+    use crate::matcher::uri::dep::regex::Regex;
+    use crate::service::web::response::IntoResponse;
+    use crate::{Body, header};
+    use rama_core::service::service_fn;
+    use std::convert::Infallible;
+
+    fn named(
+        name: &'static str,
+    ) -> impl Service<(), Request, Response = Response, Error = Infallible> {
+        service_fn(move |ctx: Context<()>, _req: Request| async move {
+            let route = ctx
+                .get::<MatchedHeaderRoute>()
+                .map(|MatchedHeaderRoute(route)| route.clone())
+                .unwrap_or_default();
+            Ok::<_, Infallible>(format!("{name}|{route}").into_response())
+        })
+    }
+
+    async fn serve(
+        svc: &impl Service<(), Request, Response = Response, Error = BoxError>,
+        headers: &[(&str, &str)],
+    ) -> String {
+        let mut builder = Request::builder();
+        for (name, value) in headers {
+            builder = builder.header(*name, *value);
+        }
+        let res = svc
+            .serve(Context::default(), builder.body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let body = res.into_body().collect().await.unwrap().to_bytes();
+        String::from_utf8(body.to_vec()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_header_router() {
+        let svc = HeaderRouterLayer::new()
+            .route(
+                HeaderName::from_static("x-api-version"),
+                Pattern::regex(Regex::new(r"^2(\.[0-9]+)?$").unwrap()),
+                named("v2"),
+            )
+            .route(
+                header::CONTENT_TYPE,
+                Pattern::suffix("+json"),
+                named("json"),
+            )
+            .route(header::USER_AGENT, Pattern::glob("curl/*"), named("curl"))
+            .into_layer(named("default"));
+
+        for (headers, expected) in [
+            (vec![], "default|"),
+            (vec![("x-api-version", "1")], "default|"),
+            (
+                vec![("x-api-version", "2.1")],
+                "v2|x-api-version:regex(^2(\\.[0-9]+)?$)",
+            ),
+            (
+                vec![
+                    ("content-type", "application/vnd.api+json"),
+                    ("x-api-version", "2"),
+                ],
+                "v2|x-api-version:regex(^2(\\.[0-9]+)?$)",
+            ),
+            (
+                vec![("content-type", "application/vnd.api+json")],
+                "json|content-type:suffix(+json)",
+            ),
+            (
+                vec![("user-agent", "curl/8.0")],
+                "curl|user-agent:glob(curl/*)",
+            ),
+            (vec![("user-agent", "Mozilla/5.0")], "default|"),
+        ] {
+            assert_eq!(serve(&svc, &headers).await, expected, "{headers:?}");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_header_router_multiple_values() {
+        let svc = HeaderRouterLayer::new()
+            .route(
+                HeaderName::from_static("x-feature"),
+                Pattern::exact("beta"),
+                named("beta"),
+            )
+            .into_layer(named("default"));
+
+        assert_eq!(
+            serve(&svc, &[("x-feature", "alpha"), ("x-feature", "beta")]).await,
+            "beta|x-feature:exact(beta)"
+        );
+    }
+}
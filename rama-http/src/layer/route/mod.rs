@@ -0,0 +1,11 @@
+//! Middleware to route requests to different services based on their headers.
+//!
+//! See [`HeaderRouterLayer`] for more information.
+
+mod pattern;
+#[doc(inline)]
+pub use pattern::Pattern;
+
+mod header;
+#[doc(inline)]
+pub use header::{HeaderRouterLayer, HeaderRouterService, MatchedHeaderRoute};
@@ -0,0 +1,158 @@
+use crate::matcher::uri::dep::regex::Regex;
+use std::fmt;
+
+#[derive(Debug, Clone)]
+/// A pattern matched against a header value by the [`HeaderRouterLayer`].
+///
+/// All patterns are case-sensitive, with the exception of regular expressions
+/// which can opt-in to case-insensitive matching using the `(?i)` flag.
+///
+/// [`HeaderRouterLayer`]: super::HeaderRouterLayer
+pub struct Pattern {
+    kind: PatternKind,
+}
+
+#[derive(Debug, Clone)]
+enum PatternKind {
+    Exact(String),
+    Prefix(String),
+    Suffix(String),
+    Regex(Regex),
+    Glob(Vec<char>),
+}
+
+impl Pattern {
+    /// Create a [`Pattern`] matching values equal to the given value.
+    pub fn exact(value: impl Into<String>) -> Self {
+        Self {
+            kind: PatternKind::Exact(value.into()),
+        }
+    }
+
+    /// Create a [`Pattern`] matching values starting with the given prefix.
+    pub fn prefix(prefix: impl Into<String>) -> Self {
+        Self {
+            kind: PatternKind::Prefix(prefix.into()),
+        }
+    }
+
+    /// Create a [`Pattern`] matching values ending with the given suffix.
+    pub fn suffix(suffix: impl Into<String>) -> Self {
+        Self {
+            kind: PatternKind::Suffix(suffix.into()),
+        }
+    }
+
+    /// Create a [`Pattern`] matching values for which the given [`Regex`] finds a match.
+    ///
+    /// Use anchors (`^` and `$`) to match the full value.
+    pub fn regex(regex: Regex) -> Self {
+        Self {
+            kind: PatternKind::Regex(regex),
+        }
+    }
+
+    /// Create a [`Pattern`] matching values against the given glob,
+    /// where `*` matches any sequence of characters and `?` matches a single character.
+    pub fn glob(glob: impl Into<String>) -> Self {
+        Self {
+            kind: PatternKind::Glob(glob.into().chars().collect()),
+        }
+    }
+
+    /// Returns `true` if the given value matches this [`Pattern`].
+    pub fn matches(&self, value: &str) -> bool {
+        match &self.kind {
+            PatternKind::Exact(expected) => value == expected,
+            PatternKind::Prefix(prefix) => value.starts_with(prefix.as_str()),
+            PatternKind::Suffix(suffix) => value.ends_with(suffix.as_str()),
+            PatternKind::Regex(regex) => regex.is_match(value),
+            PatternKind::Glob(glob) => glob_matches(glob, &value.chars().collect::<Vec<_>>()),
+        }
+    }
+}
+
+impl fmt::Display for Pattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.kind {
+            PatternKind::Exact(value) => write!(f, "exact({value})"),
+            PatternKind::Prefix(prefix) => write!(f, "prefix({prefix})"),
+            PatternKind::Suffix(suffix) => write!(f, "suffix({suffix})"),
+            PatternKind::Regex(regex) => write!(f, "regex({regex})"),
+            PatternKind::Glob(glob) => write!(f, "glob({})", glob.iter().collect::<String>()),
+        }
+    }
+}
+
+/// Match the value against the glob, backtracking to the last `*` on a mismatch.
+fn glob_matches(glob: &[char], value: &[char]) -> bool {
+    let (mut g, mut v) = (0, 0);
+    let mut backtrack = None;
+    while v < value.len() {
+        match glob.get(g) {
+            Some('*') => {
+                backtrack = Some((g, v));
+                g += 1;
+            }
+            Some(&c) if c == '?' || c == value[v] => {
+                g += 1;
+                v += 1;
+            }
+            _ => match backtrack {
+                Some((star_g, star_v)) => {
+                    backtrack = Some((star_g, star_v + 1));
+                    g = star_g + 1;
+                    v = star_v + 1;
+                }
+                None => return false,
+            },
+        }
+    }
+    glob[g..].iter().all(|&c| c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pattern_matches() {
+        for (pattern, value, expected) in [
+            (Pattern::exact("2"), "2", true),
+            (Pattern::exact("2"), "20", false),
+            (Pattern::prefix("application/"), "application/json", true),
+            (Pattern::prefix("application/"), "text/plain", false),
+            (Pattern::suffix("+json"), "application/vnd.api+json", true),
+            (Pattern::suffix("+json"), "application/json", false),
+            (
+                Pattern::regex(Regex::new(r"^v[0-9]+$").unwrap()),
+                "v12",
+                true,
+            ),
+            (
+                Pattern::regex(Regex::new(r"^v[0-9]+$").unwrap()),
+                "v1.2",
+                false,
+            ),
+            (Pattern::glob("Mozilla/*"), "Mozilla/5.0 (X11)", true),
+            (
+                Pattern::glob("*Chrome*Safari*"),
+                "Mozilla Chrome/1 Safari/2",
+                true,
+            ),
+            (
+                Pattern::glob("*Chrome*Safari*"),
+                "Mozilla Safari/2 Chrome/1",
+                false,
+            ),
+            (Pattern::glob("v?"), "v1", true),
+            (Pattern::glob("v?"), "v12", false),
+            (Pattern::glob("caf?"), "café", true),
+            (Pattern::glob("*"), "", true),
+            (Pattern::glob("a*b"), "aXbXb", true),
+            (Pattern::glob("a*b"), "aXbXc", false),
+        ] {
+            assert_eq!(pattern.matches(value), expected, "{pattern} ~ {value}");
+        }
+    }
+}
@@ -2,6 +2,12 @@
 //!
 //! [sensitive]: https://docs.rs/http/latest/http/header/struct.HeaderValue.html#method.set_sensitive
 //!
+//! Besides marking the header values themselves, the [`SensitiveHeadersLayer`]
+//! can be used to insert the [`SensitiveHeaders`] in the [`Context`], leaving the
+//! request untouched. Downstream services still see the real values, while
+//! loggers (e.g. tracing or HAR recording) can use [`SensitiveHeaders::redact`]
+//! to get a sanitized copy of the headers.
+//!
 //! # Example
 //!
 //! ```
@@ -37,12 +43,186 @@
 //! # Ok(())
 //! # }
 //! ```
+//!
+//! Redacting headers for logging purposes:
+//!
+//! ```
+//! use rama_http::layer::sensitive_headers::{SensitiveHeaders, SensitiveHeadersLayer};
+//! use rama_http::{Body, HeaderName, Request, Response, header};
+//! use rama_core::service::service_fn;
+//! use rama_core::{Context, Service, Layer};
+//! use rama_core::error::BoxError;
+//! use std::convert::Infallible;
+//!
+//! async fn handle(ctx: Context<()>, req: Request) -> Result<Response, Infallible> {
+//!     // the service itself still sees the real value
+//!     assert_eq!(req.headers()[header::AUTHORIZATION], "Bearer secret");
+//!
+//!     // while a logger would use the redacted headers
+//!     let headers = ctx.get::<SensitiveHeaders>().unwrap().redact(req.headers());
+//!     assert_eq!(headers[header::AUTHORIZATION], "[REDACTED]");
+//!     assert_eq!(headers["x-session"], "[REDACTED]");
+//!     assert_eq!(headers[header::ACCEPT], "*/*");
+//!     # Ok(Response::new(Body::empty()))
+//! }
+//!
+//! # #[tokio::main]
+//! # async fn main() -> Result<(), BoxError> {
+//! let service = SensitiveHeadersLayer::new()
+//!     .add_sensitive_header(HeaderName::from_static("x-session"))
+//!     .into_layer(service_fn(handle));
+//!
+//! let req = Request::builder()
+//!     .header(header::AUTHORIZATION, "Bearer secret")
+//!     .header("x-session", "42")
+//!     .header(header::ACCEPT, "*/*")
+//!     .body(Body::empty())?;
+//! service.serve(Context::default(), req).await?;
+//! # Ok(())
+//! # }
+//! ```
 
-use crate::{HeaderName, Request, Response, header};
+use crate::{HeaderMap, HeaderName, HeaderValue, Request, Response, header};
 use rama_core::{Context, Layer, Service};
 use rama_utils::macros::define_inner_service_accessors;
+use std::collections::HashSet;
 use std::sync::Arc;
 
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// The set of headers which are to be considered sensitive,
+/// inserted in the [`Context`] by the [`SensitiveHeadersLayer`].
+///
+/// The [`Default`] set contains `Authorization`, `Proxy-Authorization`,
+/// `Cookie`, `Set-Cookie` and `X-Api-Key`.
+pub struct SensitiveHeaders(pub HashSet<HeaderName>);
+
+impl Default for SensitiveHeaders {
+    fn default() -> Self {
+        Self(HashSet::from([
+            header::AUTHORIZATION,
+            header::PROXY_AUTHORIZATION,
+            header::COOKIE,
+            header::SET_COOKIE,
+            HeaderName::from_static("x-api-key"),
+        ]))
+    }
+}
+
+impl SensitiveHeaders {
+    /// Returns true if the given header is considered sensitive.
+    pub fn contains(&self, name: &HeaderName) -> bool {
+        self.0.contains(name)
+    }
+
+    /// Returns a copy of the given headers, with the values of
+    /// all sensitive headers replaced by `[REDACTED]`.
+    pub fn redact(&self, headers: &HeaderMap) -> HeaderMap {
+        let mut headers = headers.clone();
+        for (name, value) in headers.iter_mut() {
+            if self.0.contains(name) {
+                *value = HeaderValue::from_static("[REDACTED]");
+            }
+        }
+        headers
+    }
+}
+
+/// Layer that applies the [`SensitiveHeadersService`] middleware,
+/// which inserts the [`SensitiveHeaders`] in the [`Context`].
+///
+/// See the [module docs](crate::layer::sensitive_headers) for more details.
+#[derive(Debug, Clone, Default)]
+pub struct SensitiveHeadersLayer {
+    headers: SensitiveHeaders,
+}
+
+impl SensitiveHeadersLayer {
+    /// Create a new [`SensitiveHeadersLayer`], using the [`Default`] [`SensitiveHeaders`].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a new [`SensitiveHeadersLayer`] without any sensitive headers.
+    pub fn empty() -> Self {
+        Self {
+            headers: SensitiveHeaders(HashSet::new()),
+        }
+    }
+
+    /// Add a header to the set of sensitive headers.
+    pub fn add_sensitive_header(mut self, name: HeaderName) -> Self {
+        self.headers.0.insert(name);
+        self
+    }
+}
+
+impl<S> Layer<S> for SensitiveHeadersLayer {
+    type Service = SensitiveHeadersService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        SensitiveHeadersService {
+            inner,
+            headers: self.headers.clone(),
+        }
+    }
+
+    fn into_layer(self, inner: S) -> Self::Service {
+        SensitiveHeadersService {
+            inner,
+            headers: self.headers,
+        }
+    }
+}
+
+/// Middleware which inserts the [`SensitiveHeaders`] in the [`Context`],
+/// without modifying the request.
+///
+/// In case [`SensitiveHeaders`] are already present in the [`Context`]
+/// they are extended with the headers of this middleware.
+///
+/// See the [module docs](crate::layer::sensitive_headers) for more details.
+#[derive(Debug, Clone)]
+pub struct SensitiveHeadersService<S> {
+    inner: S,
+    headers: SensitiveHeaders,
+}
+
+impl<S> SensitiveHeadersService<S> {
+    /// Create a new [`SensitiveHeadersService`], using the [`Default`] [`SensitiveHeaders`].
+    pub fn new(inner: S) -> Self {
+        Self {
+            inner,
+            headers: SensitiveHeaders::default(),
+        }
+    }
+
+    define_inner_service_accessors!();
+}
+
+impl<State, S, Request> Service<State, Request> for SensitiveHeadersService<S>
+where
+    State: Clone + Send + Sync + 'static,
+    S: Service<State, Request>,
+    Request: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+
+    async fn serve(
+        &self,
+        mut ctx: Context<State>,
+        req: Request,
+    ) -> Result<Self::Response, Self::Error> {
+        match ctx.get_mut::<SensitiveHeaders>() {
+            Some(headers) => headers.0.extend(self.headers.0.iter().cloned()),
+            None => {
+                ctx.insert(self.headers.clone());
+            }
+        }
+        self.inner.serve(ctx, req).await
+    }
+}
+
 /// Mark headers as [sensitive] on both requests and responses.
 ///
 /// Produces [`SetSensitiveHeaders`] services.
@@ -363,4 +543,37 @@ mod tests {
             assert!(value.is_sensitive())
         }
     }
+
+    #[tokio::test]
+    async fn sensitive_headers_in_context() {
+        async fn handle(ctx: Context<()>, req: Request<()>) -> Result<Response<()>, ()> {
+            let sensitive = ctx.get::<SensitiveHeaders>().unwrap();
+            assert!(sensitive.contains(&header::COOKIE));
+            assert!(sensitive.contains(&HeaderName::from_static("x-internal")));
+            assert!(sensitive.contains(&HeaderName::from_static("x-api-key")));
+
+            assert_eq!(req.headers()[header::COOKIE], "a=b");
+
+            let headers = sensitive.redact(req.headers());
+            assert_eq!(headers[header::COOKIE], "[REDACTED]");
+            assert_eq!(headers["x-internal"], "[REDACTED]");
+            assert_eq!(headers[header::ACCEPT], "*/*");
+            Ok(Response::new(()))
+        }
+
+        let service = (
+            SensitiveHeadersLayer::new(),
+            SensitiveHeadersLayer::empty()
+                .add_sensitive_header(HeaderName::from_static("x-internal")),
+        )
+            .into_layer(service_fn(handle));
+
+        let req = Request::builder()
+            .header(header::COOKIE, "a=b")
+            .header("x-internal", "foo")
+            .header(header::ACCEPT, "*/*")
+            .body(())
+            .unwrap();
+        service.serve(Context::default(), req).await.unwrap();
+    }
 }
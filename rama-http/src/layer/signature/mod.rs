@@ -0,0 +1,123 @@
+//! Middleware that verifies HMAC request signatures.
+//!
+//! Signed requests are commonly used by webhook integrations (e.g. GitHub),
+//! where the sender includes a signature of the request body, computed using
+//! a shared secret, in a request header such as `X-Hub-Signature-256: sha256=<hex>`.
+//!
+//! The [`HmacSignatureService`] buffers the request body in order to verify this
+//! signature, and rejects requests without a valid signature with
+//! a `401 Unauthorized` response. Request bodies larger than the configured
+//! maximum size (see [`HmacSignatureLayer::with_max_body_size`]) are rejected
+//! with a `413 Payload Too Large` response.
+//!
+//! Replay protection can be enabled using [`HmacSignatureLayer::with_timestamp_header`],
+//! in which case the request must contain a unix timestamp (in seconds) within the allowed drift,
+//! and the signature is computed over `<timestamp>.<body>` instead of only the body.
+//!
+//! # Example
+//!
+//! ```
+//! use rama_core::service::service_fn;
+//! use rama_core::{Context, Layer, Service};
+//! use rama_crypto::dep::aws_lc_rs::hmac;
+//! use rama_http::layer::signature::{HmacAlgorithm, HmacSignatureLayer};
+//! use rama_http::{Body, Request, Response, StatusCode};
+//! use std::convert::Infallible;
+//!
+//! # #[tokio::main]
+//! # async fn main() {
+//! let svc = HmacSignatureLayer::new(HmacAlgorithm::Sha256, "webhook-secret")
+//!     .into_layer(service_fn(async |_req: Request| {
+//!         Ok::<_, Infallible>(Response::new(Body::empty()))
+//!     }));
+//!
+//! let body = r#"{"action":"opened"}"#;
+//! let key = hmac::Key::new(hmac::HMAC_SHA256, b"webhook-secret");
+//! let signature = format!("sha256={}", hex::encode(hmac::sign(&key, body.as_bytes())));
+//!
+//! let req = Request::builder()
+//!     .header("x-hub-signature-256", signature)
+//!     .body(Body::from(body))
+//!     .unwrap();
+//! let res = svc.serve(Context::default(), req).await.unwrap();
+//! assert_eq!(res.status(), StatusCode::OK);
+//!
+//! let req = Request::builder()
+//!     .header("x-hub-signature-256", "sha256=00")
+//!     .body(Body::from(body))
+//!     .unwrap();
+//! let res = svc.serve(Context::default(), req).await.unwrap();
+//! assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+//! # }
+//! ```
+
+use rama_crypto::dep::aws_lc_rs::hmac;
+
+mod service;
+pub use service::{HmacSignatureLayer, HmacSignatureService};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// The HMAC algorithm used to sign requests.
+pub enum HmacAlgorithm {
+    /// HMAC using SHA-256
+    Sha256,
+    /// HMAC using SHA-512
+    Sha512,
+}
+
+impl HmacAlgorithm {
+    fn hmac_algorithm(self) -> hmac::Algorithm {
+        match self {
+            Self::Sha256 => hmac::HMAC_SHA256,
+            Self::Sha512 => hmac::HMAC_SHA512,
+        }
+    }
+
+    fn prefix(self) -> &'static str {
+        match self {
+            Self::Sha256 => "sha256=",
+            Self::Sha512 => "sha512=",
+        }
+    }
+
+    fn default_header(self) -> &'static str {
+        match self {
+            Self::Sha256 => "x-hub-signature-256",
+            Self::Sha512 => "x-hub-signature-512",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// The format in which the signature is encoded in the signature header.
+pub enum SignatureFormat {
+    /// The signature encoded as hex, prefixed with the algorithm,
+    /// e.g. `sha256=<hex>`, as used by GitHub.
+    ///
+    /// This is the default format.
+    PrefixedHex,
+    /// The signature encoded as hex, without any prefix.
+    Hex,
+    /// The signature encoded using standard base64.
+    Base64,
+}
+
+impl SignatureFormat {
+    fn decode(&self, algorithm: HmacAlgorithm, value: &str) -> Option<Vec<u8>> {
+        use base64::Engine as _;
+
+        let value = value.trim();
+        match self {
+            Self::PrefixedHex => {
+                let prefix = algorithm.prefix();
+                let (name, sig) = value.split_at_checked(prefix.len())?;
+                if !name.eq_ignore_ascii_case(prefix) {
+                    return None;
+                }
+                hex::decode(sig).ok()
+            }
+            Self::Hex => hex::decode(value).ok(),
+            Self::Base64 => base64::engine::general_purpose::STANDARD.decode(value).ok(),
+        }
+    }
+}
@@ -0,0 +1,431 @@
+use super::{HmacAlgorithm, SignatureFormat};
+use crate::dep::http_body_util::{BodyExt, LengthLimitError, Limited};
+use crate::utils::hmac::HmacKey;
+use crate::{Body, HeaderMap, HeaderName, Request, Response, StatusCode};
+use rama_core::error::BoxError;
+use rama_core::telemetry::tracing;
+use rama_core::{Context, Layer, Service};
+use rama_utils::macros::define_inner_service_accessors;
+use std::fmt;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+const DEFAULT_MAX_BODY_SIZE: usize = 1024 * 1024;
+
+/// Layer that applies the [`HmacSignatureService`] middleware,
+/// which verifies the HMAC signature of requests.
+///
+/// See the [module docs](super) for more details.
+#[derive(Clone)]
+pub struct HmacSignatureLayer {
+    config: Arc<SignatureConfig>,
+}
+
+#[derive(Clone)]
+struct SignatureConfig {
+    algorithm: HmacAlgorithm,
+    key: HmacKey,
+    header: HeaderName,
+    format: SignatureFormat,
+    timestamp: Option<(HeaderName, Duration)>,
+    max_body_size: usize,
+}
+
+impl fmt::Debug for HmacSignatureLayer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("HmacSignatureLayer")
+            .field("algorithm", &self.config.algorithm)
+            .field("header", &self.config.header)
+            .field("format", &self.config.format)
+            .field("timestamp", &self.config.timestamp)
+            .field("max_body_size", &self.config.max_body_size)
+            .finish()
+    }
+}
+
+impl HmacSignatureLayer {
+    /// Create a new [`HmacSignatureLayer`] verifying signatures
+    /// using the given algorithm and shared secret.
+    ///
+    /// By default the signature is expected in the `X-Hub-Signature-256`
+    /// (or `X-Hub-Signature-512`) header, using the [`SignatureFormat::PrefixedHex`] format.
+    pub fn new(algorithm: HmacAlgorithm, secret: impl AsRef<[u8]>) -> Self {
+        Self {
+            config: Arc::new(SignatureConfig {
+                algorithm,
+                key: HmacKey::new(algorithm.hmac_algorithm(), secret.as_ref()),
+                header: HeaderName::from_static(algorithm.default_header()),
+                format: SignatureFormat::PrefixedHex,
+                timestamp: None,
+                max_body_size: DEFAULT_MAX_BODY_SIZE,
+            }),
+        }
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Set the name of the header which contains the signature.
+        pub fn header(mut self, header: HeaderName) -> Self {
+            Arc::make_mut(&mut self.config).header = header;
+            self
+        }
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Set the [`SignatureFormat`] of the signature header.
+        pub fn format(mut self, format: SignatureFormat) -> Self {
+            Arc::make_mut(&mut self.config).format = format;
+            self
+        }
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Require a unix timestamp (in seconds) in the given header,
+        /// which may differ at most `max_drift` from the current time.
+        ///
+        /// The signature is then expected to be computed over `<timestamp>.<body>`.
+        pub fn timestamp_header(mut self, header: HeaderName, max_drift: Duration) -> Self {
+            Arc::make_mut(&mut self.config).timestamp = Some((header, max_drift));
+            self
+        }
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Set the maximum size of the request body which is buffered
+        /// in order to verify its signature, by default this is 1 MiB.
+        ///
+        /// Requests with a larger body are rejected with a `413 Payload Too Large` response.
+        pub fn max_body_size(mut self, size: usize) -> Self {
+            Arc::make_mut(&mut self.config).max_body_size = size;
+            self
+        }
+    }
+}
+
+impl<S> Layer<S> for HmacSignatureLayer {
+    type Service = HmacSignatureService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        HmacSignatureService {
+            inner,
+            config: self.config.clone(),
+        }
+    }
+
+    fn into_layer(self, inner: S) -> Self::Service {
+        HmacSignatureService {
+            inner,
+            config: self.config,
+        }
+    }
+}
+
+/// Middleware which verifies the HMAC signature of requests.
+///
+/// See the [module docs](super) for more details.
+pub struct HmacSignatureService<S> {
+    inner: S,
+    config: Arc<SignatureConfig>,
+}
+
+impl<S> HmacSignatureService<S> {
+    define_inner_service_accessors!();
+}
+
+impl<S: fmt::Debug> fmt::Debug for HmacSignatureService<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("HmacSignatureService")
+            .field("inner", &self.inner)
+            .field("algorithm", &self.config.algorithm)
+            .field("header", &self.config.header)
+            .field("format", &self.config.format)
+            .field("timestamp", &self.config.timestamp)
+            .field("max_body_size", &self.config.max_body_size)
+            .finish()
+    }
+}
+
+impl<S: Clone> Clone for HmacSignatureService<S> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            config: self.config.clone(),
+        }
+    }
+}
+
+impl SignatureConfig {
+    /// Returns the timestamp header value in case it is within the allowed drift,
+    /// `Ok(None)` in case no timestamp is required.
+    fn timestamp<'a>(&self, headers: &'a HeaderMap) -> Result<Option<&'a str>, &'static str> {
+        let Some((header, max_drift)) = &self.timestamp else {
+            return Ok(None);
+        };
+
+        let value = headers
+            .get(header)
+            .and_then(|value| value.to_str().ok())
+            .map(str::trim)
+            .ok_or("missing timestamp")?;
+        let timestamp: u64 = value.parse().map_err(|_| "invalid timestamp")?;
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        if now.abs_diff(timestamp) > max_drift.as_secs() {
+            return Err("timestamp outside of allowed drift");
+        }
+        Ok(Some(value))
+    }
+
+    fn verify(&self, timestamp: Option<&str>, body: &[u8], signature: &[u8]) -> bool {
+        match timestamp {
+            Some(timestamp) => self
+                .key
+                .verify(&[timestamp.as_bytes(), b".", body], signature),
+            None => self.key.verify(&[body], signature),
+        }
+    }
+}
+
+fn status_response<ResBody>(status: StatusCode) -> Response<ResBody>
+where
+    Body: Into<ResBody>,
+{
+    let mut res = Response::new(Body::empty());
+    *res.status_mut() = status;
+    res.map(Into::into)
+}
+
+impl<State, S, ReqBody, ResBody> Service<State, Request<ReqBody>> for HmacSignatureService<S>
+where
+    State: Clone + Send + Sync + 'static,
+    S: Service<State, Request, Response = Response<ResBody>>,
+    ReqBody: crate::dep::http_body::Body<Data: Send, Error: Into<BoxError>> + Send + 'static,
+    ResBody: Send + 'static,
+    Body: Into<ResBody>,
+{
+    type Response = Response<ResBody>;
+    type Error = S::Error;
+
+    async fn serve(
+        &self,
+        ctx: Context<State>,
+        req: Request<ReqBody>,
+    ) -> Result<Self::Response, Self::Error> {
+        let config = &self.config;
+
+        let Some(signature) = req
+            .headers()
+            .get(&config.header)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| config.format.decode(config.algorithm, value))
+        else {
+            tracing::trace!("missing or malformed request signature");
+            return Ok(status_response(StatusCode::UNAUTHORIZED));
+        };
+
+        let timestamp = match config.timestamp(req.headers()) {
+            Ok(timestamp) => timestamp.map(ToOwned::to_owned),
+            Err(reason) => {
+                tracing::trace!("request signature rejected: {reason}");
+                return Ok(status_response(StatusCode::UNAUTHORIZED));
+            }
+        };
+
+        let (parts, body) = req.into_parts();
+        let body = match Limited::new(body, config.max_body_size).collect().await {
+            Ok(body) => body.to_bytes(),
+            Err(err) if err.is::<LengthLimitError>() => {
+                tracing::trace!("request body too large to verify its signature");
+                return Ok(status_response(StatusCode::PAYLOAD_TOO_LARGE));
+            }
+            Err(err) => {
+                tracing::debug!("failed to buffer request body: {err}");
+                return Ok(status_response(StatusCode::BAD_REQUEST));
+            }
+        };
+
+        if !config.verify(timestamp.as_deref(), &body, &signature) {
+            tracing::trace!("invalid request signature");
+            return Ok(status_response(StatusCode::UNAUTHORIZED));
+        }
+
+        self.inner
+            .serve(ctx, Request::from_parts(parts, Body::from(body)))
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use base64::Engine as _;
+    use rama_core::service::service_fn;
+    use std::convert::Infallible;
+
+    const SECRET: &[u8] = b"It's a Secret to Everybody";
+    const BODY: &str = "Hello, World!";
+
+    fn sign_sha256(message: &[u8]) -> Vec<u8> {
+        HmacKey::sha256(SECRET).sign(&[message]).as_ref().to_vec()
+    }
+
+    async fn serve(layer: HmacSignatureLayer, req: Request) -> StatusCode {
+        layer
+            .into_layer(service_fn(async |req: Request| {
+                let body = req.into_body().collect().await.unwrap().to_bytes();
+                assert_eq!(body, BODY);
+                Ok::<_, Infallible>(Response::new(Body::empty()))
+            }))
+            .serve(Context::default(), req)
+            .await
+            .unwrap()
+            .status()
+    }
+
+    fn request(headers: &[(&str, String)]) -> Request {
+        let mut req = Request::builder();
+        for (name, value) in headers {
+            req = req.header(*name, value);
+        }
+        req.body(Body::from(BODY)).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_hmac_signature_github() {
+        let layer = HmacSignatureLayer::new(HmacAlgorithm::Sha256, SECRET);
+
+        // test vector from the GitHub webhook documentation
+        let signature =
+            "sha256=757107ea0eb2509fc211221cce984b8a37570b6d7586c22c46f4379c8b043e17".to_owned();
+        assert_eq!(
+            serve(
+                layer.clone(),
+                request(&[("x-hub-signature-256", signature)])
+            )
+            .await,
+            StatusCode::OK
+        );
+
+        for headers in [
+            vec![],
+            vec![("x-hub-signature-256", "sha256=zz".to_owned())],
+            vec![(
+                "x-hub-signature-256",
+                format!("sha512={}", hex::encode(sign_sha256(BODY.as_bytes()))),
+            )],
+            vec![(
+                "x-hub-signature-256",
+                format!("sha256={}", hex::encode(sign_sha256(b"other body"))),
+            )],
+        ] {
+            assert_eq!(
+                serve(layer.clone(), request(&headers)).await,
+                StatusCode::UNAUTHORIZED,
+                "headers: {headers:?}"
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_hmac_signature_max_body_size() {
+        let signature = || {
+            vec![(
+                "x-hub-signature-256",
+                format!("sha256={}", hex::encode(sign_sha256(BODY.as_bytes()))),
+            )]
+        };
+
+        let layer =
+            HmacSignatureLayer::new(HmacAlgorithm::Sha256, SECRET).with_max_body_size(BODY.len());
+        assert_eq!(serve(layer, request(&signature())).await, StatusCode::OK);
+
+        let layer = HmacSignatureLayer::new(HmacAlgorithm::Sha256, SECRET)
+            .with_max_body_size(BODY.len() - 1);
+        assert_eq!(
+            serve(layer, request(&signature())).await,
+            StatusCode::PAYLOAD_TOO_LARGE
+        );
+    }
+
+    #[tokio::test]
+    async fn test_hmac_signature_sha512_base64() {
+        let layer = HmacSignatureLayer::new(HmacAlgorithm::Sha512, SECRET)
+            .with_header(HeaderName::from_static("x-signature"))
+            .with_format(SignatureFormat::Base64);
+
+        let signature = base64::engine::general_purpose::STANDARD.encode(
+            HmacKey::new(HmacAlgorithm::Sha512.hmac_algorithm(), SECRET).sign(&[BODY.as_bytes()]),
+        );
+
+        assert_eq!(
+            serve(layer.clone(), request(&[("x-signature", signature)])).await,
+            StatusCode::OK
+        );
+
+        let signature =
+            base64::engine::general_purpose::STANDARD.encode(sign_sha256(BODY.as_bytes()));
+        assert_eq!(
+            serve(layer, request(&[("x-signature", signature)])).await,
+            StatusCode::UNAUTHORIZED
+        );
+    }
+
+    #[tokio::test]
+    async fn test_hmac_signature_timestamp() {
+        let layer = HmacSignatureLayer::new(HmacAlgorithm::Sha256, SECRET)
+            .with_format(SignatureFormat::Hex)
+            .with_timestamp_header(
+                HeaderName::from_static("x-timestamp"),
+                Duration::from_secs(300),
+            );
+
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+
+        let signed = |timestamp: u64| {
+            vec![
+                ("x-timestamp", timestamp.to_string()),
+                (
+                    "x-hub-signature-256",
+                    hex::encode(sign_sha256(format!("{timestamp}.{BODY}").as_bytes())),
+                ),
+            ]
+        };
+
+        assert_eq!(
+            serve(layer.clone(), request(&signed(now))).await,
+            StatusCode::OK
+        );
+        assert_eq!(
+            serve(layer.clone(), request(&signed(now - 60))).await,
+            StatusCode::OK
+        );
+
+        // replayed request
+        assert_eq!(
+            serve(layer.clone(), request(&signed(now - 3600))).await,
+            StatusCode::UNAUTHORIZED
+        );
+
+        // timestamp not covered by the signature
+        let mut headers = signed(now - 3600);
+        headers[0].1 = now.to_string();
+        assert_eq!(
+            serve(layer.clone(), request(&headers)).await,
+            StatusCode::UNAUTHORIZED
+        );
+
+        // missing timestamp
+        let headers = vec![(
+            "x-hub-signature-256",
+            hex::encode(sign_sha256(BODY.as_bytes())),
+        )];
+        assert_eq!(
+            serve(layer, request(&headers)).await,
+            StatusCode::UNAUTHORIZED
+        );
+    }
+}
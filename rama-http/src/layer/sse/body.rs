@@ -0,0 +1,214 @@
+use crate::dep::http_body::{Body, Frame, SizeHint};
+use pin_project_lite::pin_project;
+use rama_core::bytes::Bytes;
+use rama_core::error::BoxError;
+use std::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll, ready},
+    time::Duration,
+};
+use tokio::time::{Instant, Sleep, sleep};
+
+const KEEPALIVE_COMMENT: &[u8] = b": keepalive\n\n";
+
+pin_project! {
+    /// Wrapper around a `text/event-stream` [`Body`] which sends an SSE
+    /// comment line whenever no data was sent for the configured interval.
+    ///
+    /// See the [module docs](super) for more details.
+    pub struct SseKeepaliveBody<B> {
+        interval: Duration,
+        #[pin]
+        sleep: Sleep,
+        // last (up to) 4 bytes sent, used to detect event boundaries
+        tail: [u8; 4],
+        #[pin]
+        body: B,
+    }
+}
+
+impl<B> SseKeepaliveBody<B> {
+    /// Creates a new [`SseKeepaliveBody`].
+    pub fn new(interval: Duration, body: B) -> Self {
+        Self {
+            interval,
+            sleep: sleep(interval),
+            // start of the stream counts as an event boundary
+            tail: *b"\n\n\n\n",
+            body,
+        }
+    }
+}
+
+impl<B> std::fmt::Debug for SseKeepaliveBody<B>
+where
+    B: std::fmt::Debug,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SseKeepaliveBody")
+            .field("interval", &self.interval)
+            .field("body", &self.body)
+            .finish()
+    }
+}
+
+fn push_tail(tail: &mut [u8; 4], data: &[u8]) {
+    for &b in &data[data.len().saturating_sub(4)..] {
+        tail.rotate_left(1);
+        tail[3] = b;
+    }
+}
+
+fn is_event_boundary(tail: &[u8; 4]) -> bool {
+    tail.ends_with(b"\n\n") || tail.ends_with(b"\r\r") || tail == b"\r\n\r\n"
+}
+
+impl<B> Body for SseKeepaliveBody<B>
+where
+    B: Body<Data = Bytes, Error: Into<BoxError>>,
+{
+    type Data = Bytes;
+    type Error = BoxError;
+
+    fn poll_frame(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
+        let mut this = self.project();
+
+        if let Poll::Ready(frame) = this.body.poll_frame(cx) {
+            if let Some(data) = frame
+                .as_ref()
+                .and_then(|frame| frame.as_ref().ok())
+                .and_then(Frame::data_ref)
+            {
+                push_tail(this.tail, data);
+            }
+            this.sleep.reset(Instant::now() + *this.interval);
+            return Poll::Ready(frame.map(|frame| frame.map_err(Into::into)));
+        }
+
+        loop {
+            ready!(this.sleep.as_mut().poll(cx));
+            this.sleep.as_mut().reset(Instant::now() + *this.interval);
+            if is_event_boundary(this.tail) {
+                return Poll::Ready(Some(Ok(Frame::data(Bytes::from_static(KEEPALIVE_COMMENT)))));
+            }
+        }
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.body.is_end_stream()
+    }
+
+    fn size_hint(&self) -> SizeHint {
+        SizeHint::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dep::http_body_util::{BodyExt, StreamBody};
+    use tokio::sync::mpsc;
+    use tokio_stream::wrappers::UnboundedReceiverStream;
+
+    fn channel_body() -> (
+        mpsc::UnboundedSender<Result<Frame<Bytes>, BoxError>>,
+        Pin<
+            Box<
+                SseKeepaliveBody<
+                    StreamBody<UnboundedReceiverStream<Result<Frame<Bytes>, BoxError>>>,
+                >,
+            >,
+        >,
+    ) {
+        let (tx, rx) = mpsc::unbounded_channel();
+        let body = Box::pin(SseKeepaliveBody::new(
+            Duration::from_secs(1),
+            StreamBody::new(UnboundedReceiverStream::new(rx)),
+        ));
+        (tx, body)
+    }
+
+    async fn next_data<B: Body<Data = Bytes> + Unpin>(body: &mut B) -> Option<Bytes>
+    where
+        B::Error: std::fmt::Debug,
+    {
+        body.frame()
+            .await
+            .map(|frame| frame.unwrap().into_data().unwrap())
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_keepalive_emitted_without_events() {
+        let (tx, mut body) = channel_body();
+
+        for _ in 0..3 {
+            assert_eq!(next_data(&mut body).await.unwrap(), KEEPALIVE_COMMENT);
+        }
+
+        tx.send(Ok(Frame::data(Bytes::from_static(b"data: hello\n\n"))))
+            .unwrap();
+        assert_eq!(next_data(&mut body).await.unwrap(), "data: hello\n\n");
+        assert_eq!(next_data(&mut body).await.unwrap(), KEEPALIVE_COMMENT);
+
+        drop(tx);
+        assert!(next_data(&mut body).await.is_none());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_keepalive_delayed_by_events() {
+        let (tx, mut body) = channel_body();
+
+        tokio::spawn(async move {
+            for _ in 0..3 {
+                tokio::time::sleep(Duration::from_millis(500)).await;
+                tx.send(Ok(Frame::data(Bytes::from_static(b"data: tick\n\n"))))
+                    .unwrap();
+            }
+        });
+
+        for _ in 0..3 {
+            assert_eq!(next_data(&mut body).await.unwrap(), "data: tick\n\n");
+        }
+        assert!(next_data(&mut body).await.is_none());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_keepalive_not_injected_within_event() {
+        let (tx, mut body) = channel_body();
+
+        tx.send(Ok(Frame::data(Bytes::from_static(b"data: hel"))))
+            .unwrap();
+        assert_eq!(next_data(&mut body).await.unwrap(), "data: hel");
+
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_secs(5)).await;
+            tx.send(Ok(Frame::data(Bytes::from_static(b"lo\r\n\r\n"))))
+                .unwrap();
+            tokio::time::sleep(Duration::from_secs(5)).await;
+        });
+
+        assert_eq!(next_data(&mut body).await.unwrap(), "lo\r\n\r\n");
+        assert_eq!(next_data(&mut body).await.unwrap(), KEEPALIVE_COMMENT);
+    }
+
+    #[test]
+    fn test_is_event_boundary() {
+        for (data, expected) in [
+            (&b""[..], true),
+            (b"data: a\n\n", true),
+            (b"data: a\r\r", true),
+            (b"data: a\r\n\r\n", true),
+            (b"data: a\n", false),
+            (b"data: a\r\n", false),
+            (b"data: a", false),
+        ] {
+            let mut tail = *b"\n\n\n\n";
+            push_tail(&mut tail, data);
+            assert_eq!(is_event_boundary(&tail), expected, "{data:?}");
+        }
+    }
+}
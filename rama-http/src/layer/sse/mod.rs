@@ -0,0 +1,39 @@
+//! Middleware which keeps Server-Sent Events (SSE) responses alive.
+//!
+//! SSE connections going through proxies or load balancers are often
+//! dropped after a period of inactivity (commonly 60 seconds).
+//! The [`SseKeepaliveLayer`] prevents this by injecting an SSE comment line
+//! (`: keepalive`) in `text/event-stream` response bodies whenever
+//! no data was sent for the configured interval.
+//!
+//! Comments are only injected in between events, such that a partially
+//! written event is never interrupted. All other responses are left as-is.
+//!
+//! For SSE responses created by rama itself
+//! (using [`Sse`]) you can also use [`Sse::with_keep_alive`]. This layer
+//! is useful for SSE responses which are produced elsewhere, e.g. proxied ones.
+//!
+//! # Example
+//!
+//! ```
+//! use rama_http::layer::sse::SseKeepaliveLayer;
+//! use rama_http::{Body, Request, Response};
+//! use rama_core::{Layer, service::service_fn};
+//! use std::{convert::Infallible, time::Duration};
+//!
+//! let svc = SseKeepaliveLayer::new(Duration::from_secs(15))
+//!     .into_layer(service_fn(async |_req: Request| {
+//!         Ok::<_, Infallible>(Response::new(Body::empty()))
+//!     }));
+//! ```
+//!
+//! [`Sse`]: crate::service::web::response::Sse
+//! [`Sse::with_keep_alive`]: crate::service::web::response::Sse::with_keep_alive
+
+mod body;
+#[doc(inline)]
+pub use body::SseKeepaliveBody;
+
+mod service;
+#[doc(inline)]
+pub use service::{SseKeepaliveLayer, SseKeepaliveService};
@@ -0,0 +1,160 @@
+use super::SseKeepaliveBody;
+use crate::dep::http_body;
+use crate::dep::mime;
+use crate::{Body, HeaderMap, Request, Response, header};
+use rama_core::bytes::Bytes;
+use rama_core::error::BoxError;
+use rama_core::{Context, Layer, Service};
+use rama_utils::macros::define_inner_service_accessors;
+use std::{fmt, time::Duration};
+
+/// Layer that applies the [`SseKeepaliveService`] middleware.
+///
+/// See the [module docs](super) for more details.
+#[derive(Debug, Clone)]
+pub struct SseKeepaliveLayer {
+    interval: Duration,
+}
+
+impl SseKeepaliveLayer {
+    /// Create a new [`SseKeepaliveLayer`], sending a keep-alive
+    /// comment after each `interval` without any data sent.
+    pub const fn new(interval: Duration) -> Self {
+        Self { interval }
+    }
+}
+
+impl<S> Layer<S> for SseKeepaliveLayer {
+    type Service = SseKeepaliveService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        SseKeepaliveService {
+            inner,
+            interval: self.interval,
+        }
+    }
+
+    fn into_layer(self, inner: S) -> Self::Service {
+        SseKeepaliveService {
+            inner,
+            interval: self.interval,
+        }
+    }
+}
+
+/// Middleware which injects keep-alive comments in `text/event-stream` responses.
+///
+/// See the [module docs](super) for more details.
+pub struct SseKeepaliveService<S> {
+    inner: S,
+    interval: Duration,
+}
+
+impl<S> SseKeepaliveService<S> {
+    /// Create a new [`SseKeepaliveService`].
+    pub const fn new(inner: S, interval: Duration) -> Self {
+        Self { inner, interval }
+    }
+
+    define_inner_service_accessors!();
+}
+
+impl<S: fmt::Debug> fmt::Debug for SseKeepaliveService<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SseKeepaliveService")
+            .field("inner", &self.inner)
+            .field("interval", &self.interval)
+            .finish()
+    }
+}
+
+impl<S: Clone> Clone for SseKeepaliveService<S> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            interval: self.interval,
+        }
+    }
+}
+
+fn is_event_stream(headers: &HeaderMap) -> bool {
+    headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse::<mime::Mime>().ok())
+        .is_some_and(|mime| mime.essence_str() == mime::TEXT_EVENT_STREAM.essence_str())
+}
+
+impl<State, S, ReqBody, ResBody> Service<State, Request<ReqBody>> for SseKeepaliveService<S>
+where
+    State: Clone + Send + Sync + 'static,
+    S: Service<State, Request<ReqBody>, Response = Response<ResBody>>,
+    ReqBody: Send + 'static,
+    ResBody: http_body::Body<Data = Bytes, Error: Into<BoxError>> + Send + Sync + 'static,
+{
+    type Response = Response;
+    type Error = S::Error;
+
+    async fn serve(
+        &self,
+        ctx: Context<State>,
+        req: Request<ReqBody>,
+    ) -> Result<Self::Response, Self::Error> {
+        let res = self.inner.serve(ctx, req).await?;
+        if !is_event_stream(res.headers()) {
+            return Ok(res.map(Body::new));
+        }
+        let interval = self.interval;
+        Ok(res.map(|body| Body::new(SseKeepaliveBody::new(interval, body))))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dep::http_body_util::BodyExt;
+    use rama_core::service::service_fn;
+    use std::convert::Infallible;
+
+    #[tokio::test(start_paused = true)]
+    async fn test_keepalive_only_for_event_streams() {
+        for (content_type, expected) in [
+            ("text/event-stream", true),
+            ("text/event-stream; charset=utf-8", true),
+            ("text/plain", false),
+        ] {
+            let svc = SseKeepaliveLayer::new(Duration::from_secs(1)).into_layer(service_fn(
+                move |_req: Request| async move {
+                    let (tx, rx) = tokio::sync::mpsc::channel::<Result<Bytes, Infallible>>(1);
+                    tokio::spawn(async move {
+                        tokio::time::sleep(Duration::from_secs(5)).await;
+                        tx.send(Ok(Bytes::from_static(b"data: hello\n\n")))
+                            .await
+                            .unwrap();
+                    });
+                    Ok::<_, Infallible>(
+                        Response::builder()
+                            .header(header::CONTENT_TYPE, content_type)
+                            .body(Body::from_stream(
+                                tokio_stream::wrappers::ReceiverStream::new(rx),
+                            ))
+                            .unwrap(),
+                    )
+                },
+            ));
+
+            let mut body = svc
+                .serve(Context::default(), Request::new(Body::empty()))
+                .await
+                .unwrap()
+                .into_body();
+
+            let data = body.frame().await.unwrap().unwrap().into_data().unwrap();
+            if expected {
+                assert_eq!(data, ": keepalive\n\n", "{content_type}");
+            } else {
+                assert_eq!(data, "data: hello\n\n", "{content_type}");
+            }
+        }
+    }
+}
@@ -0,0 +1,174 @@
+use crate::HeaderMap;
+use crate::dep::http_body::{Body, Frame, SizeHint};
+use pin_project_lite::pin_project;
+use rama_core::bytes::{Buf, Bytes, BytesMut};
+use rama_core::error::BoxError;
+use std::{
+    pin::Pin,
+    task::{Context, Poll},
+    time::Duration,
+};
+use tokio::time::Sleep;
+
+pin_project! {
+    /// Response body for [`StreamingBody`], re-chunking the data of the inner body.
+    ///
+    /// Data is emitted in chunks of exactly `chunk_size` bytes, except for the last chunk,
+    /// and — in case a flush interval is defined — for partially filled chunks which
+    /// are flushed once the interval elapsed since the data was buffered.
+    ///
+    /// [`StreamingBody`]: super::StreamingBody
+    pub(super) struct ChunkedBody<B> {
+        #[pin]
+        inner: B,
+        chunk_size: usize,
+        flush_interval: Option<Duration>,
+        flush: Option<Pin<Box<Sleep>>>,
+        buffer: BytesMut,
+        trailers: Option<HeaderMap>,
+        done: bool,
+    }
+}
+
+impl<B> ChunkedBody<B> {
+    pub(super) fn new(inner: B, chunk_size: usize, flush_interval: Option<Duration>) -> Self {
+        Self {
+            inner,
+            chunk_size: chunk_size.max(1),
+            flush_interval,
+            flush: None,
+            buffer: BytesMut::new(),
+            trailers: None,
+            done: false,
+        }
+    }
+}
+
+impl<B> Body for ChunkedBody<B>
+where
+    B: Body<Error: Into<BoxError>>,
+{
+    type Data = Bytes;
+    type Error = BoxError;
+
+    fn poll_frame(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
+        let mut this = self.project();
+        loop {
+            if this.buffer.len() >= *this.chunk_size {
+                let chunk = this.buffer.split_to(*this.chunk_size).freeze();
+                if this.buffer.is_empty() {
+                    *this.flush = None;
+                }
+                return Poll::Ready(Some(Ok(Frame::data(chunk))));
+            }
+
+            if *this.done {
+                if !this.buffer.is_empty() {
+                    *this.flush = None;
+                    return Poll::Ready(Some(Ok(Frame::data(this.buffer.split().freeze()))));
+                }
+                return Poll::Ready(this.trailers.take().map(|t| Ok(Frame::trailers(t))));
+            }
+
+            match this.inner.as_mut().poll_frame(cx) {
+                Poll::Ready(Some(Ok(frame))) => match frame.into_data() {
+                    Ok(mut data) => {
+                        if this.buffer.is_empty() {
+                            *this.flush = this
+                                .flush_interval
+                                .map(|interval| Box::pin(tokio::time::sleep(interval)));
+                        }
+                        while data.has_remaining() {
+                            let chunk = data.chunk();
+                            this.buffer.extend_from_slice(chunk);
+                            let n = chunk.len();
+                            data.advance(n);
+                        }
+                    }
+                    Err(frame) => {
+                        if let Ok(trailers) = frame.into_trailers() {
+                            *this.trailers = Some(trailers);
+                        }
+                        *this.done = true;
+                    }
+                },
+                Poll::Ready(Some(Err(err))) => return Poll::Ready(Some(Err(err.into()))),
+                Poll::Ready(None) => *this.done = true,
+                Poll::Pending => {
+                    if let Some(flush) = this.flush.as_mut() {
+                        if flush.as_mut().poll(cx).is_ready() {
+                            *this.flush = None;
+                            return Poll::Ready(Some(Ok(Frame::data(
+                                this.buffer.split().freeze(),
+                            ))));
+                        }
+                    }
+                    return Poll::Pending;
+                }
+            }
+        }
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.done && self.buffer.is_empty() && self.trailers.is_none()
+    }
+
+    fn size_hint(&self) -> SizeHint {
+        // the size is intentionally not exposed,
+        // as that would allow the body to be sent with a `Content-Length`
+        SizeHint::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dep::http_body_util::{BodyExt, StreamBody};
+    use rama_core::futures::stream;
+    use std::convert::Infallible;
+    use std::time::Instant;
+
+    #[tokio::test]
+    async fn test_chunked_body() {
+        let inner = StreamBody::new(stream::iter(
+            ["hel", "lo w", "orld", "!"].map(|s| Ok::<_, Infallible>(Frame::data(Bytes::from(s)))),
+        ));
+        let mut body = ChunkedBody::new(inner, 5, None);
+
+        let mut chunks = Vec::new();
+        while let Some(frame) = body.frame().await {
+            chunks.push(frame.unwrap().into_data().unwrap());
+        }
+        assert_eq!(chunks, ["hello", " worl", "d!"]);
+        assert!(body.is_end_stream());
+    }
+
+    #[tokio::test]
+    async fn test_chunked_body_flush_interval() {
+        let inner = StreamBody::new(stream::unfold(0, async |i| match i {
+            0 => Some((Ok::<_, Infallible>(Frame::data(Bytes::from("ab"))), 1)),
+            1 => {
+                tokio::time::sleep(Duration::from_millis(100)).await;
+                Some((Ok(Frame::data(Bytes::from("cd"))), 2))
+            }
+            _ => None,
+        }));
+        let mut body = std::pin::pin!(ChunkedBody::new(
+            inner,
+            1024,
+            Some(Duration::from_millis(10))
+        ));
+
+        let start = Instant::now();
+        let chunk = body.as_mut().frame().await.unwrap().unwrap();
+        assert_eq!(chunk.into_data().unwrap(), "ab");
+        assert!(start.elapsed() < Duration::from_millis(100));
+
+        let chunk = body.as_mut().frame().await.unwrap().unwrap();
+        assert_eq!(chunk.into_data().unwrap(), "cd");
+        assert!(body.frame().await.is_none());
+    }
+}
@@ -0,0 +1,44 @@
+//! Middleware which streams large response bodies in chunks.
+//!
+//! Response bodies of which the size is known and does not exceed
+//! [`StreamingBodyLayer::with_threshold`] are sent as a whole, with a `Content-Length` header.
+//! All other bodies (including those of unknown size) are streamed in chunks
+//! of [`StreamingBodyLayer::with_chunk_size`] bytes, removing the `Content-Length` header
+//! and, for HTTP/1.1 and older, setting the `Transfer-Encoding: chunked` header.
+//!
+//! By default a chunk is only sent once it is filled (or the body ended).
+//! Use [`StreamingBodyLayer::with_flush_interval`] to also send partially filled chunks
+//! once the interval elapsed, e.g. for generated content which is produced slowly.
+//!
+//! Responses to `HEAD` requests and responses which cannot have a body are left as-is.
+//!
+//! # Example
+//!
+//! ```
+//! use rama_core::service::service_fn;
+//! use rama_core::{Context, Layer, Service};
+//! use rama_http::layer::streaming::StreamingBodyLayer;
+//! use rama_http::{Body, Request, Response, header};
+//! use std::convert::Infallible;
+//! use std::time::Duration;
+//!
+//! # #[tokio::main]
+//! # async fn main() {
+//! let svc = StreamingBodyLayer::new()
+//!     .with_threshold(1024)
+//!     .with_chunk_size(512)
+//!     .with_flush_interval(Duration::from_millis(100))
+//!     .into_layer(service_fn(async |_req: Request| {
+//!         Ok::<_, Infallible>(Response::new(Body::from(vec![b'x'; 4096])))
+//!     }));
+//!
+//! let res = svc.serve(Context::default(), Request::new(Body::empty())).await.unwrap();
+//! assert_eq!(res.headers()[header::TRANSFER_ENCODING], "chunked");
+//! # }
+//! ```
+
+mod body;
+
+mod service;
+#[doc(inline)]
+pub use service::{StreamingBody, StreamingBodyLayer};
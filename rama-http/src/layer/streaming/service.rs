@@ -0,0 +1,260 @@
+use super::body::ChunkedBody;
+use crate::dep::http_body;
+use crate::{Body, HeaderValue, Method, Request, Response, StatusCode, Version, header};
+use rama_core::bytes::Bytes;
+use rama_core::error::BoxError;
+use rama_core::{Context, Layer, Service};
+use rama_utils::macros::define_inner_service_accessors;
+use std::fmt;
+use std::time::Duration;
+
+const DEFAULT_THRESHOLD: usize = 64 * 1024;
+const DEFAULT_CHUNK_SIZE: usize = 16 * 1024;
+
+/// Layer that applies the [`StreamingBody`] middleware.
+///
+/// See the [module docs](super) for more details.
+#[derive(Debug, Clone)]
+pub struct StreamingBodyLayer {
+    threshold: usize,
+    chunk_size: usize,
+    flush_interval: Option<Duration>,
+}
+
+impl Default for StreamingBodyLayer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl StreamingBodyLayer {
+    /// Create a new [`StreamingBodyLayer`] using the default configuration.
+    pub const fn new() -> Self {
+        Self {
+            threshold: DEFAULT_THRESHOLD,
+            chunk_size: DEFAULT_CHUNK_SIZE,
+            flush_interval: None,
+        }
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Set the body size (in bytes) above which the body is streamed,
+        /// by default 64 KiB.
+        pub fn threshold(mut self, threshold: usize) -> Self {
+            self.threshold = threshold;
+            self
+        }
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Set the size (in bytes) of the chunks of a streamed body,
+        /// by default 16 KiB.
+        pub fn chunk_size(mut self, chunk_size: usize) -> Self {
+            self.chunk_size = chunk_size;
+            self
+        }
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Set the interval after which a partially filled chunk is flushed,
+        /// by default chunks are only sent once filled.
+        pub fn flush_interval(mut self, interval: Option<Duration>) -> Self {
+            self.flush_interval = interval;
+            self
+        }
+    }
+}
+
+impl<S> Layer<S> for StreamingBodyLayer {
+    type Service = StreamingBody<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        StreamingBody {
+            inner,
+            threshold: self.threshold,
+            chunk_size: self.chunk_size,
+            flush_interval: self.flush_interval,
+        }
+    }
+}
+
+/// Middleware which streams response bodies above a size threshold
+/// in chunks of a configurable size.
+///
+/// See the [module docs](super) for more details.
+pub struct StreamingBody<S> {
+    inner: S,
+    threshold: usize,
+    chunk_size: usize,
+    flush_interval: Option<Duration>,
+}
+
+impl<S> StreamingBody<S> {
+    /// Create a new [`StreamingBody`] using the default configuration.
+    pub const fn new(inner: S) -> Self {
+        Self {
+            inner,
+            threshold: DEFAULT_THRESHOLD,
+            chunk_size: DEFAULT_CHUNK_SIZE,
+            flush_interval: None,
+        }
+    }
+
+    define_inner_service_accessors!();
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Set the body size (in bytes) above which the body is streamed,
+        /// by default 64 KiB.
+        pub fn threshold(mut self, threshold: usize) -> Self {
+            self.threshold = threshold;
+            self
+        }
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Set the size (in bytes) of the chunks of a streamed body,
+        /// by default 16 KiB.
+        pub fn chunk_size(mut self, chunk_size: usize) -> Self {
+            self.chunk_size = chunk_size;
+            self
+        }
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Set the interval after which a partially filled chunk is flushed,
+        /// by default chunks are only sent once filled.
+        pub fn flush_interval(mut self, interval: Option<Duration>) -> Self {
+            self.flush_interval = interval;
+            self
+        }
+    }
+}
+
+impl<S: fmt::Debug> fmt::Debug for StreamingBody<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("StreamingBody")
+            .field("inner", &self.inner)
+            .field("threshold", &self.threshold)
+            .field("chunk_size", &self.chunk_size)
+            .field("flush_interval", &self.flush_interval)
+            .finish()
+    }
+}
+
+impl<S: Clone> Clone for StreamingBody<S> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            threshold: self.threshold,
+            chunk_size: self.chunk_size,
+            flush_interval: self.flush_interval,
+        }
+    }
+}
+
+impl<State, S, ReqBody, ResBody> Service<State, Request<ReqBody>> for StreamingBody<S>
+where
+    State: Clone + Send + Sync + 'static,
+    S: Service<State, Request<ReqBody>, Response = Response<ResBody>>,
+    ReqBody: Send + 'static,
+    ResBody: http_body::Body<Data = Bytes, Error: Into<BoxError>> + Send + Sync + 'static,
+{
+    type Response = Response;
+    type Error = S::Error;
+
+    async fn serve(
+        &self,
+        ctx: Context<State>,
+        req: Request<ReqBody>,
+    ) -> Result<Self::Response, Self::Error> {
+        let is_head = req.method() == Method::HEAD;
+        let version = req.version();
+
+        let res = self.inner.serve(ctx, req).await?;
+        if is_head
+            || res.status().is_informational()
+            || matches!(
+                res.status(),
+                StatusCode::NO_CONTENT | StatusCode::NOT_MODIFIED
+            )
+        {
+            return Ok(res.map(Body::new));
+        }
+
+        let (mut parts, body) = res.into_parts();
+        match http_body::Body::size_hint(&body).exact() {
+            Some(size) if size <= self.threshold as u64 => {
+                parts
+                    .headers
+                    .entry(header::CONTENT_LENGTH)
+                    .or_insert_with(|| HeaderValue::from(size));
+                Ok(Response::from_parts(parts, Body::new(body)))
+            }
+            _ => {
+                parts.headers.remove(header::CONTENT_LENGTH);
+                if version <= Version::HTTP_11 {
+                    parts.headers.insert(
+                        header::TRANSFER_ENCODING,
+                        HeaderValue::from_static("chunked"),
+                    );
+                }
+                let body = ChunkedBody::new(body, self.chunk_size, self.flush_interval);
+                Ok(Response::from_parts(parts, Body::new(body)))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dep::http_body_util::BodyExt;
+    use rama_core::service::service_fn;
+    use std::convert::Infallible;
+
+    async fn serve(
+        svc: &impl Service<(), Request, Response = Response, Error = Infallible>,
+        req: Request,
+    ) -> (Response, Vec<Bytes>) {
+        let res = svc.serve(Context::default(), req).await.unwrap();
+        let (parts, mut body) = res.into_parts();
+        let mut chunks = Vec::new();
+        while let Some(frame) = body.frame().await {
+            chunks.push(frame.unwrap().into_data().unwrap());
+        }
+        (Response::from_parts(parts, Body::empty()), chunks)
+    }
+
+    #[tokio::test]
+    async fn test_streaming_body() {
+        let svc = StreamingBodyLayer::new()
+            .with_threshold(8)
+            .with_chunk_size(4)
+            .into_layer(service_fn(async |req: Request| {
+                let body = req.into_body().collect().await.unwrap().to_bytes();
+                Ok::<_, Infallible>(Response::new(Body::from(body)))
+            }));
+
+        let (res, chunks) = serve(&svc, Request::new(Body::from("small"))).await;
+        assert_eq!(res.headers()[header::CONTENT_LENGTH], "5");
+        assert!(!res.headers().contains_key(header::TRANSFER_ENCODING));
+        assert_eq!(chunks, ["small"]);
+
+        let (res, chunks) = serve(&svc, Request::new(Body::from("larger body"))).await;
+        assert!(!res.headers().contains_key(header::CONTENT_LENGTH));
+        assert_eq!(res.headers()[header::TRANSFER_ENCODING], "chunked");
+        assert_eq!(chunks, ["larg", "er b", "ody"]);
+
+        let mut req = Request::new(Body::from("larger body"));
+        *req.version_mut() = Version::HTTP_2;
+        let (res, chunks) = serve(&svc, req).await;
+        assert!(!res.headers().contains_key(header::CONTENT_LENGTH));
+        assert!(!res.headers().contains_key(header::TRANSFER_ENCODING));
+        assert_eq!(chunks, ["larg", "er b", "ody"]);
+
+        let mut req = Request::new(Body::from("larger body"));
+        *req.method_mut() = Method::HEAD;
+        let (res, _) = serve(&svc, req).await;
+        assert!(!res.headers().contains_key(header::TRANSFER_ENCODING));
+    }
+}
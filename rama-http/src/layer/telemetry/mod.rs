@@ -0,0 +1,5 @@
+//! Http telemetry [`Layer`]s.
+//!
+//! [`Layer`]: rama_core::Layer
+
+pub mod otel;
@@ -0,0 +1,429 @@
+//! OpenTelemetry trace context propagation for distributed tracing.
+//!
+//! The [`OtelPropagationLayer`] propagates the [W3C Trace Context]
+//! (`traceparent` and `tracestate` headers):
+//!
+//! - [`OtelPropagationLayer::server`] extracts the trace context from incoming requests
+//!   and starts a child server span, with the `http.method`, `http.url`, `http.scheme`
+//!   and `http.host` attributes. The resulting [`opentelemetry::Context`] is inserted in the
+//!   [`Context`] and is the current context while the inner service is running.
+//! - [`OtelPropagationLayer::client`] starts a client span, as a child of the
+//!   [`opentelemetry::Context`] found in the [`Context`] (or the current context),
+//!   and injects it as the trace context of outgoing requests.
+//!
+//! Spans are ended once the inner service produced a response or an error,
+//! or when the response future is dropped.
+//!
+//! [W3C Trace Context]: https://www.w3.org/TR/trace-context/
+//! [`opentelemetry::Context`]: rama_core::telemetry::opentelemetry::Context
+//! [`Context`]: rama_core::Context
+//!
+//! # Example
+//!
+//! ```
+//! use rama_core::service::service_fn;
+//! use rama_core::telemetry::opentelemetry::Context as OtelContext;
+//! use rama_core::{Context, Layer, Service};
+//! use rama_http::layer::telemetry::otel::OtelPropagationLayer;
+//! use rama_http::{Body, Request, Response};
+//! use std::convert::Infallible;
+//!
+//! # #[tokio::main]
+//! # async fn main() {
+//! let client = OtelPropagationLayer::client().into_layer(service_fn(async |req: Request| {
+//!     // the request is sent with the trace context of the client span
+//!     # let _ = req.headers().get("traceparent");
+//!     Ok::<_, Infallible>(Response::new(Body::empty()))
+//! }));
+//!
+//! let server = OtelPropagationLayer::server().into_layer(service_fn(
+//!     move |ctx: Context<()>, _req: Request| {
+//!         let client = client.clone();
+//!         async move {
+//!             // the server span is found in the context, used as parent of the client span
+//!             assert!(ctx.contains::<OtelContext>());
+//!             let req = Request::get("http://example.com").body(Body::empty()).unwrap();
+//!             client.serve(ctx, req).await
+//!         }
+//!     },
+//! ));
+//!
+//! let req = Request::builder()
+//!     .uri("http://localhost/")
+//!     .header("traceparent", "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01")
+//!     .body(Body::empty())
+//!     .unwrap();
+//! server.serve(Context::default(), req).await.unwrap();
+//! # }
+//! ```
+
+use crate::{Request, Response};
+use opentelemetry_http::{HeaderExtractor, HeaderInjector};
+use rama_core::telemetry::opentelemetry::{
+    Context as OtelContext, InstrumentationScope, KeyValue,
+    context::FutureExt,
+    global::{self, BoxedTracer, ObjectSafeTracer},
+    propagation::TextMapPropagator,
+    sdk::propagation::TraceContextPropagator,
+    semantic_conventions,
+    trace::{SpanKind, Status, TraceContextExt, Tracer},
+};
+use rama_core::{Context, Layer, Service};
+use rama_net::http::RequestContext;
+use rama_utils::macros::define_inner_service_accessors;
+use std::fmt;
+use std::sync::Arc;
+
+const HTTP_METHOD: &str = "http.method";
+const HTTP_URL: &str = "http.url";
+const HTTP_SCHEME: &str = "http.scheme";
+const HTTP_HOST: &str = "http.host";
+const HTTP_STATUS_CODE: &str = "http.status_code";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Mode {
+    Server,
+    Client,
+}
+
+/// A [`Layer`] that produces [`OtelPropagationService`]s,
+/// propagating the W3C trace context of requests.
+///
+/// See the [module docs](self) for more information.
+pub struct OtelPropagationLayer {
+    tracer: Arc<BoxedTracer>,
+    mode: Mode,
+}
+
+impl fmt::Debug for OtelPropagationLayer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("OtelPropagationLayer")
+            .field("tracer", &self.tracer)
+            .field("mode", &self.mode)
+            .finish()
+    }
+}
+
+impl Clone for OtelPropagationLayer {
+    fn clone(&self) -> Self {
+        Self {
+            tracer: self.tracer.clone(),
+            mode: self.mode,
+        }
+    }
+}
+
+impl OtelPropagationLayer {
+    /// Create a new [`OtelPropagationLayer`] for incoming requests,
+    /// using a tracer of the global tracer provider.
+    pub fn server() -> Self {
+        Self {
+            tracer: Arc::new(get_versioned_tracer()),
+            mode: Mode::Server,
+        }
+    }
+
+    /// Create a new [`OtelPropagationLayer`] for outgoing requests,
+    /// using a tracer of the global tracer provider.
+    pub fn client() -> Self {
+        Self {
+            tracer: Arc::new(get_versioned_tracer()),
+            mode: Mode::Client,
+        }
+    }
+
+    /// Use the given [`Tracer`] to create spans, instead of the global one.
+    pub fn with_tracer<T>(mut self, tracer: T) -> Self
+    where
+        T: ObjectSafeTracer + Send + Sync + 'static,
+    {
+        self.tracer = Arc::new(BoxedTracer::new(Box::new(tracer)));
+        self
+    }
+
+    /// Use the given [`Tracer`] to create spans, instead of the global one.
+    pub fn set_tracer<T>(&mut self, tracer: T) -> &mut Self
+    where
+        T: ObjectSafeTracer + Send + Sync + 'static,
+    {
+        self.tracer = Arc::new(BoxedTracer::new(Box::new(tracer)));
+        self
+    }
+}
+
+fn get_versioned_tracer() -> BoxedTracer {
+    global::tracer_with_scope(
+        InstrumentationScope::builder(const_format::formatcp!(
+            "{}-network-http",
+            rama_utils::info::NAME
+        ))
+        .with_version(rama_utils::info::VERSION)
+        .with_schema_url(semantic_conventions::SCHEMA_URL)
+        .build(),
+    )
+}
+
+impl<S> Layer<S> for OtelPropagationLayer {
+    type Service = OtelPropagationService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        OtelPropagationService {
+            inner,
+            tracer: self.tracer.clone(),
+            mode: self.mode,
+        }
+    }
+
+    fn into_layer(self, inner: S) -> Self::Service {
+        OtelPropagationService {
+            inner,
+            tracer: self.tracer,
+            mode: self.mode,
+        }
+    }
+}
+
+/// A [`Service`] which propagates the W3C trace context of requests,
+/// recording a span for each request.
+///
+/// See the [module docs](self) for more information.
+pub struct OtelPropagationService<S> {
+    inner: S,
+    tracer: Arc<BoxedTracer>,
+    mode: Mode,
+}
+
+impl<S> OtelPropagationService<S> {
+    define_inner_service_accessors!();
+}
+
+impl<S: fmt::Debug> fmt::Debug for OtelPropagationService<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("OtelPropagationService")
+            .field("inner", &self.inner)
+            .field("tracer", &self.tracer)
+            .field("mode", &self.mode)
+            .finish()
+    }
+}
+
+impl<S: Clone> Clone for OtelPropagationService<S> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            tracer: self.tracer.clone(),
+            mode: self.mode,
+        }
+    }
+}
+
+impl<S, State, ReqBody, ResBody> Service<State, Request<ReqBody>> for OtelPropagationService<S>
+where
+    S: Service<State, Request<ReqBody>, Response = Response<ResBody>, Error: fmt::Display>,
+    State: Clone + Send + Sync + 'static,
+    ReqBody: Send + 'static,
+    ResBody: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+
+    async fn serve(
+        &self,
+        mut ctx: Context<State>,
+        mut req: Request<ReqBody>,
+    ) -> Result<Self::Response, Self::Error> {
+        let propagator = TraceContextPropagator::new();
+        let (parent_cx, kind) = match self.mode {
+            Mode::Server => (
+                propagator.extract(&HeaderExtractor(req.headers())),
+                SpanKind::Server,
+            ),
+            Mode::Client => (
+                ctx.get::<OtelContext>()
+                    .cloned()
+                    .unwrap_or_else(OtelContext::current),
+                SpanKind::Client,
+            ),
+        };
+
+        let attributes = request_attributes(&mut ctx, &req);
+        let span = self
+            .tracer
+            .span_builder(req.method().to_string())
+            .with_kind(kind)
+            .with_attributes(attributes)
+            .start_with_context(self.tracer.as_ref(), &parent_cx);
+        let cx = parent_cx.with_span(span);
+
+        if self.mode == Mode::Client {
+            propagator.inject_context(&cx, &mut HeaderInjector(req.headers_mut()));
+        }
+        ctx.insert(cx.clone());
+
+        let result = self.inner.serve(ctx, req).with_context(cx.clone()).await;
+
+        let span = cx.span();
+        match &result {
+            Ok(res) => {
+                let status = res.status();
+                span.set_attribute(KeyValue::new(HTTP_STATUS_CODE, status.as_u16() as i64));
+                if status.is_server_error()
+                    || (self.mode == Mode::Client && status.is_client_error())
+                {
+                    span.set_status(Status::error(status.to_string()));
+                }
+            }
+            Err(err) => span.set_status(Status::error(err.to_string())),
+        }
+        span.end();
+
+        result
+    }
+}
+
+fn request_attributes<State, Body>(ctx: &mut Context<State>, req: &Request<Body>) -> Vec<KeyValue> {
+    let mut attributes = Vec::with_capacity(4);
+    attributes.push(KeyValue::new(HTTP_METHOD, req.method().to_string()));
+
+    let request_ctx: Option<&mut RequestContext> = ctx
+        .get_or_try_insert_with_ctx(|ctx| (ctx, req).try_into())
+        .ok();
+    match request_ctx {
+        Some(request_ctx) => {
+            let path_and_query = req
+                .uri()
+                .path_and_query()
+                .map(|pq| pq.as_str())
+                .unwrap_or("/");
+            attributes.push(KeyValue::new(
+                HTTP_URL,
+                format!(
+                    "{}://{}{path_and_query}",
+                    request_ctx.protocol, request_ctx.authority
+                ),
+            ));
+            attributes.push(KeyValue::new(HTTP_SCHEME, request_ctx.protocol.to_string()));
+            attributes.push(KeyValue::new(HTTP_HOST, request_ctx.authority.to_string()));
+        }
+        None => attributes.push(KeyValue::new(HTTP_URL, req.uri().to_string())),
+    }
+
+    attributes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Body, StatusCode};
+    use rama_core::service::service_fn;
+    use rama_core::telemetry::opentelemetry::sdk::trace::SdkTracerProvider;
+    use rama_core::telemetry::opentelemetry::trace::{SpanId, TraceId, TracerProvider};
+    use std::convert::Infallible;
+
+    const TRACE_ID: &str = "4bf92f3577b34da6a3ce929d0e0e4736";
+    const PARENT_SPAN_ID: &str = "00f067aa0ba902b7";
+
+    fn tracer() -> impl ObjectSafeTracer + Send + Sync + 'static {
+        SdkTracerProvider::builder().build().tracer("test")
+    }
+
+    #[tokio::test]
+    async fn test_otel_propagation_server_to_client() {
+        let client = OtelPropagationLayer::client()
+            .with_tracer(tracer())
+            .into_layer(service_fn(async |ctx: Context<()>, req: Request| {
+                let cx = ctx.get::<OtelContext>().unwrap();
+                let span_context = cx.span().span_context().clone();
+                let traceparent = req.headers()["traceparent"].to_str().unwrap();
+                assert_eq!(
+                    span_context.trace_id(),
+                    TraceId::from_hex(TRACE_ID).unwrap()
+                );
+                assert_eq!(
+                    traceparent,
+                    format!(
+                        "00-{}-{}-01",
+                        span_context.trace_id(),
+                        span_context.span_id()
+                    )
+                );
+                Ok::<_, Infallible>(Response::new(Body::empty()))
+            }));
+
+        let server = OtelPropagationLayer::server()
+            .with_tracer(tracer())
+            .into_layer(service_fn(move |ctx: Context<()>, _req: Request| {
+                let client = client.clone();
+                async move {
+                    let cx = ctx.get::<OtelContext>().unwrap().clone();
+                    let span_context = cx.span().span_context().clone();
+                    assert_eq!(
+                        span_context.trace_id(),
+                        TraceId::from_hex(TRACE_ID).unwrap()
+                    );
+                    assert_ne!(
+                        span_context.span_id(),
+                        SpanId::from_hex(PARENT_SPAN_ID).unwrap()
+                    );
+                    // the server span is the current span while serving
+                    assert_eq!(
+                        OtelContext::current().span().span_context().span_id(),
+                        span_context.span_id()
+                    );
+
+                    let req = Request::get("http://example.com/")
+                        .body(Body::empty())
+                        .unwrap();
+                    client.serve(ctx, req).await
+                }
+            }));
+
+        let req = Request::builder()
+            .uri("http://localhost/")
+            .header("traceparent", format!("00-{TRACE_ID}-{PARENT_SPAN_ID}-01"))
+            .body(Body::empty())
+            .unwrap();
+        let res = server.serve(Context::default(), req).await.unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_otel_propagation_server_without_parent() {
+        let server = OtelPropagationLayer::server()
+            .with_tracer(tracer())
+            .into_layer(service_fn(async |ctx: Context<()>, _req: Request| {
+                let cx = ctx.get::<OtelContext>().unwrap();
+                let span_context = cx.span().span_context().clone();
+                assert!(span_context.is_valid());
+                assert!(!span_context.is_remote());
+                Err::<Response, _>("boom")
+            }));
+
+        let req = Request::get("http://localhost/")
+            .body(Body::empty())
+            .unwrap();
+        assert!(server.serve(Context::default(), req).await.is_err());
+    }
+
+    #[test]
+    fn test_request_attributes() {
+        let req = Request::get("http://example.com:8080/foo?bar=baz")
+            .body(Body::empty())
+            .unwrap();
+        let attributes = request_attributes(&mut Context::<()>::default(), &req);
+        let attributes: Vec<_> = attributes
+            .into_iter()
+            .map(|kv| (kv.key.to_string(), kv.value.to_string()))
+            .collect();
+        assert_eq!(
+            attributes,
+            [
+                ("http.method", "GET"),
+                ("http.url", "http://example.com:8080/foo?bar=baz"),
+                ("http.scheme", "http"),
+                ("http.host", "example.com:8080"),
+            ]
+            .map(|(k, v)| (k.to_owned(), v.to_owned()))
+        );
+    }
+}
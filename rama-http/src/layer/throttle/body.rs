@@ -0,0 +1,115 @@
+use http_body::{Body, Frame, SizeHint};
+use pin_project_lite::pin_project;
+use rama_core::bytes::Buf;
+use rama_http_types::dep::http_body;
+use std::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll, ready},
+    time::Duration,
+};
+use tokio::time::{Instant, Sleep, sleep_until};
+
+pin_project! {
+    /// Wrapper around a [`Body`][`http_body::Body`] which limits
+    /// the rate at which its data is produced to the given amount of bytes per second.
+    ///
+    /// Data frames are not split up, instead the frame is delayed
+    /// until its bytes fit in the bandwidth budget, counted from the first poll.
+    ///
+    /// A rate of `0` bytes per second disables the throttling.
+    pub struct ThrottledBody<B: Body> {
+        bytes_per_second: u64,
+        started: Option<Instant>,
+        bytes_read: u64,
+        pending: Option<Frame<B::Data>>,
+        #[pin]
+        sleep: Option<Sleep>,
+        #[pin]
+        body: B,
+    }
+}
+
+impl<B: Body> ThrottledBody<B> {
+    /// Creates a new [`ThrottledBody`].
+    pub fn new(bytes_per_second: u64, body: B) -> Self {
+        Self {
+            bytes_per_second,
+            started: None,
+            bytes_read: 0,
+            pending: None,
+            sleep: None,
+            body,
+        }
+    }
+
+    /// The amount of bytes per second this body is limited to.
+    pub fn bytes_per_second(&self) -> u64 {
+        self.bytes_per_second
+    }
+}
+
+impl<B: Body> Body for ThrottledBody<B> {
+    type Data = B::Data;
+    type Error = B::Error;
+
+    fn poll_frame(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
+        let mut this = self.project();
+
+        if let Some(sleep) = this.sleep.as_mut().as_pin_mut() {
+            ready!(sleep.poll(cx));
+            this.sleep.set(None);
+            if let Some(frame) = this.pending.take() {
+                return Poll::Ready(Some(Ok(frame)));
+            }
+        }
+
+        let frame = match ready!(this.body.poll_frame(cx)) {
+            Some(Ok(frame)) => frame,
+            other => return Poll::Ready(other),
+        };
+
+        let Some(data) = frame.data_ref() else {
+            return Poll::Ready(Some(Ok(frame)));
+        };
+        if *this.bytes_per_second == 0 {
+            return Poll::Ready(Some(Ok(frame)));
+        }
+
+        let now = Instant::now();
+        let started = *this.started.get_or_insert(now);
+        *this.bytes_read += data.remaining() as u64;
+        let deadline = started
+            + Duration::from_secs_f64(*this.bytes_read as f64 / *this.bytes_per_second as f64);
+        if deadline <= now {
+            return Poll::Ready(Some(Ok(frame)));
+        }
+
+        // delay the frame until its bytes fit within the budget
+        *this.pending = Some(frame);
+        let mut sleep = this.sleep;
+        sleep.set(Some(sleep_until(deadline)));
+        ready!(sleep.as_mut().as_pin_mut().unwrap().poll(cx));
+        sleep.set(None);
+        Poll::Ready(this.pending.take().map(Ok))
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.pending.is_none() && self.body.is_end_stream()
+    }
+
+    fn size_hint(&self) -> SizeHint {
+        let mut hint = self.body.size_hint();
+        if let Some(data) = self.pending.as_ref().and_then(Frame::data_ref) {
+            let pending = data.remaining() as u64;
+            if let Some(upper) = hint.upper() {
+                hint.set_upper(upper + pending);
+            }
+            hint.set_lower(hint.lower() + pending);
+        }
+        hint
+    }
+}
@@ -0,0 +1,41 @@
+//! Middleware that limits the bandwidth of response bodies.
+//!
+//! This can be used to simulate slow clients or networks in tests,
+//! or to enforce a bandwidth limit per connection for fairness.
+//!
+//! The limit applied by the [`Throttle`] middleware can be overwritten
+//! (e.g. per connection) by inserting a [`ThrottleConfig`] in the [`Context`].
+//!
+//! # Example
+//!
+//! ```
+//! use std::convert::Infallible;
+//!
+//! use rama_core::Layer;
+//! use rama_core::service::service_fn;
+//! use rama_http::{Body, Request, Response};
+//! use rama_http::layer::throttle::ThrottleLayer;
+//! use rama_core::error::BoxError;
+//!
+//! async fn handle(_: Request) -> Result<Response, Infallible> {
+//!     // ...
+//!     # Ok(Response::new(Body::empty()))
+//! }
+//!
+//! # #[tokio::main]
+//! # async fn main() -> Result<(), BoxError> {
+//! let svc = (
+//!     // Limit response bodies to 64 KiB per second
+//!     ThrottleLayer::new(64 * 1024),
+//! ).into_layer(service_fn(handle));
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [`Context`]: rama_core::Context
+
+mod body;
+mod service;
+
+pub use body::ThrottledBody;
+pub use service::{Throttle, ThrottleConfig, ThrottleLayer};
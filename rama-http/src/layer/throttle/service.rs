@@ -0,0 +1,201 @@
+use super::ThrottledBody;
+use crate::{Request, Response};
+use rama_core::{Context, Layer, Service};
+use rama_http_types::dep::http_body::Body;
+use rama_utils::macros::define_inner_service_accessors;
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Bandwidth limit which can be inserted in the [`Context`] (e.g. per connection)
+/// to overwrite the limit applied by the [`Throttle`] middleware.
+pub struct ThrottleConfig {
+    /// The amount of bytes per second the response body is limited to,
+    /// `0` disables the throttling.
+    pub bytes_per_second: u64,
+}
+
+impl ThrottleConfig {
+    /// Creates a new [`ThrottleConfig`].
+    pub const fn new(bytes_per_second: u64) -> Self {
+        Self { bytes_per_second }
+    }
+}
+
+/// Layer that applies the [`Throttle`] middleware which limits the bandwidth of response bodies.
+///
+/// See the [module docs](super) for an example.
+#[derive(Debug, Clone)]
+pub struct ThrottleLayer {
+    bytes_per_second: Option<u64>,
+}
+
+impl ThrottleLayer {
+    /// Creates a new [`ThrottleLayer`].
+    ///
+    /// A [`ThrottleConfig`] found in the [`Context`] takes precedence over the given limit.
+    pub const fn new(bytes_per_second: u64) -> Self {
+        Self {
+            bytes_per_second: Some(bytes_per_second),
+        }
+    }
+
+    /// Creates a new [`ThrottleLayer`] which only applies the [`ThrottleConfig`]
+    /// found in the [`Context`], no throttling is applied in case it is missing.
+    pub const fn from_context() -> Self {
+        Self {
+            bytes_per_second: None,
+        }
+    }
+}
+
+impl<S> Layer<S> for ThrottleLayer {
+    type Service = Throttle<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        Throttle {
+            inner,
+            bytes_per_second: self.bytes_per_second,
+        }
+    }
+}
+
+/// Middleware which limits the bandwidth of response bodies,
+/// by wrapping them in a [`ThrottledBody`].
+///
+/// See the [module docs](super) for an example.
+pub struct Throttle<S> {
+    inner: S,
+    bytes_per_second: Option<u64>,
+}
+
+impl<S> Throttle<S> {
+    /// Creates a new [`Throttle`].
+    ///
+    /// A [`ThrottleConfig`] found in the [`Context`] takes precedence over the given limit.
+    pub const fn new(inner: S, bytes_per_second: u64) -> Self {
+        Self {
+            inner,
+            bytes_per_second: Some(bytes_per_second),
+        }
+    }
+
+    /// Creates a new [`Throttle`] which only applies the [`ThrottleConfig`]
+    /// found in the [`Context`], no throttling is applied in case it is missing.
+    pub const fn from_context(inner: S) -> Self {
+        Self {
+            inner,
+            bytes_per_second: None,
+        }
+    }
+
+    define_inner_service_accessors!();
+}
+
+impl<S: fmt::Debug> fmt::Debug for Throttle<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Throttle")
+            .field("inner", &self.inner)
+            .field("bytes_per_second", &self.bytes_per_second)
+            .finish()
+    }
+}
+
+impl<S: Clone> Clone for Throttle<S> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            bytes_per_second: self.bytes_per_second,
+        }
+    }
+}
+
+impl<S, State, ReqBody, ResBody> Service<State, Request<ReqBody>> for Throttle<S>
+where
+    S: Service<State, Request<ReqBody>, Response = Response<ResBody>>,
+    ReqBody: Send + 'static,
+    ResBody: Body<Data: Send> + Send + 'static,
+    State: Clone + Send + Sync + 'static,
+{
+    type Response = Response<ThrottledBody<ResBody>>;
+    type Error = S::Error;
+
+    async fn serve(
+        &self,
+        ctx: Context<State>,
+        req: Request<ReqBody>,
+    ) -> Result<Self::Response, Self::Error> {
+        let bytes_per_second = ctx
+            .get::<ThrottleConfig>()
+            .map(|config| config.bytes_per_second)
+            .or(self.bytes_per_second)
+            .unwrap_or_default();
+
+        let res = self.inner.serve(ctx, req).await?;
+        Ok(res.map(|body| ThrottledBody::new(bytes_per_second, body)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::Body;
+    use crate::dep::http_body_util::BodyExt;
+    use rama_core::service::service_fn;
+    use std::convert::Infallible;
+    use std::time::Duration;
+    use tokio::time::Instant;
+
+    async fn chunked(_req: Request) -> Result<Response, Infallible> {
+        let chunks = (0..4).map(|_| Ok::<_, Infallible>(vec![b'x'; 100]));
+        Ok(Response::new(Body::from_stream(
+            rama_core::futures::stream::iter(chunks),
+        )))
+    }
+
+    async fn read_body(res: Response<ThrottledBody<Body>>) -> (usize, Duration) {
+        let start = Instant::now();
+        let body = res.into_body().collect().await.unwrap().to_bytes();
+        (body.len(), start.elapsed())
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_throttle() {
+        let svc = ThrottleLayer::new(200).into_layer(service_fn(chunked));
+
+        let res = svc
+            .serve(Context::default(), Request::new(Body::empty()))
+            .await
+            .unwrap();
+        let (len, elapsed) = read_body(res).await;
+        assert_eq!(len, 400);
+        assert_eq!(elapsed, Duration::from_secs(2));
+
+        let mut ctx = Context::default();
+        ctx.insert(ThrottleConfig::new(400));
+        let res = svc.serve(ctx, Request::new(Body::empty())).await.unwrap();
+        let (len, elapsed) = read_body(res).await;
+        assert_eq!(len, 400);
+        assert_eq!(elapsed, Duration::from_secs(1));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_throttle_from_context() {
+        let svc = ThrottleLayer::from_context().into_layer(service_fn(chunked));
+
+        let res = svc
+            .serve(Context::default(), Request::new(Body::empty()))
+            .await
+            .unwrap();
+        let (len, elapsed) = read_body(res).await;
+        assert_eq!(len, 400);
+        assert_eq!(elapsed, Duration::ZERO);
+
+        let mut ctx = Context::default();
+        ctx.insert(ThrottleConfig::new(100));
+        let res = svc.serve(ctx, Request::new(Body::empty())).await.unwrap();
+        let (len, elapsed) = read_body(res).await;
+        assert_eq!(len, 400);
+        assert_eq!(elapsed, Duration::from_secs(4));
+    }
+}
@@ -39,13 +39,53 @@
 //! # }
 //! ```
 //!
+//! # Per-route timeouts
+//!
+//! The [`Timeout`] middleware applies the [`RequestTimeout`] found in the [`Context`]
+//! instead of its own timeout, if any. Using the [`SetTimeoutLayer`] a route can
+//! therefore overwrite the timeout of a [`Timeout`] middleware which wraps
+//! the service shared by all routes, e.g. an upstream http client of a reverse proxy.
+//!
+//! Note that a [`Timeout`] middleware wrapping the router itself already starts
+//! its timer before the route is matched, and thus cannot be overwritten per route.
+//!
+//! ```
+//! use std::{convert::Infallible, time::Duration};
+//!
+//! use rama_core::Layer;
+//! use rama_core::service::service_fn;
+//! use rama_http::{Body, Request, Response};
+//! use rama_http::layer::timeout::{SetTimeoutLayer, TimeoutLayer};
+//! use rama_http::service::web::WebService;
+//!
+//! async fn forward(_: Request) -> Result<Response, Infallible> {
+//!     // ...
+//!     # Ok(Response::new(Body::empty()))
+//! }
+//!
+//! // Timeout requests after 5 seconds by default
+//! let upstream = TimeoutLayer::new(Duration::from_secs(5)).into_layer(service_fn(forward));
+//!
+//! let svc = WebService::<()>::default()
+//!     .post(
+//!         "/upload",
+//!         SetTimeoutLayer::new(Duration::from_secs(60)).into_layer(upstream.clone()),
+//!     )
+//!     .get(
+//!         "/health",
+//!         SetTimeoutLayer::new(Duration::from_millis(200)).into_layer(upstream.clone()),
+//!     )
+//!     .not_found(upstream);
+//! ```
+//!
 //! [`Infallible`]: std::convert::Infallible
+//! [`Context`]: rama_core::Context
 
 mod body;
 mod service;
 
 pub use body::{TimeoutBody, TimeoutError};
 pub use service::{
-    RequestBodyTimeout, RequestBodyTimeoutLayer, ResponseBodyTimeout, ResponseBodyTimeoutLayer,
-    Timeout, TimeoutLayer,
+    RequestBodyTimeout, RequestBodyTimeoutLayer, RequestTimeout, ResponseBodyTimeout,
+    ResponseBodyTimeoutLayer, SetTimeout, SetTimeoutLayer, Timeout, TimeoutLayer,
 };
@@ -5,18 +5,33 @@ use rama_utils::macros::define_inner_service_accessors;
 use std::fmt;
 use std::time::Duration;
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Timeout which can be inserted in the [`Context`] (e.g. per route)
+/// to overwrite the timeout applied by the [`Timeout`] middleware.
+pub struct RequestTimeout(pub Duration);
+
 /// Layer that applies the [`Timeout`] middleware which apply a timeout to requests.
 ///
 /// See the [module docs](super) for an example.
 #[derive(Debug, Clone)]
 pub struct TimeoutLayer {
-    timeout: Duration,
+    timeout: Option<Duration>,
 }
 
 impl TimeoutLayer {
     /// Creates a new [`TimeoutLayer`].
+    ///
+    /// A [`RequestTimeout`] found in the [`Context`] takes precedence over the given timeout.
     pub const fn new(timeout: Duration) -> Self {
-        TimeoutLayer { timeout }
+        TimeoutLayer {
+            timeout: Some(timeout),
+        }
+    }
+
+    /// Creates a new [`TimeoutLayer`] which only applies the [`RequestTimeout`]
+    /// found in the [`Context`], no timeout is applied in case it is missing.
+    pub const fn from_context() -> Self {
+        TimeoutLayer { timeout: None }
     }
 }
 
@@ -24,7 +39,10 @@ impl<S> Layer<S> for TimeoutLayer {
     type Service = Timeout<S>;
 
     fn layer(&self, inner: S) -> Self::Service {
-        Timeout::new(inner, self.timeout)
+        Timeout {
+            inner,
+            timeout: self.timeout,
+        }
     }
 }
 
@@ -36,13 +54,27 @@ impl<S> Layer<S> for TimeoutLayer {
 /// See the [module docs](super) for an example.
 pub struct Timeout<S> {
     inner: S,
-    timeout: Duration,
+    timeout: Option<Duration>,
 }
 
 impl<S> Timeout<S> {
     /// Creates a new [`Timeout`].
+    ///
+    /// A [`RequestTimeout`] found in the [`Context`] takes precedence over the given timeout.
     pub const fn new(inner: S, timeout: Duration) -> Self {
-        Self { inner, timeout }
+        Self {
+            inner,
+            timeout: Some(timeout),
+        }
+    }
+
+    /// Creates a new [`Timeout`] which only applies the [`RequestTimeout`]
+    /// found in the [`Context`], no timeout is applied in case it is missing.
+    pub const fn from_context(inner: S) -> Self {
+        Self {
+            inner,
+            timeout: None,
+        }
     }
 
     define_inner_service_accessors!();
@@ -83,9 +115,17 @@ where
         ctx: Context<State>,
         req: Request<ReqBody>,
     ) -> Result<Self::Response, Self::Error> {
+        let Some(timeout) = ctx
+            .get::<RequestTimeout>()
+            .map(|RequestTimeout(timeout)| *timeout)
+            .or(self.timeout)
+        else {
+            return self.inner.serve(ctx, req).await;
+        };
+
         tokio::select! {
             res = self.inner.serve(ctx, req) => res,
-            _ = tokio::time::sleep(self.timeout) => {
+            _ = tokio::time::sleep(timeout) => {
                 let mut res = Response::new(ResBody::default());
                 *res.status_mut() = StatusCode::REQUEST_TIMEOUT;
                 Ok(res)
@@ -94,6 +134,85 @@ where
     }
 }
 
+/// Layer that applies the [`SetTimeout`] middleware,
+/// which inserts a [`RequestTimeout`] in the [`Context`].
+///
+/// See the [module docs](super) for an example.
+#[derive(Debug, Clone)]
+pub struct SetTimeoutLayer {
+    timeout: Duration,
+}
+
+impl SetTimeoutLayer {
+    /// Creates a new [`SetTimeoutLayer`].
+    pub const fn new(timeout: Duration) -> Self {
+        Self { timeout }
+    }
+}
+
+impl<S> Layer<S> for SetTimeoutLayer {
+    type Service = SetTimeout<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        SetTimeout::new(inner, self.timeout)
+    }
+}
+
+/// Middleware which inserts a [`RequestTimeout`] in the [`Context`],
+/// overwriting the timeout applied by an inner [`Timeout`] middleware.
+///
+/// See the [module docs](super) for an example.
+pub struct SetTimeout<S> {
+    inner: S,
+    timeout: Duration,
+}
+
+impl<S> SetTimeout<S> {
+    /// Creates a new [`SetTimeout`].
+    pub const fn new(inner: S, timeout: Duration) -> Self {
+        Self { inner, timeout }
+    }
+
+    define_inner_service_accessors!();
+}
+
+impl<S: fmt::Debug> fmt::Debug for SetTimeout<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SetTimeout")
+            .field("inner", &self.inner)
+            .field("timeout", &self.timeout)
+            .finish()
+    }
+}
+
+impl<S: Clone> Clone for SetTimeout<S> {
+    fn clone(&self) -> Self {
+        SetTimeout {
+            inner: self.inner.clone(),
+            timeout: self.timeout,
+        }
+    }
+}
+
+impl<S, State, ReqBody> Service<State, Request<ReqBody>> for SetTimeout<S>
+where
+    S: Service<State, Request<ReqBody>>,
+    ReqBody: Send + 'static,
+    State: Clone + Send + Sync + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+
+    fn serve(
+        &self,
+        mut ctx: Context<State>,
+        req: Request<ReqBody>,
+    ) -> impl Future<Output = Result<Self::Response, Self::Error>> + Send + '_ {
+        ctx.insert(RequestTimeout(self.timeout));
+        self.inner.serve(ctx, req)
+    }
+}
+
 /// Applies a [`TimeoutBody`] to the request body.
 #[derive(Clone, Debug)]
 pub struct RequestBodyTimeoutLayer {
@@ -227,3 +346,68 @@ impl<S> ResponseBodyTimeout<S> {
 
     define_inner_service_accessors!();
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::Body;
+    use rama_core::service::service_fn;
+    use std::convert::Infallible;
+
+    async fn slow(_req: Request) -> Result<Response, Infallible> {
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        Ok(Response::new(Body::empty()))
+    }
+
+    #[tokio::test]
+    async fn test_timeout() {
+        let svc = TimeoutLayer::new(Duration::from_millis(10)).into_layer(service_fn(slow));
+        let res = svc
+            .serve(Context::default(), Request::new(Body::empty()))
+            .await
+            .unwrap();
+        assert_eq!(res.status(), StatusCode::REQUEST_TIMEOUT);
+
+        let mut ctx = Context::default();
+        ctx.insert(RequestTimeout(Duration::from_secs(1)));
+        let res = svc.serve(ctx, Request::new(Body::empty())).await.unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_timeout_from_context() {
+        let svc = TimeoutLayer::from_context().into_layer(service_fn(slow));
+        let res = svc
+            .serve(Context::default(), Request::new(Body::empty()))
+            .await
+            .unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+
+        let mut ctx = Context::default();
+        ctx.insert(RequestTimeout(Duration::from_millis(10)));
+        let res = svc.serve(ctx, Request::new(Body::empty())).await.unwrap();
+        assert_eq!(res.status(), StatusCode::REQUEST_TIMEOUT);
+    }
+
+    #[tokio::test]
+    async fn test_set_timeout_per_route() {
+        use crate::service::web::WebService;
+
+        let handler = TimeoutLayer::new(Duration::from_millis(10)).into_layer(service_fn(slow));
+        let svc = WebService::default()
+            .get(
+                "/upload",
+                SetTimeoutLayer::new(Duration::from_secs(1)).into_layer(handler.clone()),
+            )
+            .get("/", handler);
+
+        let req = Request::get("/upload").body(Body::empty()).unwrap();
+        let res = svc.serve(Context::default(), req).await.unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+
+        let req = Request::get("/").body(Body::empty()).unwrap();
+        let res = svc.serve(Context::default(), req).await.unwrap();
+        assert_eq!(res.status(), StatusCode::REQUEST_TIMEOUT);
+    }
+}
@@ -0,0 +1,280 @@
+use crate::{HeaderMap, header};
+use regex::Regex;
+use std::fmt;
+use std::sync::{Arc, LazyLock};
+
+/// The result of the bot detection performed by the [`BotDetectionService`],
+/// inserted in the [`Context`] of each request.
+///
+/// [`BotDetectionService`]: super::BotDetectionService
+/// [`Context`]: rama_core::Context
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BotClassification {
+    /// The request is most likely made by a human, using a regular browser.
+    Human,
+    /// The request is made by a known (and self-identifying) bot.
+    KnownBot(BotCategory),
+    /// The request is most likely made by a bot which did not identify itself.
+    UnknownBot,
+}
+
+impl BotClassification {
+    /// Returns `true` if the request is classified as made by a bot, known or not.
+    pub fn is_bot(&self) -> bool {
+        !matches!(self, Self::Human)
+    }
+}
+
+impl fmt::Display for BotClassification {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Human => f.write_str("human"),
+            Self::KnownBot(category) => write!(f, "known-bot({category})"),
+            Self::UnknownBot => f.write_str("unknown-bot"),
+        }
+    }
+}
+
+/// The category of a known bot.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum BotCategory {
+    /// Crawlers of search engines, e.g. `Googlebot` or `bingbot`.
+    SearchEngine,
+    /// Link preview fetchers of social media and chat apps, e.g. `Twitterbot` or `Slackbot`.
+    SocialMedia,
+    /// Crawlers collecting data for AI models and assistants, e.g. `GPTBot` or `ClaudeBot`.
+    AiCrawler,
+    /// Crawlers of SEO and marketing tools, e.g. `AhrefsBot` or `SemrushBot`.
+    Seo,
+    /// Uptime and performance monitoring services, e.g. `UptimeRobot` or `Pingdom`.
+    Monitoring,
+    /// Feed readers and aggregators, e.g. `Feedly`.
+    Feed,
+    /// Command line tools and http client libraries, e.g. `curl` or `python-requests`.
+    HttpClient,
+    /// Any other known bot, e.g. matched by a custom pattern.
+    Other,
+}
+
+impl fmt::Display for BotCategory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::SearchEngine => "search-engine",
+            Self::SocialMedia => "social-media",
+            Self::AiCrawler => "ai-crawler",
+            Self::Seo => "seo",
+            Self::Monitoring => "monitoring",
+            Self::Feed => "feed",
+            Self::HttpClient => "http-client",
+            Self::Other => "other",
+        })
+    }
+}
+
+/// User-Agent patterns of known bots, matched case-insensitive.
+const DEFAULT_KNOWN_BOT_PATTERNS: &[(&str, BotCategory)] = &[
+    (
+        r"googlebot|bingbot|yandex(bot|images)|baiduspider|duckduckbot|yahoo! slurp|applebot|seznambot|sogou|exabot",
+        BotCategory::SearchEngine,
+    ),
+    (
+        r"facebookexternalhit|facebot|twitterbot|linkedinbot|slackbot|discordbot|telegrambot|whatsapp|pinterestbot|redditbot|embedly",
+        BotCategory::SocialMedia,
+    ),
+    (
+        r"gptbot|chatgpt-user|oai-searchbot|claudebot|claude-web|anthropic-ai|ccbot|perplexitybot|bytespider|amazonbot|cohere-ai",
+        BotCategory::AiCrawler,
+    ),
+    (
+        r"ahrefsbot|semrushbot|mj12bot|dotbot|rogerbot|screaming frog|petalbot|blexbot",
+        BotCategory::Seo,
+    ),
+    (
+        r"uptimerobot|pingdom|statuscake|site24x7|newrelicpinger|datadog|betteruptime",
+        BotCategory::Monitoring,
+    ),
+    (
+        r"feedly|feedfetcher|inoreader|newsblur|feedbin",
+        BotCategory::Feed,
+    ),
+    (
+        r"^(curl|wget|python-requests|python-urllib|aiohttp|python-httpx|go-http-client|okhttp|java/|apache-httpclient|libwww-perl|node-fetch|axios|undici|rama|postmanruntime|insomnia)\b",
+        BotCategory::HttpClient,
+    ),
+];
+
+/// User-Agent pattern of bots which do not identify as a known bot,
+/// but still reveal themselves as automated clients.
+static UNKNOWN_BOT_PATTERN: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?i)bot\b|crawl|spider|scrape|headless|phantomjs|selenium|puppeteer|playwright")
+        .expect("valid unknown bot pattern")
+});
+
+/// User-Agent patterns of known bots, checked in order.
+pub(super) type KnownBotPatterns = Arc<Vec<(Regex, BotCategory)>>;
+
+static DEFAULT_KNOWN_BOTS: LazyLock<KnownBotPatterns> = LazyLock::new(|| {
+    Arc::new(
+        DEFAULT_KNOWN_BOT_PATTERNS
+            .iter()
+            .map(|(pattern, category)| {
+                (
+                    Regex::new(&format!("(?i){pattern}")).expect("valid known bot pattern"),
+                    *category,
+                )
+            })
+            .collect(),
+    )
+});
+
+/// Default [`KnownBotPatterns`], shared by all layers
+/// which do not add custom patterns.
+pub(super) fn default_known_bots() -> KnownBotPatterns {
+    DEFAULT_KNOWN_BOTS.clone()
+}
+
+/// Return the [`BotCategory`] of the first known bot pattern matching the User-Agent.
+pub(super) fn match_known_bot(patterns: &[(Regex, BotCategory)], ua: &str) -> Option<BotCategory> {
+    patterns
+        .iter()
+        .find(|(re, _)| re.is_match(ua))
+        .map(|(_, category)| *category)
+}
+
+/// Returns `true` if the User-Agent reveals an automated client.
+pub(super) fn is_unknown_bot_ua(ua: &str) -> bool {
+    UNKNOWN_BOT_PATTERN.is_match(ua)
+}
+
+/// Count the request header anomalies which are unusual for a regular browser.
+pub(super) fn header_anomalies(headers: &HeaderMap) -> u8 {
+    let mut anomalies = 0;
+    if !headers.contains_key(header::USER_AGENT) {
+        anomalies += 1;
+    }
+    if !headers.contains_key(header::ACCEPT) {
+        anomalies += 1;
+    }
+    if !headers
+        .get(header::ACCEPT_LANGUAGE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(is_usual_accept_language)
+    {
+        anomalies += 1;
+    }
+    anomalies
+}
+
+/// Returns `true` if the value is a list of (weighted) language tags,
+/// as sent by regular browsers, e.g. `en-US,en;q=0.9`.
+///
+/// A wildcard or empty value is not considered usual.
+fn is_usual_accept_language(value: &str) -> bool {
+    !value.trim().is_empty()
+        && value.split(',').all(|item| {
+            let mut parts = item.split(';');
+            let tag = parts.next().unwrap_or_default().trim();
+            is_language_tag(tag)
+                && parts.all(|param| {
+                    param
+                        .trim()
+                        .strip_prefix("q=")
+                        .and_then(|q| q.parse::<f32>().ok())
+                        .is_some_and(|q| (0.0..=1.0).contains(&q))
+                })
+        })
+}
+
+fn is_language_tag(tag: &str) -> bool {
+    let mut subtags = tag.split('-');
+    subtags.next().is_some_and(|primary| {
+        (1..=8).contains(&primary.len()) && primary.bytes().all(|b| b.is_ascii_alphabetic())
+    }) && subtags.all(|subtag| {
+        (1..=8).contains(&subtag.len()) && subtag.bytes().all(|b| b.is_ascii_alphanumeric())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_match_known_bot() {
+        let patterns = default_known_bots();
+        for (ua, expected) in [
+            (
+                "Mozilla/5.0 (compatible; Googlebot/2.1; +http://www.google.com/bot.html)",
+                Some(BotCategory::SearchEngine),
+            ),
+            ("Twitterbot/1.0", Some(BotCategory::SocialMedia)),
+            (
+                "Mozilla/5.0 AppleWebKit/537.36 (KHTML, like Gecko; compatible; GPTBot/1.1; +https://openai.com/gptbot)",
+                Some(BotCategory::AiCrawler),
+            ),
+            (
+                "Mozilla/5.0 (compatible; AhrefsBot/7.0; +http://ahrefs.com/robot/)",
+                Some(BotCategory::Seo),
+            ),
+            (
+                "Mozilla/5.0 (compatible; UptimeRobot/2.0; http://www.uptimerobot.com/)",
+                Some(BotCategory::Monitoring),
+            ),
+            ("curl/8.5.0", Some(BotCategory::HttpClient)),
+            ("python-requests/2.31.0", Some(BotCategory::HttpClient)),
+            (
+                "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/124.0.0.0 Safari/537.36",
+                None,
+            ),
+            ("my-curl-wrapper/1.0", None),
+        ] {
+            assert_eq!(match_known_bot(&patterns, ua), expected, "{ua}");
+        }
+    }
+
+    #[test]
+    fn test_is_unknown_bot_ua() {
+        assert!(is_unknown_bot_ua("SuperCrawler/0.1"));
+        assert!(is_unknown_bot_ua("Mozilla/5.0 (compatible; FooBot/1.0)"));
+        assert!(is_unknown_bot_ua(
+            "Mozilla/5.0 (X11; Linux x86_64) AppleWebKit/537.36 (KHTML, like Gecko) HeadlessChrome/124.0.0.0 Safari/537.36"
+        ));
+        assert!(!is_unknown_bot_ua(
+            "Mozilla/5.0 (Macintosh; Intel Mac OS X 14_4) AppleWebKit/605.1.15 (KHTML, like Gecko) Version/17.4 Safari/605.1.15"
+        ));
+        assert!(!is_unknown_bot_ua(
+            "Mozilla/5.0 (X11; Linux x86_64; rv:125.0) Gecko/20100101 Firefox/125.0"
+        ));
+    }
+
+    #[test]
+    fn test_is_usual_accept_language() {
+        for value in [
+            "en",
+            "en-US,en;q=0.9",
+            "nl-BE, nl;q=0.8, en;q=0.5",
+            "zh-Hant-TW",
+        ] {
+            assert!(is_usual_accept_language(value), "{value}");
+        }
+        for value in ["", "*", "en;q=2", "en-US,,en", "english_us", "en;x=1"] {
+            assert!(!is_usual_accept_language(value), "{value}");
+        }
+    }
+
+    #[test]
+    fn test_header_anomalies() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(header_anomalies(&headers), 3);
+
+        headers.insert(header::USER_AGENT, "Mozilla/5.0".parse().unwrap());
+        headers.insert(header::ACCEPT, "text/html".parse().unwrap());
+        assert_eq!(header_anomalies(&headers), 1);
+
+        headers.insert(header::ACCEPT_LANGUAGE, "*".parse().unwrap());
+        assert_eq!(header_anomalies(&headers), 1);
+
+        headers.insert(header::ACCEPT_LANGUAGE, "en-GB,en;q=0.9".parse().unwrap());
+        assert_eq!(header_anomalies(&headers), 0);
+    }
+}
@@ -0,0 +1,68 @@
+//! Middleware to detect bots, classifying requests as made by a human or a bot.
+//!
+//! The [`BotDetectionService`] inserts a [`BotClassification`] in the [`Context`]
+//! of each request, which is one of:
+//!
+//! - [`BotClassification::KnownBot`] in case the User-Agent matches the pattern of a known bot,
+//!   e.g. `Googlebot` or `curl`, or the client IP is reported as belonging to a known bot
+//!   by the [`IpReputationProvider`];
+//! - [`BotClassification::UnknownBot`] in case the client IP is reported as malicious,
+//!   the User-Agent reveals an automated client (e.g. `HeadlessChrome`),
+//!   or the request has too many anomalies for a regular browser:
+//!   a missing `User-Agent` or `Accept` header, a missing or unusual `Accept-Language` header,
+//!   and a suspicious client IP;
+//! - [`BotClassification::Human`] in all other cases.
+//!
+//! Requests of known bots can be blocked or rate limited using a [`BotPolicy`].
+//! Requests of unknown bots are always passed to the inner service,
+//! which can act on the [`BotClassification`] found in the [`Context`].
+//!
+//! # Example
+//!
+//! ```
+//! use rama_http::layer::ua::bot::{BotClassification, BotDetectionLayer, BotPolicy};
+//! use rama_http::{Body, Request, Response, StatusCode};
+//! use rama_core::service::service_fn;
+//! use rama_core::{Context, Layer, Service};
+//! use std::convert::Infallible;
+//!
+//! # #[tokio::main]
+//! # async fn main() {
+//! let svc = BotDetectionLayer::new()
+//!     .with_policy(BotPolicy::Block)
+//!     .into_layer(service_fn(async |ctx: Context<()>, _req: Request| {
+//!         assert_eq!(ctx.get(), Some(&BotClassification::Human));
+//!         Ok::<_, Infallible>(Response::new(Body::empty()))
+//!     }));
+//!
+//! let req = Request::builder()
+//!     .header("user-agent", "Mozilla/5.0 (compatible; Googlebot/2.1; +http://www.google.com/bot.html)")
+//!     .body(Body::empty())
+//!     .unwrap();
+//! let res = svc.serve(Context::default(), req).await.unwrap();
+//! assert_eq!(res.status(), StatusCode::FORBIDDEN);
+//!
+//! let req = Request::builder()
+//!     .header("user-agent", "Mozilla/5.0 (X11; Linux x86_64; rv:125.0) Gecko/20100101 Firefox/125.0")
+//!     .header("accept", "text/html")
+//!     .header("accept-language", "en-US,en;q=0.5")
+//!     .body(Body::empty())
+//!     .unwrap();
+//! let res = svc.serve(Context::default(), req).await.unwrap();
+//! assert_eq!(res.status(), StatusCode::OK);
+//! # }
+//! ```
+//!
+//! [`Context`]: rama_core::Context
+
+mod classification;
+#[doc(inline)]
+pub use classification::{BotCategory, BotClassification};
+
+mod reputation;
+#[doc(inline)]
+pub use reputation::{IpReputation, IpReputationProvider};
+
+mod service;
+#[doc(inline)]
+pub use service::{BotDetectionLayer, BotDetectionService, BotPolicy};
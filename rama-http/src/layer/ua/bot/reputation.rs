@@ -0,0 +1,66 @@
+use super::BotCategory;
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::Arc;
+
+/// The reputation of a client IP, as reported by an [`IpReputationProvider`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum IpReputation {
+    /// Nothing (bad) is known about the IP.
+    #[default]
+    Neutral,
+    /// The IP is suspicious, e.g. part of a datacenter or proxy range.
+    ///
+    /// Counted as an anomaly by the [`BotDetectionService`],
+    /// on top of the request header anomalies.
+    ///
+    /// [`BotDetectionService`]: super::BotDetectionService
+    Suspicious,
+    /// The IP is known to be used by bots or for abuse.
+    Malicious,
+    /// The IP is verified to belong to a known bot,
+    /// e.g. using the published IP ranges of a search engine.
+    KnownBot(BotCategory),
+}
+
+/// A provider of the [`IpReputation`] of client IPs,
+/// used by the [`BotDetectionService`].
+///
+/// Implemented for `()`, which reports all IPs as [`IpReputation::Neutral`],
+/// and for a [`HashMap`] of IPs with a fixed reputation.
+///
+/// Providers which fail to look up the reputation of an IP,
+/// e.g. due to a network error, should report it as [`IpReputation::Neutral`].
+///
+/// [`BotDetectionService`]: super::BotDetectionService
+pub trait IpReputationProvider: Send + Sync + 'static {
+    /// Look up the [`IpReputation`] of the given IP.
+    fn ip_reputation(&self, ip: IpAddr) -> impl Future<Output = IpReputation> + Send + '_;
+}
+
+impl IpReputationProvider for () {
+    async fn ip_reputation(&self, _ip: IpAddr) -> IpReputation {
+        IpReputation::Neutral
+    }
+}
+
+impl<P: IpReputationProvider> IpReputationProvider for Arc<P> {
+    fn ip_reputation(&self, ip: IpAddr) -> impl Future<Output = IpReputation> + Send + '_ {
+        (**self).ip_reputation(ip)
+    }
+}
+
+impl<P: IpReputationProvider> IpReputationProvider for Option<P> {
+    async fn ip_reputation(&self, ip: IpAddr) -> IpReputation {
+        match self {
+            Some(provider) => provider.ip_reputation(ip).await,
+            None => IpReputation::Neutral,
+        }
+    }
+}
+
+impl IpReputationProvider for HashMap<IpAddr, IpReputation> {
+    async fn ip_reputation(&self, ip: IpAddr) -> IpReputation {
+        self.get(&ip).copied().unwrap_or_default()
+    }
+}
@@ -0,0 +1,494 @@
+use super::classification::{
+    KnownBotPatterns, default_known_bots, header_anomalies, is_unknown_bot_ua, match_known_bot,
+};
+use super::{BotCategory, BotClassification, IpReputation, IpReputationProvider};
+use crate::dep::http_body;
+use crate::service::web::extract::client_ip::resolve_client_ip;
+use crate::service::web::response::IntoResponse;
+use crate::{Body, HeaderMap, Request, Response, StatusCode, header};
+use rama_core::bytes::Bytes;
+use rama_core::error::BoxError;
+use rama_core::layer::limit::policy::{
+    Policy, PolicyOutput, PolicyResult, RateLimitKeyFn, TokenBucketPolicy,
+};
+use rama_core::telemetry::tracing;
+use rama_core::{Context, Layer, Service};
+use rama_utils::macros::define_inner_service_accessors;
+use regex::Regex;
+use std::fmt;
+use std::sync::Arc;
+
+/// The policy applied by the [`BotDetectionService`] to requests of known bots.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub enum BotPolicy {
+    /// Allow all requests of known bots, the default.
+    #[default]
+    Allow,
+    /// Block all requests of known bots with a `403 Forbidden` response.
+    Block,
+    /// Rate limit the requests of known bots per [`BotCategory`],
+    /// using the token bucket algorithm (see [`TokenBucketPolicy`]).
+    ///
+    /// Requests exceeding the limit get a `429 Too Many Requests` response.
+    RateLimit {
+        /// The maximum amount of requests allowed in a burst.
+        capacity: u64,
+        /// The amount of requests per second the quota is restored with.
+        rate: f64,
+    },
+}
+
+/// [`BotPolicy`] ready to be enforced, sharing its rate limit state
+/// between all clones of the layer and its services.
+#[derive(Debug, Clone)]
+enum EnforcedBotPolicy {
+    Allow,
+    Block,
+    RateLimit(TokenBucketPolicy<KnownBotKey, BotCategory>),
+}
+
+impl From<BotPolicy> for EnforcedBotPolicy {
+    fn from(policy: BotPolicy) -> Self {
+        match policy {
+            BotPolicy::Allow => Self::Allow,
+            BotPolicy::Block => Self::Block,
+            BotPolicy::RateLimit { capacity, rate } => {
+                Self::RateLimit(TokenBucketPolicy::new(capacity, rate).with_key_fn(KnownBotKey))
+            }
+        }
+    }
+}
+
+/// [`RateLimitKeyFn`] which rate limits requests per [`BotCategory`] of known bots.
+#[derive(Debug, Clone)]
+struct KnownBotKey;
+
+impl<State, Request> RateLimitKeyFn<State, Request> for KnownBotKey {
+    type Key = BotCategory;
+
+    fn rate_limit_key(&self, ctx: &Context<State>, _request: &Request) -> Option<Self::Key> {
+        match ctx.get::<BotClassification>() {
+            Some(BotClassification::KnownBot(category)) => Some(*category),
+            _ => None,
+        }
+    }
+}
+
+const DEFAULT_ANOMALY_THRESHOLD: u8 = 2;
+
+/// Layer that applies the [`BotDetectionService`] middleware.
+///
+/// See the [module docs](super) for more details.
+#[derive(Debug, Clone)]
+pub struct BotDetectionLayer<P = ()> {
+    known_bots: KnownBotPatterns,
+    ip_reputation: P,
+    anomaly_threshold: u8,
+    policy: EnforcedBotPolicy,
+}
+
+impl BotDetectionLayer {
+    /// Create a new [`BotDetectionLayer`], using the default known bot patterns,
+    /// without an [`IpReputationProvider`] and allowing all requests.
+    pub fn new() -> Self {
+        Self {
+            known_bots: default_known_bots(),
+            ip_reputation: (),
+            anomaly_threshold: DEFAULT_ANOMALY_THRESHOLD,
+            policy: EnforcedBotPolicy::Allow,
+        }
+    }
+}
+
+impl Default for BotDetectionLayer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<P> BotDetectionLayer<P> {
+    /// Use the given [`IpReputationProvider`] to look up the reputation
+    /// of the client IP, for requests not made by a known bot.
+    pub fn with_ip_reputation_provider<P2>(self, provider: P2) -> BotDetectionLayer<P2> {
+        BotDetectionLayer {
+            known_bots: self.known_bots,
+            ip_reputation: provider,
+            anomaly_threshold: self.anomaly_threshold,
+            policy: self.policy,
+        }
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Add a User-Agent regex pattern of a known bot, matched before the default patterns.
+        ///
+        /// See docs at <https://docs.rs/regex> for more information on regex patterns.
+        /// (e.g. to use flags like (?i) for case-insensitive matching)
+        ///
+        /// # Panics
+        ///
+        /// Panics if the regex pattern is invalid.
+        pub fn user_agent_pattern(mut self, pattern: impl AsRef<str>, category: BotCategory) -> Self {
+            let re = Regex::new(pattern.as_ref()).expect("valid regex pattern");
+            Arc::make_mut(&mut self.known_bots).insert(0, (re, category));
+            self
+        }
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Set the amount of anomalies (e.g. a missing `Accept` header)
+        /// from which a request is classified as [`BotClassification::UnknownBot`], 2 by default.
+        pub fn anomaly_threshold(mut self, threshold: u8) -> Self {
+            self.anomaly_threshold = threshold;
+            self
+        }
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Set the [`BotPolicy`] applied to requests of known bots,
+        /// [`BotPolicy::Allow`] by default.
+        pub fn policy(mut self, policy: BotPolicy) -> Self {
+            self.policy = policy.into();
+            self
+        }
+    }
+}
+
+impl<S, P: Clone> Layer<S> for BotDetectionLayer<P> {
+    type Service = BotDetectionService<S, P>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        BotDetectionService {
+            inner,
+            known_bots: self.known_bots.clone(),
+            ip_reputation: self.ip_reputation.clone(),
+            anomaly_threshold: self.anomaly_threshold,
+            policy: self.policy.clone(),
+        }
+    }
+
+    fn into_layer(self, inner: S) -> Self::Service {
+        BotDetectionService {
+            inner,
+            known_bots: self.known_bots,
+            ip_reputation: self.ip_reputation,
+            anomaly_threshold: self.anomaly_threshold,
+            policy: self.policy,
+        }
+    }
+}
+
+/// Middleware which classifies requests as made by a human or a bot,
+/// inserting the [`BotClassification`] in the [`Context`].
+///
+/// See the [module docs](super) for more details.
+pub struct BotDetectionService<S, P = ()> {
+    inner: S,
+    known_bots: KnownBotPatterns,
+    ip_reputation: P,
+    anomaly_threshold: u8,
+    policy: EnforcedBotPolicy,
+}
+
+impl<S, P> BotDetectionService<S, P> {
+    define_inner_service_accessors!();
+}
+
+impl<S: fmt::Debug, P: fmt::Debug> fmt::Debug for BotDetectionService<S, P> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BotDetectionService")
+            .field("inner", &self.inner)
+            .field("known_bots", &self.known_bots)
+            .field("ip_reputation", &self.ip_reputation)
+            .field("anomaly_threshold", &self.anomaly_threshold)
+            .field("policy", &self.policy)
+            .finish()
+    }
+}
+
+impl<S: Clone, P: Clone> Clone for BotDetectionService<S, P> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            known_bots: self.known_bots.clone(),
+            ip_reputation: self.ip_reputation.clone(),
+            anomaly_threshold: self.anomaly_threshold,
+            policy: self.policy.clone(),
+        }
+    }
+}
+
+impl<S, P: IpReputationProvider> BotDetectionService<S, P> {
+    async fn classify<State>(&self, ctx: &Context<State>, headers: &HeaderMap) -> BotClassification
+    where
+        State: Clone + Send + Sync + 'static,
+    {
+        let ua = headers
+            .get(header::USER_AGENT)
+            .and_then(|value| value.to_str().ok());
+
+        if let Some(category) = ua.and_then(|ua| match_known_bot(&self.known_bots, ua)) {
+            return BotClassification::KnownBot(category);
+        }
+
+        let reputation = match resolve_client_ip(ctx, headers) {
+            Some(ip) => self.ip_reputation.ip_reputation(ip).await,
+            None => IpReputation::Neutral,
+        };
+        match reputation {
+            IpReputation::KnownBot(category) => return BotClassification::KnownBot(category),
+            IpReputation::Malicious => return BotClassification::UnknownBot,
+            IpReputation::Neutral | IpReputation::Suspicious => (),
+        }
+
+        if ua.is_some_and(is_unknown_bot_ua) {
+            return BotClassification::UnknownBot;
+        }
+
+        let mut anomalies = header_anomalies(headers);
+        if reputation == IpReputation::Suspicious {
+            anomalies += 1;
+        }
+        if anomalies >= self.anomaly_threshold {
+            BotClassification::UnknownBot
+        } else {
+            BotClassification::Human
+        }
+    }
+}
+
+impl<State, S, P, ReqBody, ResBody> Service<State, Request<ReqBody>> for BotDetectionService<S, P>
+where
+    State: Clone + Send + Sync + 'static,
+    S: Service<State, Request<ReqBody>, Response = Response<ResBody>>,
+    P: IpReputationProvider,
+    ReqBody: Send + 'static,
+    ResBody: http_body::Body<Data = Bytes, Error: Into<BoxError>> + Send + Sync + 'static,
+{
+    type Response = Response;
+    type Error = S::Error;
+
+    async fn serve(
+        &self,
+        mut ctx: Context<State>,
+        mut req: Request<ReqBody>,
+    ) -> Result<Self::Response, Self::Error> {
+        let classification = self.classify(&ctx, req.headers()).await;
+        tracing::trace!(%classification, "bot detection: classified request");
+        ctx.insert(classification);
+
+        if let BotClassification::KnownBot(category) = classification {
+            match &self.policy {
+                EnforcedBotPolicy::Allow => (),
+                EnforcedBotPolicy::Block => {
+                    tracing::debug!(%category, "bot detection: blocked known bot");
+                    return Ok(StatusCode::FORBIDDEN.into_response());
+                }
+                EnforcedBotPolicy::RateLimit(policy) => {
+                    let PolicyResult {
+                        ctx: policy_ctx,
+                        request,
+                        output,
+                    } = policy.check(ctx, req).await;
+                    if let PolicyOutput::Abort(err) = output {
+                        tracing::debug!(%category, "bot detection: rate limited known bot");
+                        return Ok(err.into_response());
+                    }
+                    ctx = policy_ctx;
+                    req = request;
+                }
+            }
+        }
+
+        let res = self.inner.serve(ctx, req).await?;
+        Ok(res.map(Body::new))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rama_core::service::service_fn;
+    use rama_net::stream::SocketInfo;
+    use std::collections::HashMap;
+    use std::convert::Infallible;
+    use std::net::IpAddr;
+
+    const CHROME_UA: &str = "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/124.0.0.0 Safari/537.36";
+    const GOOGLEBOT_UA: &str =
+        "Mozilla/5.0 (compatible; Googlebot/2.1; +http://www.google.com/bot.html)";
+
+    fn classification_svc<P: IpReputationProvider + Clone>(
+        layer: BotDetectionLayer<P>,
+    ) -> impl Service<(), Request, Response = Response, Error = Infallible> {
+        layer.into_layer(service_fn(async |ctx: Context<()>, _req: Request| {
+            let classification = *ctx.get::<BotClassification>().unwrap();
+            Ok::<_, Infallible>(classification.to_string().into_response())
+        }))
+    }
+
+    async fn classify<S>(svc: &S, ctx: Context<()>, headers: &[(&str, &str)]) -> String
+    where
+        S: Service<(), Request, Response = Response, Error = Infallible>,
+    {
+        use crate::dep::http_body_util::BodyExt;
+
+        let mut req = Request::builder().uri("http://example.com");
+        for (name, value) in headers {
+            req = req.header(*name, *value);
+        }
+        let res = svc
+            .serve(ctx, req.body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let body = res.into_body().collect().await.unwrap().to_bytes();
+        String::from_utf8(body.to_vec()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_bot_classification() {
+        let svc = classification_svc(BotDetectionLayer::new());
+
+        for (headers, expected) in [
+            (
+                vec![
+                    ("user-agent", CHROME_UA),
+                    ("accept", "text/html"),
+                    ("accept-language", "en-US,en;q=0.9"),
+                ],
+                "human",
+            ),
+            (
+                vec![("user-agent", CHROME_UA), ("accept", "text/html")],
+                "human",
+            ),
+            (vec![("user-agent", CHROME_UA)], "unknown-bot"),
+            (vec![], "unknown-bot"),
+            (
+                vec![("user-agent", GOOGLEBOT_UA)],
+                "known-bot(search-engine)",
+            ),
+            (
+                vec![("user-agent", "curl/8.5.0"), ("accept", "*/*")],
+                "known-bot(http-client)",
+            ),
+            (
+                vec![
+                    ("user-agent", "FancyCrawler/1.0"),
+                    ("accept", "text/html"),
+                    ("accept-language", "en"),
+                ],
+                "unknown-bot",
+            ),
+        ] {
+            assert_eq!(
+                classify(&svc, Context::default(), &headers).await,
+                expected,
+                "{headers:?}"
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_bot_classification_custom_pattern() {
+        let svc = classification_svc(
+            BotDetectionLayer::new()
+                .with_user_agent_pattern(r"^InternalProbe/", BotCategory::Monitoring)
+                .with_anomaly_threshold(3),
+        );
+
+        assert_eq!(
+            classify(
+                &svc,
+                Context::default(),
+                &[("user-agent", "InternalProbe/1.0")]
+            )
+            .await,
+            "known-bot(monitoring)"
+        );
+        assert_eq!(
+            classify(&svc, Context::default(), &[("user-agent", CHROME_UA)]).await,
+            "human"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_bot_classification_ip_reputation() {
+        let suspicious: IpAddr = [10, 0, 0, 1].into();
+        let malicious: IpAddr = [10, 0, 0, 2].into();
+        let google: IpAddr = [66, 249, 66, 1].into();
+
+        let svc = classification_svc(BotDetectionLayer::new().with_ip_reputation_provider(
+            HashMap::from([
+                (suspicious, IpReputation::Suspicious),
+                (malicious, IpReputation::Malicious),
+                (google, IpReputation::KnownBot(BotCategory::SearchEngine)),
+            ]),
+        ));
+
+        let ctx_for = |ip: IpAddr| {
+            let mut ctx = Context::default();
+            ctx.insert(SocketInfo::new(None, (ip, 443).into()));
+            ctx
+        };
+        let headers = [("user-agent", CHROME_UA), ("accept", "text/html")];
+
+        assert_eq!(
+            classify(&svc, ctx_for([10, 0, 0, 3].into()), &headers).await,
+            "human"
+        );
+        assert_eq!(
+            classify(&svc, ctx_for(suspicious), &headers).await,
+            "unknown-bot"
+        );
+        assert_eq!(
+            classify(&svc, ctx_for(malicious), &headers).await,
+            "unknown-bot"
+        );
+        assert_eq!(
+            classify(&svc, ctx_for(google), &headers).await,
+            "known-bot(search-engine)"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_bot_policy() {
+        let ok_svc =
+            service_fn(async |_req: Request| Ok::<_, Infallible>(Response::new(Body::empty())));
+
+        let svc = BotDetectionLayer::new()
+            .with_policy(BotPolicy::Block)
+            .into_layer(ok_svc.clone());
+        for (ua, expected) in [
+            (GOOGLEBOT_UA, StatusCode::FORBIDDEN),
+            ("FancyCrawler/1.0", StatusCode::OK),
+        ] {
+            let req = Request::builder()
+                .header("user-agent", ua)
+                .body(Body::empty())
+                .unwrap();
+            let res = svc.serve(Context::default(), req).await.unwrap();
+            assert_eq!(res.status(), expected, "{ua}");
+        }
+
+        let svc = BotDetectionLayer::new()
+            .with_policy(BotPolicy::RateLimit {
+                capacity: 1,
+                rate: 0.0,
+            })
+            .into_layer(ok_svc);
+        for (ua, expected) in [
+            (GOOGLEBOT_UA, StatusCode::OK),
+            (GOOGLEBOT_UA, StatusCode::TOO_MANY_REQUESTS),
+            ("curl/8.5.0", StatusCode::OK),
+            (CHROME_UA, StatusCode::OK),
+            (CHROME_UA, StatusCode::OK),
+        ] {
+            let req = Request::builder()
+                .header("user-agent", ua)
+                .body(Body::empty())
+                .unwrap();
+            let res = svc.serve(Context::default(), req).await.unwrap();
+            assert_eq!(res.status(), expected, "{ua}");
+        }
+    }
+}
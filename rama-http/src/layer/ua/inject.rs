@@ -0,0 +1,252 @@
+use super::UserAgent;
+use crate::{HeaderValue, Request, header::USER_AGENT};
+use rama_core::{Context, Layer, Service, telemetry::tracing};
+use rama_utils::macros::define_inner_service_accessors;
+use std::fmt;
+
+#[derive(Debug, Clone)]
+enum UserAgentSource {
+    Override(HeaderValue),
+    IfMissing(HeaderValue),
+    FromContext,
+}
+
+/// A [`Layer`] that wraps a [`Service`] with a [`UserAgentInjection`].
+///
+/// This [`Layer`] is used to inject or normalize the `User-Agent` header
+/// of outgoing [`Request`]s, as some servers refuse requests without one.
+///
+/// # Example
+///
+/// ```
+/// use rama_http::layer::ua::{UserAgent, UserAgentInjectionLayer};
+/// use rama_http::{Body, HeaderValue, Request, Response, header::USER_AGENT};
+/// use rama_core::service::service_fn;
+/// use rama_core::{Context, Layer, Service};
+/// use std::convert::Infallible;
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// let ua = UserAgent::rama_default();
+/// let svc = UserAgentInjectionLayer::set_if_missing(
+///     HeaderValue::from_str(ua.header_str()).unwrap(),
+/// )
+/// .into_layer(service_fn(async |req: Request| {
+///     assert!(req.headers()[USER_AGENT].to_str().unwrap().starts_with("rama/"));
+///     Ok::<_, Infallible>(Response::new(Body::empty()))
+/// }));
+///
+/// let _ = svc.serve(Context::default(), Request::new(Body::empty())).await.unwrap();
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct UserAgentInjectionLayer {
+    source: UserAgentSource,
+}
+
+impl UserAgentInjectionLayer {
+    /// Create a new [`UserAgentInjectionLayer`] which always sets
+    /// the `User-Agent` header to the given value, overwriting any existing value.
+    pub const fn set(value: HeaderValue) -> Self {
+        Self {
+            source: UserAgentSource::Override(value),
+        }
+    }
+
+    /// Create a new [`UserAgentInjectionLayer`] which sets the `User-Agent` header
+    /// to the given value only if it is missing or empty.
+    pub const fn set_if_missing(value: HeaderValue) -> Self {
+        Self {
+            source: UserAgentSource::IfMissing(value),
+        }
+    }
+
+    /// Create a new [`UserAgentInjectionLayer`] which sets the `User-Agent` header
+    /// to the [`UserAgent`] found in the [`Context`], overwriting any existing value.
+    ///
+    /// Requests are left untouched if no [`UserAgent`] is found.
+    pub const fn from_context() -> Self {
+        Self {
+            source: UserAgentSource::FromContext,
+        }
+    }
+}
+
+impl<S> Layer<S> for UserAgentInjectionLayer {
+    type Service = UserAgentInjection<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        UserAgentInjection {
+            inner,
+            source: self.source.clone(),
+        }
+    }
+
+    fn into_layer(self, inner: S) -> Self::Service {
+        UserAgentInjection {
+            inner,
+            source: self.source,
+        }
+    }
+}
+
+/// A [`Service`] that injects or normalizes the `User-Agent` header of [`Request`]s.
+///
+/// See [`UserAgentInjectionLayer`] for more details.
+pub struct UserAgentInjection<S> {
+    inner: S,
+    source: UserAgentSource,
+}
+
+impl<S> UserAgentInjection<S> {
+    define_inner_service_accessors!();
+}
+
+impl<S: fmt::Debug> fmt::Debug for UserAgentInjection<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("UserAgentInjection")
+            .field("inner", &self.inner)
+            .field("source", &self.source)
+            .finish()
+    }
+}
+
+impl<S: Clone> Clone for UserAgentInjection<S> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            source: self.source.clone(),
+        }
+    }
+}
+
+impl<S, State, Body> Service<State, Request<Body>> for UserAgentInjection<S>
+where
+    S: Service<State, Request<Body>>,
+    State: Clone + Send + Sync + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+
+    fn serve(
+        &self,
+        ctx: Context<State>,
+        mut req: Request<Body>,
+    ) -> impl Future<Output = Result<Self::Response, Self::Error>> + Send + '_ {
+        match &self.source {
+            UserAgentSource::Override(value) => {
+                req.headers_mut().insert(USER_AGENT, value.clone());
+            }
+            UserAgentSource::IfMissing(value) => {
+                let is_missing = req
+                    .headers()
+                    .get(USER_AGENT)
+                    .is_none_or(|ua| ua.as_bytes().trim_ascii().is_empty());
+                if is_missing {
+                    req.headers_mut().insert(USER_AGENT, value.clone());
+                }
+            }
+            UserAgentSource::FromContext => {
+                if let Some(ua) = ctx.get::<UserAgent>() {
+                    match HeaderValue::from_str(ua.header_str()) {
+                        Ok(value) => {
+                            req.headers_mut().insert(USER_AGENT, value);
+                        }
+                        Err(err) => {
+                            tracing::debug!(
+                                "failed to inject user agent '{}' from context: {err}",
+                                ua.header_str(),
+                            );
+                        }
+                    }
+                }
+            }
+        }
+
+        self.inner.serve(ctx, req)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Body;
+    use rama_core::service::service_fn;
+    use std::convert::Infallible;
+
+    async fn injected_ua(
+        layer: UserAgentInjectionLayer,
+        ctx: Context<()>,
+        ua: Option<&'static str>,
+    ) -> Option<String> {
+        let svc = layer.into_layer(service_fn(async |req: Request| {
+            Ok::<_, Infallible>(
+                req.headers()
+                    .get(USER_AGENT)
+                    .map(|value| value.to_str().unwrap().to_owned()),
+            )
+        }));
+
+        let mut req = Request::builder();
+        if let Some(ua) = ua {
+            req = req.header(USER_AGENT, ua);
+        }
+        svc.serve(ctx, req.body(Body::empty()).unwrap())
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_user_agent_injection_set() {
+        let layer = UserAgentInjectionLayer::set(HeaderValue::from_static("foo/1.0"));
+        for ua in [None, Some(""), Some("bar/2.0")] {
+            assert_eq!(
+                injected_ua(layer.clone(), Context::default(), ua)
+                    .await
+                    .as_deref(),
+                Some("foo/1.0"),
+                "{ua:?}"
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_user_agent_injection_set_if_missing() {
+        let layer = UserAgentInjectionLayer::set_if_missing(HeaderValue::from_static("foo/1.0"));
+        for (ua, expected) in [
+            (None, "foo/1.0"),
+            (Some(""), "foo/1.0"),
+            (Some("  "), "foo/1.0"),
+            (Some("bar/2.0"), "bar/2.0"),
+        ] {
+            assert_eq!(
+                injected_ua(layer.clone(), Context::default(), ua)
+                    .await
+                    .as_deref(),
+                Some(expected),
+                "{ua:?}"
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_user_agent_injection_from_context() {
+        let layer = UserAgentInjectionLayer::from_context();
+
+        assert_eq!(
+            injected_ua(layer.clone(), Context::default(), Some("bar/2.0")).await,
+            Some("bar/2.0".to_owned()),
+        );
+        assert_eq!(
+            injected_ua(layer.clone(), Context::default(), None).await,
+            None,
+        );
+
+        let mut ctx = Context::default();
+        ctx.insert(UserAgent::rama_default());
+        assert_eq!(
+            injected_ua(layer, ctx, Some("bar/2.0")).await,
+            Some(UserAgent::rama_default().header_str().to_owned()),
+        );
+    }
+}
@@ -1,5 +1,8 @@
 //! User-Agent (see also `rama-ua`) http layer support
 //!
+//! See the [`bot`] module for middleware to detect bots,
+//! and [`UserAgentInjectionLayer`] to set the `User-Agent` header of outgoing requests.
+//!
 //! # Example
 //!
 //! ```
@@ -36,6 +39,12 @@
 //! # }
 //! ```
 
+pub mod bot;
+
+mod inject;
+#[doc(inline)]
+pub use inject::{UserAgentInjection, UserAgentInjectionLayer};
+
 use crate::{
     HeaderName, Request,
     headers::{self, HeaderMapExt},
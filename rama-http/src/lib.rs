@@ -25,6 +25,8 @@ pub use ::rama_http_types::{
 
 pub use ::rama_http_headers as headers;
 
+pub mod body;
+
 pub mod matcher;
 
 pub mod layer;
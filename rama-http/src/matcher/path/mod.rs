@@ -1,31 +1,35 @@
 use crate::service::web::response::IntoResponse;
 use crate::{Request, StatusCode};
 use rama_core::{Context, context::Extensions};
-use std::collections::HashMap;
 
 mod de;
 
 #[derive(Debug, Clone, Default)]
 /// parameters that are inserted in the [`Context`],
 /// in case the [`PathMatcher`] found a match for the given [`Request`].
+///
+/// The parameters are kept in the order they were captured,
+/// such that they can also be deserialized into a tuple.
 pub struct UriParams {
-    params: Option<HashMap<String, String>>,
+    params: Option<Vec<(String, String)>>,
     glob: Option<String>,
 }
 
 impl UriParams {
     fn insert(&mut self, name: String, value: String) {
-        self.params
-            .get_or_insert_with(HashMap::new)
-            .insert(name, value);
+        let params = self.params.get_or_insert_with(Vec::new);
+        match params.iter_mut().find(|(k, _)| *k == name) {
+            Some((_, v)) => *v = value,
+            None => params.push((name, value)),
+        }
     }
 
     /// Some str slice will be returned in case a param could be found for the given name.
     pub fn get(&self, name: impl AsRef<str>) -> Option<&str> {
         self.params
             .as_ref()
-            .and_then(|params| params.get(name.as_ref()))
-            .map(String::as_str)
+            .and_then(|params| params.iter().find(|(k, _)| k == name.as_ref()))
+            .map(|(_, v)| v.as_str())
     }
 
     fn append_glob(&mut self, value: &str) {
@@ -70,9 +74,8 @@ impl UriParams {
         K: Into<String>,
         V: Into<String>,
     {
-        let params = self.params.get_or_insert_with(HashMap::new);
         for (k, v) in iter {
-            params.insert(k.into(), v.into());
+            self.insert(k.into(), v.into());
         }
         self
     }
@@ -353,11 +356,7 @@ mod test {
                 "/person/glen%20dc/age",
                 "/person/:name/age",
                 UriParams {
-                    params: Some({
-                        let mut params = HashMap::new();
-                        params.insert("name".to_owned(), "glen dc".to_owned());
-                        params
-                    }),
+                    params: Some(vec![("name".to_owned(), "glen dc".to_owned())]),
                     ..UriParams::default()
                 },
             ),
@@ -469,12 +468,10 @@ mod test {
     #[test]
     fn test_deserialize_uri_params() {
         let params = UriParams {
-            params: Some({
-                let mut params = HashMap::new();
-                params.insert("name".to_owned(), "glen dc".to_owned());
-                params.insert("age".to_owned(), "42".to_owned());
-                params
-            }),
+            params: Some(vec![
+                ("name".to_owned(), "glen dc".to_owned()),
+                ("age".to_owned(), "42".to_owned()),
+            ]),
             glob: Some("/age".to_owned()),
         };
 
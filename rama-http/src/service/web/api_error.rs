@@ -0,0 +1,346 @@
+//! Structured JSON errors for web APIs.
+//!
+//! An [`ApiError`] is serialized as:
+//!
+//! ```json
+//! {"error": {"code": "not_found", "message": "user not found", "details": {"id": 42}}}
+//! ```
+//!
+//! where `details` is omitted in case none are set.
+//!
+//! # Example
+//!
+//! ```
+//! use rama_http::service::web::api_error::{ApiError, ApiErrorCode};
+//! use rama_http::StatusCode;
+//! use serde_json::json;
+//!
+//! async fn handler() -> Result<String, ApiError> {
+//!     Err(ApiError::new(ApiErrorCode::ValidationError, "invalid email address")
+//!         .with_details(json!({"field": "email"})))
+//! }
+//!
+//! let error = ApiError::new(ApiErrorCode::custom("quota_exceeded", StatusCode::PAYMENT_REQUIRED), "quota exceeded");
+//! assert_eq!(error.status(), StatusCode::PAYMENT_REQUIRED);
+//! ```
+
+use crate::service::web::response::{IntoResponse, Json};
+use crate::{Response, StatusCode};
+use rama_core::error::{BoxError, OpaqueError};
+use rama_core::layer::limit::policy::{LimitReached, RateLimitReached};
+use rama_core::layer::timeout::Elapsed;
+use rama_core::telemetry::tracing;
+use serde::Serialize;
+use std::borrow::Cow;
+use std::{fmt, io};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// The machine-readable code of an [`ApiError`],
+/// which also determines the [`StatusCode`] of its response.
+pub enum ApiErrorCode {
+    /// `bad_request`: 400 Bad Request
+    BadRequest,
+    /// `validation_error`: 422 Unprocessable Entity
+    ValidationError,
+    /// `unauthorized`: 401 Unauthorized
+    Unauthorized,
+    /// `forbidden`: 403 Forbidden
+    Forbidden,
+    /// `not_found`: 404 Not Found
+    NotFound,
+    /// `conflict`: 409 Conflict
+    Conflict,
+    /// `rate_limited`: 429 Too Many Requests
+    RateLimited,
+    /// `internal_error`: 500 Internal Server Error
+    InternalError,
+    /// `service_unavailable`: 503 Service Unavailable
+    ServiceUnavailable,
+    /// `timeout`: 504 Gateway Timeout
+    Timeout,
+    /// A custom code, created using [`ApiErrorCode::custom`].
+    Custom {
+        /// The code as serialized in the error response.
+        code: Cow<'static, str>,
+        /// The status code of the error response.
+        status: StatusCode,
+    },
+}
+
+impl ApiErrorCode {
+    /// Create a custom [`ApiErrorCode`], responded with the given [`StatusCode`].
+    pub fn custom(code: impl Into<Cow<'static, str>>, status: StatusCode) -> Self {
+        Self::Custom {
+            code: code.into(),
+            status,
+        }
+    }
+
+    /// Return the code as serialized in the error response.
+    pub fn as_str(&self) -> &str {
+        match self {
+            Self::BadRequest => "bad_request",
+            Self::ValidationError => "validation_error",
+            Self::Unauthorized => "unauthorized",
+            Self::Forbidden => "forbidden",
+            Self::NotFound => "not_found",
+            Self::Conflict => "conflict",
+            Self::RateLimited => "rate_limited",
+            Self::InternalError => "internal_error",
+            Self::ServiceUnavailable => "service_unavailable",
+            Self::Timeout => "timeout",
+            Self::Custom { code, .. } => code,
+        }
+    }
+
+    /// Return the [`StatusCode`] of the error response.
+    pub fn status(&self) -> StatusCode {
+        match self {
+            Self::BadRequest => StatusCode::BAD_REQUEST,
+            Self::ValidationError => StatusCode::UNPROCESSABLE_ENTITY,
+            Self::Unauthorized => StatusCode::UNAUTHORIZED,
+            Self::Forbidden => StatusCode::FORBIDDEN,
+            Self::NotFound => StatusCode::NOT_FOUND,
+            Self::Conflict => StatusCode::CONFLICT,
+            Self::RateLimited => StatusCode::TOO_MANY_REQUESTS,
+            Self::InternalError => StatusCode::INTERNAL_SERVER_ERROR,
+            Self::ServiceUnavailable => StatusCode::SERVICE_UNAVAILABLE,
+            Self::Timeout => StatusCode::GATEWAY_TIMEOUT,
+            Self::Custom { status, .. } => *status,
+        }
+    }
+}
+
+impl fmt::Display for ApiErrorCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.as_str().fmt(f)
+    }
+}
+
+impl Serialize for ApiErrorCode {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+#[derive(Debug, Clone)]
+/// A structured error, responded as a JSON body.
+///
+/// It implements [`std::error::Error`], such that it can be returned
+/// as a service error, to be turned into a response by the [`ApiErrorLayer`].
+///
+/// See the [module docs](self) for more information.
+///
+/// [`ApiErrorLayer`]: crate::layer::api_error::ApiErrorLayer
+pub struct ApiError {
+    code: ApiErrorCode,
+    message: String,
+    details: Option<serde_json::Value>,
+}
+
+impl ApiError {
+    /// Create a new [`ApiError`] with the given code and message.
+    pub fn new(code: ApiErrorCode, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+            details: None,
+        }
+    }
+
+    /// Attach details to the [`ApiError`], serialized as the `details` field.
+    pub fn with_details(mut self, details: serde_json::Value) -> Self {
+        self.details = Some(details);
+        self
+    }
+
+    /// Attach details to the [`ApiError`], serialized as the `details` field.
+    pub fn set_details(&mut self, details: serde_json::Value) -> &mut Self {
+        self.details = Some(details);
+        self
+    }
+
+    /// Return the [`ApiErrorCode`] of this error.
+    pub fn code(&self) -> &ApiErrorCode {
+        &self.code
+    }
+
+    /// Return the message of this error.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// Return the details of this error, if any.
+    pub fn details(&self) -> Option<&serde_json::Value> {
+        self.details.as_ref()
+    }
+
+    /// Return the [`StatusCode`] of the error response.
+    pub fn status(&self) -> StatusCode {
+        self.code.status()
+    }
+
+    /// Classify an error, by looking for known error types in its source chain.
+    ///
+    /// The message of unknown errors is not exposed, as it might leak internal details.
+    fn from_error(err: &(dyn std::error::Error + 'static)) -> Self {
+        let mut source = Some(err);
+        while let Some(err) = source {
+            if let Some(api_error) = err.downcast_ref::<Self>() {
+                return api_error.clone();
+            }
+            let code = if err.is::<Elapsed>() {
+                Some(ApiErrorCode::Timeout)
+            } else if err.is::<RateLimitReached>() {
+                Some(ApiErrorCode::RateLimited)
+            } else if err.is::<LimitReached>() {
+                Some(ApiErrorCode::ServiceUnavailable)
+            } else if let Some(err) = err.downcast_ref::<io::Error>() {
+                match err.kind() {
+                    io::ErrorKind::NotFound => Some(ApiErrorCode::NotFound),
+                    io::ErrorKind::PermissionDenied => Some(ApiErrorCode::Forbidden),
+                    io::ErrorKind::TimedOut => Some(ApiErrorCode::Timeout),
+                    _ => None,
+                }
+            } else {
+                None
+            };
+            if let Some(code) = code {
+                return Self::new(code, err.to_string());
+            }
+            // some errors (e.g. static str errors) return themselves as source
+            source = err
+                .source()
+                .filter(|next| !std::ptr::addr_eq(*next as *const _, err as *const _));
+        }
+
+        tracing::debug!("respond with internal api error for unknown error: {err}");
+        Self::new(ApiErrorCode::InternalError, "internal server error")
+    }
+}
+
+impl fmt::Display for ApiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.code, self.message)
+    }
+}
+
+impl std::error::Error for ApiError {}
+
+impl From<OpaqueError> for ApiError {
+    fn from(err: OpaqueError) -> Self {
+        match err.downcast::<Self>() {
+            Ok(err) => err,
+            Err(err) => Self::from_error(&err),
+        }
+    }
+}
+
+impl From<BoxError> for ApiError {
+    fn from(err: BoxError) -> Self {
+        match err.downcast::<Self>() {
+            Ok(err) => *err,
+            Err(err) => Self::from_error(err.as_ref()),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct ApiErrorBody<'a> {
+    error: ApiErrorBodyInner<'a>,
+}
+
+#[derive(Serialize)]
+struct ApiErrorBodyInner<'a> {
+    code: &'a ApiErrorCode,
+    message: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    details: Option<&'a serde_json::Value>,
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let body = ApiErrorBody {
+            error: ApiErrorBodyInner {
+                code: &self.code,
+                message: &self.message,
+                details: self.details.as_ref(),
+            },
+        };
+        (self.status(), Json(body)).into_response()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dep::http_body_util::BodyExt;
+    use crate::header;
+    use rama_core::error::ErrorExt;
+    use serde_json::json;
+
+    async fn response_json(err: ApiError) -> (StatusCode, serde_json::Value) {
+        let res = err.into_response();
+        assert_eq!(res.headers()[header::CONTENT_TYPE], "application/json");
+        let status = res.status();
+        let body = res.into_body().collect().await.unwrap().to_bytes();
+        (status, serde_json::from_slice(&body).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_api_error_response() {
+        let (status, body) = response_json(
+            ApiError::new(ApiErrorCode::ValidationError, "invalid email")
+                .with_details(json!({"field": "email"})),
+        )
+        .await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(
+            body,
+            json!({"error": {"code": "validation_error", "message": "invalid email", "details": {"field": "email"}}})
+        );
+
+        let (status, body) = response_json(ApiError::new(
+            ApiErrorCode::custom("quota_exceeded", StatusCode::PAYMENT_REQUIRED),
+            "quota exceeded",
+        ))
+        .await;
+        assert_eq!(status, StatusCode::PAYMENT_REQUIRED);
+        assert_eq!(
+            body,
+            json!({"error": {"code": "quota_exceeded", "message": "quota exceeded"}})
+        );
+    }
+
+    #[test]
+    fn test_api_error_from_errors() {
+        let err: BoxError = ApiError::new(ApiErrorCode::NotFound, "no such user").into();
+        let err = ApiError::from(err);
+        assert_eq!(err.code(), &ApiErrorCode::NotFound);
+        assert_eq!(err.message(), "no such user");
+
+        let err = ApiError::new(ApiErrorCode::Conflict, "exists").context("create user");
+        assert_eq!(ApiError::from(err).code(), &ApiErrorCode::Conflict);
+
+        let err = io::Error::new(io::ErrorKind::NotFound, "no file").context("read file");
+        assert_eq!(ApiError::from(err).code(), &ApiErrorCode::NotFound);
+
+        let err: BoxError = Box::new(LimitReached::new());
+        assert_eq!(
+            ApiError::from(err).code(),
+            &ApiErrorCode::ServiceUnavailable
+        );
+
+        let err = OpaqueError::from_display("db password is hunter2");
+        let err = ApiError::from(err);
+        assert_eq!(err.code(), &ApiErrorCode::InternalError);
+        assert_eq!(err.message(), "internal server error");
+
+        rama_utils::macros::error::static_str_error! {
+            #[doc = "unknown error"]
+            pub struct UnknownError;
+        }
+        let err: BoxError = Box::new(UnknownError::new());
+        assert_eq!(ApiError::from(err).code(), &ApiErrorCode::InternalError);
+    }
+}
@@ -0,0 +1,74 @@
+//! Echo handler which responds with the received request, for debugging purposes.
+//!
+//! See [`WebService::echo_handler`] for more information.
+//!
+//! [`WebService::echo_handler`]: super::WebService::echo_handler
+
+use crate::dep::http_body_util::BodyExt;
+use crate::service::web::response::{IntoResponse, Json};
+use crate::{Request, Response, StatusCode};
+use rama_core::Context;
+use serde::Serialize;
+use std::fmt;
+
+#[derive(Debug, Clone, Serialize)]
+/// The request as received by the [`WebService::echo_handler`],
+/// returned to the client as the JSON body of the response.
+///
+/// [`WebService::echo_handler`]: super::WebService::echo_handler
+pub struct EchoResponse {
+    /// Method of the request, e.g. `GET`.
+    pub method: String,
+    /// Full uri of the request, as received.
+    pub uri: String,
+    /// Path of the request uri.
+    pub path: String,
+    /// Query of the request uri, if any.
+    pub query: Option<String>,
+    /// Http version of the request, e.g. `HTTP/1.1`.
+    pub version: String,
+    /// Headers of the request as name-value pairs, in the order received.
+    pub headers: Vec<(String, String)>,
+    /// Body of the request, lossy decoded as UTF-8.
+    pub body: String,
+    /// [`Debug`] representation of the [`Context`] state.
+    pub state: String,
+}
+
+pub(super) async fn echo<State>(ctx: Context<State>, req: Request) -> Response
+where
+    State: fmt::Debug + Clone + Send + Sync + 'static,
+{
+    let (parts, body) = req.into_parts();
+    let body = match body.collect().await {
+        Ok(body) => body.to_bytes(),
+        Err(err) => {
+            return (
+                StatusCode::BAD_REQUEST,
+                format!("failed to read request body: {err}"),
+            )
+                .into_response();
+        }
+    };
+
+    Json(EchoResponse {
+        method: parts.method.to_string(),
+        uri: parts.uri.to_string(),
+        path: parts.uri.path().to_owned(),
+        query: parts.uri.query().map(ToOwned::to_owned),
+        version: format!("{:?}", parts.version),
+        headers: parts
+            .headers
+            .iter()
+            .map(|(name, value)| {
+                (
+                    name.as_str().to_owned(),
+                    String::from_utf8_lossy(value.as_bytes()).into_owned(),
+                )
+            })
+            .collect(),
+        body: String::from_utf8_lossy(&body).into_owned(),
+        state: format!("{:?}", ctx.state()),
+    })
+    .into_response()
+}
@@ -0,0 +1,414 @@
+//! Module in function of the [`AcceptLanguage`] and [`NegotiatedLanguage`] extractors.
+
+use super::FromRequestContextRefPair;
+use crate::dep::http::request::Parts;
+use crate::header::ACCEPT_LANGUAGE;
+use crate::headers::specifier::Quality;
+use crate::utils::macros::{composite_http_rejection, define_http_rejection};
+use rama_core::Context;
+use rama_core::error::OpaqueError;
+use rama_utils::macros::impl_deref;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::str::FromStr;
+use std::sync::Arc;
+
+#[derive(Debug, Clone)]
+/// A language tag as defined in [RFC 5646], e.g. `en`, `en-US` or `zh-Hant-TW`,
+/// or the `*` wildcard when used as a language range.
+///
+/// Only the syntax of the tag is validated: it consists of subtags of
+/// 1 to 8 alphanumeric characters separated by `-`, where the first subtag
+/// only consists of alphabetic characters. Comparison is case-insensitive.
+///
+/// [RFC 5646]: https://datatracker.ietf.org/doc/html/rfc5646
+pub struct LanguageTag(Arc<str>);
+
+impl LanguageTag {
+    /// Create a [`LanguageTag`] from a static str.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the str is not a valid [`LanguageTag`].
+    pub fn from_static(s: &'static str) -> Self {
+        s.parse().expect("valid language tag")
+    }
+
+    /// Return the [`LanguageTag`] as a str.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Returns `true` if this is the `*` wildcard language range.
+    pub fn is_wildcard(&self) -> bool {
+        &*self.0 == "*"
+    }
+
+    /// Return the primary language subtag, e.g. `en` for `en-US`.
+    pub fn primary_language(&self) -> &str {
+        self.0.split('-').next().unwrap_or_default()
+    }
+}
+
+impl FromStr for LanguageTag {
+    type Err = OpaqueError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if s == "*" {
+            return Ok(Self(s.into()));
+        }
+
+        let mut subtags = s.split('-');
+        let valid = subtags.next().is_some_and(|primary| {
+            (1..=8).contains(&primary.len()) && primary.bytes().all(|b| b.is_ascii_alphabetic())
+        }) && subtags.all(|subtag| {
+            (1..=8).contains(&subtag.len()) && subtag.bytes().all(|b| b.is_ascii_alphanumeric())
+        });
+        if !valid {
+            return Err(OpaqueError::from_display(format!(
+                "invalid language tag: '{s}'"
+            )));
+        }
+        Ok(Self(s.into()))
+    }
+}
+
+impl PartialEq for LanguageTag {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.eq_ignore_ascii_case(&other.0)
+    }
+}
+
+impl Eq for LanguageTag {}
+
+impl Hash for LanguageTag {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        for b in self.0.bytes() {
+            state.write_u8(b.to_ascii_lowercase());
+        }
+    }
+}
+
+impl fmt::Display for LanguageTag {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+/// Extractor that parses the `Accept-Language` header of the request
+/// into language tags and their q-values, sorted from most to least preferred.
+///
+/// Entries with an invalid language tag or q-value are ignored.
+#[derive(Debug, Clone)]
+pub struct AcceptLanguage(pub Vec<(LanguageTag, f32)>);
+
+impl_deref!(AcceptLanguage: Vec<(LanguageTag, f32)>);
+
+impl AcceptLanguage {
+    fn parse(value: &str) -> Self {
+        let mut languages: Vec<_> = value
+            .split(',')
+            .filter_map(|entry| {
+                let mut params = entry.split(';');
+                let tag: LanguageTag = params.next()?.parse().ok()?;
+                let quality = match params.next() {
+                    Some(q) => q.trim().parse::<Quality>().ok()?,
+                    None => Quality::one(),
+                };
+                Some((tag, f32::from(quality.as_u16()) / 1000.0))
+            })
+            .collect();
+        // stable sort, so languages with equal q-values keep their order
+        languages.sort_by(|(_, a), (_, b)| b.total_cmp(a));
+        Self(languages)
+    }
+}
+
+define_http_rejection! {
+    #[status = BAD_REQUEST]
+    #[body = "Missing or invalid Accept-Language header"]
+    /// Rejection type used if the [`AcceptLanguage`] extractor is unable to
+    /// find any valid language in the `Accept-Language` header.
+    pub struct MissingAcceptLanguage;
+}
+
+impl<S> FromRequestContextRefPair<S> for AcceptLanguage
+where
+    S: Clone + Send + Sync + 'static,
+{
+    type Rejection = MissingAcceptLanguage;
+
+    async fn from_request_context_ref_pair(
+        _ctx: &Context<S>,
+        parts: &Parts,
+    ) -> Result<Self, Self::Rejection> {
+        let value = parts
+            .headers
+            .get_all(ACCEPT_LANGUAGE)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .collect::<Vec<_>>()
+            .join(",");
+        let accept_language = Self::parse(&value);
+        if accept_language.is_empty() {
+            return Err(MissingAcceptLanguage);
+        }
+        Ok(accept_language)
+    }
+}
+
+#[derive(Debug, Clone)]
+/// Configuration used by the [`NegotiatedLanguage`] extractor,
+/// expected to be found in the [`Context`].
+pub struct LanguageConfig {
+    supported: Vec<LanguageTag>,
+}
+
+impl LanguageConfig {
+    /// Create a new [`LanguageConfig`] for the given supported languages.
+    ///
+    /// The first supported language is considered the default language,
+    /// selected in case the client accepts any language using the `*` wildcard.
+    pub fn new(supported: impl IntoIterator<Item = LanguageTag>) -> Self {
+        Self {
+            supported: supported.into_iter().collect(),
+        }
+    }
+
+    /// Return the supported languages.
+    pub fn supported(&self) -> &[LanguageTag] {
+        &self.supported
+    }
+
+    /// Select the best supported language for the given [`AcceptLanguage`],
+    /// using the lookup matching scheme as defined in [RFC 4647, section 3.4].
+    ///
+    /// [RFC 4647, section 3.4]: https://datatracker.ietf.org/doc/html/rfc4647#section-3.4
+    pub fn lookup(&self, accept_language: &AcceptLanguage) -> Option<&LanguageTag> {
+        let mut wildcard = false;
+        for (range, quality) in accept_language.iter() {
+            if *quality <= 0.0 {
+                continue;
+            }
+            if range.is_wildcard() {
+                wildcard = true;
+                continue;
+            }
+
+            // progressively truncate the range from the end until a supported language matches
+            let mut range = range.as_str();
+            loop {
+                if let Some(tag) = self
+                    .supported
+                    .iter()
+                    .find(|tag| tag.as_str().eq_ignore_ascii_case(range))
+                {
+                    return Some(tag);
+                }
+                let Some((rest, _)) = range.rsplit_once('-') else {
+                    break;
+                };
+                // single character subtags (e.g. the `x` private use prefix)
+                // are removed together with the subtag following them
+                range = match rest.rsplit_once('-') {
+                    Some((prefix, last)) if last.len() == 1 => prefix,
+                    _ => rest,
+                };
+            }
+        }
+        if wildcard {
+            self.supported.first()
+        } else {
+            None
+        }
+    }
+}
+
+/// Extractor that selects the best language supported by the service
+/// for the `Accept-Language` header of the request,
+/// using the [`LanguageConfig`] found in the [`Context`].
+///
+/// See [`LanguageConfig::lookup`] for more information about the matching.
+#[derive(Debug, Clone)]
+pub struct NegotiatedLanguage(pub LanguageTag);
+
+impl_deref!(NegotiatedLanguage: LanguageTag);
+
+define_http_rejection! {
+    #[status = INTERNAL_SERVER_ERROR]
+    #[body = "No language config found"]
+    /// Rejection type used if no [`LanguageConfig`] is found in the [`Context`].
+    pub struct MissingLanguageConfig;
+}
+
+define_http_rejection! {
+    #[status = NOT_ACCEPTABLE]
+    #[body = "None of the accepted languages is supported"]
+    /// Rejection type used if the [`NegotiatedLanguage`] extractor is unable to
+    /// match any of the accepted languages with the supported languages.
+    pub struct NoMatchingLanguage;
+}
+
+composite_http_rejection! {
+    /// Rejection used for [`NegotiatedLanguage`].
+    ///
+    /// Contains one variant for each way the [`NegotiatedLanguage`] extractor
+    /// can fail.
+    pub enum NegotiatedLanguageRejection {
+        MissingAcceptLanguage,
+        MissingLanguageConfig,
+        NoMatchingLanguage,
+    }
+}
+
+impl<S> FromRequestContextRefPair<S> for NegotiatedLanguage
+where
+    S: Clone + Send + Sync + 'static,
+{
+    type Rejection = NegotiatedLanguageRejection;
+
+    async fn from_request_context_ref_pair(
+        ctx: &Context<S>,
+        parts: &Parts,
+    ) -> Result<Self, Self::Rejection> {
+        let accept_language = AcceptLanguage::from_request_context_ref_pair(ctx, parts).await?;
+        let config = ctx.get::<LanguageConfig>().ok_or(MissingLanguageConfig)?;
+        config
+            .lookup(&accept_language)
+            .cloned()
+            .map(Self)
+            .ok_or_else(|| NoMatchingLanguage.into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::dep::http_body_util::BodyExt as _;
+    use crate::service::web::WebService;
+    use crate::{Body, Request, StatusCode};
+    use rama_core::Service;
+
+    fn tags(accept_language: &AcceptLanguage) -> Vec<(&str, f32)> {
+        accept_language
+            .iter()
+            .map(|(tag, q)| (tag.as_str(), *q))
+            .collect()
+    }
+
+    #[test]
+    fn parse_language_tag() {
+        for tag in ["en", "en-US", "zh-Hant-TW", "de-CH-1996", "*", "x-klingon"] {
+            assert_eq!(tag.parse::<LanguageTag>().unwrap().as_str(), tag);
+        }
+        for tag in [
+            "",
+            "-",
+            "en-",
+            "1en",
+            "en_US",
+            "toolongtag",
+            "en-toolongtag",
+        ] {
+            assert!(tag.parse::<LanguageTag>().is_err(), "tag: {tag}");
+        }
+        assert_eq!(
+            LanguageTag::from_static("en-US"),
+            LanguageTag::from_static("EN-us")
+        );
+        assert_eq!(LanguageTag::from_static("en-US").primary_language(), "en");
+    }
+
+    #[test]
+    fn parse_accept_language() {
+        let accept_language =
+            AcceptLanguage::parse("fr-CH, fr;q=0.9, en;q=0.8, de;q=0.7, *;q=0.5, nl;q=0.9");
+        assert_eq!(
+            tags(&accept_language),
+            vec![
+                ("fr-CH", 1.0),
+                ("fr", 0.9),
+                ("nl", 0.9),
+                ("en", 0.8),
+                ("de", 0.7),
+                ("*", 0.5),
+            ]
+        );
+
+        let accept_language = AcceptLanguage::parse("en;q=2, 1nvalid, de ; q=0.25,, es;Q=0");
+        assert_eq!(tags(&accept_language), vec![("de", 0.25), ("es", 0.0)]);
+    }
+
+    #[test]
+    fn language_lookup() {
+        let config = LanguageConfig::new([
+            LanguageTag::from_static("en"),
+            LanguageTag::from_static("de-CH"),
+            LanguageTag::from_static("zh-Hant"),
+        ]);
+
+        for (header, expected) in [
+            ("en", Some("en")),
+            ("EN-gb", Some("en")),
+            ("de-CH-1996, en;q=0.5", Some("de-CH")),
+            ("de, en;q=0.5", Some("en")),
+            ("zh-Hant-CN-x-private1", Some("zh-Hant")),
+            ("fr, *;q=0.1", Some("en")),
+            ("fr, en;q=0", None),
+            ("fr, nl", None),
+        ] {
+            let accept_language = AcceptLanguage::parse(header);
+            assert_eq!(
+                config.lookup(&accept_language).map(LanguageTag::as_str),
+                expected,
+                "header: {header}"
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn negotiated_language_extractor() {
+        let svc = WebService::default().get(
+            "/",
+            async |NegotiatedLanguage(language): NegotiatedLanguage| language.to_string(),
+        );
+
+        let mut ctx = Context::default();
+        ctx.insert(LanguageConfig::new([
+            LanguageTag::from_static("en"),
+            LanguageTag::from_static("nl"),
+        ]));
+
+        let req = Request::builder()
+            .uri("/")
+            .header(ACCEPT_LANGUAGE, "fr-BE, nl-BE;q=0.8, en;q=0.5")
+            .body(Body::empty())
+            .unwrap();
+        let res = svc.serve(ctx.clone(), req).await.unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+        let body = res.into_body().collect().await.unwrap().to_bytes();
+        assert_eq!(body, "nl");
+
+        let req = Request::builder()
+            .uri("/")
+            .header(ACCEPT_LANGUAGE, "fr")
+            .body(Body::empty())
+            .unwrap();
+        let res = svc.serve(ctx.clone(), req).await.unwrap();
+        assert_eq!(res.status(), StatusCode::NOT_ACCEPTABLE);
+
+        let req = Request::builder().uri("/").body(Body::empty()).unwrap();
+        let res = svc.serve(ctx, req).await.unwrap();
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+
+        let req = Request::builder()
+            .uri("/")
+            .header(ACCEPT_LANGUAGE, "en")
+            .body(Body::empty())
+            .unwrap();
+        let res = svc.serve(Context::default(), req).await.unwrap();
+        assert_eq!(res.status(), StatusCode::INTERNAL_SERVER_ERROR);
+    }
+}
@@ -0,0 +1,107 @@
+//! Module in function of the [`BearerToken`] extractor.
+
+use super::{FromRequestContextRefPair, OptionalFromRequestContextRefPair};
+use crate::dep::http::request::Parts;
+use crate::headers::{Authorization, HeaderMapExt};
+use crate::utils::macros::define_http_rejection;
+use rama_core::Context;
+use rama_net::user::Bearer;
+use rama_utils::macros::impl_deref;
+
+/// Extractor that resolves the bearer token found
+/// in the `Authorization: Bearer <token>` header of the request.
+#[derive(Debug, Clone)]
+pub struct BearerToken(pub Bearer);
+
+impl_deref!(BearerToken: Bearer);
+
+define_http_rejection! {
+    #[status = UNAUTHORIZED]
+    #[body = "Missing or invalid bearer token"]
+    /// Rejection type used if the [`BearerToken`] extractor is unable to
+    /// find a valid bearer token in the `Authorization` header.
+    pub struct MissingBearerToken;
+}
+
+impl<S> FromRequestContextRefPair<S> for BearerToken
+where
+    S: Clone + Send + Sync + 'static,
+{
+    type Rejection = MissingBearerToken;
+
+    async fn from_request_context_ref_pair(
+        _ctx: &Context<S>,
+        parts: &Parts,
+    ) -> Result<Self, Self::Rejection> {
+        parts
+            .headers
+            .typed_get::<Authorization<Bearer>>()
+            .map(|Authorization(bearer)| Self(bearer))
+            .ok_or(MissingBearerToken)
+    }
+}
+
+impl<S> OptionalFromRequestContextRefPair<S> for BearerToken
+where
+    S: Clone + Send + Sync + 'static,
+{
+    type Rejection = MissingBearerToken;
+
+    async fn from_request_context_ref_pair(
+        _ctx: &Context<S>,
+        parts: &Parts,
+    ) -> Result<Option<Self>, Self::Rejection> {
+        if !parts.headers.contains_key(crate::header::AUTHORIZATION) {
+            return Ok(None);
+        }
+        parts
+            .headers
+            .typed_get::<Authorization<Bearer>>()
+            .map(|Authorization(bearer)| Some(Self(bearer)))
+            .ok_or(MissingBearerToken)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::dep::http_body_util::BodyExt as _;
+    use crate::service::web::WebService;
+    use crate::{Body, Request, StatusCode, header::AUTHORIZATION};
+    use rama_core::Service;
+
+    #[tokio::test]
+    async fn bearer_token() {
+        let svc = WebService::default().get("/", async |BearerToken(bearer): BearerToken| {
+            bearer.token().to_owned()
+        });
+
+        let req = Request::builder()
+            .uri("/")
+            .header(AUTHORIZATION, "Bearer abc.def.ghi")
+            .body(Body::empty())
+            .unwrap();
+        let res = svc.serve(Context::default(), req).await.unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+        let body = res.into_body().collect().await.unwrap().to_bytes();
+        assert_eq!(body, "abc.def.ghi");
+    }
+
+    #[tokio::test]
+    async fn missing_bearer_token() {
+        let svc = WebService::default().get("/", async |BearerToken(bearer): BearerToken| {
+            bearer.token().to_owned()
+        });
+
+        for header in [None, Some("Basic YWxhZGRpbjpvcGVuc2VzYW1l")] {
+            let mut builder = Request::builder().uri("/");
+            if let Some(value) = header {
+                builder = builder.header(AUTHORIZATION, value);
+            }
+            let req = builder.body(Body::empty()).unwrap();
+            let res = svc.serve(Context::default(), req).await.unwrap();
+            assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+        }
+    }
+}
@@ -37,6 +37,37 @@ composite_http_rejection! {
     }
 }
 
+/// Wrapper used to extract Form from Http [`Request`] bodies,
+/// regardless of their `Content-Type` header.
+///
+/// Same as [`Form`], but for clients which do not (properly) set the
+/// `Content-Type: application/x-www-form-urlencoded` header,
+/// for which [`Form`] rejects the request with `415 Unsupported Media Type`.
+///
+/// # Example
+///
+/// ```
+/// use rama_http::service::web::extract::RelaxedForm;
+///
+/// #[derive(Debug, serde::Deserialize)]
+/// struct Input {
+///     name: String,
+///     age: u8,
+/// }
+///
+/// async fn handler(RelaxedForm(input): RelaxedForm<Input>) {
+///     println!("{} is {} years old", input.name, input.age);
+/// }
+/// ```
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RelaxedForm<T>(pub T);
+
+impl<T> From<RelaxedForm<T>> for Form<T> {
+    fn from(form: RelaxedForm<T>) -> Self {
+        Self(form.0)
+    }
+}
+
 impl<T> FromRequest for Form<T>
 where
     T: serde::de::DeserializeOwned + Send + Sync + 'static,
@@ -44,35 +75,52 @@ where
     type Rejection = FormRejection;
 
     async fn from_request(req: Request) -> Result<Self, Self::Rejection> {
-        // Extracted into separate fn so it's only compiled once for all T.
-        async fn extract_form_body_bytes(req: Request) -> Result<Bytes, FormRejection> {
-            if !crate::service::web::extract::has_any_content_type(
+        extract_form(req, true).await.map(Form)
+    }
+}
+
+impl<T> FromRequest for RelaxedForm<T>
+where
+    T: serde::de::DeserializeOwned + Send + Sync + 'static,
+{
+    type Rejection = FormRejection;
+
+    async fn from_request(req: Request) -> Result<Self, Self::Rejection> {
+        extract_form(req, false).await.map(RelaxedForm)
+    }
+}
+
+async fn extract_form<T>(req: Request, check_content_type: bool) -> Result<T, FormRejection>
+where
+    T: serde::de::DeserializeOwned,
+{
+    // Extracted into separate fn so it's only compiled once for all T.
+    async fn extract_form_body_bytes(
+        req: Request,
+        check_content_type: bool,
+    ) -> Result<Bytes, FormRejection> {
+        if check_content_type
+            && !crate::service::web::extract::has_any_content_type(
                 req.headers(),
                 &[&mime::APPLICATION_WWW_FORM_URLENCODED],
-            ) {
-                return Err(InvalidFormContentType.into());
-            }
+            )
+        {
+            return Err(InvalidFormContentType.into());
+        }
 
-            let body = req.into_body();
-            let bytes = body.collect().await.map_err(BytesRejection::from_err)?;
+        let body = req.into_body();
+        let bytes = body.collect().await.map_err(BytesRejection::from_err)?;
 
-            Ok(bytes.to_bytes())
-        }
+        Ok(bytes.to_bytes())
+    }
 
-        if req.method() == Method::GET {
-            let query = req.uri().query().unwrap_or_default();
-            let value = match serde_html_form::from_bytes(query.as_bytes()) {
-                Ok(value) => value,
-                Err(err) => return Err(FailedToDeserializeForm::from_err(err).into()),
-            };
-            Ok(Form(value))
-        } else {
-            let b = extract_form_body_bytes(req).await?;
-            Ok(Form(match serde_html_form::from_bytes(&b) {
-                Ok(value) => value,
-                Err(err) => return Err(FailedToDeserializeForm::from_err(err).into()),
-            }))
-        }
+    if req.method() == Method::GET {
+        let query = req.uri().query().unwrap_or_default();
+        serde_html_form::from_bytes(query.as_bytes())
+            .map_err(|err| FailedToDeserializeForm::from_err(err).into())
+    } else {
+        let b = extract_form_body_bytes(req, check_content_type).await?;
+        serde_html_form::from_bytes(&b).map_err(|err| FailedToDeserializeForm::from_err(err).into())
     }
 }
 
@@ -148,6 +196,36 @@ mod test {
         assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
     }
 
+    #[tokio::test]
+    async fn test_form_post_content_type() {
+        #[derive(Debug, serde::Deserialize)]
+        struct Input {
+            name: String,
+            age: u8,
+        }
+
+        let service = WebService::default()
+            .post("/strict", async |Form(_): Form<Input>| StatusCode::OK)
+            .post("/relaxed", async |RelaxedForm(body): RelaxedForm<Input>| {
+                assert_eq!(body.name, "Devan");
+                assert_eq!(body.age, 29);
+            });
+
+        for (path, expected_status) in [
+            ("/strict", StatusCode::UNSUPPORTED_MEDIA_TYPE),
+            ("/relaxed", StatusCode::OK),
+        ] {
+            let req = Request::builder()
+                .uri(path)
+                .method(Method::POST)
+                .header("content-type", "text/plain")
+                .body(r#"name=Devan&age=29"#.into())
+                .unwrap();
+            let resp = service.serve(Context::default(), req).await.unwrap();
+            assert_eq!(resp.status(), expected_status, "{path}");
+        }
+    }
+
     #[tokio::test]
     async fn test_form_get() {
         #[derive(Debug, serde::Deserialize)]
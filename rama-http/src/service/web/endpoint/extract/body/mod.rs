@@ -25,6 +25,14 @@ mod form;
 #[doc(inline)]
 pub use form::*;
 
+mod multipart;
+#[doc(inline)]
+pub use multipart::*;
+
+mod read_body;
+#[doc(inline)]
+pub use read_body::*;
+
 /// Extractor to get the response body.
 #[derive(Debug)]
 pub struct Body(pub http::Body);
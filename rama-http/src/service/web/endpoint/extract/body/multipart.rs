@@ -0,0 +1,342 @@
+use crate::service::web::extract::FromRequest;
+use crate::utils::macros::{composite_http_rejection, define_http_rejection};
+use crate::{HeaderMap, Request, header};
+use rama_core::bytes::Bytes;
+use std::fmt;
+
+define_http_rejection! {
+    #[status = UNSUPPORTED_MEDIA_TYPE]
+    #[body = "Multipart requests must have `Content-Type: multipart/form-data`"]
+    /// Rejection type for [`Multipart`]
+    /// used if the `Content-Type` header is missing
+    /// or its value is not `multipart/form-data`.
+    pub struct InvalidMultipartContentType;
+}
+
+define_http_rejection! {
+    #[status = BAD_REQUEST]
+    #[body = "Multipart requests must define a boundary in their `Content-Type` header"]
+    /// Rejection type for [`Multipart`]
+    /// used if the `multipart/form-data` content type has no boundary parameter.
+    pub struct MissingMultipartBoundary;
+}
+
+define_http_rejection! {
+    #[status = BAD_REQUEST]
+    #[body = "Failed to parse multipart body"]
+    /// Rejection type for [`Multipart`]
+    /// used if the body could not be parsed as `multipart/form-data`.
+    pub struct FailedToParseMultipart(Error);
+}
+
+define_http_rejection! {
+    #[status = PAYLOAD_TOO_LARGE]
+    #[body = "Multipart field exceeds the size limit"]
+    /// Rejection type for [`Multipart`]
+    /// used if a field exceeds the [`MultipartConfig::max_field_size`].
+    pub struct MultipartFieldTooLarge(Error);
+}
+
+define_http_rejection! {
+    #[status = PAYLOAD_TOO_LARGE]
+    #[body = "Multipart body contains too many fields"]
+    /// Rejection type for [`Multipart`]
+    /// used if the body contains more than [`MultipartConfig::max_fields`] fields.
+    pub struct TooManyMultipartFields;
+}
+
+composite_http_rejection! {
+    /// Rejection used for [`Multipart`]
+    ///
+    /// Contains one variant for each way the [`Multipart`] extractor
+    /// and its [`Field`]s can fail.
+    pub enum MultipartRejection {
+        InvalidMultipartContentType,
+        MissingMultipartBoundary,
+        FailedToParseMultipart,
+        MultipartFieldTooLarge,
+        TooManyMultipartFields,
+    }
+}
+
+impl From<multer::Error> for MultipartRejection {
+    fn from(err: multer::Error) -> Self {
+        match err {
+            multer::Error::FieldSizeExceeded { .. } | multer::Error::StreamSizeExceeded { .. } => {
+                MultipartFieldTooLarge::from_err(err).into()
+            }
+            err => FailedToParseMultipart::from_err(err).into(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// Limits applied by the [`Multipart`] extractor.
+///
+/// As [`FromRequest`] extractors have no access to the [`Context`],
+/// the config is expected to be found in the extensions of the [`Request`]
+/// (e.g. inserted by a layer). The default config is used if none is found.
+///
+/// [`Context`]: rama_core::Context
+pub struct MultipartConfig {
+    /// The max size in bytes of a single field.
+    pub max_field_size: u64,
+    /// The max amount of fields in a single body.
+    pub max_fields: usize,
+}
+
+impl Default for MultipartConfig {
+    fn default() -> Self {
+        Self {
+            max_field_size: 2 * 1024 * 1024,
+            max_fields: 128,
+        }
+    }
+}
+
+/// Extractor to parse a `multipart/form-data` request body,
+/// yielding its [`Field`]s one by one.
+///
+/// The body is streamed, fields have to be consumed in order,
+/// as the next field can only be read once the previous one is dropped or consumed.
+pub struct Multipart {
+    inner: multer::Multipart<'static>,
+    max_fields: usize,
+    field_count: usize,
+}
+
+impl fmt::Debug for Multipart {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Multipart")
+            .field("max_fields", &self.max_fields)
+            .field("field_count", &self.field_count)
+            .finish()
+    }
+}
+
+impl Multipart {
+    /// Yield the next [`Field`], or `None` in case all fields have been read.
+    pub async fn next_field(&mut self) -> Result<Option<Field>, MultipartRejection> {
+        let Some(field) = self.inner.next_field().await? else {
+            return Ok(None);
+        };
+        self.field_count += 1;
+        if self.field_count > self.max_fields {
+            return Err(TooManyMultipartFields.into());
+        }
+        Ok(Some(Field { inner: field }))
+    }
+}
+
+/// A single field of a [`Multipart`] body.
+pub struct Field {
+    inner: multer::Field<'static>,
+}
+
+impl fmt::Debug for Field {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Field")
+            .field("name", &self.name())
+            .field("file_name", &self.file_name())
+            .field("content_type", &self.content_type())
+            .finish()
+    }
+}
+
+impl Field {
+    /// The name of the field, as found in its `Content-Disposition` header.
+    pub fn name(&self) -> Option<&str> {
+        self.inner.name()
+    }
+
+    /// The file name of the field, as found in its `Content-Disposition` header.
+    pub fn file_name(&self) -> Option<&str> {
+        self.inner.file_name()
+    }
+
+    /// The content type of the field, as found in its `Content-Type` header.
+    pub fn content_type(&self) -> Option<&mime::Mime> {
+        self.inner.content_type()
+    }
+
+    /// The headers of the field.
+    pub fn headers(&self) -> &HeaderMap {
+        self.inner.headers()
+    }
+
+    /// Read the next chunk of the field body, or `None` in case the body was fully read.
+    pub async fn chunk(&mut self) -> Result<Option<Bytes>, MultipartRejection> {
+        Ok(self.inner.chunk().await?)
+    }
+
+    /// Read the full field body as bytes.
+    pub async fn bytes(self) -> Result<Bytes, MultipartRejection> {
+        Ok(self.inner.bytes().await?)
+    }
+
+    /// Read the full field body as text.
+    pub async fn text(self) -> Result<String, MultipartRejection> {
+        Ok(self.inner.text().await?)
+    }
+}
+
+impl FromRequest for Multipart {
+    type Rejection = MultipartRejection;
+
+    async fn from_request(req: Request) -> Result<Self, Self::Rejection> {
+        if !crate::service::web::extract::has_any_content_type(
+            req.headers(),
+            &[&mime::MULTIPART_FORM_DATA],
+        ) {
+            return Err(InvalidMultipartContentType.into());
+        }
+        let boundary = req
+            .headers()
+            .get(header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| multer::parse_boundary(value).ok())
+            .ok_or(MissingMultipartBoundary)?;
+
+        let config = req
+            .extensions()
+            .get::<MultipartConfig>()
+            .cloned()
+            .unwrap_or_default();
+        let constraints = multer::Constraints::new()
+            .size_limit(multer::SizeLimit::new().per_field(config.max_field_size));
+
+        Ok(Self {
+            inner: multer::Multipart::with_constraints(
+                req.into_body().into_data_stream(),
+                boundary,
+                constraints,
+            ),
+            max_fields: config.max_fields,
+            field_count: 0,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::dep::http_body_util::BodyExt;
+    use crate::service::web::WebService;
+    use crate::service::web::endpoint::IntoResponse;
+    use crate::{Body, Method, Response, StatusCode};
+    use rama_core::{Context, Service};
+    use std::convert::Infallible;
+
+    const BODY: &str = "--X-BOUNDARY\r\n\
+        Content-Disposition: form-data; name=\"name\"\r\n\
+        \r\n\
+        glen\r\n\
+        --X-BOUNDARY\r\n\
+        Content-Disposition: form-data; name=\"avatar\"; filename=\"avatar.txt\"\r\n\
+        Content-Type: text/plain\r\n\
+        \r\n\
+        hello world\r\n\
+        --X-BOUNDARY--\r\n";
+
+    fn multipart_request(content_type: &str, config: Option<MultipartConfig>) -> Request {
+        let mut req = Request::builder()
+            .method(Method::POST)
+            .header(header::CONTENT_TYPE, content_type)
+            .body(BODY.into())
+            .unwrap();
+        if let Some(config) = config {
+            req.extensions_mut().insert(config);
+        }
+        req
+    }
+
+    async fn describe_fields(mut multipart: Multipart) -> Result<String, MultipartRejection> {
+        let mut fields = Vec::new();
+        while let Some(field) = multipart.next_field().await? {
+            let description = format!(
+                "{}:{}:{}",
+                field.name().unwrap_or_default(),
+                field.file_name().unwrap_or_default(),
+                field
+                    .content_type()
+                    .map(ToString::to_string)
+                    .unwrap_or_default(),
+            );
+            let text = field.text().await?;
+            fields.push(format!("{description}={text}"));
+        }
+        Ok(fields.join(";"))
+    }
+
+    fn service() -> impl Service<(), Request, Response = Response, Error = Infallible> {
+        WebService::default().post("/", async |multipart: Multipart| {
+            describe_fields(multipart).await.into_response()
+        })
+    }
+
+    #[tokio::test]
+    async fn test_multipart() {
+        let res = service()
+            .serve(
+                Context::default(),
+                multipart_request("multipart/form-data; boundary=X-BOUNDARY", None),
+            )
+            .await
+            .unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+        let body = res.into_body().collect().await.unwrap().to_bytes();
+        assert_eq!(body, "name::=glen;avatar:avatar.txt:text/plain=hello world");
+    }
+
+    #[tokio::test]
+    async fn test_multipart_invalid_content_type() {
+        for (content_type, expected_status) in [
+            ("application/json", StatusCode::UNSUPPORTED_MEDIA_TYPE),
+            ("multipart/form-data", StatusCode::BAD_REQUEST),
+        ] {
+            let res = service()
+                .serve(Context::default(), multipart_request(content_type, None))
+                .await
+                .unwrap();
+            assert_eq!(
+                res.status(),
+                expected_status,
+                "content type: {content_type}"
+            );
+        }
+
+        let req = Request::builder()
+            .method(Method::POST)
+            .header(header::CONTENT_TYPE, "multipart/form-data; boundary=Y")
+            .body(Body::from("not multipart"))
+            .unwrap();
+        let res = service().serve(Context::default(), req).await.unwrap();
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_multipart_limits() {
+        for config in [
+            MultipartConfig {
+                max_field_size: 5,
+                ..Default::default()
+            },
+            MultipartConfig {
+                max_fields: 1,
+                ..Default::default()
+            },
+        ] {
+            let res = service()
+                .serve(
+                    Context::default(),
+                    multipart_request(
+                        "multipart/form-data; boundary=X-BOUNDARY",
+                        Some(config.clone()),
+                    ),
+                )
+                .await
+                .unwrap();
+            assert_eq!(res.status(), StatusCode::PAYLOAD_TOO_LARGE, "{config:?}");
+        }
+    }
+}
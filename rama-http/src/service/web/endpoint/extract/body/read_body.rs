@@ -0,0 +1,86 @@
+use crate::body::BufferedBody;
+use crate::dep::http::request::Parts;
+use crate::service::web::extract::FromRequestContextRefPair;
+use crate::utils::macros::define_http_rejection;
+use rama_core::Context;
+use rama_core::bytes::Bytes;
+use rama_utils::macros::impl_deref;
+
+define_http_rejection! {
+    #[status = INTERNAL_SERVER_ERROR]
+    #[body = "Request body was not buffered"]
+    /// Rejection type used if the [`ReadBody`] extractor is unable to
+    /// find a [`BufferedBody`] for the request.
+    pub struct MissingBufferedBody;
+}
+
+/// Extractor to get the buffered request body as [`Bytes`].
+///
+/// Unlike the [`Bytes`](super::Bytes) extractor, it does not consume the request body,
+/// such that it can be combined with other (body) extractors. This requires the body
+/// to be buffered as a [`BufferedBody`], found in the [`Context`] (as inserted by the
+/// [`ReadBodyLayer`]) or the request extensions.
+///
+/// [`ReadBodyLayer`]: crate::layer::read_body::ReadBodyLayer
+#[derive(Debug, Clone)]
+pub struct ReadBody(pub Bytes);
+
+impl_deref!(ReadBody: Bytes);
+
+impl<S> FromRequestContextRefPair<S> for ReadBody
+where
+    S: Clone + Send + Sync + 'static,
+{
+    type Rejection = MissingBufferedBody;
+
+    async fn from_request_context_ref_pair(
+        ctx: &Context<S>,
+        parts: &Parts,
+    ) -> Result<Self, Self::Rejection> {
+        ctx.get::<BufferedBody>()
+            .or_else(|| parts.extensions.get::<BufferedBody>())
+            .map(|body| Self(body.bytes().clone()))
+            .ok_or(MissingBufferedBody)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::layer::read_body::ReadBodyLayer;
+    use crate::service::web::WebService;
+    use crate::service::web::extract::Json;
+    use crate::{Body, BodyExtractExt, Request, StatusCode};
+    use rama_core::{Layer, Service};
+
+    #[tokio::test]
+    async fn test_read_body() {
+        #[derive(Debug, serde::Deserialize)]
+        struct Input {
+            name: String,
+        }
+
+        let service = ReadBodyLayer::new().into_layer(WebService::default().post(
+            "/",
+            async |ReadBody(raw): ReadBody, Json(input): Json<Input>| {
+                format!("{}:{}", raw.len(), input.name)
+            },
+        ));
+
+        let req = Request::post("/")
+            .header("content-type", "application/json")
+            .body(Body::from(r#"{"name":"glen"}"#))
+            .unwrap();
+        let res = service.serve(Context::default(), req).await.unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(res.into_body().try_into_string().await.unwrap(), "15:glen");
+    }
+
+    #[tokio::test]
+    async fn test_read_body_missing() {
+        let service = WebService::default().post("/", async |ReadBody(raw): ReadBody| raw);
+        let req = Request::post("/").body(Body::from("hello")).unwrap();
+        let res = service.serve(Context::default(), req).await.unwrap();
+        assert_eq!(res.status(), StatusCode::INTERNAL_SERVER_ERROR);
+    }
+}
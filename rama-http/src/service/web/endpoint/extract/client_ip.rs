@@ -0,0 +1,273 @@
+//! Module in function of the [`ClientIp`] extractor.
+
+use super::FromRequestContextRefPair;
+use crate::HeaderMap;
+use crate::dep::http::request::Parts;
+use crate::headers::HeaderMapExt;
+use crate::headers::forwarded::{Forwarded, XForwardedFor, XRealIp};
+use crate::utils::macros::define_http_rejection;
+use rama_core::Context;
+use rama_net::stream::SocketInfo;
+use rama_net::stream::dep::ipnet::IpNet;
+use rama_utils::macros::impl_deref;
+use std::net::IpAddr;
+
+/// Extractor that resolves the IP address of the client.
+///
+/// The following sources are checked, in order of precedence:
+///
+/// 1. the `for` parameter of the first [`Forwarded`] header element;
+/// 2. the first entry of the [`XForwardedFor`] header;
+/// 3. the [`XRealIp`] header;
+/// 4. the peer address of the [`SocketInfo`] found in the [`Context`].
+///
+/// The headers are only trusted as far as the [`ClientIpConfig`] found in the [`Context`]
+/// allows it. In case no such config is found no headers are trusted,
+/// and the peer address is used, as the headers can be spoofed by any client.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ClientIp(pub IpAddr);
+
+impl_deref!(ClientIp: IpAddr);
+
+define_http_rejection! {
+    #[status = BAD_REQUEST]
+    #[body = "Failed to detect the client IP"]
+    /// Rejection type used if the [`ClientIp`] extractor is unable to
+    /// determine the IP address of the client.
+    pub struct MissingClientIp;
+}
+
+#[derive(Debug, Clone, Default)]
+/// Configuration used by the [`ClientIp`] extractor,
+/// defining to what extent forwarding headers can be trusted.
+///
+/// Insert it in the [`Context`] to configure the [`ClientIp`] extractor.
+/// Without it no forwarding headers are trusted, see [`ClientIpConfig::trust_none`].
+pub struct ClientIpConfig {
+    trust: ClientIpTrust,
+}
+
+#[derive(Debug, Clone, Default)]
+enum ClientIpTrust {
+    All,
+    #[default]
+    None,
+    Hops(usize),
+    Proxies(Vec<IpNet>),
+}
+
+impl ClientIpConfig {
+    /// Trust all forwarding headers, using the first (left-most) client IP.
+    ///
+    /// Only use this when your service is behind a proxy which overwrites
+    /// these headers, as otherwise any client can spoof its IP.
+    pub fn trust_all() -> Self {
+        Self {
+            trust: ClientIpTrust::All,
+        }
+    }
+
+    /// Trust no forwarding headers, only using the peer address of the connection.
+    ///
+    /// This is the default.
+    pub fn trust_none() -> Self {
+        Self {
+            trust: ClientIpTrust::None,
+        }
+    }
+
+    /// Trust the given amount of proxy hops in front of this service.
+    ///
+    /// E.g. when trusting a single hop, the last (right-most) IP
+    /// in the forwarding chain is used, as that is the IP which was
+    /// added by the single proxy you trust.
+    pub fn trust_hops(hops: usize) -> Self {
+        Self {
+            trust: ClientIpTrust::Hops(hops.max(1)),
+        }
+    }
+
+    /// Trust the forwarding headers only for connections coming from
+    /// one of the given proxy networks.
+    ///
+    /// The client IP is the right-most IP in the forwarding chain
+    /// which is not part of one of the trusted networks.
+    pub fn trust_proxies(proxies: impl IntoIterator<Item = IpNet>) -> Self {
+        Self {
+            trust: ClientIpTrust::Proxies(proxies.into_iter().collect()),
+        }
+    }
+
+    fn select(&self, peer_ip: Option<IpAddr>, chain: &[IpAddr]) -> Option<IpAddr> {
+        match &self.trust {
+            ClientIpTrust::All => chain.first().copied().or(peer_ip),
+            ClientIpTrust::None => peer_ip,
+            ClientIpTrust::Hops(hops) => chain
+                .get(chain.len().saturating_sub(*hops))
+                .copied()
+                .or(peer_ip),
+            ClientIpTrust::Proxies(proxies) => {
+                let is_trusted = |ip: &IpAddr| proxies.iter().any(|net| net.contains(ip));
+                match peer_ip {
+                    Some(peer_ip) if is_trusted(&peer_ip) => chain
+                        .iter()
+                        .rev()
+                        .find(|ip| !is_trusted(ip))
+                        .or(chain.first())
+                        .copied()
+                        .or(Some(peer_ip)),
+                    peer_ip => peer_ip,
+                }
+            }
+        }
+    }
+}
+
+fn forwarded_ip_chain(headers: &HeaderMap) -> Vec<IpAddr> {
+    if let Some(forwarded) = headers.typed_get::<Forwarded>() {
+        let chain: Vec<_> = forwarded
+            .into_inner()
+            .iter()
+            .filter_map(|element| element.ref_forwarded_for().and_then(|node| node.ip()))
+            .collect();
+        if !chain.is_empty() {
+            return chain;
+        }
+    }
+    if let Some(x_forwarded_for) = headers.typed_get::<XForwardedFor>() {
+        let chain: Vec<_> = x_forwarded_for.iter().copied().collect();
+        if !chain.is_empty() {
+            return chain;
+        }
+    }
+    headers
+        .typed_get::<XRealIp>()
+        .into_iter()
+        .flatten()
+        .filter_map(|element| element.ref_forwarded_for().and_then(|node| node.ip()))
+        .collect()
+}
+
+impl<S> FromRequestContextRefPair<S> for ClientIp
+where
+    S: Clone + Send + Sync + 'static,
+{
+    type Rejection = MissingClientIp;
+
+    async fn from_request_context_ref_pair(
+        ctx: &Context<S>,
+        parts: &Parts,
+    ) -> Result<Self, Self::Rejection> {
+        resolve_client_ip(ctx, &parts.headers)
+            .map(ClientIp)
+            .ok_or(MissingClientIp)
+    }
+}
+
+/// Resolve the client IP as documented for the [`ClientIp`] extractor.
+pub(crate) fn resolve_client_ip<S>(ctx: &Context<S>, headers: &HeaderMap) -> Option<IpAddr> {
+    let peer_ip = ctx.get::<SocketInfo>().map(|info| info.peer_addr().ip());
+    let chain = forwarded_ip_chain(headers);
+    match ctx.get::<ClientIpConfig>() {
+        Some(cfg) => cfg.select(peer_ip, &chain),
+        None => ClientIpConfig::default().select(peer_ip, &chain),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::dep::http_body_util::BodyExt as _;
+    use crate::service::web::WebService;
+    use crate::{Body, Request, StatusCode};
+    use rama_core::Service;
+
+    async fn test_client_ip(
+        cfg: Option<ClientIpConfig>,
+        headers: Vec<(&str, &str)>,
+        expected: &str,
+    ) {
+        let svc = WebService::default().get("/", async |ClientIp(ip): ClientIp| ip.to_string());
+
+        let mut ctx = Context::default();
+        ctx.insert(SocketInfo::new(None, ([10, 0, 0, 1], 8080).into()));
+        if let Some(cfg) = cfg {
+            ctx.insert(cfg);
+        }
+
+        let mut builder = Request::builder().uri("/");
+        for (name, value) in headers {
+            builder = builder.header(name, value);
+        }
+        let req = builder.body(Body::empty()).unwrap();
+
+        let res = svc.serve(ctx, req).await.unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+        let body = res.into_body().collect().await.unwrap().to_bytes();
+        assert_eq!(body, expected);
+    }
+
+    #[tokio::test]
+    async fn client_ip_sources() {
+        let all = || Some(ClientIpConfig::trust_all());
+        test_client_ip(all(), vec![], "10.0.0.1").await;
+        test_client_ip(all(), vec![("x-real-ip", "1.1.1.1")], "1.1.1.1").await;
+        test_client_ip(
+            all(),
+            vec![
+                ("x-real-ip", "1.1.1.1"),
+                ("x-forwarded-for", "2.2.2.2, 3.3.3.3"),
+            ],
+            "2.2.2.2",
+        )
+        .await;
+        test_client_ip(
+            all(),
+            vec![
+                ("x-forwarded-for", "2.2.2.2"),
+                ("forwarded", "for=4.4.4.4;proto=https, for=5.5.5.5"),
+            ],
+            "4.4.4.4",
+        )
+        .await;
+    }
+
+    #[tokio::test]
+    async fn client_ip_trust() {
+        let xff = || vec![("x-forwarded-for", "2.2.2.2, 3.3.3.3, 10.0.0.2")];
+
+        // forwarding headers are not trusted by default
+        test_client_ip(None, xff(), "10.0.0.1").await;
+        test_client_ip(None, vec![("x-real-ip", "1.1.1.1")], "10.0.0.1").await;
+
+        test_client_ip(Some(ClientIpConfig::trust_none()), xff(), "10.0.0.1").await;
+        test_client_ip(Some(ClientIpConfig::trust_hops(1)), xff(), "10.0.0.2").await;
+        test_client_ip(Some(ClientIpConfig::trust_hops(2)), xff(), "3.3.3.3").await;
+        test_client_ip(Some(ClientIpConfig::trust_hops(10)), xff(), "2.2.2.2").await;
+        test_client_ip(
+            Some(ClientIpConfig::trust_proxies(["10.0.0.0/8"
+                .parse()
+                .unwrap()])),
+            xff(),
+            "3.3.3.3",
+        )
+        .await;
+        test_client_ip(
+            Some(ClientIpConfig::trust_proxies(["192.168.0.0/16"
+                .parse()
+                .unwrap()])),
+            xff(),
+            "10.0.0.1",
+        )
+        .await;
+    }
+
+    #[tokio::test]
+    async fn missing_client_ip() {
+        let svc = WebService::default().get("/", async |ClientIp(ip): ClientIp| ip.to_string());
+        let req = Request::builder().uri("/").body(Body::empty()).unwrap();
+        let res = svc.serve(Context::default(), req).await.unwrap();
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+    }
+}
@@ -0,0 +1,354 @@
+//! Module in function of the [`Cookies`], [`CookieValue`] and [`SignedCookies`] extractors.
+
+use super::FromRequestContextRefPair;
+use crate::dep::http::request::Parts;
+use crate::headers::{Cookie, HeaderMapExt};
+#[cfg(feature = "crypto")]
+use crate::utils::hmac::HmacKey;
+use crate::utils::macros::define_http_rejection;
+#[cfg(feature = "crypto")]
+use base64::Engine as _;
+#[cfg(feature = "crypto")]
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use rama_core::Context;
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::fmt;
+use std::marker::PhantomData;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+/// The cookies found in the `Cookie` header of a request,
+/// as extracted by [`Cookies`] and [`SignedCookies`].
+pub struct CookieJar(HashMap<String, String>);
+
+impl CookieJar {
+    /// Get the value of the cookie with the given name.
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.0.get(name).map(String::as_str)
+    }
+
+    /// Returns `true` if a cookie with the given name exists.
+    pub fn contains(&self, name: &str) -> bool {
+        self.0.contains_key(name)
+    }
+
+    /// Get the number of cookies in this [`CookieJar`].
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Returns `true` if this [`CookieJar`] contains no cookies.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Iterate over the name-value pairs of this [`CookieJar`].
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.0.iter().map(|(k, v)| (k.as_str(), v.as_str()))
+    }
+
+    fn from_parts(parts: &Parts) -> Self {
+        parts
+            .headers
+            .typed_get::<Cookie>()
+            .map(|cookie| {
+                Self(
+                    cookie
+                        .iter()
+                        .map(|(k, v)| (k.to_owned(), v.to_owned()))
+                        .collect(),
+                )
+            })
+            .unwrap_or_default()
+    }
+}
+
+impl IntoIterator for CookieJar {
+    type Item = (String, String);
+    type IntoIter = std::collections::hash_map::IntoIter<String, String>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
+/// Extractor that parses the `Cookie` header into a [`CookieJar`].
+///
+/// A request without cookies results in an empty [`CookieJar`].
+#[derive(Debug, Clone, Default)]
+pub struct Cookies(pub CookieJar);
+
+impl<S> FromRequestContextRefPair<S> for Cookies
+where
+    S: Clone + Send + Sync + 'static,
+{
+    type Rejection = Infallible;
+
+    async fn from_request_context_ref_pair(
+        _ctx: &Context<S>,
+        parts: &Parts,
+    ) -> Result<Self, Self::Rejection> {
+        Ok(Self(CookieJar::from_parts(parts)))
+    }
+}
+
+/// Name of a cookie, used by the [`CookieValue`] extractor.
+///
+/// # Example
+///
+/// ```
+/// use rama_http::service::web::extract::cookie::{CookieName, CookieValue};
+///
+/// struct SessionId;
+///
+/// impl CookieName for SessionId {
+///     const NAME: &'static str = "session_id";
+/// }
+///
+/// async fn handler(CookieValue(session_id, _): CookieValue<SessionId>) -> String {
+///     session_id
+/// }
+/// ```
+pub trait CookieName: Send + Sync + 'static {
+    /// The name of the cookie.
+    const NAME: &'static str;
+}
+
+/// Extractor that retrieves the value of the cookie named by `N`.
+pub struct CookieValue<N>(pub String, pub PhantomData<fn() -> N>);
+
+impl<N> CookieValue<N> {
+    /// Consume `self` and return the cookie value.
+    pub fn into_inner(self) -> String {
+        self.0
+    }
+}
+
+impl<N: CookieName> fmt::Debug for CookieValue<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CookieValue")
+            .field("name", &N::NAME)
+            .field("value", &self.0)
+            .finish()
+    }
+}
+
+impl<N> Clone for CookieValue<N> {
+    fn clone(&self) -> Self {
+        Self(self.0.clone(), PhantomData)
+    }
+}
+
+define_http_rejection! {
+    #[status = BAD_REQUEST]
+    #[body = "Missing cookie"]
+    /// Rejection type used if the [`CookieValue`] extractor
+    /// is unable to find the cookie.
+    pub struct MissingCookie;
+}
+
+impl<S, N> FromRequestContextRefPair<S> for CookieValue<N>
+where
+    S: Clone + Send + Sync + 'static,
+    N: CookieName,
+{
+    type Rejection = MissingCookie;
+
+    async fn from_request_context_ref_pair(
+        _ctx: &Context<S>,
+        parts: &Parts,
+    ) -> Result<Self, Self::Rejection> {
+        parts
+            .headers
+            .typed_get::<Cookie>()
+            .and_then(|cookie| cookie.get(N::NAME).map(ToOwned::to_owned))
+            .map(|value| Self(value, PhantomData))
+            .ok_or(MissingCookie)
+    }
+}
+
+#[cfg(feature = "crypto")]
+#[derive(Clone)]
+/// Secret key used to sign and verify cookies,
+/// required in the [`Context`] by the [`SignedCookies`] extractor.
+///
+/// A signed cookie value has the form `<value>.<signature>`, where the signature
+/// is the url-safe base64 encoded HMAC-SHA256 of `<name>=<value>`.
+pub struct CookieKey(HmacKey);
+
+#[cfg(feature = "crypto")]
+impl fmt::Debug for CookieKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("CookieKey").finish_non_exhaustive()
+    }
+}
+
+#[cfg(feature = "crypto")]
+impl CookieKey {
+    /// Create a new [`CookieKey`] from the given secret.
+    pub fn new(secret: impl AsRef<[u8]>) -> Self {
+        Self(HmacKey::sha256(secret.as_ref()))
+    }
+
+    /// Sign the value of the cookie with the given name,
+    /// returning the value to be used in a `Set-Cookie` header.
+    pub fn sign(&self, name: &str, value: &str) -> String {
+        let signature =
+            URL_SAFE_NO_PAD.encode(self.0.sign(&[name.as_bytes(), b"=", value.as_bytes()]));
+        format!("{value}.{signature}")
+    }
+
+    /// Verify the signed value of the cookie with the given name,
+    /// returning the original value in case the signature is valid.
+    pub fn verify<'a>(&self, name: &str, signed_value: &'a str) -> Option<&'a str> {
+        let (value, signature) = signed_value.rsplit_once('.')?;
+        let signature = URL_SAFE_NO_PAD.decode(signature).ok()?;
+        self.0
+            .verify(&[name.as_bytes(), b"=", value.as_bytes()], &signature)
+            .then_some(value)
+    }
+}
+
+/// Extractor that parses the `Cookie` header into a [`CookieJar`],
+/// only containing the cookies with a valid signature
+/// according to the [`CookieKey`] found in the [`Context`].
+///
+/// The values in the [`CookieJar`] are the original values,
+/// with the signature stripped. Cookies with an invalid signature are dropped.
+#[cfg(feature = "crypto")]
+#[derive(Debug, Clone, Default)]
+pub struct SignedCookies(pub CookieJar);
+
+#[cfg(feature = "crypto")]
+define_http_rejection! {
+    #[status = INTERNAL_SERVER_ERROR]
+    #[body = "No cookie key found to verify signed cookies"]
+    /// Rejection type used if the [`SignedCookies`] extractor
+    /// is unable to find a [`CookieKey`] in the [`Context`].
+    pub struct MissingCookieKey;
+}
+
+#[cfg(feature = "crypto")]
+impl<S> FromRequestContextRefPair<S> for SignedCookies
+where
+    S: Clone + Send + Sync + 'static,
+{
+    type Rejection = MissingCookieKey;
+
+    async fn from_request_context_ref_pair(
+        ctx: &Context<S>,
+        parts: &Parts,
+    ) -> Result<Self, Self::Rejection> {
+        let key = ctx.get::<CookieKey>().ok_or(MissingCookieKey)?;
+        let jar = CookieJar::from_parts(parts)
+            .into_iter()
+            .filter_map(|(name, value)| {
+                let value = key.verify(&name, &value)?.to_owned();
+                Some((name, value))
+            })
+            .collect();
+        Ok(Self(CookieJar(jar)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::dep::http_body_util::BodyExt as _;
+    use crate::service::web::WebService;
+    use crate::{Body, Request, StatusCode, header::COOKIE};
+    use rama_core::Service;
+
+    struct Lang;
+
+    impl CookieName for Lang {
+        const NAME: &'static str = "lang";
+    }
+
+    async fn serve_cookie(
+        svc: &impl Service<(), Request, Response = crate::Response, Error = Infallible>,
+        ctx: Context<()>,
+        cookie: Option<String>,
+    ) -> (StatusCode, String) {
+        let mut builder = Request::builder().uri("/");
+        if let Some(cookie) = cookie {
+            builder = builder.header(COOKIE, cookie);
+        }
+        let res = svc
+            .serve(ctx, builder.body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let status = res.status();
+        let body = res.into_body().collect().await.unwrap().to_bytes();
+        (status, String::from_utf8(body.to_vec()).unwrap())
+    }
+
+    #[tokio::test]
+    async fn cookies() {
+        let svc = WebService::default().get("/", async |Cookies(jar): Cookies| {
+            format!("{}:{}", jar.len(), jar.get("lang").unwrap_or_default())
+        });
+
+        assert_eq!(
+            serve_cookie(&svc, Context::default(), None).await,
+            (StatusCode::OK, "0:".to_owned())
+        );
+        assert_eq!(
+            serve_cookie(
+                &svc,
+                Context::default(),
+                Some("SID=31d4d96e407aad42; lang=en-US".to_owned())
+            )
+            .await,
+            (StatusCode::OK, "2:en-US".to_owned())
+        );
+    }
+
+    #[tokio::test]
+    async fn cookie_value() {
+        let svc =
+            WebService::default().get("/", async |CookieValue(lang, _): CookieValue<Lang>| lang);
+
+        assert_eq!(
+            serve_cookie(&svc, Context::default(), Some("lang=nl-BE".to_owned())).await,
+            (StatusCode::OK, "nl-BE".to_owned())
+        );
+        assert_eq!(
+            serve_cookie(&svc, Context::default(), Some("SID=1".to_owned()))
+                .await
+                .0,
+            StatusCode::BAD_REQUEST
+        );
+    }
+
+    #[cfg(feature = "crypto")]
+    #[tokio::test]
+    async fn signed_cookies() {
+        let svc = WebService::default().get("/", async |SignedCookies(jar): SignedCookies| {
+            let mut cookies: Vec<_> = jar.iter().map(|(k, v)| format!("{k}={v}")).collect();
+            cookies.sort();
+            cookies.join(";")
+        });
+
+        let key = CookieKey::new("secret");
+        let mut ctx = Context::default();
+        ctx.insert(key.clone());
+
+        let cookie = format!(
+            "sid={}; lang={}; forged={}",
+            key.sign("sid", "42"),
+            key.sign("lang", "en"),
+            key.sign("other", "1"),
+        );
+
+        assert_eq!(
+            serve_cookie(&svc, ctx, Some(cookie.clone())).await,
+            (StatusCode::OK, "lang=en;sid=42".to_owned())
+        );
+        assert_eq!(
+            serve_cookie(&svc, Context::default(), Some(cookie)).await.0,
+            StatusCode::INTERNAL_SERVER_ERROR
+        );
+    }
+}
@@ -0,0 +1,658 @@
+//! Module in function of the [`JwtClaims`] extractor.
+
+use super::FromRequestContextRefPair;
+use super::bearer::{BearerToken, MissingBearerToken};
+use crate::dep::http::request::Parts;
+use crate::utils::hmac::HmacKey;
+use crate::utils::macros::{composite_http_rejection, define_http_rejection};
+use base64::Engine as _;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use rama_core::Context;
+use rama_core::error::{ErrorContext, OpaqueError};
+use rama_crypto::dep::aws_lc_rs::{
+    rsa::PublicKey as RsaPublicKey,
+    signature::{ECDSA_P256_SHA256_FIXED, RSA_PKCS1_2048_8192_SHA256, UnparsedPublicKey},
+};
+use rama_crypto::jose::{JWA, JWK};
+use serde::Deserialize;
+use serde::de::DeserializeOwned;
+use std::fmt;
+use std::ops::{Deref, DerefMut};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Extractor that decodes and validates the JWT found as bearer token
+/// in the `Authorization` header, using the [`JwtValidator`] found in the [`Context`].
+///
+/// `T` is expected to implement [`serde::Deserialize`] for the claims of the JWT.
+pub struct JwtClaims<T>(pub T);
+
+define_http_rejection! {
+    #[status = INTERNAL_SERVER_ERROR]
+    #[body = "No jwt validator found"]
+    /// Rejection type used if no [`JwtValidator`] is found in the [`Context`].
+    pub struct MissingJwtValidator;
+}
+
+define_http_rejection! {
+    #[status = UNAUTHORIZED]
+    #[body = "Invalid jwt"]
+    /// Rejection type used if the [`JwtClaims`] extractor
+    /// failed to decode or validate the bearer token as a JWT.
+    pub struct InvalidJwt(Error);
+}
+
+composite_http_rejection! {
+    /// Rejection used for [`JwtClaims`].
+    ///
+    /// Contains one variant for each way the [`JwtClaims`] extractor
+    /// can fail.
+    pub enum JwtClaimsRejection {
+        MissingBearerToken,
+        MissingJwtValidator,
+        InvalidJwt,
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+/// Algorithms supported by the [`JwtValidator`].
+pub enum JwtAlgorithm {
+    /// HMAC using SHA-256
+    HS256,
+    /// RSASSA-PKCS1-v1_5 using SHA-256
+    RS256,
+    /// ECDSA using P-256 and SHA-256
+    ES256,
+}
+
+/// Verifier of JWT signatures used by the [`JwtValidator`].
+///
+/// Built-in verifiers are available for all [`JwtAlgorithm`]s,
+/// see [`JwtValidator::hs256`], [`JwtValidator::rs256`], [`JwtValidator::es256`]
+/// and [`JwtValidator::jwk`]. Implement this trait to bring your own key material,
+/// e.g. to support key rotation.
+pub trait JwtVerifier: Send + Sync + 'static {
+    /// Verify that the signature is valid for the given message
+    /// (the encoded header and payload), algorithm and key id
+    /// (the `kid` header parameter, if any).
+    fn verify(
+        &self,
+        algorithm: JwtAlgorithm,
+        key_id: Option<&str>,
+        message: &[u8],
+        signature: &[u8],
+    ) -> Result<(), OpaqueError>;
+}
+
+struct HmacSha256Verifier(HmacKey);
+
+impl JwtVerifier for HmacSha256Verifier {
+    fn verify(
+        &self,
+        algorithm: JwtAlgorithm,
+        _key_id: Option<&str>,
+        message: &[u8],
+        signature: &[u8],
+    ) -> Result<(), OpaqueError> {
+        if algorithm != JwtAlgorithm::HS256 {
+            return Err(OpaqueError::from_display(
+                "hmac secret can only verify HS256 signatures",
+            ));
+        }
+        if self.0.verify(&[message], signature) {
+            Ok(())
+        } else {
+            Err(OpaqueError::from_display("invalid hmac signature"))
+        }
+    }
+}
+
+/// [`JwtVerifier`] verifying signatures of a single asymmetric [`JwtAlgorithm`]
+/// using the public key of the signer.
+struct PublicKeyVerifier {
+    algorithm: JwtAlgorithm,
+    key: UnparsedPublicKey<Vec<u8>>,
+}
+
+impl JwtVerifier for PublicKeyVerifier {
+    fn verify(
+        &self,
+        algorithm: JwtAlgorithm,
+        _key_id: Option<&str>,
+        message: &[u8],
+        signature: &[u8],
+    ) -> Result<(), OpaqueError> {
+        if algorithm != self.algorithm {
+            return Err(OpaqueError::from_display(format!(
+                "public key can only verify {:?} signatures",
+                self.algorithm
+            )));
+        }
+        self.key
+            .verify(message, signature)
+            .context("verify public key signature")
+    }
+}
+
+impl TryFrom<JWA> for JwtAlgorithm {
+    type Error = OpaqueError;
+
+    fn try_from(value: JWA) -> Result<Self, Self::Error> {
+        match value {
+            JWA::HS256 => Ok(Self::HS256),
+            JWA::RS256 => Ok(Self::RS256),
+            JWA::ES256 => Ok(Self::ES256),
+            alg => Err(OpaqueError::from_display(format!(
+                "jwt algorithm {alg:?} is not supported"
+            ))),
+        }
+    }
+}
+
+#[derive(Clone)]
+/// Validator used by the [`JwtClaims`] extractor,
+/// expected to be found in the [`Context`].
+///
+/// Besides the signature it also validates the `exp` and `nbf` claims
+/// in case they are present, as well as the `aud` and `iss` claims
+/// in case an audience or issuer is configured.
+pub struct JwtValidator {
+    verifier: Arc<dyn JwtVerifier>,
+    algorithms: Vec<JwtAlgorithm>,
+    leeway: Duration,
+    audience: Option<String>,
+    issuer: Option<String>,
+}
+
+impl fmt::Debug for JwtValidator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("JwtValidator")
+            .field("algorithms", &self.algorithms)
+            .field("leeway", &self.leeway)
+            .field("audience", &self.audience)
+            .field("issuer", &self.issuer)
+            .finish()
+    }
+}
+
+#[derive(Deserialize)]
+struct JwtHeader {
+    alg: JwtAlgorithm,
+    kid: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct JwtRegisteredClaims {
+    exp: Option<u64>,
+    nbf: Option<u64>,
+    iss: Option<String>,
+    aud: Option<JwtAudience>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum JwtAudience {
+    Single(String),
+    Multiple(Vec<String>),
+}
+
+impl JwtAudience {
+    fn contains(&self, audience: &str) -> bool {
+        match self {
+            Self::Single(aud) => aud == audience,
+            Self::Multiple(auds) => auds.iter().any(|aud| aud == audience),
+        }
+    }
+}
+
+impl JwtValidator {
+    /// Create a new [`JwtValidator`] which accepts HS256 signed tokens
+    /// using the given shared secret.
+    pub fn hs256(secret: impl AsRef<[u8]>) -> Self {
+        Self::new(
+            HmacSha256Verifier(HmacKey::sha256(secret.as_ref())),
+            [JwtAlgorithm::HS256],
+        )
+    }
+
+    /// Create a new [`JwtValidator`] which accepts RS256 signed tokens
+    /// using the given DER encoded RSA public key,
+    /// either as a `RSAPublicKey` (PKCS#1) or `SubjectPublicKeyInfo` structure.
+    pub fn rs256(public_key_der: impl AsRef<[u8]>) -> Result<Self, OpaqueError> {
+        let public_key =
+            RsaPublicKey::from_der(public_key_der.as_ref()).context("parse rsa public key")?;
+        Ok(Self::new(
+            PublicKeyVerifier {
+                algorithm: JwtAlgorithm::RS256,
+                key: UnparsedPublicKey::new(
+                    &RSA_PKCS1_2048_8192_SHA256,
+                    public_key.as_ref().to_vec(),
+                ),
+            },
+            [JwtAlgorithm::RS256],
+        ))
+    }
+
+    /// Create a new [`JwtValidator`] which accepts ES256 signed tokens
+    /// using the given P-256 public key, either as an uncompressed point
+    /// or DER encoded `SubjectPublicKeyInfo` structure.
+    ///
+    /// The key is only parsed when verifying a signature,
+    /// at which point an invalid key results in an invalid jwt.
+    pub fn es256(public_key: impl Into<Vec<u8>>) -> Self {
+        Self::new(
+            PublicKeyVerifier {
+                algorithm: JwtAlgorithm::ES256,
+                key: UnparsedPublicKey::new(&ECDSA_P256_SHA256_FIXED, public_key.into()),
+            },
+            [JwtAlgorithm::ES256],
+        )
+    }
+
+    /// Create a new [`JwtValidator`] which accepts tokens signed
+    /// with the algorithm and key material of the given [`JWK`].
+    pub fn jwk(jwk: &JWK) -> Result<Self, OpaqueError> {
+        let algorithm = JwtAlgorithm::try_from(jwk.alg())?;
+        if algorithm == JwtAlgorithm::HS256 {
+            return Err(OpaqueError::from_display(
+                "symmetric jwk is not supported, use JwtValidator::hs256 instead",
+            ));
+        }
+        let key = jwk.unparsed_public_key().context("jwk as public key")?;
+        Ok(Self::new(PublicKeyVerifier { algorithm, key }, [algorithm]))
+    }
+
+    /// Create a new [`JwtValidator`] using the given [`JwtVerifier`],
+    /// accepting only tokens signed with one of the given algorithms.
+    pub fn new(
+        verifier: impl JwtVerifier,
+        algorithms: impl IntoIterator<Item = JwtAlgorithm>,
+    ) -> Self {
+        Self {
+            verifier: Arc::new(verifier),
+            algorithms: algorithms.into_iter().collect(),
+            leeway: Duration::from_secs(60),
+            audience: None,
+            issuer: None,
+        }
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Set the leeway used to validate the `exp` and `nbf` claims.
+        ///
+        /// Defaults to 60 seconds.
+        pub fn leeway(mut self, leeway: Duration) -> Self {
+            self.leeway = leeway;
+            self
+        }
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Only accept tokens of which the `aud` claim contains the given audience.
+        pub fn audience(mut self, audience: impl Into<String>) -> Self {
+            self.audience = Some(audience.into());
+            self
+        }
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Only accept tokens of which the `iss` claim equals the given issuer.
+        pub fn issuer(mut self, issuer: impl Into<String>) -> Self {
+            self.issuer = Some(issuer.into());
+            self
+        }
+    }
+
+    /// Decode and validate the given JWT,
+    /// returning its claims deserialized as `T`.
+    pub fn validate<T: DeserializeOwned>(&self, token: &str) -> Result<T, OpaqueError> {
+        let Some((message, signature)) = token.rsplit_once('.') else {
+            return Err(OpaqueError::from_display(
+                "jwt is expected to consist out of three parts",
+            ));
+        };
+        let Some((header, payload)) = message.split_once('.') else {
+            return Err(OpaqueError::from_display(
+                "jwt is expected to consist out of three parts",
+            ));
+        };
+
+        let header: JwtHeader = serde_json::from_slice(
+            &URL_SAFE_NO_PAD
+                .decode(header)
+                .context("base64 decode jwt header")?,
+        )
+        .context("deserialize jwt header")?;
+        if !self.algorithms.contains(&header.alg) {
+            return Err(OpaqueError::from_display(format!(
+                "jwt algorithm {:?} is not supported",
+                header.alg
+            )));
+        }
+
+        let signature = URL_SAFE_NO_PAD
+            .decode(signature)
+            .context("base64 decode jwt signature")?;
+        self.verifier
+            .verify(
+                header.alg,
+                header.kid.as_deref(),
+                message.as_bytes(),
+                &signature,
+            )
+            .context("verify jwt signature")?;
+
+        let payload = URL_SAFE_NO_PAD
+            .decode(payload)
+            .context("base64 decode jwt payload")?;
+
+        let registered: JwtRegisteredClaims =
+            serde_json::from_slice(&payload).context("deserialize jwt registered claims")?;
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        let leeway = self.leeway.as_secs();
+        if let Some(exp) = registered.exp
+            && exp.saturating_add(leeway) <= now
+        {
+            return Err(OpaqueError::from_display("jwt has expired"));
+        }
+        if let Some(nbf) = registered.nbf
+            && nbf > now.saturating_add(leeway)
+        {
+            return Err(OpaqueError::from_display("jwt is not yet valid"));
+        }
+        if let Some(audience) = &self.audience
+            && !registered
+                .aud
+                .as_ref()
+                .is_some_and(|aud| aud.contains(audience))
+        {
+            return Err(OpaqueError::from_display("jwt audience is not accepted"));
+        }
+        if let Some(issuer) = &self.issuer
+            && registered.iss.as_ref() != Some(issuer)
+        {
+            return Err(OpaqueError::from_display("jwt issuer is not accepted"));
+        }
+
+        serde_json::from_slice(&payload).context("deserialize jwt claims")
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for JwtClaims<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("JwtClaims").field(&self.0).finish()
+    }
+}
+
+impl<T: Clone> Clone for JwtClaims<T> {
+    fn clone(&self) -> Self {
+        Self(self.0.clone())
+    }
+}
+
+impl<S, T> FromRequestContextRefPair<S> for JwtClaims<T>
+where
+    S: Clone + Send + Sync + 'static,
+    T: DeserializeOwned + Send + Sync + 'static,
+{
+    type Rejection = JwtClaimsRejection;
+
+    async fn from_request_context_ref_pair(
+        ctx: &Context<S>,
+        parts: &Parts,
+    ) -> Result<Self, Self::Rejection> {
+        let BearerToken(bearer) = BearerToken::from_request_context_ref_pair(ctx, parts).await?;
+        let validator = ctx.get::<JwtValidator>().ok_or(MissingJwtValidator)?;
+        let claims = validator.validate(bearer.token()).map_err(InvalidJwt)?;
+        Ok(Self(claims))
+    }
+}
+
+impl<T> Deref for JwtClaims<T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<T> DerefMut for JwtClaims<T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    use crate::dep::http_body_util::BodyExt as _;
+    use crate::service::web::WebService;
+    use crate::{Body, Request, StatusCode, header::AUTHORIZATION};
+    use rama_core::Service;
+
+    pub(crate) const SECRET: &[u8] = b"rama-secret";
+
+    pub(crate) fn signed_token(
+        header: &str,
+        claims: &str,
+        sign: impl FnOnce(&[u8]) -> Vec<u8>,
+    ) -> String {
+        let message = format!(
+            "{}.{}",
+            URL_SAFE_NO_PAD.encode(header),
+            URL_SAFE_NO_PAD.encode(claims)
+        );
+        let signature = URL_SAFE_NO_PAD.encode(sign(message.as_bytes()));
+        format!("{message}.{signature}")
+    }
+
+    pub(crate) fn hs256_token(header: &str, claims: &str) -> String {
+        signed_token(header, claims, |message| {
+            HmacKey::sha256(SECRET).sign(&[message]).as_ref().to_vec()
+        })
+    }
+
+    #[derive(Debug, Deserialize)]
+    struct Claims {
+        sub: String,
+    }
+
+    #[test]
+    fn validate_hs256() {
+        let validator = JwtValidator::hs256(SECRET);
+
+        let token = hs256_token(r#"{"alg":"HS256","typ":"JWT"}"#, r#"{"sub":"john"}"#);
+        let claims: Claims = validator.validate(&token).unwrap();
+        assert_eq!(claims.sub, "john");
+
+        let token = hs256_token(
+            r#"{"alg":"HS256","typ":"JWT"}"#,
+            r#"{"sub":"john","exp":1}"#,
+        );
+        assert!(validator.validate::<Claims>(&token).is_err());
+
+        let token = hs256_token(
+            r#"{"alg":"HS256","typ":"JWT"}"#,
+            r#"{"sub":"john","nbf":99999999999}"#,
+        );
+        assert!(validator.validate::<Claims>(&token).is_err());
+
+        let token = hs256_token(r#"{"alg":"RS256","typ":"JWT"}"#, r#"{"sub":"john"}"#);
+        assert!(validator.validate::<Claims>(&token).is_err());
+
+        let mut token = hs256_token(r#"{"alg":"HS256","typ":"JWT"}"#, r#"{"sub":"john"}"#);
+        token.pop();
+        assert!(validator.validate::<Claims>(&token).is_err());
+
+        assert!(validator.validate::<Claims>("a.b").is_err());
+    }
+
+    #[test]
+    fn validate_rs256() {
+        use rama_crypto::dep::aws_lc_rs::encoding::AsDer as _;
+        use rama_crypto::dep::aws_lc_rs::rand::SystemRandom;
+        use rama_crypto::dep::aws_lc_rs::rsa::{KeyPair, KeySize};
+        use rama_crypto::dep::aws_lc_rs::signature::{KeyPair as _, RSA_PKCS1_SHA256};
+
+        let key_pair = KeyPair::generate(KeySize::Rsa2048).unwrap();
+        let sign = |message: &[u8]| {
+            let mut signature = vec![0; key_pair.public_modulus_len()];
+            key_pair
+                .sign(
+                    &RSA_PKCS1_SHA256,
+                    &SystemRandom::new(),
+                    message,
+                    &mut signature,
+                )
+                .unwrap();
+            signature
+        };
+
+        for der in [
+            key_pair.public_key().as_ref().to_vec(),
+            key_pair.public_key().as_der().unwrap().as_ref().to_vec(),
+        ] {
+            let validator = JwtValidator::rs256(der).unwrap();
+
+            let token = signed_token(r#"{"alg":"RS256"}"#, r#"{"sub":"john"}"#, sign);
+            let claims: Claims = validator.validate(&token).unwrap();
+            assert_eq!(claims.sub, "john");
+
+            let token = hs256_token(r#"{"alg":"HS256"}"#, r#"{"sub":"john"}"#);
+            assert!(validator.validate::<Claims>(&token).is_err());
+
+            let token = signed_token(r#"{"alg":"ES256"}"#, r#"{"sub":"john"}"#, sign);
+            assert!(validator.validate::<Claims>(&token).is_err());
+        }
+
+        assert!(JwtValidator::rs256(b"not a key").is_err());
+    }
+
+    #[test]
+    fn validate_es256() {
+        use rama_crypto::dep::aws_lc_rs::signature::{
+            ECDSA_P256_SHA256_FIXED_SIGNING, EcdsaKeyPair, KeyPair as _,
+        };
+        use rama_crypto::jose::EcdsaKey;
+
+        let (_, pkcs8) = EcdsaKey::generate().unwrap().pkcs8_der().unwrap();
+        let key_pair =
+            EcdsaKeyPair::from_pkcs8(&ECDSA_P256_SHA256_FIXED_SIGNING, pkcs8.as_ref()).unwrap();
+        let rng = rama_crypto::dep::aws_lc_rs::rand::SystemRandom::new();
+        let sign = |message: &[u8]| key_pair.sign(&rng, message).unwrap().as_ref().to_vec();
+
+        let validator = JwtValidator::es256(key_pair.public_key().as_ref());
+        let token = signed_token(r#"{"alg":"ES256"}"#, r#"{"sub":"john"}"#, sign);
+        let claims: Claims = validator.validate(&token).unwrap();
+        assert_eq!(claims.sub, "john");
+
+        let mut tampered = token.clone();
+        tampered.insert_str(tampered.find('.').unwrap() + 1, "e30");
+        assert!(validator.validate::<Claims>(&tampered).is_err());
+
+        let other = EcdsaKey::generate().unwrap().create_jwk();
+        let validator = JwtValidator::jwk(&other).unwrap();
+        assert!(validator.validate::<Claims>(&token).is_err());
+    }
+
+    #[test]
+    fn validate_jwk() {
+        use rama_crypto::dep::aws_lc_rs::signature::{
+            ECDSA_P256_SHA256_FIXED_SIGNING, EcdsaKeyPair,
+        };
+        use rama_crypto::jose::EcdsaKey;
+
+        let key = EcdsaKey::generate().unwrap();
+        let (_, pkcs8) = key.pkcs8_der().unwrap();
+        let key_pair =
+            EcdsaKeyPair::from_pkcs8(&ECDSA_P256_SHA256_FIXED_SIGNING, pkcs8.as_ref()).unwrap();
+        let token = signed_token(r#"{"alg":"ES256"}"#, r#"{"sub":"john"}"#, |message| {
+            key_pair.sign(key.rng(), message).unwrap().as_ref().to_vec()
+        });
+
+        let validator = JwtValidator::jwk(&key.create_jwk()).unwrap();
+        let claims: Claims = validator.validate(&token).unwrap();
+        assert_eq!(claims.sub, "john");
+    }
+
+    #[test]
+    fn validate_audience_and_issuer() {
+        let validator = JwtValidator::hs256(SECRET)
+            .with_audience("rama")
+            .with_issuer("https://auth.example.com");
+
+        let header = r#"{"alg":"HS256","typ":"JWT"}"#;
+
+        let token = hs256_token(
+            header,
+            r#"{"sub":"john","aud":"rama","iss":"https://auth.example.com"}"#,
+        );
+        assert!(validator.validate::<Claims>(&token).is_ok());
+
+        let token = hs256_token(
+            header,
+            r#"{"sub":"john","aud":["other","rama"],"iss":"https://auth.example.com"}"#,
+        );
+        assert!(validator.validate::<Claims>(&token).is_ok());
+
+        let token = hs256_token(
+            header,
+            r#"{"sub":"john","aud":"other","iss":"https://auth.example.com"}"#,
+        );
+        assert!(validator.validate::<Claims>(&token).is_err());
+
+        let token = hs256_token(header, r#"{"sub":"john","iss":"https://auth.example.com"}"#);
+        assert!(validator.validate::<Claims>(&token).is_err());
+
+        let token = hs256_token(
+            header,
+            r#"{"sub":"john","aud":"rama","iss":"https://evil.example.com"}"#,
+        );
+        assert!(validator.validate::<Claims>(&token).is_err());
+    }
+
+    #[tokio::test]
+    async fn jwt_claims_extractor() {
+        let svc =
+            WebService::default().get("/", async |JwtClaims(claims): JwtClaims<Claims>| claims.sub);
+
+        let token = hs256_token(r#"{"alg":"HS256"}"#, r#"{"sub":"john"}"#);
+
+        let mut ctx = Context::default();
+        ctx.insert(JwtValidator::hs256(SECRET));
+
+        let req = Request::builder()
+            .uri("/")
+            .header(AUTHORIZATION, format!("Bearer {token}"))
+            .body(Body::empty())
+            .unwrap();
+        let res = svc.serve(ctx.clone(), req).await.unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+        let body = res.into_body().collect().await.unwrap().to_bytes();
+        assert_eq!(body, "john");
+
+        let req = Request::builder()
+            .uri("/")
+            .header(AUTHORIZATION, "Bearer foo.bar.baz")
+            .body(Body::empty())
+            .unwrap();
+        let res = svc.serve(ctx.clone(), req).await.unwrap();
+        assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+
+        let req = Request::builder().uri("/").body(Body::empty()).unwrap();
+        let res = svc.serve(ctx, req).await.unwrap();
+        assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+
+        let req = Request::builder()
+            .uri("/")
+            .header(AUTHORIZATION, format!("Bearer {token}"))
+            .body(Body::empty())
+            .unwrap();
+        let res = svc.serve(Context::default(), req).await.unwrap();
+        assert_eq!(res.status(), StatusCode::INTERNAL_SERVER_ERROR);
+    }
+}
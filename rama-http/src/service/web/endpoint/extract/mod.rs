@@ -8,17 +8,50 @@ pub mod host;
 #[doc(inline)]
 pub use host::Host;
 
+pub mod accept_language;
+#[doc(inline)]
+pub use accept_language::{AcceptLanguage, LanguageConfig, LanguageTag, NegotiatedLanguage};
+
 pub mod authority;
 #[doc(inline)]
 pub use authority::Authority;
 
+pub mod basic_auth;
+#[doc(inline)]
+pub use basic_auth::BasicAuth;
+
+pub mod bearer;
+#[doc(inline)]
+pub use bearer::BearerToken;
+
+pub mod client_ip;
+#[doc(inline)]
+pub use client_ip::ClientIp;
+
+pub mod cookie;
+#[cfg(feature = "crypto")]
+#[doc(inline)]
+pub use cookie::SignedCookies;
+#[doc(inline)]
+pub use cookie::{CookieValue, Cookies};
+
+#[cfg(feature = "crypto")]
+pub mod jwt;
+#[cfg(feature = "crypto")]
+#[doc(inline)]
+pub use jwt::{JwtClaims, JwtValidator};
+
 pub mod path;
 #[doc(inline)]
 pub use path::Path;
 
 pub mod query;
 #[doc(inline)]
-pub use query::Query;
+pub use query::{Query, RawQuery};
+
+pub mod request_id;
+#[doc(inline)]
+pub use request_id::RequestId;
 
 mod method;
 mod request;
@@ -29,7 +62,7 @@ pub use typed_header::TypedHeader;
 
 pub mod body;
 #[doc(inline)]
-pub use body::{Body, Bytes, Csv, Form, Json, Text};
+pub use body::{Body, Bytes, Csv, Form, Json, Multipart, ReadBody, RelaxedForm, Text};
 
 pub mod datastar;
 
@@ -74,7 +107,10 @@ pub trait FromRequest: Sized + Send + 'static {
     ) -> impl Future<Output = Result<Self, Self::Rejection>> + Send;
 }
 
-fn has_any_content_type(headers: &HeaderMap, expected_content_types: &[&mime::Mime]) -> bool {
+pub(crate) fn has_any_content_type(
+    headers: &HeaderMap,
+    expected_content_types: &[&mime::Mime],
+) -> bool {
     let content_type = if let Some(content_type) = headers.get(header::CONTENT_TYPE) {
         content_type
     } else {
@@ -80,6 +80,7 @@ impl<T> DerefMut for Path<T> {
 mod tests {
     use super::*;
 
+    use crate::dep::http_body_util::BodyExt as _;
     use crate::service::web::WebService;
     use crate::{Body, Request, StatusCode};
     use rama_core::Service;
@@ -107,4 +108,41 @@ mod tests {
         let resp = svc.serve(Context::default(), req).await.unwrap();
         assert_eq!(resp.status(), StatusCode::OK);
     }
+
+    #[tokio::test]
+    async fn test_path_single_capture() {
+        let svc = WebService::default().get("/users/:id", async |Path(id): Path<u64>| {
+            format!("user {id}")
+        });
+
+        let req = Request::get("http://example.com/users/42")
+            .body(Body::empty())
+            .unwrap();
+        let resp = svc.serve(Context::default(), req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let body = resp.into_body().collect().await.unwrap().to_bytes();
+        assert_eq!(body, "user 42");
+
+        let req = Request::get("http://example.com/users/foo")
+            .body(Body::empty())
+            .unwrap();
+        let resp = svc.serve(Context::default(), req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_path_multiple_captures() {
+        let svc = WebService::default().get(
+            "/users/:id/posts/:slug",
+            async |Path((id, slug)): Path<(u64, String)>| format!("{id}:{slug}"),
+        );
+
+        let req = Request::get("http://example.com/users/7/posts/hello-world")
+            .body(Body::empty())
+            .unwrap();
+        let resp = svc.serve(Context::default(), req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let body = resp.into_body().collect().await.unwrap().to_bytes();
+        assert_eq!(body, "7:hello-world");
+    }
 }
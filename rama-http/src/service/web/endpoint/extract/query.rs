@@ -4,6 +4,7 @@ use super::{FromRequestContextRefPair, OptionalFromRequestContextRefPair};
 use crate::dep::http::request::Parts;
 use crate::utils::macros::define_http_rejection;
 use rama_core::Context;
+use rama_utils::macros::impl_deref;
 use serde::de::DeserializeOwned;
 
 /// Extractor that deserializes query strings into some type.
@@ -19,6 +20,23 @@ define_http_rejection! {
     pub struct FailedToDeserializeQueryString(Error);
 }
 
+/// Extractor that gives access to the raw query string of the request,
+/// without any deserialization applied.
+///
+/// Use [`Query`] in case you want it deserialized into some type.
+#[derive(Debug, Clone)]
+pub struct RawQuery(pub String);
+
+impl_deref!(RawQuery: String);
+
+define_http_rejection! {
+    #[status = BAD_REQUEST]
+    #[body = "Missing query string"]
+    /// Rejection type used if the [`RawQuery`] extractor is used
+    /// for a request without a query string.
+    pub struct MissingQueryString;
+}
+
 impl<T: std::fmt::Debug> std::fmt::Debug for Query<T> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_tuple("Query").field(&self.0).finish()
@@ -82,3 +100,74 @@ where
         }
     }
 }
+
+impl<S> FromRequestContextRefPair<S> for RawQuery
+where
+    S: Clone + Send + Sync + 'static,
+{
+    type Rejection = MissingQueryString;
+
+    async fn from_request_context_ref_pair(
+        _ctx: &Context<S>,
+        parts: &Parts,
+    ) -> Result<Self, Self::Rejection> {
+        parts
+            .uri
+            .query()
+            .map(|query| Self(query.to_owned()))
+            .ok_or(MissingQueryString)
+    }
+}
+
+impl<S> OptionalFromRequestContextRefPair<S> for RawQuery
+where
+    S: Clone + Send + Sync + 'static,
+{
+    type Rejection = MissingQueryString;
+
+    async fn from_request_context_ref_pair(
+        _ctx: &Context<S>,
+        parts: &Parts,
+    ) -> Result<Option<Self>, Self::Rejection> {
+        Ok(parts.uri.query().map(|query| Self(query.to_owned())))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::dep::http_body_util::BodyExt as _;
+    use crate::service::web::WebService;
+    use crate::{Body, Request, StatusCode};
+    use rama_core::Service;
+
+    #[tokio::test]
+    async fn raw_query() {
+        let svc = WebService::default()
+            .get("/", async |RawQuery(query): RawQuery| query)
+            .get("/optional", async |query: Option<RawQuery>| {
+                query.map(|RawQuery(query)| query).unwrap_or_default()
+            });
+
+        let req = Request::builder()
+            .uri("/?a=1&b=%20")
+            .body(Body::empty())
+            .unwrap();
+        let res = svc.serve(Context::default(), req).await.unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+        let body = res.into_body().collect().await.unwrap().to_bytes();
+        assert_eq!(body, "a=1&b=%20");
+
+        let req = Request::builder().uri("/").body(Body::empty()).unwrap();
+        let res = svc.serve(Context::default(), req).await.unwrap();
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+
+        let req = Request::builder()
+            .uri("/optional")
+            .body(Body::empty())
+            .unwrap();
+        let res = svc.serve(Context::default(), req).await.unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+    }
+}
@@ -0,0 +1,116 @@
+//! Module in function of the [`RequestId`] extractor.
+
+use super::{FromRequestContextRefPair, OptionalFromRequestContextRefPair};
+use crate::dep::http::request::Parts;
+use crate::layer::request_id::{REQUEST_ID, X_REQUEST_ID};
+use crate::utils::macros::define_http_rejection;
+use rama_core::Context;
+
+#[doc(inline)]
+pub use crate::layer::request_id::RequestId;
+
+define_http_rejection! {
+    #[status = BAD_REQUEST]
+    #[body = "Missing request id"]
+    /// Rejection type used if the [`RequestId`] extractor is unable to
+    /// find a request id for the request.
+    pub struct MissingRequestId;
+}
+
+/// Find the [`RequestId`], checking the following sources in order:
+///
+/// 1. the request extensions, as set by [`SetRequestId`];
+/// 2. the [`Context`];
+/// 3. the `x-request-id` header;
+/// 4. the `request-id` header.
+///
+/// [`SetRequestId`]: crate::layer::request_id::SetRequestId
+fn find_request_id<S>(ctx: &Context<S>, parts: &Parts) -> Option<RequestId> {
+    parts
+        .extensions
+        .get::<RequestId>()
+        .or_else(|| ctx.get::<RequestId>())
+        .cloned()
+        .or_else(|| {
+            parts
+                .headers
+                .get(X_REQUEST_ID)
+                .or_else(|| parts.headers.get(REQUEST_ID))
+                .cloned()
+                .map(RequestId::new)
+        })
+}
+
+impl<S> FromRequestContextRefPair<S> for RequestId
+where
+    S: Clone + Send + Sync + 'static,
+{
+    type Rejection = MissingRequestId;
+
+    async fn from_request_context_ref_pair(
+        ctx: &Context<S>,
+        parts: &Parts,
+    ) -> Result<Self, Self::Rejection> {
+        find_request_id(ctx, parts).ok_or(MissingRequestId)
+    }
+}
+
+impl<S> OptionalFromRequestContextRefPair<S> for RequestId
+where
+    S: Clone + Send + Sync + 'static,
+{
+    type Rejection = std::convert::Infallible;
+
+    async fn from_request_context_ref_pair(
+        ctx: &Context<S>,
+        parts: &Parts,
+    ) -> Result<Option<Self>, Self::Rejection> {
+        Ok(find_request_id(ctx, parts))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::dep::http_body_util::BodyExt as _;
+    use crate::layer::request_id::{MakeRequestUuid, SetRequestIdLayer};
+    use crate::service::web::WebService;
+    use crate::{Body, Request, StatusCode};
+    use rama_core::{Layer, Service};
+
+    async fn handler(request_id: RequestId) -> String {
+        request_id.header_value().to_str().unwrap().to_owned()
+    }
+
+    #[tokio::test]
+    async fn request_id_from_header() {
+        let svc = WebService::default().get("/", handler);
+
+        let req = Request::builder()
+            .uri("/")
+            .header("x-request-id", "abc")
+            .body(Body::empty())
+            .unwrap();
+        let res = svc.serve(Context::default(), req).await.unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+        let body = res.into_body().collect().await.unwrap().to_bytes();
+        assert_eq!(body, "abc");
+
+        let req = Request::builder().uri("/").body(Body::empty()).unwrap();
+        let res = svc.serve(Context::default(), req).await.unwrap();
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn request_id_from_extensions() {
+        let svc = SetRequestIdLayer::x_request_id(MakeRequestUuid)
+            .into_layer(WebService::default().get("/", handler));
+
+        let req = Request::builder().uri("/").body(Body::empty()).unwrap();
+        let res = svc.serve(Context::default(), req).await.unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+        let body = res.into_body().collect().await.unwrap().to_bytes();
+        assert_eq!(body.len(), 36);
+    }
+}
@@ -12,6 +12,15 @@ pub(crate) struct Endpoint<State> {
     pub(crate) service: BoxService<State, Request, Response, Infallible>,
 }
 
+impl<State> Clone for Endpoint<State> {
+    fn clone(&self) -> Self {
+        Self {
+            matcher: self.matcher.clone(),
+            service: self.service.clone(),
+        }
+    }
+}
+
 /// utility trait to accept multiple types as an endpoint service for [`super::WebService`]
 pub trait IntoEndpointService<State, T>: private::Sealed<T> {
     /// convert the type into a [`rama_core::Service`].
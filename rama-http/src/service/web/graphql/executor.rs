@@ -0,0 +1,180 @@
+use super::{GraphqlError, GraphqlRequest, GraphqlResponse};
+use rama_core::error::BoxError;
+use rama_core::layer::MapErr;
+use rama_core::service::BoxService;
+use rama_core::{Context, Service};
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+
+/// Validates a [`GraphqlRequest`] against a schema, prior to its execution.
+///
+/// Implemented for `()`, accepting all requests,
+/// and for closures taking a reference to the [`GraphqlRequest`].
+pub trait GraphqlSchemaValidator: Send + Sync + 'static {
+    /// Validate the [`GraphqlRequest`], returning the validation errors if any.
+    fn validate(&self, request: &GraphqlRequest) -> Result<(), Vec<GraphqlError>>;
+}
+
+impl GraphqlSchemaValidator for () {
+    fn validate(&self, _request: &GraphqlRequest) -> Result<(), Vec<GraphqlError>> {
+        Ok(())
+    }
+}
+
+impl<F> GraphqlSchemaValidator for F
+where
+    F: Fn(&GraphqlRequest) -> Result<(), Vec<GraphqlError>> + Send + Sync + 'static,
+{
+    fn validate(&self, request: &GraphqlRequest) -> Result<(), Vec<GraphqlError>> {
+        (self)(request)
+    }
+}
+
+/// Executes a validated [`GraphqlRequest`].
+///
+/// Implemented for all [`Service`]s serving [`GraphqlRequest`]s with a [`GraphqlResponse`],
+/// such as the [`GraphqlRouter`].
+pub trait GraphqlExecutor<State>: Send + Sync + 'static {
+    /// Execute the [`GraphqlRequest`].
+    fn execute(
+        &self,
+        ctx: Context<State>,
+        request: GraphqlRequest,
+    ) -> impl Future<Output = Result<GraphqlResponse, BoxError>> + Send + '_;
+}
+
+impl<State, S> GraphqlExecutor<State> for S
+where
+    S: Service<State, GraphqlRequest, Response = GraphqlResponse, Error: Into<BoxError>>,
+    State: Clone + Send + Sync + 'static,
+{
+    async fn execute(
+        &self,
+        ctx: Context<State>,
+        request: GraphqlRequest,
+    ) -> Result<GraphqlResponse, BoxError> {
+        self.serve(ctx, request).await.map_err(Into::into)
+    }
+}
+
+type ResolverService<State> = BoxService<State, GraphqlRequest, GraphqlResponse, BoxError>;
+
+/// A [`GraphqlExecutor`] dispatching requests to the resolver [`Service`]
+/// registered for the name of the requested operation.
+///
+/// Requests for which no resolver is registered are served by the fallback resolver,
+/// or responded with a [`GraphqlError`] in case there is none.
+pub struct GraphqlRouter<State> {
+    resolvers: Arc<HashMap<String, ResolverService<State>>>,
+    fallback: Option<ResolverService<State>>,
+}
+
+impl<State> fmt::Debug for GraphqlRouter<State> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("GraphqlRouter")
+            .field("operations", &self.resolvers.keys().collect::<Vec<_>>())
+            .field("fallback", &self.fallback.is_some())
+            .finish()
+    }
+}
+
+impl<State> Clone for GraphqlRouter<State> {
+    fn clone(&self) -> Self {
+        Self {
+            resolvers: self.resolvers.clone(),
+            fallback: self.fallback.clone(),
+        }
+    }
+}
+
+impl<State> Default for GraphqlRouter<State> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<State> GraphqlRouter<State> {
+    /// Create a new [`GraphqlRouter`] without any resolvers.
+    pub fn new() -> Self {
+        Self {
+            resolvers: Arc::new(HashMap::new()),
+            fallback: None,
+        }
+    }
+}
+
+impl<State> GraphqlRouter<State>
+where
+    State: Clone + Send + Sync + 'static,
+{
+    /// Register the resolver [`Service`] for the operation with the given name.
+    pub fn with_operation<S>(mut self, name: impl Into<String>, resolver: S) -> Self
+    where
+        S: Service<State, GraphqlRequest, Response = GraphqlResponse, Error: Into<BoxError>>,
+    {
+        self.set_operation(name, resolver);
+        self
+    }
+
+    /// Register the resolver [`Service`] for the operation with the given name.
+    pub fn set_operation<S>(&mut self, name: impl Into<String>, resolver: S) -> &mut Self
+    where
+        S: Service<State, GraphqlRequest, Response = GraphqlResponse, Error: Into<BoxError>>,
+    {
+        Arc::make_mut(&mut self.resolvers)
+            .insert(name.into(), MapErr::new(resolver, Into::into).boxed());
+        self
+    }
+
+    /// Set the resolver [`Service`] used for operations without a registered resolver,
+    /// including anonymous operations.
+    pub fn with_fallback<S>(mut self, resolver: S) -> Self
+    where
+        S: Service<State, GraphqlRequest, Response = GraphqlResponse, Error: Into<BoxError>>,
+    {
+        self.set_fallback(resolver);
+        self
+    }
+
+    /// Set the resolver [`Service`] used for operations without a registered resolver,
+    /// including anonymous operations.
+    pub fn set_fallback<S>(&mut self, resolver: S) -> &mut Self
+    where
+        S: Service<State, GraphqlRequest, Response = GraphqlResponse, Error: Into<BoxError>>,
+    {
+        self.fallback = Some(MapErr::new(resolver, Into::into).boxed());
+        self
+    }
+}
+
+impl<State> Service<State, GraphqlRequest> for GraphqlRouter<State>
+where
+    State: Clone + Send + Sync + 'static,
+{
+    type Response = GraphqlResponse;
+    type Error = BoxError;
+
+    async fn serve(
+        &self,
+        ctx: Context<State>,
+        request: GraphqlRequest,
+    ) -> Result<Self::Response, Self::Error> {
+        let operation = match request.operation() {
+            Ok(operation) => operation,
+            Err(err) => return Ok(GraphqlResponse::errors([err])),
+        };
+        let resolver = operation
+            .name
+            .as_deref()
+            .and_then(|name| self.resolvers.get(name))
+            .or(self.fallback.as_ref());
+        match resolver {
+            Some(resolver) => resolver.serve(ctx, request).await,
+            None => Ok(GraphqlResponse::errors([GraphqlError::new(format!(
+                "no resolver found for operation '{}'",
+                operation.name.as_deref().unwrap_or("<anonymous>"),
+            ))])),
+        }
+    }
+}
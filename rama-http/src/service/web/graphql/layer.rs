@@ -0,0 +1,396 @@
+use super::{
+    GraphqlError, GraphqlExecutor, GraphqlOperationType, GraphqlRequest, GraphqlResponse,
+    GraphqlSchemaValidator,
+};
+use crate::service::web::response::{IntoResponse, Json};
+use crate::{Method, Request, Response, StatusCode, header};
+use rama_core::telemetry::tracing;
+use rama_core::{Context, Layer, Service};
+use rama_utils::macros::define_inner_service_accessors;
+use std::borrow::Cow;
+use std::fmt;
+use std::sync::Arc;
+
+/// A [`Layer`] that produces [`GraphqlService`]s,
+/// serving the GraphQL endpoint in front of the wrapped [`Service`].
+pub struct GraphqlLayer<V, E> {
+    validator: Arc<V>,
+    executor: Arc<E>,
+    path: Cow<'static, str>,
+}
+
+impl<V: fmt::Debug, E: fmt::Debug> fmt::Debug for GraphqlLayer<V, E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("GraphqlLayer")
+            .field("validator", &self.validator)
+            .field("executor", &self.executor)
+            .field("path", &self.path)
+            .finish()
+    }
+}
+
+impl<V, E> Clone for GraphqlLayer<V, E> {
+    fn clone(&self) -> Self {
+        Self {
+            validator: self.validator.clone(),
+            executor: self.executor.clone(),
+            path: self.path.clone(),
+        }
+    }
+}
+
+impl<V, E> GraphqlLayer<V, E> {
+    /// Create a new [`GraphqlLayer`], validating requests using the given schema
+    /// and executing them using the given executor.
+    ///
+    /// The GraphQL endpoint is served on `/graphql` by default.
+    pub fn new(schema: V, executor: E) -> Self {
+        Self {
+            validator: Arc::new(schema),
+            executor: Arc::new(executor),
+            path: Cow::Borrowed("/graphql"),
+        }
+    }
+
+    /// Set the path on which the GraphQL endpoint is served.
+    pub fn with_path(mut self, path: impl Into<Cow<'static, str>>) -> Self {
+        self.path = path.into();
+        self
+    }
+
+    /// Set the path on which the GraphQL endpoint is served.
+    pub fn set_path(&mut self, path: impl Into<Cow<'static, str>>) -> &mut Self {
+        self.path = path.into();
+        self
+    }
+}
+
+impl<S, V, E> Layer<S> for GraphqlLayer<V, E> {
+    type Service = GraphqlService<S, V, E>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        GraphqlService {
+            inner,
+            validator: self.validator.clone(),
+            executor: self.executor.clone(),
+            path: self.path.clone(),
+        }
+    }
+
+    fn into_layer(self, inner: S) -> Self::Service {
+        GraphqlService {
+            inner,
+            validator: self.validator,
+            executor: self.executor,
+            path: self.path,
+        }
+    }
+}
+
+/// A [`Service`] serving GraphQL requests on its configured path,
+/// forwarding all other requests to the inner [`Service`].
+///
+/// GraphQL requests are accepted as:
+///
+/// - `POST` requests with an `application/json` body;
+/// - `POST` requests with a `multipart/form-data` body,
+///   following the [GraphQL multipart request spec] for file uploads;
+/// - `GET` requests with the operation encoded in the query string,
+///   in which case only queries are allowed.
+///
+/// [GraphQL multipart request spec]: https://github.com/jaydenseric/graphql-multipart-request-spec
+pub struct GraphqlService<S, V, E> {
+    inner: S,
+    validator: Arc<V>,
+    executor: Arc<E>,
+    path: Cow<'static, str>,
+}
+
+impl<S, V, E> GraphqlService<S, V, E> {
+    define_inner_service_accessors!();
+}
+
+impl<S: fmt::Debug, V: fmt::Debug, E: fmt::Debug> fmt::Debug for GraphqlService<S, V, E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("GraphqlService")
+            .field("inner", &self.inner)
+            .field("validator", &self.validator)
+            .field("executor", &self.executor)
+            .field("path", &self.path)
+            .finish()
+    }
+}
+
+impl<S: Clone, V, E> Clone for GraphqlService<S, V, E> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            validator: self.validator.clone(),
+            executor: self.executor.clone(),
+            path: self.path.clone(),
+        }
+    }
+}
+
+impl<S, V, E, State> Service<State, Request> for GraphqlService<S, V, E>
+where
+    S: Service<State, Request, Response = Response>,
+    V: GraphqlSchemaValidator,
+    E: GraphqlExecutor<State>,
+    State: Clone + Send + Sync + 'static,
+{
+    type Response = Response;
+    type Error = S::Error;
+
+    async fn serve(
+        &self,
+        ctx: Context<State>,
+        req: Request,
+    ) -> Result<Self::Response, Self::Error> {
+        if req.uri().path() != self.path {
+            return self.inner.serve(ctx, req).await;
+        }
+
+        let is_get = match *req.method() {
+            Method::GET => true,
+            Method::POST => false,
+            _ => {
+                return Ok(error_response(
+                    StatusCode::METHOD_NOT_ALLOWED,
+                    Some("GET, POST"),
+                    GraphqlError::new("GraphQL requests must use the GET or POST method"),
+                ));
+            }
+        };
+
+        let request = match GraphqlRequest::from_http(req).await {
+            Ok(request) => request,
+            Err((status, err)) => return Ok(error_response(status, None, err)),
+        };
+
+        if is_get
+            && let Ok(operation) = request.operation()
+            && operation.kind != GraphqlOperationType::Query
+        {
+            return Ok(error_response(
+                StatusCode::METHOD_NOT_ALLOWED,
+                Some("POST"),
+                GraphqlError::new("only query operations are allowed for GET requests"),
+            ));
+        }
+
+        if let Err(errors) = self.validator.validate(&request) {
+            return Ok(GraphqlResponse::errors(errors).into_response());
+        }
+
+        match self.executor.execute(ctx, request).await {
+            Ok(response) => Ok(response.into_response()),
+            Err(err) => {
+                tracing::error!("failed to execute graphql request: {err}");
+                Ok(error_response(
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    None,
+                    GraphqlError::new("internal server error"),
+                ))
+            }
+        }
+    }
+}
+
+fn error_response(status: StatusCode, allow: Option<&'static str>, err: GraphqlError) -> Response {
+    let body = Json(GraphqlResponse::errors([err]));
+    match allow {
+        Some(allow) => (status, [(header::ALLOW, allow)], body).into_response(),
+        None => (status, body).into_response(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Body;
+    use crate::dep::http_body_util::BodyExt;
+    use crate::service::web::WebService;
+    use crate::service::web::graphql::GraphqlRouter;
+    use rama_core::error::{BoxError, OpaqueError};
+    use rama_core::service::service_fn;
+    use serde_json::{Value, json};
+    use std::convert::Infallible;
+
+    fn service() -> impl Service<(), Request, Response = Response, Error = Infallible> {
+        let router = GraphqlRouter::new()
+            .with_operation(
+                "GetUser",
+                service_fn(async |req: GraphqlRequest| {
+                    Ok::<_, Infallible>(GraphqlResponse::data(
+                        json!({"user": {"id": req.variables["id"]}}),
+                    ))
+                }),
+            )
+            .with_operation(
+                "Upload",
+                service_fn(async |req: GraphqlRequest| {
+                    let upload = &req.uploads[0];
+                    Ok::<_, Infallible>(GraphqlResponse::data(json!({
+                        "path": upload.path,
+                        "name": upload.file_name,
+                        "content": String::from_utf8_lossy(&upload.content),
+                        "variable": req.variables["file"],
+                    })))
+                }),
+            )
+            .with_operation(
+                "Broken",
+                service_fn(async |_req: GraphqlRequest| {
+                    Err::<GraphqlResponse, _>(OpaqueError::from_display("db down"))
+                }),
+            );
+        let validator = |req: &GraphqlRequest| {
+            if req.query.contains("secret") {
+                Err(vec![GraphqlError::new("unknown field 'secret'")])
+            } else {
+                Ok(())
+            }
+        };
+        GraphqlLayer::new(validator, router).into_layer(WebService::default().get("/", "home"))
+    }
+
+    async fn serve(req: Request) -> (StatusCode, Value) {
+        let res = service().serve(Context::default(), req).await.unwrap();
+        let status = res.status();
+        let body = res.into_body().collect().await.unwrap().to_bytes();
+        (status, serde_json::from_slice(&body).unwrap_or(Value::Null))
+    }
+
+    fn json_request(body: Value) -> Request {
+        Request::post("/graphql")
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_graphql_post_json() {
+        let (status, body) = serve(json_request(json!({
+            "query": "query GetUser($id: ID!) { user(id: $id) { id } } mutation Other { x }",
+            "operationName": "GetUser",
+            "variables": {"id": "42"},
+        })))
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body, json!({"data": {"user": {"id": "42"}}}));
+
+        let (status, body) = serve(json_request(json!({"query": "{ user { id } }"}))).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(
+            body,
+            json!({"errors": [{"message": "no resolver found for operation '<anonymous>'"}]})
+        );
+
+        let (status, body) =
+            serve(json_request(json!({"query": "query GetUser { secret }"}))).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(
+            body,
+            json!({"errors": [{"message": "unknown field 'secret'"}]})
+        );
+
+        let (status, body) = serve(json_request(json!({"query": "query Broken { x }"}))).await;
+        assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(
+            body,
+            json!({"errors": [{"message": "internal server error"}]})
+        );
+
+        let (status, _) = serve(json_request(json!({"variables": {}}))).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+
+        let req = Request::post("/graphql")
+            .header(header::CONTENT_TYPE, "text/plain")
+            .body(Body::from("{ user { id } }"))
+            .unwrap();
+        let (status, _) = serve(req).await;
+        assert_eq!(status, StatusCode::UNSUPPORTED_MEDIA_TYPE);
+    }
+
+    #[tokio::test]
+    async fn test_graphql_get() {
+        let req = Request::get(
+            "/graphql?query=query%20GetUser%20%7B%20user%20%7B%20id%20%7D%20%7D&variables=%7B%22id%22%3A%227%22%7D",
+        )
+        .body(Body::empty())
+        .unwrap();
+        let (status, body) = serve(req).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body, json!({"data": {"user": {"id": "7"}}}));
+
+        let req = Request::get("/graphql?query=mutation%20GetUser%20%7B%20x%20%7D")
+            .body(Body::empty())
+            .unwrap();
+        let res = service().serve(Context::default(), req).await.unwrap();
+        assert_eq!(res.status(), StatusCode::METHOD_NOT_ALLOWED);
+        assert_eq!(res.headers()[header::ALLOW], "POST");
+
+        let req = Request::delete("/graphql").body(Body::empty()).unwrap();
+        let res = service().serve(Context::default(), req).await.unwrap();
+        assert_eq!(res.status(), StatusCode::METHOD_NOT_ALLOWED);
+        assert_eq!(res.headers()[header::ALLOW], "GET, POST");
+    }
+
+    #[tokio::test]
+    async fn test_graphql_multipart_upload() {
+        let body = "--X\r\n\
+            Content-Disposition: form-data; name=\"operations\"\r\n\
+            \r\n\
+            {\"query\": \"mutation Upload($file: Upload!) { upload(file: $file) }\", \"variables\": {\"file\": null}}\r\n\
+            --X\r\n\
+            Content-Disposition: form-data; name=\"map\"\r\n\
+            \r\n\
+            {\"0\": [\"variables.file\"]}\r\n\
+            --X\r\n\
+            Content-Disposition: form-data; name=\"0\"; filename=\"a.txt\"\r\n\
+            Content-Type: text/plain\r\n\
+            \r\n\
+            hello\r\n\
+            --X--\r\n";
+        let req = Request::post("/graphql")
+            .header(header::CONTENT_TYPE, "multipart/form-data; boundary=X")
+            .body(Body::from(body))
+            .unwrap();
+        let (status, body) = serve(req).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(
+            body,
+            json!({"data": {"path": "variables.file", "name": "a.txt", "content": "hello", "variable": null}})
+        );
+
+        let req = Request::post("/graphql")
+            .header(header::CONTENT_TYPE, "multipart/form-data; boundary=X")
+            .body(Body::from(
+                "--X\r\nContent-Disposition: form-data; name=\"0\"\r\n\r\nhello\r\n--X--\r\n",
+            ))
+            .unwrap();
+        let (status, _) = serve(req).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_graphql_other_paths() {
+        let req = Request::get("/").body(Body::empty()).unwrap();
+        let res = service().serve(Context::default(), req).await.unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+        let body = res.into_body().collect().await.unwrap().to_bytes();
+        assert_eq!(body, "home");
+
+        let svc = GraphqlLayer::new((), GraphqlRouter::new())
+            .with_path("/api/graphql")
+            .into_layer(service_fn(async |_req: Request| {
+                Ok::<_, BoxError>(StatusCode::NOT_FOUND.into_response())
+            }));
+        let res = svc
+            .serve(Context::default(), json_request(json!({"query": "{ x }"})))
+            .await
+            .unwrap();
+        assert_eq!(res.status(), StatusCode::NOT_FOUND);
+    }
+}
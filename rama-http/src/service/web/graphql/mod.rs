@@ -0,0 +1,61 @@
+//! GraphQL support for web services.
+//!
+//! The [`GraphqlLayer`] serves GraphQL requests on a single path (`/graphql` by default),
+//! validating them using a [`GraphqlSchemaValidator`] and executing them using a [`GraphqlExecutor`],
+//! such as the [`GraphqlRouter`] which dispatches requests to a resolver [`Service`] per operation.
+//!
+//! This module does not implement a GraphQL engine: parsing of the document
+//! is limited to finding its operations, validation and execution are left
+//! to the validator and executor.
+//!
+//! # Example
+//!
+//! ```
+//! use rama_core::{Context, Layer, Service, service::service_fn};
+//! use rama_http::service::web::WebService;
+//! use rama_http::service::web::graphql::{
+//!     GraphqlLayer, GraphqlRequest, GraphqlResponse, GraphqlRouter,
+//! };
+//! use rama_http::{Body, Request, StatusCode, header};
+//! use serde_json::json;
+//! use std::convert::Infallible;
+//!
+//! # #[tokio::main]
+//! # async fn main() {
+//! let router = GraphqlRouter::new().with_operation(
+//!     "GetUser",
+//!     service_fn(async |req: GraphqlRequest| {
+//!         Ok::<_, Infallible>(GraphqlResponse::data(json!({"user": {"id": req.variables["id"]}})))
+//!     }),
+//! );
+//!
+//! let service = GraphqlLayer::new((), router).into_layer(WebService::default());
+//!
+//! let req = Request::post("/graphql")
+//!     .header(header::CONTENT_TYPE, "application/json")
+//!     .body(Body::from(
+//!         r#"{"query": "query GetUser($id: ID!) { user(id: $id) { id } }", "variables": {"id": "1"}}"#,
+//!     ))
+//!     .unwrap();
+//! let res = service.serve(Context::default(), req).await.unwrap();
+//! assert_eq!(res.status(), StatusCode::OK);
+//! # }
+//! ```
+//!
+//! [`Service`]: rama_core::Service
+
+mod request;
+#[doc(inline)]
+pub use request::{GraphqlOperation, GraphqlOperationType, GraphqlRequest, GraphqlUpload};
+
+mod response;
+#[doc(inline)]
+pub use response::{GraphqlError, GraphqlResponse};
+
+mod executor;
+#[doc(inline)]
+pub use executor::{GraphqlExecutor, GraphqlRouter, GraphqlSchemaValidator};
+
+mod layer;
+#[doc(inline)]
+pub use layer::{GraphqlLayer, GraphqlService};
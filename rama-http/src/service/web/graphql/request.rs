@@ -0,0 +1,457 @@
+use super::GraphqlError;
+use crate::service::web::extract::{FromRequest, Multipart};
+use crate::{Method, Request, StatusCode};
+use rama_core::bytes::Bytes;
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// The type of a GraphQL operation.
+pub enum GraphqlOperationType {
+    /// A `query` operation, also used for the `{ ... }` shorthand.
+    Query,
+    /// A `mutation` operation.
+    Mutation,
+    /// A `subscription` operation.
+    Subscription,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// An operation defined in a GraphQL document.
+pub struct GraphqlOperation {
+    /// The type of the operation.
+    pub kind: GraphqlOperationType,
+    /// The name of the operation, `None` for anonymous operations.
+    pub name: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+/// A file uploaded as part of a [GraphQL multipart request].
+///
+/// As required by the spec, the variable it is mapped to is set to `null`.
+///
+/// [GraphQL multipart request]: https://github.com/jaydenseric/graphql-multipart-request-spec
+pub struct GraphqlUpload {
+    /// The object path of the variable the file is mapped to, e.g. `variables.file`.
+    pub path: String,
+    /// The file name of the uploaded file.
+    pub file_name: Option<String>,
+    /// The content type of the uploaded file.
+    pub content_type: Option<mime::Mime>,
+    /// The content of the uploaded file.
+    pub content: Bytes,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+/// A GraphQL request, as received over http.
+pub struct GraphqlRequest {
+    /// The GraphQL document.
+    pub query: String,
+    /// The name of the operation to execute,
+    /// required in case the document defines multiple operations.
+    #[serde(default)]
+    pub operation_name: Option<String>,
+    /// The values of the operation variables.
+    #[serde(default, deserialize_with = "null_as_default")]
+    pub variables: Map<String, Value>,
+    /// Protocol extensions, e.g. used for persisted queries.
+    #[serde(default, deserialize_with = "null_as_default")]
+    pub extensions: Map<String, Value>,
+    /// Files uploaded using a GraphQL multipart request.
+    #[serde(skip)]
+    pub uploads: Vec<GraphqlUpload>,
+}
+
+fn null_as_default<'de, D>(deserializer: D) -> Result<Map<String, Value>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    Ok(Option::deserialize(deserializer)?.unwrap_or_default())
+}
+
+impl GraphqlRequest {
+    /// Create a new [`GraphqlRequest`] for the given document.
+    pub fn new(query: impl Into<String>) -> Self {
+        Self {
+            query: query.into(),
+            operation_name: None,
+            variables: Map::new(),
+            extensions: Map::new(),
+            uploads: Vec::new(),
+        }
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Set the name of the operation to execute.
+        pub fn operation_name(mut self, name: Option<String>) -> Self {
+            self.operation_name = name;
+            self
+        }
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Set the values of the operation variables.
+        pub fn variables(mut self, variables: Map<String, Value>) -> Self {
+            self.variables = variables;
+            self
+        }
+    }
+
+    /// Return all operations defined in the document.
+    ///
+    /// This is a lightweight scan of the document, which does not validate it.
+    pub fn operations(&self) -> Vec<GraphqlOperation> {
+        scan_operations(&self.query)
+    }
+
+    /// Return the operation selected for execution: the operation matching the
+    /// [`operation_name`] or, if none is given, the only operation in the document.
+    ///
+    /// [`operation_name`]: Self::operation_name
+    pub fn operation(&self) -> Result<GraphqlOperation, GraphqlError> {
+        let mut operations = self.operations();
+        match self.operation_name.as_deref() {
+            Some(name) => operations
+                .into_iter()
+                .find(|op| op.name.as_deref() == Some(name))
+                .ok_or_else(|| GraphqlError::new(format!("unknown operation named '{name}'"))),
+            None if operations.len() == 1 => Ok(operations.remove(0)),
+            None if operations.is_empty() => {
+                Err(GraphqlError::new("document does not contain any operation"))
+            }
+            None => Err(GraphqlError::new(
+                "operation name is required for documents with multiple operations",
+            )),
+        }
+    }
+
+    /// Parse a [`GraphqlRequest`] from an http [`Request`].
+    pub(super) async fn from_http(req: Request) -> Result<Self, (StatusCode, GraphqlError)> {
+        let bad_request = |msg: String| (StatusCode::BAD_REQUEST, GraphqlError::new(msg));
+
+        if req.method() == Method::GET {
+            return Self::from_query(req.uri().query().unwrap_or_default()).map_err(bad_request);
+        }
+
+        if crate::service::web::extract::has_any_content_type(
+            req.headers(),
+            &[&mime::MULTIPART_FORM_DATA],
+        ) {
+            let multipart = Multipart::from_request(req)
+                .await
+                .map_err(|err| bad_request(err.to_string()))?;
+            return Self::from_multipart(multipart).await.map_err(bad_request);
+        }
+
+        if !crate::service::web::extract::has_any_content_type(
+            req.headers(),
+            &[&mime::APPLICATION_JSON],
+        ) {
+            return Err((
+                StatusCode::UNSUPPORTED_MEDIA_TYPE,
+                GraphqlError::new(
+                    "GraphQL requests must have `Content-Type: application/json` or `multipart/form-data`",
+                ),
+            ));
+        }
+        let body = crate::dep::http_body_util::BodyExt::collect(req.into_body())
+            .await
+            .map_err(|err| bad_request(format!("failed to read body: {err}")))?
+            .to_bytes();
+        serde_json::from_slice(&body).map_err(|err| bad_request(format!("invalid body: {err}")))
+    }
+
+    fn from_query(query: &str) -> Result<Self, String> {
+        #[derive(Deserialize)]
+        #[serde(rename_all = "camelCase")]
+        struct Params {
+            query: String,
+            operation_name: Option<String>,
+            variables: Option<String>,
+            extensions: Option<String>,
+        }
+
+        let params: Params =
+            serde_html_form::from_str(query).map_err(|err| format!("invalid query: {err}"))?;
+        let json_map = |value: Option<String>, name: &str| {
+            value
+                .filter(|value| !value.is_empty())
+                .map(|value| serde_json::from_str::<Option<Map<String, Value>>>(&value))
+                .transpose()
+                .map(|value| value.flatten().unwrap_or_default())
+                .map_err(|err| format!("invalid {name}: {err}"))
+        };
+        Ok(Self {
+            query: params.query,
+            operation_name: params.operation_name.filter(|name| !name.is_empty()),
+            variables: json_map(params.variables, "variables")?,
+            extensions: json_map(params.extensions, "extensions")?,
+            uploads: Vec::new(),
+        })
+    }
+
+    async fn from_multipart(mut multipart: Multipart) -> Result<Self, String> {
+        let mut request: Option<Self> = None;
+        let mut map: Option<HashMap<String, Vec<String>>> = None;
+
+        while let Some(field) = multipart
+            .next_field()
+            .await
+            .map_err(|err| err.to_string())?
+        {
+            let name = field.name().unwrap_or_default().to_owned();
+            match name.as_str() {
+                "operations" => {
+                    let bytes = field.bytes().await.map_err(|err| err.to_string())?;
+                    request = Some(
+                        serde_json::from_slice(&bytes)
+                            .map_err(|err| format!("invalid operations: {err}"))?,
+                    );
+                }
+                "map" => {
+                    let bytes = field.bytes().await.map_err(|err| err.to_string())?;
+                    map = Some(
+                        serde_json::from_slice(&bytes)
+                            .map_err(|err| format!("invalid map: {err}"))?,
+                    );
+                }
+                _ => {
+                    let (Some(request), Some(map)) = (request.as_mut(), map.as_ref()) else {
+                        return Err(
+                            "operations and map fields must precede the file fields".to_owned()
+                        );
+                    };
+                    let Some(paths) = map.get(&name) else {
+                        return Err(format!("file field '{name}' is not mapped"));
+                    };
+                    let file_name = field.file_name().map(ToOwned::to_owned);
+                    let content_type = field.content_type().cloned();
+                    let content = field.bytes().await.map_err(|err| err.to_string())?;
+                    for path in paths {
+                        request.set_variable_null(path)?;
+                        request.uploads.push(GraphqlUpload {
+                            path: path.clone(),
+                            file_name: file_name.clone(),
+                            content_type: content_type.clone(),
+                            content: content.clone(),
+                        });
+                    }
+                }
+            }
+        }
+
+        request.ok_or_else(|| "missing operations field".to_owned())
+    }
+
+    /// Verify that the given object path (e.g. `variables.files.0`)
+    /// points to an existing variable, and set it to `null`.
+    fn set_variable_null(&mut self, path: &str) -> Result<(), String> {
+        let invalid_path = || format!("upload path '{path}' does not point to a variable");
+        let mut segments = path.split('.');
+        if segments.next() != Some("variables") {
+            return Err(invalid_path());
+        }
+        let Some(first) = segments.next() else {
+            return Err(invalid_path());
+        };
+        let mut value = self.variables.get_mut(first);
+        for segment in segments {
+            value = match value {
+                Some(Value::Object(map)) => map.get_mut(segment),
+                Some(Value::Array(list)) => segment
+                    .parse::<usize>()
+                    .ok()
+                    .and_then(|idx| list.get_mut(idx)),
+                _ => None,
+            };
+        }
+        match value {
+            Some(value) => {
+                *value = Value::Null;
+                Ok(())
+            }
+            None => Err(invalid_path()),
+        }
+    }
+}
+
+/// Scan the top-level definitions of a GraphQL document for operations,
+/// skipping comments, strings and selection sets.
+fn scan_operations(document: &str) -> Vec<GraphqlOperation> {
+    let mut operations = Vec::new();
+    let mut chars = document.char_indices().peekable();
+    let mut depth = 0usize;
+    let mut pending: Option<GraphqlOperation> = None;
+    let mut skip_definition = false;
+
+    while let Some((idx, c)) = chars.next() {
+        match c {
+            '#' => {
+                for (_, c) in chars.by_ref() {
+                    if c == '\n' || c == '\r' {
+                        break;
+                    }
+                }
+            }
+            '"' => {
+                let mut escaped = false;
+                for (_, c) in chars.by_ref() {
+                    match c {
+                        '\\' if !escaped => escaped = true,
+                        '"' if !escaped => break,
+                        _ => escaped = false,
+                    }
+                }
+            }
+            '{' => {
+                if depth == 0 {
+                    match pending.take() {
+                        Some(operation) => operations.push(operation),
+                        None if !skip_definition => operations.push(GraphqlOperation {
+                            kind: GraphqlOperationType::Query,
+                            name: None,
+                        }),
+                        None => (),
+                    }
+                    skip_definition = false;
+                }
+                depth += 1;
+            }
+            '}' => depth = depth.saturating_sub(1),
+            c if depth == 0 && (c.is_ascii_alphabetic() || c == '_') => {
+                let mut end = idx + c.len_utf8();
+                while let Some(&(next_idx, next)) = chars.peek() {
+                    if !(next.is_ascii_alphanumeric() || next == '_') {
+                        break;
+                    }
+                    end = next_idx + next.len_utf8();
+                    chars.next();
+                }
+                let word = &document[idx..end];
+                if let Some(operation) = pending.as_mut() {
+                    if operation.name.is_none() {
+                        operation.name = Some(word.to_owned());
+                    }
+                    continue;
+                }
+                if skip_definition {
+                    continue;
+                }
+                let kind = match word {
+                    "query" => GraphqlOperationType::Query,
+                    "mutation" => GraphqlOperationType::Mutation,
+                    "subscription" => GraphqlOperationType::Subscription,
+                    _ => {
+                        // e.g. fragment or type system definitions
+                        skip_definition = true;
+                        continue;
+                    }
+                };
+                pending = Some(GraphqlOperation { kind, name: None });
+            }
+            '(' | '@' if depth == 0 => {
+                // variable definitions and directives end the operation name
+                if let Some(operation) = pending.as_mut()
+                    && operation.name.is_none()
+                {
+                    operation.name = Some(String::new());
+                }
+                if c == '(' {
+                    for (_, c) in chars.by_ref() {
+                        if c == ')' {
+                            break;
+                        }
+                    }
+                }
+            }
+            _ => (),
+        }
+    }
+
+    for operation in operations.iter_mut() {
+        if operation.name.as_deref() == Some("") {
+            operation.name = None;
+        }
+    }
+    operations
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn op(kind: GraphqlOperationType, name: Option<&str>) -> GraphqlOperation {
+        GraphqlOperation {
+            kind,
+            name: name.map(ToOwned::to_owned),
+        }
+    }
+
+    #[test]
+    fn test_scan_operations() {
+        use GraphqlOperationType::*;
+
+        for (document, expected) in [
+            ("{ user { id } }", vec![op(Query, None)]),
+            ("query { user { id } }", vec![op(Query, None)]),
+            (
+                "query GetUser($id: ID!) { user(id: $id) { id } }",
+                vec![op(Query, Some("GetUser"))],
+            ),
+            (
+                r#"
+                # mutation Commented { x }
+                mutation CreateUser @auth { create(name: "query Fake { x }") { id } }
+                fragment UserFields on User { id name }
+                subscription OnUser($id: ID = "}") { user { ...UserFields } }
+                query($x: Int) { x }
+                "#,
+                vec![
+                    op(Mutation, Some("CreateUser")),
+                    op(Subscription, Some("OnUser")),
+                    op(Query, None),
+                ],
+            ),
+            ("fragment F on User { id }", vec![]),
+        ] {
+            assert_eq!(scan_operations(document), expected, "document: {document}");
+        }
+    }
+
+    #[test]
+    fn test_selected_operation() {
+        let request = GraphqlRequest::new("query A { a } mutation B { b }");
+        assert!(request.operation().is_err());
+
+        let request = request.with_operation_name("B".to_owned());
+        assert_eq!(
+            request.operation().unwrap(),
+            op(GraphqlOperationType::Mutation, Some("B"))
+        );
+
+        let request = request.with_operation_name("C".to_owned());
+        assert!(request.operation().is_err());
+
+        assert!(
+            GraphqlRequest::new("fragment F on User { id }")
+                .operation()
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_from_query() {
+        let request = GraphqlRequest::from_query(
+            "query=query%20Q(%24id%3A%20ID)%20%7B%20user(id%3A%20%24id)%20%7B%20id%20%7D%20%7D&operationName=Q&variables=%7B%22id%22%3A%221%22%7D",
+        )
+        .unwrap();
+        assert_eq!(request.query, "query Q($id: ID) { user(id: $id) { id } }");
+        assert_eq!(request.operation_name.as_deref(), Some("Q"));
+        assert_eq!(request.variables["id"], "1");
+
+        assert!(GraphqlRequest::from_query("variables=%7B%7D").is_err());
+        assert!(GraphqlRequest::from_query("query=%7Bx%7D&variables=nope").is_err());
+    }
+}
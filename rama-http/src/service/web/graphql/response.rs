@@ -0,0 +1,100 @@
+use crate::Response;
+use crate::service::web::response::{IntoResponse, Json};
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::fmt;
+
+#[derive(Debug, Clone, Default, Serialize)]
+/// The result of a GraphQL request, responded as a JSON body.
+pub struct GraphqlResponse {
+    /// The data produced by the executed operation.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub data: Option<Value>,
+    /// The errors raised while handling the request.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub errors: Vec<GraphqlError>,
+}
+
+impl GraphqlResponse {
+    /// Create a new [`GraphqlResponse`] with the given data.
+    pub fn data(data: Value) -> Self {
+        Self {
+            data: Some(data),
+            errors: Vec::new(),
+        }
+    }
+
+    /// Create a new [`GraphqlResponse`] containing only the given errors.
+    pub fn errors(errors: impl IntoIterator<Item = GraphqlError>) -> Self {
+        Self {
+            data: None,
+            errors: errors.into_iter().collect(),
+        }
+    }
+
+    /// Add an error to the [`GraphqlResponse`].
+    pub fn with_error(mut self, error: GraphqlError) -> Self {
+        self.errors.push(error);
+        self
+    }
+
+    /// Add an error to the [`GraphqlResponse`].
+    pub fn set_error(&mut self, error: GraphqlError) -> &mut Self {
+        self.errors.push(error);
+        self
+    }
+}
+
+impl IntoResponse for GraphqlResponse {
+    fn into_response(self) -> Response {
+        Json(self).into_response()
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+/// An error as found in the `errors` field of a [`GraphqlResponse`].
+pub struct GraphqlError {
+    /// The description of the error.
+    pub message: String,
+    /// The path of the response field which raised the error.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path: Option<Vec<Value>>,
+    /// Additional information about the error.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub extensions: Option<Map<String, Value>>,
+}
+
+impl GraphqlError {
+    /// Create a new [`GraphqlError`] with the given message.
+    pub fn new(message: impl Into<String>) -> Self {
+        Self {
+            message: message.into(),
+            path: None,
+            extensions: None,
+        }
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Set the path of the response field which raised the error.
+        pub fn path(mut self, path: Option<Vec<Value>>) -> Self {
+            self.path = path;
+            self
+        }
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Set additional information about the error.
+        pub fn extensions(mut self, extensions: Option<Map<String, Value>>) -> Self {
+            self.extensions = extensions;
+            self
+        }
+    }
+}
+
+impl fmt::Display for GraphqlError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.message.fmt(f)
+    }
+}
+
+impl std::error::Error for GraphqlError {}
@@ -1,14 +1,21 @@
 //! k8s web service
 
 use crate::{
-    Request, Response, StatusCode, matcher::HttpMatcher,
-    service::web::endpoint::response::IntoResponse,
+    Request, Response, StatusCode,
+    matcher::HttpMatcher,
+    service::web::endpoint::response::{IntoResponse, Json},
 };
 use rama_core::{
     Context, Service,
+    futures::future::{BoxFuture, join_all},
     service::{BoxService, service_fn},
 };
-use std::{convert::Infallible, fmt, marker::PhantomData, sync::Arc};
+use serde::Serialize;
+use std::{convert::Infallible, fmt, marker::PhantomData, sync::Arc, time::Duration};
+use tokio::{
+    sync::{RwLock, Semaphore},
+    time::Instant,
+};
 
 use super::match_service;
 
@@ -34,6 +41,9 @@ where
 ///
 /// In case a conditional is provided and it returns `false`,
 /// a 503 (Service Unavailable) will be returned instead.
+///
+/// The readiness check can also be defined by a [`DependencyHealthRegistry`],
+/// checking the health of the dependencies of the service.
 pub struct K8sHealthServiceBuilder<A, R, S> {
     alive: A,
     ready: R,
@@ -86,7 +96,9 @@ impl<R, S> K8sHealthServiceBuilder<(), R, S> {
 
 impl<A, S> K8sHealthServiceBuilder<A, (), S> {
     /// define an ready condition to be used by the k8s health web service for the readiness check
-    pub fn ready<R: Fn() -> bool>(self, ready: R) -> K8sHealthServiceBuilder<A, R, S> {
+    ///
+    /// This can be a `Fn() -> bool` closure or a [`DependencyHealthRegistry`].
+    pub fn ready<R: ToK8sService<S>>(self, ready: R) -> K8sHealthServiceBuilder<A, R, S> {
         K8sHealthServiceBuilder {
             alive: self.alive,
             ready,
@@ -125,6 +137,286 @@ where
 {
 }
 
+impl<S: Clone + Send + Sync + 'static> ToK8sService<S> for DependencyHealthRegistry {}
+
+/// The status reported by a [`HealthCheck`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HealthStatus {
+    /// `true` if the dependency is healthy.
+    pub ok: bool,
+    /// Optional details about the status, e.g. the reason a dependency is unhealthy.
+    pub detail: Option<String>,
+}
+
+impl HealthStatus {
+    /// Create a healthy [`HealthStatus`].
+    pub const fn healthy() -> Self {
+        Self {
+            ok: true,
+            detail: None,
+        }
+    }
+
+    /// Create an unhealthy [`HealthStatus`] with the given detail.
+    pub fn unhealthy(detail: impl Into<String>) -> Self {
+        Self {
+            ok: false,
+            detail: Some(detail.into()),
+        }
+    }
+}
+
+/// A health check of a dependency, registered in a [`DependencyHealthRegistry`].
+///
+/// Implemented for all async closures returning a [`HealthStatus`].
+pub trait HealthCheck: Send + Sync + 'static {
+    /// Check the health of the dependency.
+    fn check(&self) -> impl Future<Output = HealthStatus> + Send + '_;
+}
+
+impl<F, Fut> HealthCheck for F
+where
+    F: Fn() -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = HealthStatus> + Send + 'static,
+{
+    fn check(&self) -> impl Future<Output = HealthStatus> + Send + '_ {
+        (self)()
+    }
+}
+
+/// A [`HealthCheck`] which caches the [`HealthStatus`] of the wrapped check
+/// for a configurable time to live (TTL).
+///
+/// This prevents the underlying check (e.g. a database ping) from being run
+/// for every request in case the health endpoint is hit by many requests at once.
+/// On a cache miss only a single check runs at a time,
+/// with concurrent callers waiting for and sharing its result.
+pub struct CachedHealthCheck<H> {
+    inner: H,
+    ttl: Duration,
+    cache: RwLock<Option<(Instant, HealthStatus)>>,
+    semaphore: Semaphore,
+}
+
+impl<H: fmt::Debug> fmt::Debug for CachedHealthCheck<H> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CachedHealthCheck")
+            .field("inner", &self.inner)
+            .field("ttl", &self.ttl)
+            .field("cache", &self.cache)
+            .finish()
+    }
+}
+
+impl<H> CachedHealthCheck<H> {
+    /// Create a new [`CachedHealthCheck`], caching the status
+    /// of the given [`HealthCheck`] for the given time to live.
+    pub fn new(inner: H, ttl: Duration) -> Self {
+        Self {
+            inner,
+            ttl,
+            cache: RwLock::new(None),
+            semaphore: Semaphore::new(1),
+        }
+    }
+
+    async fn cached(&self) -> Option<HealthStatus> {
+        self.cache
+            .read()
+            .await
+            .as_ref()
+            .filter(|(checked_at, _)| checked_at.elapsed() < self.ttl)
+            .map(|(_, status)| status.clone())
+    }
+}
+
+impl<H: HealthCheck> HealthCheck for CachedHealthCheck<H> {
+    async fn check(&self) -> HealthStatus {
+        if let Some(status) = self.cached().await {
+            return status;
+        }
+
+        let _permit = self
+            .semaphore
+            .acquire()
+            .await
+            .expect("semaphore of cached health check is never closed");
+        // the status might have been refreshed while waiting for the permit
+        if let Some(status) = self.cached().await {
+            return status;
+        }
+
+        let status = self.inner.check().await;
+        *self.cache.write().await = Some((Instant::now(), status.clone()));
+        status
+    }
+}
+
+/// Object safe version of [`HealthCheck`], used to store checks of different types.
+trait DynHealthCheck: Send + Sync + 'static {
+    fn check_boxed(&self) -> BoxFuture<'_, HealthStatus>;
+}
+
+impl<H: HealthCheck> DynHealthCheck for H {
+    fn check_boxed(&self) -> BoxFuture<'_, HealthStatus> {
+        Box::pin(self.check())
+    }
+}
+
+const DEFAULT_CHECK_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// A named [`HealthCheck`] of a dependency, to be registered in a [`DependencyHealthRegistry`].
+///
+/// By default a dependency is critical and its check times out after 1 second.
+#[derive(Clone)]
+pub struct DependencyCheck {
+    name: String,
+    check: Arc<dyn DynHealthCheck>,
+    timeout: Duration,
+    critical: bool,
+}
+
+impl fmt::Debug for DependencyCheck {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DependencyCheck")
+            .field("name", &self.name)
+            .field("timeout", &self.timeout)
+            .field("critical", &self.critical)
+            .finish()
+    }
+}
+
+impl DependencyCheck {
+    /// Create a new critical [`DependencyCheck`] with the given name and [`HealthCheck`].
+    pub fn new(name: impl Into<String>, check: impl HealthCheck) -> Self {
+        Self {
+            name: name.into(),
+            check: Arc::new(check),
+            timeout: DEFAULT_CHECK_TIMEOUT,
+            critical: true,
+        }
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Set the duration after which the check is considered to have failed.
+        pub fn timeout(mut self, timeout: Duration) -> Self {
+            self.timeout = timeout;
+            self
+        }
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Define whether or not the dependency is critical.
+        ///
+        /// A failing non-critical dependency degrades the service,
+        /// but does not make it unready.
+        pub fn critical(mut self, critical: bool) -> Self {
+            self.critical = critical;
+            self
+        }
+    }
+
+    async fn run(&self) -> HealthStatus {
+        tokio::time::timeout(self.timeout, self.check.check_boxed())
+            .await
+            .unwrap_or_else(|_| HealthStatus::unhealthy("health check timed out"))
+    }
+}
+
+/// A registry of [`DependencyCheck`]s, used as the readiness check
+/// of the k8s health web service (see [`K8sHealthServiceBuilder::ready`]).
+///
+/// All checks run concurrently. In case any critical dependency is unhealthy
+/// a 503 (Service Unavailable) is returned, and otherwise a 200 (OK).
+/// The JSON body reports the overall status (`ok`, `degraded` or `unavailable`)
+/// and lists the checks which failed.
+#[derive(Debug, Clone, Default)]
+pub struct DependencyHealthRegistry {
+    checks: Vec<DependencyCheck>,
+}
+
+impl DependencyHealthRegistry {
+    /// Create a new empty [`DependencyHealthRegistry`].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Register a [`DependencyCheck`].
+        pub fn dependency(mut self, check: DependencyCheck) -> Self {
+            self.checks.push(check);
+            self
+        }
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Register a critical [`HealthCheck`] with the default timeout.
+        pub fn check(mut self, name: impl Into<String>, check: impl HealthCheck) -> Self {
+            self.checks.push(DependencyCheck::new(name, check));
+            self
+        }
+    }
+
+    /// Run all registered checks, returning the aggregated report.
+    async fn report(&self) -> DependencyHealthReport {
+        let results = join_all(self.checks.iter().map(DependencyCheck::run)).await;
+
+        let failed: Vec<_> = self
+            .checks
+            .iter()
+            .zip(results)
+            .filter(|(_, status)| !status.ok)
+            .map(|(check, status)| FailedDependency {
+                name: check.name.clone(),
+                critical: check.critical,
+                detail: status.detail,
+            })
+            .collect();
+
+        let status = if failed.iter().any(|dep| dep.critical) {
+            "unavailable"
+        } else if !failed.is_empty() {
+            "degraded"
+        } else {
+            "ok"
+        };
+
+        DependencyHealthReport { status, failed }
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct DependencyHealthReport {
+    status: &'static str,
+    failed: Vec<FailedDependency>,
+}
+
+#[derive(Debug, Serialize)]
+struct FailedDependency {
+    name: String,
+    critical: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    detail: Option<String>,
+}
+
+impl<State> Service<State, Request> for DependencyHealthRegistry
+where
+    State: Clone + Send + Sync + 'static,
+{
+    type Response = Response;
+    type Error = Infallible;
+
+    async fn serve(&self, _: Context<State>, _: Request) -> Result<Self::Response, Self::Error> {
+        let report = self.report().await;
+        let status = if report.status == "unavailable" {
+            StatusCode::SERVICE_UNAVAILABLE
+        } else {
+            StatusCode::OK
+        };
+        Ok((status, Json(report)).into_response())
+    }
+}
+
 struct K8sService<F> {
     f: F,
 }
@@ -186,4 +478,123 @@ mod private {
             K8sService::new(self).boxed()
         }
     }
+
+    impl<S: Clone + Send + Sync + 'static> Sealed<S> for DependencyHealthRegistry {
+        fn to_k8s_service(self) -> BoxService<S, Request, Response, Infallible> {
+            self.boxed()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::Body;
+    use crate::dep::http_body_util::BodyExt as _;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    async fn ready(
+        svc: &impl Service<(), Request, Response = Response, Error = Infallible>,
+    ) -> (StatusCode, serde_json::Value) {
+        let req = Request::builder()
+            .uri("/k8s/ready")
+            .body(Body::empty())
+            .unwrap();
+        let res = svc.serve(Context::default(), req).await.unwrap();
+        let status = res.status();
+        let body = res.into_body().collect().await.unwrap().to_bytes();
+        (status, serde_json::from_slice(&body).unwrap())
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn k8s_health_dependencies() {
+        let svc = k8s_health_builder()
+            .ready(
+                DependencyHealthRegistry::new()
+                    .with_check("db", async || HealthStatus::healthy())
+                    .with_dependency(
+                        DependencyCheck::new("cache", async || {
+                            HealthStatus::unhealthy("connection refused")
+                        })
+                        .with_critical(false),
+                    ),
+            )
+            .build();
+
+        let (status, body) = ready(&svc).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(
+            body,
+            serde_json::json!({
+                "status": "degraded",
+                "failed": [{"name": "cache", "critical": false, "detail": "connection refused"}],
+            })
+        );
+
+        let svc = k8s_health_builder()
+            .ready(
+                DependencyHealthRegistry::new().with_dependency(
+                    DependencyCheck::new("db", async || {
+                        tokio::time::sleep(Duration::from_secs(10)).await;
+                        HealthStatus::healthy()
+                    })
+                    .with_timeout(Duration::from_millis(100)),
+                ),
+            )
+            .build();
+
+        let (status, body) = ready(&svc).await;
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(
+            body,
+            serde_json::json!({
+                "status": "unavailable",
+                "failed": [{"name": "db", "critical": true, "detail": "health check timed out"}],
+            })
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn cached_health_check() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let check = Arc::new(CachedHealthCheck::new(
+            {
+                let calls = calls.clone();
+                move || {
+                    let calls = calls.clone();
+                    async move {
+                        calls.fetch_add(1, Ordering::SeqCst);
+                        tokio::time::sleep(Duration::from_millis(100)).await;
+                        HealthStatus::healthy()
+                    }
+                }
+            },
+            Duration::from_secs(5),
+        ));
+
+        let concurrent_checks = || {
+            join_all((0..10).map(|_| {
+                let check = check.clone();
+                tokio::spawn(async move { check.check().await })
+            }))
+        };
+
+        for result in concurrent_checks().await {
+            assert_eq!(result.unwrap(), HealthStatus::healthy());
+        }
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+        tokio::time::advance(Duration::from_secs(4)).await;
+        for result in concurrent_checks().await {
+            assert_eq!(result.unwrap(), HealthStatus::healthy());
+        }
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+        tokio::time::advance(Duration::from_secs(2)).await;
+        for result in concurrent_checks().await {
+            assert_eq!(result.unwrap(), HealthStatus::healthy());
+        }
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
 }
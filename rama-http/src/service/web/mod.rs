@@ -8,9 +8,24 @@ mod endpoint;
 #[doc(inline)]
 pub use endpoint::{EndpointServiceFn, IntoEndpointService, StaticService, extract, response};
 
+pub mod api_error;
+#[doc(inline)]
+pub use api_error::{ApiError, ApiErrorCode};
+
+pub mod echo;
+#[doc(inline)]
+pub use echo::EchoResponse;
+
+pub mod graphql;
+#[doc(inline)]
+pub use graphql::{GraphqlLayer, GraphqlRouter};
+
 pub mod k8s;
 #[doc(inline)]
-pub use k8s::{k8s_health, k8s_health_builder};
+pub use k8s::{
+    CachedHealthCheck, DependencyCheck, DependencyHealthRegistry, HealthCheck, HealthStatus,
+    k8s_health, k8s_health_builder,
+};
 
 mod router;
 #[doc(inline)]
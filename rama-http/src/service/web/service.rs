@@ -6,7 +6,7 @@ use crate::{
     service::web::endpoint::response::IntoResponse,
 };
 use rama_core::{
-    Context,
+    Context, Layer,
     context::Extensions,
     matcher::Matcher,
     service::{BoxService, Service, service_fn},
@@ -160,6 +160,36 @@ where
         self
     }
 
+    /// apply the given [`Layer`] to the most recently added route only.
+    ///
+    /// The layered service is only executed for requests matching that route,
+    /// with the extensions generated by its matcher (e.g. the [`UriParams`])
+    /// already inserted in the [`Context`]. Calling this method multiple times
+    /// stacks the layers, with the last applied layer being the outermost one.
+    ///
+    /// # Panics
+    ///
+    /// Panics in case no route was added yet.
+    pub fn layer<L>(mut self, layer: L) -> Self
+    where
+        L: Layer<
+                BoxService<State, Request, Response, Infallible>,
+                Service: Service<State, Request, Response: IntoResponse, Error: IntoResponse>,
+            >,
+    {
+        let endpoint = self
+            .endpoints
+            .pop()
+            .expect("WebService::layer: add a route prior to layering it");
+        let Endpoint { matcher, service } = Arc::unwrap_or_clone(endpoint);
+        let endpoint = Endpoint {
+            matcher,
+            service: ScopedRouteService(layer.into_layer(service)).boxed(),
+        };
+        self.endpoints.push(Arc::new(endpoint));
+        self
+    }
+
     /// use the given service in case no match could be found.
     pub fn not_found<I, T>(mut self, service: I) -> Self
     where
@@ -170,6 +200,36 @@ where
     }
 }
 
+impl<State> WebService<State>
+where
+    State: fmt::Debug + Clone + Send + Sync + 'static,
+{
+    /// create a handler which responds with the received request as JSON,
+    /// for debugging purposes, similar to `httpbin.org/anything`.
+    ///
+    /// The JSON body is an [`EchoResponse`], containing the method, uri,
+    /// headers and body of the request, as well as the [`Debug`] representation
+    /// of the [`Context`] state. This can be useful when testing proxies,
+    /// load balancers and middleware chains.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rama_http::service::web::WebService;
+    ///
+    /// let svc = WebService::default().get("/anything", WebService::echo_handler());
+    /// # let _: WebService<()> = svc;
+    /// ```
+    ///
+    /// [`EchoResponse`]: super::EchoResponse
+    pub fn echo_handler()
+    -> impl Service<State, Request, Response = Response, Error = Infallible> + Clone {
+        service_fn(async |ctx: Context<State>, req: Request| {
+            Ok::<_, Infallible>(super::echo::echo(ctx, req).await)
+        })
+    }
+}
+
 struct NestedService<S>(S);
 
 impl<S: fmt::Debug> fmt::Debug for NestedService<S> {
@@ -219,6 +279,32 @@ where
     }
 }
 
+/// Endpoint service of a route, layered using [`WebService::layer`].
+struct ScopedRouteService<S>(S);
+
+impl<S: fmt::Debug> fmt::Debug for ScopedRouteService<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("ScopedRouteService").field(&self.0).finish()
+    }
+}
+
+impl<S, State> Service<State, Request> for ScopedRouteService<S>
+where
+    State: Clone + Send + Sync + 'static,
+    S: Service<State, Request, Response: IntoResponse, Error: IntoResponse>,
+{
+    type Response = Response;
+    type Error = Infallible;
+
+    async fn serve(
+        &self,
+        ctx: Context<State>,
+        req: Request,
+    ) -> Result<Self::Response, Self::Error> {
+        Ok(self.0.serve(ctx, req).await.into_response())
+    }
+}
+
 impl<State> Default for WebService<State>
 where
     State: Clone + Send + Sync + 'static,
@@ -337,6 +423,7 @@ mod test {
     use crate::Body;
     use crate::dep::http_body_util::BodyExt;
     use crate::matcher::MethodMatcher;
+    use rama_core::layer::layer_fn;
 
     use super::*;
 
@@ -423,6 +510,49 @@ mod test {
         assert_eq!(res.status(), StatusCode::NOT_FOUND);
     }
 
+    #[tokio::test]
+    async fn test_web_service_layer() {
+        let admin_only = layer_fn(|inner: BoxService<(), Request, Response, Infallible>| {
+            service_fn(move |ctx: Context<()>, req: Request| {
+                let inner = inner.clone();
+                async move {
+                    if ctx.get::<UriParams>().and_then(|params| params.get("user")) != Some("admin")
+                    {
+                        return Err(StatusCode::FORBIDDEN);
+                    }
+                    inner.serve(ctx, req).await.map_err(|err| match err {})
+                }
+            })
+        });
+        let svc = WebService::new()
+            .get("/hello", "hello")
+            .get("/users/:user/secret", "secret")
+            .layer(admin_only)
+            .get("/users/:user", "user");
+
+        let res = get_response(&svc, "https://www.test.io/users/admin/secret").await;
+        assert_eq!(res.status(), StatusCode::OK);
+        let body = res.into_body().collect().await.unwrap().to_bytes();
+        assert_eq!(body, "secret");
+
+        let res = get_response(&svc, "https://www.test.io/users/joe/secret").await;
+        assert_eq!(res.status(), StatusCode::FORBIDDEN);
+
+        let res = get_response(&svc, "https://www.test.io/users/joe").await;
+        assert_eq!(res.status(), StatusCode::OK);
+        let body = res.into_body().collect().await.unwrap().to_bytes();
+        assert_eq!(body, "user");
+
+        let res = get_response(&svc, "https://www.test.io/hello").await;
+        assert_eq!(res.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    #[should_panic]
+    async fn test_web_service_layer_without_route() {
+        let _ = WebService::<()>::new().layer(layer_fn(|inner| inner));
+    }
+
     #[tokio::test]
     async fn test_web_service_dir() {
         let tmp_dir = tempfile::tempdir().unwrap();
@@ -495,4 +625,27 @@ mod test {
         let res = get_response(&svc, "https://www.test.io").await;
         assert_eq!(res.status(), StatusCode::NOT_FOUND);
     }
+
+    #[tokio::test]
+    async fn test_echo_handler() {
+        let svc = WebService::default().post("/echo", WebService::echo_handler());
+
+        let req = Request::post("https://www.test.io/echo?foo=bar")
+            .header("x-test", "hello")
+            .body(Body::from("ping"))
+            .unwrap();
+        let res = svc.serve(Context::default(), req).await.unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+
+        let body = res.into_body().collect().await.unwrap().to_bytes();
+        let echo: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(echo["method"], "POST");
+        assert_eq!(echo["uri"], "https://www.test.io/echo?foo=bar");
+        assert_eq!(echo["path"], "/echo");
+        assert_eq!(echo["query"], "foo=bar");
+        assert_eq!(echo["version"], "HTTP/1.1");
+        assert_eq!(echo["headers"], serde_json::json!([["x-test", "hello"]]));
+        assert_eq!(echo["body"], "ping");
+        assert_eq!(echo["state"], "()");
+    }
 }
@@ -0,0 +1,47 @@
+use rama_crypto::dep::aws_lc_rs::{constant_time, hmac};
+
+#[derive(Debug, Clone)]
+/// HMAC key shared by the signing and verifying utilities of this crate,
+/// such as signed cookies, HS256 json web tokens and webhook signatures.
+pub(crate) struct HmacKey(hmac::Key);
+
+impl HmacKey {
+    /// Create a new [`HmacKey`] for the given algorithm, using the given secret.
+    pub(crate) fn new(algorithm: hmac::Algorithm, secret: &[u8]) -> Self {
+        Self(hmac::Key::new(algorithm, secret))
+    }
+
+    /// Create a new HMAC-SHA256 [`HmacKey`] using the given secret.
+    pub(crate) fn sha256(secret: &[u8]) -> Self {
+        Self::new(hmac::HMAC_SHA256, secret)
+    }
+
+    /// Sign the concatenation of the given message parts.
+    pub(crate) fn sign(&self, parts: &[&[u8]]) -> hmac::Tag {
+        let mut ctx = hmac::Context::with_key(&self.0);
+        for part in parts {
+            ctx.update(part);
+        }
+        ctx.sign()
+    }
+
+    /// Verify, in constant time, the signature of the concatenation of the given message parts.
+    pub(crate) fn verify(&self, parts: &[&[u8]], signature: &[u8]) -> bool {
+        constant_time::verify_slices_are_equal(self.sign(parts).as_ref(), signature).is_ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hmac_key_parts() {
+        let key = HmacKey::sha256(b"secret");
+        let signature = key.sign(&[b"foo", b".", b"bar"]);
+        assert_eq!(signature.as_ref(), key.sign(&[b"foo.bar"]).as_ref());
+        assert!(key.verify(&[b"foo.", b"bar"], signature.as_ref()));
+        assert!(!key.verify(&[b"foo.baz"], signature.as_ref()));
+        assert!(!HmacKey::sha256(b"other").verify(&[b"foo.bar"], signature.as_ref()));
+    }
+}
@@ -8,5 +8,8 @@ pub use header_value::{HeaderValueErr, HeaderValueGetter};
 #[macro_use]
 pub(crate) mod macros;
 
+#[cfg(feature = "crypto")]
+pub(crate) mod hmac;
+
 mod req_switch_version_ext;
 pub use req_switch_version_ext::RequestSwitchVersionExt;
@@ -114,7 +114,7 @@ impl Display for ProxyAddress {
                 ProxyCredential::Basic(basic) => {
                     write!(f, "{basic}@")?;
                 }
-                ProxyCredential::Bearer(_) => {
+                ProxyCredential::Bearer(_) | ProxyCredential::OAuth2 { .. } => {
                     tracing::trace!(
                         "ignore bearer token for ProxyAddress display (other means are required for these)"
                     )
@@ -128,15 +128,22 @@ struct PooledConnection<C, ID> {
     conn: C,
     id: ID,
     pool_slot: PoolSlot,
+    created_at: Instant,
     last_used: Instant,
 }
 
+/// Health check used by the [`FiFoReuseLruDropPool`] to verify
+/// that an idle connection can still be used, prior to handing it out.
+type HealthCheck<C> = Arc<dyn Fn(&C) -> bool + Send + Sync + 'static>;
+
 /// Connection pool that uses FiFo for reuse and LRU to evict connections
 pub struct FiFoReuseLruDropPool<C, ID> {
     storage: Arc<Mutex<VecDeque<PooledConnection<C, ID>>>>,
     total_slots: Arc<Semaphore>,
     active_slots: Arc<Semaphore>,
     idle_timeout: Option<Duration>,
+    max_lifetime: Option<Duration>,
+    health_check: Option<HealthCheck<C>>,
     returner: ConnReturner<C, ID>,
 }
 
@@ -169,6 +176,8 @@ impl<C, ID> Clone for FiFoReuseLruDropPool<C, ID> {
             active_slots: self.active_slots.clone(),
             returner: self.returner.clone(),
             idle_timeout: self.idle_timeout,
+            max_lifetime: self.max_lifetime,
+            health_check: self.health_check.clone(),
         }
     }
 }
@@ -193,6 +202,8 @@ impl<C, ID> FiFoReuseLruDropPool<C, ID> {
             total_slots: Arc::new(Semaphore::const_new(max_total)),
             active_slots: Arc::new(Semaphore::const_new(max_active)),
             idle_timeout: None,
+            max_lifetime: None,
+            health_check: None,
         })
     }
 
@@ -207,6 +218,44 @@ impl<C, ID> FiFoReuseLruDropPool<C, ID> {
             self
         }
     }
+
+    generate_set_and_with! {
+        /// If connections have been created longer ago then the provided lifetime they
+        /// will be dropped and removed from the pool, regardless of how recently they were used
+        ///
+        /// Note: lifetime is only checked when a connection is requested from the pool,
+        /// it is not something that is done periodically
+        pub fn max_lifetime(mut self, lifetime: Option<Duration>) -> Self {
+            self.max_lifetime = lifetime;
+            self
+        }
+    }
+
+    /// Set a health check which is run on idle connections before they are reused,
+    /// connections for which it returns `false` are dropped and removed from the pool
+    ///
+    /// This can for example be used to detect connections that were closed by the peer
+    /// while sitting idle in the pool
+    pub fn with_health_check<F>(mut self, health_check: F) -> Self
+    where
+        F: Fn(&C) -> bool + Send + Sync + 'static,
+    {
+        self.health_check = Some(Arc::new(health_check));
+        self
+    }
+
+    /// Set a health check which is run on idle connections before they are reused,
+    /// connections for which it returns `false` are dropped and removed from the pool
+    ///
+    /// This can for example be used to detect connections that were closed by the peer
+    /// while sitting idle in the pool
+    pub fn set_health_check<F>(&mut self, health_check: F) -> &mut Self
+    where
+        F: Fn(&C) -> bool + Send + Sync + 'static,
+    {
+        self.health_check = Some(Arc::new(health_check));
+        self
+    }
 }
 
 impl<C, ID> Pool<C, ID> for FiFoReuseLruDropPool<C, ID>
@@ -231,33 +280,57 @@ where
 
         let mut storage = self.storage.lock();
 
-        let idx = storage.iter().position(|stored| &stored.id == id);
-        let pooled_conn = idx.and_then(|idx| storage.remove(idx));
-
-        if let (Some(idx), Some(pooled_conn)) = (idx, pooled_conn) {
+        let mut start = 0;
+        while let Some(idx) = storage
+            .iter()
+            .skip(start)
+            .position(|stored| &stored.id == id)
+            .map(|idx| idx + start)
+        {
+            let pooled_conn = storage
+                .remove(idx)
+                .context("remove found connection from storage")?;
             trace!("fifo connection pool: connection #{idx} found for given id {id:?}");
-            let mut timeout_triggered = false;
-            if let Some(timeout) = self.idle_timeout {
-                if pooled_conn.last_used.elapsed() > timeout {
-                    timeout_triggered = true;
-                }
-            }
 
             // Since we always insert in the beginning we can assume if this connection has been idle for too long
             // all connections older then this one should also be evicted
-            if timeout_triggered {
+            if self
+                .idle_timeout
+                .is_some_and(|timeout| pooled_conn.last_used.elapsed() > timeout)
+            {
                 trace!(
                     "fifo connection pool: idle timeout was triggered, dropping this connection (w/ start index {idx:?}) and all older ones"
                 );
                 storage.drain(idx..);
-            } else {
-                return Ok(ConnectionResult::Connection(LeasedConnection {
-                    active_slot,
-                    pooled_conn: Some(pooled_conn),
-                    returner: self.returner.clone(),
-                    failed: false.into(),
-                }));
+                break;
             }
+
+            // Lifetime and health are specific to this connection, so we only drop this one
+            // and continue looking for another suitable connection
+            if self
+                .max_lifetime
+                .is_some_and(|lifetime| pooled_conn.created_at.elapsed() > lifetime)
+            {
+                trace!(
+                    "fifo connection pool: max lifetime was exceeded, dropping connection #{idx}"
+                );
+                start = idx;
+                continue;
+            }
+            if let Some(health_check) = self.health_check.as_ref()
+                && !health_check(&pooled_conn.conn)
+            {
+                trace!("fifo connection pool: health check failed, dropping connection #{idx}");
+                start = idx;
+                continue;
+            }
+
+            return Ok(ConnectionResult::Connection(LeasedConnection {
+                active_slot,
+                pooled_conn: Some(pooled_conn),
+                returner: self.returner.clone(),
+                failed: false.into(),
+            }));
         }
 
         let pool_slot = match self.total_slots.clone().try_acquire_owned() {
@@ -291,6 +364,7 @@ where
                 id,
                 conn,
                 pool_slot,
+                created_at: Instant::now(),
                 last_used: Instant::now(),
             }),
         }
@@ -654,15 +728,19 @@ pub mod http {
     use super::{FiFoReuseLruDropPool, PooledConnector, ReqToConnID};
     use crate::{Protocol, address::Authority, client::pool::OpaqueError, http::RequestContext};
     use rama_core::Context;
-    use rama_http_types::Request;
+    use rama_http_types::{Request, Version, conn::TargetHttpVersion};
     use std::time::Duration;
 
     #[derive(Clone, Debug, Default)]
     #[non_exhaustive]
     /// [`BasicHttpConnIdentifier`] can be used together with a [`super::Pool`] to create a basic http connection pool
+    ///
+    /// Connections are grouped on protocol, authority and the [`TargetHttpVersion`] (if any).
+    /// The latter makes sure that a connection which negotiated a specific http version
+    /// (e.g. using tls alpn) is only reused for requests which target that same version.
     pub struct BasicHttpConnIdentifier;
 
-    pub type BasicHttpConId = (Protocol, Authority);
+    pub type BasicHttpConId = (Protocol, Authority, Option<Version>);
 
     impl<State, Body> ReqToConnID<State, Request<Body>> for BasicHttpConnIdentifier {
         type ID = BasicHttpConId;
@@ -673,7 +751,11 @@ pub mod http {
                 None => &RequestContext::try_from((ctx, req))?,
             };
 
-            Ok((req_ctx.protocol.clone(), req_ctx.authority.clone()))
+            Ok((
+                req_ctx.protocol.clone(),
+                req_ctx.authority.clone(),
+                ctx.get::<TargetHttpVersion>().map(|version| version.0),
+            ))
         }
     }
 
@@ -696,6 +778,12 @@ pub mod http {
         /// Note: timeout is only checked when a connection is requested from the pool,
         /// it is not something that is done periodically
         pub idle_timeout: Option<Duration>,
+        /// If connections have been created longer ago then the provided lifetime they
+        /// will be dropped and removed from the pool, regardless of how recently they were used
+        ///
+        /// Note: lifetime is only checked when a connection is requested from the pool,
+        /// it is not something that is done periodically
+        pub max_lifetime: Option<Duration>,
         /// When a pool is operating at max active capacity wait for this duration
         /// to get a connection from the pool before the connector raises a timeout error
         pub wait_for_pool_timeout: Option<Duration>,
@@ -708,11 +796,21 @@ pub mod http {
                 max_active: 20,
                 wait_for_pool_timeout: Some(Duration::from_secs(120)),
                 idle_timeout: Some(Duration::from_secs(300)),
+                max_lifetime: None,
             }
         }
     }
 
     impl HttpPooledConnectorConfig {
+        /// Build the [`FiFoReuseLruDropPool`] described by this config
+        pub fn build_pool<C>(
+            &self,
+        ) -> Result<FiFoReuseLruDropPool<C, BasicHttpConId>, OpaqueError> {
+            Ok(FiFoReuseLruDropPool::new(self.max_active, self.max_total)?
+                .maybe_with_idle_timeout(self.idle_timeout)
+                .maybe_with_max_lifetime(self.max_lifetime))
+        }
+
         pub fn build_connector<C, S>(
             self,
             inner: S,
@@ -720,8 +818,7 @@ pub mod http {
             PooledConnector<S, FiFoReuseLruDropPool<C, BasicHttpConId>, BasicHttpConnIdentifier>,
             OpaqueError,
         > {
-            let pool = FiFoReuseLruDropPool::new(self.max_active, self.max_total)?
-                .maybe_with_idle_timeout(self.idle_timeout);
+            let pool = self.build_pool()?;
 
             Ok(PooledConnector::new(inner, pool, BasicHttpConnIdentifier)
                 .maybe_with_wait_for_pool_timeout(self.wait_for_pool_timeout))
@@ -1010,4 +1107,111 @@ mod tests {
         assert_eq!(svc.inner.created_connection.load(Ordering::Relaxed), 2);
         drop(conn);
     }
+
+    #[tokio::test]
+    async fn drop_connections_exceeding_max_lifetime() {
+        let pool = FiFoReuseLruDropPool::new(5, 10)
+            .unwrap()
+            .with_max_lifetime(Duration::from_millis(50));
+
+        let svc = PooledConnector::new(TestService::default(), pool, StringRequestLengthID {});
+
+        let conn = svc
+            .connect(Context::default(), String::from(""))
+            .await
+            .unwrap()
+            .conn;
+        drop(conn);
+
+        // connection is still young enough to be reused
+        let conn = svc
+            .connect(Context::default(), String::from(""))
+            .await
+            .unwrap()
+            .conn;
+        assert_eq!(svc.inner.created_connection.load(Ordering::Relaxed), 1);
+        drop(conn);
+
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        // despite having been used recently, this connection has exceeded its lifetime
+        let conn = svc
+            .connect(Context::default(), String::from(""))
+            .await
+            .unwrap()
+            .conn;
+        assert_eq!(svc.inner.created_connection.load(Ordering::Relaxed), 2);
+        drop(conn);
+    }
+
+    #[tokio::test]
+    async fn drop_unhealthy_connections() {
+        let pool = FiFoReuseLruDropPool::new(5, 10)
+            .unwrap()
+            .with_health_check(|conn: &Vec<u32>| conn.is_empty());
+
+        let svc = PooledConnector::new(TestService::default(), pool, StringRequestLengthID {});
+
+        let mut unhealthy_conn = svc
+            .connect(Context::default(), String::from(""))
+            .await
+            .unwrap()
+            .conn;
+        unhealthy_conn.push(1);
+
+        let healthy_conn = svc
+            .connect(Context::default(), String::from(""))
+            .await
+            .unwrap()
+            .conn;
+        assert_eq!(svc.inner.created_connection.load(Ordering::Relaxed), 2);
+
+        // healthy connection is returned last, so unhealthy one is found first
+        drop(healthy_conn);
+        drop(unhealthy_conn);
+
+        let conn = svc
+            .connect(Context::default(), String::from(""))
+            .await
+            .unwrap()
+            .conn;
+        assert!(conn.is_empty());
+        drop(conn);
+
+        let conn = svc
+            .connect(Context::default(), String::from(""))
+            .await
+            .unwrap()
+            .conn;
+        assert!(conn.is_empty());
+        assert_eq!(svc.inner.created_connection.load(Ordering::Relaxed), 2);
+    }
+
+    #[cfg(feature = "http")]
+    #[test]
+    fn test_basic_http_conn_id_target_version() {
+        use http::BasicHttpConnIdentifier;
+        use rama_http_types::{Request, Version, conn::TargetHttpVersion};
+
+        let req = Request::builder()
+            .uri("https://example.com")
+            .body(())
+            .unwrap();
+
+        let mut ctx = Context::default();
+        let any_version = BasicHttpConnIdentifier.id(&ctx, &req).unwrap();
+        assert_eq!(any_version.2, None);
+
+        ctx.insert(TargetHttpVersion(Version::HTTP_2));
+        let h2 = BasicHttpConnIdentifier.id(&ctx, &req).unwrap();
+        assert_eq!(h2.2, Some(Version::HTTP_2));
+
+        ctx.insert(TargetHttpVersion(Version::HTTP_11));
+        let h1 = BasicHttpConnIdentifier.id(&ctx, &req).unwrap();
+        assert_eq!(h1.2, Some(Version::HTTP_11));
+
+        assert_eq!((&any_version.0, &any_version.1), (&h2.0, &h2.1));
+        assert_ne!(any_version, h2);
+        assert_ne!(h1, h2);
+    }
 }
@@ -0,0 +1,176 @@
+use super::Ja3;
+use rama_core::telemetry::tracing;
+use rama_core::{Context, Layer, Service};
+use rama_utils::macros::define_inner_service_accessors;
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+/// The [`Ja3`] hash of the client, as a lowercase hex string,
+/// inserted in the [`Context`] by the [`Ja3FingerprintService`].
+pub struct Ja3Fingerprint(pub String);
+
+impl fmt::Display for Ja3Fingerprint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+/// Layer that applies the [`Ja3FingerprintService`] middleware.
+///
+/// The [`Ja3`] is computed from the [`ClientHello`] stored in the [`SecureTransport`]
+/// by the TLS acceptor, which only happens in case the acceptor is configured
+/// to store the client hello (e.g. `TlsAcceptorLayer::with_store_client_hello`).
+/// This layer is therefore to be applied after the TLS acceptor.
+///
+/// [`ClientHello`]: crate::tls::client::ClientHello
+/// [`SecureTransport`]: crate::tls::SecureTransport
+#[derive(Debug, Clone, Default)]
+#[non_exhaustive]
+pub struct Ja3FingerprintLayer;
+
+impl Ja3FingerprintLayer {
+    /// Create a new [`Ja3FingerprintLayer`].
+    pub const fn new() -> Self {
+        Self
+    }
+}
+
+impl<S> Layer<S> for Ja3FingerprintLayer {
+    type Service = Ja3FingerprintService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        Ja3FingerprintService::new(inner)
+    }
+}
+
+/// Middleware which inserts the [`Ja3Fingerprint`] of the client in the [`Context`],
+/// such that downstream services (e.g. fraud detection) can inspect it.
+///
+/// No fingerprint is inserted in case the [`Ja3`] could not be computed,
+/// e.g. because no client hello is available.
+///
+/// See [`Ja3FingerprintLayer`] for more information.
+pub struct Ja3FingerprintService<S> {
+    inner: S,
+}
+
+impl<S> Ja3FingerprintService<S> {
+    /// Create a new [`Ja3FingerprintService`].
+    pub const fn new(inner: S) -> Self {
+        Self { inner }
+    }
+
+    define_inner_service_accessors!();
+}
+
+impl<S: fmt::Debug> fmt::Debug for Ja3FingerprintService<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Ja3FingerprintService")
+            .field("inner", &self.inner)
+            .finish()
+    }
+}
+
+impl<S: Clone> Clone for Ja3FingerprintService<S> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl<S, State, Request> Service<State, Request> for Ja3FingerprintService<S>
+where
+    S: Service<State, Request>,
+    State: Clone + Send + Sync + 'static,
+    Request: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+
+    async fn serve(
+        &self,
+        mut ctx: Context<State>,
+        req: Request,
+    ) -> Result<Self::Response, Self::Error> {
+        match Ja3::compute(ctx.extensions()) {
+            Ok(ja3) => {
+                ctx.insert(Ja3Fingerprint(ja3.hash()));
+            }
+            Err(err) => {
+                tracing::trace!("failed to compute ja3 fingerprint: {err}");
+            }
+        }
+        self.inner.serve(ctx, req).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tls::client::{ClientHello, ClientHelloExtension};
+    use crate::tls::{
+        CipherSuite, ECPointFormat, ProtocolVersion, SecureTransport, SupportedGroup,
+    };
+    use rama_core::service::service_fn;
+    use std::convert::Infallible;
+
+    async fn fingerprint(ctx: Context<()>) -> Option<Ja3Fingerprint> {
+        Ja3FingerprintLayer::new()
+            .into_layer(service_fn(async |ctx: Context<()>, _req: ()| {
+                Ok::<_, Infallible>(ctx.get::<Ja3Fingerprint>().cloned())
+            }))
+            .serve(ctx, ())
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_ja3_fingerprint_layer() {
+        let client_hello = ClientHello::new(
+            ProtocolVersion::TLSv1_2,
+            vec![
+                CipherSuite::from(0x0a0a), // grease
+                CipherSuite::TLS13_AES_128_GCM_SHA256,
+            ],
+            vec![],
+            vec![
+                ClientHelloExtension::SupportedGroups(vec![
+                    SupportedGroup::X25519,
+                    SupportedGroup::SECP256R1,
+                ]),
+                ClientHelloExtension::ECPointFormats(vec![ECPointFormat::Uncompressed]),
+            ],
+        );
+
+        let mut ctx = Context::default();
+        ctx.insert(SecureTransport::with_client_hello(client_hello));
+
+        // ja3 string: 771,4865,10-11,29-23,0
+        assert_eq!(
+            fingerprint(ctx).await,
+            Some(Ja3Fingerprint(
+                "87c38a2de9b8e118c385f43ce1a4232e".to_owned()
+            ))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_ja3_fingerprint_layer_without_client_hello() {
+        assert_eq!(fingerprint(Context::default()).await, None);
+
+        let mut ctx = Context::default();
+        ctx.insert(SecureTransport::default());
+        assert_eq!(fingerprint(ctx).await, None);
+
+        // a client hello without any cipher suite cannot be fingerprinted
+        let mut ctx = Context::default();
+        ctx.insert(SecureTransport::with_client_hello(ClientHello::new(
+            ProtocolVersion::TLSv1_2,
+            vec![CipherSuite::from(0x0a0a)],
+            vec![],
+            vec![],
+        )));
+        assert_eq!(fingerprint(ctx).await, None);
+    }
+}
@@ -9,6 +9,9 @@ use crate::tls::{
 
 use super::ClientHelloProvider;
 
+mod layer;
+pub use layer::{Ja3Fingerprint, Ja3FingerprintLayer, Ja3FingerprintService};
+
 #[derive(Debug, Clone)]
 /// Data which can be hashed using [`Self::hash`],
 /// and which is also displayed as a "ja3" hash.
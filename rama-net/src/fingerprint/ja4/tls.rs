@@ -633,6 +633,7 @@ mod tests {
                 ext.insert(NegotiatedTlsParameters {
                     protocol_version: negotiated_protocol_version,
                     application_layer_protocol: None,
+                    cipher_suite: None,
                     peer_certificate_chain: None,
                 });
             }
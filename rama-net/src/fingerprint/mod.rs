@@ -19,7 +19,7 @@ pub use peet::{PeetComputeError, PeetPrint};
 mod ja3;
 
 #[cfg(feature = "tls")]
-pub use ja3::{Ja3, Ja3ComputeError};
+pub use ja3::{Ja3, Ja3ComputeError, Ja3Fingerprint, Ja3FingerprintLayer, Ja3FingerprintService};
 
 #[cfg(feature = "tls")]
 mod tls_utils {
@@ -0,0 +1,106 @@
+use crate::stream::matcher::ip::{IntoIpNet, IpNet};
+use std::net::IpAddr;
+
+#[derive(Debug, Clone, Default)]
+/// Allow and deny lists of IP networks used by the [`IpFilterLayer`].
+///
+/// An IP address is allowed in case it is not contained by any of the denied networks,
+/// and, if any allowed networks are defined, is contained by one of them.
+/// Denied networks therefore take precedence over allowed networks.
+///
+/// Both IPv4 and IPv6 networks are supported,
+/// which can be parsed from their CIDR notation (e.g. `192.168.0.0/24` or `::1/128`).
+///
+/// [`IpFilterLayer`]: super::IpFilterLayer
+pub struct IpFilter {
+    allow: Vec<IpNet>,
+    deny: Vec<IpNet>,
+}
+
+impl IpFilter {
+    /// Create a new [`IpFilter`], allowing all IP addresses.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add an IP network to the allow list.
+    ///
+    /// Once an IP network is allowed, all IP addresses outside
+    /// of the allowed networks are denied.
+    pub fn with_allow(mut self, net: impl IntoIpNet) -> Self {
+        self.allow.push(net.into_ip_net());
+        self
+    }
+
+    /// Add an IP network to the allow list.
+    ///
+    /// Once an IP network is allowed, all IP addresses outside
+    /// of the allowed networks are denied.
+    pub fn set_allow(&mut self, net: impl IntoIpNet) -> &mut Self {
+        self.allow.push(net.into_ip_net());
+        self
+    }
+
+    /// Add an IP network to the deny list.
+    pub fn with_deny(mut self, net: impl IntoIpNet) -> Self {
+        self.deny.push(net.into_ip_net());
+        self
+    }
+
+    /// Add an IP network to the deny list.
+    pub fn set_deny(&mut self, net: impl IntoIpNet) -> &mut Self {
+        self.deny.push(net.into_ip_net());
+        self
+    }
+
+    /// Returns `true` if the given IP address is allowed by this [`IpFilter`].
+    pub fn is_allowed(&self, ip: IpAddr) -> bool {
+        let ip = ip.to_canonical();
+        if self.deny.iter().any(|net| net.contains(&ip)) {
+            return false;
+        }
+        self.allow.is_empty() || self.allow.iter().any(|net| net.contains(&ip))
+    }
+
+    /// Returns `true` if a peer of which the IP address is unknown is allowed,
+    /// which is only the case if no allow list is defined.
+    pub(super) fn is_unknown_allowed(&self) -> bool {
+        self.allow.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ip_filter() {
+        let filter = IpFilter::new()
+            .with_allow("192.168.0.0/24".parse::<IpNet>().unwrap())
+            .with_allow("::1/128".parse::<IpNet>().unwrap())
+            .with_deny("192.168.0.42/32".parse::<IpNet>().unwrap());
+
+        for (ip, expected) in [
+            ("192.168.0.1", true),
+            ("192.168.0.255", true),
+            ("192.168.0.42", false),
+            ("192.168.1.1", false),
+            ("10.0.0.1", false),
+            ("::1", true),
+            ("::2", false),
+            ("::ffff:192.168.0.1", true),
+            ("::ffff:192.168.0.42", false),
+        ] {
+            assert_eq!(filter.is_allowed(ip.parse().unwrap()), expected, "{ip}");
+        }
+        assert!(!filter.is_unknown_allowed());
+    }
+
+    #[test]
+    fn test_ip_filter_deny_only() {
+        let filter = IpFilter::new().with_deny([10, 0, 0, 0]);
+        assert!(!filter.is_allowed([10, 0, 0, 0].into()));
+        assert!(filter.is_allowed([10, 0, 0, 1].into()));
+        assert!(filter.is_unknown_allowed());
+    }
+}
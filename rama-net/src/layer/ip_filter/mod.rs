@@ -0,0 +1,11 @@
+//! Middleware to allow or deny peers based on their IP address.
+//!
+//! See [`IpFilterLayer`] for more information.
+
+mod filter;
+#[doc(inline)]
+pub use filter::IpFilter;
+
+mod service;
+#[doc(inline)]
+pub use service::{IpFilterLayer, IpFilterService, IpNotAllowed};
@@ -0,0 +1,257 @@
+use super::IpFilter;
+use crate::stream::Socket;
+use parking_lot::RwLock;
+use rama_core::error::BoxError;
+use rama_core::telemetry::tracing;
+use rama_core::{Context, Layer, Service};
+use rama_utils::macros::define_inner_service_accessors;
+use std::fmt;
+use std::net::IpAddr;
+use std::sync::Arc;
+
+#[cfg(feature = "http")]
+use crate::{forwarded::Forwarded, stream::SocketInfo};
+#[cfg(feature = "http")]
+use rama_http_types::{Body, Request, Response, StatusCode};
+
+rama_utils::macros::error::static_str_error! {
+    #[doc = "ip address of the peer is not allowed by the ip filter"]
+    pub struct IpNotAllowed;
+}
+
+/// A [`Layer`] that produces [`IpFilterService`]s,
+/// allowing or denying peers based on their IP address using an [`IpFilter`].
+///
+/// The [`IpFilterService`] can be used for streams as well as for http requests:
+///
+/// - for streams (e.g. a `TcpStream`) the peer address of the [`Socket`] is used,
+///   and the stream is closed immediately in case it is denied,
+///   returning an [`IpNotAllowed`] error;
+/// - for http requests the client IP is taken from the [`Forwarded`] information
+///   found in the [`Context`] (e.g. inserted by a `GetForwardedHeadersLayer`),
+///   falling back to the peer address of the [`SocketInfo`], responding
+///   with `403 Forbidden` in case it is denied.
+///
+/// Peers of which the IP address is unknown are only allowed
+/// in case the [`IpFilter`] does not define an allow list.
+///
+/// The [`IpFilter`] can be hot-reloaded using [`IpFilterLayer::reload`],
+/// which applies to all services created by this layer (or its clones).
+///
+/// [`Forwarded`]: crate::forwarded::Forwarded
+/// [`SocketInfo`]: crate::stream::SocketInfo
+#[derive(Debug, Clone)]
+pub struct IpFilterLayer {
+    filter: Arc<RwLock<IpFilter>>,
+}
+
+impl IpFilterLayer {
+    /// Create a new [`IpFilterLayer`] using the given [`IpFilter`].
+    pub fn new(filter: IpFilter) -> Self {
+        Self {
+            filter: Arc::new(RwLock::new(filter)),
+        }
+    }
+
+    /// Replace the [`IpFilter`] used by this layer and all services created by it.
+    pub fn reload(&self, filter: IpFilter) {
+        *self.filter.write() = filter;
+    }
+}
+
+impl<S> Layer<S> for IpFilterLayer {
+    type Service = IpFilterService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        IpFilterService {
+            inner,
+            filter: self.filter.clone(),
+        }
+    }
+
+    fn into_layer(self, inner: S) -> Self::Service {
+        IpFilterService {
+            inner,
+            filter: self.filter,
+        }
+    }
+}
+
+/// Middleware [`Service`] allowing or denying peers based on their IP address.
+///
+/// See [`IpFilterLayer`] for more information.
+pub struct IpFilterService<S> {
+    inner: S,
+    filter: Arc<RwLock<IpFilter>>,
+}
+
+impl<S: fmt::Debug> fmt::Debug for IpFilterService<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("IpFilterService")
+            .field("inner", &self.inner)
+            .field("filter", &self.filter)
+            .finish()
+    }
+}
+
+impl<S: Clone> Clone for IpFilterService<S> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            filter: self.filter.clone(),
+        }
+    }
+}
+
+impl<S> IpFilterService<S> {
+    /// Create a new [`IpFilterService`] using the given [`IpFilter`].
+    pub fn new(inner: S, filter: IpFilter) -> Self {
+        Self {
+            inner,
+            filter: Arc::new(RwLock::new(filter)),
+        }
+    }
+
+    /// Replace the [`IpFilter`] used by this service and all its clones.
+    pub fn reload(&self, filter: IpFilter) {
+        *self.filter.write() = filter;
+    }
+
+    define_inner_service_accessors!();
+
+    fn is_allowed(&self, ip: Option<IpAddr>) -> bool {
+        let filter = self.filter.read();
+        match ip {
+            Some(ip) => filter.is_allowed(ip),
+            None => filter.is_unknown_allowed(),
+        }
+    }
+}
+
+impl<S, State, IO> Service<State, IO> for IpFilterService<S>
+where
+    S: Service<State, IO, Error: Into<BoxError>>,
+    State: Clone + Send + Sync + 'static,
+    IO: Socket,
+{
+    type Response = S::Response;
+    type Error = BoxError;
+
+    async fn serve(&self, ctx: Context<State>, stream: IO) -> Result<Self::Response, Self::Error> {
+        let peer_ip = stream.peer_addr().ok().map(|addr| addr.ip());
+        if !self.is_allowed(peer_ip) {
+            tracing::debug!(?peer_ip, "IpFilterService: close stream of denied peer");
+            return Err(IpNotAllowed::new().into());
+        }
+        self.inner.serve(ctx, stream).await.map_err(Into::into)
+    }
+}
+
+#[cfg(feature = "http")]
+impl<S, State, ReqBody> Service<State, Request<ReqBody>> for IpFilterService<S>
+where
+    S: Service<State, Request<ReqBody>, Response = Response>,
+    State: Clone + Send + Sync + 'static,
+    ReqBody: Send + 'static,
+{
+    type Response = Response;
+    type Error = S::Error;
+
+    async fn serve(
+        &self,
+        ctx: Context<State>,
+        req: Request<ReqBody>,
+    ) -> Result<Self::Response, Self::Error> {
+        let client_ip = ctx
+            .get::<Forwarded>()
+            .and_then(Forwarded::client_ip)
+            .or_else(|| ctx.get::<SocketInfo>().map(|info| info.peer_addr().ip()));
+        if !self.is_allowed(client_ip) {
+            tracing::debug!(
+                ?client_ip,
+                "IpFilterService: forbid request of denied client"
+            );
+            let mut res = Response::new(Body::empty());
+            *res.status_mut() = StatusCode::FORBIDDEN;
+            return Ok(res);
+        }
+        self.inner.serve(ctx, req).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stream::matcher::ip::IpNet;
+    use rama_core::service::service_fn;
+    use std::convert::Infallible;
+    use tokio::net::{TcpListener, TcpStream};
+
+    fn net(s: &str) -> IpNet {
+        s.parse().unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_ip_filter_stream() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        let layer = IpFilterLayer::new(IpFilter::new().with_allow(net("10.0.0.0/8")));
+        let svc = layer.layer(service_fn(async |_: TcpStream| Ok::<_, Infallible>(())));
+
+        let _client = TcpStream::connect(addr).await.unwrap();
+        let (stream, _) = listener.accept().await.unwrap();
+        let err = svc.serve(Context::default(), stream).await.unwrap_err();
+        assert!(err.is::<IpNotAllowed>());
+
+        layer.reload(IpFilter::new().with_allow(net("127.0.0.0/8")));
+
+        let _client = TcpStream::connect(addr).await.unwrap();
+        let (stream, _) = listener.accept().await.unwrap();
+        svc.serve(Context::default(), stream).await.unwrap();
+    }
+
+    #[cfg(feature = "http")]
+    #[tokio::test]
+    async fn test_ip_filter_http() {
+        let svc = IpFilterLayer::new(IpFilter::new().with_deny(net("::1/128"))).into_layer(
+            service_fn(async |_: Request<()>| Ok::<_, Infallible>(Response::new(Body::empty()))),
+        );
+
+        for (peer, forwarded, expected) in [
+            ("[::1]:8080", None, StatusCode::FORBIDDEN),
+            ("[::2]:8080", None, StatusCode::OK),
+            ("[::2]:8080", Some("for=\"[::1]\""), StatusCode::FORBIDDEN),
+            ("[::1]:8080", Some("for=192.168.0.1"), StatusCode::OK),
+        ] {
+            let mut ctx = Context::default();
+            ctx.insert(SocketInfo::new(None, peer.parse().unwrap()));
+            if let Some(forwarded) = forwarded {
+                ctx.insert(forwarded.parse::<Forwarded>().unwrap());
+            }
+            let res = svc.serve(ctx, Request::new(())).await.unwrap();
+            assert_eq!(res.status(), expected, "{peer} {forwarded:?}");
+        }
+    }
+
+    #[cfg(feature = "http")]
+    #[tokio::test]
+    async fn test_ip_filter_http_unknown_ip() {
+        let svc = IpFilterService::new(
+            service_fn(async |_: Request<()>| Ok::<_, Infallible>(Response::new(Body::empty()))),
+            IpFilter::new().with_deny(net("10.0.0.0/8")),
+        );
+        let res = svc
+            .serve(Context::default(), Request::new(()))
+            .await
+            .unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+
+        svc.reload(IpFilter::new().with_allow(net("10.0.0.0/8")));
+        let res = svc
+            .serve(Context::default(), Request::new(()))
+            .await
+            .unwrap();
+        assert_eq!(res.status(), StatusCode::FORBIDDEN);
+    }
+}
@@ -0,0 +1,8 @@
+//! Rama middleware operating on network information,
+//! such as the IP address of the peer.
+//!
+//! These layers can be used for streams (e.g. `TCP`) as well as for `HTTP` services.
+
+pub mod ip_filter;
+#[doc(inline)]
+pub use ip_filter::{IpFilter, IpFilterLayer, IpFilterService};
@@ -21,6 +21,7 @@ pub mod asn;
 pub mod client;
 pub mod conn;
 pub mod forwarded;
+pub mod layer;
 pub mod mode;
 pub mod proxy;
 pub mod stream;
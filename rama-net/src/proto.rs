@@ -70,6 +70,15 @@ enum ProtocolKind {
     /// (Websocket over HTTPS)
     /// <https://datatracker.ietf.org/doc/html/rfc6455>
     Wss,
+    /// The `socks4` protocol.
+    ///
+    /// <https://www.openssh.com/txt/socks4.protocol>
+    Socks4,
+    /// The `socks4a` protocol.
+    ///
+    /// An extension of [`Self::Socks4`] where the proxy resolves the hostname.
+    /// <https://www.openssh.com/txt/socks4a.protocol>
+    Socks4a,
     /// The `socks5` protocol.
     ///
     /// <https://datatracker.ietf.org/doc/html/rfc1928>
@@ -87,6 +96,8 @@ enum ProtocolKind {
 
 const SCHEME_HTTP: &str = "http";
 const SCHEME_HTTPS: &str = "https";
+const SCHEME_SOCKS4: &str = "socks4";
+const SCHEME_SOCKS4A: &str = "socks4a";
 const SCHEME_SOCKS5: &str = "socks5";
 const SCHEME_SOCKS5H: &str = "socks5h";
 const SCHEME_WS: &str = "ws";
@@ -105,6 +116,12 @@ impl Protocol {
     /// `WSS` protocol.
     pub const WSS: Self = Protocol(ProtocolKind::Wss);
 
+    /// `SOCKS4` protocol.
+    pub const SOCKS4: Self = Protocol(ProtocolKind::Socks4);
+
+    /// `SOCKS4A` protocol.
+    pub const SOCKS4A: Self = Protocol(ProtocolKind::Socks4a);
+
     /// `SOCKS5` protocol.
     pub const SOCKS5: Self = Protocol(ProtocolKind::Socks5);
 
@@ -130,6 +147,10 @@ impl Protocol {
             ProtocolKind::Https
         } else if s.is_empty() || eq_ignore_ascii_case!(s, SCHEME_HTTP) {
             ProtocolKind::Http
+        } else if eq_ignore_ascii_case!(s, SCHEME_SOCKS4) {
+            ProtocolKind::Socks4
+        } else if eq_ignore_ascii_case!(s, SCHEME_SOCKS4A) {
+            ProtocolKind::Socks4a
         } else if eq_ignore_ascii_case!(s, SCHEME_SOCKS5) {
             ProtocolKind::Socks5
         } else if eq_ignore_ascii_case!(s, SCHEME_SOCKS5H) {
@@ -151,6 +172,8 @@ impl Protocol {
             ProtocolKind::Http | ProtocolKind::Https => true,
             ProtocolKind::Ws
             | ProtocolKind::Wss
+            | ProtocolKind::Socks4
+            | ProtocolKind::Socks4a
             | ProtocolKind::Socks5
             | ProtocolKind::Socks5h
             | ProtocolKind::Custom(_) => false,
@@ -163,6 +186,22 @@ impl Protocol {
             ProtocolKind::Ws | ProtocolKind::Wss => true,
             ProtocolKind::Http
             | ProtocolKind::Https
+            | ProtocolKind::Socks4
+            | ProtocolKind::Socks4a
+            | ProtocolKind::Socks5
+            | ProtocolKind::Socks5h
+            | ProtocolKind::Custom(_) => false,
+        }
+    }
+
+    /// Returns `true` if this protocol is socks4(a).
+    pub fn is_socks4(&self) -> bool {
+        match &self.0 {
+            ProtocolKind::Socks4 | ProtocolKind::Socks4a => true,
+            ProtocolKind::Http
+            | ProtocolKind::Https
+            | ProtocolKind::Ws
+            | ProtocolKind::Wss
             | ProtocolKind::Socks5
             | ProtocolKind::Socks5h
             | ProtocolKind::Custom(_) => false,
@@ -177,6 +216,8 @@ impl Protocol {
             | ProtocolKind::Https
             | ProtocolKind::Ws
             | ProtocolKind::Wss
+            | ProtocolKind::Socks4
+            | ProtocolKind::Socks4a
             | ProtocolKind::Custom(_) => false,
         }
     }
@@ -187,6 +228,8 @@ impl Protocol {
             ProtocolKind::Https | ProtocolKind::Wss => true,
             ProtocolKind::Ws
             | ProtocolKind::Http
+            | ProtocolKind::Socks4
+            | ProtocolKind::Socks4a
             | ProtocolKind::Socks5
             | ProtocolKind::Socks5h
             | ProtocolKind::Custom(_) => false,
@@ -198,7 +241,10 @@ impl Protocol {
         match &self.0 {
             ProtocolKind::Https | ProtocolKind::Wss => Some(443),
             ProtocolKind::Http | ProtocolKind::Ws => Some(80),
-            ProtocolKind::Socks5 | ProtocolKind::Socks5h => Some(1080),
+            ProtocolKind::Socks4
+            | ProtocolKind::Socks4a
+            | ProtocolKind::Socks5
+            | ProtocolKind::Socks5h => Some(1080),
             ProtocolKind::Custom(_) => None,
         }
     }
@@ -210,6 +256,8 @@ impl Protocol {
             ProtocolKind::Https => "https",
             ProtocolKind::Ws => "ws",
             ProtocolKind::Wss => "wss",
+            ProtocolKind::Socks4 => "socks4",
+            ProtocolKind::Socks4a => "socks4a",
             ProtocolKind::Socks5 => "socks5",
             ProtocolKind::Socks5h => "socks5h",
             ProtocolKind::Custom(s) => s.as_ref(),
@@ -229,6 +277,10 @@ fn try_to_convert_str_to_non_custom_protocol(
         ProtocolKind::Https
     } else if s.is_empty() || eq_ignore_ascii_case!(s, SCHEME_HTTP) {
         ProtocolKind::Http
+    } else if eq_ignore_ascii_case!(s, SCHEME_SOCKS4) {
+        ProtocolKind::Socks4
+    } else if eq_ignore_ascii_case!(s, SCHEME_SOCKS4A) {
+        ProtocolKind::Socks4a
     } else if eq_ignore_ascii_case!(s, SCHEME_SOCKS5) {
         ProtocolKind::Socks5
     } else if eq_ignore_ascii_case!(s, SCHEME_SOCKS5H) {
@@ -303,6 +355,8 @@ impl PartialEq<str> for Protocol {
         match &self.0 {
             ProtocolKind::Https => other.eq_ignore_ascii_case(SCHEME_HTTPS),
             ProtocolKind::Http => other.eq_ignore_ascii_case(SCHEME_HTTP) || other.is_empty(),
+            ProtocolKind::Socks4 => other.eq_ignore_ascii_case(SCHEME_SOCKS4),
+            ProtocolKind::Socks4a => other.eq_ignore_ascii_case(SCHEME_SOCKS4A),
             ProtocolKind::Socks5 => other.eq_ignore_ascii_case(SCHEME_SOCKS5),
             ProtocolKind::Socks5h => other.eq_ignore_ascii_case(SCHEME_SOCKS5H),
             ProtocolKind::Ws => other.eq_ignore_ascii_case("ws"),
@@ -455,6 +509,8 @@ mod tests {
         assert_eq!("https".parse(), Ok(Protocol::HTTPS));
         assert_eq!("ws".parse(), Ok(Protocol::WS));
         assert_eq!("wss".parse(), Ok(Protocol::WSS));
+        assert_eq!("socks4".parse(), Ok(Protocol::SOCKS4));
+        assert_eq!("socks4a".parse(), Ok(Protocol::SOCKS4A));
         assert_eq!("socks5".parse(), Ok(Protocol::SOCKS5));
         assert_eq!("socks5h".parse(), Ok(Protocol::SOCKS5H));
         assert_eq!("custom".parse(), Ok(Protocol::from_static("custom")));
@@ -464,7 +520,7 @@ mod tests {
     #[test]
     fn test_from_http_scheme() {
         for s in [
-            "http", "https", "ws", "wss", "socks5", "socks5h", "", "custom",
+            "http", "https", "ws", "wss", "socks4", "socks4a", "socks5", "socks5h", "", "custom",
         ]
         .iter()
         {
@@ -478,6 +534,8 @@ mod tests {
     fn test_scheme_is_secure() {
         assert!(!Protocol::HTTP.is_secure());
         assert!(Protocol::HTTPS.is_secure());
+        assert!(!Protocol::SOCKS4.is_secure());
+        assert!(!Protocol::SOCKS4A.is_secure());
         assert!(!Protocol::SOCKS5.is_secure());
         assert!(!Protocol::SOCKS5H.is_secure());
         assert!(!Protocol::WS.is_secure());
@@ -493,6 +551,8 @@ mod tests {
             ("https://example.com", Some((Some(Protocol::HTTPS), 8))),
             ("ws://example.com", Some((Some(Protocol::WS), 5))),
             ("wss://example.com", Some((Some(Protocol::WSS), 6))),
+            ("socks4://example.com", Some((Some(Protocol::SOCKS4), 9))),
+            ("socks4a://example.com", Some((Some(Protocol::SOCKS4A), 10))),
             ("socks5://example.com", Some((Some(Protocol::SOCKS5), 9))),
             ("socks5h://example.com", Some((Some(Protocol::SOCKS5H), 10))),
             (
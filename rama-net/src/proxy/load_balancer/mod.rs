@@ -0,0 +1,57 @@
+//! Middleware that distributes requests across a set of backends.
+//!
+//! The [`LoadBalancerService`] selects one of its backends using a [`LoadBalancePolicy`],
+//! and inserts its address as the [`ProxyTarget`] in the [`Context`], such that
+//! it can be used by a forwarder (e.g. `rama::tcp::client::service::Forwarder::ctx`)
+//! to connect to it.
+//!
+//! The following policies are available:
+//!
+//! - [`RoundRobin`]: selects the backends one after the other;
+//! - [`Random`]: selects a random backend;
+//! - [`LeastConnections`]: selects the backend with the least in-flight requests.
+//!
+//! Backends can be added and removed at runtime using
+//! [`LoadBalancerLayer::add_backend`] and [`LoadBalancerLayer::remove_backend`],
+//! affecting all services created by the layer (and their clones).
+//!
+//! # Example
+//!
+//! ```
+//! use rama_core::service::service_fn;
+//! use rama_core::{Context, Layer, Service};
+//! use rama_net::proxy::ProxyTarget;
+//! use rama_net::proxy::load_balancer::LoadBalancerLayer;
+//! use std::convert::Infallible;
+//!
+//! # #[tokio::main]
+//! # async fn main() {
+//! let layer = LoadBalancerLayer::round_robin([
+//!     "127.0.0.1:8081".parse().unwrap(),
+//!     "127.0.0.1:8082".parse().unwrap(),
+//! ]);
+//! let svc = layer.layer(service_fn(async |ctx: Context<()>, _req: ()| {
+//!     Ok::<_, Infallible>(ctx.get::<ProxyTarget>().unwrap().0.to_string())
+//! }));
+//!
+//! assert_eq!(svc.serve(Context::default(), ()).await.unwrap(), "127.0.0.1:8081");
+//! assert_eq!(svc.serve(Context::default(), ()).await.unwrap(), "127.0.0.1:8082");
+//!
+//! layer.remove_backend("127.0.0.1:8081".parse().unwrap());
+//! assert_eq!(svc.serve(Context::default(), ()).await.unwrap(), "127.0.0.1:8082");
+//! # }
+//! ```
+//!
+//! [`ProxyTarget`]: super::ProxyTarget
+//! [`Context`]: rama_core::Context
+
+mod policy;
+mod service;
+
+#[doc(inline)]
+pub use policy::{Backend, LeastConnections, LoadBalancePolicy, Random, RoundRobin};
+#[doc(inline)]
+pub use service::{LoadBalancerLayer, LoadBalancerService};
+
+#[cfg(test)]
+mod tests;
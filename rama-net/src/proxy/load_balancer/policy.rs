@@ -0,0 +1,134 @@
+use rand::Rng;
+use std::fmt;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+#[derive(Clone)]
+/// A backend of the [`LoadBalancerService`], as seen by a [`LoadBalancePolicy`].
+///
+/// [`LoadBalancerService`]: super::LoadBalancerService
+pub struct Backend {
+    addr: SocketAddr,
+    in_flight: Arc<AtomicUsize>,
+}
+
+impl Backend {
+    pub(super) fn new(addr: SocketAddr) -> Self {
+        Self {
+            addr,
+            in_flight: Arc::new(AtomicUsize::new(0)),
+        }
+    }
+
+    /// The address of this [`Backend`].
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// The amount of requests currently in-flight for this [`Backend`].
+    pub fn in_flight(&self) -> usize {
+        self.in_flight.load(Ordering::Acquire)
+    }
+
+    pub(super) fn in_flight_guard(&self) -> InFlightGuard {
+        self.in_flight.fetch_add(1, Ordering::AcqRel);
+        InFlightGuard(self.in_flight.clone())
+    }
+}
+
+impl fmt::Debug for Backend {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Backend")
+            .field("addr", &self.addr)
+            .field("in_flight", &self.in_flight())
+            .finish()
+    }
+}
+
+/// Decrements the in-flight counter of a [`Backend`] when dropped.
+pub(super) struct InFlightGuard(Arc<AtomicUsize>);
+
+impl Drop for InFlightGuard {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+/// A policy used by the [`LoadBalancerService`] to select a [`Backend`].
+///
+/// [`LoadBalancerService`]: super::LoadBalancerService
+pub trait LoadBalancePolicy: Send + Sync + 'static {
+    /// Select the index of the [`Backend`] to use,
+    /// or `None` in case no backend can be selected.
+    fn select(&self, backends: &[Backend]) -> Option<usize>;
+}
+
+#[derive(Debug, Default)]
+/// [`LoadBalancePolicy`] which selects the backends one after the other.
+pub struct RoundRobin {
+    next: AtomicUsize,
+}
+
+impl RoundRobin {
+    /// Create a new [`RoundRobin`] policy.
+    pub const fn new() -> Self {
+        Self {
+            next: AtomicUsize::new(0),
+        }
+    }
+}
+
+impl LoadBalancePolicy for RoundRobin {
+    fn select(&self, backends: &[Backend]) -> Option<usize> {
+        if backends.is_empty() {
+            return None;
+        }
+        Some(self.next.fetch_add(1, Ordering::Relaxed) % backends.len())
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+#[non_exhaustive]
+/// [`LoadBalancePolicy`] which selects a random backend.
+pub struct Random;
+
+impl Random {
+    /// Create a new [`Random`] policy.
+    pub const fn new() -> Self {
+        Self
+    }
+}
+
+impl LoadBalancePolicy for Random {
+    fn select(&self, backends: &[Backend]) -> Option<usize> {
+        if backends.is_empty() {
+            return None;
+        }
+        Some(rand::rng().random_range(0..backends.len()))
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+#[non_exhaustive]
+/// [`LoadBalancePolicy`] which selects the backend with the least in-flight requests.
+///
+/// In case of a tie the backend added first is selected.
+pub struct LeastConnections;
+
+impl LeastConnections {
+    /// Create a new [`LeastConnections`] policy.
+    pub const fn new() -> Self {
+        Self
+    }
+}
+
+impl LoadBalancePolicy for LeastConnections {
+    fn select(&self, backends: &[Backend]) -> Option<usize> {
+        backends
+            .iter()
+            .enumerate()
+            .min_by_key(|(_, backend)| backend.in_flight())
+            .map(|(index, _)| index)
+    }
+}
@@ -0,0 +1,228 @@
+use super::{Backend, LeastConnections, LoadBalancePolicy, Random, RoundRobin};
+use crate::proxy::ProxyTarget;
+use parking_lot::RwLock;
+use rama_core::error::{BoxError, OpaqueError};
+use rama_core::telemetry::tracing;
+use rama_core::{Context, Layer, Service};
+use rama_utils::macros::define_inner_service_accessors;
+use std::fmt;
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+#[derive(Debug, Default)]
+struct Backends(RwLock<Vec<Backend>>);
+
+impl Backends {
+    fn new(addrs: impl IntoIterator<Item = SocketAddr>) -> Self {
+        let mut backends: Vec<Backend> = Vec::new();
+        for addr in addrs {
+            if !backends.iter().any(|backend| backend.addr() == addr) {
+                backends.push(Backend::new(addr));
+            }
+        }
+        Self(RwLock::new(backends))
+    }
+
+    fn add(&self, addr: SocketAddr) -> bool {
+        let mut backends = self.0.write();
+        if backends.iter().any(|backend| backend.addr() == addr) {
+            return false;
+        }
+        backends.push(Backend::new(addr));
+        true
+    }
+
+    fn remove(&self, addr: SocketAddr) -> bool {
+        let mut backends = self.0.write();
+        let len = backends.len();
+        backends.retain(|backend| backend.addr() != addr);
+        backends.len() != len
+    }
+
+    fn addrs(&self) -> Vec<SocketAddr> {
+        self.0.read().iter().map(Backend::addr).collect()
+    }
+
+    fn select(&self, policy: &impl LoadBalancePolicy) -> Option<Backend> {
+        let backends = self.0.read();
+        policy
+            .select(&backends)
+            .and_then(|index| backends.get(index).cloned())
+    }
+}
+
+macro_rules! impl_backend_management {
+    () => {
+        /// Add a backend, returning `false` if it was already present.
+        ///
+        /// The backend is added to all layers and services
+        /// sharing the same backends, without requiring a restart.
+        pub fn add_backend(&self, addr: SocketAddr) -> bool {
+            self.backends.add(addr)
+        }
+
+        /// Remove a backend, returning `false` if it was not present.
+        ///
+        /// In-flight requests to the removed backend are not interrupted.
+        pub fn remove_backend(&self, addr: SocketAddr) -> bool {
+            self.backends.remove(addr)
+        }
+
+        /// The addresses of the current backends, in the order they were added.
+        pub fn backends(&self) -> Vec<SocketAddr> {
+            self.backends.addrs()
+        }
+    };
+}
+
+/// Layer that applies the [`LoadBalancerService`] middleware.
+///
+/// See the [module docs](super) for more details.
+pub struct LoadBalancerLayer<P> {
+    backends: Arc<Backends>,
+    policy: Arc<P>,
+}
+
+impl<P> LoadBalancerLayer<P> {
+    /// Create a new [`LoadBalancerLayer`] for the given backends,
+    /// selected using the given [`LoadBalancePolicy`].
+    pub fn new(backends: impl IntoIterator<Item = SocketAddr>, policy: P) -> Self {
+        Self {
+            backends: Arc::new(Backends::new(backends)),
+            policy: Arc::new(policy),
+        }
+    }
+
+    impl_backend_management!();
+}
+
+impl LoadBalancerLayer<RoundRobin> {
+    /// Create a new [`LoadBalancerLayer`] using the [`RoundRobin`] policy.
+    pub fn round_robin(backends: impl IntoIterator<Item = SocketAddr>) -> Self {
+        Self::new(backends, RoundRobin::new())
+    }
+}
+
+impl LoadBalancerLayer<Random> {
+    /// Create a new [`LoadBalancerLayer`] using the [`Random`] policy.
+    pub fn random(backends: impl IntoIterator<Item = SocketAddr>) -> Self {
+        Self::new(backends, Random::new())
+    }
+}
+
+impl LoadBalancerLayer<LeastConnections> {
+    /// Create a new [`LoadBalancerLayer`] using the [`LeastConnections`] policy.
+    pub fn least_connections(backends: impl IntoIterator<Item = SocketAddr>) -> Self {
+        Self::new(backends, LeastConnections::new())
+    }
+}
+
+impl<P: fmt::Debug> fmt::Debug for LoadBalancerLayer<P> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LoadBalancerLayer")
+            .field("backends", &self.backends)
+            .field("policy", &self.policy)
+            .finish()
+    }
+}
+
+impl<P> Clone for LoadBalancerLayer<P> {
+    fn clone(&self) -> Self {
+        Self {
+            backends: self.backends.clone(),
+            policy: self.policy.clone(),
+        }
+    }
+}
+
+impl<S, P> Layer<S> for LoadBalancerLayer<P> {
+    type Service = LoadBalancerService<S, P>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        LoadBalancerService {
+            inner,
+            backends: self.backends.clone(),
+            policy: self.policy.clone(),
+        }
+    }
+
+    fn into_layer(self, inner: S) -> Self::Service {
+        LoadBalancerService {
+            inner,
+            backends: self.backends,
+            policy: self.policy,
+        }
+    }
+}
+
+/// Middleware which selects a backend using a [`LoadBalancePolicy`]
+/// and inserts it as the [`ProxyTarget`] in the [`Context`].
+///
+/// See the [module docs](super) for more details.
+pub struct LoadBalancerService<S, P> {
+    inner: S,
+    backends: Arc<Backends>,
+    policy: Arc<P>,
+}
+
+impl<S, P> LoadBalancerService<S, P> {
+    /// Create a new [`LoadBalancerService`] for the given backends,
+    /// selected using the given [`LoadBalancePolicy`].
+    pub fn new(inner: S, backends: impl IntoIterator<Item = SocketAddr>, policy: P) -> Self {
+        Self {
+            inner,
+            backends: Arc::new(Backends::new(backends)),
+            policy: Arc::new(policy),
+        }
+    }
+
+    define_inner_service_accessors!();
+
+    impl_backend_management!();
+}
+
+impl<S: fmt::Debug, P: fmt::Debug> fmt::Debug for LoadBalancerService<S, P> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LoadBalancerService")
+            .field("inner", &self.inner)
+            .field("backends", &self.backends)
+            .field("policy", &self.policy)
+            .finish()
+    }
+}
+
+impl<S: Clone, P> Clone for LoadBalancerService<S, P> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            backends: self.backends.clone(),
+            policy: self.policy.clone(),
+        }
+    }
+}
+
+impl<State, S, P, Request> Service<State, Request> for LoadBalancerService<S, P>
+where
+    State: Clone + Send + Sync + 'static,
+    S: Service<State, Request, Error: Into<BoxError>>,
+    P: LoadBalancePolicy,
+    Request: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = BoxError;
+
+    async fn serve(
+        &self,
+        mut ctx: Context<State>,
+        req: Request,
+    ) -> Result<Self::Response, Self::Error> {
+        let backend = self.backends.select(self.policy.as_ref()).ok_or_else(|| {
+            OpaqueError::from_display("load balancer: no backend available").into_boxed()
+        })?;
+        tracing::trace!("load balancer: selected backend {}", backend.addr());
+
+        let _guard = backend.in_flight_guard();
+        ctx.insert(ProxyTarget(backend.addr().into()));
+        self.inner.serve(ctx, req).await.map_err(Into::into)
+    }
+}
@@ -0,0 +1,99 @@
+use super::*;
+
+use crate::proxy::ProxyTarget;
+use rama_core::service::service_fn;
+use rama_core::{Context, Layer, Service};
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use tokio::sync::Notify;
+
+fn addr(port: u16) -> SocketAddr {
+    SocketAddr::from(([127, 0, 0, 1], port))
+}
+
+fn echo_target() -> impl Service<(), (), Response = SocketAddr, Error = Infallible> + Clone {
+    service_fn(async |ctx: Context<()>, _req: ()| {
+        let target = ctx.get::<ProxyTarget>().unwrap();
+        Ok::<_, Infallible>(SocketAddr::new(
+            target.0.host().to_string().parse().unwrap(),
+            target.0.port(),
+        ))
+    })
+}
+
+#[tokio::test]
+async fn round_robin() {
+    let layer = LoadBalancerLayer::round_robin([addr(1), addr(2), addr(3)]);
+    let svc = layer.layer(echo_target());
+
+    let mut selected = Vec::new();
+    for _ in 0..6 {
+        selected.push(svc.serve(Context::default(), ()).await.unwrap());
+    }
+    assert_eq!(
+        selected,
+        [addr(1), addr(2), addr(3), addr(1), addr(2), addr(3)]
+    );
+}
+
+#[tokio::test]
+async fn random() {
+    let svc = LoadBalancerLayer::random([addr(1), addr(2)]).into_layer(echo_target());
+    for _ in 0..16 {
+        let selected = svc.serve(Context::default(), ()).await.unwrap();
+        assert!(selected == addr(1) || selected == addr(2), "{selected}");
+    }
+}
+
+#[tokio::test]
+async fn least_connections() {
+    let release = Arc::new(Notify::new());
+    let svc_release = release.clone();
+    let svc = LoadBalancerLayer::least_connections([addr(1), addr(2)]).into_layer(service_fn(
+        move |ctx: Context<()>, wait: bool| {
+            let release = svc_release.clone();
+            async move {
+                if wait {
+                    release.notified().await;
+                }
+                Ok::<_, Infallible>(ctx.get::<ProxyTarget>().unwrap().0.port())
+            }
+        },
+    ));
+
+    // keep a request in-flight for the first backend
+    let in_flight = tokio::spawn({
+        let svc = svc.clone();
+        async move { svc.serve(Context::default(), true).await.unwrap() }
+    });
+    tokio::task::yield_now().await;
+
+    for _ in 0..3 {
+        assert_eq!(svc.serve(Context::default(), false).await.unwrap(), 2);
+    }
+
+    release.notify_one();
+    assert_eq!(in_flight.await.unwrap(), 1);
+    assert_eq!(svc.serve(Context::default(), false).await.unwrap(), 1);
+}
+
+#[tokio::test]
+async fn add_and_remove_backends() {
+    let layer = LoadBalancerLayer::round_robin([addr(1), addr(1)]);
+    let svc = layer.layer(echo_target());
+    assert_eq!(layer.backends(), [addr(1)]);
+
+    assert!(layer.add_backend(addr(2)));
+    assert!(!svc.add_backend(addr(2)));
+    assert_eq!(svc.backends(), [addr(1), addr(2)]);
+
+    assert!(svc.remove_backend(addr(1)));
+    assert!(!layer.remove_backend(addr(1)));
+    for _ in 0..3 {
+        assert_eq!(svc.serve(Context::default(), ()).await.unwrap(), addr(2));
+    }
+
+    assert!(layer.remove_backend(addr(2)));
+    assert!(svc.serve(Context::default(), ()).await.is_err());
+}
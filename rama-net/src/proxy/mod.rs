@@ -10,6 +10,12 @@ mod forward;
 #[doc(inline)]
 pub use forward::StreamForwardService;
 
+mod zero_copy;
+#[doc(inline)]
+pub use zero_copy::{ZeroCopyProxyLayer, ZeroCopyProxyService};
+
+pub mod load_balancer;
+
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 /// Target [`Authority`] for a proxy/forwarder service.
 pub struct ProxyTarget(pub Authority);
@@ -0,0 +1,168 @@
+use rama_core::telemetry::tracing;
+use rama_core::{
+    Context, Layer, Service,
+    error::{BoxError, ErrorExt},
+};
+use rama_utils::macros::define_inner_service_accessors;
+use std::fmt;
+
+use crate::stream::{Stream, ZeroCopyForwarder};
+
+use super::ProxyRequest;
+
+/// A proxy [`Service`] which forwards a [`ProxyRequest`] using the
+/// [`ZeroCopyForwarder`] when both the source and target [`Stream`]s
+/// support it, and delegates to the inner [`Service`] otherwise.
+///
+/// Zero-copy forwarding is only applicable when the bytes do not need to pass
+/// through userspace, meaning both streams are raw TCP streams, not wrapped by
+/// TLS, byte trackers or any other transforming stream. The inner [`Service`]
+/// is used for all other proxy requests, e.g. a [`StreamForwardService`].
+///
+/// [`StreamForwardService`]: super::StreamForwardService
+pub struct ZeroCopyProxyService<S> {
+    inner: S,
+    forwarder: ZeroCopyForwarder,
+}
+
+impl<S: fmt::Debug> fmt::Debug for ZeroCopyProxyService<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ZeroCopyProxyService")
+            .field("inner", &self.inner)
+            .field("forwarder", &self.forwarder)
+            .finish()
+    }
+}
+
+impl<S> ZeroCopyProxyService<S> {
+    /// Create a new [`ZeroCopyProxyService`].
+    ///
+    /// See [`ZeroCopyProxyService`] for more information.
+    pub const fn new(inner: S) -> Self {
+        Self {
+            inner,
+            forwarder: ZeroCopyForwarder::new(),
+        }
+    }
+
+    define_inner_service_accessors!();
+}
+
+impl<S> Clone for ZeroCopyProxyService<S>
+where
+    S: Clone,
+{
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            forwarder: self.forwarder.clone(),
+        }
+    }
+}
+
+impl<State, S, Source, Target> Service<State, ProxyRequest<Source, Target>>
+    for ZeroCopyProxyService<S>
+where
+    State: Clone + Send + Sync + 'static,
+    S: Service<State, ProxyRequest<Source, Target>, Response = (), Error: Into<BoxError>>,
+    Source: Stream + Unpin,
+    Target: Stream + Unpin,
+{
+    type Response = ();
+    type Error = BoxError;
+
+    async fn serve(
+        &self,
+        ctx: Context<State>,
+        ProxyRequest {
+            mut source,
+            mut target,
+        }: ProxyRequest<Source, Target>,
+    ) -> Result<Self::Response, Self::Error> {
+        if !ZeroCopyForwarder::is_supported(&source, &target) {
+            return self
+                .inner
+                .serve(ctx, ProxyRequest { source, target })
+                .await
+                .map_err(Into::into);
+        }
+
+        match self.forwarder.forward(&mut source, &mut target).await {
+            Ok((bytes_copied_north, bytes_copied_south)) => {
+                tracing::trace!(
+                    "(proxy) zero-copy stream forwarder finished: bytes north: {}; bytes south: {}",
+                    bytes_copied_north,
+                    bytes_copied_south,
+                );
+                Ok(())
+            }
+            Err(err) => {
+                if crate::conn::is_connection_error(&err) {
+                    Ok(())
+                } else {
+                    Err(err.context("(proxy) zero-copy stream forwarder").into())
+                }
+            }
+        }
+    }
+}
+
+/// A [`Layer`] that produces a [`ZeroCopyProxyService`],
+/// forwarding raw TCP proxy requests without copying through userspace.
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct ZeroCopyProxyLayer;
+
+impl ZeroCopyProxyLayer {
+    /// Create a new [`ZeroCopyProxyLayer`].
+    pub const fn new() -> Self {
+        Self
+    }
+}
+
+impl Default for ZeroCopyProxyLayer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<S> Layer<S> for ZeroCopyProxyLayer {
+    type Service = ZeroCopyProxyService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        ZeroCopyProxyService::new(inner)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proxy::StreamForwardService;
+    use rama_core::service::service_fn;
+    use std::sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    };
+
+    #[tokio::test]
+    async fn test_fallback_to_inner_service() {
+        let called = Arc::new(AtomicBool::new(false));
+        let svc = ZeroCopyProxyLayer::new().into_layer(service_fn({
+            let called = called.clone();
+            move |ctx, req| {
+                let called = called.clone();
+                async move {
+                    called.store(true, Ordering::SeqCst);
+                    StreamForwardService::new().serve(ctx, req).await
+                }
+            }
+        }));
+
+        let source = tokio_test::io::Builder::new().read(b"ping").build();
+        let target = tokio_test::io::Builder::new().write(b"ping").build();
+        svc.serve(Context::default(), ProxyRequest { source, target })
+            .await
+            .unwrap();
+        assert!(called.load(Ordering::SeqCst));
+    }
+}
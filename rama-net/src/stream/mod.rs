@@ -17,6 +17,10 @@ pub use peek::PeekStream;
 
 pub mod rewind;
 
+mod zero_copy;
+#[doc(inline)]
+pub use zero_copy::ZeroCopyForwarder;
+
 /// A stream is a type that implements `AsyncRead`, `AsyncWrite` and `Send`.
 /// This is specific to Rama and is directly linked to the supertraits of `Tokio`.
 pub trait Stream: AsyncRead + AsyncWrite + Send + 'static {}
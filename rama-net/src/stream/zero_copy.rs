@@ -0,0 +1,252 @@
+//! Zero-copy forwarding of bytes between two [`Stream`]s.
+
+use crate::stream::Stream;
+use std::any::Any;
+use tokio::net::TcpStream;
+
+/// Forwards bytes bidirectionally between two [`Stream`]s,
+/// without copying them through userspace buffers where possible.
+///
+/// When both streams are raw [`TcpStream`]s and the target OS is Linux,
+/// the bytes are moved from one socket to the other via an in-kernel pipe
+/// using `splice(2)`. In all other cases (e.g. TLS streams or wrapped
+/// streams) it falls back to [`tokio::io::copy_bidirectional`].
+///
+/// Use [`ZeroCopyForwarder::is_supported`] to check upfront
+/// if the zero-copy path would be used for a pair of streams.
+///
+/// # Example
+///
+/// ```rust
+/// use rama_net::stream::ZeroCopyForwarder;
+/// use tokio::net::{TcpListener, TcpStream};
+///
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// # let listener = TcpListener::bind("127.0.0.1:0").await?;
+/// # let addr = listener.local_addr()?;
+/// # let (source, (_, _)) = tokio::try_join!(TcpStream::connect(addr), listener.accept())?;
+/// # let (target, (_, _)) = tokio::try_join!(TcpStream::connect(addr), listener.accept())?;
+/// assert_eq!(
+///     ZeroCopyForwarder::is_supported(&source, &target),
+///     cfg!(target_os = "linux"),
+/// );
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct ZeroCopyForwarder {
+    _phantom: (),
+}
+
+impl ZeroCopyForwarder {
+    /// Creates a new [`ZeroCopyForwarder`].
+    pub const fn new() -> Self {
+        Self { _phantom: () }
+    }
+
+    /// Returns `true` if bytes between the given streams
+    /// can be forwarded without copying them through userspace.
+    pub fn is_supported<S: Stream, T: Stream>(source: &S, target: &T) -> bool {
+        cfg!(target_os = "linux")
+            && (source as &dyn Any).is::<TcpStream>()
+            && (target as &dyn Any).is::<TcpStream>()
+    }
+
+    /// Forwards bytes from the source to the target stream and vice versa,
+    /// until both directions reached EOF.
+    ///
+    /// Returns the bytes copied from source to target
+    /// and from target to source, similar to [`tokio::io::copy_bidirectional`].
+    pub async fn forward<S, T>(&self, source: &mut S, target: &mut T) -> std::io::Result<(u64, u64)>
+    where
+        S: Stream + Unpin,
+        T: Stream + Unpin,
+    {
+        #[cfg(target_os = "linux")]
+        if let (Some(source), Some(target)) = (
+            (source as &mut dyn Any).downcast_ref::<TcpStream>(),
+            (target as &mut dyn Any).downcast_ref::<TcpStream>(),
+        ) {
+            return splice::splice_bidirectional(source, target).await;
+        }
+
+        tokio::io::copy_bidirectional(source, target).await
+    }
+}
+
+impl Default for ZeroCopyForwarder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod splice {
+    use socket2::SockRef;
+    use std::{
+        io,
+        net::Shutdown,
+        os::fd::{AsRawFd, FromRawFd, OwnedFd, RawFd},
+    };
+    use tokio::{io::Interest, net::TcpStream};
+
+    /// Max amount of bytes moved per `splice(2)` call,
+    /// matching the default pipe capacity on Linux.
+    const PIPE_SIZE: usize = 64 * 1024;
+
+    pub(super) async fn splice_bidirectional(
+        source: &TcpStream,
+        target: &TcpStream,
+    ) -> io::Result<(u64, u64)> {
+        tokio::try_join!(splice_one(source, target), splice_one(target, source))
+    }
+
+    async fn splice_one(reader: &TcpStream, writer: &TcpStream) -> io::Result<u64> {
+        let pipe = Pipe::new()?;
+        let mut total = 0;
+
+        loop {
+            reader.readable().await?;
+            let n = match reader.try_io(Interest::READABLE, || {
+                splice(reader.as_raw_fd(), pipe.write.as_raw_fd(), PIPE_SIZE)
+            }) {
+                Ok(0) => break,
+                Ok(n) => n,
+                Err(err) if err.kind() == io::ErrorKind::WouldBlock => continue,
+                Err(err) => return Err(err),
+            };
+
+            let mut remaining = n;
+            while remaining > 0 {
+                writer.writable().await?;
+                match writer.try_io(Interest::WRITABLE, || {
+                    splice(pipe.read.as_raw_fd(), writer.as_raw_fd(), remaining)
+                }) {
+                    Ok(0) => return Err(io::ErrorKind::WriteZero.into()),
+                    Ok(m) => {
+                        remaining -= m;
+                        total += m as u64;
+                    }
+                    Err(err) if err.kind() == io::ErrorKind::WouldBlock => {}
+                    Err(err) => return Err(err),
+                }
+            }
+        }
+
+        SockRef::from(writer).shutdown(Shutdown::Write)?;
+        Ok(total)
+    }
+
+    fn splice(fd_in: RawFd, fd_out: RawFd, len: usize) -> io::Result<usize> {
+        // SAFETY: both file descriptors are valid for the duration of this call,
+        // and no offsets are passed as neither of them are seekable files.
+        let n = unsafe {
+            libc::splice(
+                fd_in,
+                std::ptr::null_mut(),
+                fd_out,
+                std::ptr::null_mut(),
+                len,
+                libc::SPLICE_F_MOVE | libc::SPLICE_F_NONBLOCK,
+            )
+        };
+        if n < 0 {
+            Err(io::Error::last_os_error())
+        } else {
+            Ok(n as usize)
+        }
+    }
+
+    /// Non-blocking in-kernel pipe, used as the intermediate
+    /// buffer between two sockets, as required by `splice(2)`.
+    struct Pipe {
+        read: OwnedFd,
+        write: OwnedFd,
+    }
+
+    impl Pipe {
+        fn new() -> io::Result<Self> {
+            let mut fds: [RawFd; 2] = [-1; 2];
+            // SAFETY: fds is a valid array of two file descriptors to be filled in.
+            if unsafe { libc::pipe2(fds.as_mut_ptr(), libc::O_NONBLOCK | libc::O_CLOEXEC) } < 0 {
+                return Err(io::Error::last_os_error());
+            }
+            // SAFETY: pipe2 succeeded, so both file descriptors are open and owned by us.
+            Ok(unsafe {
+                Self {
+                    read: OwnedFd::from_raw_fd(fds[0]),
+                    write: OwnedFd::from_raw_fd(fds[1]),
+                }
+            })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::TcpListener,
+    };
+
+    async fn tcp_pair() -> (TcpStream, TcpStream) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let (client, (server, _)) =
+            tokio::try_join!(TcpStream::connect(addr), listener.accept()).unwrap();
+        (client, server)
+    }
+
+    #[tokio::test]
+    async fn test_forward_tcp() {
+        let (mut client, mut source) = tcp_pair().await;
+        let (mut target, mut server) = tcp_pair().await;
+        assert_eq!(
+            ZeroCopyForwarder::is_supported(&source, &target),
+            cfg!(target_os = "linux")
+        );
+
+        let request = vec![1u8; 1024 * 1024];
+        let response = b"pong";
+
+        let forward = tokio::spawn(async move {
+            ZeroCopyForwarder::new()
+                .forward(&mut source, &mut target)
+                .await
+        });
+
+        let server = tokio::spawn(async move {
+            let mut buf = Vec::new();
+            server.read_to_end(&mut buf).await.unwrap();
+            server.write_all(response).await.unwrap();
+            buf
+        });
+
+        client.write_all(&request).await.unwrap();
+        client.shutdown().await.unwrap();
+        let mut buf = Vec::new();
+        client.read_to_end(&mut buf).await.unwrap();
+
+        assert_eq!(buf, response);
+        assert_eq!(server.await.unwrap(), request);
+        assert_eq!(
+            forward.await.unwrap().unwrap(),
+            (request.len() as u64, response.len() as u64)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_forward_fallback() {
+        let mut source = tokio_test::io::Builder::new().read(b"ping").build();
+        let mut target = tokio_test::io::Builder::new().write(b"ping").build();
+        assert!(!ZeroCopyForwarder::is_supported(&source, &target));
+
+        let (north, south) = ZeroCopyForwarder::new()
+            .forward(&mut source, &mut target)
+            .await
+            .unwrap();
+        assert_eq!((north, south), (4, 0));
+    }
+}
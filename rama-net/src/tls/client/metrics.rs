@@ -0,0 +1,58 @@
+use super::{ApplicationProtocol, ProtocolVersion};
+use rama_core::telemetry::tracing;
+use std::fmt;
+use std::time::Duration;
+
+#[derive(Debug, Clone)]
+/// Metrics of a single client TLS handshake,
+/// recorded by a tls connector using a [`TlsMetricsRecorder`].
+pub struct TlsHandshakeMetrics {
+    /// Time it took for the handshake to complete or fail.
+    pub duration: Duration,
+    /// Whether or not the handshake succeeded.
+    pub success: bool,
+    /// The negotiated [`ProtocolVersion`], only known if the handshake succeeded.
+    pub protocol_version: Option<ProtocolVersion>,
+    /// The negotiated [`ApplicationProtocol`] (ALPN),
+    /// only known if the handshake succeeded and a protocol was agreed upon.
+    pub application_layer_protocol: Option<ApplicationProtocol>,
+}
+
+/// Recorder of the [`TlsHandshakeMetrics`] of client TLS handshakes.
+///
+/// Implement this trait to export tls handshake metrics to a metrics backend
+/// of choice, or use the [`TracingMetricsRecorder`] to emit them as tracing events.
+pub trait TlsMetricsRecorder: fmt::Debug + Send + Sync + 'static {
+    /// Record the metrics of a completed (successful or failed) handshake.
+    fn record_handshake(&self, metrics: &TlsHandshakeMetrics);
+}
+
+#[derive(Debug, Clone, Default)]
+#[non_exhaustive]
+/// A [`TlsMetricsRecorder`] which emits the [`TlsHandshakeMetrics`] as a tracing event,
+/// with the duration (in seconds) as a `histogram.` prefixed field,
+/// such that metric exporters which act on tracing events
+/// (e.g. `tracing-opentelemetry`) can pick it up as a histogram.
+pub struct TracingMetricsRecorder;
+
+impl TracingMetricsRecorder {
+    /// Create a new [`TracingMetricsRecorder`].
+    pub const fn new() -> Self {
+        Self
+    }
+}
+
+impl TlsMetricsRecorder for TracingMetricsRecorder {
+    fn record_handshake(&self, metrics: &TlsHandshakeMetrics) {
+        tracing::info!(
+            histogram.tls_client_handshake_duration_seconds = metrics.duration.as_secs_f64(),
+            tls.handshake.success = metrics.success,
+            tls.protocol.version = metrics.protocol_version.map(tracing::field::display),
+            tls.alpn = metrics
+                .application_layer_protocol
+                .as_ref()
+                .map(tracing::field::display),
+            "tls client handshake completed",
+        );
+    }
+}
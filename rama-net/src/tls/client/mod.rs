@@ -18,6 +18,10 @@ pub use parser::{
     parse_client_hello,
 };
 
+mod metrics;
+#[doc(inline)]
+pub use metrics::{TlsHandshakeMetrics, TlsMetricsRecorder, TracingMetricsRecorder};
+
 mod config;
 #[doc(inline)]
 pub use config::{
@@ -26,7 +30,12 @@ pub use config::{
     extract_client_config_from_ctx,
 };
 
-use super::{ApplicationProtocol, DataEncoding, ProtocolVersion};
+use super::{ApplicationProtocol, CipherSuite, DataEncoding, ProtocolVersion};
+
+rama_utils::macros::error::static_str_error! {
+    #[doc = "tls connector refuses new connections as shutdown is in progress"]
+    pub struct TlsConnectorShutdown;
+}
 
 #[derive(Debug, Clone)]
 /// Indicate (some) of the negotiated tls parameters that
@@ -42,6 +51,11 @@ pub struct NegotiatedTlsParameters {
     ///
     /// e.g. [`ApplicationProtocol::HTTP_2`]
     pub application_layer_protocol: Option<ApplicationProtocol>,
+    /// Indicates the agreed upon [`CipherSuite`]
+    /// in case the tls implementation can surfice this.
+    ///
+    /// e.g. [`CipherSuite::TLS13_AES_128_GCM_SHA256`]
+    pub cipher_suite: Option<CipherSuite>,
     /// Certificate chain provided the peer (only stored if config requested this)
     pub peer_certificate_chain: Option<DataEncoding>,
 }
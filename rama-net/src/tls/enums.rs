@@ -451,6 +451,156 @@ enum_builder! {
 
 impl_u16_is_grease!(CipherSuite);
 
+impl CipherSuite {
+    /// returns true if this cipher suite provides forward secrecy,
+    /// meaning that it uses an ephemeral key exchange.
+    ///
+    /// All TLS 1.3 cipher suites are forward secret,
+    /// as TLS 1.3 only supports ephemeral key exchanges.
+    pub fn is_forward_secret(&self) -> bool {
+        match self {
+            CipherSuite::Unknown(_) => false,
+            suite if (0x1301..=0x13ff).contains(&u16::from(*suite)) => true,
+            // (EC)DHE key exchanges
+            CipherSuite::TLS_DHE_DSS_EXPORT_WITH_DES40_CBC_SHA
+            | CipherSuite::TLS_DHE_DSS_WITH_DES_CBC_SHA
+            | CipherSuite::TLS_DHE_DSS_WITH_3DES_EDE_CBC_SHA
+            | CipherSuite::TLS_DHE_RSA_EXPORT_WITH_DES40_CBC_SHA
+            | CipherSuite::TLS_DHE_RSA_WITH_DES_CBC_SHA
+            | CipherSuite::TLS_DHE_RSA_WITH_3DES_EDE_CBC_SHA
+            | CipherSuite::TLS_DHE_PSK_WITH_NULL_SHA
+            | CipherSuite::TLS_DHE_DSS_WITH_AES_128_CBC_SHA
+            | CipherSuite::TLS_DHE_RSA_WITH_AES_128_CBC_SHA
+            | CipherSuite::TLS_DHE_DSS_WITH_AES_256_CBC_SHA
+            | CipherSuite::TLS_DHE_RSA_WITH_AES_256_CBC_SHA
+            | CipherSuite::TLS_DHE_DSS_WITH_AES_128_CBC_SHA256
+            | CipherSuite::TLS_DHE_DSS_WITH_CAMELLIA_128_CBC_SHA
+            | CipherSuite::TLS_DHE_RSA_WITH_CAMELLIA_128_CBC_SHA
+            | CipherSuite::TLS_DHE_DSS_EXPORT1024_WITH_DES_CBC_SHA
+            | CipherSuite::TLS_DHE_DSS_EXPORT1024_WITH_RC4_56_SHA
+            | CipherSuite::TLS_DHE_DSS_WITH_RC4_128_SHA
+            | CipherSuite::TLS_DHE_RSA_WITH_AES_128_CBC_SHA256
+            | CipherSuite::TLS_DHE_DSS_WITH_AES_256_CBC_SHA256
+            | CipherSuite::TLS_DHE_RSA_WITH_AES_256_CBC_SHA256
+            | CipherSuite::TLS_DHE_DSS_WITH_3DES_EDE_CBC_RMD
+            | CipherSuite::TLS_DHE_DSS_WITH_AES_128_CBC_RMD
+            | CipherSuite::TLS_DHE_DSS_WITH_AES_256_CBC_RMD
+            | CipherSuite::TLS_DHE_RSA_WITH_3DES_EDE_CBC_RMD
+            | CipherSuite::TLS_DHE_RSA_WITH_AES_128_CBC_RMD
+            | CipherSuite::TLS_DHE_RSA_WITH_AES_256_CBC_RMD
+            | CipherSuite::TLS_DHE_DSS_WITH_CAMELLIA_256_CBC_SHA
+            | CipherSuite::TLS_DHE_RSA_WITH_CAMELLIA_256_CBC_SHA
+            | CipherSuite::TLS_DHE_PSK_WITH_RC4_128_SHA
+            | CipherSuite::TLS_DHE_PSK_WITH_3DES_EDE_CBC_SHA
+            | CipherSuite::TLS_DHE_PSK_WITH_AES_128_CBC_SHA
+            | CipherSuite::TLS_DHE_PSK_WITH_AES_256_CBC_SHA
+            | CipherSuite::TLS_DHE_DSS_WITH_SEED_CBC_SHA
+            | CipherSuite::TLS_DHE_RSA_WITH_SEED_CBC_SHA
+            | CipherSuite::TLS_DHE_RSA_WITH_AES_128_GCM_SHA256
+            | CipherSuite::TLS_DHE_RSA_WITH_AES_256_GCM_SHA384
+            | CipherSuite::TLS_DHE_DSS_WITH_AES_128_GCM_SHA256
+            | CipherSuite::TLS_DHE_DSS_WITH_AES_256_GCM_SHA384
+            | CipherSuite::TLS_DHE_PSK_WITH_AES_128_GCM_SHA256
+            | CipherSuite::TLS_DHE_PSK_WITH_AES_256_GCM_SHA384
+            | CipherSuite::TLS_DHE_PSK_WITH_AES_128_CBC_SHA256
+            | CipherSuite::TLS_DHE_PSK_WITH_AES_256_CBC_SHA384
+            | CipherSuite::TLS_DHE_PSK_WITH_NULL_SHA256
+            | CipherSuite::TLS_DHE_PSK_WITH_NULL_SHA384
+            | CipherSuite::TLS_DHE_DSS_WITH_CAMELLIA_128_CBC_SHA256
+            | CipherSuite::TLS_DHE_RSA_WITH_CAMELLIA_128_CBC_SHA256
+            | CipherSuite::TLS_DHE_DSS_WITH_CAMELLIA_256_CBC_SHA256
+            | CipherSuite::TLS_DHE_RSA_WITH_CAMELLIA_256_CBC_SHA256
+            | CipherSuite::TLS_ECDHE_ECDSA_WITH_NULL_SHA
+            | CipherSuite::TLS_ECDHE_ECDSA_WITH_RC4_128_SHA
+            | CipherSuite::TLS_ECDHE_ECDSA_WITH_3DES_EDE_CBC_SHA
+            | CipherSuite::TLS_ECDHE_ECDSA_WITH_AES_128_CBC_SHA
+            | CipherSuite::TLS_ECDHE_ECDSA_WITH_AES_256_CBC_SHA
+            | CipherSuite::TLS_ECDHE_RSA_WITH_NULL_SHA
+            | CipherSuite::TLS_ECDHE_RSA_WITH_RC4_128_SHA
+            | CipherSuite::TLS_ECDHE_RSA_WITH_3DES_EDE_CBC_SHA
+            | CipherSuite::TLS_ECDHE_RSA_WITH_AES_128_CBC_SHA
+            | CipherSuite::TLS_ECDHE_RSA_WITH_AES_256_CBC_SHA
+            | CipherSuite::TLS_ECDHE_ECDSA_WITH_AES_128_CBC_SHA256
+            | CipherSuite::TLS_ECDHE_ECDSA_WITH_AES_256_CBC_SHA384
+            | CipherSuite::TLS_ECDHE_RSA_WITH_AES_128_CBC_SHA256
+            | CipherSuite::TLS_ECDHE_RSA_WITH_AES_256_CBC_SHA384
+            | CipherSuite::TLS_ECDHE_ECDSA_WITH_AES_128_GCM_SHA256
+            | CipherSuite::TLS_ECDHE_ECDSA_WITH_AES_256_GCM_SHA384
+            | CipherSuite::TLS_ECDHE_RSA_WITH_AES_128_GCM_SHA256
+            | CipherSuite::TLS_ECDHE_RSA_WITH_AES_256_GCM_SHA384
+            | CipherSuite::TLS_ECDHE_PSK_WITH_RC4_128_SHA
+            | CipherSuite::TLS_ECDHE_PSK_WITH_3DES_EDE_CBC_SHA
+            | CipherSuite::TLS_ECDHE_PSK_WITH_AES_128_CBC_SHA
+            | CipherSuite::TLS_ECDHE_PSK_WITH_AES_256_CBC_SHA
+            | CipherSuite::TLS_ECDHE_PSK_WITH_AES_128_CBC_SHA256
+            | CipherSuite::TLS_ECDHE_PSK_WITH_AES_256_CBC_SHA384
+            | CipherSuite::TLS_ECDHE_PSK_WITH_NULL_SHA
+            | CipherSuite::TLS_ECDHE_PSK_WITH_NULL_SHA256
+            | CipherSuite::TLS_ECDHE_PSK_WITH_NULL_SHA384
+            | CipherSuite::TLS_DHE_DSS_WITH_ARIA_128_CBC_SHA256
+            | CipherSuite::TLS_DHE_DSS_WITH_ARIA_256_CBC_SHA384
+            | CipherSuite::TLS_DHE_RSA_WITH_ARIA_128_CBC_SHA256
+            | CipherSuite::TLS_DHE_RSA_WITH_ARIA_256_CBC_SHA384
+            | CipherSuite::TLS_ECDHE_ECDSA_WITH_ARIA_128_CBC_SHA256
+            | CipherSuite::TLS_ECDHE_ECDSA_WITH_ARIA_256_CBC_SHA384
+            | CipherSuite::TLS_ECDHE_RSA_WITH_ARIA_128_CBC_SHA256
+            | CipherSuite::TLS_ECDHE_RSA_WITH_ARIA_256_CBC_SHA384
+            | CipherSuite::TLS_DHE_RSA_WITH_ARIA_128_GCM_SHA256
+            | CipherSuite::TLS_DHE_RSA_WITH_ARIA_256_GCM_SHA384
+            | CipherSuite::TLS_DHE_DSS_WITH_ARIA_128_GCM_SHA256
+            | CipherSuite::TLS_DHE_DSS_WITH_ARIA_256_GCM_SHA384
+            | CipherSuite::TLS_ECDHE_ECDSA_WITH_ARIA_128_GCM_SHA256
+            | CipherSuite::TLS_ECDHE_ECDSA_WITH_ARIA_256_GCM_SHA384
+            | CipherSuite::TLS_ECDHE_RSA_WITH_ARIA_128_GCM_SHA256
+            | CipherSuite::TLS_ECDHE_RSA_WITH_ARIA_256_GCM_SHA384
+            | CipherSuite::TLS_DHE_PSK_WITH_ARIA_128_CBC_SHA256
+            | CipherSuite::TLS_DHE_PSK_WITH_ARIA_256_CBC_SHA384
+            | CipherSuite::TLS_DHE_PSK_WITH_ARIA_128_GCM_SHA256
+            | CipherSuite::TLS_DHE_PSK_WITH_ARIA_256_GCM_SHA384
+            | CipherSuite::TLS_ECDHE_PSK_WITH_ARIA_128_CBC_SHA256
+            | CipherSuite::TLS_ECDHE_PSK_WITH_ARIA_256_CBC_SHA384
+            | CipherSuite::TLS_ECDHE_ECDSA_WITH_CAMELLIA_128_CBC_SHA256
+            | CipherSuite::TLS_ECDHE_ECDSA_WITH_CAMELLIA_256_CBC_SHA384
+            | CipherSuite::TLS_ECDHE_RSA_WITH_CAMELLIA_128_CBC_SHA256
+            | CipherSuite::TLS_ECDHE_RSA_WITH_CAMELLIA_256_CBC_SHA384
+            | CipherSuite::TLS_DHE_RSA_WITH_CAMELLIA_128_GCM_SHA256
+            | CipherSuite::TLS_DHE_RSA_WITH_CAMELLIA_256_GCM_SHA384
+            | CipherSuite::TLS_DHE_DSS_WITH_CAMELLIA_128_GCM_SHA256
+            | CipherSuite::TLS_DHE_DSS_WITH_CAMELLIA_256_GCM_SHA384
+            | CipherSuite::TLS_ECDHE_ECDSA_WITH_CAMELLIA_128_GCM_SHA256
+            | CipherSuite::TLS_ECDHE_ECDSA_WITH_CAMELLIA_256_GCM_SHA384
+            | CipherSuite::TLS_ECDHE_RSA_WITH_CAMELLIA_128_GCM_SHA256
+            | CipherSuite::TLS_ECDHE_RSA_WITH_CAMELLIA_256_GCM_SHA384
+            | CipherSuite::TLS_DHE_PSK_WITH_CAMELLIA_128_GCM_SHA256
+            | CipherSuite::TLS_DHE_PSK_WITH_CAMELLIA_256_GCM_SHA384
+            | CipherSuite::TLS_DHE_PSK_WITH_CAMELLIA_128_CBC_SHA256
+            | CipherSuite::TLS_DHE_PSK_WITH_CAMELLIA_256_CBC_SHA384
+            | CipherSuite::TLS_ECDHE_PSK_WITH_CAMELLIA_128_CBC_SHA256
+            | CipherSuite::TLS_ECDHE_PSK_WITH_CAMELLIA_256_CBC_SHA384
+            | CipherSuite::TLS_DHE_RSA_WITH_AES_128_CCM
+            | CipherSuite::TLS_DHE_RSA_WITH_AES_256_CCM
+            | CipherSuite::TLS_DHE_RSA_WITH_AES_128_CCM_8
+            | CipherSuite::TLS_DHE_RSA_WITH_AES_256_CCM_8
+            | CipherSuite::TLS_DHE_PSK_WITH_AES_128_CCM
+            | CipherSuite::TLS_DHE_PSK_WITH_AES_256_CCM
+            | CipherSuite::TLS_ECDHE_ECDSA_WITH_AES_128_CCM
+            | CipherSuite::TLS_ECDHE_ECDSA_WITH_AES_256_CCM
+            | CipherSuite::TLS_ECDHE_ECDSA_WITH_AES_128_CCM_8
+            | CipherSuite::TLS_ECDHE_ECDSA_WITH_AES_256_CCM_8
+            | CipherSuite::TLS_ECDHE_RSA_WITH_CHACHA20_POLY1305_SHA256
+            | CipherSuite::TLS_ECDHE_ECDSA_WITH_CHACHA20_POLY1305_SHA256
+            | CipherSuite::TLS_DHE_RSA_WITH_CHACHA20_POLY1305_SHA256
+            | CipherSuite::TLS_ECDHE_PSK_WITH_CHACHA20_POLY1305_SHA256
+            | CipherSuite::TLS_DHE_PSK_WITH_CHACHA20_POLY1305_SHA256
+            | CipherSuite::TLS_ECDHE_PSK_WITH_AES_128_GCM_SHA256
+            | CipherSuite::TLS_ECDHE_PSK_WITH_AES_256_GCM_SHA384
+            | CipherSuite::TLS_ECDHE_PSK_WITH_AES_128_CCM_8_SHA256
+            | CipherSuite::TLS_ECDHE_PSK_WITH_AES_128_CCM_SHA256 => true,
+            _ => false,
+        }
+    }
+}
+
 enum_builder! {
     /// The `SignatureScheme` TLS protocol enum.  Values in this enum are taken
     /// from the various RFCs covering TLS, and are listed by IANA.
@@ -787,6 +937,29 @@ mod tests {
         assert_eq!("GREASE (0xdada)", SupportedGroup::from(0xdada).to_string());
     }
 
+    #[test]
+    fn test_cipher_suite_is_forward_secret() {
+        for suite in [
+            CipherSuite::TLS13_AES_128_GCM_SHA256,
+            CipherSuite::TLS13_CHACHA20_POLY1305_SHA256,
+            CipherSuite::TLS_ECDHE_RSA_WITH_AES_128_GCM_SHA256,
+            CipherSuite::TLS_ECDHE_ECDSA_WITH_CHACHA20_POLY1305_SHA256,
+            CipherSuite::TLS_DHE_RSA_WITH_AES_256_GCM_SHA384,
+        ] {
+            assert!(suite.is_forward_secret(), "{suite}");
+        }
+
+        for suite in [
+            CipherSuite::TLS_RSA_WITH_AES_128_GCM_SHA256,
+            CipherSuite::TLS_DH_RSA_WITH_AES_128_GCM_SHA256,
+            CipherSuite::TLS_ECDH_ECDSA_WITH_AES_128_GCM_SHA256,
+            CipherSuite::TLS_PSK_WITH_AES_128_GCM_SHA256,
+            CipherSuite::from(0xdada),
+        ] {
+            assert!(!suite.is_forward_secret(), "{suite}");
+        }
+    }
+
     #[test]
     fn test_enum_bytes_display() {
         assert_eq!("http/1.1", ApplicationProtocol::HTTP_11.to_string());
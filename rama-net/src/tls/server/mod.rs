@@ -14,3 +14,7 @@ pub use peek::{NoTlsRejectError, TlsPeekRouter, TlsPeekStream};
 mod sni;
 #[doc(inline)]
 pub use sni::{SniPeekStream, SniRequest, SniRouter};
+
+mod sni_router;
+#[doc(inline)]
+pub use sni_router::{SniRouterBuilder, SniRouterLayer, SniRouterService};
@@ -0,0 +1,348 @@
+use std::{fmt, sync::Arc};
+
+use rama_core::telemetry::tracing;
+use rama_core::{
+    Context, Layer, Service,
+    error::{BoxError, ErrorContext, OpaqueError},
+};
+
+use crate::address::Domain;
+
+use super::{SniRequest, SniRouter, TlsPeekStream};
+
+/// A [`Layer`] which produces a [`SniRouterService`],
+/// dispatching tls traffic to one of multiple services based on the SNI
+/// found in the client hello.
+///
+/// The inner service wrapped by this layer is used as the fallback
+/// for non-tls traffic, in the same way as [`SniRouter::with_fallback`].
+///
+/// Create one using [`SniRouterLayer::builder`].
+pub struct SniRouterLayer<S> {
+    routes: SniRoutes<S>,
+}
+
+impl SniRouterLayer<()> {
+    /// Create a new [`SniRouterBuilder`] which can be used
+    /// to register the services to route to.
+    pub fn builder<S>() -> SniRouterBuilder<S> {
+        SniRouterBuilder {
+            routes: Vec::new(),
+            default: None,
+        }
+    }
+}
+
+impl<S: Clone> Clone for SniRouterLayer<S> {
+    fn clone(&self) -> Self {
+        Self {
+            routes: self.routes.clone(),
+        }
+    }
+}
+
+impl<S: fmt::Debug> fmt::Debug for SniRouterLayer<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SniRouterLayer")
+            .field("routes", &self.routes)
+            .finish()
+    }
+}
+
+impl<S: Clone, F> Layer<F> for SniRouterLayer<S> {
+    type Service = SniRouterService<S, F>;
+
+    fn layer(&self, inner: F) -> Self::Service {
+        SniRouterService {
+            router: SniRouter::new(self.routes.clone()).with_fallback(inner),
+        }
+    }
+
+    fn into_layer(self, inner: F) -> Self::Service {
+        SniRouterService {
+            router: SniRouter::new(self.routes).with_fallback(inner),
+        }
+    }
+}
+
+/// Builder used to create a [`SniRouterLayer`].
+///
+/// Routes are matched in the order they were registered,
+/// with the first matching route being used.
+pub struct SniRouterBuilder<S> {
+    routes: Vec<(SniPattern, S)>,
+    default: Option<S>,
+}
+
+impl<S: fmt::Debug> fmt::Debug for SniRouterBuilder<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SniRouterBuilder")
+            .field("routes", &self.routes)
+            .field("default", &self.default)
+            .finish()
+    }
+}
+
+impl<S> SniRouterBuilder<S> {
+    /// Route tls traffic for which the SNI matches the given pattern
+    /// to the given service.
+    ///
+    /// The pattern is either an exact hostname (e.g. `example.com`)
+    /// or a wildcard prefix (e.g. `*.example.com`), which matches
+    /// all subdomains of the given domain but not the domain itself.
+    ///
+    /// # Panics
+    ///
+    /// Panics in case the pattern is not a valid (wildcard) domain,
+    /// use [`Self::try_route`] in case you want to handle this error.
+    pub fn route(self, pattern: &str, service: S) -> Self {
+        self.try_route(pattern, service)
+            .expect("valid sni route pattern")
+    }
+
+    /// Try to route tls traffic for which the SNI matches the given pattern
+    /// to the given service.
+    ///
+    /// See [`Self::route`] for more information.
+    pub fn try_route(mut self, pattern: &str, service: S) -> Result<Self, OpaqueError> {
+        let pattern = pattern.parse()?;
+        self.routes.push((pattern, service));
+        Ok(self)
+    }
+
+    /// Use the given service for tls traffic without SNI
+    /// or for which the SNI matched none of the registered routes.
+    ///
+    /// Without a default service such traffic is rejected.
+    pub fn default_service(mut self, service: S) -> Self {
+        self.default = Some(service);
+        self
+    }
+
+    /// Build the [`SniRouterLayer`].
+    pub fn build(self) -> SniRouterLayer<S> {
+        SniRouterLayer {
+            routes: SniRoutes(Arc::new(SniRoutesInner {
+                routes: self.routes,
+                default: self.default,
+            })),
+        }
+    }
+}
+
+/// A [`Service`] which routes tls traffic based on the SNI
+/// found in the client hello to one of the services registered
+/// using the [`SniRouterBuilder`].
+///
+/// The bytes peeked in order to extract the SNI are prepended back,
+/// such that the selected service receives the complete stream.
+///
+/// Created using the [`SniRouterLayer`].
+pub struct SniRouterService<S, F> {
+    router: SniRouter<SniRoutes<S>, F>,
+}
+
+impl<S, F: Clone> Clone for SniRouterService<S, F> {
+    fn clone(&self) -> Self {
+        Self {
+            router: self.router.clone(),
+        }
+    }
+}
+
+impl<S: fmt::Debug, F: fmt::Debug> fmt::Debug for SniRouterService<S, F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SniRouterService")
+            .field("router", &self.router)
+            .finish()
+    }
+}
+
+impl<State, Stream, Response, S, F> Service<State, Stream> for SniRouterService<S, F>
+where
+    State: Clone + Send + Sync + 'static,
+    Stream: crate::stream::Stream + Unpin,
+    Response: Send + 'static,
+    S: Service<State, SniRequest<Stream>, Response = Response, Error: Into<BoxError>>,
+    F: Service<State, TlsPeekStream<Stream>, Response = Response, Error: Into<BoxError>>,
+{
+    type Response = Response;
+    type Error = BoxError;
+
+    fn serve(
+        &self,
+        ctx: Context<State>,
+        stream: Stream,
+    ) -> impl Future<Output = Result<Self::Response, Self::Error>> + Send + '_ {
+        self.router.serve(ctx, stream)
+    }
+}
+
+struct SniRoutes<S>(Arc<SniRoutesInner<S>>);
+
+struct SniRoutesInner<S> {
+    routes: Vec<(SniPattern, S)>,
+    default: Option<S>,
+}
+
+impl<S> Clone for SniRoutes<S> {
+    fn clone(&self) -> Self {
+        Self(self.0.clone())
+    }
+}
+
+impl<S: fmt::Debug> fmt::Debug for SniRoutes<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SniRoutes")
+            .field("routes", &self.0.routes)
+            .field("default", &self.0.default)
+            .finish()
+    }
+}
+
+impl<S> SniRoutes<S> {
+    fn find(&self, sni: Option<&Domain>) -> Option<&S> {
+        sni.and_then(|sni| {
+            self.0
+                .routes
+                .iter()
+                .find_map(|(pattern, service)| pattern.is_match(sni).then_some(service))
+        })
+        .or(self.0.default.as_ref())
+    }
+}
+
+impl<State, Stream, S> Service<State, SniRequest<Stream>> for SniRoutes<S>
+where
+    State: Clone + Send + Sync + 'static,
+    Stream: crate::stream::Stream + Unpin,
+    S: Service<State, SniRequest<Stream>, Error: Into<BoxError>>,
+{
+    type Response = S::Response;
+    type Error = BoxError;
+
+    async fn serve(
+        &self,
+        ctx: Context<State>,
+        req: SniRequest<Stream>,
+    ) -> Result<Self::Response, Self::Error> {
+        let Some(service) = self.find(req.sni.as_ref()) else {
+            tracing::debug!(sni = ?req.sni, "sni router: no route or default service found");
+            return Err(OpaqueError::from_display("sni router: no route found").into_boxed());
+        };
+        service.serve(ctx, req).await.map_err(Into::into)
+    }
+}
+
+#[derive(Debug, Clone)]
+enum SniPattern {
+    Exact(Domain),
+    Wildcard(Domain),
+}
+
+impl SniPattern {
+    fn is_match(&self, sni: &Domain) -> bool {
+        match self {
+            Self::Exact(domain) => domain == sni,
+            Self::Wildcard(domain) => sni.is_sub_of(domain) && domain != sni,
+        }
+    }
+}
+
+impl std::str::FromStr for SniPattern {
+    type Err = OpaqueError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.strip_prefix("*.") {
+            Some(domain) => domain
+                .parse()
+                .map(Self::Wildcard)
+                .context("parse wildcard sni pattern"),
+            None => s
+                .parse()
+                .map(Self::Exact)
+                .context("parse exact sni pattern"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::stream::{HeapReader, PeekStream};
+    use rama_core::service::service_fn;
+    use std::convert::Infallible;
+
+    fn sni_request(sni: Option<&'static str>) -> SniRequest<std::io::Cursor<Vec<u8>>> {
+        SniRequest {
+            stream: PeekStream::new(
+                HeapReader::from(Vec::new()),
+                std::io::Cursor::new(Vec::new()),
+            ),
+            sni: sni.map(Domain::from_static),
+        }
+    }
+
+    #[test]
+    fn test_sni_pattern_match() {
+        let exact: SniPattern = "example.com".parse().unwrap();
+        assert!(exact.is_match(&Domain::from_static("example.com")));
+        assert!(exact.is_match(&Domain::from_static("EXAMPLE.com")));
+        assert!(!exact.is_match(&Domain::from_static("www.example.com")));
+
+        let wildcard: SniPattern = "*.example.com".parse().unwrap();
+        assert!(wildcard.is_match(&Domain::from_static("www.example.com")));
+        assert!(wildcard.is_match(&Domain::from_static("a.b.example.com")));
+        assert!(!wildcard.is_match(&Domain::from_static("example.com")));
+        assert!(!wildcard.is_match(&Domain::from_static("example.org")));
+
+        assert!("*.".parse::<SniPattern>().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_sni_routes() {
+        let layer = SniRouterLayer::builder()
+            .route(
+                "example.com",
+                service_fn(async |_, _| Ok::<_, Infallible>("exact")).boxed(),
+            )
+            .route(
+                "*.example.com",
+                service_fn(async |_, _| Ok::<_, Infallible>("wildcard")).boxed(),
+            )
+            .default_service(service_fn(async |_, _| Ok::<_, Infallible>("default")).boxed())
+            .build();
+
+        for (sni, expected) in [
+            (Some("example.com"), "exact"),
+            (Some("www.example.com"), "wildcard"),
+            (Some("example.org"), "default"),
+            (None, "default"),
+        ] {
+            let response = layer
+                .routes
+                .serve(Context::default(), sni_request(sni))
+                .await
+                .unwrap();
+            assert_eq!(expected, response, "sni: {sni:?}");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_sni_routes_no_default() {
+        let layer = SniRouterLayer::builder()
+            .route(
+                "example.com",
+                service_fn(async |_, _| Ok::<_, Infallible>("exact")),
+            )
+            .build();
+
+        assert!(
+            layer
+                .routes
+                .serve(Context::default(), sni_request(Some("example.org")))
+                .await
+                .is_err()
+        );
+    }
+}
@@ -1,14 +1,62 @@
-use std::fmt;
+use std::{
+    fmt,
+    time::{Duration, Instant},
+};
 
 use super::{Basic, Bearer};
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Clone, PartialEq, Eq)]
 /// Proxy credentials.
 pub enum ProxyCredential {
     /// [`Basic`]` credentials.
     Basic(Basic),
     /// [`Bearer`] credentials.
     Bearer(Bearer),
+    /// OAuth2 access token credentials,
+    /// used as a bearer token for proxy authorization.
+    OAuth2 {
+        /// The access token.
+        token: String,
+        /// The moment the access token expires, if known.
+        expires_at: Option<Instant>,
+        /// The refresh token, if any.
+        refresh_token: Option<String>,
+    },
+}
+
+impl ProxyCredential {
+    /// Returns `true` in case these are [`ProxyCredential::OAuth2`] credentials
+    /// which expire within the given threshold (or have already expired).
+    ///
+    /// Credentials without an expiration moment never expire.
+    pub fn expires_within(&self, threshold: Duration) -> bool {
+        match self {
+            Self::OAuth2 {
+                expires_at: Some(expires_at),
+                ..
+            } => expires_at.saturating_duration_since(Instant::now()) <= threshold,
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Debug for ProxyCredential {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Basic(basic) => f.debug_tuple("Basic").field(basic).finish(),
+            Self::Bearer(bearer) => f.debug_tuple("Bearer").field(bearer).finish(),
+            Self::OAuth2 {
+                expires_at,
+                refresh_token,
+                ..
+            } => f
+                .debug_struct("OAuth2")
+                .field("token", &"***")
+                .field("expires_at", expires_at)
+                .field("refresh_token", &refresh_token.as_ref().map(|_| "***"))
+                .finish(),
+        }
+    }
 }
 
 impl From<Basic> for ProxyCredential {
@@ -28,6 +76,39 @@ impl fmt::Display for ProxyCredential {
         match self {
             ProxyCredential::Basic(basic) => basic.fmt(f),
             ProxyCredential::Bearer(bearer) => bearer.fmt(f),
+            ProxyCredential::OAuth2 { token, .. } => token.fmt(f),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_proxy_credential_expires_within() {
+        let threshold = Duration::from_secs(30);
+        let oauth2 = |expires_at| ProxyCredential::OAuth2 {
+            token: "token".to_owned(),
+            expires_at,
+            refresh_token: None,
+        };
+
+        assert!(!ProxyCredential::Bearer(Bearer::new_static("token")).expires_within(threshold));
+        assert!(!oauth2(None).expires_within(threshold));
+        assert!(!oauth2(Some(Instant::now() + Duration::from_secs(60))).expires_within(threshold));
+        assert!(oauth2(Some(Instant::now() + Duration::from_secs(10))).expires_within(threshold));
+        assert!(oauth2(Some(Instant::now())).expires_within(threshold));
+    }
+
+    #[test]
+    fn test_proxy_credential_oauth2_debug_redacts_tokens() {
+        let credential = ProxyCredential::OAuth2 {
+            token: "secret-token".to_owned(),
+            expires_at: None,
+            refresh_token: Some("secret-refresh".to_owned()),
+        };
+        let debug = format!("{credential:?}");
+        assert!(!debug.contains("secret"), "debug: {debug}");
+    }
+}
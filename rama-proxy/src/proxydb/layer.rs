@@ -250,7 +250,7 @@ where
                             None => credential, // nothing to do
                         }
                     }
-                    ProxyCredential::Bearer(_) => credential, // Remark: we can support this in future too if needed
+                    ProxyCredential::Bearer(_) | ProxyCredential::OAuth2 { .. } => credential, // Remark: we can support this in future too if needed
                 }
             });
 
@@ -448,7 +448,7 @@ where
                 );
                 client.set_auth(basic.clone());
             }
-            Some(ProxyCredential::Bearer(_)) => {
+            Some(ProxyCredential::Bearer(_) | ProxyCredential::OAuth2 { .. }) => {
                 return Err(OpaqueError::from_display(
                     "socks5proxy does not support auth with bearer credential",
                 )
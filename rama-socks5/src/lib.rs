@@ -50,6 +50,9 @@ pub mod client;
 pub use client::Socks5Client;
 pub use client::{Socks5ProxyConnector, Socks5ProxyConnectorLayer};
 
+pub mod socks4;
+pub use socks4::{Socks4ProxyConnector, Socks4ProxyConnectorLayer};
+
 pub mod server;
 pub use server::Socks5Acceptor;
 
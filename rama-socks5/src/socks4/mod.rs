@@ -0,0 +1,25 @@
+//! Client-side support for the legacy [SOCKS4] protocol and its [SOCKS4A] extension.
+//!
+//! SOCKS4 only supports IPv4 targets without authentication,
+//! where the optional user id is taken from the username of the
+//! [`ProxyCredential::Basic`] of the [`ProxyAddress`] (if any).
+//! SOCKS4A extends this with support for domain targets,
+//! which are resolved by the proxy instead of the client.
+//!
+//! Use the `socks4` scheme for a proxy which only supports IPv4 targets,
+//! and the `socks4a` scheme for a proxy which also accepts domain targets.
+//!
+//! [SOCKS4]: https://www.openssh.com/txt/socks4.protocol
+//! [SOCKS4A]: https://www.openssh.com/txt/socks4a.protocol
+//! [`ProxyCredential::Basic`]: rama_net::user::ProxyCredential::Basic
+//! [`ProxyAddress`]: rama_net::address::ProxyAddress
+
+pub mod proto;
+
+mod proxy_connector;
+mod proxy_error;
+
+#[doc(inline)]
+pub use proxy_connector::{Socks4ProxyConnector, Socks4ProxyConnectorLayer};
+#[doc(inline)]
+pub use proxy_error::Socks4ProxyError;
@@ -0,0 +1,325 @@
+//! Implementation of the [SOCKS4] Protocol and its [SOCKS4A] extension.
+//!
+//! [SOCKS4]: https://www.openssh.com/txt/socks4.protocol
+//! [SOCKS4A]: https://www.openssh.com/txt/socks4a.protocol
+
+use crate::proto::ProtocolError;
+use rama_core::bytes::{BufMut, BytesMut};
+use rama_net::address::{Authority, Host};
+use rama_utils::macros::enums::enum_builder;
+use std::net::{IpAddr, Ipv4Addr, SocketAddrV4};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+/// Version number used by a SOCKS4(A) client request.
+const VERSION: u8 = 0x04;
+
+/// Version number used by a SOCKS4(A) server reply.
+const REPLY_VERSION: u8 = 0x00;
+
+/// Maximum length of the null-terminated strings (user id and domain)
+/// that are accepted when reading a [`Request`].
+const MAX_STR_LEN: usize = 255;
+
+enum_builder! {
+    /// Request Command.
+    ///
+    /// Reference: <https://www.openssh.com/txt/socks4.protocol>
+    @U8
+    pub enum Command {
+        /// Establish a TCP/IP stream connection.
+        Connect => 0x01,
+        /// Establish a TCP/IP port binding.
+        Bind => 0x02,
+    }
+}
+
+enum_builder! {
+    /// Reply code of the server.
+    ///
+    /// Reference: <https://www.openssh.com/txt/socks4.protocol>
+    @U8
+    pub enum ReplyKind {
+        /// Request granted.
+        Granted => 0x5a,
+        /// Request rejected or failed.
+        Rejected => 0x5b,
+        /// Request rejected because the SOCKS server cannot connect to identd on the client.
+        IdentdUnreachable => 0x5c,
+        /// Request rejected because the client program and identd report different user-ids.
+        IdentdMismatch => 0x5d,
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// The SOCKS4(A) request sent by the client.
+///
+/// ```plain
+/// +----+----+----+----+----+----+----+----+----+----+....+----+
+/// | VN | CD | DSTPORT |      DSTIP        | USERID       |NULL|
+/// +----+----+----+----+----+----+----+----+----+----+....+----+
+///    1    1      2              4           variable       1
+/// ```
+///
+/// In case the destination is a domain name (SOCKS4A), DSTIP is set
+/// to the invalid address `0.0.0.1` and the null-terminated domain
+/// name follows the (null-terminated) USERID.
+///
+/// IPv6 destinations are not supported by the SOCKS4(A) protocol.
+pub struct Request {
+    pub command: Command,
+    pub destination: Authority,
+    pub user_id: String,
+}
+
+impl Request {
+    /// Create a new [`Request`].
+    pub fn new(command: Command, destination: Authority, user_id: impl Into<String>) -> Self {
+        Self {
+            command,
+            destination,
+            user_id: user_id.into(),
+        }
+    }
+
+    /// Read the client [`Request`], decoded from binary format from the reader.
+    pub async fn read_from<R>(r: &mut R) -> Result<Self, ProtocolError>
+    where
+        R: AsyncRead + Unpin,
+    {
+        let version = r.read_u8().await?;
+        if version != VERSION {
+            return Err(ProtocolError::unexpected_byte(0, version));
+        }
+
+        let command: Command = r.read_u8().await?.into();
+        let port = r.read_u16().await?;
+        let ip = Ipv4Addr::from(r.read_u32().await?);
+        let user_id = read_null_terminated_str(r).await?;
+
+        let octets = ip.octets();
+        let host = if octets[..3] == [0, 0, 0] && octets[3] != 0 {
+            let domain = read_null_terminated_str(r).await?;
+            Host::Name(domain.try_into()?)
+        } else {
+            Host::Address(IpAddr::V4(ip))
+        };
+
+        Ok(Self {
+            command,
+            destination: Authority::new(host, port),
+            user_id,
+        })
+    }
+
+    /// Write the client [`Request`] in binary format into the writer.
+    ///
+    /// An error is returned in case the destination is an IPv6 address,
+    /// which cannot be addressed using the SOCKS4(A) protocol.
+    pub async fn write_to<W>(&self, w: &mut W) -> Result<(), std::io::Error>
+    where
+        W: AsyncWrite + Unpin,
+    {
+        let mut buf = BytesMut::with_capacity(self.serialized_len());
+        self.write_to_buf(&mut buf)?;
+        w.write_all(&buf).await
+    }
+
+    /// Write the client [`Request`] in binary format into the buffer.
+    ///
+    /// An error is returned in case the destination is an IPv6 address,
+    /// which cannot be addressed using the SOCKS4(A) protocol.
+    pub fn write_to_buf<B: BufMut>(&self, buf: &mut B) -> Result<(), std::io::Error> {
+        let ip = match self.destination.host() {
+            Host::Address(ip) => match ip.to_canonical() {
+                IpAddr::V4(ip) => ip,
+                IpAddr::V6(_) => {
+                    return Err(std::io::Error::new(
+                        std::io::ErrorKind::InvalidInput,
+                        "socks4 protocol does not support ipv6 destinations",
+                    ));
+                }
+            },
+            Host::Name(_) => Ipv4Addr::new(0, 0, 0, 1),
+        };
+
+        buf.put_u8(VERSION);
+        buf.put_u8(self.command.into());
+        buf.put_u16(self.destination.port());
+        buf.put_slice(&ip.octets());
+        buf.put_slice(self.user_id.as_bytes());
+        buf.put_u8(0);
+        if let Host::Name(domain) = self.destination.host() {
+            buf.put_slice(domain.as_str().as_bytes());
+            buf.put_u8(0);
+        }
+        Ok(())
+    }
+
+    fn serialized_len(&self) -> usize {
+        let domain_len = match self.destination.host() {
+            Host::Name(domain) => domain.as_str().len() + 1,
+            Host::Address(_) => 0,
+        };
+        8 + self.user_id.len() + 1 + domain_len
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// The SOCKS4(A) reply sent by the server.
+///
+/// ```plain
+/// +----+----+----+----+----+----+----+----+
+/// | VN | CD | DSTPORT |      DSTIP        |
+/// +----+----+----+----+----+----+----+----+
+///    1    1      2              4
+/// ```
+///
+/// For a [`Command::Connect`] request the DSTPORT and DSTIP fields are ignored.
+pub struct Reply {
+    pub kind: ReplyKind,
+    pub bind_address: SocketAddrV4,
+}
+
+impl Reply {
+    /// Create a new [`Reply`].
+    pub fn new(kind: ReplyKind, bind_address: SocketAddrV4) -> Self {
+        Self { kind, bind_address }
+    }
+
+    /// Read the server [`Reply`], decoded from binary format from the reader.
+    pub async fn read_from<R>(r: &mut R) -> Result<Self, ProtocolError>
+    where
+        R: AsyncRead + Unpin,
+    {
+        let version = r.read_u8().await?;
+        if version != REPLY_VERSION {
+            return Err(ProtocolError::unexpected_byte(0, version));
+        }
+
+        let kind: ReplyKind = r.read_u8().await?.into();
+        let port = r.read_u16().await?;
+        let ip = Ipv4Addr::from(r.read_u32().await?);
+
+        Ok(Self {
+            kind,
+            bind_address: SocketAddrV4::new(ip, port),
+        })
+    }
+
+    /// Write the server [`Reply`] in binary format into the writer.
+    pub async fn write_to<W>(&self, w: &mut W) -> Result<(), std::io::Error>
+    where
+        W: AsyncWrite + Unpin,
+    {
+        let mut buf = [0u8; 8];
+        self.write_to_buf(&mut buf.as_mut_slice());
+        w.write_all(&buf).await
+    }
+
+    /// Write the server [`Reply`] in binary format into the buffer.
+    pub fn write_to_buf<B: BufMut>(&self, buf: &mut B) {
+        buf.put_u8(REPLY_VERSION);
+        buf.put_u8(self.kind.into());
+        buf.put_u16(self.bind_address.port());
+        buf.put_slice(&self.bind_address.ip().octets());
+    }
+}
+
+async fn read_null_terminated_str<R>(r: &mut R) -> Result<String, ProtocolError>
+where
+    R: AsyncRead + Unpin,
+{
+    let mut bytes = Vec::new();
+    loop {
+        match r.read_u8().await? {
+            0 => break,
+            byte if bytes.len() == MAX_STR_LEN => {
+                return Err(ProtocolError::unexpected_byte(MAX_STR_LEN, byte));
+            }
+            byte => bytes.push(byte),
+        }
+    }
+    Ok(String::from_utf8(bytes)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rama_net::address::Domain;
+
+    #[tokio::test]
+    async fn test_request_write_read_eq() {
+        for request in [
+            Request::new(
+                Command::Connect,
+                Authority::new(Host::Address(IpAddr::V4(Ipv4Addr::LOCALHOST)), 80),
+                "",
+            ),
+            Request::new(
+                Command::Bind,
+                Authority::new(Host::Address(IpAddr::V4(Ipv4Addr::new(1, 2, 3, 4))), 443),
+                "john",
+            ),
+            Request::new(
+                Command::Connect,
+                Authority::new(Host::Name(Domain::from_static("example.com")), 8080),
+                "john",
+            ),
+        ] {
+            let mut buf = Vec::new();
+            request.write_to(&mut buf).await.unwrap();
+            let mut r = std::io::Cursor::new(buf);
+            assert_eq!(Request::read_from(&mut r).await.unwrap(), request);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_request_encoding_socks4a() {
+        let request = Request::new(
+            Command::Connect,
+            Authority::new(Host::Name(Domain::from_static("a.io")), 80),
+            "u",
+        );
+        let mut buf = Vec::new();
+        request.write_to(&mut buf).await.unwrap();
+        assert_eq!(
+            buf,
+            [4, 1, 0, 80, 0, 0, 0, 1, b'u', 0, b'a', b'.', b'i', b'o', 0]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_request_ipv6_not_supported() {
+        let request = Request::new(
+            Command::Connect,
+            Authority::new(Host::Address(IpAddr::V6(std::net::Ipv6Addr::LOCALHOST)), 80),
+            "",
+        );
+        let mut buf = Vec::new();
+        let err = request.write_to(&mut buf).await.unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+    }
+
+    #[tokio::test]
+    async fn test_reply_write_read_eq() {
+        for reply in [
+            Reply::new(
+                ReplyKind::Granted,
+                SocketAddrV4::new(Ipv4Addr::LOCALHOST, 1080),
+            ),
+            Reply::new(
+                ReplyKind::Rejected,
+                SocketAddrV4::new(Ipv4Addr::UNSPECIFIED, 0),
+            ),
+            Reply::new(
+                ReplyKind::Unknown(42),
+                SocketAddrV4::new(Ipv4Addr::UNSPECIFIED, 0),
+            ),
+        ] {
+            let mut buf = Vec::new();
+            reply.write_to(&mut buf).await.unwrap();
+            let mut r = std::io::Cursor::new(buf);
+            assert_eq!(Reply::read_from(&mut r).await.unwrap(), reply);
+        }
+    }
+}
@@ -0,0 +1,399 @@
+use super::proto::{self, Command, Reply, ReplyKind};
+use super::proxy_error::Socks4ProxyError;
+use rama_core::telemetry::tracing;
+use rama_core::{
+    Context, Layer, Service,
+    error::{BoxError, ErrorExt, OpaqueError},
+};
+use rama_net::{
+    Protocol,
+    address::{Authority, Host, ProxyAddress},
+    client::{ConnectorService, EstablishedClientConnection},
+    stream::Stream,
+    transport::TryRefIntoTransportContext,
+    user::ProxyCredential,
+};
+use rama_utils::macros::define_inner_service_accessors;
+use std::{fmt, net::IpAddr};
+
+#[derive(Debug, Clone, Default)]
+/// A [`Layer`] which wraps the given service with a [`Socks4ProxyConnector`].
+///
+/// See [`Socks4ProxyConnector`] for more information.
+pub struct Socks4ProxyConnectorLayer {
+    required: bool,
+}
+
+impl Socks4ProxyConnectorLayer {
+    /// Create a new [`Socks4ProxyConnectorLayer`] which creates a [`Socks4ProxyConnector`]
+    /// which will only connect via a socks4 proxy in case the [`ProxyAddress`] is available
+    /// in the [`Context`].
+    ///
+    /// [`Context`]: rama_core::Context
+    /// [`ProxyAddress`]: rama_net::address::ProxyAddress
+    pub fn optional() -> Self {
+        Self { required: false }
+    }
+
+    /// Create a new [`Socks4ProxyConnectorLayer`] which creates a [`Socks4ProxyConnector`]
+    /// which will always connect via a socks4 proxy, but fail in case the [`ProxyAddress`] is
+    /// not available in the [`Context`].
+    ///
+    /// [`Context`]: rama_core::Context
+    /// [`ProxyAddress`]: rama_net::address::ProxyAddress
+    pub fn required() -> Self {
+        Self { required: true }
+    }
+}
+
+impl<S> Layer<S> for Socks4ProxyConnectorLayer {
+    type Service = Socks4ProxyConnector<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        Socks4ProxyConnector::new(inner, self.required)
+    }
+}
+
+/// A connector which can be used to establish a connection over a SOCKS4(A) Proxy.
+///
+/// This behaviour is optional and only triggered in case there
+/// is a [`ProxyAddress`] found in the [`Context`].
+///
+/// Domain targets are only supported for the `socks4a` protocol
+/// (or in case no protocol is defined), as the `socks4` protocol
+/// can only address IPv4 targets. IPv6 targets are never supported.
+pub struct Socks4ProxyConnector<S> {
+    inner: S,
+    required: bool,
+}
+
+impl<S: fmt::Debug> fmt::Debug for Socks4ProxyConnector<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Socks4ProxyConnector")
+            .field("inner", &self.inner)
+            .field("required", &self.required)
+            .finish()
+    }
+}
+
+impl<S: Clone> Clone for Socks4ProxyConnector<S> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            required: self.required,
+        }
+    }
+}
+
+impl<S> Socks4ProxyConnector<S> {
+    /// Creates a new [`Socks4ProxyConnector`].
+    fn new(inner: S, required: bool) -> Self {
+        Self { inner, required }
+    }
+
+    /// Creates a new optional [`Socks4ProxyConnector`].
+    #[inline]
+    pub fn optional(inner: S) -> Self {
+        Self::new(inner, false)
+    }
+
+    /// Creates a new required [`Socks4ProxyConnector`].
+    #[inline]
+    pub fn required(inner: S) -> Self {
+        Self::new(inner, true)
+    }
+
+    define_inner_service_accessors!();
+}
+
+impl<S, State, Request> Service<State, Request> for Socks4ProxyConnector<S>
+where
+    S: ConnectorService<State, Request, Connection: Stream + Unpin, Error: Into<BoxError>>,
+    State: Clone + Send + Sync + 'static,
+    Request:
+        TryRefIntoTransportContext<State, Error: Into<BoxError> + Send + 'static> + Send + 'static,
+{
+    type Response = EstablishedClientConnection<S::Connection, State, Request>;
+    type Error = BoxError;
+
+    async fn serve(
+        &self,
+        ctx: Context<State>,
+        req: Request,
+    ) -> Result<Self::Response, Self::Error> {
+        let address = ctx.get::<ProxyAddress>().cloned();
+        if !address
+            .as_ref()
+            .and_then(|addr| addr.protocol.as_ref())
+            .map(|p| p.is_socks4())
+            .unwrap_or(true)
+        {
+            return Err(OpaqueError::from_display(
+                "socks4 proxy connector can only serve socks4 protocol",
+            )
+            .into_boxed());
+        }
+
+        let established_conn =
+            self.inner
+                .connect(ctx, req)
+                .await
+                .map_err(|err| match address.as_ref() {
+                    Some(address) => OpaqueError::from_std(Socks4ProxyError::Transport(
+                        OpaqueError::from_boxed(err.into())
+                            .context(format!(
+                                "establish connection to proxy {} (protocol: {:?})",
+                                address.authority, address.protocol,
+                            ))
+                            .into_boxed(),
+                    )),
+                    None => {
+                        OpaqueError::from_boxed(err.into()).context("establish connection target")
+                    }
+                })?;
+
+        // return early in case we did not use a proxy
+        let proxy_address = match address {
+            Some(address) => address,
+            None => {
+                return if self.required {
+                    Err("socks4 proxy required but none is defined".into())
+                } else {
+                    tracing::trace!(
+                        "socks4 proxy connector: no proxy required or set: proceed with direct connection"
+                    );
+                    Ok(established_conn)
+                };
+            }
+        };
+        // and do the handshake otherwise...
+
+        let EstablishedClientConnection {
+            mut ctx,
+            req,
+            mut conn,
+        } = established_conn;
+
+        let transport_ctx = ctx
+            .get_or_try_insert_with_ctx(|ctx| req.try_ref_into_transport_ctx(ctx))
+            .map_err(|err| {
+                OpaqueError::from_boxed(err.into())
+                    .context("socks4 proxy connector: get transport context")
+            })?
+            .clone();
+
+        tracing::trace!(
+            network.peer.address = %proxy_address.authority.host(),
+            network.peer.port = %proxy_address.authority.port(),
+            server.address = %transport_ctx.authority.host(),
+            server.port = %transport_ctx.authority.port(),
+            "socks4 proxy connector: connected to proxy",
+        );
+
+        let user_id = match &proxy_address.credential {
+            Some(ProxyCredential::Basic(basic)) => basic.username().to_owned(),
+            Some(ProxyCredential::Bearer(_) | ProxyCredential::OAuth2 { .. }) => {
+                return Err(OpaqueError::from_display(
+                    "socks4 proxy does not support auth with bearer credential",
+                )
+                .into_boxed());
+            }
+            None => String::new(),
+        };
+
+        let destination = socks4_destination(
+            proxy_address.protocol.as_ref(),
+            transport_ctx.authority.clone(),
+        )?;
+
+        proto::Request::new(Command::Connect, destination, user_id)
+            .write_to(&mut conn)
+            .await
+            .map_err(Socks4ProxyError::from)?;
+
+        let reply = Reply::read_from(&mut conn)
+            .await
+            .map_err(Socks4ProxyError::from)?;
+        if reply.kind != ReplyKind::Granted {
+            return Err(Box::new(Socks4ProxyError::Rejected(reply.kind)));
+        }
+
+        tracing::trace!(
+            network.peer.address = %proxy_address.authority.host(),
+            network.peer.port = %proxy_address.authority.port(),
+            server.address = %transport_ctx.authority.host(),
+            server.port = %transport_ctx.authority.port(),
+            bind_addr = %reply.bind_address,
+            "socks4 proxy connector: handshake complete",
+        );
+
+        Ok(EstablishedClientConnection { ctx, req, conn })
+    }
+}
+
+/// Normalize the target [`Authority`] into a destination
+/// which can be addressed using the given socks4(a) protocol.
+fn socks4_destination(
+    protocol: Option<&Protocol>,
+    authority: Authority,
+) -> Result<Authority, Socks4ProxyError> {
+    let (host, port) = authority.into_parts();
+    let host = match host {
+        Host::Name(_) if protocol == Some(&Protocol::SOCKS4) => {
+            return Err(Socks4ProxyError::UnsupportedDestination(
+                "domain targets require the socks4a protocol",
+            ));
+        }
+        Host::Name(domain) => Host::Name(domain),
+        Host::Address(ip) => match ip.to_canonical() {
+            IpAddr::V4(ip) => Host::Address(IpAddr::V4(ip)),
+            IpAddr::V6(_) => {
+                return Err(Socks4ProxyError::UnsupportedDestination(
+                    "ipv6 targets are not supported by the socks4 protocol",
+                ));
+            }
+        },
+    };
+    Ok(Authority::new(host, port))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rama_core::service::service_fn;
+    use rama_net::user::Basic;
+    use std::convert::Infallible;
+
+    fn mock_connector(
+        stream: tokio_test::io::Mock,
+    ) -> impl ConnectorService<
+        (),
+        rama_tcp::client::Request,
+        Connection = tokio_test::io::Mock,
+        Error = Infallible,
+    > {
+        let stream = std::sync::Mutex::new(Some(stream));
+        service_fn(move |ctx: Context<()>, req: rama_tcp::client::Request| {
+            let conn = stream.lock().unwrap().take().unwrap();
+            async move { Ok(EstablishedClientConnection { ctx, req, conn }) }
+        })
+    }
+
+    fn ctx_with_proxy(addr: &str) -> Context<()> {
+        let mut ctx = Context::default();
+        ctx.insert(ProxyAddress::try_from(addr).unwrap());
+        ctx
+    }
+
+    #[tokio::test]
+    async fn test_socks4_connect_ipv4() {
+        let stream = tokio_test::io::Builder::new()
+            .write(&[4, 1, 0, 80, 127, 0, 0, 1, 0])
+            .read(&[0, 90, 0, 0, 0, 0, 0, 0])
+            .build();
+
+        let connector = Socks4ProxyConnectorLayer::required().into_layer(mock_connector(stream));
+        connector
+            .serve(
+                ctx_with_proxy("socks4://127.0.0.1:1080"),
+                rama_tcp::client::Request::new(Authority::local_ipv4(80)),
+            )
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_socks4a_connect_hostname_with_user_id() {
+        let stream = tokio_test::io::Builder::new()
+            .write(&[4, 1, 1, 187, 0, 0, 0, 1])
+            .write(b"john\0example.com\0")
+            .read(&[0, 90, 0, 0, 0, 0, 0, 0])
+            .build();
+
+        let connector = Socks4ProxyConnectorLayer::required().into_layer(mock_connector(stream));
+        let mut ctx = ctx_with_proxy("socks4a://127.0.0.1:1080");
+        ctx.get_mut::<ProxyAddress>().unwrap().credential =
+            Some(ProxyCredential::Basic(Basic::new_insecure("john")));
+        connector
+            .serve(
+                ctx,
+                rama_tcp::client::Request::new(Authority::try_from("example.com:443").unwrap()),
+            )
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_socks4a_rejected() {
+        let stream = tokio_test::io::Builder::new()
+            .write(&[4, 1, 0, 80, 0, 0, 0, 1, 0])
+            .write(b"example.com\0")
+            .read(&[0, 91, 0, 0, 0, 0, 0, 0])
+            .build();
+
+        let connector = Socks4ProxyConnectorLayer::required().into_layer(mock_connector(stream));
+        let err = connector
+            .serve(
+                ctx_with_proxy("socks4a://127.0.0.1:1080"),
+                rama_tcp::client::Request::new(Authority::try_from("example.com:80").unwrap()),
+            )
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<Socks4ProxyError>(),
+            Some(Socks4ProxyError::Rejected(ReplyKind::Rejected))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_socks4_hostname_not_supported() {
+        let stream = tokio_test::io::Builder::new().build();
+
+        let connector = Socks4ProxyConnectorLayer::required().into_layer(mock_connector(stream));
+        let err = connector
+            .serve(
+                ctx_with_proxy("socks4://127.0.0.1:1080"),
+                rama_tcp::client::Request::new(Authority::try_from("example.com:80").unwrap()),
+            )
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<Socks4ProxyError>(),
+            Some(Socks4ProxyError::UnsupportedDestination(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_socks4_ipv6_not_supported() {
+        let stream = tokio_test::io::Builder::new().build();
+
+        let connector = Socks4ProxyConnectorLayer::required().into_layer(mock_connector(stream));
+        let err = connector
+            .serve(
+                ctx_with_proxy("socks4a://127.0.0.1:1080"),
+                rama_tcp::client::Request::new(Authority::local_ipv6(80)),
+            )
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<Socks4ProxyError>(),
+            Some(Socks4ProxyError::UnsupportedDestination(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_socks5_protocol_rejected() {
+        let stream = tokio_test::io::Builder::new().build();
+
+        let connector = Socks4ProxyConnectorLayer::required().into_layer(mock_connector(stream));
+        assert!(
+            connector
+                .serve(
+                    ctx_with_proxy("socks5://127.0.0.1:1080"),
+                    rama_tcp::client::Request::new(Authority::local_ipv4(80)),
+                )
+                .await
+                .is_err()
+        );
+    }
+}
@@ -0,0 +1,72 @@
+use super::proto::ReplyKind;
+use crate::proto::ProtocolError;
+use rama_core::error::BoxError;
+use std::fmt;
+
+#[derive(Debug)]
+/// error that can be returned in case a socks4 proxy
+/// did not manage to establish a connection
+pub enum Socks4ProxyError {
+    /// The target cannot be addressed using the socks4(a) protocol
+    ///
+    /// (e.g. an IPv6 address, or a domain for the socks4 protocol)
+    UnsupportedDestination(&'static str),
+    /// Socks4 protocol error (e.g. unexpected reply)
+    Protocol(ProtocolError),
+    /// The socks4 proxy did not grant the request
+    Rejected(ReplyKind),
+    /// I/O error happened as part of Socks4 Proxy Connection Establishment
+    ///
+    /// (e.g. some kind of TCP error)
+    Transport(BoxError),
+}
+
+impl fmt::Display for Socks4ProxyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Socks4ProxyError::UnsupportedDestination(reason) => {
+                write!(f, "socks4 proxy error: unsupported destination: {reason}")
+            }
+            Socks4ProxyError::Protocol(error) => {
+                write!(f, "socks4 proxy error: protocol error [{error}]")
+            }
+            Socks4ProxyError::Rejected(kind) => {
+                write!(f, "socks4 proxy error: request rejected [{kind:?}]")
+            }
+            Socks4ProxyError::Transport(error) => {
+                write!(f, "socks4 proxy error: transport error: I/O [{error}]")
+            }
+        }
+    }
+}
+
+impl From<std::io::Error> for Socks4ProxyError {
+    fn from(value: std::io::Error) -> Self {
+        Self::Transport(value.into())
+    }
+}
+
+impl From<ProtocolError> for Socks4ProxyError {
+    fn from(value: ProtocolError) -> Self {
+        Self::Protocol(value)
+    }
+}
+
+impl std::error::Error for Socks4ProxyError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Socks4ProxyError::UnsupportedDestination(_) | Socks4ProxyError::Rejected(_) => None,
+            Socks4ProxyError::Protocol(err) => Some(err as &dyn std::error::Error),
+            Socks4ProxyError::Transport(err) => {
+                // filter out generic io errors,
+                // but do allow custom errors (e.g. because IP is blocked)
+                let err_ref = err.source().unwrap_or_else(|| err.as_ref());
+                if err_ref.is::<std::io::Error>() {
+                    Some(self)
+                } else {
+                    Some(err_ref)
+                }
+            }
+        }
+    }
+}
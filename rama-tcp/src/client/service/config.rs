@@ -0,0 +1,115 @@
+use crate::TcpStream;
+use rama_core::error::{ErrorContext, OpaqueError};
+use rama_net::socket::core::SockRef;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+/// Socket options applied by the [`TcpConnector`] on established connections.
+///
+/// [`TcpConnector`]: super::TcpConnector
+pub struct TcpConnectorConfig {
+    /// Disable Nagle's algorithm (`TCP_NODELAY`),
+    /// sending small segments without delay at the cost of bandwidth.
+    pub nodelay: bool,
+    /// The size of the send buffer (`SO_SNDBUF`), left to the OS default if `None`.
+    pub send_buffer_size: Option<u32>,
+    /// The size of the receive buffer (`SO_RCVBUF`), left to the OS default if `None`.
+    pub recv_buffer_size: Option<u32>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// The effective socket options of a connection established by a [`TcpConnector`]
+/// configured with a [`TcpConnectorConfig`], as reported by the OS.
+///
+/// Inserted in the [`Context`] for inspection by downstream layers.
+/// Note that the buffer sizes can differ from the configured ones,
+/// e.g. Linux doubles the requested size to allow space for bookkeeping.
+///
+/// [`TcpConnector`]: super::TcpConnector
+/// [`Context`]: rama_core::Context
+pub struct TcpSocketConfig {
+    /// Whether Nagle's algorithm is disabled (`TCP_NODELAY`).
+    pub nodelay: bool,
+    /// The size of the send buffer (`SO_SNDBUF`).
+    pub send_buffer_size: u32,
+    /// The size of the receive buffer (`SO_RCVBUF`).
+    pub recv_buffer_size: u32,
+}
+
+impl TcpConnectorConfig {
+    /// Apply the socket options to the given [`TcpStream`],
+    /// returning the effective [`TcpSocketConfig`].
+    pub(super) fn apply(&self, stream: &TcpStream) -> Result<TcpSocketConfig, OpaqueError> {
+        stream
+            .set_nodelay(self.nodelay)
+            .context("set TCP_NODELAY socket option")?;
+
+        let socket = SockRef::from(stream);
+        if let Some(size) = self.send_buffer_size {
+            socket
+                .set_send_buffer_size(size as usize)
+                .context("set SO_SNDBUF socket option")?;
+        }
+        if let Some(size) = self.recv_buffer_size {
+            socket
+                .set_recv_buffer_size(size as usize)
+                .context("set SO_RCVBUF socket option")?;
+        }
+
+        Ok(TcpSocketConfig {
+            nodelay: stream.nodelay().context("get TCP_NODELAY socket option")?,
+            send_buffer_size: socket
+                .send_buffer_size()
+                .context("get SO_SNDBUF socket option")?
+                .try_into()
+                .unwrap_or(u32::MAX),
+            recv_buffer_size: socket
+                .recv_buffer_size()
+                .context("get SO_RCVBUF socket option")?
+                .try_into()
+                .unwrap_or(u32::MAX),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::Request;
+    use crate::client::service::TcpConnector;
+    use rama_core::{Context, Service};
+    use tokio::net::TcpListener;
+
+    #[tokio::test]
+    async fn test_tcp_connector_config() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        let connector = TcpConnector::new().with_tcp_config(TcpConnectorConfig {
+            nodelay: true,
+            send_buffer_size: Some(64 * 1024),
+            recv_buffer_size: None,
+        });
+        let conn = connector
+            .serve(Context::default(), Request::new(addr.into()))
+            .await
+            .unwrap();
+
+        assert!(conn.conn.nodelay().unwrap());
+        let socket_config = conn.ctx.get::<TcpSocketConfig>().unwrap();
+        assert!(socket_config.nodelay);
+        assert!(socket_config.send_buffer_size >= 64 * 1024);
+        assert!(socket_config.recv_buffer_size > 0);
+    }
+
+    #[tokio::test]
+    async fn test_tcp_connector_without_config() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        let conn = TcpConnector::new()
+            .serve(Context::default(), Request::new(addr.into()))
+            .await
+            .unwrap();
+        assert!(conn.ctx.get::<TcpSocketConfig>().is_none());
+    }
+}
@@ -14,12 +14,16 @@ use rama_net::{
 use crate::TcpStream;
 use crate::client::connect::TcpStreamConnector;
 
-use super::{CreatedTcpStreamConnector, TcpStreamConnectorCloneFactory, TcpStreamConnectorFactory};
+use super::{
+    CreatedTcpStreamConnector, TcpConnectorConfig, TcpStreamConnectorCloneFactory,
+    TcpStreamConnectorFactory,
+};
 
 /// A connector which can be used to establish a TCP connection to a server.
 pub struct TcpConnector<Dns = GlobalDnsResolver, ConnectorFactory = ()> {
     dns: Dns,
     connector_factory: ConnectorFactory,
+    tcp_config: Option<TcpConnectorConfig>,
 }
 
 impl<Dns: std::fmt::Debug, ConnectorFactory: std::fmt::Debug> std::fmt::Debug
@@ -29,6 +33,7 @@ impl<Dns: std::fmt::Debug, ConnectorFactory: std::fmt::Debug> std::fmt::Debug
         f.debug_struct("TcpConnector")
             .field("dns", &self.dns)
             .field("connector_factory", &self.connector_factory)
+            .field("tcp_config", &self.tcp_config)
             .finish()
     }
 }
@@ -38,11 +43,40 @@ impl<Dns: Clone, ConnectorFactory: Clone> Clone for TcpConnector<Dns, ConnectorF
         Self {
             dns: self.dns.clone(),
             connector_factory: self.connector_factory.clone(),
+            tcp_config: self.tcp_config.clone(),
         }
     }
 }
 
-impl<Dns, Connector> TcpConnector<Dns, Connector> {}
+impl<Dns, Connector> TcpConnector<Dns, Connector> {
+    rama_utils::macros::generate_set_and_with! {
+        /// Set the [`TcpConnectorConfig`] used to configure the socket options
+        /// of established connections, such as disabling Nagle's algorithm (`TCP_NODELAY`).
+        ///
+        /// The effective socket options are inserted in the [`Context`]
+        /// as a [`TcpSocketConfig`].
+        ///
+        /// [`TcpSocketConfig`]: super::TcpSocketConfig
+        pub fn tcp_config(mut self, config: Option<TcpConnectorConfig>) -> Self {
+            self.tcp_config = config;
+            self
+        }
+    }
+
+    fn apply_tcp_config<State>(
+        &self,
+        ctx: &mut Context<State>,
+        conn: &TcpStream,
+    ) -> Result<(), OpaqueError> {
+        if let Some(config) = &self.tcp_config {
+            let socket_config = config
+                .apply(conn)
+                .context("tcp connector: apply tcp config")?;
+            ctx.insert(socket_config);
+        }
+        Ok(())
+    }
+}
 
 impl TcpConnector {
     /// Create a new [`TcpConnector`], which is used to establish a connection to a server.
@@ -53,6 +87,7 @@ impl TcpConnector {
         Self {
             dns: GlobalDnsResolver::new(),
             connector_factory: (),
+            tcp_config: None,
         }
     }
 }
@@ -66,6 +101,7 @@ impl<Dns, ConnectorFactory> TcpConnector<Dns, ConnectorFactory> {
         TcpConnector {
             dns,
             connector_factory: self.connector_factory,
+            tcp_config: self.tcp_config,
         }
     }
 }
@@ -80,6 +116,7 @@ where {
         TcpConnector {
             dns: self.dns,
             connector_factory: TcpStreamConnectorCloneFactory(connector),
+            tcp_config: self.tcp_config,
         }
     }
 
@@ -89,6 +126,7 @@ where {
         TcpConnector {
             dns: self.dns,
             connector_factory: factory,
+            tcp_config: self.tcp_config,
         }
     }
 }
@@ -135,6 +173,7 @@ where
             )
             .await
             .context("tcp connector: conncept to proxy")?;
+            self.apply_tcp_config(&mut ctx, &conn)?;
 
             ctx.insert(ClientSocketInfo(SocketInfo::new(
                 conn.local_addr()
@@ -172,6 +211,7 @@ where
         let (conn, addr) = crate::client::tcp_connect(&ctx, authority, self.dns.clone(), connector)
             .await
             .context("tcp connector: connect to server")?;
+        self.apply_tcp_config(&mut ctx, &conn)?;
 
         ctx.insert(ClientSocketInfo(SocketInfo::new(
             conn.local_addr()
@@ -8,6 +8,10 @@ mod connector;
 #[doc(inline)]
 pub use connector::TcpConnector;
 
+mod config;
+#[doc(inline)]
+pub use config::{TcpConnectorConfig, TcpSocketConfig};
+
 mod select;
 #[doc(inline)]
 pub use select::{
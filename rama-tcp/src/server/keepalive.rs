@@ -0,0 +1,195 @@
+use crate::TcpStream;
+use rama_core::{Context, Layer, Service, telemetry::tracing};
+use rama_net::socket::core::{SockRef, TcpKeepalive};
+use rama_utils::macros::define_inner_service_accessors;
+use std::{fmt, time::Duration};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// TCP keepalive parameters applied by the [`TcpKeepaliveService`].
+pub struct TcpKeepaliveConfig {
+    /// The amount of time a connection has to be idle
+    /// before TCP keepalive probes are sent (`TCP_KEEPIDLE`).
+    pub idle: Duration,
+    /// The time interval between TCP keepalive probes (`TCP_KEEPINTVL`).
+    ///
+    /// Ignored on platforms which do not support this option.
+    pub interval: Duration,
+    /// The maximum number of TCP keepalive probes that are sent
+    /// before dropping the connection (`TCP_KEEPCNT`).
+    ///
+    /// Ignored on platforms which do not support this option (e.g. Windows).
+    pub retries: u32,
+}
+
+impl Default for TcpKeepaliveConfig {
+    fn default() -> Self {
+        Self {
+            idle: Duration::from_secs(60),
+            interval: Duration::from_secs(10),
+            retries: 5,
+        }
+    }
+}
+
+impl TcpKeepaliveConfig {
+    fn to_socket_keepalive(&self) -> TcpKeepalive {
+        let ka = TcpKeepalive::new().with_time(self.idle);
+
+        #[cfg(not(any(
+            target_os = "openbsd",
+            target_os = "redox",
+            target_os = "solaris",
+            target_os = "nto",
+            target_os = "espidf",
+            target_os = "vita",
+            target_os = "haiku",
+        )))]
+        let ka = ka.with_interval(self.interval);
+
+        #[cfg(not(any(
+            target_os = "openbsd",
+            target_os = "redox",
+            target_os = "solaris",
+            target_os = "windows",
+            target_os = "nto",
+            target_os = "espidf",
+            target_os = "vita",
+            target_os = "haiku",
+        )))]
+        let ka = ka.with_retries(self.retries);
+
+        ka
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+/// A [`Layer`] that produces [`TcpKeepaliveService`]s,
+/// enabling TCP keepalive (`SO_KEEPALIVE`) on accepted connections.
+///
+/// Keepalive probes allow the detection of dead peers,
+/// e.g. connections behind a NAT which silently dropped them,
+/// which would otherwise be kept open by the OS indefinitely.
+pub struct TcpKeepaliveLayer {
+    config: TcpKeepaliveConfig,
+}
+
+impl TcpKeepaliveLayer {
+    /// Create a new [`TcpKeepaliveLayer`] using the given [`TcpKeepaliveConfig`].
+    pub const fn new(config: TcpKeepaliveConfig) -> Self {
+        Self { config }
+    }
+}
+
+impl<S> Layer<S> for TcpKeepaliveLayer {
+    type Service = TcpKeepaliveService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        TcpKeepaliveService::new(inner, self.config.clone())
+    }
+
+    fn into_layer(self, inner: S) -> Self::Service {
+        TcpKeepaliveService::new(inner, self.config)
+    }
+}
+
+/// A [`Service`] which enables TCP keepalive on the [`TcpStream`]
+/// prior to passing it to the inner [`Service`].
+///
+/// Failing to set the socket options is logged, but does not fail the connection.
+///
+/// See [`TcpKeepaliveLayer`] for more information.
+pub struct TcpKeepaliveService<S> {
+    inner: S,
+    config: TcpKeepaliveConfig,
+}
+
+impl<S> TcpKeepaliveService<S> {
+    /// Create a new [`TcpKeepaliveService`] using the given [`TcpKeepaliveConfig`].
+    pub const fn new(inner: S, config: TcpKeepaliveConfig) -> Self {
+        Self { inner, config }
+    }
+
+    define_inner_service_accessors!();
+}
+
+impl<S: fmt::Debug> fmt::Debug for TcpKeepaliveService<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TcpKeepaliveService")
+            .field("inner", &self.inner)
+            .field("config", &self.config)
+            .finish()
+    }
+}
+
+impl<S: Clone> Clone for TcpKeepaliveService<S> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            config: self.config.clone(),
+        }
+    }
+}
+
+impl<S, State> Service<State, TcpStream> for TcpKeepaliveService<S>
+where
+    S: Service<State, TcpStream>,
+    State: Clone + Send + Sync + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+
+    fn serve(
+        &self,
+        ctx: Context<State>,
+        stream: TcpStream,
+    ) -> impl Future<Output = Result<Self::Response, Self::Error>> + Send + '_ {
+        if let Err(err) =
+            SockRef::from(&stream).set_tcp_keepalive(&self.config.to_socket_keepalive())
+        {
+            tracing::debug!("failed to enable tcp keepalive on accepted connection: {err}");
+        }
+        self.inner.serve(ctx, stream)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rama_core::service::service_fn;
+    use std::convert::Infallible;
+    use tokio::net::TcpListener;
+
+    #[tokio::test]
+    async fn test_tcp_keepalive_service() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let _client = tokio::net::TcpStream::connect(addr).await.unwrap();
+        let (stream, _) = listener.accept().await.unwrap();
+        assert!(!SockRef::from(&stream).keepalive().unwrap());
+
+        let config = TcpKeepaliveConfig {
+            idle: Duration::from_secs(30),
+            interval: Duration::from_secs(5),
+            retries: 3,
+        };
+        let svc =
+            TcpKeepaliveLayer::new(config).into_layer(service_fn(async |stream: TcpStream| {
+                let socket = SockRef::from(&stream);
+                assert!(socket.keepalive().unwrap());
+                #[cfg(target_os = "linux")]
+                {
+                    assert_eq!(
+                        socket.tcp_keepalive_time().unwrap(),
+                        Duration::from_secs(30)
+                    );
+                    assert_eq!(
+                        socket.tcp_keepalive_interval().unwrap(),
+                        Duration::from_secs(5)
+                    );
+                    assert_eq!(socket.tcp_keepalive_retries().unwrap(), 3);
+                }
+                Ok::<_, Infallible>(())
+            }));
+        svc.serve(Context::default(), stream).await.unwrap();
+    }
+}
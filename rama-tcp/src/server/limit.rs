@@ -0,0 +1,253 @@
+use crate::TcpStream;
+use rama_core::{
+    Context, Layer, Service,
+    error::{BoxError, OpaqueError},
+    telemetry::tracing,
+};
+use rama_utils::macros::define_inner_service_accessors;
+use std::{
+    fmt,
+    sync::{
+        Arc,
+        atomic::{AtomicUsize, Ordering},
+    },
+};
+
+#[derive(Debug, Clone, Default)]
+/// A shared handle to the number of currently active TCP connections,
+/// as tracked by the [`TcpConnectionLimitService`].
+///
+/// It can be cloned and used to read the current count, e.g. from a metrics endpoint.
+pub struct ConnectionCount(Arc<AtomicUsize>);
+
+impl ConnectionCount {
+    /// Return the number of currently active TCP connections.
+    pub fn get(&self) -> usize {
+        self.0.load(Ordering::Acquire)
+    }
+
+    fn try_acquire(&self, max: usize) -> Option<ConnectionGuard> {
+        self.0
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |count| {
+                (count < max).then_some(count + 1)
+            })
+            .ok()
+            .map(|_| ConnectionGuard(self.0.clone()))
+    }
+}
+
+/// Decrements the [`ConnectionCount`] when dropped,
+/// which also happens in case the connection handler panics.
+struct ConnectionGuard(Arc<AtomicUsize>);
+
+impl Drop for ConnectionGuard {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+#[derive(Debug, Clone)]
+/// A [`Layer`] that produces [`TcpConnectionLimitService`]s,
+/// limiting the number of concurrently active TCP connections.
+///
+/// All services created by the same layer share the same [`ConnectionCount`].
+pub struct TcpConnectionLimitLayer {
+    max: usize,
+    count: ConnectionCount,
+}
+
+impl TcpConnectionLimitLayer {
+    /// Create a new [`TcpConnectionLimitLayer`],
+    /// allowing at most `max` concurrently active TCP connections.
+    pub fn new(max: usize) -> Self {
+        Self {
+            max,
+            count: ConnectionCount::default(),
+        }
+    }
+
+    /// Return a handle to the [`ConnectionCount`] tracked by this layer.
+    pub fn connection_count(&self) -> ConnectionCount {
+        self.count.clone()
+    }
+}
+
+impl<S> Layer<S> for TcpConnectionLimitLayer {
+    type Service = TcpConnectionLimitService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        TcpConnectionLimitService {
+            inner,
+            max: self.max,
+            count: self.count.clone(),
+        }
+    }
+
+    fn into_layer(self, inner: S) -> Self::Service {
+        TcpConnectionLimitService {
+            inner,
+            max: self.max,
+            count: self.count,
+        }
+    }
+}
+
+/// A [`Service`] which limits the number of concurrently active TCP connections.
+///
+/// Connections accepted while the limit is reached are closed immediately,
+/// by dropping the [`TcpStream`] without passing it to the inner [`Service`].
+///
+/// See [`TcpConnectionLimitLayer`] for more information.
+pub struct TcpConnectionLimitService<S> {
+    inner: S,
+    max: usize,
+    count: ConnectionCount,
+}
+
+impl<S> TcpConnectionLimitService<S> {
+    /// Create a new [`TcpConnectionLimitService`],
+    /// allowing at most `max` concurrently active TCP connections.
+    pub fn new(inner: S, max: usize) -> Self {
+        Self {
+            inner,
+            max,
+            count: ConnectionCount::default(),
+        }
+    }
+
+    /// Return a handle to the [`ConnectionCount`] tracked by this service.
+    pub fn connection_count(&self) -> ConnectionCount {
+        self.count.clone()
+    }
+
+    define_inner_service_accessors!();
+}
+
+impl<S: fmt::Debug> fmt::Debug for TcpConnectionLimitService<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TcpConnectionLimitService")
+            .field("inner", &self.inner)
+            .field("max", &self.max)
+            .field("count", &self.count)
+            .finish()
+    }
+}
+
+impl<S: Clone> Clone for TcpConnectionLimitService<S> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            max: self.max,
+            count: self.count.clone(),
+        }
+    }
+}
+
+impl<S, State> Service<State, TcpStream> for TcpConnectionLimitService<S>
+where
+    S: Service<State, TcpStream, Error: Into<BoxError>>,
+    State: Clone + Send + Sync + 'static,
+{
+    type Response = S::Response;
+    type Error = BoxError;
+
+    async fn serve(
+        &self,
+        ctx: Context<State>,
+        stream: TcpStream,
+    ) -> Result<Self::Response, Self::Error> {
+        let Some(_guard) = self.count.try_acquire(self.max) else {
+            match stream.peer_addr() {
+                Ok(addr) => tracing::warn!(
+                    network.peer.address = %addr.ip(),
+                    network.peer.port = %addr.port(),
+                    "tcp connection limit of {} reached: reject connection",
+                    self.max,
+                ),
+                Err(_) => tracing::warn!(
+                    "tcp connection limit of {} reached: reject connection",
+                    self.max
+                ),
+            }
+            return Err(OpaqueError::from_display("tcp connection limit reached").into_boxed());
+        };
+        self.inner.serve(ctx, stream).await.map_err(Into::into)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rama_core::service::service_fn;
+    use std::{convert::Infallible, time::Duration};
+    use tokio::{io::AsyncReadExt, net::TcpListener, sync::oneshot};
+
+    async fn tcp_stream_pair(listener: &TcpListener) -> (TcpStream, TcpStream) {
+        let client = TcpStream::connect(listener.local_addr().unwrap())
+            .await
+            .unwrap();
+        let (server, _) = listener.accept().await.unwrap();
+        (client, server)
+    }
+
+    #[tokio::test]
+    async fn test_tcp_connection_limit() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+
+        let (release_tx, release_rx) = oneshot::channel::<()>();
+        let release_rx = Arc::new(tokio::sync::Mutex::new(Some(release_rx)));
+        let layer = TcpConnectionLimitLayer::new(1);
+        let count = layer.connection_count();
+        let svc = Arc::new(layer.into_layer(service_fn(move |_stream: TcpStream| {
+            let release_rx = release_rx.clone();
+            async move {
+                if let Some(rx) = release_rx.lock().await.take() {
+                    let _ = rx.await;
+                }
+                Ok::<_, Infallible>(())
+            }
+        })));
+
+        let (_client, server) = tcp_stream_pair(&listener).await;
+        let first = tokio::spawn({
+            let svc = svc.clone();
+            async move { svc.serve(Context::default(), server).await }
+        });
+        while count.get() == 0 {
+            tokio::time::sleep(Duration::from_millis(1)).await;
+        }
+
+        // the second connection is rejected and closed
+        let (mut client, server) = tcp_stream_pair(&listener).await;
+        assert!(svc.serve(Context::default(), server).await.is_err());
+        assert_eq!(client.read(&mut [0u8; 1]).await.unwrap(), 0);
+        assert_eq!(count.get(), 1);
+
+        release_tx.send(()).unwrap();
+        first.await.unwrap().unwrap();
+        assert_eq!(count.get(), 0);
+
+        // once released, connections are accepted again
+        let (_client, server) = tcp_stream_pair(&listener).await;
+        svc.serve(Context::default(), server).await.unwrap();
+        assert_eq!(count.get(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_tcp_connection_limit_decrements_on_panic() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+
+        let layer = TcpConnectionLimitLayer::new(1);
+        let count = layer.connection_count();
+        let svc = layer.into_layer(service_fn(async |_stream: TcpStream| {
+            panic!("connection handler panic");
+            #[allow(unreachable_code)]
+            Ok::<_, Infallible>(())
+        }));
+
+        let (_client, server) = tcp_stream_pair(&listener).await;
+        let result = tokio::spawn(async move { svc.serve(Context::default(), server).await }).await;
+        assert!(result.unwrap_err().is_panic());
+        assert_eq!(count.get(), 0);
+    }
+}
@@ -41,3 +41,11 @@
 mod listener;
 #[doc(inline)]
 pub use listener::{TcpListener, TcpListenerBuilder};
+
+mod keepalive;
+#[doc(inline)]
+pub use keepalive::{TcpKeepaliveConfig, TcpKeepaliveLayer, TcpKeepaliveService};
+
+mod limit;
+#[doc(inline)]
+pub use limit::{ConnectionCount, TcpConnectionLimitLayer, TcpConnectionLimitService};
@@ -1,20 +1,26 @@
 use crate::RamaTryInto;
 use rama_boring_tokio::SslStream;
-use rama_core::error::{BoxError, ErrorExt, OpaqueError};
+use rama_core::error::{BoxError, ErrorContext, ErrorExt, OpaqueError};
+use rama_core::futures::FutureExt;
+use rama_core::graceful::WeakShutdownGuard;
 use rama_core::telemetry::tracing;
 use rama_core::{Context, Layer, Service};
+use rama_http_types::Version;
 use rama_http_types::conn::TargetHttpVersion;
 use rama_net::address::Host;
 use rama_net::client::{ConnectorService, EstablishedClientConnection};
 use rama_net::stream::Stream;
 use rama_net::tls::ApplicationProtocol;
-use rama_net::tls::client::NegotiatedTlsParameters;
+use rama_net::tls::client::{NegotiatedTlsParameters, TlsConnectorShutdown, TlsHandshakeMetrics};
 use rama_net::transport::TryRefIntoTransportContext;
 use rama_utils::macros::generate_set_and_with;
 use std::fmt;
 use std::sync::Arc;
+use std::time::Instant;
 
-use super::{AutoTlsStream, TlsConnectorData, TlsConnectorDataBuilder, TlsStream};
+use super::{AutoTlsStream, HostTlsConfig, TlsConnectorData, TlsConnectorDataBuilder, TlsStream};
+use crate::server::ClientHelloExtensions;
+use crate::type_conversion::cipher_suite_from_ssl_cipher;
 use crate::types::TlsTunnel;
 
 /// A [`Layer`] which wraps the given service with a [`TlsConnector`].
@@ -22,6 +28,8 @@ use crate::types::TlsTunnel;
 /// See [`TlsConnector`] for more information.
 pub struct TlsConnectorLayer<K = ConnectorKindAuto> {
     connector_data: Option<Arc<TlsConnectorDataBuilder>>,
+    host_connector_data: Option<Arc<HostTlsConfig>>,
+    graceful_guard: Option<WeakShutdownGuard>,
     kind: K,
 }
 
@@ -29,6 +37,8 @@ impl<K: fmt::Debug> std::fmt::Debug for TlsConnectorLayer<K> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("TlsConnectorLayer")
             .field("connector_data", &self.connector_data)
+            .field("host_connector_data", &self.host_connector_data)
+            .field("graceful_guard", &self.graceful_guard)
             .field("kind", &self.kind)
             .finish()
     }
@@ -38,6 +48,8 @@ impl<K: Clone> Clone for TlsConnectorLayer<K> {
     fn clone(&self) -> Self {
         Self {
             connector_data: self.connector_data.clone(),
+            host_connector_data: self.host_connector_data.clone(),
+            graceful_guard: self.graceful_guard.clone(),
             kind: self.kind.clone(),
         }
     }
@@ -63,6 +75,42 @@ impl<K> TlsConnectorLayer<K> {
             self
         }
     );
+
+    generate_set_and_with!(
+        /// Set the [`HostTlsConfig`] used to override the base
+        /// [`TlsConnectorDataBuilder`] for specific hosts.
+        pub fn host_tls_config(mut self, config: Option<Arc<HostTlsConfig>>) -> Self {
+            self.host_connector_data = config;
+            self
+        }
+    );
+
+    generate_set_and_with!(
+        /// Set the [`TlsConnectorDataBuilder`] to be used for the given host,
+        /// chained on top of the base [`TlsConnectorDataBuilder`] of this connector.
+        ///
+        /// See [`HostTlsConfig`] for more information.
+        pub fn host_connector_data(
+            mut self,
+            host: Host,
+            connector_data: Arc<TlsConnectorDataBuilder>,
+        ) -> Self {
+            Arc::make_mut(self.host_connector_data.get_or_insert_default())
+                .set_host(host, connector_data);
+            self
+        }
+    );
+
+    generate_set_and_with!(
+        /// Set the [`WeakShutdownGuard`] used by the [`TlsConnector`] created by this layer.
+        ///
+        /// New connections are refused with a [`TlsConnectorShutdown`] error
+        /// once its shutdown is initiated.
+        pub fn graceful_guard(mut self, guard: Option<WeakShutdownGuard>) -> Self {
+            self.graceful_guard = guard;
+            self
+        }
+    );
 }
 
 impl TlsConnectorLayer<ConnectorKindAuto> {
@@ -72,6 +120,8 @@ impl TlsConnectorLayer<ConnectorKindAuto> {
     pub fn auto() -> Self {
         Self {
             connector_data: None,
+            host_connector_data: None,
+            graceful_guard: None,
             kind: ConnectorKindAuto,
         }
     }
@@ -83,6 +133,8 @@ impl TlsConnectorLayer<ConnectorKindSecure> {
     pub fn secure() -> Self {
         Self {
             connector_data: None,
+            host_connector_data: None,
+            graceful_guard: None,
             kind: ConnectorKindSecure,
         }
     }
@@ -94,6 +146,8 @@ impl TlsConnectorLayer<ConnectorKindTunnel> {
     pub fn tunnel(host: Option<Host>) -> Self {
         Self {
             connector_data: None,
+            host_connector_data: None,
+            graceful_guard: None,
             kind: ConnectorKindTunnel { host },
         }
     }
@@ -106,6 +160,8 @@ impl<K: Clone, S> Layer<S> for TlsConnectorLayer<K> {
         TlsConnector {
             inner,
             connector_data: self.connector_data.clone(),
+            host_connector_data: self.host_connector_data.clone(),
+            graceful_guard: self.graceful_guard.clone(),
             kind: self.kind.clone(),
         }
     }
@@ -114,6 +170,8 @@ impl<K: Clone, S> Layer<S> for TlsConnectorLayer<K> {
         TlsConnector {
             inner,
             connector_data: self.connector_data,
+            host_connector_data: self.host_connector_data,
+            graceful_guard: self.graceful_guard,
             kind: self.kind,
         }
     }
@@ -132,9 +190,14 @@ impl Default for TlsConnectorLayer<ConnectorKindAuto> {
 /// only if the request requires a secure connection. You can instead use
 /// [`TlsConnector::secure_only`] to force the connector to always
 /// establish a secure connection.
+///
+/// A [`WeakShutdownGuard`] can be attached to refuse new connections once
+/// shutdown is initiated, while the connections already established are allowed to drain.
 pub struct TlsConnector<S, K = ConnectorKindAuto> {
     inner: S,
     connector_data: Option<Arc<TlsConnectorDataBuilder>>,
+    host_connector_data: Option<Arc<HostTlsConfig>>,
+    graceful_guard: Option<WeakShutdownGuard>,
     kind: K,
 }
 
@@ -143,6 +206,8 @@ impl<S: fmt::Debug, K: fmt::Debug> fmt::Debug for TlsConnector<S, K> {
         f.debug_struct("TlsConnector")
             .field("inner", &self.inner)
             .field("connector_data", &self.connector_data)
+            .field("host_connector_data", &self.host_connector_data)
+            .field("graceful_guard", &self.graceful_guard)
             .field("kind", &self.kind)
             .finish()
     }
@@ -153,6 +218,8 @@ impl<S: Clone, K: Clone> Clone for TlsConnector<S, K> {
         Self {
             inner: self.inner.clone(),
             connector_data: self.connector_data.clone(),
+            host_connector_data: self.host_connector_data.clone(),
+            graceful_guard: self.graceful_guard.clone(),
             kind: self.kind.clone(),
         }
     }
@@ -164,6 +231,8 @@ impl<S, K> TlsConnector<S, K> {
         Self {
             inner,
             connector_data: None,
+            host_connector_data: None,
+            graceful_guard: None,
             kind,
         }
     }
@@ -187,6 +256,42 @@ impl<S, K> TlsConnector<S, K> {
             self
         }
     );
+
+    generate_set_and_with!(
+        /// Set the [`HostTlsConfig`] used to override the base
+        /// [`TlsConnectorDataBuilder`] for specific hosts.
+        pub fn host_tls_config(mut self, config: Option<Arc<HostTlsConfig>>) -> Self {
+            self.host_connector_data = config;
+            self
+        }
+    );
+
+    generate_set_and_with!(
+        /// Set the [`TlsConnectorDataBuilder`] to be used for the given host,
+        /// chained on top of the base [`TlsConnectorDataBuilder`] of this connector.
+        ///
+        /// See [`HostTlsConfig`] for more information.
+        pub fn host_connector_data(
+            mut self,
+            host: Host,
+            connector_data: Arc<TlsConnectorDataBuilder>,
+        ) -> Self {
+            Arc::make_mut(self.host_connector_data.get_or_insert_default())
+                .set_host(host, connector_data);
+            self
+        }
+    );
+
+    generate_set_and_with!(
+        /// Set the [`WeakShutdownGuard`] used by this [`TlsConnector`].
+        ///
+        /// New connections are refused with a [`TlsConnectorShutdown`] error
+        /// once its shutdown is initiated.
+        pub fn graceful_guard(mut self, guard: Option<WeakShutdownGuard>) -> Self {
+            self.graceful_guard = guard;
+            self
+        }
+    );
 }
 
 impl<S> TlsConnector<S, ConnectorKindAuto> {
@@ -231,6 +336,8 @@ where
         ctx: Context<State>,
         req: Request,
     ) -> Result<Self::Response, Self::Error> {
+        self.ensure_not_shutting_down()?;
+
         let EstablishedClientConnection { mut ctx, req, conn } =
             self.inner.connect(ctx, req).await.map_err(Into::into)?;
 
@@ -262,7 +369,7 @@ where
 
         let host = transport_ctx.authority.host().clone();
 
-        let connector_data = self.connector_data(&mut ctx)?;
+        let connector_data = self.connector_data(&mut ctx, &host)?;
         let (stream, negotiated_params) = handshake(connector_data, host, conn).await?;
 
         tracing::trace!(
@@ -273,11 +380,22 @@ where
 
         ctx.insert(negotiated_params);
 
-        Ok(EstablishedClientConnection {
-            ctx,
-            req,
-            conn: AutoTlsStream::secure(stream),
-        })
+        let conn = AutoTlsStream::secure(stream);
+
+        if conn.negotiated_protocol() == Some(ApplicationProtocol::HTTP_2)
+            && ctx
+                .get::<TargetHttpVersion>()
+                .is_none_or(|TargetHttpVersion(version)| *version == Version::HTTP_11)
+        {
+            tracing::trace!(
+                server.address = %transport_ctx.authority.host(),
+                server.port = %transport_ctx.authority.port(),
+                "TlsConnector(auto): h2 negotiated using ALPN, upgrade target http version to HTTP/2",
+            );
+            ctx.insert(TargetHttpVersion(Version::HTTP_2));
+        }
+
+        Ok(EstablishedClientConnection { ctx, req, conn })
     }
 }
 
@@ -296,6 +414,8 @@ where
         ctx: Context<State>,
         req: Request,
     ) -> Result<Self::Response, Self::Error> {
+        self.ensure_not_shutting_down()?;
+
         let EstablishedClientConnection { mut ctx, req, conn } =
             self.inner.connect(ctx, req).await.map_err(Into::into)?;
 
@@ -314,7 +434,7 @@ where
 
         let host = transport_ctx.authority.host().clone();
 
-        let connector_data = self.connector_data(&mut ctx)?;
+        let connector_data = self.connector_data(&mut ctx, &host)?;
         let (conn, negotiated_params) = handshake(connector_data, host, conn).await?;
         let conn = TlsStream::new(conn);
         ctx.insert(negotiated_params);
@@ -337,6 +457,8 @@ where
         ctx: Context<State>,
         req: Request,
     ) -> Result<Self::Response, Self::Error> {
+        self.ensure_not_shutting_down()?;
+
         let EstablishedClientConnection { mut ctx, req, conn } =
             self.inner.connect(ctx, req).await.map_err(Into::into)?;
 
@@ -359,7 +481,7 @@ where
             }
         };
 
-        let connector_data = self.connector_data(&mut ctx)?;
+        let connector_data = self.connector_data(&mut ctx, &host)?;
         let (stream, negotiated_params) = handshake(connector_data, host, conn).await?;
         ctx.insert(negotiated_params);
 
@@ -373,21 +495,62 @@ where
 }
 
 impl<S, K> TlsConnector<S, K> {
+    fn ensure_not_shutting_down(&self) -> Result<(), BoxError> {
+        if self
+            .graceful_guard
+            .as_ref()
+            .is_some_and(|guard| guard.shutdown_signal_triggered().now_or_never().is_some())
+        {
+            tracing::debug!("TlsConnector: refuse new connection: shutdown in progress");
+            return Err(TlsConnectorShutdown::new().into());
+        }
+        Ok(())
+    }
+
     fn connector_data<State: 'static>(
         &self,
         ctx: &mut Context<State>,
+        host: &Host,
     ) -> Result<TlsConnectorData, OpaqueError> {
         let target_version = ctx
             .get::<TargetHttpVersion>()
             .map(|version| ApplicationProtocol::try_from(version.0))
             .transpose()?;
 
+        let host_builder = ctx
+            .get::<HostTlsConfig>()
+            .and_then(|config| config.get(host))
+            .or_else(|| {
+                self.host_connector_data
+                    .as_ref()
+                    .and_then(|config| config.get(host))
+            })
+            .cloned();
+
+        let client_hello_extensions = ctx.get::<ClientHelloExtensions>().cloned();
+
         let builder = ctx.get_or_insert_default::<TlsConnectorDataBuilder>();
 
+        if let Some(host_builder) = host_builder {
+            builder.prepend_base_config(host_builder);
+        }
         if let Some(base_builder) = self.connector_data.clone() {
             builder.prepend_base_config(base_builder);
         }
 
+        if builder.mirror_client_hello().unwrap_or_default() {
+            match client_hello_extensions {
+                Some(extensions) => {
+                    builder.try_set_client_hello_extensions(&extensions)?;
+                }
+                None => {
+                    tracing::debug!(
+                        "TlsConnector: mirror client hello: no client hello extensions found in context"
+                    );
+                }
+            }
+        }
+
         if let Some(target_version) = target_version {
             builder.try_set_rama_alpn_protos(&[target_version])?;
         }
@@ -409,15 +572,20 @@ where
     };
 
     let server_host = data.server_name.map(Host::Name).unwrap_or(server_host);
-    let stream: SslStream<T> =
-        rama_boring_tokio::connect(data.config, server_host.to_string().as_str(), stream)
+    let server_host = server_host.to_string();
+    let connect = rama_boring_tokio::connect(data.config, server_host.as_str(), stream);
+    let result = match data.handshake_timeout {
+        Some(timeout) => tokio::time::timeout(timeout, connect)
             .await
-            .map_err(|err| match err.as_io_error() {
-                Some(err) => OpaqueError::from_display(err.to_string())
-                    .context("boring ssl connector: connect")
-                    .into_boxed(),
-                None => OpaqueError::from_display("boring ssl connector: connect").into_boxed(),
-            })?;
+            .context("tls handshake timeout")?,
+        None => connect.await,
+    };
+    let stream: SslStream<T> = result.map_err(|err| match err.as_io_error() {
+        Some(err) => OpaqueError::from_display(err.to_string())
+            .context("boring ssl connector: connect")
+            .into_boxed(),
+        None => OpaqueError::from_display("boring ssl connector: connect").into_boxed(),
+    })?;
     Ok(TlsStream::new(stream))
 }
 
@@ -426,6 +594,31 @@ async fn handshake<T>(
     server_host: Host,
     stream: T,
 ) -> Result<(SslStream<T>, NegotiatedTlsParameters), BoxError>
+where
+    T: Stream + Unpin,
+{
+    let Some(metrics_recorder) = connector_data.metrics_recorder.clone() else {
+        return negotiate(connector_data, server_host, stream).await;
+    };
+
+    let start = Instant::now();
+    let result = negotiate(connector_data, server_host, stream).await;
+    let params = result.as_ref().ok().map(|(_, params)| params);
+    metrics_recorder.record_handshake(&TlsHandshakeMetrics {
+        duration: start.elapsed(),
+        success: params.is_some(),
+        protocol_version: params.map(|params| params.protocol_version),
+        application_layer_protocol: params
+            .and_then(|params| params.application_layer_protocol.clone()),
+    });
+    result
+}
+
+async fn negotiate<T>(
+    connector_data: TlsConnectorData,
+    server_host: Host,
+    stream: T,
+) -> Result<(SslStream<T>, NegotiatedTlsParameters), BoxError>
 where
     T: Stream + Unpin,
 {
@@ -458,9 +651,15 @@ where
                 None => None,
             };
 
+            let cipher_suite = stream
+                .ssl()
+                .current_cipher()
+                .map(cipher_suite_from_ssl_cipher);
+
             NegotiatedTlsParameters {
                 protocol_version,
                 application_layer_protocol,
+                cipher_suite,
                 peer_certificate_chain: server_certificate_chain,
             }
         }
@@ -508,6 +707,7 @@ pub struct ConnectorKindTunnel {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use rama_net::tls::client::TlsMetricsRecorder;
 
     #[test]
     fn assert_send() {
@@ -522,4 +722,94 @@ mod tests {
 
         assert_sync::<TlsConnectorLayer>();
     }
+
+    #[test]
+    fn host_connector_data_override() {
+        use rama_net::address::Domain;
+
+        let builder = |name: &'static str| {
+            Arc::new(TlsConnectorDataBuilder::new().with_server_name(Domain::from_static(name)))
+        };
+        let server_name = |connector: &TlsConnector<(), ConnectorKindSecure>,
+                           mut ctx: Context<()>,
+                           host: &'static str| {
+            connector
+                .connector_data(&mut ctx, &Host::Name(Domain::from_static(host)))
+                .unwrap()
+                .server_name
+                .unwrap()
+                .to_string()
+        };
+
+        let connector = TlsConnector::secure(())
+            .with_connector_data(builder("base.example"))
+            .with_host_connector_data(
+                Host::Name(Domain::from_static("a.example")),
+                builder("override.example"),
+            );
+
+        assert_eq!(
+            server_name(&connector, Context::default(), "a.example"),
+            "override.example"
+        );
+        assert_eq!(
+            server_name(&connector, Context::default(), "b.example"),
+            "base.example"
+        );
+
+        let mut ctx = Context::default();
+        ctx.insert(HostTlsConfig::new().with_host(
+            Host::Name(Domain::from_static("a.example")),
+            builder("ctx.example"),
+        ));
+        assert_eq!(server_name(&connector, ctx, "a.example"), "ctx.example");
+    }
+
+    #[tokio::test]
+    async fn tls_connect_handshake_timeout() {
+        // the server side of the stream is kept open but never responds,
+        // stalling the handshake
+        let (client, _server) = tokio::io::duplex(1024);
+
+        let data = TlsConnectorDataBuilder::new()
+            .with_handshake_timeout(std::time::Duration::from_millis(1))
+            .build()
+            .unwrap();
+
+        let err = tls_connect(Host::LOCALHOST_NAME, client, Some(data))
+            .await
+            .expect_err("handshake to time out");
+        assert!(err.to_string().contains("tls handshake timeout"), "{err}");
+    }
+
+    #[tokio::test]
+    async fn handshake_records_metrics() {
+        #[derive(Debug, Default)]
+        struct Recorder(parking_lot::Mutex<Vec<TlsHandshakeMetrics>>);
+
+        impl TlsMetricsRecorder for Recorder {
+            fn record_handshake(&self, metrics: &TlsHandshakeMetrics) {
+                self.0.lock().push(metrics.clone());
+            }
+        }
+
+        // the server side of the stream is dropped, failing the handshake
+        let (client, _) = tokio::io::duplex(1024);
+
+        let recorder = Arc::new(Recorder::default());
+        let data = TlsConnectorDataBuilder::new()
+            .with_metrics_recorder(recorder.clone())
+            .build()
+            .unwrap();
+
+        handshake(data, Host::LOCALHOST_NAME, client)
+            .await
+            .expect_err("handshake to fail");
+
+        let metrics = recorder.0.lock();
+        assert_eq!(metrics.len(), 1);
+        assert!(!metrics[0].success);
+        assert!(metrics[0].protocol_version.is_none());
+        assert!(metrics[0].application_layer_protocol.is_none());
+    }
 }
@@ -6,7 +6,10 @@ use rama_boring::{
     hash::MessageDigest,
     pkey::{PKey, Private},
     rsa::Rsa,
-    ssl::{ConnectConfiguration, SslCurve, SslSignatureAlgorithm, SslVerifyMode, SslVersion},
+    ssl::{
+        ConnectConfiguration, SslCurve, SslOptions, SslSignatureAlgorithm, SslVerifyMode,
+        SslVersion,
+    },
     x509::{
         X509,
         extension::{BasicConstraints, KeyUsage, SubjectKeyIdentifier},
@@ -25,9 +28,12 @@ use rama_net::tls::{
     DataEncoding,
     client::{ClientAuth, ClientHelloExtension},
 };
-use rama_net::{address::Domain, tls::client::ServerVerifyMode};
+use rama_net::{
+    address::{Domain, Host},
+    tls::client::{ServerVerifyMode, TlsMetricsRecorder},
+};
 use rama_utils::macros::generate_set_and_with;
-use std::{fmt, sync::Arc};
+use std::{collections::HashMap, fmt, sync::Arc, time::Duration};
 
 #[cfg(feature = "compression")]
 use super::compress_certificate::{
@@ -35,6 +41,7 @@ use super::compress_certificate::{
 };
 
 use crate::keylog::new_key_log_file_handle;
+use crate::server::ClientHelloExtensions;
 
 /// [`TlsConnectorData`] that will be used by the connector
 ///
@@ -46,7 +53,10 @@ use crate::keylog::new_key_log_file_handle;
 pub struct TlsConnectorData {
     pub config: ConnectConfiguration,
     pub store_server_certificate_chain: bool,
+    pub mirror_client_hello: bool,
     pub server_name: Option<Domain>,
+    pub handshake_timeout: Option<Duration>,
+    pub metrics_recorder: Option<Arc<dyn TlsMetricsRecorder>>,
 }
 
 impl std::fmt::Debug for TlsConnectorData {
@@ -56,7 +66,10 @@ impl std::fmt::Debug for TlsConnectorData {
                 "store_server_certificate_chain",
                 &self.store_server_certificate_chain,
             )
+            .field("mirror_client_hello", &self.mirror_client_hello)
             .field("server_name", &self.server_name)
+            .field("handshake_timeout", &self.handshake_timeout)
+            .field("metrics_recorder", &self.metrics_recorder)
             .finish()
     }
 }
@@ -67,6 +80,44 @@ impl TlsConnectorData {
     }
 }
 
+#[derive(Debug, Clone, Default)]
+/// Per host [`TlsConnectorDataBuilder`] overrides, used by the [`TlsConnector`]
+/// to connect to backends requiring a different tls config,
+/// e.g. a different CA or client certificate.
+///
+/// The builder registered for the server host is chained on top of the base
+/// builder of the [`TlsConnector`], and below the [`TlsConnectorDataBuilder`]
+/// found in the context. A [`HostTlsConfig`] found in the context takes precedence
+/// over the one configured on the [`TlsConnector`], allowing dynamic injection.
+///
+/// [`TlsConnector`]: super::TlsConnector
+pub struct HostTlsConfig(HashMap<Host, Arc<TlsConnectorDataBuilder>>);
+
+impl HostTlsConfig {
+    /// Create a new empty [`HostTlsConfig`].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    generate_set_and_with!(
+        /// Set the [`TlsConnectorDataBuilder`] to be used for the given host.
+        pub fn host(mut self, host: Host, connector_data: Arc<TlsConnectorDataBuilder>) -> Self {
+            self.0.insert(host, connector_data);
+            self
+        }
+    );
+
+    /// Get the [`TlsConnectorDataBuilder`] registered for the given host.
+    pub fn get(&self, host: &Host) -> Option<&Arc<TlsConnectorDataBuilder>> {
+        self.0.get(host)
+    }
+
+    /// Remove the [`TlsConnectorDataBuilder`] registered for the given host.
+    pub fn remove(&mut self, host: &Host) -> Option<Arc<TlsConnectorDataBuilder>> {
+        self.0.remove(host)
+    }
+}
+
 #[derive(Clone, Default)]
 /// Use [`TlsConnectorDataBuilder`] to build a [`TlsConnectorData`] in an ergonomic way
 ///
@@ -78,6 +129,7 @@ pub struct TlsConnectorDataBuilder {
     keylog_intent: Option<KeyLogIntent>,
     cipher_list: Option<Vec<u16>>,
     store_server_certificate_chain: Option<bool>,
+    mirror_client_hello: Option<bool>,
     alpn_protos: Option<Bytes>,
     min_ssl_version: Option<SslVersion>,
     max_ssl_version: Option<SslVersion>,
@@ -86,6 +138,7 @@ pub struct TlsConnectorDataBuilder {
     grease_enabled: Option<bool>,
     ocsp_stapling_enabled: Option<bool>,
     signed_cert_timestamps_enabled: Option<bool>,
+    session_ticket_enabled: Option<bool>,
     extension_order: Option<Vec<u16>>,
 
     curves: Option<Vec<SslCurve>>,
@@ -94,6 +147,8 @@ pub struct TlsConnectorDataBuilder {
     certificate_compression_algorithms: Option<Vec<CertificateCompressionAlgorithm>>,
     delegated_credential_schemes: Option<Vec<SslSignatureAlgorithm>>,
     server_name: Option<Domain>,
+    handshake_timeout: Option<Duration>,
+    metrics_recorder: Option<Arc<dyn TlsMetricsRecorder>>,
 }
 
 macro_rules! implement_copy_getters {
@@ -152,9 +207,12 @@ impl TlsConnectorDataBuilder {
         record_size_limit: Option<u16>,
         encrypted_client_hello: Option<bool>,
         store_server_certificate_chain: Option<bool>,
+        mirror_client_hello: Option<bool>,
         grease_enabled: Option<bool>,
         ocsp_stapling_enabled: Option<bool>,
         signed_cert_timestamps_enabled: Option<bool>,
+        session_ticket_enabled: Option<bool>,
+        handshake_timeout: Option<Duration>,
     );
 
     implement_reference_getters!(
@@ -167,6 +225,7 @@ impl TlsConnectorDataBuilder {
         certificate_compression_algorithms: Option<Vec<CertificateCompressionAlgorithm>>,
         delegated_credential_schemes: Option<Vec<SslSignatureAlgorithm>>,
         server_name: Option<Domain>,
+        metrics_recorder: Option<Arc<dyn TlsMetricsRecorder>>,
     );
 
     /// Return the SSL keylog file path if one exists.
@@ -191,21 +250,15 @@ impl TlsConnectorDataBuilder {
     }
 
     pub fn new_http_auto() -> Self {
-        Self::new()
-            .try_with_rama_alpn_protos(&[ApplicationProtocol::HTTP_2, ApplicationProtocol::HTTP_11])
-            .expect("with http2 and http1")
+        Self::new().with_http1_and_http2_alpn()
     }
 
     pub fn new_http_1() -> Self {
-        Self::new()
-            .try_with_rama_alpn_protos(&[ApplicationProtocol::HTTP_11])
-            .expect("with http1")
+        Self::new().with_http1_alpn()
     }
 
     pub fn new_http_2() -> Self {
-        Self::new()
-            .try_with_rama_alpn_protos(&[ApplicationProtocol::HTTP_2])
-            .expect("with http 2")
+        Self::new().with_http2_alpn()
     }
 
     /// Add [`ConfigBuilder`] to the end of our base builder
@@ -268,6 +321,58 @@ impl TlsConnectorDataBuilder {
         }
     );
 
+    generate_set_and_with!(
+        /// Mirror the TLS extensions of the original client in the client hello sent to the server
+        ///
+        /// When enabled the [`TlsConnector`] copies the SNI, ALPN, supported groups and
+        /// session ticket extensions of the [`ClientHelloExtensions`] found in the context,
+        /// as stored by the [`TlsAcceptorService`] while terminating the original connection.
+        ///
+        /// [`TlsConnector`]: super::TlsConnector
+        /// [`TlsAcceptorService`]: crate::server::TlsAcceptorService
+        pub fn mirror_client_hello(mut self, mirror_client_hello: Option<bool>) -> Self {
+            self.mirror_client_hello = mirror_client_hello;
+            self
+        }
+    );
+
+    generate_set_and_with!(
+        /// Copy the SNI, ALPN, supported groups and session ticket extensions
+        /// of the given [`ClientHelloExtensions`] into this config
+        pub fn client_hello_extensions(
+            mut self,
+            extensions: &ClientHelloExtensions,
+        ) -> Result<Self, OpaqueError> {
+            if let Some(server_name) = extensions.server_name.clone() {
+                self.server_name = Some(server_name);
+            }
+            if let Some(alpn) = extensions.alpn.as_deref() {
+                self.alpn_protos = Some(
+                    ApplicationProtocol::encode_alpns(alpn)
+                        .context("build (boring) ssl connector: encode mirrored alpns")?,
+                );
+            }
+            if let Some(groups) = extensions.supported_groups.as_deref() {
+                self.curves = Some(
+                    groups
+                        .iter()
+                        .filter(|c| !c.is_grease())
+                        .filter_map(|c| match (*c).rama_try_into() {
+                            Ok(v) => Some(v),
+                            Err(c) => {
+                                trace!("ignore unsupported mirrored support group (curve) {c}");
+                                None
+                            }
+                        })
+                        .dedup()
+                        .collect(),
+                );
+            }
+            self.session_ticket_enabled = Some(extensions.session_ticket);
+            Ok(self)
+        }
+    );
+
     generate_set_and_with!(
         /// Set alpn protos that this client will send to server
         ///
@@ -300,6 +405,69 @@ impl TlsConnectorDataBuilder {
         }
     );
 
+    generate_set_and_with!(
+        /// Only advertise `http/1.1` as [`ApplicationProtocol`] to the server.
+        pub fn http1_alpn(mut self) -> Self {
+            self.alpn_protos = Some(encode_http_alpns(&[ApplicationProtocol::HTTP_11]));
+            self
+        }
+    );
+
+    generate_set_and_with!(
+        /// Only advertise `h2` as [`ApplicationProtocol`] to the server.
+        pub fn http2_alpn(mut self) -> Self {
+            self.alpn_protos = Some(encode_http_alpns(&[ApplicationProtocol::HTTP_2]));
+            self
+        }
+    );
+
+    generate_set_and_with!(
+        /// Advertise both `h2` and `http/1.1` as [`ApplicationProtocol`] to the server,
+        /// preferring `h2`.
+        ///
+        /// Use [`TlsConnectorDataBuilder::with_prefer_http2`] to prefer `http/1.1` instead.
+        pub fn http1_and_http2_alpn(mut self) -> Self {
+            self.alpn_protos = Some(encode_http_alpns(&[
+                ApplicationProtocol::HTTP_2,
+                ApplicationProtocol::HTTP_11,
+            ]));
+            self
+        }
+    );
+
+    generate_set_and_with!(
+        /// Order `h2` and `http/1.1` by the given preference in the advertised [`ApplicationProtocol`]s.
+        ///
+        /// If no alpn protos are configured yet, both `h2` and `http/1.1` are advertised.
+        /// Otherwise the configured list is kept as is, except that `h2` and `http/1.1`
+        /// swap places when both are present and not yet in the preferred order.
+        pub fn prefer_http2(mut self, prefer_http2: bool) -> Self {
+            let (preferred, other) = if prefer_http2 {
+                (ApplicationProtocol::HTTP_2, ApplicationProtocol::HTTP_11)
+            } else {
+                (ApplicationProtocol::HTTP_11, ApplicationProtocol::HTTP_2)
+            };
+            self.alpn_protos = Some(match self.alpn_protos.take() {
+                None => encode_http_alpns(&[preferred, other]),
+                Some(protos) => match decode_alpns(&protos) {
+                    Some(mut alpns) => {
+                        let preferred_idx = alpns.iter().position(|alpn| *alpn == preferred);
+                        let other_idx = alpns.iter().position(|alpn| *alpn == other);
+                        match (preferred_idx, other_idx) {
+                            (Some(i), Some(j)) if i > j => {
+                                alpns.swap(i, j);
+                                encode_http_alpns(&alpns)
+                            }
+                            _ => protos,
+                        }
+                    }
+                    None => protos,
+                },
+            });
+            self
+        }
+    );
+
     generate_set_and_with!(
         /// Set the minimum ssl version that this connector will accept
         pub fn min_ssl_version(mut self, version: Option<SslVersion>) -> Self {
@@ -324,6 +492,14 @@ impl TlsConnectorDataBuilder {
         }
     );
 
+    generate_set_and_with!(
+        /// Set if session tickets should be enabled, defaults to true
+        pub fn session_ticket_enabled(mut self, value: Option<bool>) -> Self {
+            self.session_ticket_enabled = value;
+            self
+        }
+    );
+
     generate_set_and_with!(
         /// Set if encrypted client hello should be enabled
         pub fn encrypted_client_hello(mut self, value: Option<bool>) -> Self {
@@ -418,6 +594,28 @@ impl TlsConnectorDataBuilder {
         }
     );
 
+    generate_set_and_with!(
+        /// Set the maximum duration of the tls handshake,
+        /// after which the connection attempt is aborted.
+        ///
+        /// No timeout is applied by default.
+        pub fn handshake_timeout(mut self, timeout: Option<Duration>) -> Self {
+            self.handshake_timeout = timeout;
+            self
+        }
+    );
+
+    generate_set_and_with!(
+        /// Set the [`TlsMetricsRecorder`] used to record the
+        /// duration and outcome of each tls handshake.
+        ///
+        /// No metrics are recorded by default.
+        pub fn metrics_recorder(mut self, recorder: Option<Arc<dyn TlsMetricsRecorder>>) -> Self {
+            self.metrics_recorder = recorder;
+            self
+        }
+    );
+
     pub fn into_shared_builder(self) -> Arc<Self> {
         Arc::new(self)
     }
@@ -507,6 +705,11 @@ impl TlsConnectorDataBuilder {
             cfg_builder.enable_signed_cert_timestamps();
         }
 
+        if !self.session_ticket_enabled().unwrap_or(true) {
+            trace!("boring connector: disable session tickets");
+            cfg_builder.set_options(SslOptions::NO_TICKET);
+        }
+
         if let Some(compression_algorithms) = self.certificate_compression_algorithms() {
             for compressor in compression_algorithms.iter() {
                 #[cfg(feature = "compression")]
@@ -608,11 +811,26 @@ impl TlsConnectorDataBuilder {
             store_server_certificate_chain: self
                 .store_server_certificate_chain()
                 .unwrap_or_default(),
+            mirror_client_hello: self.mirror_client_hello().unwrap_or_default(),
             server_name: self.server_name().cloned(),
+            handshake_timeout: self.handshake_timeout(),
+            metrics_recorder: self.metrics_recorder().cloned(),
         })
     }
 }
 
+fn encode_http_alpns(protos: &[ApplicationProtocol]) -> Bytes {
+    ApplicationProtocol::encode_alpns(protos).expect("http alpns to be encodable")
+}
+
+fn decode_alpns(mut protos: &[u8]) -> Option<Vec<ApplicationProtocol>> {
+    let mut alpns = Vec::new();
+    while !protos.is_empty() {
+        alpns.push(ApplicationProtocol::decode_wire_format(&mut protos).ok()?);
+    }
+    Some(alpns)
+}
+
 impl std::fmt::Debug for TlsConnectorDataBuilder {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         // Debug implementation of this struct will print each field, but also the getter of
@@ -634,6 +852,8 @@ impl std::fmt::Debug for TlsConnectorDataBuilder {
                 "store_server_certificate_chain()",
                 &self.store_server_certificate_chain(),
             )
+            .field("mirror_client_hello", &self.mirror_client_hello)
+            .field("mirror_client_hello()", &self.mirror_client_hello())
             .field("alpn_protos", &self.alpn_protos)
             .field("alpn_protos()", &self.alpn_protos())
             .field("min_ssl_version", &self.min_ssl_version)
@@ -656,6 +876,8 @@ impl std::fmt::Debug for TlsConnectorDataBuilder {
                 "signed_cert_timestamps_enabled()",
                 &self.signed_cert_timestamps_enabled(),
             )
+            .field("session_ticket_enabled", &self.session_ticket_enabled)
+            .field("session_ticket_enabled()", &self.session_ticket_enabled())
             .field("extension_order", &self.extension_order)
             .field("extension_order()", &self.extension_order())
             .field("curves", &self.curves)
@@ -682,6 +904,10 @@ impl std::fmt::Debug for TlsConnectorDataBuilder {
             )
             .field("server_name", &self.server_name)
             .field("server_name()", &self.server_name())
+            .field("handshake_timeout", &self.handshake_timeout)
+            .field("handshake_timeout()", &self.handshake_timeout())
+            .field("metrics_recorder", &self.metrics_recorder)
+            .field("metrics_recorder()", &self.metrics_recorder())
             .field("base_builders", &self.base_builders)
             .finish()
     }
@@ -995,14 +1221,18 @@ impl TlsConnectorDataBuilder {
             server_verify_mode,
             client_auth,
             store_server_certificate_chain: Some(store_server_certificate_chain),
+            mirror_client_hello: None,
             grease_enabled: Some(grease_enabled),
             ocsp_stapling_enabled: Some(ocsp_stapling_enabled),
             signed_cert_timestamps_enabled: Some(signed_cert_timestamps_enabled),
+            session_ticket_enabled: None,
             certificate_compression_algorithms,
             delegated_credential_schemes,
             record_size_limit,
             encrypted_client_hello,
             server_name,
+            handshake_timeout: None,
+            metrics_recorder: None,
         })
     }
 }
@@ -1115,4 +1345,115 @@ mod tests {
 
         assert_eq!(builder.store_server_certificate_chain(), Some(true));
     }
+
+    #[test]
+    fn test_client_hello_extensions() {
+        let extensions = ClientHelloExtensions {
+            server_name: Some(Domain::from_static("example.com")),
+            alpn: Some(vec![ApplicationProtocol::HTTP_2]),
+            supported_groups: None,
+            session_ticket: false,
+        };
+
+        let builder = TlsConnectorDataBuilder::new_http_1()
+            .try_with_client_hello_extensions(&extensions)
+            .unwrap();
+
+        assert_eq!(
+            builder.server_name(),
+            Some(&Domain::from_static("example.com"))
+        );
+        assert_eq!(
+            builder.alpn_protos(),
+            Some(&encode_http_alpns(&[ApplicationProtocol::HTTP_2]))
+        );
+        assert_eq!(builder.curves(), None);
+        assert_eq!(builder.session_ticket_enabled(), Some(false));
+    }
+
+    #[test]
+    fn test_http_alpn_helpers() {
+        let h1 = ApplicationProtocol::encode_alpns(&[ApplicationProtocol::HTTP_11]).unwrap();
+        let h2 = ApplicationProtocol::encode_alpns(&[ApplicationProtocol::HTTP_2]).unwrap();
+        let h2_h1 = ApplicationProtocol::encode_alpns(&[
+            ApplicationProtocol::HTTP_2,
+            ApplicationProtocol::HTTP_11,
+        ])
+        .unwrap();
+        let h1_h2 = ApplicationProtocol::encode_alpns(&[
+            ApplicationProtocol::HTTP_11,
+            ApplicationProtocol::HTTP_2,
+        ])
+        .unwrap();
+
+        for (builder, expected) in [
+            (TlsConnectorDataBuilder::new().with_http1_alpn(), &h1),
+            (TlsConnectorDataBuilder::new().with_http2_alpn(), &h2),
+            (
+                TlsConnectorDataBuilder::new().with_http1_and_http2_alpn(),
+                &h2_h1,
+            ),
+            (
+                TlsConnectorDataBuilder::new().with_prefer_http2(true),
+                &h2_h1,
+            ),
+            (
+                TlsConnectorDataBuilder::new().with_prefer_http2(false),
+                &h1_h2,
+            ),
+            (TlsConnectorDataBuilder::new_http_auto(), &h2_h1),
+            (TlsConnectorDataBuilder::new_http_1(), &h1),
+            (TlsConnectorDataBuilder::new_http_2(), &h2),
+        ] {
+            assert_eq!(builder.alpn_protos(), Some(expected));
+        }
+
+        let mut builder = TlsConnectorDataBuilder::new_http_2();
+        builder.set_http1_alpn();
+        assert_eq!(builder.alpn_protos(), Some(&h1));
+    }
+
+    #[test]
+    fn test_prefer_http2_reorders_configured_alpn_protos() {
+        let custom = ApplicationProtocol::from(b"custom");
+        let protos = |alpns: &[ApplicationProtocol]| {
+            TlsConnectorDataBuilder::new()
+                .try_with_rama_alpn_protos(alpns)
+                .unwrap()
+        };
+        let encode =
+            |alpns: &[ApplicationProtocol]| ApplicationProtocol::encode_alpns(alpns).unwrap();
+
+        let builder = protos(&[
+            custom.clone(),
+            ApplicationProtocol::HTTP_11,
+            ApplicationProtocol::HTTP_2,
+        ])
+        .with_prefer_http2(true);
+        assert_eq!(
+            builder.alpn_protos(),
+            Some(&encode(&[
+                custom.clone(),
+                ApplicationProtocol::HTTP_2,
+                ApplicationProtocol::HTTP_11,
+            ]))
+        );
+
+        let builder = builder.with_prefer_http2(false);
+        assert_eq!(
+            builder.alpn_protos(),
+            Some(&encode(&[
+                custom.clone(),
+                ApplicationProtocol::HTTP_11,
+                ApplicationProtocol::HTTP_2,
+            ]))
+        );
+
+        // a protocol is never added to a configured list
+        let builder = protos(&[ApplicationProtocol::HTTP_11]).with_prefer_http2(true);
+        assert_eq!(
+            builder.alpn_protos(),
+            Some(&encode(&[ApplicationProtocol::HTTP_11]))
+        );
+    }
 }
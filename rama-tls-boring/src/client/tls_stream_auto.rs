@@ -4,6 +4,7 @@ use pin_project_lite::pin_project;
 use rama_boring::ssl::SslRef;
 use rama_boring_tokio::SslStream;
 use rama_net::stream::Stream;
+use rama_net::tls::ApplicationProtocol;
 use tokio::io::{AsyncRead, AsyncWrite};
 
 pin_project! {
@@ -33,6 +34,14 @@ impl<S> AutoTlsStream<S> {
             AutoTlsStreamData::Plain { .. } => None,
         }
     }
+
+    /// Returns the [`ApplicationProtocol`] negotiated using ALPN,
+    /// `None` for plain streams or in case no protocol was negotiated.
+    pub fn negotiated_protocol(&self) -> Option<ApplicationProtocol> {
+        self.ssl_ref()?
+            .selected_alpn_protocol()
+            .map(ApplicationProtocol::from)
+    }
 }
 
 impl<S: fmt::Debug> fmt::Debug for AutoTlsStream<S> {
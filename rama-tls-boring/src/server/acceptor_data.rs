@@ -31,6 +31,7 @@ use rama_net::{
         },
     },
 };
+use rama_utils::macros::generate_set_and_with;
 use std::{sync::Arc, time::Duration};
 
 #[derive(Debug, Clone)]
@@ -41,6 +42,35 @@ pub struct TlsAcceptorData {
     pub(super) config: Arc<TlsConfig>,
 }
 
+impl TlsAcceptorData {
+    generate_set_and_with!(
+        /// Set the [`ClientCertificateVerification`] used to verify
+        /// the certificates of clients (mTLS).
+        pub fn client_certificate_verification(
+            mut self,
+            verification: Option<ClientCertificateVerification>,
+        ) -> Self {
+            Arc::make_mut(&mut self.config).client_certificate_verification = verification;
+            self
+        }
+    );
+}
+
+#[derive(Debug, Clone)]
+/// Verification of client certificates (mTLS) by the [`super::TlsAcceptorService`].
+///
+/// The verified client certificate is inserted in the [`Context`]
+/// as a [`super::PeerCertificate`].
+///
+/// [`Context`]: rama_core::Context
+pub struct ClientCertificateVerification {
+    /// CA certificate used to verify the client certificates.
+    pub ca_cert: X509,
+    /// Reject clients which do not provide a certificate if `true`,
+    /// otherwise the certificate is only verified when provided.
+    pub require: bool,
+}
+
 #[derive(Debug, Clone)]
 pub(super) struct TlsConfig {
     /// source for certs
@@ -55,6 +85,8 @@ pub(super) struct TlsConfig {
     pub(super) client_cert_chain: Option<Vec<X509>>,
     /// store client certificate chain if true and client provided this
     pub store_client_certificate_chain: bool,
+    /// optionally verify client certificates (mTLS)
+    pub(super) client_certificate_verification: Option<ClientCertificateVerification>,
 }
 
 #[derive(Debug, Clone)]
@@ -354,6 +386,7 @@ impl TryFrom<rama_net::tls::server::ServerConfig> for TlsAcceptorData {
                 protocol_versions: value.protocol_versions.clone(),
                 client_cert_chain,
                 store_client_certificate_chain: value.store_client_certificate_chain,
+                client_certificate_verification: None,
             }),
         })
     }
@@ -494,11 +527,13 @@ fn self_signed_server_auth(data: SelfSignedData) -> Result<IssuedCert, OpaqueErr
 }
 
 #[inline]
-fn self_signed_server_ca(data: SelfSignedData) -> Result<(X509, PKey<Private>), OpaqueError> {
+pub(super) fn self_signed_server_ca(
+    data: SelfSignedData,
+) -> Result<(X509, PKey<Private>), OpaqueError> {
     self_signed_server_auth_gen_ca(&data)
 }
 
-fn self_signed_server_auth_gen_cert(
+pub(super) fn self_signed_server_auth_gen_cert(
     data: &SelfSignedData,
     ca_cert: &X509,
     ca_privkey: &PKey<Private>,
@@ -12,11 +12,11 @@
 
 mod acceptor_data;
 #[doc(inline)]
-pub use acceptor_data::TlsAcceptorData;
+pub use acceptor_data::{ClientCertificateVerification, TlsAcceptorData};
 
 mod service;
 #[doc(inline)]
-pub use service::TlsAcceptorService;
+pub use service::{ClientHelloExtensions, PeerCertificate, TlsAcceptorService};
 
 mod layer;
 #[doc(inline)]
@@ -2,10 +2,12 @@ use super::TlsAcceptorData;
 use crate::{
     RamaTryInto,
     core::{
-        ssl::{AlpnError, SslAcceptor, SslMethod, SslRef},
+        ssl::{AlpnError, SslAcceptor, SslMethod, SslRef, SslVerifyMode},
         tokio::SslStream,
+        x509::X509,
     },
     keylog::new_key_log_file_handle,
+    type_conversion::cipher_suite_from_ssl_cipher,
     types::SecureTransport,
 };
 use parking_lot::Mutex;
@@ -15,15 +17,60 @@ use rama_core::{
     error::{BoxError, ErrorContext, ErrorExt, OpaqueError},
 };
 use rama_net::{
-    address::Host,
+    address::{Domain, Host},
     http::RequestContext,
     stream::Stream,
-    tls::{ApplicationProtocol, DataEncoding, client::NegotiatedTlsParameters},
+    tls::{
+        ApplicationProtocol, DataEncoding, ExtensionId, SupportedGroup,
+        client::{ClientHello, NegotiatedTlsParameters},
+    },
     transport::TransportContext,
 };
 use rama_utils::macros::define_inner_service_accessors;
 use std::{io::ErrorKind, sync::Arc};
 
+#[derive(Debug, Clone)]
+/// The verified certificate of the client, inserted in the [`Context`]
+/// by the [`TlsAcceptorService`] in case [`ClientCertificateVerification`] is enabled.
+///
+/// [`ClientCertificateVerification`]: super::ClientCertificateVerification
+pub struct PeerCertificate(pub X509);
+
+#[derive(Debug, Clone, Default)]
+/// The TLS extensions of the original [`ClientHello`], inserted in the [`Context`]
+/// by the [`TlsAcceptorService`] in case the client hello is stored.
+///
+/// These can be forwarded to the backend in the [`ClientHello`] of
+/// the outgoing connection, see [`TlsConnectorDataBuilder::set_mirror_client_hello`].
+///
+/// [`TlsConnectorDataBuilder::set_mirror_client_hello`]: crate::client::TlsConnectorDataBuilder::set_mirror_client_hello
+pub struct ClientHelloExtensions {
+    /// The server name (SNI) requested by the client.
+    pub server_name: Option<Domain>,
+    /// The application protocols (ALPN) advertised by the client.
+    pub alpn: Option<Vec<ApplicationProtocol>>,
+    /// The supported groups (curves) advertised by the client.
+    pub supported_groups: Option<Vec<SupportedGroup>>,
+    /// Whether or not the client advertised support for session tickets.
+    pub session_ticket: bool,
+}
+
+impl From<&ClientHello> for ClientHelloExtensions {
+    fn from(client_hello: &ClientHello) -> Self {
+        Self {
+            server_name: client_hello.ext_server_name().cloned(),
+            alpn: client_hello.ext_alpn().map(|alpn| alpn.to_vec()),
+            supported_groups: client_hello
+                .ext_supported_groups()
+                .map(|groups| groups.to_vec()),
+            session_ticket: client_hello
+                .extensions()
+                .iter()
+                .any(|ext| ext.id() == ExtensionId::SESSION_TICKET),
+        }
+    }
+}
+
 /// A [`Service`] which accepts TLS connections and delegates the underlying transport
 /// stream to the given service.
 pub struct TlsAcceptorService<S> {
@@ -142,6 +189,21 @@ where
                 .context("build boring ssl acceptor: set ca client cert")?;
         }
 
+        if let Some(verification) = tls_config.client_certificate_verification.as_ref() {
+            acceptor_builder
+                .cert_store_mut()
+                .add_cert(verification.ca_cert.clone())
+                .context("build boring ssl acceptor: add client verification ca cert to store")?;
+            acceptor_builder
+                .add_client_ca(&verification.ca_cert)
+                .context("build boring ssl acceptor: set client verification ca cert")?;
+            acceptor_builder.set_verify(if verification.require {
+                SslVerifyMode::PEER | SslVerifyMode::FAIL_IF_NO_PEER_CERT
+            } else {
+                SslVerifyMode::PEER
+            });
+        }
+
         if let Some(alpn_protocols) = tls_config.alpn_protocols.clone() {
             trace!("tls boring server service: set alpn protos callback");
             acceptor_builder.set_alpn_select_callback(
@@ -236,9 +298,21 @@ where
                     None
                 };
 
+                if tls_config.client_certificate_verification.is_some()
+                    && let Some(certificate) = stream.ssl().peer_certificate()
+                {
+                    ctx.insert(PeerCertificate(certificate));
+                }
+
+                let cipher_suite = stream
+                    .ssl()
+                    .current_cipher()
+                    .map(cipher_suite_from_ssl_cipher);
+
                 ctx.insert(NegotiatedTlsParameters {
                     protocol_version,
                     application_layer_protocol,
+                    cipher_suite,
                     peer_certificate_chain: client_certificate_chain,
                 });
             }
@@ -253,7 +327,10 @@ where
         let secure_transport = maybe_client_hello
             .take()
             .and_then(|maybe_client_hello| maybe_client_hello.lock().take())
-            .map(SecureTransport::with_client_hello)
+            .map(|client_hello| {
+                ctx.insert(ClientHelloExtensions::from(&client_hello));
+                SecureTransport::with_client_hello(client_hello)
+            })
             .unwrap_or_default();
         ctx.insert(secure_transport);
 
@@ -264,3 +341,110 @@ where
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::{TlsConnectorDataBuilder, tls_connect};
+    use crate::core::pkey::{PKey, Private};
+    use crate::server::ClientCertificateVerification;
+    use crate::server::acceptor_data::{self_signed_server_auth_gen_cert, self_signed_server_ca};
+    use rama_core::service::service_fn;
+    use rama_net::tls::client::{ClientAuth, ClientAuthData, ClientConfig, ServerVerifyMode};
+    use rama_net::tls::server::{SelfSignedData, ServerAuth, ServerConfig};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt, DuplexStream};
+
+    fn client_cert(ca_cert: &X509, ca_key: &PKey<Private>) -> (X509, PKey<Private>) {
+        self_signed_server_auth_gen_cert(&SelfSignedData::default(), ca_cert, ca_key).unwrap()
+    }
+
+    /// Perform a tls handshake, returning whether or not
+    /// the [`PeerCertificate`] was found by the inner service.
+    async fn mtls_handshake(
+        verification: Option<ClientCertificateVerification>,
+        client_auth: Option<(X509, PKey<Private>)>,
+    ) -> Result<bool, BoxError> {
+        let data = TlsAcceptorData::try_from(ServerConfig::new(ServerAuth::default()))
+            .unwrap()
+            .maybe_with_client_certificate_verification(verification);
+        let acceptor = TlsAcceptorService::new(
+            data,
+            service_fn(
+                async |ctx: Context<()>, mut stream: SslStream<DuplexStream>| {
+                    stream.write_all(b"ok").await?;
+                    stream.shutdown().await?;
+                    Ok::<_, std::io::Error>(ctx.contains::<PeerCertificate>())
+                },
+            ),
+            false,
+        );
+
+        let client_config = ClientConfig {
+            server_verify_mode: Some(ServerVerifyMode::Disable),
+            client_auth: client_auth.map(|(cert, key)| {
+                ClientAuth::Single(ClientAuthData {
+                    private_key: DataEncoding::Der(key.private_key_to_der().unwrap()),
+                    cert_chain: DataEncoding::Der(cert.to_der().unwrap()),
+                })
+            }),
+            ..Default::default()
+        };
+        let connector_data = TlsConnectorDataBuilder::try_from(&client_config)
+            .unwrap()
+            .build()
+            .unwrap();
+
+        let (client_stream, server_stream) = tokio::io::duplex(16 * 1024);
+        let client = async move {
+            let mut stream =
+                tls_connect(Host::LOCALHOST_NAME, client_stream, Some(connector_data)).await?;
+            // with TLS 1.3 the server only verifies the client certificate
+            // after the client completed its side of the handshake
+            let mut buf = [0; 2];
+            stream.read_exact(&mut buf).await?;
+            Ok::<_, BoxError>(())
+        };
+
+        let (server_result, client_result) =
+            tokio::join!(acceptor.serve(Context::default(), server_stream), client);
+        let has_peer_certificate = server_result?;
+        client_result?;
+        Ok(has_peer_certificate)
+    }
+
+    #[tokio::test]
+    async fn mtls_client_certificate_verification() {
+        let (ca_cert, ca_key) = self_signed_server_ca(SelfSignedData::default()).unwrap();
+        let (other_ca_cert, other_ca_key) =
+            self_signed_server_ca(SelfSignedData::default()).unwrap();
+
+        let verification = |require| {
+            Some(ClientCertificateVerification {
+                ca_cert: ca_cert.clone(),
+                require,
+            })
+        };
+        let valid = || Some(client_cert(&ca_cert, &ca_key));
+        let invalid = || Some(client_cert(&other_ca_cert, &other_ca_key));
+
+        // required
+        assert!(mtls_handshake(verification(true), valid()).await.unwrap());
+        mtls_handshake(verification(true), None)
+            .await
+            .expect_err("missing client certificate");
+        mtls_handshake(verification(true), invalid())
+            .await
+            .expect_err("untrusted client certificate");
+
+        // optional
+        assert!(mtls_handshake(verification(false), valid()).await.unwrap());
+        assert!(!mtls_handshake(verification(false), None).await.unwrap());
+        mtls_handshake(verification(false), invalid())
+            .await
+            .expect_err("untrusted client certificate");
+
+        // none
+        assert!(!mtls_handshake(None, valid()).await.unwrap());
+        assert!(!mtls_handshake(None, None).await.unwrap());
+    }
+}
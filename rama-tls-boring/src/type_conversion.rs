@@ -1,4 +1,5 @@
 use crate::RamaTryFrom;
+use foreign_types::ForeignTypeRef;
 use itertools::Itertools;
 use rama_core::error::{ErrorContext, OpaqueError};
 use rama_core::telemetry::tracing::trace;
@@ -120,6 +121,16 @@ impl RamaTryFrom<&rama_boring::stack::StackRef<rama_boring::x509::X509>>
     }
 }
 
+/// get the [`CipherSuite`] matching the given (boring) ssl cipher,
+/// using its IANA-assigned protocol id.
+pub(crate) fn cipher_suite_from_ssl_cipher(
+    cipher: &rama_boring::ssl::SslCipherRef,
+) -> rama_net::tls::CipherSuite {
+    // SAFETY: the cipher ref points to a valid (static) boring ssl cipher
+    let id = unsafe { rama_boring_sys::SSL_CIPHER_get_protocol_id(cipher.as_ptr()) };
+    rama_net::tls::CipherSuite::from(id)
+}
+
 /// create an openssl cipher list str from the given [`CipherSuite`]
 ///
 /// ref doc: <https://docs.openssl.org/1.1.1/man1/ciphers/#tls-v13-cipher-suites>
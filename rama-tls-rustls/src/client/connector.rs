@@ -5,14 +5,19 @@ use crate::{RamaInto, RamaTryFrom};
 use pin_project_lite::pin_project;
 use rama_core::error::ErrorContext;
 use rama_core::error::{BoxError, ErrorExt, OpaqueError};
+use rama_core::futures::FutureExt;
+use rama_core::graceful::WeakShutdownGuard;
 use rama_core::telemetry::tracing;
 use rama_core::{Context, Layer, Service};
+use rama_http_types::Version;
+use rama_http_types::conn::TargetHttpVersion;
 use rama_net::address::Host;
 use rama_net::client::{ConnectorService, EstablishedClientConnection};
 use rama_net::stream::Stream;
 use rama_net::tls::ApplicationProtocol;
-use rama_net::tls::client::NegotiatedTlsParameters;
+use rama_net::tls::client::{NegotiatedTlsParameters, TlsConnectorShutdown};
 use rama_net::transport::TryRefIntoTransportContext;
+use rama_utils::macros::generate_set_and_with;
 use std::fmt;
 use tokio::io::{AsyncRead, AsyncWrite};
 
@@ -21,6 +26,7 @@ use tokio::io::{AsyncRead, AsyncWrite};
 /// See [`TlsConnector`] for more information.
 pub struct TlsConnectorLayer<K = ConnectorKindAuto> {
     connector_data: Option<TlsConnectorData>,
+    graceful_guard: Option<WeakShutdownGuard>,
     kind: K,
 }
 
@@ -28,6 +34,7 @@ impl<K: fmt::Debug> std::fmt::Debug for TlsConnectorLayer<K> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("TlsConnectorLayer")
             .field("connector_data", &self.connector_data)
+            .field("graceful_guard", &self.graceful_guard)
             .field("kind", &self.kind)
             .finish()
     }
@@ -37,6 +44,7 @@ impl<K: Clone> Clone for TlsConnectorLayer<K> {
     fn clone(&self) -> Self {
         Self {
             connector_data: self.connector_data.clone(),
+            graceful_guard: self.graceful_guard.clone(),
             kind: self.kind.clone(),
         }
     }
@@ -63,6 +71,17 @@ impl<K> TlsConnectorLayer<K> {
         self.connector_data = Some(connector_data);
         self
     }
+
+    generate_set_and_with!(
+        /// Set the [`WeakShutdownGuard`] used by the [`TlsConnector`] created by this layer.
+        ///
+        /// New connections are refused with a [`TlsConnectorShutdown`] error
+        /// once its shutdown is initiated.
+        pub fn graceful_guard(mut self, guard: Option<WeakShutdownGuard>) -> Self {
+            self.graceful_guard = guard;
+            self
+        }
+    );
 }
 
 impl TlsConnectorLayer<ConnectorKindAuto> {
@@ -72,6 +91,7 @@ impl TlsConnectorLayer<ConnectorKindAuto> {
     pub fn auto() -> Self {
         Self {
             connector_data: None,
+            graceful_guard: None,
             kind: ConnectorKindAuto,
         }
     }
@@ -83,6 +103,7 @@ impl TlsConnectorLayer<ConnectorKindSecure> {
     pub fn secure() -> Self {
         Self {
             connector_data: None,
+            graceful_guard: None,
             kind: ConnectorKindSecure,
         }
     }
@@ -94,6 +115,7 @@ impl TlsConnectorLayer<ConnectorKindTunnel> {
     pub fn tunnel(host: Option<Host>) -> Self {
         Self {
             connector_data: None,
+            graceful_guard: None,
             kind: ConnectorKindTunnel { host },
         }
     }
@@ -106,6 +128,7 @@ impl<K: Clone, S> Layer<S> for TlsConnectorLayer<K> {
         TlsConnector {
             inner,
             connector_data: self.connector_data.clone(),
+            graceful_guard: self.graceful_guard.clone(),
             kind: self.kind.clone(),
         }
     }
@@ -114,6 +137,7 @@ impl<K: Clone, S> Layer<S> for TlsConnectorLayer<K> {
         TlsConnector {
             inner,
             connector_data: self.connector_data,
+            graceful_guard: self.graceful_guard,
             kind: self.kind,
         }
     }
@@ -132,9 +156,13 @@ impl Default for TlsConnectorLayer<ConnectorKindAuto> {
 /// only if the request requires a secure connection. You can instead use
 /// [`TlsConnector::secure_only`] to force the connector to always
 /// establish a secure connection.
+///
+/// A [`WeakShutdownGuard`] can be attached to refuse new connections once
+/// shutdown is initiated, while the connections already established are allowed to drain.
 pub struct TlsConnector<S, K = ConnectorKindAuto> {
     inner: S,
     connector_data: Option<TlsConnectorData>,
+    graceful_guard: Option<WeakShutdownGuard>,
     kind: K,
 }
 
@@ -143,6 +171,7 @@ impl<S: fmt::Debug, K: fmt::Debug> fmt::Debug for TlsConnector<S, K> {
         f.debug_struct("TlsConnector")
             .field("inner", &self.inner)
             .field("connector_data", &self.connector_data)
+            .field("graceful_guard", &self.graceful_guard)
             .field("kind", &self.kind)
             .finish()
     }
@@ -153,6 +182,7 @@ impl<S: Clone, K: Clone> Clone for TlsConnector<S, K> {
         Self {
             inner: self.inner.clone(),
             connector_data: self.connector_data.clone(),
+            graceful_guard: self.graceful_guard.clone(),
             kind: self.kind.clone(),
         }
     }
@@ -164,6 +194,7 @@ impl<S, K> TlsConnector<S, K> {
         Self {
             inner,
             connector_data: None,
+            graceful_guard: None,
             kind,
         }
     }
@@ -194,6 +225,30 @@ impl<S, K> TlsConnector<S, K> {
         self.connector_data = Some(connector_data);
         self
     }
+
+    generate_set_and_with!(
+        /// Set the [`WeakShutdownGuard`] used by this [`TlsConnector`].
+        ///
+        /// New connections are refused with a [`TlsConnectorShutdown`] error
+        /// once its shutdown is initiated.
+        pub fn graceful_guard(mut self, guard: Option<WeakShutdownGuard>) -> Self {
+            self.graceful_guard = guard;
+            self
+        }
+    );
+
+    /// Return an error in case the shutdown of the attached graceful guard is initiated.
+    fn ensure_not_shutting_down(&self) -> Result<(), BoxError> {
+        if self
+            .graceful_guard
+            .as_ref()
+            .is_some_and(|guard| guard.shutdown_signal_triggered().now_or_never().is_some())
+        {
+            tracing::debug!("TlsConnector: refuse new connection: shutdown in progress");
+            return Err(TlsConnectorShutdown::new().into());
+        }
+        Ok(())
+    }
 }
 
 impl<S> TlsConnector<S, ConnectorKindAuto> {
@@ -238,6 +293,8 @@ where
         ctx: Context<State>,
         req: Request,
     ) -> Result<Self::Response, Self::Error> {
+        self.ensure_not_shutting_down()?;
+
         let EstablishedClientConnection { mut ctx, req, conn } =
             self.inner.connect(ctx, req).await.map_err(Into::into)?;
         let transport_ctx = ctx
@@ -289,13 +346,24 @@ where
 
         ctx.insert(negotiated_params);
 
-        Ok(EstablishedClientConnection {
-            ctx,
-            req,
-            conn: AutoTlsStream {
-                inner: AutoTlsStreamData::Secure { inner: stream },
-            },
-        })
+        let conn = AutoTlsStream {
+            inner: AutoTlsStreamData::Secure { inner: stream },
+        };
+
+        if conn.negotiated_protocol() == Some(ApplicationProtocol::HTTP_2)
+            && ctx
+                .get::<TargetHttpVersion>()
+                .is_none_or(|TargetHttpVersion(version)| *version == Version::HTTP_11)
+        {
+            tracing::trace!(
+                server.address = %transport_ctx.authority.host(),
+                server.port = %transport_ctx.authority.port(),
+                "TlsConnector(auto): h2 negotiated using ALPN, upgrade target http version to HTTP/2",
+            );
+            ctx.insert(TargetHttpVersion(Version::HTTP_2));
+        }
+
+        Ok(EstablishedClientConnection { ctx, req, conn })
     }
 }
 
@@ -314,6 +382,8 @@ where
         ctx: Context<State>,
         req: Request,
     ) -> Result<Self::Response, Self::Error> {
+        self.ensure_not_shutting_down()?;
+
         let EstablishedClientConnection { mut ctx, req, conn } =
             self.inner.connect(ctx, req).await.map_err(Into::into)?;
 
@@ -354,6 +424,8 @@ where
         ctx: Context<State>,
         req: Request,
     ) -> Result<Self::Response, Self::Error> {
+        self.ensure_not_shutting_down()?;
+
         let EstablishedClientConnection { mut ctx, req, conn } =
             self.inner.connect(ctx, req).await.map_err(Into::into)?;
 
@@ -431,6 +503,9 @@ impl<S, K> TlsConnector<S, K> {
             application_layer_protocol: conn_data_ref
                 .alpn_protocol()
                 .map(ApplicationProtocol::from),
+            cipher_suite: conn_data_ref
+                .negotiated_cipher_suite()
+                .map(|suite| suite.suite().rama_into()),
             peer_certificate_chain: server_certificate_chain,
         };
 
@@ -446,6 +521,21 @@ pin_project! {
     }
 }
 
+impl<S> AutoTlsStream<S> {
+    /// Returns the [`ApplicationProtocol`] negotiated using ALPN,
+    /// `None` for plain streams or in case no protocol was negotiated.
+    pub fn negotiated_protocol(&self) -> Option<ApplicationProtocol> {
+        match &self.inner {
+            AutoTlsStreamData::Secure { inner } => inner
+                .get_ref()
+                .1
+                .alpn_protocol()
+                .map(ApplicationProtocol::from),
+            AutoTlsStreamData::Plain { .. } => None,
+        }
+    }
+}
+
 impl<S: fmt::Debug> fmt::Debug for AutoTlsStream<S> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("AutoTlsStream")
@@ -573,4 +663,130 @@ mod tests {
 
         assert_sync::<TlsConnectorLayer>();
     }
+
+    #[tokio::test]
+    async fn test_refuse_connections_on_shutdown() {
+        use rama_core::graceful::Shutdown;
+        use rama_core::service::service_fn;
+        use std::convert::Infallible;
+
+        let (tx, rx) = tokio::sync::oneshot::channel::<()>();
+        let shutdown = Shutdown::new(async move {
+            let _ = rx.await;
+        });
+
+        let connector = TlsConnector::tunnel(
+            service_fn(async |ctx: Context<()>, req: ()| {
+                let (conn, _) = tokio::io::duplex(64);
+                Ok::<_, Infallible>(EstablishedClientConnection { ctx, req, conn })
+            }),
+            None,
+        )
+        .with_graceful_guard(shutdown.guard_weak());
+
+        assert!(connector.serve(Context::default(), ()).await.is_ok());
+
+        tx.send(()).unwrap();
+        shutdown.shutdown().await;
+
+        let err = connector.serve(Context::default(), ()).await.unwrap_err();
+        assert!(err.is::<TlsConnectorShutdown>());
+    }
+
+    async fn target_http_version_after_handshake(
+        server_alpn: &[ApplicationProtocol],
+        target_version: Option<Version>,
+    ) -> Option<Version> {
+        use crate::client::TlsConnectorDataBuilder;
+        use crate::server::{TlsAcceptorDataBuilder, TlsAcceptorService};
+        use rama_core::service::service_fn;
+        use rama_http_types::Request;
+        use rama_net::tls::server::SelfSignedData;
+        use std::convert::Infallible;
+        use std::sync::Mutex;
+
+        let (client_conn, server_conn) = tokio::io::duplex(16 * 1024);
+
+        let acceptor_data = TlsAcceptorDataBuilder::new_self_signed(SelfSignedData::default())
+            .unwrap()
+            .with_alpn_protocols(server_alpn)
+            .build();
+        let acceptor = TlsAcceptorService::new(
+            acceptor_data,
+            service_fn(async |_stream| Ok::<_, Infallible>(())),
+            false,
+        );
+        let server = tokio::spawn(async move {
+            acceptor
+                .serve(Context::default(), server_conn)
+                .await
+                .unwrap();
+        });
+
+        let client_conn = Mutex::new(Some(client_conn));
+        let connector = TlsConnector::auto(service_fn(move |ctx: Context<()>, req| {
+            let conn = client_conn.lock().unwrap().take().unwrap();
+            std::future::ready(Ok::<_, Infallible>(EstablishedClientConnection {
+                ctx,
+                req,
+                conn,
+            }))
+        }))
+        .with_connector_data(
+            TlsConnectorDataBuilder::new()
+                .with_no_cert_verifier()
+                .with_alpn_protocols_http_auto()
+                .build(),
+        );
+
+        let mut ctx = Context::default();
+        if let Some(version) = target_version {
+            ctx.insert(TargetHttpVersion(version));
+        }
+        let req = Request::builder()
+            .uri("https://example.com")
+            .body(())
+            .unwrap();
+
+        // keep the client connection open until the server is done with its handshake
+        let EstablishedClientConnection { ctx, conn, .. } =
+            connector.serve(ctx, req).await.unwrap();
+        server.await.unwrap();
+        drop(conn);
+
+        ctx.get::<TargetHttpVersion>()
+            .map(|TargetHttpVersion(version)| *version)
+    }
+
+    #[tokio::test]
+    async fn test_auto_upgrade_target_http_version_on_h2_alpn() {
+        assert_eq!(
+            target_http_version_after_handshake(&[ApplicationProtocol::HTTP_2], None).await,
+            Some(Version::HTTP_2),
+        );
+        assert_eq!(
+            target_http_version_after_handshake(
+                &[ApplicationProtocol::HTTP_2],
+                Some(Version::HTTP_11)
+            )
+            .await,
+            Some(Version::HTTP_2),
+        );
+    }
+
+    #[tokio::test]
+    async fn test_auto_keep_target_http_version_without_h2_alpn() {
+        assert_eq!(
+            target_http_version_after_handshake(&[ApplicationProtocol::HTTP_11], None).await,
+            None,
+        );
+        assert_eq!(
+            target_http_version_after_handshake(
+                &[ApplicationProtocol::HTTP_2],
+                Some(Version::HTTP_10)
+            )
+            .await,
+            Some(Version::HTTP_10),
+        );
+    }
 }
@@ -22,6 +22,7 @@ pub mod server;
 pub mod verify;
 
 pub mod key_log;
+pub mod monitor;
 
 mod type_conversion;
 
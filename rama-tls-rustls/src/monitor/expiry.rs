@@ -0,0 +1,343 @@
+use crate::RamaTryFrom;
+use crate::client::{TlsConnectorData, TlsConnectorDataBuilder};
+use crate::dep::pki_types::{CertificateDer, ServerName};
+use crate::dep::tokio_rustls::TlsConnector as RustlsConnector;
+use rama_core::error::{ErrorContext, OpaqueError};
+use rama_core::telemetry::tracing;
+use rama_net::address::Host;
+use rama_utils::macros::generate_set_and_with;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::{net::TcpStream, task::JoinHandle};
+
+const DAY: Duration = Duration::from_secs(24 * 60 * 60);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Expiry status of a certificate chain, as checked by the [`CertExpiryMonitor`].
+pub enum CertExpiryStatus {
+    /// The chain does not expire within the warning window,
+    /// with the time left until it expires.
+    Valid(Duration),
+    /// The chain expires within the warning window,
+    /// with the time left until it expires.
+    Warning(Duration),
+    /// The chain expires within the critical window,
+    /// with the time left until it expires.
+    Critical(Duration),
+    /// The chain has expired.
+    Expired,
+}
+
+#[derive(Debug, Clone)]
+/// Monitor which periodically checks the `not_after` field
+/// of a certificate chain and logs when it is about to expire.
+///
+/// A [`tracing`] warning is emitted when the chain expires within
+/// the warning window (30 days by default), and an error once it expires within
+/// the critical window (7 days by default) or has expired. The earliest expiry
+/// of all certificates in the chain is used, as an expired intermediate
+/// certificate invalidates the chain as well.
+///
+/// For a server, create the monitor with the same certificate chain
+/// that is used to build its [`TlsAcceptorData`]. The certificates can
+/// not be retrieved from the [`TlsAcceptorData`] itself, as rustls
+/// only resolves them during a handshake.
+///
+/// # Example
+///
+/// ```
+/// use rama_tls_rustls::monitor::{CertExpiryMonitor, CertExpiryStatus};
+/// use rama_tls_rustls::dep::rcgen::{CertificateParams, KeyPair};
+/// use std::time::Duration;
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let key_pair = KeyPair::generate()?;
+/// let cert = CertificateParams::new(vec!["example.com".to_owned()])?.self_signed(&key_pair)?;
+///
+/// let monitor = CertExpiryMonitor::new(vec![cert.into()])
+///     .with_warn_before(Duration::from_secs(14 * 24 * 60 * 60));
+/// assert!(matches!(monitor.check()?, CertExpiryStatus::Valid(_)));
+/// # Ok(())
+/// # }
+/// ```
+///
+/// [`TlsAcceptorData`]: crate::server::TlsAcceptorData
+pub struct CertExpiryMonitor {
+    cert_chain: Vec<CertificateDer<'static>>,
+    warn_before: Duration,
+    critical_before: Duration,
+    check_interval: Duration,
+}
+
+impl CertExpiryMonitor {
+    /// Create a new [`CertExpiryMonitor`] for the given certificate chain.
+    pub fn new(cert_chain: Vec<CertificateDer<'static>>) -> Self {
+        Self {
+            cert_chain,
+            warn_before: 30 * DAY,
+            critical_before: 7 * DAY,
+            check_interval: Duration::from_secs(60 * 60),
+        }
+    }
+
+    /// Create a new [`CertExpiryMonitor`] for the client
+    /// (auth) certificate chain of the given [`TlsConnectorData`].
+    ///
+    /// Returns an error if the [`TlsConnectorData`] has no client certificate.
+    pub fn try_from_connector_data(data: &TlsConnectorData) -> Result<Self, OpaqueError> {
+        let certified_key = data
+            .client_config
+            .client_auth_cert_resolver
+            .resolve(&[], &[])
+            .context("tls connector data has no client certificate")?;
+        Ok(Self::new(certified_key.cert.clone()))
+    }
+
+    generate_set_and_with! {
+        /// Set the window before expiry in which a warning is logged.
+        ///
+        /// Default is 30 days.
+        pub fn warn_before(mut self, duration: Duration) -> Self {
+            self.warn_before = duration;
+            self
+        }
+    }
+
+    generate_set_and_with! {
+        /// Set the window before expiry in which an error is logged.
+        ///
+        /// Default is 7 days.
+        pub fn critical_before(mut self, duration: Duration) -> Self {
+            self.critical_before = duration;
+            self
+        }
+    }
+
+    generate_set_and_with! {
+        /// Set the interval between two checks of a spawned monitor.
+        ///
+        /// Default is 1 hour.
+        pub fn check_interval(mut self, interval: Duration) -> Self {
+            self.check_interval = interval;
+            self
+        }
+    }
+
+    /// Check the expiry of the certificate chain once.
+    pub fn check(&self) -> Result<CertExpiryStatus, OpaqueError> {
+        let Some(remaining) = time_until_expiry(&self.cert_chain)? else {
+            return Ok(CertExpiryStatus::Expired);
+        };
+        Ok(if remaining <= self.critical_before {
+            CertExpiryStatus::Critical(remaining)
+        } else if remaining <= self.warn_before {
+            CertExpiryStatus::Warning(remaining)
+        } else {
+            CertExpiryStatus::Valid(remaining)
+        })
+    }
+
+    /// Spawn a task which checks the expiry of the certificate chain
+    /// at the configured interval, starting immediately.
+    ///
+    /// The task runs until the returned [`JoinHandle`] is aborted.
+    pub fn spawn(self) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(self.check_interval);
+            loop {
+                interval.tick().await;
+                match self.check() {
+                    Ok(CertExpiryStatus::Valid(remaining)) => {
+                        tracing::trace!("tls certificate chain expires in {}s", remaining.as_secs())
+                    }
+                    Ok(CertExpiryStatus::Warning(remaining)) => tracing::warn!(
+                        "tls certificate chain expires soon: in {}s",
+                        remaining.as_secs(),
+                    ),
+                    Ok(CertExpiryStatus::Critical(remaining)) => tracing::error!(
+                        "tls certificate chain is about to expire: in {}s",
+                        remaining.as_secs(),
+                    ),
+                    Ok(CertExpiryStatus::Expired) => {
+                        tracing::error!("tls certificate chain has expired")
+                    }
+                    Err(err) => {
+                        tracing::error!("failed to check tls certificate chain expiry: {err:?}")
+                    }
+                }
+            }
+        })
+    }
+}
+
+/// Connect to the given server and return the time left
+/// until its certificate chain expires.
+///
+/// The server certificate is not verified, such that the expiry of
+/// invalid (e.g. self-signed) certificates can be checked as well.
+/// [`Duration::ZERO`] is returned for an already expired certificate chain.
+pub async fn check_server_cert_expiry(host: Host, port: u16) -> Result<Duration, OpaqueError> {
+    let stream = match &host {
+        Host::Name(domain) => TcpStream::connect((domain.as_str(), port)).await,
+        Host::Address(ip) => TcpStream::connect((*ip, port)).await,
+    }
+    .context("connect to server")?;
+
+    let server_name = ServerName::rama_try_from(host)?;
+    let connector = RustlsConnector::from(
+        TlsConnectorDataBuilder::new()
+            .with_no_cert_verifier()
+            .build()
+            .client_config,
+    );
+    let stream = connector
+        .connect(server_name, stream)
+        .await
+        .context("tls handshake with server")?;
+
+    let cert_chain = stream
+        .get_ref()
+        .1
+        .peer_certificates()
+        .context("server did not present a certificate")?;
+    Ok(time_until_expiry(cert_chain)?.unwrap_or_default())
+}
+
+/// Returns the time left until the earliest `not_after` of the chain,
+/// or `None` if it has already passed.
+fn time_until_expiry(cert_chain: &[CertificateDer<'_>]) -> Result<Option<Duration>, OpaqueError> {
+    let not_after = cert_chain
+        .iter()
+        .map(not_after)
+        .try_fold(None, |earliest: Option<SystemTime>, not_after| {
+            let not_after = not_after?;
+            Ok::<_, OpaqueError>(Some(earliest.map_or(not_after, |t| t.min(not_after))))
+        })?
+        .context("empty certificate chain")?;
+    Ok(not_after.duration_since(SystemTime::now()).ok())
+}
+
+fn not_after(cert: &CertificateDer<'_>) -> Result<SystemTime, OpaqueError> {
+    let (_, cert) = x509_parser::parse_x509_certificate(cert.as_ref())
+        .map_err(OpaqueError::from_std)
+        .context("parse x509 certificate")?;
+    let timestamp = cert.validity().not_after.timestamp();
+    Ok(if timestamp >= 0 {
+        UNIX_EPOCH + Duration::from_secs(timestamp as u64)
+    } else {
+        UNIX_EPOCH - Duration::from_secs(timestamp.unsigned_abs())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dep::pki_types::{PrivateKeyDer, PrivatePkcs8KeyDer};
+    use crate::dep::rcgen::{CertificateParams, KeyPair, date_time_ymd};
+    use crate::dep::tokio_rustls::TlsAcceptor;
+    use crate::server::TlsAcceptorDataBuilder;
+    use std::sync::Arc;
+    use tokio::net::TcpListener;
+
+    fn cert_expiring_in(remaining: Duration) -> (CertificateDer<'static>, PrivateKeyDer<'static>) {
+        let key_pair = KeyPair::generate().unwrap();
+        let mut params = CertificateParams::new(vec!["localhost".to_owned()]).unwrap();
+        params.not_before = date_time_ymd(2000, 1, 1);
+        params.not_after = (SystemTime::now() + remaining).into();
+        let cert = params.self_signed(&key_pair).unwrap();
+        (
+            cert.into(),
+            PrivatePkcs8KeyDer::from(key_pair.serialize_der()).into(),
+        )
+    }
+
+    #[test]
+    fn test_check_status() {
+        for (remaining, expected) in [
+            (60 * DAY, CertExpiryStatus::Valid(Duration::ZERO)),
+            (20 * DAY, CertExpiryStatus::Warning(Duration::ZERO)),
+            (DAY, CertExpiryStatus::Critical(Duration::ZERO)),
+        ] {
+            let (cert, _) = cert_expiring_in(remaining);
+            let status = CertExpiryMonitor::new(vec![cert]).check().unwrap();
+            match (status, expected) {
+                (CertExpiryStatus::Valid(d), CertExpiryStatus::Valid(_))
+                | (CertExpiryStatus::Warning(d), CertExpiryStatus::Warning(_))
+                | (CertExpiryStatus::Critical(d), CertExpiryStatus::Critical(_)) => {
+                    assert!(d <= remaining && d > remaining - Duration::from_secs(60));
+                }
+                _ => panic!("unexpected status {status:?}, expected {expected:?}"),
+            }
+        }
+    }
+
+    #[test]
+    fn test_check_expired() {
+        let key_pair = KeyPair::generate().unwrap();
+        let mut params = CertificateParams::new(vec!["localhost".to_owned()]).unwrap();
+        params.not_before = date_time_ymd(2000, 1, 1);
+        params.not_after = date_time_ymd(2001, 1, 1);
+        let cert = params.self_signed(&key_pair).unwrap();
+
+        let monitor = CertExpiryMonitor::new(vec![cert.into()]);
+        assert_eq!(monitor.check().unwrap(), CertExpiryStatus::Expired);
+    }
+
+    #[test]
+    fn test_check_earliest_expiry_in_chain() {
+        let (leaf, _) = cert_expiring_in(60 * DAY);
+        let (intermediate, _) = cert_expiring_in(DAY);
+
+        let monitor = CertExpiryMonitor::new(vec![leaf, intermediate]);
+        assert!(matches!(
+            monitor.check().unwrap(),
+            CertExpiryStatus::Critical(_)
+        ));
+    }
+
+    #[test]
+    fn test_check_invalid_chain() {
+        assert!(CertExpiryMonitor::new(vec![]).check().is_err());
+        assert!(
+            CertExpiryMonitor::new(vec![CertificateDer::from(vec![1, 2, 3])])
+                .check()
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_try_from_connector_data() {
+        let (cert, key) = cert_expiring_in(20 * DAY);
+        let data = TlsConnectorDataBuilder::new_with_client_auth(vec![cert], key)
+            .unwrap()
+            .build();
+        let monitor = CertExpiryMonitor::try_from_connector_data(&data).unwrap();
+        assert!(matches!(
+            monitor.check().unwrap(),
+            CertExpiryStatus::Warning(_)
+        ));
+
+        let data = TlsConnectorDataBuilder::new().build();
+        assert!(CertExpiryMonitor::try_from_connector_data(&data).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_check_server_cert_expiry() {
+        let (cert, key) = cert_expiring_in(10 * DAY);
+        let server_config = TlsAcceptorDataBuilder::new(vec![cert], key)
+            .unwrap()
+            .into_rustls_config();
+        let acceptor = TlsAcceptor::from(Arc::new(server_config));
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let _ = acceptor.accept(stream).await;
+        });
+
+        let remaining = check_server_cert_expiry(Host::Address(addr.ip()), addr.port())
+            .await
+            .unwrap();
+        assert!(remaining <= 10 * DAY && remaining > 10 * DAY - Duration::from_secs(60));
+    }
+}
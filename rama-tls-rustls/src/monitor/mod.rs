@@ -0,0 +1,10 @@
+//! Monitoring utilities for rustls certificates.
+//!
+//! - [`CertExpiryMonitor`] periodically checks when a certificate chain expires,
+//!   logging a warning or error as expiry approaches;
+//! - [`check_server_cert_expiry`] connects to a server and returns the time
+//!   left until its certificate chain expires.
+
+mod expiry;
+#[doc(inline)]
+pub use expiry::{CertExpiryMonitor, CertExpiryStatus, check_server_cert_expiry};
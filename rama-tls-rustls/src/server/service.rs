@@ -95,6 +95,9 @@ where
             application_layer_protocol: conn_data_ref
                 .alpn_protocol()
                 .map(ApplicationProtocol::from),
+            cipher_suite: conn_data_ref
+                .negotiated_cipher_suite()
+                .map(|suite| suite.suite().rama_into()),
             // Currently not supported as this would mean we need to wrap rustls config
             peer_certificate_chain: None,
         });
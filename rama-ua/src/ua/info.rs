@@ -63,6 +63,17 @@ impl UserAgent {
         parse_http_user_agent_header(header.into())
     }
 
+    /// Create the default [`UserAgent`] of rama,
+    /// e.g. `rama/0.2.0 (https://github.com/plabayo/rama)`.
+    pub fn rama_default() -> Self {
+        Self::new(format!(
+            "{}/{} ({})",
+            rama_utils::info::NAME,
+            rama_utils::info::VERSION,
+            rama_utils::info::REPOSITORY,
+        ))
+    }
+
     /// Overwrite the [`HttpAgent`] advertised by the [`UserAgent`].
     pub fn with_http_agent(mut self, http_agent: HttpAgent) -> Self {
         self.http_agent_overwrite = Some(http_agent);
@@ -558,6 +569,19 @@ mod tests {
         assert_eq!(ua.tls_agent(), Some(TlsAgent::Boringssl));
     }
 
+    #[test]
+    fn test_user_agent_rama_default() {
+        let ua = UserAgent::rama_default();
+        assert_eq!(
+            ua.header_str(),
+            format!(
+                "rama/{} (https://github.com/plabayo/rama)",
+                rama_utils::info::VERSION
+            )
+        );
+        assert!(ua.info().is_none());
+    }
+
     #[test]
     fn test_user_agent_parse() {
         let ua: UserAgent = "Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_7) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/124.0.0.0 Safari/537.36".parse().unwrap();
@@ -0,0 +1,96 @@
+//! Collection types shared by the rama crates.
+
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::ops::{Deref, DerefMut};
+
+const MIN_PRUNE_AT: usize = 1024;
+
+/// A [`HashMap`] which is pruned as it grows,
+/// such that entries which are no longer relevant do not accumulate.
+///
+/// Pruning happens once the map holds twice the amount of entries it held
+/// after the previous prune (with a minimum of 1024 entries),
+/// keeping the amortized cost of pruning constant for each inserted entry.
+///
+/// # Examples
+///
+/// ```
+/// use rama_utils::collections::PruningHashMap;
+///
+/// let mut map = PruningHashMap::new();
+/// map.prune(|_, expired: &mut bool| !*expired);
+/// map.insert("key", false);
+/// assert_eq!(map.len(), 1);
+/// ```
+#[derive(Debug, Clone)]
+pub struct PruningHashMap<K, V> {
+    map: HashMap<K, V>,
+    prune_at: usize,
+}
+
+impl<K, V> Default for PruningHashMap<K, V> {
+    fn default() -> Self {
+        Self {
+            map: HashMap::new(),
+            prune_at: MIN_PRUNE_AT,
+        }
+    }
+}
+
+impl<K, V> PruningHashMap<K, V> {
+    /// Create a new empty [`PruningHashMap`].
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl<K: Eq + Hash, V> PruningHashMap<K, V> {
+    /// Prune the map in case it grew large enough since the previous prune,
+    /// retaining only the entries for which `keep` returns `true`.
+    ///
+    /// Call this prior to inserting new entries.
+    pub fn prune(&mut self, keep: impl FnMut(&K, &mut V) -> bool) {
+        if self.map.len() >= self.prune_at {
+            self.map.retain(keep);
+            self.prune_at = (self.map.len() * 2).max(MIN_PRUNE_AT);
+        }
+    }
+}
+
+impl<K, V> Deref for PruningHashMap<K, V> {
+    type Target = HashMap<K, V>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.map
+    }
+}
+
+impl<K, V> DerefMut for PruningHashMap<K, V> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.map
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pruning_hash_map() {
+        let mut map = PruningHashMap::new();
+        for i in 0..MIN_PRUNE_AT {
+            map.prune(|_, _| false);
+            map.insert(i, i % 2 == 0);
+        }
+        assert_eq!(map.len(), MIN_PRUNE_AT);
+
+        // only the entries to keep remain after the prune
+        map.prune(|_, keep| *keep);
+        assert_eq!(map.len(), MIN_PRUNE_AT / 2);
+
+        // no prune happens until the map grew large enough again
+        map.prune(|_, _| false);
+        assert_eq!(map.len(), MIN_PRUNE_AT / 2);
+    }
+}
@@ -5,3 +5,6 @@ pub const NAME: &str = "rama";
 
 /// The version of the crate.
 pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// The repository of the crate.
+pub const REPOSITORY: &str = env!("CARGO_PKG_REPOSITORY");
@@ -26,6 +26,7 @@
 pub mod macros;
 
 pub mod backoff;
+pub mod collections;
 pub mod info;
 pub mod latency;
 pub mod octets;
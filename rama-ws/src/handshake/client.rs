@@ -154,6 +154,7 @@ pub enum ResponseValidateError {
     MissingConnectionUpgradeHeader,
     SecWebSocketAcceptKeyMismatch,
     SubProtocolMismatch(Option<HeaderValue>),
+    ExtensionMismatch(OpaqueError),
 }
 
 #[derive(Debug)]
@@ -185,6 +186,9 @@ impl fmt::Display for ResponseValidateError {
             ResponseValidateError::SubProtocolMismatch(header_value) => {
                 write!(f, "sub protocol mismatch: {header_value:?}")
             }
+            ResponseValidateError::ExtensionMismatch(error) => {
+                write!(f, "extension mismatch: {error}")
+            }
         }
     }
 }
@@ -563,6 +567,9 @@ where
 
     rama_utils::macros::generate_set_and_with! {
         /// Set the [`WebSocketConfig`], overwriting the previous config if already set.
+        ///
+        /// The `permessage-deflate` extension is offered to the server
+        /// in case [`WebSocketConfig::compression`] is set.
         pub fn config(mut self, cfg: Option<WebSocketConfig>) -> Self {
             self.inner.config = cfg;
             self
@@ -591,6 +598,15 @@ where
             builder
         };
 
+        let compression = self.inner.config.and_then(|config| config.compression);
+        let builder = match compression {
+            Some(compression) => builder.overwrite_header(
+                header::SEC_WEBSOCKET_EXTENSIONS,
+                compression.to_header_value(),
+            ),
+            None => builder,
+        };
+
         // only required in h1, but because of layers such as tls we might anyway turn from h1 into h2
         let builder = builder.extension(Protocol::from_static("websocket"));
 
@@ -603,8 +619,30 @@ where
         let accepted_protocol = validate_http_server_response(&response, key, self.sub_protocols)
             .map_err(HandshakeError::ValidationError)?;
 
+        // If the response includes a |Sec-WebSocket-Extensions| header
+        // field and this header field indicates the use of an extension
+        // that was not present in the client's handshake, the client
+        // MUST _Fail the WebSocket Connection_. (RFC 6455)
+        let extensions = response.headers().get_all(header::SEC_WEBSOCKET_EXTENSIONS);
+        let compression = match compression {
+            Some(compression) => compression.negotiate_response(extensions).map_err(|err| {
+                HandshakeError::ValidationError(ResponseValidateError::ExtensionMismatch(err))
+            })?,
+            None => {
+                if let Some(extension) = extensions.iter().next() {
+                    return Err(HandshakeError::ValidationError(
+                        ResponseValidateError::ExtensionMismatch(OpaqueError::from_display(
+                            format!("unexpected extension: {extension:?}"),
+                        )),
+                    ));
+                }
+                None
+            }
+        };
+
         tracing::trace!(
             websocket.protocol = ?accepted_protocol,
+            websocket.compression = ?compression,
             "websocket handshake http response is valid",
         );
 
@@ -615,7 +653,11 @@ where
 
         let (parts, _) = response.into_parts();
 
-        let socket = AsyncWebSocket::from_raw_socket(stream, Role::Client, self.inner.config).await;
+        let config = self
+            .inner
+            .config
+            .map(|config| config.compression(compression));
+        let socket = AsyncWebSocket::from_raw_socket(stream, Role::Client, config).await;
 
         Ok(ClientWebSocket {
             socket,
@@ -3,13 +3,15 @@
 use std::{
     fmt,
     ops::{Deref, DerefMut},
+    pin::Pin,
+    task::{Context as TaskContext, Poll},
 };
 
 use rama_core::{
     Context, Service,
     context::Extensions,
     error::{ErrorContext, OpaqueError},
-    futures::{StreamExt, TryStreamExt},
+    futures::{self, Sink, StreamExt, TryStreamExt},
     matcher::Matcher,
     telemetry::tracing::{self, Instrument},
 };
@@ -26,8 +28,9 @@ use smallvec::SmallVec;
 use smol_str::SmolStr;
 
 use crate::{
-    Message,
+    Message, ProtocolError,
     handshake::{AcceptedSubProtocol, SubProtocols},
+    heartbeat::HeartbeatWebSocket,
     protocol::{Role, WebSocketConfig},
     runtime::AsyncWebSocket,
 };
@@ -555,6 +558,9 @@ impl<S: Clone> Clone for WebSocketAcceptorService<S> {
 impl<S> WebSocketAcceptorService<S> {
     rama_utils::macros::generate_set_and_with! {
         /// Set the [`WebSocketConfig`], overwriting the previous config if already set.
+        ///
+        /// The `permessage-deflate` extension is accepted if offered by the client
+        /// in case [`WebSocketConfig::compression`] is set.
         pub fn config(mut self, cfg: Option<WebSocketConfig>) -> Self {
             self.config = cfg;
             self
@@ -604,6 +610,49 @@ impl ServerWebSocket {
     }
 }
 
+impl futures::Stream for ServerWebSocket {
+    type Item = Result<Message, ProtocolError>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut TaskContext<'_>) -> Poll<Option<Self::Item>> {
+        Pin::new(&mut self.socket).poll_next(cx)
+    }
+}
+
+impl futures::stream::FusedStream for ServerWebSocket {
+    fn is_terminated(&self) -> bool {
+        self.socket.is_terminated()
+    }
+}
+
+impl Sink<Message> for ServerWebSocket {
+    type Error = ProtocolError;
+
+    fn poll_ready(
+        mut self: Pin<&mut Self>,
+        cx: &mut TaskContext<'_>,
+    ) -> Poll<Result<(), Self::Error>> {
+        Pin::new(&mut self.socket).poll_ready(cx)
+    }
+
+    fn start_send(mut self: Pin<&mut Self>, item: Message) -> Result<(), Self::Error> {
+        Pin::new(&mut self.socket).start_send(item)
+    }
+
+    fn poll_flush(
+        mut self: Pin<&mut Self>,
+        cx: &mut TaskContext<'_>,
+    ) -> Poll<Result<(), Self::Error>> {
+        Pin::new(&mut self.socket).poll_flush(cx)
+    }
+
+    fn poll_close(
+        mut self: Pin<&mut Self>,
+        cx: &mut TaskContext<'_>,
+    ) -> Poll<Result<(), Self::Error>> {
+        Pin::new(&mut self.socket).poll_close(cx)
+    }
+}
+
 impl<S, State, Body> Service<State, Request<Body>> for WebSocketAcceptorService<S>
 where
     S: Clone + Service<State, ServerWebSocket, Response = ()>,
@@ -619,7 +668,26 @@ where
         req: Request<Body>,
     ) -> Result<Self::Response, Self::Error> {
         match self.acceptor.serve(ctx, req).await {
-            Ok((resp, ctx, mut req)) => {
+            Ok((mut resp, ctx, mut req)) => {
+                let config = self.config.map(|config| {
+                    let compression = config.compression.and_then(|compression| {
+                        compression.negotiate_offers(
+                            req.headers().get_all(header::SEC_WEBSOCKET_EXTENSIONS),
+                        )
+                    });
+                    if let Some(compression) = compression {
+                        tracing::trace!(
+                            ?compression,
+                            "WebSocketAcceptorService: permessage-deflate extension accepted"
+                        );
+                        resp.headers_mut().insert(
+                            header::SEC_WEBSOCKET_EXTENSIONS,
+                            compression.to_header_value(),
+                        );
+                    }
+                    config.compression(compression)
+                });
+
                 let handler = self.service.clone();
                 let span = tracing::trace_root_span!(
                     "ws::serve",
@@ -638,7 +706,7 @@ where
                         match upgrade::on(&mut req).await {
                             Ok(upgraded) => {
                                 let socket =
-                                    AsyncWebSocket::from_raw_socket(upgraded, Role::Server, None)
+                                    AsyncWebSocket::from_raw_socket(upgraded, Role::Server, config)
                                         .await;
                                 let (parts, _) = req.into_parts();
 
@@ -696,55 +764,79 @@ where
     type Response = ();
     type Error = OpaqueError;
 
-    async fn serve(
+    fn serve(
         &self,
         ctx: Context<State>,
         socket: AsyncWebSocket,
-    ) -> Result<Self::Response, Self::Error> {
-        let protocol = ctx
-            .get::<AcceptedSubProtocol>()
-            .map(|p| p.as_str())
-            .unwrap_or(ECHO_SERVICE_SUB_PROTOCOL_DEFAULT);
-        let transformer = if protocol.eq_ignore_ascii_case(ECHO_SERVICE_SUB_PROTOCOL_LOWER) {
-            |msg: Message| {
-                std::future::ready(Ok(match msg {
-                    Message::Text(original) => Some(original.to_lowercase().to_string().into()),
-                    msg @ Message::Binary(_) => Some(msg),
-                    Message::Ping(_) | Message::Pong(_) | Message::Close(_) | Message::Frame(_) => {
-                        None
-                    }
-                }))
-            }
-        } else if protocol.eq_ignore_ascii_case(ECHO_SERVICE_SUB_PROTOCOL_UPPER) {
-            |msg: Message| {
-                std::future::ready(Ok(match msg {
-                    Message::Text(original) => Some(original.to_uppercase().to_string().into()),
-                    msg @ Message::Binary(_) => Some(msg),
-                    Message::Ping(_) | Message::Pong(_) | Message::Close(_) | Message::Frame(_) => {
-                        None
-                    }
-                }))
-            }
-        } else {
-            |msg: Message| {
-                std::future::ready(Ok(match msg {
-                    msg @ (Message::Text(_) | Message::Binary(_)) => Some(msg),
-                    Message::Ping(_) | Message::Pong(_) | Message::Close(_) | Message::Frame(_) => {
-                        None
-                    }
-                }))
-            }
-        };
+    ) -> impl Future<Output = Result<Self::Response, Self::Error>> + Send + '_ {
+        echo(ctx, socket)
+    }
+}
 
-        let (write, read) = socket.split();
-        // We should not forward messages other than text or binary.
-        read.try_filter_map(transformer)
-            .forward(write)
-            .await
-            .context("forward messages")
+impl<State, W> Service<State, HeartbeatWebSocket<W>> for WebSocketEchoService
+where
+    State: Clone + Send + Sync + 'static,
+    W: futures::Stream<Item = Result<Message, ProtocolError>>
+        + Sink<Message, Error = ProtocolError>
+        + Send
+        + Unpin
+        + 'static,
+{
+    type Response = ();
+    type Error = OpaqueError;
+
+    fn serve(
+        &self,
+        ctx: Context<State>,
+        socket: HeartbeatWebSocket<W>,
+    ) -> impl Future<Output = Result<Self::Response, Self::Error>> + Send + '_ {
+        echo(ctx, socket)
     }
 }
 
+async fn echo<State, W>(ctx: Context<State>, socket: W) -> Result<(), OpaqueError>
+where
+    W: futures::Stream<Item = Result<Message, ProtocolError>>
+        + Sink<Message, Error = ProtocolError>
+        + Send,
+{
+    let protocol = ctx
+        .get::<AcceptedSubProtocol>()
+        .map(|p| p.as_str())
+        .unwrap_or(ECHO_SERVICE_SUB_PROTOCOL_DEFAULT);
+    let transformer = if protocol.eq_ignore_ascii_case(ECHO_SERVICE_SUB_PROTOCOL_LOWER) {
+        |msg: Message| {
+            std::future::ready(Ok(match msg {
+                Message::Text(original) => Some(original.to_lowercase().to_string().into()),
+                msg @ Message::Binary(_) => Some(msg),
+                Message::Ping(_) | Message::Pong(_) | Message::Close(_) | Message::Frame(_) => None,
+            }))
+        }
+    } else if protocol.eq_ignore_ascii_case(ECHO_SERVICE_SUB_PROTOCOL_UPPER) {
+        |msg: Message| {
+            std::future::ready(Ok(match msg {
+                Message::Text(original) => Some(original.to_uppercase().to_string().into()),
+                msg @ Message::Binary(_) => Some(msg),
+                Message::Ping(_) | Message::Pong(_) | Message::Close(_) | Message::Frame(_) => None,
+            }))
+        }
+    } else {
+        |msg: Message| {
+            std::future::ready(Ok(match msg {
+                msg @ (Message::Text(_) | Message::Binary(_)) => Some(msg),
+                Message::Ping(_) | Message::Pong(_) | Message::Close(_) | Message::Frame(_) => None,
+            }))
+        }
+    };
+
+    let (write, read) = socket.split();
+    // We should not forward messages other than text or binary.
+    read.try_filter_map(transformer)
+        .forward(write)
+        .await
+        .context("forward messages")
+}
+
 impl<State> Service<State, ServerWebSocket> for WebSocketEchoService
 where
     State: Clone + Send + Sync + 'static,
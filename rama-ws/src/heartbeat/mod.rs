@@ -0,0 +1,32 @@
+//! WebSocket heartbeat, detecting connections which silently died.
+//!
+//! The [`WsHeartbeatLayer`] wraps the [`ServerWebSocket`] (or any other WebSocket)
+//! passed to the inner service in a [`HeartbeatWebSocket`], which sends a `Ping`
+//! frame each interval. In case no `Pong` frame is received within the configured
+//! timeout of a ping, the connection is closed with [`CloseCode::Policy`]
+//! (policy violation) and an error is returned to the reader.
+//!
+//! # Example
+//!
+//! ```
+//! use rama_ws::handshake::server::{WebSocketAcceptor, WebSocketEchoService};
+//! use rama_ws::heartbeat::WsHeartbeatLayer;
+//! use rama_core::Layer;
+//! use std::time::Duration;
+//!
+//! let svc = WebSocketAcceptor::new().into_service(
+//!     WsHeartbeatLayer::new(Duration::from_secs(30), Duration::from_secs(10))
+//!         .into_layer(WebSocketEchoService::new()),
+//! );
+//! ```
+//!
+//! [`ServerWebSocket`]: crate::handshake::server::ServerWebSocket
+//! [`CloseCode::Policy`]: crate::protocol::frame::coding::CloseCode::Policy
+
+mod socket;
+#[doc(inline)]
+pub use socket::HeartbeatWebSocket;
+
+mod service;
+#[doc(inline)]
+pub use service::{WsHeartbeatLayer, WsHeartbeatService};
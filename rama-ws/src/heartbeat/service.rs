@@ -0,0 +1,167 @@
+use std::{fmt, time::Duration};
+
+use rama_core::{Context, Layer, Service};
+use rama_utils::macros::define_inner_service_accessors;
+
+use super::HeartbeatWebSocket;
+
+/// Layer that applies the [`WsHeartbeatService`] middleware.
+///
+/// See the [module docs](super) for more details.
+#[derive(Debug, Clone)]
+pub struct WsHeartbeatLayer {
+    interval: Duration,
+    timeout: Duration,
+}
+
+impl WsHeartbeatLayer {
+    /// Create a new [`WsHeartbeatLayer`], sending a ping each `interval`
+    /// and closing the connection if no pong is received within `timeout` of a ping.
+    pub const fn new(interval: Duration, timeout: Duration) -> Self {
+        Self { interval, timeout }
+    }
+}
+
+impl<S> Layer<S> for WsHeartbeatLayer {
+    type Service = WsHeartbeatService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        WsHeartbeatService::new(inner, self.interval, self.timeout)
+    }
+}
+
+/// Middleware which wraps WebSockets in a [`HeartbeatWebSocket`]
+/// before passing them to the inner service.
+///
+/// See the [module docs](super) for more details.
+pub struct WsHeartbeatService<S> {
+    inner: S,
+    interval: Duration,
+    timeout: Duration,
+}
+
+impl<S> WsHeartbeatService<S> {
+    /// Create a new [`WsHeartbeatService`], sending a ping each `interval`
+    /// and closing the connection if no pong is received within `timeout` of a ping.
+    pub const fn new(inner: S, interval: Duration, timeout: Duration) -> Self {
+        Self {
+            inner,
+            interval,
+            timeout,
+        }
+    }
+
+    define_inner_service_accessors!();
+}
+
+impl<S: fmt::Debug> fmt::Debug for WsHeartbeatService<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WsHeartbeatService")
+            .field("inner", &self.inner)
+            .field("interval", &self.interval)
+            .field("timeout", &self.timeout)
+            .finish()
+    }
+}
+
+impl<S: Clone> Clone for WsHeartbeatService<S> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            interval: self.interval,
+            timeout: self.timeout,
+        }
+    }
+}
+
+impl<State, S, W> Service<State, W> for WsHeartbeatService<S>
+where
+    State: Clone + Send + Sync + 'static,
+    S: Service<State, HeartbeatWebSocket<W>>,
+    W: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+
+    fn serve(
+        &self,
+        ctx: Context<State>,
+        socket: W,
+    ) -> impl Future<Output = Result<Self::Response, Self::Error>> + Send + '_ {
+        let socket = HeartbeatWebSocket::new(socket, self.interval, self.timeout);
+        self.inner.serve(ctx, socket)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::handshake::server::WebSocketEchoService;
+    use crate::protocol::{CloseFrame, Role, frame::coding::CloseCode};
+    use crate::{AsyncWebSocket, Message};
+    use tokio::io::DuplexStream;
+
+    async fn socket_pair() -> (AsyncWebSocket<DuplexStream>, AsyncWebSocket<DuplexStream>) {
+        let (client, server) = tokio::io::duplex(4096);
+        (
+            AsyncWebSocket::from_raw_socket(client, Role::Client, None).await,
+            AsyncWebSocket::from_raw_socket(server, Role::Server, None).await,
+        )
+    }
+
+    fn echo_server(
+        server: AsyncWebSocket<DuplexStream>,
+    ) -> tokio::task::JoinHandle<Result<(), rama_core::error::OpaqueError>> {
+        let svc = WsHeartbeatLayer::new(Duration::from_secs(1), Duration::from_millis(500))
+            .into_layer(WebSocketEchoService::new());
+        tokio::spawn(async move { svc.serve(Context::default(), server).await })
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_heartbeat_keeps_responsive_connection_alive() {
+        let (mut client, server) = socket_pair().await;
+        let server = echo_server(server);
+
+        // reading auto-responds to the pings with pongs
+        let mut pings = 0;
+        let deadline = tokio::time::Instant::now() + Duration::from_secs(5);
+        while let Ok(msg) = tokio::time::timeout_at(deadline, client.recv_message()).await {
+            assert!(msg.unwrap().is_ping());
+            pings += 1;
+        }
+        assert!(pings >= 4, "pings: {pings}");
+
+        client.send_message(Message::text("hello")).await.unwrap();
+        let msg = loop {
+            let msg = client.recv_message().await.unwrap();
+            if !msg.is_ping() {
+                break msg;
+            }
+        };
+        assert_eq!(msg, Message::text("hello"));
+        assert!(!server.is_finished());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_heartbeat_closes_unresponsive_connection() {
+        let (mut client, server) = socket_pair().await;
+        let mut server =
+            HeartbeatWebSocket::new(server, Duration::from_secs(1), Duration::from_millis(500));
+
+        // client is not reading, so no pong is sent for the ping
+        let err = server.recv_message().await.unwrap_err();
+        assert!(
+            matches!(&err, crate::ProtocolError::Io(err) if err.kind() == std::io::ErrorKind::TimedOut),
+            "{err:?}"
+        );
+
+        assert!(client.recv_message().await.unwrap().is_ping());
+        assert_eq!(
+            client.recv_message().await.unwrap(),
+            Message::Close(Some(CloseFrame {
+                code: CloseCode::Policy,
+                reason: "heartbeat timeout".into(),
+            }))
+        );
+    }
+}
@@ -0,0 +1,229 @@
+use std::{
+    io,
+    pin::Pin,
+    task::{Context, Poll, ready},
+    time::Duration,
+};
+
+use rama_core::{
+    bytes::Bytes,
+    error::OpaqueError,
+    futures::{self, Sink, SinkExt, StreamExt},
+    telemetry::tracing,
+};
+use rama_http::dep::http::request;
+use tokio::time::{Instant, Sleep, sleep};
+
+use crate::{
+    Message, ProtocolError,
+    handshake::server::ServerWebSocket,
+    protocol::{CloseFrame, frame::coding::CloseCode},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum HeartbeatState {
+    /// Waiting for the interval to elapse, to send the next ping.
+    Idle,
+    /// Ping sent, waiting for a pong before the timeout elapses.
+    AwaitingPong,
+    /// No pong received in time, the connection is closed.
+    TimedOut,
+}
+
+/// WebSocket which sends a ping each interval and closes the connection
+/// if no pong is received within the configured timeout of a ping.
+///
+/// The heartbeat is driven by reading from the socket, as is also
+/// required to receive (and automatically respond to) control frames.
+/// All messages, including pongs, are passed on to the reader.
+///
+/// Created by the [`WsHeartbeatService`] or using [`HeartbeatWebSocket::new`].
+///
+/// [`WsHeartbeatService`]: super::WsHeartbeatService
+pub struct HeartbeatWebSocket<W = ServerWebSocket> {
+    socket: W,
+    interval: Duration,
+    timeout: Duration,
+    sleep: Pin<Box<Sleep>>,
+    state: HeartbeatState,
+}
+
+impl<W: std::fmt::Debug> std::fmt::Debug for HeartbeatWebSocket<W> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("HeartbeatWebSocket")
+            .field("socket", &self.socket)
+            .field("interval", &self.interval)
+            .field("timeout", &self.timeout)
+            .field("state", &self.state)
+            .finish()
+    }
+}
+
+impl<W> HeartbeatWebSocket<W> {
+    /// Create a new [`HeartbeatWebSocket`], sending a ping each `interval`
+    /// and closing the connection if no pong is received within `timeout` of a ping.
+    ///
+    /// # Panics
+    ///
+    /// Panics if called outside of a tokio runtime.
+    pub fn new(socket: W, interval: Duration, timeout: Duration) -> Self {
+        Self {
+            socket,
+            interval,
+            timeout,
+            sleep: Box::pin(sleep(interval)),
+            state: HeartbeatState::Idle,
+        }
+    }
+
+    /// Gets a reference to the underlying socket.
+    pub fn get_ref(&self) -> &W {
+        &self.socket
+    }
+
+    /// Gets a mutable reference to the underlying socket.
+    ///
+    /// Messages received directly from the underlying socket
+    /// do not count towards the heartbeat.
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.socket
+    }
+
+    /// Consume `self` into the underlying socket, stopping the heartbeat.
+    pub fn into_inner(self) -> W {
+        self.socket
+    }
+}
+
+impl HeartbeatWebSocket<ServerWebSocket> {
+    /// View the original request data, from which this server web socket was created.
+    pub fn request(&self) -> &request::Parts {
+        self.socket.request()
+    }
+}
+
+impl<W> HeartbeatWebSocket<W>
+where
+    W: futures::Stream<Item = Result<Message, ProtocolError>>
+        + Sink<Message, Error = ProtocolError>
+        + Unpin,
+{
+    /// Writes and immediately flushes a message.
+    pub async fn send_message(&mut self, msg: Message) -> Result<(), ProtocolError> {
+        self.send(msg).await
+    }
+
+    /// Receive the next message, driving the heartbeat while waiting for it.
+    pub async fn recv_message(&mut self) -> Result<Message, ProtocolError> {
+        self.next().await.ok_or_else(|| {
+            ProtocolError::Io(io::Error::new(
+                io::ErrorKind::ConnectionAborted,
+                OpaqueError::from_display(
+                    "Connection closed: no messages to be received any longer",
+                ),
+            ))
+        })?
+    }
+
+    /// Send a ping or close the connection, depending on the state,
+    /// now that the heartbeat timer elapsed.
+    ///
+    /// Returns an error in case the connection is to be considered dead.
+    fn poll_heartbeat(&mut self, cx: &mut Context<'_>) -> Result<(), ProtocolError> {
+        match self.state {
+            HeartbeatState::Idle => {
+                // try again on the next poll in case the socket is not ready yet
+                if let Poll::Ready(result) = Pin::new(&mut self.socket).poll_ready(cx) {
+                    result?;
+                    Pin::new(&mut self.socket).start_send(Message::Ping(Bytes::new()))?;
+                    // completed by subsequent reads and writes if still pending
+                    if let Poll::Ready(result) = Pin::new(&mut self.socket).poll_flush(cx) {
+                        result?;
+                    }
+                    tracing::trace!("ws heartbeat: ping sent");
+                    self.state = HeartbeatState::AwaitingPong;
+                    self.sleep.as_mut().reset(Instant::now() + self.timeout);
+                    // register the waker for the reset timer
+                    let _ = self.sleep.as_mut().poll(cx);
+                }
+                Ok(())
+            }
+            HeartbeatState::AwaitingPong => {
+                tracing::debug!(
+                    timeout = ?self.timeout,
+                    "ws heartbeat: no pong received in time: closing connection",
+                );
+                self.state = HeartbeatState::TimedOut;
+                if let Poll::Ready(Ok(())) = Pin::new(&mut self.socket).poll_ready(cx) {
+                    let _ =
+                        Pin::new(&mut self.socket).start_send(Message::Close(Some(CloseFrame {
+                            code: CloseCode::Policy,
+                            reason: "heartbeat timeout".into(),
+                        })));
+                    let _ = Pin::new(&mut self.socket).poll_flush(cx);
+                }
+                Err(ProtocolError::Io(io::Error::new(
+                    io::ErrorKind::TimedOut,
+                    OpaqueError::from_display("ws heartbeat: no pong received in time"),
+                )))
+            }
+            HeartbeatState::TimedOut => Ok(()),
+        }
+    }
+}
+
+impl<W> futures::Stream for HeartbeatWebSocket<W>
+where
+    W: futures::Stream<Item = Result<Message, ProtocolError>>
+        + Sink<Message, Error = ProtocolError>
+        + Unpin,
+{
+    type Item = Result<Message, ProtocolError>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+
+        if this.state == HeartbeatState::TimedOut {
+            return Poll::Ready(None);
+        }
+
+        if this.sleep.as_mut().poll(cx).is_ready() {
+            if let Err(err) = this.poll_heartbeat(cx) {
+                return Poll::Ready(Some(Err(err)));
+            }
+        }
+
+        let item = ready!(Pin::new(&mut this.socket).poll_next(cx));
+        if let Some(Ok(Message::Pong(_))) = item {
+            if this.state == HeartbeatState::AwaitingPong {
+                tracing::trace!("ws heartbeat: pong received");
+                this.state = HeartbeatState::Idle;
+                this.sleep.as_mut().reset(Instant::now() + this.interval);
+            }
+        }
+        Poll::Ready(item)
+    }
+}
+
+impl<W> Sink<Message> for HeartbeatWebSocket<W>
+where
+    W: Sink<Message, Error = ProtocolError> + Unpin,
+{
+    type Error = ProtocolError;
+
+    fn poll_ready(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Pin::new(&mut self.get_mut().socket).poll_ready(cx)
+    }
+
+    fn start_send(self: Pin<&mut Self>, item: Message) -> Result<(), Self::Error> {
+        Pin::new(&mut self.get_mut().socket).start_send(item)
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Pin::new(&mut self.get_mut().socket).poll_flush(cx)
+    }
+
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Pin::new(&mut self.get_mut().socket).poll_close(cx)
+    }
+}
@@ -20,6 +20,7 @@
 #![cfg_attr(not(test), warn(clippy::print_stdout, clippy::dbg_macro))]
 
 pub mod handshake;
+pub mod heartbeat;
 pub mod protocol;
 pub mod runtime;
 
@@ -0,0 +1,509 @@
+//! Per-message compression extension (`permessage-deflate`, [RFC 7692]).
+//!
+//! [RFC 7692]: https://datatracker.ietf.org/doc/html/rfc7692
+
+use crate::protocol::{ProtocolError, Role};
+use flate2::{Compress, Compression, Decompress, FlushCompress, FlushDecompress, Status};
+use rama_core::error::OpaqueError;
+use rama_http::HeaderValue;
+
+/// Name of the `permessage-deflate` extension.
+const PERMESSAGE_DEFLATE: &str = "permessage-deflate";
+
+const SERVER_NO_CONTEXT_TAKEOVER: &str = "server_no_context_takeover";
+const CLIENT_NO_CONTEXT_TAKEOVER: &str = "client_no_context_takeover";
+const SERVER_MAX_WINDOW_BITS: &str = "server_max_window_bits";
+const CLIENT_MAX_WINDOW_BITS: &str = "client_max_window_bits";
+
+/// The only LZ77 sliding window size supported by this implementation.
+const MAX_WINDOW_BITS: u8 = 15;
+
+/// Trailing bytes of a deflate block flushed using a sync flush,
+/// removed from (and appended to) the compressed message payload. (RFC 7692)
+const DEFLATE_TRAILER: [u8; 4] = [0x00, 0x00, 0xff, 0xff];
+
+/// The configuration of the `permessage-deflate` extension ([RFC 7692]).
+///
+/// When set as the [`WebSocketConfig::compression`] of a client,
+/// the extension is offered as part of the handshake, and when set
+/// for a server, the extension is accepted if offered by the client.
+/// Once agreed upon, `Text` and `Binary` messages are compressed.
+///
+/// Only the default (and maximum) LZ77 sliding window size of 15 bits is supported,
+/// offers which require a smaller window for the server are declined.
+///
+/// # Example
+/// ```
+/// # use rama_ws::protocol::{DeflateConfig, WebSocketConfig};
+///
+/// let conf = WebSocketConfig::default()
+///     .compression(Some(DeflateConfig::default().server_no_context_takeover(true)));
+/// ```
+///
+/// [RFC 7692]: https://datatracker.ietf.org/doc/html/rfc7692
+/// [`WebSocketConfig::compression`]: super::WebSocketConfig::compression
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct DeflateConfig {
+    /// When `true` the server resets its compression context after each message,
+    /// trading compression ratio for lower memory usage.
+    ///
+    /// A server always accepts this parameter when requested by the client.
+    pub server_no_context_takeover: bool,
+
+    /// When `true` the client resets its compression context after each message,
+    /// trading compression ratio for lower memory usage.
+    ///
+    /// A client always accepts this parameter when requested by the server.
+    pub client_no_context_takeover: bool,
+}
+
+impl DeflateConfig {
+    /// Set [`Self::server_no_context_takeover`].
+    pub fn server_no_context_takeover(mut self, server_no_context_takeover: bool) -> Self {
+        self.server_no_context_takeover = server_no_context_takeover;
+        self
+    }
+
+    /// Set [`Self::client_no_context_takeover`].
+    pub fn client_no_context_takeover(mut self, client_no_context_takeover: bool) -> Self {
+        self.client_no_context_takeover = client_no_context_takeover;
+        self
+    }
+
+    /// Encode this config as a `Sec-WebSocket-Extensions` header value,
+    /// used for both the client offer and the server response.
+    pub(crate) fn to_header_value(self) -> HeaderValue {
+        let mut value = String::from(PERMESSAGE_DEFLATE);
+        if self.server_no_context_takeover {
+            value.push_str("; ");
+            value.push_str(SERVER_NO_CONTEXT_TAKEOVER);
+        }
+        if self.client_no_context_takeover {
+            value.push_str("; ");
+            value.push_str(CLIENT_NO_CONTEXT_TAKEOVER);
+        }
+        HeaderValue::try_from(value).expect("static extension params to be a valid header value")
+    }
+
+    /// Negotiate the extension as a server, using `self` as the server preferences.
+    ///
+    /// Returns the agreed upon config for the first acceptable
+    /// `permessage-deflate` offer of the client, if any.
+    pub(crate) fn negotiate_offers<'a>(
+        self,
+        headers: impl IntoIterator<Item = &'a HeaderValue>,
+    ) -> Option<Self> {
+        parse_extensions(headers)
+            .filter(|ext| ext.name.eq_ignore_ascii_case(PERMESSAGE_DEFLATE))
+            .find_map(|ext| {
+                let mut agreed = self;
+                let mut seen: Vec<&str> = Vec::with_capacity(ext.params.len());
+                for (name, value) in ext.params {
+                    if seen.iter().any(|s| s.eq_ignore_ascii_case(name)) {
+                        return None;
+                    }
+                    seen.push(name);
+
+                    if name.eq_ignore_ascii_case(SERVER_NO_CONTEXT_TAKEOVER) && value.is_none() {
+                        agreed.server_no_context_takeover = true;
+                    } else if name.eq_ignore_ascii_case(CLIENT_NO_CONTEXT_TAKEOVER)
+                        && value.is_none()
+                    {
+                        agreed.client_no_context_takeover = true;
+                    } else if name.eq_ignore_ascii_case(SERVER_MAX_WINDOW_BITS) {
+                        // a smaller window for our own compressor is not supported
+                        if parse_window_bits(value?)? != MAX_WINDOW_BITS {
+                            return None;
+                        }
+                    } else if name.eq_ignore_ascii_case(CLIENT_MAX_WINDOW_BITS) {
+                        // only a hint that the client supports limiting its window,
+                        // which we do not require as any window size can be decompressed
+                        if let Some(value) = value {
+                            parse_window_bits(value)?;
+                        }
+                    } else {
+                        return None;
+                    }
+                }
+                Some(agreed)
+            })
+    }
+
+    /// Validate the server response as a client, using `self` as the offered config.
+    ///
+    /// Returns the agreed upon config in case the extension was accepted by the server,
+    /// or an error in case the response contains an extension or parameter which
+    /// was not offered or cannot be honored.
+    pub(crate) fn negotiate_response<'a>(
+        self,
+        headers: impl IntoIterator<Item = &'a HeaderValue>,
+    ) -> Result<Option<Self>, OpaqueError> {
+        let mut agreed = None;
+        for ext in parse_extensions(headers) {
+            if !ext.name.eq_ignore_ascii_case(PERMESSAGE_DEFLATE) {
+                return Err(OpaqueError::from_display(format!(
+                    "unexpected extension: {}",
+                    ext.name
+                )));
+            }
+            if agreed.is_some() {
+                return Err(OpaqueError::from_display(
+                    "permessage-deflate extension accepted more than once",
+                ));
+            }
+
+            let mut config = self;
+            for (name, value) in ext.params {
+                if name.eq_ignore_ascii_case(SERVER_NO_CONTEXT_TAKEOVER) && value.is_none() {
+                    config.server_no_context_takeover = true;
+                } else if name.eq_ignore_ascii_case(CLIENT_NO_CONTEXT_TAKEOVER) && value.is_none() {
+                    config.client_no_context_takeover = true;
+                } else if name.eq_ignore_ascii_case(SERVER_MAX_WINDOW_BITS) {
+                    value.and_then(parse_window_bits).ok_or_else(|| {
+                        OpaqueError::from_display("invalid server_max_window_bits param")
+                    })?;
+                } else if name.eq_ignore_ascii_case(CLIENT_MAX_WINDOW_BITS) {
+                    // never offered by us, and a smaller window is not supported either way
+                    return Err(OpaqueError::from_display(
+                        "unexpected client_max_window_bits param",
+                    ));
+                } else {
+                    return Err(OpaqueError::from_display(format!(
+                        "unexpected permessage-deflate param: {name}"
+                    )));
+                }
+            }
+            agreed = Some(config);
+        }
+        Ok(agreed)
+    }
+}
+
+struct Extension<'a> {
+    name: &'a str,
+    params: Vec<(&'a str, Option<&'a str>)>,
+}
+
+/// Parse the (comma separated) extensions found in the
+/// given `Sec-WebSocket-Extensions` header values.
+///
+/// Header values which are not valid utf-8 are ignored.
+fn parse_extensions<'a>(
+    headers: impl IntoIterator<Item = &'a HeaderValue>,
+) -> impl Iterator<Item = Extension<'a>> {
+    headers
+        .into_iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .filter_map(|ext| {
+            let mut parts = ext.split(';').map(str::trim);
+            let name = parts.next().filter(|name| !name.is_empty())?;
+            let params = parts
+                .filter(|param| !param.is_empty())
+                .map(|param| match param.split_once('=') {
+                    Some((name, value)) => (name.trim(), Some(value.trim().trim_matches('"'))),
+                    None => (param, None),
+                })
+                .collect();
+            Some(Extension { name, params })
+        })
+}
+
+fn parse_window_bits(value: &str) -> Option<u8> {
+    value
+        .parse()
+        .ok()
+        .filter(|bits| (8..=MAX_WINDOW_BITS).contains(bits))
+}
+
+/// Compression state of a WebSocket for which the `permessage-deflate` extension was agreed upon.
+pub(crate) struct PerMessageDeflate {
+    compress: Compress,
+    decompress: Decompress,
+    compress_reset: bool,
+    decompress_reset: bool,
+}
+
+impl std::fmt::Debug for PerMessageDeflate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PerMessageDeflate")
+            .field("compress_reset", &self.compress_reset)
+            .field("decompress_reset", &self.decompress_reset)
+            .finish()
+    }
+}
+
+impl PerMessageDeflate {
+    pub(crate) fn new(role: Role, config: DeflateConfig) -> Self {
+        let (compress_reset, decompress_reset) = match role {
+            Role::Server => (
+                config.server_no_context_takeover,
+                config.client_no_context_takeover,
+            ),
+            Role::Client => (
+                config.client_no_context_takeover,
+                config.server_no_context_takeover,
+            ),
+        };
+        Self {
+            compress: Compress::new(Compression::default(), false),
+            decompress: Decompress::new(false),
+            compress_reset,
+            decompress_reset,
+        }
+    }
+
+    /// Compress the payload of a full message.
+    pub(crate) fn compress(&mut self, data: &[u8]) -> Result<Vec<u8>, ProtocolError> {
+        let mut output = Vec::with_capacity(data.len() / 2 + 64);
+        let mut consumed = 0;
+        loop {
+            if output.len() == output.capacity() {
+                output.reserve(output.capacity().max(64));
+            }
+            let before_in = self.compress.total_in();
+            self.compress
+                .compress_vec(&data[consumed..], &mut output, FlushCompress::Sync)
+                .map_err(|err| ProtocolError::Compression(OpaqueError::from_std(err)))?;
+            consumed += (self.compress.total_in() - before_in) as usize;
+            // the flush is complete once all input is consumed
+            // without the output buffer being completely filled
+            if consumed == data.len() && output.len() < output.capacity() {
+                break;
+            }
+        }
+
+        if output.ends_with(&DEFLATE_TRAILER) {
+            output.truncate(output.len() - DEFLATE_TRAILER.len());
+        }
+        if self.compress_reset {
+            self.compress.reset();
+        }
+        Ok(output)
+    }
+
+    /// Decompress the payload of a (fragment of a) message,
+    /// with `is_final` indicating whether it is the final fragment of the message.
+    pub(crate) fn decompress(
+        &mut self,
+        data: &[u8],
+        is_final: bool,
+        max_size: Option<usize>,
+    ) -> Result<Vec<u8>, ProtocolError> {
+        let mut output = Vec::with_capacity(data.len().saturating_mul(2).max(64));
+        self.decompress_into(data, &mut output, max_size)?;
+        if is_final {
+            self.decompress_into(&DEFLATE_TRAILER, &mut output, max_size)?;
+            if self.decompress_reset {
+                self.decompress.reset(false);
+            }
+        }
+        Ok(output)
+    }
+
+    fn decompress_into(
+        &mut self,
+        data: &[u8],
+        output: &mut Vec<u8>,
+        max_size: Option<usize>,
+    ) -> Result<(), ProtocolError> {
+        let mut consumed = 0;
+        loop {
+            if output.len() == output.capacity() {
+                output.reserve(output.capacity().max(64));
+            }
+            let before_in = self.decompress.total_in();
+            let before_out = output.len();
+            let status = self
+                .decompress
+                .decompress_vec(&data[consumed..], output, FlushDecompress::Sync)
+                .map_err(|err| ProtocolError::Compression(OpaqueError::from_std(err)))?;
+            consumed += (self.decompress.total_in() - before_in) as usize;
+
+            if let Some(max_size) = max_size
+                && output.len() > max_size
+            {
+                return Err(ProtocolError::MessageTooLong {
+                    size: output.len(),
+                    max_size,
+                });
+            }
+
+            let progress = self.decompress.total_in() != before_in || output.len() != before_out;
+            if status == Status::StreamEnd
+                || !progress
+                || (consumed == data.len() && output.len() < output.capacity())
+            {
+                return Ok(());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn header_values(values: &[&'static str]) -> Vec<HeaderValue> {
+        values.iter().map(|v| HeaderValue::from_static(v)).collect()
+    }
+
+    #[test]
+    fn test_to_header_value() {
+        assert_eq!(
+            DeflateConfig::default().to_header_value(),
+            "permessage-deflate"
+        );
+        assert_eq!(
+            DeflateConfig::default()
+                .server_no_context_takeover(true)
+                .client_no_context_takeover(true)
+                .to_header_value(),
+            "permessage-deflate; server_no_context_takeover; client_no_context_takeover"
+        );
+    }
+
+    #[test]
+    fn test_negotiate_offers() {
+        let server = DeflateConfig::default();
+        for (offers, expected) in [
+            (vec![], None),
+            (vec!["x-webkit-deflate-frame"], None),
+            (vec!["permessage-deflate"], Some(DeflateConfig::default())),
+            (
+                vec!["permessage-deflate; client_max_window_bits"],
+                Some(DeflateConfig::default()),
+            ),
+            (
+                vec!["permessage-deflate; server_no_context_takeover"],
+                Some(DeflateConfig::default().server_no_context_takeover(true)),
+            ),
+            (
+                vec!["foo, permessage-deflate; client_no_context_takeover"],
+                Some(DeflateConfig::default().client_no_context_takeover(true)),
+            ),
+            (vec!["permessage-deflate; server_max_window_bits=10"], None),
+            (
+                vec![
+                    "permessage-deflate; server_max_window_bits=10",
+                    "permessage-deflate; server_max_window_bits=15",
+                ],
+                Some(DeflateConfig::default()),
+            ),
+            (
+                vec!["permessage-deflate; server_max_window_bits=10, permessage-deflate"],
+                Some(DeflateConfig::default()),
+            ),
+            (vec!["permessage-deflate; client_max_window_bits=42"], None),
+            (vec!["permessage-deflate; unknown"], None),
+            (
+                vec!["permessage-deflate; server_no_context_takeover; server_no_context_takeover"],
+                None,
+            ),
+        ] {
+            let headers = header_values(&offers);
+            assert_eq!(
+                server.negotiate_offers(&headers),
+                expected,
+                "offers: {offers:?}"
+            );
+        }
+
+        // server preferences are kept
+        let server = DeflateConfig::default().client_no_context_takeover(true);
+        let headers = header_values(&["permessage-deflate"]);
+        assert_eq!(server.negotiate_offers(&headers), Some(server));
+    }
+
+    #[test]
+    fn test_negotiate_response() {
+        let client = DeflateConfig::default();
+        for (response, expected) in [
+            (vec![], Some(None)),
+            (vec!["permessage-deflate"], Some(Some(client))),
+            (
+                vec!["permessage-deflate; server_no_context_takeover; client_no_context_takeover"],
+                Some(Some(
+                    DeflateConfig::default()
+                        .server_no_context_takeover(true)
+                        .client_no_context_takeover(true),
+                )),
+            ),
+            (
+                vec!["permessage-deflate; server_max_window_bits=10"],
+                Some(Some(client)),
+            ),
+            (vec!["permessage-deflate; client_max_window_bits=10"], None),
+            (vec!["permessage-deflate; foo"], None),
+            (vec!["foo"], None),
+            (vec!["permessage-deflate", "permessage-deflate"], None),
+        ] {
+            let headers = header_values(&response);
+            assert_eq!(
+                client.negotiate_response(&headers).ok(),
+                expected,
+                "response: {response:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_compress_rfc_example() {
+        // RFC 7692, section 7.2.3.1: a message compressed using 1 compressed deflate block
+        let mut deflate = PerMessageDeflate::new(Role::Server, DeflateConfig::default());
+        let compressed = [0xf2, 0x48, 0xcd, 0xc9, 0xc9, 0x07, 0x00];
+        assert_eq!(
+            deflate.decompress(&compressed, true, None).unwrap(),
+            b"Hello"
+        );
+        // fragmented
+        let mut deflate = PerMessageDeflate::new(Role::Server, DeflateConfig::default());
+        let mut payload = deflate.decompress(&compressed[..3], false, None).unwrap();
+        payload.extend(deflate.decompress(&compressed[3..], true, None).unwrap());
+        assert_eq!(payload, b"Hello");
+    }
+
+    #[test]
+    fn test_compress_roundtrip_context_takeover() {
+        for config in [
+            DeflateConfig::default(),
+            DeflateConfig::default()
+                .server_no_context_takeover(true)
+                .client_no_context_takeover(true),
+        ] {
+            let mut server = PerMessageDeflate::new(Role::Server, config);
+            let mut client = PerMessageDeflate::new(Role::Client, config);
+
+            let message = br#"{"id":1,"name":"rama","tags":["proxy","http","ws"]}"#.repeat(64);
+            let first = server.compress(&message).unwrap();
+            let second = server.compress(&message).unwrap();
+            assert!(first.len() < message.len());
+            if config.server_no_context_takeover {
+                assert_eq!(first, second);
+            } else {
+                // the second message can refer to the first one
+                assert!(second.len() < first.len());
+            }
+
+            for compressed in [first, second] {
+                assert_eq!(client.decompress(&compressed, true, None).unwrap(), message);
+            }
+
+            let compressed = client.compress(b"pong").unwrap();
+            assert_eq!(server.decompress(&compressed, true, None).unwrap(), b"pong");
+        }
+    }
+
+    #[test]
+    fn test_decompress_max_size() {
+        let mut server = PerMessageDeflate::new(Role::Server, DeflateConfig::default());
+        let mut client = PerMessageDeflate::new(Role::Client, DeflateConfig::default());
+
+        let compressed = server.compress(&[b'a'; 64 * 1024]).unwrap();
+        assert!(matches!(
+            client.decompress(&compressed, true, Some(1024)),
+            Err(ProtocolError::MessageTooLong { .. })
+        ));
+    }
+}
@@ -53,6 +53,8 @@ pub enum ProtocolError {
     ExpectedFragment(OpCodeData),
     /// Type of data frame not recognised.
     UnknownDataFrameType(u8),
+    /// Failed to compress or decompress a message (`permessage-deflate`).
+    Compression(OpaqueError),
 }
 
 impl ProtocolError {
@@ -135,6 +137,7 @@ impl fmt::Display for ProtocolError {
             ProtocolError::UnknownDataFrameType(t) => {
                 write!(f, "Unknown data frame type: {t}")
             }
+            ProtocolError::Compression(err) => write!(f, "Compression error: {err:?}"),
         }
     }
 }
@@ -144,6 +147,7 @@ impl error::Error for ProtocolError {
         match self {
             ProtocolError::Utf8(err) => Some(err as &(dyn error::Error + 'static)),
             ProtocolError::Io(err) => Some(err as &(dyn std::error::Error + 'static)),
+            ProtocolError::Compression(err) => Some(err as &(dyn error::Error + 'static)),
             ProtocolError::InvalidOpcode(_)
             | ProtocolError::InvalidCloseSequence
             | ProtocolError::MessageTooLong { .. }
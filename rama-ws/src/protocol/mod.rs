@@ -1,5 +1,6 @@
 //! Generic WebSocket message stream.
 
+use rama_core::bytes::Bytes;
 use rama_core::{
     error::OpaqueError,
     telemetry::tracing::{debug, trace},
@@ -12,15 +13,18 @@ use std::{
 
 pub mod frame;
 
+mod deflate;
 mod error;
 mod message;
 
+pub use deflate::DeflateConfig;
 pub use error::ProtocolError;
 
 #[cfg(test)]
 mod tests;
 
 use crate::protocol::{
+    deflate::PerMessageDeflate,
     frame::{
         Frame, FrameCodec, Utf8Bytes,
         coding::{CloseCode, OpCode, OpCodeControl, OpCodeData},
@@ -103,6 +107,18 @@ pub struct WebSocketConfig {
     /// some popular libraries that are sending unmasked frames, ignoring the RFC.
     /// By default this option is set to `false`, i.e. according to RFC 6455.
     pub accept_unmasked_frames: bool,
+
+    /// The configuration of the per-message compression extension (`permessage-deflate`).
+    /// `None` means compression is disabled, which is the default.
+    ///
+    /// During the handshake the extension is offered (client) or accepted (server)
+    /// using this config, and replaced by the config agreed upon by both peers
+    /// (or `None` if not agreed upon) for the resulting WebSocket.
+    ///
+    /// Note that for a WebSocket created without a handshake (e.g. via
+    /// [`WebSocket::from_raw_socket`]) this config is assumed to be agreed upon.
+    /// Changing it after creation has no effect.
+    pub compression: Option<DeflateConfig>,
 }
 
 impl Default for WebSocketConfig {
@@ -114,6 +130,7 @@ impl Default for WebSocketConfig {
             max_message_size: Some(64 << 20),
             max_frame_size: Some(16 << 20),
             accept_unmasked_frames: false,
+            compression: None,
         }
     }
 }
@@ -155,6 +172,12 @@ impl WebSocketConfig {
         self
     }
 
+    /// Set [`Self::compression`].
+    pub fn compression(mut self, compression: Option<DeflateConfig>) -> Self {
+        self.compression = compression;
+        self
+    }
+
     /// Panic if values are invalid.
     pub(crate) fn assert_valid(&self) {
         assert!(
@@ -361,6 +384,10 @@ pub struct WebSocketContext {
     state: WebSocketState,
     /// Receive: an incomplete message being processed.
     incomplete: Option<IncompleteMessage>,
+    /// Receive: true if the incomplete message is compressed.
+    incomplete_compressed: bool,
+    /// Compression state, in case `permessage-deflate` is agreed upon.
+    deflate: Option<PerMessageDeflate>,
     /// Send in addition to regular messages E.g. "pong" or "close".
     additional_send: Option<Frame>,
     /// True indicates there is an additional message (like a pong)
@@ -402,6 +429,10 @@ impl WebSocketContext {
             frame,
             state: WebSocketState::Active,
             incomplete: None,
+            incomplete_compressed: false,
+            deflate: config
+                .compression
+                .map(|compression| PerMessageDeflate::new(role, compression)),
             additional_send: None,
             unflushed_additional: false,
             config,
@@ -507,8 +538,8 @@ impl WebSocketContext {
         }
 
         let frame = match message {
-            Message::Text(data) => Frame::message(data, OpCode::Data(OpCodeData::Text), true),
-            Message::Binary(data) => Frame::message(data, OpCode::Data(OpCodeData::Binary), true),
+            Message::Text(data) => self.data_frame(data.into(), OpCodeData::Text)?,
+            Message::Binary(data) => self.data_frame(data, OpCodeData::Binary)?,
             Message::Ping(data) => Frame::ping(data),
             Message::Pong(data) => {
                 self.set_additional(Frame::pong(data));
@@ -526,6 +557,20 @@ impl WebSocketContext {
         Ok(())
     }
 
+    /// Create the (final) frame of a data message,
+    /// compressing the payload in case `permessage-deflate` is agreed upon.
+    fn data_frame(&mut self, data: Bytes, opcode: OpCodeData) -> Result<Frame, ProtocolError> {
+        match self.deflate.as_mut() {
+            Some(deflate) => {
+                let mut frame =
+                    Frame::message(deflate.compress(&data)?, OpCode::Data(opcode), true);
+                frame.header_mut().rsv1 = true;
+                Ok(frame)
+            }
+            None => Ok(Frame::message(data, OpCode::Data(opcode), true)),
+        }
+    }
+
     /// Flush writes.
     ///
     /// Ensures all messages previously passed to [`write`](Self::write) and automatically
@@ -638,12 +683,21 @@ impl WebSocketContext {
             // the negotiated extensions defines the meaning of such a nonzero
             // value, the receiving endpoint MUST _Fail the WebSocket
             // Connection_.
-            {
+            //
+            // The `permessage-deflate` extension defines RSV1 for the first frame
+            // of a data message, indicating that the message is compressed. (RFC 7692)
+            let compressed = {
                 let hdr = frame.header();
-                if hdr.rsv1 || hdr.rsv2 || hdr.rsv3 {
+                let rsv1_allowed = self.deflate.is_some()
+                    && matches!(
+                        hdr.opcode,
+                        OpCode::Data(OpCodeData::Text | OpCodeData::Binary)
+                    );
+                if (hdr.rsv1 && !rsv1_allowed) || hdr.rsv2 || hdr.rsv3 {
                     return Err(ProtocolError::NonZeroReservedBits);
                 }
-            }
+                hdr.rsv1
+            };
 
             if self.role == Role::Client && frame.is_masked() {
                 // A client MUST close a connection if it detects a masked frame. (RFC 6455)
@@ -679,18 +733,32 @@ impl WebSocketContext {
                     let fin = frame.header().is_final;
                     match data {
                         OpCodeData::Continue => {
-                            if let Some(ref mut msg) = self.incomplete {
-                                msg.extend(frame.into_payload(), self.config.max_message_size)?;
-                            } else {
+                            if self.incomplete.is_none() {
                                 return Err(ProtocolError::UnexpectedContinueFrame);
                             }
+                            let payload = if self.incomplete_compressed {
+                                self.decompress(frame.payload(), fin)?
+                            } else {
+                                frame.into_payload()
+                            };
+                            if let Some(ref mut msg) = self.incomplete {
+                                msg.extend(payload, self.config.max_message_size)?;
+                            }
                             if fin {
+                                self.incomplete_compressed = false;
                                 Ok(Some(self.incomplete.take().unwrap().complete()?))
                             } else {
                                 Ok(None)
                             }
                         }
                         c if self.incomplete.is_some() => Err(ProtocolError::ExpectedFragment(c)),
+                        OpCodeData::Text if fin && compressed => {
+                            let payload = self.decompress(frame.payload(), true)?;
+                            Ok(Some(Message::Text(Utf8Bytes::try_from(payload)?)))
+                        }
+                        OpCodeData::Binary if fin && compressed => Ok(Some(Message::Binary(
+                            self.decompress(frame.payload(), true)?,
+                        ))),
                         OpCodeData::Text if fin => {
                             check_max_size(frame.payload().len(), self.config.max_message_size)?;
                             Ok(Some(Message::Text(frame.into_text()?)))
@@ -705,10 +773,15 @@ impl WebSocketContext {
                                 OpCodeData::Binary => IncompleteMessageType::Binary,
                                 _ => unreachable!("Bug: message is not text nor binary"),
                             };
+                            let payload = if compressed {
+                                self.decompress(frame.payload(), false)?
+                            } else {
+                                frame.into_payload()
+                            };
                             let mut incomplete = IncompleteMessage::new(message_type);
-                            incomplete
-                                .extend(frame.into_payload(), self.config.max_message_size)?;
+                            incomplete.extend(payload, self.config.max_message_size)?;
                             self.incomplete = Some(incomplete);
+                            self.incomplete_compressed = compressed;
                             Ok(None)
                         }
                         OpCodeData::Reserved(i) => Err(ProtocolError::UnknownDataFrameType(i)),
@@ -729,6 +802,17 @@ impl WebSocketContext {
         }
     }
 
+    /// Decompress the payload of a (fragment of a) compressed message.
+    fn decompress(&mut self, payload: &[u8], is_final: bool) -> Result<Bytes, ProtocolError> {
+        let deflate = self
+            .deflate
+            .as_mut()
+            .expect("compressed frames to only be accepted if permessage-deflate is agreed upon");
+        deflate
+            .decompress(payload, is_final, self.config.max_message_size)
+            .map(Bytes::from)
+    }
+
     /// Received a close frame. Tells if we need to return a close frame to the user.
     #[allow(clippy::option_option)]
     fn do_close(&mut self, close: Option<CloseFrame>) -> Option<Option<CloseFrame>> {
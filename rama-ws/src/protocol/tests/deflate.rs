@@ -0,0 +1,103 @@
+use crate::{
+    Message,
+    protocol::{DeflateConfig, Role, WebSocket, WebSocketConfig, error::ProtocolError},
+};
+use std::io::Cursor;
+
+fn compression_config() -> Option<WebSocketConfig> {
+    Some(WebSocketConfig::default().compression(Some(DeflateConfig::default())))
+}
+
+#[test]
+fn receive_compressed_messages() {
+    // RFC 7692, section 7.2.3.1 and 7.2.3.2: "Hello", compressed and unfragmented / fragmented
+    let incoming = Cursor::new(vec![
+        0xc1, 0x07, 0xf2, 0x48, 0xcd, 0xc9, 0xc9, 0x07, 0x00, 0x41, 0x03, 0xf2, 0x48, 0xcd, 0x80,
+        0x04, 0xc9, 0xc9, 0x07, 0x00, 0x81, 0x05, 0x48, 0x65, 0x6c, 0x6c, 0x6f,
+    ]);
+    let mut socket = WebSocket::from_raw_socket(incoming, Role::Client, compression_config());
+    for _ in 0..3 {
+        assert_eq!(socket.read().unwrap(), Message::Text("Hello".into()));
+    }
+}
+
+#[test]
+fn reject_compressed_message_without_compression() {
+    let incoming = Cursor::new(vec![0xc1, 0x07, 0xf2, 0x48, 0xcd, 0xc9, 0xc9, 0x07, 0x00]);
+    let mut socket = WebSocket::from_raw_socket(incoming, Role::Client, None);
+    assert!(matches!(
+        socket.read(),
+        Err(ProtocolError::NonZeroReservedBits)
+    ));
+}
+
+#[test]
+fn reject_compressed_continue_frame() {
+    let incoming = Cursor::new(vec![
+        0x41, 0x03, 0xf2, 0x48, 0xcd, 0xc0, 0x04, 0xc9, 0xc9, 0x07, 0x00,
+    ]);
+    let mut socket = WebSocket::from_raw_socket(incoming, Role::Client, compression_config());
+    assert!(matches!(
+        socket.read(),
+        Err(ProtocolError::NonZeroReservedBits)
+    ));
+}
+
+#[test]
+fn compressed_message_size_limiting() {
+    let mut server =
+        WebSocket::from_raw_socket(Cursor::new(Vec::new()), Role::Server, compression_config());
+    server
+        .send(Message::Binary(vec![0u8; 64 * 1024].into()))
+        .unwrap();
+
+    let outgoing = server.get_ref().get_ref().clone();
+    let mut client = WebSocket::from_raw_socket(
+        Cursor::new(outgoing),
+        Role::Client,
+        compression_config().map(|config| config.max_message_size(Some(1024))),
+    );
+    assert!(matches!(
+        client.read(),
+        Err(ProtocolError::MessageTooLong { .. })
+    ));
+}
+
+#[test]
+fn send_and_receive_compressed_messages() {
+    for compression in [
+        DeflateConfig::default(),
+        DeflateConfig::default()
+            .server_no_context_takeover(true)
+            .client_no_context_takeover(true),
+    ] {
+        let config = Some(WebSocketConfig::default().compression(Some(compression)));
+        let text = r#"{"name":"rama","kind":"proxy"}"#.repeat(1024);
+        let binary = vec![7u8; 32 * 1024];
+
+        let mut server = WebSocket::from_raw_socket(Cursor::new(Vec::new()), Role::Server, config);
+        for _ in 0..2 {
+            server.write(Message::Text(text.as_str().into())).unwrap();
+            server
+                .write(Message::Binary(binary.clone().into()))
+                .unwrap();
+        }
+        server.write(Message::Ping(vec![1].into())).unwrap();
+        server.flush().unwrap();
+
+        let outgoing = server.get_ref().get_ref().clone();
+        // FIN + RSV1 + Text opcode
+        assert_eq!(outgoing[0], 0xc1);
+        assert!(outgoing.len() < text.len());
+
+        let mut client = WebSocket::from_raw_socket(Cursor::new(outgoing), Role::Client, config);
+        for _ in 0..2 {
+            assert_eq!(client.read().unwrap(), Message::Text(text.as_str().into()));
+            assert_eq!(
+                client.read().unwrap(),
+                Message::Binary(binary.clone().into())
+            );
+        }
+        assert_eq!(client.read().unwrap(), Message::Ping(vec![1].into()));
+    }
+}
@@ -1,2 +1,3 @@
 mod auto_pong_flush;
+mod deflate;
 mod write;
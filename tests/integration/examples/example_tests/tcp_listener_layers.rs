@@ -26,4 +26,11 @@ async fn test_tcp_listener_layers() {
     let mut buf = [0; 5];
     stream.read_exact(&mut buf).await.unwrap();
     assert_eq!(&buf, b"hello");
+
+    // idle for longer than the keepalive idle time of the server,
+    // the (alive) connection is expected to be kept open
+    tokio::time::sleep(std::time::Duration::from_secs(3)).await;
+    stream.write_all(b"world").await.unwrap();
+    stream.read_exact(&mut buf).await.unwrap();
+    assert_eq!(&buf, b"world");
 }